@@ -19,6 +19,7 @@ use super::asm::*;
 use super::qlib::addr::*;
 use super::qlib::backtracer;
 use super::qlib::common::*;
+use super::qlib::kernel::memmgr::overcommit::OOMCheck;
 use super::qlib::kernel::TSC;
 use super::qlib::linux_def::*;
 use super::qlib::singleton::*;
@@ -245,9 +246,15 @@ pub fn ExceptionHandler(ev: ExceptionStackVec, ptRegs: &mut PtRegs, errorCode: u
                 .expect("DivByZeroHandler send signal fail");
         }
         ExceptionStackVec::Debug | ExceptionStackVec::Breakpoint => {
+            // like Linux, a single step is a TRAP_TRACE and int3 is SI_KERNEL
+            let code = if ev == ExceptionStackVec::Debug {
+                SignalInfo::TRAP_TRACE
+            } else {
+                SignalInfo::SIGNAL_INFO_KERNEL
+            };
             let info = SignalInfo {
                 Signo: Signal::SIGTRAP,
-                Code: 1,
+                Code: code,
                 ..Default::default()
             };
 
@@ -482,6 +489,11 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
         );
     }
 
+    // before any lock is taken, as the oom killer looks at every process' page table
+    if fromUser {
+        OOMCheck();
+    }
+
     let signal;
     // no need loop, just need to enable break
     loop {
@@ -489,16 +501,19 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
 
         let (vma, range) = match currTask.mm.GetVmaAndRangeLocked(cr2) {
             //vmas.lock().Get(cr2) {
-            None => {
-                if cr2 > 0x1000 {
-                    let map = currTask.mm.GetSnapshotLocked(currTask, false);
-                    print!("the map is {}", &map);
+            None => match currTask.mm.GrowStackLocked(currTask, cr2) {
+                Some(vma) => vma,
+                None => {
+                    if cr2 > 0x1000 {
+                        let map = currTask.mm.GetSnapshotLocked(currTask, false);
+                        print!("the map is {}", &map);
+                    }
+
+                    //todo: when to send sigbus/SIGSEGV
+                    signal = Signal::SIGSEGV;
+                    break;
                 }
-
-                //todo: when to send sigbus/SIGSEGV
-                signal = Signal::SIGSEGV;
-                break;
-            }
+            },
             Some(vma) => vma.clone(),
         };
 
@@ -539,6 +554,7 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
                 .unwrap();
             //let endtime = TSC.Rdtsc();
             if addr > 0 {
+                currTask.AccountPageFault(true);
                 //use crate::qlib::kernel::Tsc;
                 //error!("swap in page {:x?}/{:x}/{}", Addr(pageAddr).RoundDown().unwrap(), addr, Tsc::Scale(endtime - startTime));
                 CPULocal::Myself().SetMode(VcpuMode::User);
@@ -556,7 +572,7 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
                 Err(e) => {
                     panic!("PageFaultHandler error is {:?}", e)
                 }
-                _ => (),
+                Ok(major) => currTask.AccountPageFault(major),
             };
 
             for i in 1..8 {
@@ -603,6 +619,7 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
             }
 
             currTask.mm.CopyOnWriteLocked(pageAddr, &vma);
+            currTask.AccountPageFault(false);
             currTask.mm.TlbShootdown();
             if fromUser {
                 //PerfGoto(PerfType::User);
@@ -729,7 +746,7 @@ pub extern "C" fn VirtualizationHandler(ptRegs: &mut PtRegs) {
         // no need special operation
     }
 
-    if CPULocal::InterruptByThreadTimeout(mask) {
+    if CPULocal::InterruptByThreadTimeout(mask) || CPULocal::InterruptByMigrate(mask) {
         /*if SHARESPACE.config.read().KernelPagetable {
             Task::SetKernelPageTable();
         }*/
@@ -737,8 +754,17 @@ pub extern "C" fn VirtualizationHandler(ptRegs: &mut PtRegs) {
         currTask.AccountTaskLeave(SchedState::RunningApp);
         //currTask.SaveFp();
         CPULocal::Myself().ResetEnterAppTimestamp();
+        // the vcpu takes the preemption, which is progress for the vcpu watchdog
+        // even when there is no other task to switch to
+        CPULocal::IncreaseSwitchCount();
         super::qlib::kernel::taskMgr::Yield();
+        // a thread moved off its vcpu by sched_setaffinity of another thread
+        super::qlib::kernel::taskMgr::Migrate();
+        super::qlib::kernel::kernel::throttle::CpuThrottle(currTask);
         MainRun(currTask, TaskRunState::RunApp);
+        // a thread spinning in user mode only gets here, take the external stops
+        currTask.DoStop();
+        currTask.RSeqNotifyResume(true);
         currTask.RestoreFp();
         CPULocal::Myself().SetEnterAppTimestamp(TSC.Rdtsc());
         CPULocal::SetKernelStack(currTask.GetKernelSp());
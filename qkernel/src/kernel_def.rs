@@ -211,6 +211,7 @@ pub fn switch(from: TaskId, to: TaskId) {
     Task::Current().AccountTaskEnter(SchedState::Blocked);
 
     CPULocal::SetCurrentTask(to.Addr());
+    CPULocal::IncreaseSwitchCount();
     let fromCtx = from.GetTask();
     let toCtx = to.GetTask();
 
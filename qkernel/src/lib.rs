@@ -194,6 +194,7 @@ pub fn SingletonInit() {
         );
 
         perflog::THREAD_COUNTS.Init(QMutex::new(perflog::ThreadPerfCounters::default()));
+        perflog::SYSCALL_STATS.Init(perflog::SyscallStats::default());
 
         fs::file::InitSingleton();
         fs::filesystems::InitSingleton();
@@ -261,9 +262,10 @@ pub extern "C" fn syscall_handler(
     if worktime > CLOCK_TICK {
         taskMgr::Yield();
     }
+    kernel::throttle::CpuThrottle(currTask);
 
     let res;
-    let args = SyscallArguments {
+    let mut args = SyscallArguments {
         arg0: arg0,
         arg1: arg1,
         arg2: arg2,
@@ -309,10 +311,43 @@ pub extern "C" fn syscall_handler(
     let currTask = task::Task::Current();
     //currTask.DoStop();
 
-    let state = SysCall(currTask, nr, &args);
+    // a tracer stopped in the syscall-enter-stop can change the syscall and its
+    // arguments, or skip the syscall by setting orig_rax to -1
+    let mut skip = false;
+    if currTask.PtraceSyscallEnter() {
+        let pt = currTask.GetPtRegs();
+        nr = pt.orig_rax;
+        args = SyscallArguments {
+            arg0: pt.rdi,
+            arg1: pt.rsi,
+            arg2: pt.rdx,
+            arg3: pt.r10,
+            arg4: pt.r8,
+            arg5: pt.r9,
+        };
+        skip = nr == u64::MAX;
+    }
+
+    let state = if skip {
+        TaskRunState::RunApp
+    } else {
+        SysCall(currTask, nr, &args)
+    };
+
+    // every syscall which returns to the user space, rt_sigreturn as well
+    if state == TaskRunState::RunApp || state == TaskRunState::RunSyscallRet {
+        currTask.PtraceSyscallExit();
+    }
     MainRun(currTask, state);
+    // the affinity might have been changed by another thread during the syscall
+    taskMgr::Migrate();
     res = currTask.Return();
     currTask.DoStop();
+    currTask.RSeqNotifyResume(false);
+
+    if SHARESPACE.config.read().SyscallStat {
+        perflog::SYSCALL_STATS.Record(nr, (TSC.Rdtsc() - startTime) as u64);
+    }
 
     let pt = currTask.GetPtRegs();
 
@@ -489,6 +524,7 @@ pub extern "C" fn rust_main(
         GlobalIOMgr().InitPollHostEpoll(SHARESPACE.HostHostEpollfd());
         SetVCPCount(vcpuCnt as usize);
         VDSO.Initialization(vdsoParamAddr);
+        memmgr::overcommit::InitOvercommit();
 
         // release other vcpus
         HyperCall64(qlib::HYPERCALL_RELEASE_VCPU, 0, 0, 0, 0);
@@ -500,6 +536,7 @@ pub extern "C" fn rust_main(
     SHARESPACE.IncrVcpuSearching();
     taskMgr::AddNewCpu();
     RegisterSysCall(syscall_entry as u64);
+    SetTscAux(id);
 
     //interrupts::init_idt();
     interrupt::init();
@@ -27,14 +27,17 @@ pub mod sys_memfd;
 pub mod sys_mempolicy;
 pub mod sys_mmap;
 pub mod sys_mmap_socket;
+pub mod sys_mount;
 pub mod sys_msgqueue;
 pub mod sys_pipe;
 pub mod sys_poll;
 pub mod sys_prctl;
 pub mod sys_proxy;
+pub mod sys_ptrace;
 pub mod sys_random;
 pub mod sys_read;
 pub mod sys_rlimit;
+pub mod sys_rseq;
 pub mod sys_rusage;
 pub mod sys_sched;
 pub mod sys_seccomp;
@@ -147,13 +147,10 @@ pub fn SysCapSet(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             return Ok(0);
         }
         _ => {
+            // unlike capget, an unknown version is always an error
             hdr.Version = HIGHEST_CAPABILITY_VERSION;
-            match task.CopyOutObj(&hdr, hdrAddr) {
-                Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
-                Ok(()) => (),
-            };
-
-            return Ok(0);
+            task.CopyOutObj(&hdr, hdrAddr)?;
+            return Err(Error::SysError(SysErr::EINVAL));
         }
     }
 }
@@ -41,6 +41,7 @@ use super::super::qlib::range::*;
 use super::super::syscalls::syscalls::*;
 use super::super::task::*;
 use super::super::util::cstring::*;
+use fs::host::hostfileop::HostFileOp;
 use fs::host::hostinodeop::HostInodeOp;
 use fs::host::util::Fcntl;
 
@@ -185,6 +186,10 @@ pub fn CleanOpenFlags(flags: i32) -> Result<i32> {
         if flags & Flags::O_ACCMODE == Flags::O_RDONLY {
             return Err(Error::SysError(SysErr::EINVAL));
         }
+    } else if flags & Flags::O_CREAT != 0 && flags & Flags::O_DIRECTORY != 0 {
+        // O_DIRECTORY only opens an existing directory and O_CREAT can't make one,
+        // Linux rejects the pair since 6.4.
+        return Err(Error::SysError(SysErr::EINVAL));
     }
 
     // we can't read/write will readonly or writeonly
@@ -253,7 +258,9 @@ pub fn openAt(task: &Task, dirFd: i32, addr: u64, flags: u32) -> Result<i32> {
     );
 
     let mut fileFlags = FileFlags::FromFlags(flags);
-    let resolve = !fileFlags.NoFollow && !fileFlags.Path;
+    // O_PATH follows a trailing symlink unless O_NOFOLLOW is also given, so that e.g.
+    // fchdir works on an O_PATH fd of a symlink to a directory.
+    let resolve = !fileFlags.NoFollow;
     let mut fd = -1;
 
     fileOpOn(
@@ -268,6 +275,19 @@ pub fn openAt(task: &Task, dirFd: i32, addr: u64, flags: u32) -> Result<i32> {
                 inode.CheckPermission(task, &PermMask::FromFlags(flags))?;
             }
 
+            if fileFlags.NoAtime && !inode.CheckOwnership(task) {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+
+            if inode.StableAttr().IsRegular() && !fileFlags.Path {
+                let lease = inode.lock().LockCtx.Lease.clone();
+                lease.Break(
+                    task,
+                    fileFlags.Write || flags & Flags::O_TRUNC as u32 != 0,
+                    fileFlags.NonBlocking,
+                )?;
+            }
+
             if inode.StableAttr().IsSymlink() && !resolve && !fileFlags.Path {
                 return Err(Error::SysError(SysErr::ELOOP));
             }
@@ -527,6 +547,19 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
             Error::None => {
                 let mut foundInode = found.Inode();
 
+                if fileFlags.NoAtime && !foundInode.CheckOwnership(task) {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                if foundInode.StableAttr().IsRegular() {
+                    let lease = foundInode.lock().LockCtx.Lease.clone();
+                    lease.Break(
+                        task,
+                        fileFlags.Write || flags & Flags::O_TRUNC as u32 != 0,
+                        fileFlags.NonBlocking,
+                    )?;
+                }
+
                 if flags & Flags::O_TRUNC as u32 != 0 {
                     if foundInode.StableAttr().IsDir() {
                         return Err(Error::SysError(SysErr::EISDIR))
@@ -724,10 +757,61 @@ pub fn Ioctl(task: &mut Task, fd: i32, request: u64, val: u64) -> Result<()> {
             task.CopyOutObj(&who, val)?;
             return Ok(());
         }
+        IoCtlCmd::FICLONE | IoCtlCmd::FICLONERANGE => {
+            // FICLONE passes the source fd by value and clones the whole file
+            let range = if request == IoCtlCmd::FICLONE {
+                FileCloneRange {
+                    SrcFd: val as i32 as i64,
+                    ..Default::default()
+                }
+            } else {
+                task.CopyInObj(val)?
+            };
+
+            return FileClone(task, &file, &range);
+        }
         _ => return file.Ioctl(task, fd, request, val),
     }
 }
 
+// FileClone clones a range of a host file into another one on the host.
+fn FileClone(task: &Task, dst: &File, range: &FileCloneRange) -> Result<()> {
+    let src = task.GetFile(range.SrcFd as i32)?;
+    if !src.Flags().Read || !dst.Flags().Write || dst.Flags().Append {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    let srcAttr = src.Dirent.Inode().StableAttr();
+    let dstAttr = dst.Dirent.Inode().StableAttr();
+    if srcAttr.IsDir() || dstAttr.IsDir() {
+        return Err(Error::SysError(SysErr::EISDIR));
+    }
+
+    if !srcAttr.IsRegular() || !dstAttr.IsRegular() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let srcOp = src.FileOp.as_any().downcast_ref::<HostFileOp>().cloned();
+    let dstOp = dst.FileOp.as_any().downcast_ref::<HostFileOp>().cloned();
+    match (srcOp, dstOp) {
+        (Some(srcOp), Some(dstOp)) => {
+            srcOp.InodeOp.CloneRange(
+                task,
+                range.SrcOffset as i64,
+                &dstOp.InodeOp,
+                range.DestOffset as i64,
+                range.SrcLength as i64,
+            )?;
+
+            dst.Dirent
+                .InotifyEvent(InotifyEvent::IN_MODIFY, 0, EventType::InodeEvent);
+            return Ok(());
+        }
+        // the guest's own filesystems don't share extents
+        _ => return Err(Error::SysError(SysErr::EOPNOTSUPP)),
+    }
+}
+
 pub fn SysGetcwd(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let addr = args.arg0 as u64;
     let size = args.arg1 as usize;
@@ -740,18 +824,13 @@ pub fn SysGetcwd(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         s = "(unreachable)".to_string() + &s
     }
 
-    if s.len() >= size {
+    let cstr = CString::New(&s);
+    if cstr.Len() > size {
         return Err(Error::SysError(SysErr::ERANGE));
     }
 
-    let len = if s.len() + 1 > size {
-        size
-    } else {
-        s.len() + 1
-    };
-
-    task.CopyOutString(addr, len, &s)?;
-    return Ok(len as i64);
+    task.CopyOutSlice(cstr.Slice(), addr, cstr.Len())?;
+    return Ok(cstr.Len() as i64);
 }
 
 pub fn SysChroot(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -803,40 +882,24 @@ pub fn SysChdir(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     let (path, _) = copyInPath(task, addr, false)?;
     info!("SysChdir path is {}", &path);
-    let mut dir = task.Workdir();
 
-    let res = fileOpOn(
-        task,
-        ATType::AT_FDCWD,
-        &path,
-        true,
-        &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
-            let inode = d.Inode();
-            if !inode.StableAttr().IsDir() {
-                return Err(Error::SysError(SysErr::ENOTDIR));
-            }
-
-            inode.CheckPermission(
-                task,
-                &PermMask {
-                    execute: true,
-                    ..Default::default()
-                },
-            )?;
-
-            dir = d.clone();
+    let root = task.Root();
+    let dir = task.Workdir().WalkPath(task, &root, &path)?;
+    let inode = dir.Inode();
+    if !inode.StableAttr().IsDir() {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
 
-            Ok(())
+    inode.CheckPermission(
+        task,
+        &PermMask {
+            execute: true,
+            ..Default::default()
         },
-    );
+    )?;
 
-    match res {
-        Err(e) => Err(e),
-        Ok(_) => {
-            task.fsContext.SetWorkDirectory(&dir);
-            return Ok(0);
-        }
-    }
+    task.fsContext.SetWorkDirectory(&dir);
+    return Ok(0);
 }
 
 pub fn SysFchdir(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -866,11 +929,11 @@ pub fn SysFchdir(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
 // CloseRange implements linux syscall close_range(2).
 pub fn SysCloseRange(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
-    let first = args.arg0 as i32;
-    let last = args.arg1 as i32;
+    let first = args.arg0 as u32;
+    let last = args.arg1 as u32;
     let flags = args.arg2 as i32;
 
-    if first < 0 || last < 0 || first > last {
+    if first > last {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
@@ -878,6 +941,11 @@ pub fn SysCloseRange(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
+    // The range is unsigned and commonly ends at ~0U, clamp it to the valid
+    // fds so that last + 1 doesn't overflow.
+    let first = core::cmp::min(first, i32::MAX as u32) as i32;
+    let last = core::cmp::min(last, i32::MAX as u32 - 1) as i32;
+
     let cloexec = flags & Cmd::CLOSE_RANGE_CLOEXEC != 0;
     let unshare = flags & Cmd::CLOSE_RANGE_UNSHARE != 0;
 
@@ -894,6 +962,10 @@ pub fn SysCloseRange(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         }
     }
 
+    if first > last {
+        return Ok(0);
+    }
+
     if cloexec {
         let flagToApply = FDFlags { CloseOnExec: true };
 
@@ -1177,6 +1249,48 @@ pub fn PosixTestLock(task: &Task, flockAddr: u64, file: &File) -> Result<()> {
     return Ok(());
 }
 
+// The lease is only taken in the guest, F_SETLEASE isn't forwarded to the host fd:
+// an open or truncate by a process outside the sandbox doesn't break it, and the
+// holder gets no SIGIO for it. See Leases.
+fn SetLease(task: &Task, fd: i32, file: &File, typ: i32) -> Result<()> {
+    if typ != F_RDLCK && typ != F_WRLCK && typ != F_UNLCK {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let inode = file.Dirent.Inode();
+    if !inode.StableAttr().IsRegular() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let lease = inode.lock().LockCtx.Lease.clone();
+    if typ == F_UNLCK {
+        return lease.SetLease(file.UniqueId(), typ, &FileAsync::default());
+    }
+
+    // Only the owner of the file or a task with CAP_LEASE can take a lease.
+    let uattr = inode.UnstableAttr(task)?;
+    let creds = task.Creds();
+    if uattr.Owner.UID != creds.lock().EffectiveKUID
+        && !creds.HasCapability(Capability::CAP_LEASE)
+    {
+        return Err(Error::SysError(SysErr::EACCES));
+    }
+
+    // A read lease can only be placed on a read-only file descriptor.
+    if typ == F_RDLCK && file.Flags().Write {
+        return Err(Error::SysError(SysErr::EAGAIN));
+    }
+
+    // The lease break signal goes to the file's owner, which defaults to the
+    // thread group setting the lease.
+    let a = file.Async(task, Some(FileAsync::New(fd))).unwrap();
+    if let Recipient::None = a.Owner() {
+        a.SetOwnerThreadGroup(task, Some(task.Thread().ThreadGroup()));
+    }
+
+    return lease.SetLease(file.UniqueId(), typ, &a);
+}
+
 pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let fd = args.arg0 as i32;
     let cmd = args.arg1 as i32;
@@ -1186,6 +1300,17 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     match cmd {
         Cmd::F_DUPFD | Cmd::F_DUPFD_CLOEXEC => {
+            // A minfd at or beyond RLIMIT_NOFILE is EINVAL, not EMFILE.
+            let lim = task
+                .Thread()
+                .ThreadGroup()
+                .Limits()
+                .Get(LimitType::NumberOfFiles)
+                .Cur;
+            if val >= lim || val > i32::MAX as u64 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
             let from = val as i32;
             let fd = task.NewFDFrom(
                 from,
@@ -1254,6 +1379,25 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             FSetOwner(task, fd, &file, val as i32)?;
             return Ok(0);
         }
+        Cmd::F_GETSIG => {
+            if file.Flags().Path {
+                return Err(Error::SysError(SysErr::EBADF));
+            }
+
+            match file.Async(task, None) {
+                None => return Ok(0),
+                Some(a) => return Ok(a.Signal().0 as i64),
+            }
+        }
+        Cmd::F_SETSIG => {
+            if file.Flags().Path {
+                return Err(Error::SysError(SysErr::EBADF));
+            }
+
+            let a = file.Async(task, Some(FileAsync::New(fd))).unwrap();
+            a.SetSignal(val as i32)?;
+            return Ok(0);
+        }
         Cmd::F_GETOWN_EX => {
             if file.Flags().Path {
                 return Err(Error::SysError(SysErr::EBADF));
@@ -1321,6 +1465,14 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
                 _ => return Err(Error::SysError(SysErr::EINVAL)),
             }
         }
+        Cmd::F_GETLEASE => {
+            let lease = file.Dirent.Inode().lock().LockCtx.Lease.clone();
+            return Ok(lease.GetLease(file.UniqueId()) as i64);
+        }
+        Cmd::F_SETLEASE => {
+            SetLease(task, fd, &file, val as i32)?;
+            return Ok(0);
+        }
         Cmd::F_GET_SEALS => {
             let iops = file.Dirent.Inode().lock().InodeOp.clone();
             if let Some(ops) = iops.as_any().downcast_ref::<HostInodeOp>() {
@@ -1830,7 +1982,8 @@ fn readlinkAt(task: &Task, dirFd: i32, addr: u64, bufAddr: u64, size: u32) -> Re
             };
 
             info!("readlinkAt 1 path is {}, target is {}", &path, &s);
-            let mut buffer = s.as_bytes();
+            let target = BytesFromString(&s);
+            let mut buffer = &target[..];
             if buffer.len() > size {
                 buffer = &buffer[..size]
             }
@@ -22,6 +22,7 @@ use super::super::qlib::linux_def::*;
 use super::super::qlib::mem::io::*;
 use super::super::syscalls::syscalls::*;
 use super::super::task::*;
+use super::super::util::cstring::*;
 
 pub fn SysGetDents(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let fd = args.arg0 as i32;
@@ -129,7 +130,7 @@ impl Dirent {
                 },
                 Type: attr.Type.ToType(),
             },
-            Name: name.as_bytes().to_vec(),
+            Name: BytesFromString(name),
         };
 
         d.Name.push(0);
@@ -190,10 +190,13 @@ pub fn SysSetgroups(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     if size == 0 {
         let zero: [GID; 0] = [GID(0); 0];
         task.Thread().SetExtraGIDs(&zero)?;
+        task.creds = task.Thread().Creds();
         return Ok(0);
     }
 
     let gids: Vec<GID> = task.CopyInVec(addr, size as usize)?;
     task.Thread().SetExtraGIDs(&gids[..])?;
+    // the permission checks use the task's copy of the credentials
+    task.creds = task.Thread().Creds();
     return Ok(0);
 }
@@ -35,6 +35,7 @@ pub fn SysMmap(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let offset = args.arg5 as u64;
 
     let fixed = flags & MmapFlags::MAP_FIXED != 0;
+    let noreplace = flags & MmapFlags::MAP_FIXED_NOREPLACE != 0;
     let private = flags & MmapFlags::MAP_PRIVATE != 0;
     let shared = flags & MmapFlags::MAP_SHARED != 0;
     let anon = flags & MmapFlags::MAP_ANONYMOUS != 0;
@@ -49,8 +50,8 @@ pub fn SysMmap(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         Length: len,
         Addr: addr,
         Offset: offset,
-        Fixed: fixed,
-        Unmap: fixed,
+        Fixed: fixed || noreplace,
+        Unmap: fixed && !noreplace,
         Map32Bit: map32bit,
         Private: private,
         VDSO: false,
@@ -427,7 +428,7 @@ pub fn SysMincore(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         Ok(r) => r,
     };
 
-    let output = task.mm.MinCore(task, &range);
+    let output = task.mm.MinCore(task, &range)?;
     task.CopyOutSlice(&output, vec, output.len())?;
     return Ok(0);
 }
@@ -0,0 +1,193 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+
+use crate::qlib::common::*;
+use crate::qlib::kernel::fs::dirent::*;
+use crate::qlib::kernel::fs::filesystems::*;
+use crate::qlib::linux_def::*;
+use crate::syscalls::sys_file::*;
+use crate::syscalls::syscalls::*;
+use crate::task::*;
+
+// Mount implements Linux syscall mount(2).
+pub fn SysMount(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let sourceAddr = args.arg0 as u64;
+    let targetAddr = args.arg1 as u64;
+    let typeAddr = args.arg2 as u64;
+    let mut flags = args.arg3 as u64;
+    let dataAddr = args.arg4 as u64;
+
+    // The guest mounts never propagate to other mount namespaces, so making them
+    // private, as unshare(1) does after unshare(CLONE_NEWNS), has nothing to do.
+    let private = LibcConst::MS_PRIVATE | LibcConst::MS_REC | LibcConst::MS_SILENT;
+    if flags & LibcConst::MS_PRIVATE != 0 && flags & !private == 0 {
+        let userns = task.mountNS.UserNamespace();
+        if !task
+            .Thread()
+            .HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
+        {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let (targetPath, _) = copyInPath(task, targetAddr, false)?;
+        fileOpOn(
+            task,
+            ATType::AT_FDCWD,
+            &targetPath,
+            true,
+            &mut |_root: &Dirent, _d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+                return Ok(());
+            },
+        )?;
+
+        return Ok(0);
+    }
+
+    let (fsType, err) = task.CopyInString(typeAddr, MemoryDef::PAGE_SIZE as usize);
+    match err {
+        Err(e) => return Err(e),
+        _ => (),
+    }
+
+    let (sourcePath, _) = copyInPath(task, sourceAddr, true)?;
+    let (targetPath, _) = copyInPath(task, targetAddr, false)?;
+
+    let mut data = String::new();
+    if dataAddr != 0 {
+        // In Linux, a full page is always copied in regardless of null
+        // character placement, and the address is passed to each file system.
+        // All the file systems we implement treat it as a string.
+        let (str, err) = task.CopyInString(dataAddr, MemoryDef::PAGE_SIZE as usize);
+        match err {
+            Err(e) => return Err(e),
+            _ => (),
+        }
+        data = str;
+    }
+
+    // Ignore magic value that was required before Linux 2.4.
+    if flags & LibcConst::MS_MGC_MSK == LibcConst::MS_MGC_VAL {
+        flags &= !LibcConst::MS_MGC_MSK;
+    }
+
+    // Must have CAP_SYS_ADMIN in the mount namespace's associated user
+    // namespace.
+    let userns = task.mountNS.UserNamespace();
+    if !task
+        .Thread()
+        .HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
+    {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let unsupportedOps = LibcConst::MS_REMOUNT
+        | LibcConst::MS_BIND
+        | LibcConst::MS_SHARED
+        | LibcConst::MS_PRIVATE
+        | LibcConst::MS_SLAVE
+        | LibcConst::MS_UNBINDABLE
+        | LibcConst::MS_MOVE;
+
+    // MS_NODEV is silently allowed as device files aren't honored on guest
+    // mounts anyway.
+    let unsupportedFlags = LibcConst::MS_NODIRATIME;
+
+    // Linux just allows passing any flags to mount(2), it won't fail when
+    // unknown or unsupported flags are passed. As we don't implement
+    // everything, fail explicitly on flags that are unimplemented.
+    if flags & (unsupportedOps | unsupportedFlags) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let rsys = match FindFilesystem(&fsType) {
+        None => return Err(Error::SysError(SysErr::ENODEV)),
+        Some(f) => f,
+    };
+
+    if !rsys.lock().AllowUserMount() {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let superFlags = MountSourceFlags {
+        ReadOnly: flags & LibcConst::MS_RDONLY != 0,
+        NoAtime: flags & LibcConst::MS_NOATIME != 0,
+        StrictAtime: flags & LibcConst::MS_STRICTATIME != 0,
+        NoExec: flags & LibcConst::MS_NOEXEC != 0,
+        NoSuid: flags & LibcConst::MS_NOSUID != 0,
+        ..Default::default()
+    };
+
+    let rootInode = match rsys.lock().Mount(task, &sourcePath, &superFlags, &data) {
+        Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+        Ok(inode) => inode,
+    };
+    rootInode.lock().MountSource.lock().Source = sourcePath.to_string();
+
+    fileOpOn(
+        task,
+        ATType::AT_FDCWD,
+        &targetPath,
+        true,
+        &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+            return task.mountNS.Mount(d, &rootInode);
+        },
+    )?;
+
+    return Ok(0);
+}
+
+// Umount2 implements Linux syscall umount2(2).
+pub fn SysUmount2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0 as u64;
+    let flags = args.arg1 as i32 as u64;
+
+    let known = LibcConst::MNT_FORCE
+        | LibcConst::MNT_DETACH
+        | LibcConst::MNT_EXPIRE
+        | LibcConst::UMOUNT_NOFOLLOW;
+    if flags & !known != 0 || flags & LibcConst::MNT_EXPIRE != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // MNT_FORCE aborts the pending requests of network filesystems. None of the
+    // guest filesystems has requests to abort, so like a local filesystem on
+    // Linux this is a regular unmount and still fails with EBUSY when in use.
+
+    let userns = task.mountNS.UserNamespace();
+    if !task
+        .Thread()
+        .HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
+    {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let (path, _) = copyInPath(task, addr, false)?;
+
+    let resolve = flags & LibcConst::UMOUNT_NOFOLLOW == 0;
+    let detachOnly = flags & LibcConst::MNT_DETACH != 0;
+
+    fileOpOn(
+        task,
+        ATType::AT_FDCWD,
+        &path,
+        resolve,
+        &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+            return task.mountNS.Unmount(d, detachOnly);
+        },
+    )?;
+
+    return Ok(0);
+}
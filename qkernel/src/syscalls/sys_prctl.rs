@@ -153,6 +153,14 @@ pub const PR_MPX_ENABLE_MANAGEMENT: i32 = 43;
 // Protection eXtensions (MPX) bounds tables.
 pub const PR_MPX_DISABLE_MANAGEMENT: i32 = 44;
 
+// PR_CAP_AMBIENT reads or changes the ambient capability set.
+pub const PR_CAP_AMBIENT: i32 = 47;
+
+pub const PR_CAP_AMBIENT_IS_SET: u64 = 1;
+pub const PR_CAP_AMBIENT_RAISE: u64 = 2;
+pub const PR_CAP_AMBIENT_LOWER: u64 = 3;
+pub const PR_CAP_AMBIENT_CLEAR_ALL: u64 = 4;
+
 // From <asm/prctl.h>
 // Flags are used in syscall arch_prctl(2).
 pub const ARCH_SET_GS: i32 = 0x1001;
@@ -348,6 +356,45 @@ pub fn SysPrctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             }
 
             thread.DropBoundingCapability(cap as u64)?;
+            task.creds = thread.Creds();
+            return Ok(0);
+        }
+        PR_CAP_AMBIENT => {
+            // the unused arguments must be zero
+            if args.arg3 != 0 || args.arg4 != 0 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            if args.arg1 == PR_CAP_AMBIENT_CLEAR_ALL {
+                if args.arg2 != 0 {
+                    return Err(Error::SysError(SysErr::EINVAL));
+                }
+
+                thread.ClearAmbientCapabilities();
+                task.creds = thread.Creds();
+                return Ok(0);
+            }
+
+            let cap = args.arg2 as i32;
+            if !Capability::Ok(cap) {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            match args.arg1 {
+                PR_CAP_AMBIENT_IS_SET => {
+                    let cred = thread.Credentials();
+                    if CapSetOf(cap as u64).0 & cred.lock().AmbientCaps.0 != 0 {
+                        return Ok(1);
+                    }
+
+                    return Ok(0);
+                }
+                PR_CAP_AMBIENT_RAISE => thread.SetAmbientCapability(cap as u64, true)?,
+                PR_CAP_AMBIENT_LOWER => thread.SetAmbientCapability(cap as u64, false)?,
+                _ => return Err(Error::SysError(SysErr::EINVAL)),
+            }
+
+            task.creds = thread.Creds();
             return Ok(0);
         }
         PR_SET_CHILD_SUBREAPER => {
@@ -387,3 +434,16 @@ pub fn SysPrctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     return Ok(0);
 }
+
+// Personality implements Linux syscall personality(2). Every persona is accepted,
+// only ADDR_NO_RANDOMIZE has an effect, on the layout of the next exec.
+pub fn SysPersonality(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let persona = args.arg0 as u32;
+
+    let thread = task.Thread();
+    if persona == Personality::QUERY {
+        return Ok(thread.Personality() as i64);
+    }
+
+    return Ok(thread.SetPersonality(persona) as i64);
+}
@@ -0,0 +1,27 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::qlib::common::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+
+// Ptrace implements linux system call ptrace(2).
+pub fn SysPtrace(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let req = args.arg0 as i64;
+    let pid = args.arg1 as i32;
+    let addr = args.arg2;
+    let data = args.arg3;
+
+    return task.Ptrace(req, pid, addr, data);
+}
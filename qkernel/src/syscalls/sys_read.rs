@@ -191,8 +191,17 @@ pub fn Readv(task: &Task, fd: i32, addr: u64, iovcnt: i32) -> Result<i64> {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
+    if iovcnt == 0 {
+        return Ok(0);
+    }
+
     let mut dsts = task.IovsFromAddr(addr, iovcnt as usize)?;
 
+    // like read(2) with a zero size, don't block or consume anything
+    if Iovs(&dsts).Count() == 0 {
+        return Ok(0);
+    }
+
     return readv(task, &file, &mut dsts);
 }
 
@@ -270,6 +279,10 @@ pub fn Preadv(task: &Task, fd: i32, addr: u64, iovcnt: i32, offset: i64) -> Resu
     }
 
     let mut dsts = task.IovsFromAddr(addr, iovcnt as usize)?;
+    if Iovs(&dsts).Count() == 0 {
+        return Ok(0);
+    }
+
     let iovs = task.AdjustIOVecPermission(&mut dsts, true, true)?;
     let iovs = IOVecs::New(iovs);
     return preadvfull(task, &file, iovs, offset);
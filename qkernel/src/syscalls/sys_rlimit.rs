@@ -46,7 +46,15 @@ pub unsafe fn InitSingleton() {
     );
 }
 
-pub fn PrLimit64(thread: &Thread, resource: LimitType, newLimit: Option<Limit>) -> Result<Limit> {
+// PrLimit64 gets the limit of the process of thread and sets it to newLimit. The
+// caller, which can be in another process, needs CAP_SYS_RESOURCE to raise the
+// hard limit.
+pub fn PrLimit64(
+    caller: &Thread,
+    thread: &Thread,
+    resource: LimitType,
+    newLimit: Option<Limit>,
+) -> Result<Limit> {
     let tg = thread.ThreadGroup();
     let limits = tg.Limits();
 
@@ -64,7 +72,7 @@ pub fn PrLimit64(thread: &Thread, resource: LimitType, newLimit: Option<Limit>)
 
     let kernel = thread.Kernel();
     let root = kernel.RootUserNamespace();
-    let privileged = thread.HasCapabilityIn(Capability::CAP_SYS_RESOURCE, &root);
+    let privileged = caller.HasCapabilityIn(Capability::CAP_SYS_RESOURCE, &root);
 
     let oldLim = limits.Set(resource, newLimit.unwrap(), privileged)?;
 
@@ -107,7 +115,8 @@ pub fn SysGetrlimit(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         Some(r) => r,
     };
 
-    let lim = PrLimit64(&task.Thread(), resource, None)?;
+    let thread = task.Thread();
+    let lim = PrLimit64(&thread, &thread, resource, None)?;
     let rlim = RLimit64::FromLimit(&lim);
 
     //*task.GetTypeMut(addr)? = rlim;
@@ -126,7 +135,8 @@ pub fn SysSetrlimit(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     let rlim: RLimit64 = task.CopyInObj(addr)?;
 
-    PrLimit64(&task.Thread(), resource, Some(rlim.ToLimit()))?;
+    let thread = task.Thread();
+    PrLimit64(&thread, &thread, resource, Some(rlim.ToLimit()))?;
 
     return Ok(0);
 }
@@ -190,7 +200,7 @@ pub fn SysPrlimit64(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         }
     }
 
-    let oldLim = PrLimit64(&ot, resource, newlim)?;
+    let oldLim = PrLimit64(&thread, &ot, resource, newlim)?;
 
     if oldRlimAddr != 0 {
         let rlim = RLimit64::FromLimit(&oldLim);
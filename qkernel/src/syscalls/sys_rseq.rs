@@ -0,0 +1,35 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux::rseq::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+
+// RSeq implements syscall rseq(2).
+pub fn SysRSeq(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0;
+    let length = args.arg1 as u32;
+    let flags = args.arg2 as i32;
+    let signature = args.arg3 as u32;
+
+    match flags {
+        0 => task.SetRSeq(addr, length, signature)?,
+        RSEQ_FLAG_UNREGISTER => task.ClearRSeq(addr, length, signature)?,
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+
+    return Ok(0);
+}
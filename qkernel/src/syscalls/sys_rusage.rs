@@ -36,7 +36,10 @@ pub fn GetUsage(task: &Task, which: i32) -> Result<Rusage> {
     return Ok(Rusage {
         UTime: Timeval::FromNs(cs.UserTime),
         STime: Timeval::FromNs(cs.SysTime),
+        MinFlt: cs.MinorFaults as i64,
+        MajFlt: cs.MajorFaults as i64,
         NVCSw: cs.VoluntarySwitches as i64,
+        NIvCSw: cs.InvoluntarySwitches as i64,
         MaxRSS: (task.Thread().MaxRSS(which) / 1024) as i64,
         ..Default::default()
     });
@@ -49,12 +52,12 @@ pub fn GetUsage(task: &Task, which: i32) -> Result<Rusage> {
 //
 //	y    struct timeval ru_utime; /* user CPU time used */
 //	y    struct timeval ru_stime; /* system CPU time used */
-//	p    long   ru_maxrss;        /* maximum resident set size */
+//	y    long   ru_maxrss;        /* maximum resident set size */
 //	*    long   ru_ixrss;         /* integral shared memory size */
 //	*    long   ru_idrss;         /* integral unshared data size */
 //	*    long   ru_isrss;         /* integral unshared stack size */
-//	p    long   ru_minflt;        /* page reclaims (soft page faults) */
-//	p    long   ru_majflt;        /* page faults (hard page faults) */
+//	y    long   ru_minflt;        /* page reclaims (soft page faults) */
+//	y    long   ru_majflt;        /* page faults (hard page faults) */
 //	*    long   ru_nswap;         /* swaps */
 //	p    long   ru_inblock;       /* block input operations */
 //	p    long   ru_oublock;       /* block output operations */
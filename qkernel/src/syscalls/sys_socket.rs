@@ -113,11 +113,22 @@ pub fn SysSocketPair(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     s2.SetFlags(task, fileFlags);
     s2.flags.lock().0.NonSeekable = true;
 
+    // like Linux, don't leave any fd behind when the call fails
     let fd1 = task.NewFDFrom(0, &s1, &fdFlags)?;
-    let fd2 = task.NewFDFrom(0, &s2, &fdFlags)?;
+    let fd2 = match task.NewFDFrom(0, &s2, &fdFlags) {
+        Ok(fd) => fd,
+        Err(e) => {
+            task.RemoveFile(fd1)?;
+            return Err(e);
+        }
+    };
 
     let fds = [fd1, fd2];
-    task.CopyOutSlice(&fds, socks, 2)?;
+    if let Err(e) = task.CopyOutSlice(&fds, socks, 2) {
+        task.RemoveFile(fd1)?;
+        task.RemoveFile(fd2)?;
+        return Err(e);
+    }
 
     return Ok(0);
 }
@@ -74,7 +74,7 @@ pub fn Splice(task: &Task, dst: &File, src: &File, opts: &mut SpliceOpts) -> Res
         opts.DstStart = *dstLock;
     } else if !srcPipe && !opts.SrcOffset {
         srcLock = src.offset.Lock(task)?;
-        opts.SrcStart = *dstLock;
+        opts.SrcStart = *srcLock;
     }
 
     // Check append-only mode and the limit.
@@ -93,7 +93,7 @@ pub fn Splice(task: &Task, dst: &File, src: &File, opts: &mut SpliceOpts) -> Res
         let (limit, ok) = dst.checkLimit(task, opts.DstStart);
         if ok && limit == 0 {
             return Err(Error::ErrExceedsFileSizeLimit);
-        } else if ok && limit < opts.DstStart {
+        } else if ok && limit < opts.Length {
             opts.Length = limit; // Cap the write.
         }
     }
@@ -726,3 +726,88 @@ pub fn SysSendfile(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     return Ok(n);
 }
+
+// CopyFileRange implements linux syscall copy_file_range(2). Between two host files
+// the copy is done by the host, see HostFileOp::WriteTo.
+pub fn SysCopyFileRange(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let inFD = args.arg0 as i32;
+    let inOffset = args.arg1 as u64;
+    let outFD = args.arg2 as i32;
+    let outOffset = args.arg3 as u64;
+    let count = args.arg4 as u64;
+    let flags = args.arg5 as u32;
+
+    if flags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let src = task.GetFile(inFD)?;
+    let dst = task.GetFile(outFD)?;
+    if !src.Flags().Read || !dst.Flags().Write || dst.Flags().Append {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    let srcInode = src.Dirent.Inode();
+    let srcAttr = srcInode.StableAttr();
+    let dstInode = dst.Dirent.Inode();
+    let dstAttr = dstInode.StableAttr();
+    if srcAttr.IsDir() || dstAttr.IsDir() {
+        return Err(Error::SysError(SysErr::EISDIR));
+    }
+
+    if !srcAttr.IsRegular() || !dstAttr.IsRegular() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let count = if count > MAX_RW_COUNT as u64 {
+        MAX_RW_COUNT
+    } else {
+        count as i64
+    };
+
+    let mut opts = SpliceOpts {
+        Length: count,
+        ..Default::default()
+    };
+
+    if inOffset != 0 {
+        opts.SrcOffset = true;
+        opts.SrcStart = task.CopyInObj(inOffset)?;
+    }
+
+    if outOffset != 0 {
+        opts.DstOffset = true;
+        opts.DstStart = task.CopyInObj(outOffset)?;
+    }
+
+    // the ranges of a copy within a file may not overlap
+    if srcAttr.DeviceId == dstAttr.DeviceId && srcAttr.InodeId == dstAttr.InodeId {
+        let srcStart = if opts.SrcOffset {
+            opts.SrcStart
+        } else {
+            src.Offset(task)?
+        };
+        let dstStart = if opts.DstOffset {
+            opts.DstStart
+        } else {
+            dst.Offset(task)?
+        };
+
+        if srcStart < dstStart + count && dstStart < srcStart + count {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+    }
+
+    let (srcStart, dstStart) = (opts.SrcStart, opts.DstStart);
+    let n = DoSplice(task, &dst, &src, &mut opts, false)?;
+
+    if inOffset != 0 {
+        task.CopyOutObj(&(srcStart + n), inOffset)?;
+    }
+
+    if outOffset != 0 {
+        task.CopyOutObj(&(dstStart + n), outOffset)?;
+    }
+
+    return Ok(n);
+}
@@ -334,6 +334,23 @@ fn statfsImpl(task: &Task, d: &Dirent, addr: u64) -> Result<()> {
 
     let info = inode.StatFS(task)?;
 
+    let mflags = inode.lock().MountSource.lock().Flags;
+    let mut flags = StatfsFlags::ST_VALID;
+    if mflags.ReadOnly {
+        flags |= StatfsFlags::ST_RDONLY;
+    }
+    if mflags.NoSuid {
+        flags |= StatfsFlags::ST_NOSUID;
+    }
+    if mflags.NoExec {
+        flags |= StatfsFlags::ST_NOEXEC;
+    }
+    if mflags.NoAtime {
+        flags |= StatfsFlags::ST_NOATIME;
+    } else if !mflags.StrictAtime {
+        flags |= StatfsFlags::ST_RELATIME;
+    }
+
     let statfs = LibcStatfs {
         Type: info.Type,
         BlockSize: sattr.BlockSize,
@@ -344,6 +361,7 @@ fn statfsImpl(task: &Task, d: &Dirent, addr: u64) -> Result<()> {
         FilesFree: info.FreeFiles,
         NameLength: NAME_MAX as u64,
         FragmentSize: sattr.BlockSize,
+        Flags: flags,
         ..Default::default()
     };
 
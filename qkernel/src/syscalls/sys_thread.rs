@@ -24,13 +24,16 @@ pub use xmas_elf::symbol_table::{Entry, Entry64};
 pub use xmas_elf::{P32, P64};
 
 use super::super::asm::*;
+use super::super::fs::procfs::task::namespace_symlink::*;
 use super::super::kernel::cpuset::*;
 use super::super::loader::loader::*;
 use super::super::memmgr::mm::*;
+use super::super::qlib::auxv::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux::rusage::*;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::path::*;
+use super::super::qlib::task_mgr::TaskId;
 use super::super::qlib::LoadAddr;
 use super::super::syscalls::syscalls::*;
 use super::super::task::*;
@@ -39,6 +42,7 @@ use super::super::threadmgr::task_clone::*;
 use super::super::threadmgr::task_exec::*;
 use super::super::threadmgr::task_exit::*;
 use super::super::threadmgr::task_sched::*;
+use super::super::threadmgr::task_signals::*;
 use super::super::threadmgr::thread::*;
 use super::super::vcpu::*;
 use super::super::SignalDef::*;
@@ -220,6 +224,7 @@ pub fn Execvat(
 
         let fileName = ExecvFilleName(task, dirfd, &fileName, flags)?;
 
+        let mut extraAxv = Vec::new();
         {
             let t = task.Thread().clone();
             let tg = t.lock().tg.clone();
@@ -317,6 +322,8 @@ pub fn Execvat(
                 // "Any alternate signal stack is not preserved (sigaltstack(2))." - execve(2)
                 t.lock().signalStack = SignalStack::default();
                 task.signalStack = SignalStack::default();
+                // the struct rseq is gone with the old address space
+                t.lock().rseqAddr = 0;
                 // "The termination signal is reset to SIGCHLD (see clone(2))."
                 tg.lock().terminationSignal = Signal(Signal::SIGCHLD);
                 // execed indicates that the process can no longer join a process group
@@ -330,7 +337,11 @@ pub fn Execvat(
 
             t.ExitRobustList(task);
 
-            t.lock().updateCredsForExecLocked();
+            let secure = t.lock().updateCredsForExecLocked();
+            extraAxv.push(AuxEntry {
+                Key: AuxVec::AT_SECURE,
+                Val: secure as u64,
+            });
 
             t.UnstopVforkParent();
 
@@ -354,10 +365,29 @@ pub fn Execvat(
             core::mem::drop(oldMM);
         }
 
-        let extraAxv = Vec::new();
         Load(task, &fileName, &mut argv, &envv, &extraAxv)?
     };
 
+    // "If the PTRACE_O_TRACEEXEC option is not in effect, all successful calls
+    // to execve(2) by the traced process will cause it to be sent a SIGTRAP
+    // signal" - ptrace(2). The task takes the syscall exit path to stop before
+    // running the new program.
+    let t = task.Thread();
+    if t.Tracer().is_some() {
+        let pt = task.GetPtRegs();
+        *pt = Default::default();
+        pt.rip = entry;
+        pt.cs = 0x23;
+        pt.eflags = 0x2 | 1 << 9 | 1 << 12 | 1 << 13;
+        pt.rsp = usersp;
+        pt.ss = 0x1b;
+
+        let info = SignalInfoNoInfo(Signal(Signal::SIGTRAP), &t, &t);
+        t.SendSignal(&info)?;
+        return Ok(0);
+    }
+    core::mem::drop(t);
+
     //need to clean object on stack before enter_user as the stack will be destroyed
     task.AccountTaskEnter(SchedState::RunningApp);
 
@@ -494,7 +524,7 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     }
 
     if options & WaitOption::WSTOPPED != 0 {
-        wopts.Events |= EVENT_CHILD_GROUP_STOP;
+        wopts.Events |= EVENT_CHILD_GROUP_STOP | EVENT_TRACEE_STOP;
     }
 
     let wr = match task.Wait(&wopts) {
@@ -541,20 +571,19 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     if s.Exited() {
         siCode = SignalInfo::CLD_EXITED;
         sigChld.status = s.ExitStatus();
-    } else if s.Signaled() {
-        siCode = SignalInfo::CLD_KILLED;
-        sigChld.status = s.Signal();
     } else if s.CoreDump() {
         siCode = SignalInfo::CLD_DUMPED;
         sigChld.status = s.Signal();
+    } else if s.Signaled() {
+        siCode = SignalInfo::CLD_KILLED;
+        sigChld.status = s.Signal();
     } else if s.Stopped() {
         if wr.Event == EVENT_TRACEE_STOP {
             siCode = SignalInfo::CLD_TRAPPED;
-            sigChld.status = s.TrapCause();
         } else {
-            siCode = SignalInfo::CLD_TRAPPED;
-            sigChld.status = s.StopSignal();
+            siCode = SignalInfo::CLD_STOPPED;
         }
+        sigChld.status = s.StopSignal();
     } else if s.Continued() {
         siCode = SignalInfo::CLD_CONTINUED;
         sigChld.status = Signal::SIGCONT;
@@ -683,26 +712,44 @@ pub fn SysUnshare(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         NewFSContext: flags & CloneOp::CLONE_FS == CloneOp::CLONE_FS,
         NewUTSNamespace: flags & CloneOp::CLONE_NEWUTS == CloneOp::CLONE_NEWUTS,
         NewIPCNamespace: flags & CloneOp::CLONE_NEWIPC == CloneOp::CLONE_NEWIPC,
+        NewMountNamespace: flags & CloneOp::CLONE_NEWNS == CloneOp::CLONE_NEWNS,
         ..Default::default()
     };
 
-    // "CLONE_NEWPID automatically implies CLONE_THREAD as well." - unshare(2)
-    if opts.NewPIDNamespace {
-        opts.NewThreadGroup = true;
+    // "CLONE_NEWNS ... implies CLONE_FS" - unshare(2)
+    if opts.NewMountNamespace {
+        opts.NewFSContext = true;
     }
 
-    // "... specifying CLONE_NEWUSER automatically implies CLONE_THREAD. Since
-    // Linux 3.9, CLONE_NEWUSER also automatically implies CLONE_FS."
-    if opts.NewUserNamespace {
-        opts.NewThreadGroup = true;
-        opts.NewFSContext = true;
-        panic!("Doesn't support create new usernamespace...");
+    // the children of a task can't be put into another pid namespace and, as
+    // for clone(2), new user namespaces aren't supported
+    if opts.NewPIDNamespace || opts.NewUserNamespace {
+        return Err(Error::SysError(SysErr::EINVAL));
     }
 
     task.Unshare(&opts)?;
     return Ok(0);
 }
 
+// Setns implements linux syscall setns(2).
+pub fn SysSetns(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let nstype = args.arg1 as i32;
+
+    let file = task.GetFile(fd)?;
+    let ns = match NamespaceOfFile(&file) {
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(ns) => ns,
+    };
+
+    if nstype != 0 && nstype != ns.Type() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    task.SetNamespace(&ns)?;
+    return Ok(0);
+}
+
 // SchedYield implements linux syscall sched_yield(2).
 pub fn SysScheduleYield(_task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
     Yield();
@@ -737,6 +784,12 @@ pub fn SysSchedSetaffinity(task: &mut Task, args: &SyscallArguments) -> Result<i
     }
 
     t.SetCPUMask(mask)?;
+    if t == task.Thread() {
+        Migrate();
+    } else {
+        MigrateTask(TaskId::New(t.lock().taskId));
+    }
+
     return Ok(0);
 }
 
@@ -13,13 +13,15 @@
 // limitations under the License.
 
 use alloc::string::String;
-use alloc::string::ToString;
+use alloc::vec::Vec;
 
 use super::super::qlib::common::*;
+use super::super::qlib::kernel::kernel::uts_namespace::*;
 use super::super::qlib::linux_def::*;
 use super::super::syscalls::syscalls::*;
 use super::super::task::*;
 use super::super::version::*;
+use super::super::SHARESPACE;
 
 // UTSLen is the maximum length of strings contained in fields of
 // UtsName.
@@ -72,8 +74,9 @@ pub fn UtsNameString(s: &[u8; UTS_LEN + 1]) -> String {
         i -= 1;
     }
 
+    // a hostname is any bytes
     let s = &s[..i + 1];
-    return String::from_utf8(s.to_vec()).unwrap();
+    return String::from_utf8_lossy(s).into_owned();
 }
 
 pub fn SysUname(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -81,15 +84,20 @@ pub fn SysUname(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     let version = &VERSION;
     let uts = task.Thread().UTSNamespace();
+    let configured = SHARESPACE.config.read().KernelRelease;
+    let release = configured.Get().unwrap_or(version.Release);
+    let machine = version.Arch.Machine();
 
     let mut u = UtsName::default();
 
     u.Sysname[0..version.Sysname.len()].copy_from_slice(version.Sysname.as_bytes());
-    u.Nodename[0..uts.HostName().len()].copy_from_slice(uts.HostName().as_bytes());
-    u.Release[0..version.Release.len()].copy_from_slice(version.Release.as_bytes());
+    let hostName = uts.HostName();
+    u.Nodename[0..hostName.len()].copy_from_slice(&hostName);
+    u.Release[0..release.len()].copy_from_slice(release.as_bytes());
     u.Version[0..version.Version.len()].copy_from_slice(version.Version.as_bytes());
-    u.Machine[0.."x86_64".len()].copy_from_slice("x86_64".as_bytes());
-    u.Domainname[0..uts.DomainName().len()].copy_from_slice(uts.DomainName().as_bytes());
+    u.Machine[0..machine.len()].copy_from_slice(machine.as_bytes());
+    let domainName = uts.DomainName();
+    u.Domainname[0..domainName.len()].copy_from_slice(&domainName);
 
     //let va : &mut UtsName = task.GetTypeMut(va)?;
     //*va = u;
@@ -106,30 +114,31 @@ pub fn SysUname(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Ok(0);
 }
 
-// Setdomainname implements Linux syscall setdomainname.
-pub fn SysSetdomainname(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
-    let nameAddr = args.arg0 as u64;
-    let size = args.arg1 as i32;
-
-    let utsns = task.Thread().UTSNamespace();
-    {
-        let creds = task.Creds();
-        let userNS = creds.lock().UserNamespace.clone();
-        if !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userNS) {
-            return Err(Error::SysError(SysErr::EPERM));
-        }
+// CopyInUTSName copies in the new hostname or domainname of sethostname and
+// setdomainname. Like Linux, the name is size bytes, not a NUL terminated string,
+// which are kept as they are, and changing it needs CAP_SYS_ADMIN in the user
+// namespace owning the UTS namespace.
+fn CopyInUTSName(task: &mut Task, utsns: &UTSNamespace, addr: u64, size: i32) -> Result<Vec<u8>> {
+    let creds = task.Creds();
+    if !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &utsns.UserNamespace()) {
+        return Err(Error::SysError(SysErr::EPERM));
     }
 
     if size < 0 || size > UTS_LEN as i32 {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
-    let (name, err) = task.CopyInString(nameAddr, UTS_LEN - 1);
-    match err {
-        Ok(()) => (),
-        Err(e) => return Err(e),
-    };
+    let name: Vec<u8> = task.CopyInVec(addr, size as usize)?;
+    return Ok(name);
+}
 
+// Setdomainname implements Linux syscall setdomainname.
+pub fn SysSetdomainname(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let nameAddr = args.arg0 as u64;
+    let size = args.arg1 as i32;
+
+    let utsns = task.Thread().UTSNamespace();
+    let name = CopyInUTSName(task, &utsns, nameAddr, size)?;
     utsns.SetDomainName(name);
     return Ok(0);
 }
@@ -140,30 +149,7 @@ pub fn SysSethostname(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let size = args.arg1 as i32;
 
     let utsns = task.Thread().UTSNamespace();
-    {
-        let creds = task.Creds();
-        let userNS = creds.lock().UserNamespace.clone();
-        if !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userNS) {
-            return Err(Error::SysError(SysErr::EPERM));
-        }
-    }
-
-    if size < 0 || size > UTS_LEN as i32 {
-        return Err(Error::SysError(SysErr::EINVAL));
-    }
-
-    let (name, err) = task.CopyInString(nameAddr, UTS_LEN - 1);
-    match err {
-        Ok(()) => (),
-        Err(e) => return Err(e),
-    };
-
-    let size = size as usize;
-    if size < name.len() {
-        utsns.SetHostName(name[0..size].to_string());
-    } else {
-        utsns.SetHostName(name);
-    }
-
+    let name = CopyInUTSName(task, &utsns, nameAddr, size)?;
+    utsns.SetHostName(name);
     return Ok(0);
 }
@@ -196,6 +196,13 @@ pub fn Writev(task: &Task, fd: i32, addr: u64, iovcnt: i32) -> Result<i64> {
     }
 
     let srcs = task.IovsFromAddr(addr, iovcnt as usize)?;
+
+    // unlike write(2), a vectored write of zero bytes doesn't reach the file, so
+    // it doesn't send an empty packet or fail on a full pipe
+    if Iovs(&srcs).Count() == 0 {
+        return Ok(0);
+    }
+
     return writev(task, &file, &srcs);
 }
 
@@ -234,6 +241,10 @@ pub fn Pwritev(task: &Task, fd: i32, addr: u64, iovcnt: i32, offset: i64) -> Res
     }
 
     let srcs = task.IovsFromAddr(addr, iovcnt as usize)?;
+    if Iovs(&srcs).Count() == 0 {
+        return Ok(0);
+    }
+
     return pwritev(task, &file, &srcs, offset);
 }
 
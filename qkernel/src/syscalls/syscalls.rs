@@ -27,14 +27,17 @@ use super::super::syscalls::sys_memfd::*;
 use super::super::syscalls::sys_mempolicy::*;
 use super::super::syscalls::sys_mmap::*;
 use super::super::syscalls::sys_mmap_socket::*;
+use super::super::syscalls::sys_mount::*;
 use super::super::syscalls::sys_msgqueue::*;
 use super::super::syscalls::sys_pipe::*;
 use super::super::syscalls::sys_poll::*;
 use super::super::syscalls::sys_prctl::*;
 use super::super::syscalls::sys_proxy::*;
+use super::super::syscalls::sys_ptrace::*;
 use super::super::syscalls::sys_random::*;
 use super::super::syscalls::sys_read::*;
 use super::super::syscalls::sys_rlimit::*;
+use super::super::syscalls::sys_rseq::*;
 use super::super::syscalls::sys_rusage::*;
 use super::super::syscalls::sys_sched::*;
 use super::super::syscalls::sys_sem::*;
@@ -236,7 +239,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysGetrusage,           // 098 sys_getrusage,
     SysInfo,                // 099 sys_sysinfo,
     SysTimes,               // 100 sys_times,
-    SysNoSupport,           // 101 sys_ptrace,
+    SysPtrace,              // 101 sys_ptrace,
     SysGetuid,              // 102 sys_getuid,
     SysSysLog,              // 103 sys_syslog,
     SysGetgid,              // 104 sys_getgid,
@@ -270,7 +273,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysUtime,               // 132 sys_utime,
     SysMknode,              // 133 sys_mknod,
     SysObsolete,            // 134 sys_uselib,
-    SysInvalid,             // 135 sys_personality,
+    SysPersonality,         // 135 sys_personality,
     SysNoSys,               // 136 sys_ustat,      Needs filesystem support.
     SysStatfs,              // 137 sys_statfs,
     SysFstatfs,             // 138 sys_fstatfs,
@@ -300,8 +303,8 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysSync,                // 162 sys_sync,
     SysCapErr,              // 163 sys_acct,
     SysCapErr,              // 164 sys_settimeofday,
-    NotImplementSyscall,    // 165 sys_mount,
-    NotImplementSyscall,    // 166 sys_umount2,
+    SysMount,               // 165 sys_mount,
+    SysUmount2,             // 166 sys_umount2,
     SysCapErr,              // 167 sys_swapon,
     SysCapErr,              // 168 sys_swapoff,
     SysCapErr,              // 169 sys_reboot,
@@ -407,7 +410,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysFaccessat,           // 269 sys_faccessat,
     SysPSelect,             // 270 sys_pselect6,
     SysPpoll,               // 271 sys_ppoll,
-    NotImplementSyscall,    // 272 sys_unshare,
+    SysUnshare,             // 272 sys_unshare,
     SysSetRobustList,       // 273 sys_set_robust_list,
     SysGetRobustList,       // 274 sys_get_robust_list,
     SysSplice,              // 275 sys_splice,
@@ -443,7 +446,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysCapErr,              //	305 sys_clock_adjtime,       CAP_SYS_TIME
     SysSyncFs,              //	304 sys_syncfs,
     SysSendMMsg,            //	303 sys_sendmmsg,
-    SysOpNotSupport,        //	302 sys_setns,                   Needs filesystem support
+    SysSetns,               //	302 sys_setns,
     SysGetcpu,              //	301 sys_getcpu,
     SysNoSys,               //	310 sys_process_vm_readv    Need ptrace
     SysNoSys,               //	311 sys_process_vm_writev
@@ -461,7 +464,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall,    //	323 sys_userfaultfd,
     SysMembarrier,          //	324 sys_membarrier,
     SysMlock2,              //	325 mlock2,
-    SysNoSys,               //	326 sys_copy_file_range,
+    SysCopyFileRange,       //	326 sys_copy_file_range,
     SysPreadv2,             //	327 sys_preadv2,
     SysPWritev2,            //	328 sys_pwritev2,
     NotImplementSyscall,    //	329 sys_pkey_mprotect,
@@ -469,7 +472,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall,    //	331 sys_pkey_free,
     SysStatx,               //	332 sys_statx,
     NotImplementSyscall,    //	333 sys_io_pgetevents
-    SysNoSys,               //	334 sys_rseq
+    SysRSeq,                //	334 sys_rseq
     //don't use numbers 334 through 423
     ///////////////////////////////////////////////////////////////////////////////////////
     NotExisting, //	335
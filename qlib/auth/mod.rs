@@ -23,6 +23,7 @@ pub mod userns;
 use alloc::vec::Vec;
 
 use super::common::*;
+use super::kernel::kernel::namespace::*;
 use super::linux_def::*;
 //use super::fs::inode::*;
 use self::cap_set::*;
@@ -44,6 +45,8 @@ pub struct CredentialsInternal {
     pub InheritableCaps: CapSet,
     pub EffectiveCaps: CapSet,
     pub BoundingCaps: CapSet,
+    // AmbientCaps is always a subset of PermittedCaps & InheritableCaps
+    pub AmbientCaps: CapSet,
 
     pub KeepCaps: bool,
     pub UserNamespace: UserNameSpace,
@@ -114,6 +117,7 @@ impl CredentialsInternal {
             InheritableCaps: self.InheritableCaps,
             EffectiveCaps: self.EffectiveCaps,
             BoundingCaps: self.BoundingCaps,
+            AmbientCaps: self.AmbientCaps,
             KeepCaps: self.KeepCaps,
             UserNamespace: self.UserNamespace.clone(),
         };
@@ -161,6 +165,7 @@ impl Credentials {
             InheritableCaps: CapSet::New(0),
             EffectiveCaps: CapSet::New(0),
             BoundingCaps: CapSet::New(0),
+            AmbientCaps: CapSet(0),
             KeepCaps: false,
             UserNamespace: UserNameSpace::NewRootUserNamespace(),
         };
@@ -183,6 +188,7 @@ impl Credentials {
             InheritableCaps: CapSet::New(0),
             EffectiveCaps: ALL_CAP,
             BoundingCaps: ALL_CAP,
+            AmbientCaps: CapSet(0),
             KeepCaps: false,
             UserNamespace: userns,
         };
@@ -220,6 +226,8 @@ impl Credentials {
                     creds.EffectiveCaps = caps.EffectiveCaps;
                     creds.BoundingCaps = caps.BoundingCaps;
                     creds.InheritableCaps = caps.InheritableCaps;
+                    creds.AmbientCaps.0 =
+                        caps.AmbientCaps.0 & caps.PermittedCaps.0 & caps.InheritableCaps.0;
                 }
                 None => {
                     if kuid.0 == ROOT_KUID.0 {
@@ -331,6 +339,7 @@ impl Credentials {
         let internal = UserNameSpaceInternal {
             parent: Some(ns),
             owner: self.lock().EffectiveKUID,
+            ino: NewNsIno(),
             ..Default::default()
         };
 
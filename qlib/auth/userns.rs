@@ -20,6 +20,7 @@ use core::ops::Bound::Included;
 use core::ops::Deref;
 
 use super::super::common::*;
+use super::super::kernel::kernel::namespace::*;
 use super::super::linux_def::*;
 use super::id::*;
 
@@ -32,6 +33,7 @@ pub struct UserNameSpaceInternal {
     pub uidMapToParent: IdMap,
     pub gidMapFromParent: IdMap,
     pub gidMapToParent: IdMap,
+    pub ino: u64,
 }
 
 impl UserNameSpaceInternal {
@@ -85,6 +87,7 @@ impl UserNameSpace {
             uidMapToParent: IdMap::All(),
             gidMapFromParent: IdMap::All(),
             gidMapToParent: IdMap::All(),
+            ino: NewNsIno(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
@@ -1,5 +1,5 @@
 #[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AuxVec {
     AT_NULL = 0,
 
@@ -72,6 +72,9 @@ pub enum AuxVec {
 
     // AT_SYSINFO_EHDR is the address of the VDSO.
     AT_SYSINFO_EHDR = 33,
+
+    // AT_MINSIGSTKSZ is the minimal stack size for a signal handler.
+    AT_MINSIGSTKSZ = 51,
 }
 
 #[derive(Debug, Copy, Clone)]
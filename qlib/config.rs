@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::string::String;
+
+use super::linux_def::MemoryDef;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Config {
     pub DebugLevel: DebugLevel,
@@ -42,6 +46,97 @@ pub struct Config {
     pub TlbShootdownWait: bool,
     #[serde(default)]
     pub Sandboxed: bool,
+    #[serde(default)]
+    pub SyscallStat: bool,
+    #[serde(default)]
+    pub UringTurboFsync: bool,
+    #[serde(default)]
+    pub IOThreadParkMs: u64,
+    #[serde(default)]
+    pub RecordReplay: RecordReplayMode,
+    // run without a host io_uring, see EnableUringFallback. It's turned on by itself
+    // when the host can't set up a ring.
+    #[serde(default)]
+    pub UringFallback: bool,
+    // io_uring depths, 0 picks the default: 1024 sqes with UringBuf, 64 otherwise,
+    // and twice as many cqes. See UringSizes for the limits. The host maps 68 bytes
+    // per sqe (the sqe and its index slot) and 16 bytes per cqe, so the 1024/2048
+    // default takes about 100KB and the 32768/65536 maximum about 3MB per sandbox.
+    #[serde(default)]
+    pub UringSqSize: u32,
+    #[serde(default)]
+    pub UringCqSize: u32,
+    // filled from the "DevicePassthrough" list of the config file, see DeviceAllowlist
+    #[serde(skip_serializing, skip_deserializing)]
+    pub DevicePassthrough: DeviceAllowlist,
+    // what happens when the guest needs more than MemoryLimit, see OvercommitMode
+    #[serde(default)]
+    pub Overcommit: OvercommitMode,
+    // memory in MB the guest applications may use, 0 is the size of the guest heap.
+    // The oom killer of the Heuristic mode only runs with a limit set.
+    #[serde(default)]
+    pub MemoryLimit: u64,
+    // disk bandwidth of the regular file reads and writes, 0 is unlimited. The
+    // burst is the bytes which can go through at once after an idle period, 0 is
+    // one second of the rate.
+    #[serde(default)]
+    pub DiskReadBytesPerSec: u64,
+    #[serde(default)]
+    pub DiskWriteBytesPerSec: u64,
+    #[serde(default)]
+    pub DiskBurstBytes: u64,
+    // disk operations per second, 0 is unlimited. Every read, write and fsync of a
+    // regular file is one operation, fsync counts as a write.
+    #[serde(default)]
+    pub DiskReadIops: u64,
+    #[serde(default)]
+    pub DiskWriteIops: u64,
+    // bandwidth of the inet sockets, 0 is unlimited. A socket can be slowed down
+    // further with SO_MAX_PACING_RATE. The burst is as for DiskBurstBytes.
+    #[serde(default)]
+    pub NetSendBytesPerSec: u64,
+    #[serde(default)]
+    pub NetRecvBytesPerSec: u64,
+    #[serde(default)]
+    pub NetBurstBytes: u64,
+    // the max number of inet sockets open at the same time in the sandbox,
+    // including the accepted connections, 0 is unlimited. socket() and accept()
+    // over the limit fail with ENFILE.
+    #[serde(default)]
+    pub MaxSockets: u64,
+    // filled from the "KernelRelease" string of the config file, see KernelRelease
+    #[serde(skip_serializing, skip_deserializing)]
+    pub KernelRelease: KernelRelease,
+    // turn off the randomization of the stack, the mmap base and the load address
+    // of the PIE executables, as if every process had personality(ADDR_NO_RANDOMIZE)
+    #[serde(default)]
+    pub DisableASLR: bool,
+    // report a vcpu which stays in the guest for VcpuWatchdogMs without a vm exit or
+    // a task switch, 0 turns the watchdog off. VcpuWatchdogPanic stops the sandbox
+    // after the report.
+    #[serde(default)]
+    pub VcpuWatchdogMs: u64,
+    #[serde(default)]
+    pub VcpuWatchdogPanic: bool,
+    // open the host files of the lookups and close them through io_uring, the
+    // guest registers the fds itself and queues them for the qvisor's fd table
+    #[serde(default)]
+    pub UringOpenClose: bool,
+    // a readdir of a host directory fires an async statx for its entries, so that a
+    // following lookup gets the attributes of the entry without another host call
+    #[serde(default)]
+    pub StatxPrefetch: bool,
+    // sched_setaffinity is a preference: a task may run on a vcpu outside of its
+    // mask while all the vcpus in the mask are busy
+    #[serde(default)]
+    pub SoftCPUAffinity: bool,
+    // the cpu time in us the tasks of the sandbox may use in every CpuMaxPeriodUs,
+    // like the cgroup v2 cpu.max, e.g. 150000 of 100000 is 1.5 cores. 0 is
+    // unlimited, a period of 0 is 100ms.
+    #[serde(default)]
+    pub CpuMaxQuotaUs: u64,
+    #[serde(default)]
+    pub CpuMaxPeriodUs: u64,
 }
 
 impl Config {
@@ -52,6 +147,77 @@ impl Config {
     pub fn Async(&self) -> bool {
         return self.LogType == LogType::Async;
     }
+
+    // EnableUringFallback switches to synchronous io for hosts without io_uring: file
+    // and socket io, fsync and accept go through plain host syscalls, and the few
+    // async ops left are run by the host's UringFallback instead of a ring.
+    pub fn EnableUringFallback(&mut self) {
+        self.UringFallback = true;
+        // the guest queues sqes in the submitq, there is no ring memory to map
+        self.UringBuf = true;
+        self.UringIO = false;
+        self.UringFixedFile = false;
+        self.UringStatx = false;
+        self.UringTurboFsync = false;
+        self.FileBufWrite = false;
+        self.AsyncAccept = false;
+    }
+
+    // UringSizes returns the (sq, cq) entry counts of the host io_uring. Both have to
+    // be powers of two within the kernel's limits and the cq can't be smaller than
+    // the sq, so that setting up the ring doesn't silently round or clamp them.
+    pub fn UringSizes(&self) -> core::result::Result<(u32, u32), String> {
+        let sqSize = match self.UringSqSize {
+            0 if self.UringBuf => URING_DEFAULT_ENTRIES,
+            0 => URING_SMALL_ENTRIES,
+            n => n,
+        };
+
+        let cqSize = match self.UringCqSize {
+            0 => sqSize * 2,
+            n => n,
+        };
+
+        if !sqSize.is_power_of_two() || sqSize > URING_MAX_ENTRIES {
+            return Err(format!(
+                "UringSqSize {} is not a power of two up to {}",
+                sqSize, URING_MAX_ENTRIES
+            ));
+        }
+
+        if !cqSize.is_power_of_two() || cqSize > URING_MAX_CQ_ENTRIES {
+            return Err(format!(
+                "UringCqSize {} is not a power of two up to {}",
+                cqSize, URING_MAX_CQ_ENTRIES
+            ));
+        }
+
+        if cqSize < sqSize {
+            return Err(format!(
+                "UringCqSize {} is smaller than UringSqSize {}",
+                cqSize, sqSize
+            ));
+        }
+
+        return Ok((sqSize, cqSize));
+    }
+
+    pub fn MemoryLimitBytes(&self) -> u64 {
+        if self.MemoryLimit == 0 {
+            return MemoryDef::HEAP_SIZE;
+        }
+
+        return self.MemoryLimit * MemoryDef::ONE_MB;
+    }
+
+    // CpuMaxPeriod returns the cpu.max period in us
+    pub fn CpuMaxPeriod(&self) -> u64 {
+        if self.CpuMaxPeriodUs == 0 {
+            return 100_000;
+        }
+
+        return self.CpuMaxPeriodUs;
+    }
 }
 
 impl Config {}
@@ -86,6 +252,34 @@ impl Default for Config {
             CopyDataWithPf: false,
             TlbShootdownWait: false,
             Sandboxed: false,
+            SyscallStat: false,
+            UringTurboFsync: false,
+            IOThreadParkMs: 0,
+            RecordReplay: RecordReplayMode::Off,
+            UringFallback: false,
+            UringSqSize: 0,
+            UringCqSize: 0,
+            DevicePassthrough: DeviceAllowlist::default(),
+            Overcommit: OvercommitMode::Heuristic,
+            MemoryLimit: 0,
+            DiskReadBytesPerSec: 0,
+            DiskWriteBytesPerSec: 0,
+            DiskBurstBytes: 0,
+            DiskReadIops: 0,
+            DiskWriteIops: 0,
+            NetSendBytesPerSec: 0,
+            NetRecvBytesPerSec: 0,
+            NetBurstBytes: 0,
+            MaxSockets: 0,
+            KernelRelease: KernelRelease::default(),
+            DisableASLR: false,
+            VcpuWatchdogMs: 0,
+            VcpuWatchdogPanic: false,
+            UringOpenClose: false,
+            StatxPrefetch: false,
+            SoftCPUAffinity: false,
+            CpuMaxQuotaUs: 0,
+            CpuMaxPeriodUs: 100000,
         };
     }
 }
@@ -126,3 +320,228 @@ pub enum LogType {
     Sync,
     Async,
 }
+
+// RecordReplayMode selects whether the host side sources of nondeterminism (the
+// random seed and the host clock readings) are logged or fed back from a log.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum RecordReplayMode {
+    Off,
+    Record,
+    Replay,
+}
+
+impl Default for RecordReplayMode {
+    fn default() -> Self {
+        return Self::Off;
+    }
+}
+
+// OvercommitMode is the guest's vm.overcommit_memory. Strict fails mmap, brk, mremap
+// and mprotect with ENOMEM when the private writable mappings of all processes would
+// go past the memory limit. Heuristic never fails a mapping, but once the pages in
+// use go past the limit, the oom killer kills the process with the most resident
+// memory.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum OvercommitMode {
+    Heuristic,
+    Strict,
+}
+
+impl Default for OvercommitMode {
+    fn default() -> Self {
+        return Self::Heuristic;
+    }
+}
+
+pub const URING_DEFAULT_ENTRIES: u32 = 1024;
+pub const URING_SMALL_ENTRIES: u32 = 64;
+// IORING_MAX_ENTRIES and IORING_MAX_CQ_ENTRIES of the host kernel
+pub const URING_MAX_ENTRIES: u32 = 32768;
+pub const URING_MAX_CQ_ENTRIES: u32 = 2 * URING_MAX_ENTRIES;
+
+pub const MAX_PASSTHROUGH_DEVICES: usize = 16;
+
+// DeviceAllowlist holds the host character and block devices, by major:minor, which
+// the guest may open. Other device nodes on the host file systems can still be looked
+// up and stat'ed, but opening them fails with EPERM. It is a fixed size array so that
+// Config stays Copy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeviceAllowlist {
+    pub count: usize,
+    pub devices: [(u16, u32); MAX_PASSTHROUGH_DEVICES],
+}
+
+impl DeviceAllowlist {
+    // Parse parses a list of "major:minor" entries
+    pub fn Parse(devices: &[String]) -> core::result::Result<Self, String> {
+        if devices.len() > MAX_PASSTHROUGH_DEVICES {
+            return Err(format!(
+                "DevicePassthrough has {} devices, at most {} are supported",
+                devices.len(),
+                MAX_PASSTHROUGH_DEVICES
+            ));
+        }
+
+        let mut ret = Self::default();
+        for d in devices {
+            let mut fields = d.trim().split(':');
+            let major = fields.next().and_then(|s| s.parse::<u16>().ok());
+            let minor = fields.next().and_then(|s| s.parse::<u32>().ok());
+            match (major, minor, fields.next()) {
+                (Some(major), Some(minor), None) if major < 0x1000 && minor < 0x100000 => {
+                    ret.devices[ret.count] = (major, minor);
+                    ret.count += 1;
+                }
+                _ => {
+                    return Err(format!(
+                        "DevicePassthrough entry {:?} is not major:minor",
+                        d
+                    ))
+                }
+            }
+        }
+
+        return Ok(ret);
+    }
+
+    pub fn Contains(&self, major: u16, minor: u32) -> bool {
+        return self.devices[..self.count].contains(&(major, minor));
+    }
+}
+
+pub const MAX_KERNEL_RELEASE: usize = 64;
+
+// KernelRelease is the release uname() reports instead of the built-in LINUX_RELEASE,
+// for the applications which need a newer kernel. It has to start with
+// "major.minor", e.g. "5.15.0-quark". Like DeviceAllowlist, it's a fixed size array
+// so that Config stays Copy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KernelRelease {
+    pub len: usize,
+    pub release: [u8; MAX_KERNEL_RELEASE],
+}
+
+impl Default for KernelRelease {
+    fn default() -> Self {
+        return Self {
+            len: 0,
+            release: [0; MAX_KERNEL_RELEASE],
+        };
+    }
+}
+
+impl KernelRelease {
+    // Parse parses the configured release, "" keeps the built-in one
+    pub fn Parse(release: &str) -> core::result::Result<Self, String> {
+        let release = release.trim();
+        let mut ret = Self::default();
+        if release.len() == 0 {
+            return Ok(ret);
+        }
+
+        if release.len() > MAX_KERNEL_RELEASE {
+            return Err(format!(
+                "KernelRelease {:?} is longer than {} bytes",
+                release, MAX_KERNEL_RELEASE
+            ));
+        }
+
+        let isNumber = |s: &str| s.len() > 0 && s.bytes().all(|c| c.is_ascii_digit());
+        let valid = match release.split_once('.') {
+            None => false,
+            Some((major, rest)) => {
+                let minor = rest.split(|c: char| !c.is_ascii_digit()).next();
+                isNumber(major) && minor.map_or(false, isNumber)
+            }
+        };
+
+        if !valid {
+            return Err(format!(
+                "KernelRelease {:?} doesn't start with major.minor",
+                release
+            ));
+        }
+
+        ret.release[..release.len()].copy_from_slice(release.as_bytes());
+        ret.len = release.len();
+        return Ok(ret);
+    }
+
+    pub fn Get(&self) -> Option<&str> {
+        if self.len == 0 {
+            return None;
+        }
+
+        return core::str::from_utf8(&self.release[..self.len]).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_DeviceAllowlistParse() {
+        let list = DeviceAllowlist::Parse(&["10:229".to_string(), " 10:200 ".to_string()]).unwrap();
+        assert!(list.Contains(10, 229));
+        assert!(list.Contains(10, 200));
+        assert!(!list.Contains(1, 3));
+        assert!(!list.Contains(0, 0));
+
+        assert!(DeviceAllowlist::Parse(&["10".to_string()]).is_err());
+        assert!(DeviceAllowlist::Parse(&["10:229:1".to_string()]).is_err());
+        assert!(DeviceAllowlist::Parse(&["fuse".to_string()]).is_err());
+        assert!(DeviceAllowlist::Parse(&["4096:0".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_KernelReleaseParse() {
+        assert_eq!(KernelRelease::Parse("").unwrap().Get(), None);
+        assert_eq!(
+            KernelRelease::Parse(" 5.15.0-quark ").unwrap().Get(),
+            Some("5.15.0-quark")
+        );
+        assert_eq!(KernelRelease::Parse("6.1").unwrap().Get(), Some("6.1"));
+
+        assert!(KernelRelease::Parse("5").is_err());
+        assert!(KernelRelease::Parse("5-15").is_err());
+        assert!(KernelRelease::Parse("v5.15").is_err());
+        assert!(KernelRelease::Parse("5.x").is_err());
+        assert!(KernelRelease::Parse(&"5.15.".repeat(20)).is_err());
+    }
+
+    #[test]
+    fn test_UringSizes() {
+        let mut config = Config::default();
+        assert_eq!(config.UringSizes(), Ok((1024, 2048)));
+        config.UringBuf = false;
+        assert_eq!(config.UringSizes(), Ok((64, 128)));
+
+        config.UringSqSize = 256;
+        assert_eq!(config.UringSizes(), Ok((256, 512)));
+        config.UringCqSize = 256;
+        assert_eq!(config.UringSizes(), Ok((256, 256)));
+
+        config.UringCqSize = 128;
+        assert!(config.UringSizes().is_err());
+        config.UringCqSize = 0;
+        config.UringSqSize = 1000;
+        assert!(config.UringSizes().is_err());
+        config.UringSqSize = 2 * URING_MAX_ENTRIES;
+        assert!(config.UringSizes().is_err());
+        config.UringSqSize = URING_MAX_ENTRIES;
+        assert_eq!(
+            config.UringSizes(),
+            Ok((URING_MAX_ENTRIES, URING_MAX_CQ_ENTRIES))
+        );
+    }
+
+    #[test]
+    fn test_MemoryLimitBytes() {
+        let mut config = Config::default();
+        assert_eq!(config.Overcommit, OvercommitMode::Heuristic);
+        assert_eq!(config.MemoryLimitBytes(), MemoryDef::HEAP_SIZE);
+        config.MemoryLimit = 512;
+        assert_eq!(config.MemoryLimitBytes(), 512 << 20);
+    }
+}
@@ -103,6 +103,20 @@ pub struct StartArgs {
     pub process: Process,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyscallStatArgs {
+    // reset the accumulated counters after they are returned
+    pub reset: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DumpThreadArgs {
+    // thread id in the root pid namespace
+    pub tid: i32,
+    // bytes of the user stack to read, from the stack pointer up
+    pub stackBytes: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum Payload {
     RootContainerStart(RootProcessStart),
@@ -117,6 +131,11 @@ pub enum Payload {
     CreateSubContainer(CreateArgs),
     StartSubContainer(StartArgs),
     WaitAll,
+    SyscallStat(SyscallStatArgs),
+    MemStat,
+    DumpThread(DumpThreadArgs),
+    // handled in order, answered with one BatchResp
+    Batch(Vec<Payload>),
 }
 
 impl Default for Payload {
@@ -150,6 +169,10 @@ pub enum UCallResp {
     CreateSubContainerResp,
     StartSubContainerResp,
     WaitAllResp(WaitAllResp),
+    SyscallStatResp(Vec<SyscallStatEntry>),
+    MemStatResp(MemStat),
+    DumpThreadResp(ThreadDump),
+    BatchResp(Vec<UCallResp>),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -174,3 +197,118 @@ pub struct WaitAllResp {
     pub execId: String,
     pub status: i32,
 }
+
+// SyscallStatEntry is the accumulated statistic of one syscall, the time unit is us
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SyscallStatEntry {
+    pub nr: u64,
+    pub name: String,
+    pub count: u64,
+    pub total: u64,
+    pub min: u64,
+    pub max: u64,
+    pub avg: u64,
+}
+
+// MemStatMapping is the memory usage of one vma, the sizes are in bytes
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct MemStatMapping {
+    pub start: u64,
+    pub end: u64,
+    // e.g. "rw-p", as in /proc/[pid]/maps
+    pub perms: String,
+    pub name: String,
+    pub resident: u64,
+    // resident pages of host files, in shared or not yet written private mappings
+    pub file: u64,
+    // the other resident pages, e.g. anonymous memory and private file copies
+    pub anon: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MemStatProcess {
+    pub pid: i32,
+    pub cmd: String,
+    pub mapped: u64,
+    pub resident: u64,
+    pub file: u64,
+    pub anon: u64,
+    pub mappings: Vec<MemStatMapping>,
+}
+
+impl MemStatProcess {
+    pub fn New(pid: i32, cmd: String, mappings: Vec<MemStatMapping>) -> Self {
+        let mut ret = Self {
+            pid: pid,
+            cmd: cmd,
+            ..Default::default()
+        };
+
+        for m in &mappings {
+            ret.mapped += m.end - m.start;
+            ret.resident += m.resident;
+            ret.file += m.file;
+            ret.anon += m.anon;
+        }
+
+        ret.mappings = mappings;
+        return ret;
+    }
+}
+
+// MemStat is a point in time snapshot of the guest memory usage
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MemStat {
+    // bytes the guest page allocator got from the heap, and the free part of it
+    pub allocatorTotal: u64,
+    pub allocatorFree: u64,
+    pub processes: Vec<MemStatProcess>,
+}
+
+// ThreadDump is the user state of a thread, taken while the thread is stopped
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ThreadDump {
+    pub tid: i32,
+    pub name: String,
+    // (name, value) in the order of struct user_regs_struct
+    pub regs: Vec<(String, u64)>,
+    // stack holds the bytes read from stackAddr, it is shorter than asked for when
+    // the stack mapping ends before
+    pub stackAddr: u64,
+    pub stack: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_MemStatProcessTotals() {
+        let mappings = vec![
+            MemStatMapping {
+                start: 0x400000,
+                end: 0x402000,
+                perms: "r-xp".to_string(),
+                resident: 0x2000,
+                file: 0x2000,
+                ..Default::default()
+            },
+            MemStatMapping {
+                start: 0x600000,
+                end: 0x700000,
+                perms: "rw-p".to_string(),
+                resident: 0x3000,
+                file: 0x1000,
+                anon: 0x2000,
+                ..Default::default()
+            },
+        ];
+
+        let p = MemStatProcess::New(1, "init".to_string(), mappings);
+        assert_eq!(p.mapped, 0x102000);
+        assert_eq!(p.resident, 0x5000);
+        assert_eq!(p.file, 0x3000);
+        assert_eq!(p.anon, 0x2000);
+        assert_eq!(p.mappings.len(), 2);
+    }
+}
@@ -58,6 +58,12 @@ pub const UNIX98_PTY_SLAVE_MAJOR: u16 = 136;
 // PTMX_MINOR is the minor device number for /dev/ptmx.
 pub const PTMX_MINOR: u32 = 2;
 
+// MISC_MAJOR is the major device number for miscellaneous devices.
+pub const MISC_MAJOR: u16 = 10;
+
+// TUN_MINOR is the minor device number for /dev/net/tun.
+pub const TUN_MINOR: u32 = 200;
+
 pub struct Device {
     pub id: ID,
     pub last: u64,
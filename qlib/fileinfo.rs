@@ -23,6 +23,7 @@ use crate::qlib::common::*;
 use crate::qlib::kernel::kernel::waiter::*;
 use crate::qlib::kernel::IOURING;
 use crate::qlib::rdmasocket::*;
+use crate::qlib::ringbuf::*;
 use crate::qlib::*;
 
 #[derive(Clone)]
@@ -101,6 +102,9 @@ impl FdInfo {
     }
 }
 
+// The table is indexed by the host fd, which is also the fd the guest uses to refer to
+// the host file, so looking up the FdInfo of a host fd from a completion or a host
+// epoll event is a direct index, there is no separate reverse map to keep in sync.
 #[derive(Debug, Clone)]
 pub struct FdTbl {
     map: Vec<Option<FdInfo>>,
@@ -117,7 +121,7 @@ impl Default for FdTbl {
 impl FdTbl {
     pub fn Get(&self, fd: i32) -> Option<FdInfo> {
         let fd = fd as usize;
-        if fd > self.map.len() {
+        if fd >= self.map.len() {
             return None;
         }
         return self.map[fd].clone();
@@ -125,7 +129,7 @@ impl FdTbl {
 
     pub fn Remove(&mut self, fd: i32) -> Option<FdInfo> {
         let fd = fd as usize;
-        if fd > self.map.len() {
+        if fd >= self.map.len() {
             return None;
         }
         return self.map[fd].take();
@@ -133,7 +137,7 @@ impl FdTbl {
 
     pub fn Contains(&self, fd: i32) -> bool {
         let fd = fd as usize;
-        if fd > self.map.len() {
+        if fd >= self.map.len() {
             return false;
         }
         return self.map[fd].is_some();
@@ -149,9 +153,20 @@ impl FdTbl {
     }
 }
 
+// HostFdChange is a host fd the guest has opened or closed through io_uring with
+// UringOpenClose. The qvisor applies the queued changes to fdTbl before it uses the
+// table, so that the guest doesn't need a host call per open and close.
+#[derive(Clone, Copy, Debug)]
+pub enum HostFdChange {
+    Add(i32),
+    // the fd is gone from the table without being closed, the guest closes it
+    Release(i32),
+}
+
 #[derive(Default)]
 pub struct IOMgr {
     pub fdTbl: Mutex<FdTbl>,
+    pub fdChanges: QRingQueue<HostFdChange>,
     pub eventfd: i32,
     pub epollfd: AtomicI32,
 }
@@ -246,3 +261,46 @@ impl FdWaitInfo {
         queue.Notify(EventMaskFromLinux(mask as u32));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn NewFdInfo(fd: i32) -> FdInfo {
+        return FdInfo(Arc::new(Mutex::new(FdInfoIntern {
+            fd: fd,
+            waitInfo: FdWaitInfo::default(),
+            flags: Flags(0),
+            sockInfo: Mutex::new(SockInfo::File),
+        })));
+    }
+
+    #[test]
+    fn test_FdTblLookupByHost() {
+        let mut tbl = FdTbl::default();
+        for fd in 3..2000 {
+            tbl.Insert(fd, NewFdInfo(fd));
+        }
+
+        for fd in (3..2000).step_by(3) {
+            assert!(tbl.Remove(fd).is_some());
+        }
+
+        for fd in 3..2000 {
+            match tbl.Get(fd) {
+                None => assert!(fd % 3 == 0),
+                Some(fdInfo) => {
+                    assert!(fd % 3 != 0);
+                    assert!(fdInfo.lock().fd == fd);
+                }
+            }
+        }
+
+        // out of the table, including the first index past the end
+        let len = tbl.map.len() as i32;
+        assert!(tbl.Get(len).is_none());
+        assert!(!tbl.Contains(len));
+        assert!(tbl.Remove(len).is_none());
+        assert!(tbl.Get(-1).is_none());
+    }
+}
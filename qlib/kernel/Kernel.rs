@@ -72,6 +72,30 @@ impl HostSpace {
         return HostSpace::Call(&mut msg, false) as i64;
     }
 
+    pub fn CopyFileRange(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+        let mut msg = Msg::CopyFileRange(CopyFileRange {
+            srcFd,
+            srcOffset,
+            dstFd,
+            dstOffset,
+            len,
+        });
+
+        return HostSpace::Call(&mut msg, false) as i64;
+    }
+
+    pub fn FileClone(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+        let mut msg = Msg::FileClone(FileClone {
+            srcFd,
+            srcOffset,
+            dstFd,
+            dstOffset,
+            len,
+        });
+
+        return HostSpace::Call(&mut msg, false) as i64;
+    }
+
     pub fn Sysinfo(addr: u64) -> i64 {
         let mut msg = Msg::Sysinfo(Sysinfo { addr });
 
@@ -125,6 +149,12 @@ impl HostSpace {
         return HostSpace::HCall(&mut msg, false) as i64;
     }
 
+    pub fn InterruptVcpu(vcpuId: u64) -> i64 {
+        let mut msg = Msg::InterruptVcpu(InterruptVcpu { vcpuId: vcpuId });
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
     pub fn IORead(fd: i32, iovs: u64, iovcnt: i32) -> i64 {
         let mut msg = Msg::IORead(IORead { fd, iovs, iovcnt });
 
@@ -283,6 +313,18 @@ impl HostSpace {
         return HostSpace::HCall(&mut msg, true) as i64;
     }
 
+    pub fn NewFile(fd: i32) -> i64 {
+        let mut msg = Msg::NewFile(NewFile { fd });
+
+        return HostSpace::HCall(&mut msg, true) as i64;
+    }
+
+    pub fn ReleaseFd(fd: i32) -> i64 {
+        let mut msg = Msg::ReleaseFd(ReleaseFd { fd });
+
+        return HostSpace::HCall(&mut msg, true) as i64;
+    }
+
     pub fn FAccessAt(dirfd: i32, pathname: u64, mode: i32, flags: i32) -> i64 {
         let mut msg = Msg::FAccessAt(FAccessAt {
             dirfd,
@@ -737,6 +779,12 @@ impl HostSpace {
         return HostSpace::Call(&mut msg, false) as i64;
     }
 
+    pub fn IoUringSubmit() -> i64 {
+        let mut msg = Msg::IoUringSubmit(IoUringSubmit {});
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
     pub fn Chown(pathname: u64, owner: u32, group: u32) -> i64 {
         let mut msg = Msg::Chown(Chown {
             pathname,
@@ -286,6 +286,9 @@ impl SignalInfo {
 
     // TRAP_BRKPT indicates a breakpoint trap.
     pub const TRAP_BRKPT: i32 = 1;
+
+    // TRAP_TRACE indicates a trace trap, e.g. after a single step.
+    pub const TRAP_TRACE: i32 = 2;
 }
 
 pub const UC_FP_XSTATE: u64 = 1;
@@ -141,7 +141,9 @@ impl Context64 {
     }
 
     // NewMmapLayout implements Context.NewMmapLayout consistently with Linux.
-    pub fn NewMmapLayout(min: u64, max: u64, r: &LimitSet) -> Result<MmapLayout> {
+    // Without randomize, the mmap bases and the stack are at fixed addresses, as
+    // with personality(ADDR_NO_RANDOMIZE).
+    pub fn NewMmapLayout(min: u64, max: u64, r: &LimitSet, randomize: bool) -> Result<MmapLayout> {
         let min = Addr(min).RoundUp()?.0;
 
         let mut max = if max > MAX_ADDR64 { MAX_ADDR64 } else { max };
@@ -182,6 +184,10 @@ impl Context64 {
             }
         }
 
+        if !randomize {
+            maxRand = 0;
+        }
+
         let rnd = MMapRand(maxRand)?;
         let l = MmapLayout {
             MinAddr: min,
@@ -207,7 +213,7 @@ impl Context64 {
     }
 
     // PIELoadAddress implements Context.PIELoadAddress.
-    pub fn PIELoadAddress(l: &MmapLayout) -> Result<u64> {
+    pub fn PIELoadAddress(l: &MmapLayout, randomize: bool) -> Result<u64> {
         let mut base = PREFERRED_PIELOAD_ADDR;
 
         let max = match Addr(base).AddLen(MAX_MMAP_RAND64) {
@@ -223,7 +229,10 @@ impl Context64 {
             base = l.TopDownBase / 3 * 2;
         }
 
-        let addr = base + MMapRand(MAX_MMAP_RAND64)?;
+        let mut addr = base;
+        if randomize {
+            addr += MMapRand(MAX_MMAP_RAND64)?;
+        }
 
         return Ok(Addr(addr).RoundDown().unwrap().0);
     }
@@ -231,6 +240,10 @@ impl Context64 {
 
 // mmapRand returns a random adjustment for randomizing an mmap layout.
 pub fn MMapRand(max: u64) -> Result<u64> {
+    if max == 0 {
+        return Ok(0);
+    }
+
     let addr = RandU64()? % max;
     return Ok(Addr(addr).RoundDown().unwrap().0);
 }
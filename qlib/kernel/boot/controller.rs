@@ -26,9 +26,11 @@ use super::super::super::super::kernel_def::{
 use super::super::super::common::*;
 use super::super::super::control_msg::*;
 use super::super::super::vcpu_mgr::*;
+use super::super::perflog::SYSCALL_STATS;
 use super::super::task::*;
 use super::super::taskMgr;
 use super::super::Kernel;
+use super::super::Scale;
 use super::super::SetWaitContainerfd;
 use super::super::WaitContainerfd;
 use super::super::IOURING;
@@ -166,83 +168,113 @@ pub fn ControlMsgHandler(fd: *const u8) {
     //info!("payload: {:?}", &msg.payload);
     //defer!(error!("payload handling ends"));
     match msg.payload {
+        Payload::RootContainerStart(_) => {
+            WriteControlMsgResp(fd, &UCallResp::RootContainerStartResp, true);
+            StartRootContainer(ptr::null());
+        }
+        Payload::ExecProcess(process) => {
+            StartExecProcess(fd, process);
+        }
+        Payload::StartSubContainer(startArgs) => {
+            match LOADER.StartSubContainer(startArgs.process) {
+                Ok((_, entry, userStackAddr, kernelStackAddr)) => {
+                    WriteControlMsgResp(fd, &UCallResp::StartSubContainerResp, true);
+                    StartSubContainerProcess(entry, userStackAddr, kernelStackAddr);
+                }
+                Err(e) => {
+                    WriteControlMsgResp(fd, &UCallResp::UCallRespErr(format!("{:?}", e)), true);
+                }
+            }
+        }
+        Payload::WaitAll => {
+            SetWaitContainerfd(fd);
+        }
+        Payload::Batch(payloads) => {
+            let mut resps = Vec::with_capacity(payloads.len());
+            for payload in payloads {
+                resps.push(HandlePayload(task, payload));
+            }
+            WriteControlMsgResp(fd, &UCallResp::BatchResp(resps), true);
+        }
+        payload => {
+            let resp = HandlePayload(task, payload);
+            WriteControlMsgResp(fd, &resp, true);
+        }
+    }
+
+    // free curent task in the waitfn context
+    CPULocal::SetPendingFreeStack(Task::Current().taskId);
+    super::super::taskMgr::SwitchToNewTask();
+}
+
+// Handle the payloads which are answered right away with a single response, these are
+// also the ones allowed in a Batch.
+pub fn HandlePayload(task: &Task, payload: Payload) -> UCallResp {
+    match payload {
         Payload::Pause => {
             let kernel = LOADER.Lock(task).unwrap().kernel.clone();
             kernel.Pause();
-            WriteControlMsgResp(fd, &UCallResp::PauseResp, true);
+            return UCallResp::PauseResp;
         }
         Payload::Unpause => {
             let kernel = LOADER.Lock(task).unwrap().kernel.clone();
             kernel.Unpause();
-            WriteControlMsgResp(fd, &UCallResp::UnpauseResp, true);
+            return UCallResp::UnpauseResp;
         }
         Payload::Ps(cid) => {
             let kernel = LOADER.Lock(task).unwrap().kernel.clone();
             let ps = Processes(&kernel, &cid);
-            WriteControlMsgResp(fd, &UCallResp::PsResp(ps), true);
+            return UCallResp::PsResp(ps);
         }
         Payload::Signal(signalArgs) => {
             HandleSignal(&signalArgs);
-            WriteControlMsgResp(fd, &UCallResp::SignalResp, true);
+            return UCallResp::SignalResp;
         }
         Payload::ContainerDestroy(cid) => {
             LOADER.Lock(task).unwrap().DestroyContainer(cid).unwrap();
-            WriteControlMsgResp(fd, &UCallResp::ContainerDestroyResp, true);
-        }
-        Payload::RootContainerStart(_) => {
-            WriteControlMsgResp(fd, &UCallResp::RootContainerStartResp, true);
-            StartRootContainer(ptr::null());
-        }
-        Payload::ExecProcess(process) => {
-            StartExecProcess(fd, process);
+            return UCallResp::ContainerDestroyResp;
         }
         Payload::WaitContainer(cid) => match LOADER.WaitContainer(cid) {
-            Ok(exitStatus) => {
-                WriteControlMsgResp(fd, &UCallResp::WaitContainerResp(exitStatus), true);
-            }
-            Err(e) => {
-                WriteControlMsgResp(fd, &UCallResp::UCallRespErr(format!("{:?}", e)), true);
-            }
+            Ok(exitStatus) => return UCallResp::WaitContainerResp(exitStatus),
+            Err(e) => return UCallResp::UCallRespErr(format!("{:?}", e)),
         },
         Payload::WaitPid(waitpid) => {
             match LOADER.WaitPID(waitpid.cid, waitpid.pid, waitpid.clearStatus) {
-                Ok(exitStatus) => {
-                    WriteControlMsgResp(fd, &UCallResp::WaitPidResp(exitStatus), true);
-                }
-                Err(e) => {
-                    WriteControlMsgResp(fd, &UCallResp::UCallRespErr(format!("{:?}", e)), true);
-                }
+                Ok(exitStatus) => return UCallResp::WaitPidResp(exitStatus),
+                Err(e) => return UCallResp::UCallRespErr(format!("{:?}", e)),
             }
         }
         Payload::CreateSubContainer(createArgs) => {
             match LOADER.CreateSubContainer(createArgs.cid, createArgs.fds) {
-                Ok(()) => {
-                    WriteControlMsgResp(fd, &UCallResp::CreateSubContainerResp, true);
-                }
-                Err(e) => {
-                    WriteControlMsgResp(fd, &UCallResp::UCallRespErr(format!("{:?}", e)), true);
-                }
+                Ok(()) => return UCallResp::CreateSubContainerResp,
+                Err(e) => return UCallResp::UCallRespErr(format!("{:?}", e)),
             }
         }
-        Payload::StartSubContainer(startArgs) => {
-            match LOADER.StartSubContainer(startArgs.process) {
-                Ok((_, entry, userStackAddr, kernelStackAddr)) => {
-                    WriteControlMsgResp(fd, &UCallResp::StartSubContainerResp, true);
-                    StartSubContainerProcess(entry, userStackAddr, kernelStackAddr);
-                }
-                Err(e) => {
-                    WriteControlMsgResp(fd, &UCallResp::UCallRespErr(format!("{:?}", e)), true);
-                }
+        Payload::SyscallStat(args) => {
+            let stats = SYSCALL_STATS.Snapshot(Scale);
+            if args.reset {
+                SYSCALL_STATS.Reset();
             }
+            return UCallResp::SyscallStatResp(stats);
         }
-        Payload::WaitAll => {
-            SetWaitContainerfd(fd);
+        Payload::MemStat => {
+            let kernel = LOADER.Lock(task).unwrap().kernel.clone();
+            return UCallResp::MemStatResp(MemStats(task, &kernel));
+        }
+        Payload::DumpThread(args) => {
+            let kernel = LOADER.Lock(task).unwrap().kernel.clone();
+            match DumpThread(task, &kernel, &args) {
+                Ok(dump) => return UCallResp::DumpThreadResp(dump),
+                Err(e) => return UCallResp::UCallRespErr(format!("{:?}", e)),
+            }
+        }
+        payload => {
+            return UCallResp::UCallRespErr(format!(
+                "payload {:?} can't be handled in a batch",
+                payload
+            ));
         }
     }
-
-    // free curent task in the waitfn context
-    CPULocal::SetPendingFreeStack(Task::Current().taskId);
-    super::super::taskMgr::SwitchToNewTask();
 }
 
 pub fn WriteWaitAllResponse(cid: String, execId: String, status: i32) {
@@ -362,8 +362,23 @@ fn mountFlags(opts: &Vec<String>) -> MountSourceFlags {
             "rw" => mf.ReadOnly = false,
             "ro" => mf.ReadOnly = true,
             "noatime" => mf.NoAtime = true,
+            "relatime" => {
+                mf.NoAtime = false;
+                mf.StrictAtime = false;
+            }
+            "strictatime" => {
+                mf.NoAtime = false;
+                mf.StrictAtime = true;
+            }
             "noexec" => mf.NoExec = true,
-            _ => info!("ignoring unknown mount option {}", o),
+            "nosuid" => mf.NoSuid = true,
+            _ => match o.strip_prefix("create_mode_mask=") {
+                Some(v) => match u16::from_str_radix(v, 8) {
+                    Ok(mask) => mf.CreateModeMask = Some(mask & 0o7777),
+                    Err(_) => info!("ignoring invalid mount option {}", o),
+                },
+                None => info!("ignoring unknown mount option {}", o),
+            },
         }
     }
 
@@ -12,10 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::string::ToString;
 use alloc::vec::Vec;
 
+use super::super::super::common::*;
 use super::super::super::control_msg::*;
+use super::super::super::linux::time::*;
+use super::super::super::linux_def::*;
 use super::super::kernel::kernel::*;
+use super::super::task::*;
+use super::super::threadmgr::thread::*;
+use super::super::PAGE_MGR;
 
 pub fn Processes(k: &Kernel, containerID: &str) -> Vec<ProcessInfo> {
     let ts = k.TaskSet();
@@ -56,3 +63,125 @@ pub fn Processes(k: &Kernel, containerID: &str) -> Vec<ProcessInfo> {
 
     return ret;
 }
+
+pub fn MemStats(task: &Task, k: &Kernel) -> MemStat {
+    let root = k.TaskSet().Root();
+    let tgs = root.ThreadGroups();
+
+    let mut processes = Vec::new();
+    for tg in tgs {
+        let pid = root.IDOfThreadGroup(&tg);
+        if pid == 0 {
+            continue;
+        }
+
+        let lead = match tg.Leader() {
+            None => continue,
+            Some(t) => t,
+        };
+
+        let mappings = lead.MemoryManager().MemStatSnapshot(task);
+        processes.push(MemStatProcess::New(pid, lead.Name(), mappings));
+    }
+
+    return MemStat {
+        allocatorTotal: PAGE_MGR.pagepool.TotalPages() * MemoryDef::PAGE_SIZE,
+        allocatorFree: PAGE_MGR.pagepool.FreePages() * MemoryDef::PAGE_SIZE,
+        processes: processes,
+    };
+}
+
+// the stack dump is capped, the thread stays stopped while it is read
+pub const DUMP_STACK_MAX: u64 = 1 << 20;
+// how long DumpThread waits for the thread to stop, in ms
+pub const DUMP_STOP_TIMEOUT: i64 = 1000;
+
+// StopThread stops the thread with an external stop and waits until it is parked in
+// DoStop, which a running thread reaches at its next syscall return or preemption.
+pub fn StopThread(task: &Task, thread: &Thread) -> Result<()> {
+    thread.BeginExternalStop();
+    for _ in 0..DUMP_STOP_TIMEOUT {
+        if thread.lock().stopped {
+            return Ok(());
+        }
+
+        task.blocker
+            .BlockWithMonoTimeout(false, Some(MILLISECOND))
+            .1
+            .ok();
+    }
+
+    thread.EndExternalStop();
+    return Err(Error::SysError(SysErr::ETIMEDOUT));
+}
+
+pub fn DumpThread(task: &Task, k: &Kernel, args: &DumpThreadArgs) -> Result<ThreadDump> {
+    let thread = match k.TaskSet().Root().TaskWithID(args.tid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(t) => t,
+    };
+
+    StopThread(task, &thread)?;
+
+    let taskId = thread.lock().taskId;
+    let target = TaskId::New(taskId).GetTask();
+    let regs = *target.GetPtRegs();
+    let fsBase = target.context.fs;
+
+    let mut dump = ThreadDump {
+        tid: args.tid,
+        name: thread.Name(),
+        stackAddr: regs.rsp,
+        ..Default::default()
+    };
+
+    for (name, val) in [
+        ("r15", regs.r15),
+        ("r14", regs.r14),
+        ("r13", regs.r13),
+        ("r12", regs.r12),
+        ("rbp", regs.rbp),
+        ("rbx", regs.rbx),
+        ("r11", regs.r11),
+        ("r10", regs.r10),
+        ("r9", regs.r9),
+        ("r8", regs.r8),
+        ("rax", regs.rax),
+        ("rcx", regs.rcx),
+        ("rdx", regs.rdx),
+        ("rsi", regs.rsi),
+        ("rdi", regs.rdi),
+        ("orig_rax", regs.orig_rax),
+        ("rip", regs.rip),
+        ("cs", regs.cs),
+        ("eflags", regs.eflags),
+        ("rsp", regs.rsp),
+        ("ss", regs.ss),
+        ("fs_base", fsBase),
+    ] {
+        dump.regs.push((name.to_string(), val));
+    }
+
+    // read page by page, the stack mapping may end before stackBytes
+    let mm = thread.MemoryManager();
+    let end = regs.rsp.saturating_add(args.stackBytes.min(DUMP_STACK_MAX));
+    let mut addr = regs.rsp;
+    while addr < end {
+        let next = core::cmp::min((addr | (MemoryDef::PAGE_SIZE - 1)) + 1, end);
+        let offset = dump.stack.len();
+        dump.stack.resize(offset + (next - addr) as usize, 0);
+        let to = dump.stack[offset..].as_mut_ptr() as u64;
+        if mm
+            .CopyDataInManual(task, addr, to, (next - addr) as usize, false)
+            .is_err()
+        {
+            dump.stack.truncate(offset);
+            break;
+        }
+
+        addr = next;
+    }
+
+    thread.EndExternalStop();
+    return Ok(dump);
+}
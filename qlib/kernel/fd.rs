@@ -66,41 +66,42 @@ impl IOWriter for MemBuf {
     }
 }
 
-pub fn IORead(fd: i32, buf: &[IoVec]) -> Result<i64> {
-    if buf.len() == 0 {
-        return Ok(0);
-    }
-
-    let iovsAddr = &buf[0] as *const _ as u64;
-    let iovcnt = buf.len() as i32;
-
-    let ret = HostSpace::IORead(fd, iovsAddr, iovcnt);
+// SplitIO issues op for every UIO_MAXIOV iovecs of buf, as the host rejects a larger
+// iovcnt. It stops at the first short transfer or error; an error is only returned
+// if nothing was transferred. op gets the iovecs and the offset from the start of buf.
+fn SplitIO(buf: &[IoVec], mut op: impl FnMut(u64, i32, u64) -> i64) -> Result<i64> {
+    let mut count = 0;
+    for iovs in buf.chunks(UIO_MAXIOV) {
+        let iovsAddr = &iovs[0] as *const _ as u64;
+        let ret = op(iovsAddr, iovs.len() as i32, count as u64);
+
+        if ret < 0 {
+            if count > 0 {
+                break;
+            }
+            return Err(Error::SysError(-ret as i32));
+        }
 
-    if ret < 0 {
-        return Err(Error::SysError(-ret as i32));
+        count += ret;
+        let len: usize = iovs.iter().map(|iov| iov.len).sum();
+        if (ret as usize) < len {
+            break;
+        }
     }
 
-    return Ok(ret);
+    return Ok(count);
 }
 
-pub fn IOReadAt(fd: i32, buf: &[IoVec], offset: u64) -> Result<i64> {
-    if buf.len() == 0 {
-        return Ok(0);
-    }
-
-    let iovsAddr = &buf[0] as *const _ as u64;
-    let mut iovcnt = buf.len() as i32;
-    if iovcnt > 1024 {
-        iovcnt = 1024;
-    }
-
-    let ret = HostSpace::IOReadAt(fd, iovsAddr, iovcnt, offset);
-
-    if ret < 0 {
-        return Err(Error::SysError(-ret as i32));
-    }
+pub fn IORead(fd: i32, buf: &[IoVec]) -> Result<i64> {
+    return SplitIO(buf, |iovsAddr, iovcnt, _| {
+        HostSpace::IORead(fd, iovsAddr, iovcnt)
+    });
+}
 
-    return Ok(ret);
+pub fn IOReadAt(fd: i32, buf: &[IoVec], offset: u64) -> Result<i64> {
+    return SplitIO(buf, |iovsAddr, iovcnt, off| {
+        HostSpace::IOReadAt(fd, iovsAddr, iovcnt, offset + off)
+    });
 }
 
 pub fn IOTTYRead(fd: i32, buf: &[IoVec]) -> Result<i64> {
@@ -110,8 +111,8 @@ pub fn IOTTYRead(fd: i32, buf: &[IoVec]) -> Result<i64> {
 
     let iovsAddr = &buf[0] as *const _ as u64;
     let mut iovcnt = buf.len() as i32;
-    if iovcnt > 1024 {
-        iovcnt = 1024;
+    if iovcnt > UIO_MAXIOV as i32 {
+        iovcnt = UIO_MAXIOV as i32;
     }
 
     let ret = HostSpace::IOTTYRead(fd, iovsAddr, iovcnt);
@@ -124,38 +125,15 @@ pub fn IOTTYRead(fd: i32, buf: &[IoVec]) -> Result<i64> {
 }
 
 pub fn IOWrite(fd: i32, buf: &[IoVec]) -> Result<i64> {
-    if buf.len() == 0 {
-        return Ok(0);
-    }
-
-    let iovsAddr = &buf[0] as *const _ as u64;
-    let iovcnt = buf.len() as i32;
-    let ret = HostSpace::IOWrite(fd, iovsAddr, iovcnt);
-
-    if ret < 0 {
-        return Err(Error::SysError(-ret as i32));
-    }
-
-    return Ok(ret);
+    return SplitIO(buf, |iovsAddr, iovcnt, _| {
+        HostSpace::IOWrite(fd, iovsAddr, iovcnt)
+    });
 }
 
 pub fn IOWriteAt(fd: i32, buf: &[IoVec], offset: u64) -> Result<i64> {
-    if buf.len() == 0 {
-        return Ok(0);
-    }
-
-    let iovsAddr = &buf[0] as *const _ as u64;
-    let mut iovcnt = buf.len() as i32;
-    if iovcnt > 1024 {
-        iovcnt = 1024;
-    }
-    let ret = HostSpace::IOWriteAt(fd, iovsAddr, iovcnt, offset);
-
-    if ret < 0 {
-        return Err(Error::SysError(-ret as i32));
-    }
-
-    return Ok(ret);
+    return SplitIO(buf, |iovsAddr, iovcnt, off| {
+        HostSpace::IOWriteAt(fd, iovsAddr, iovcnt, offset + off)
+    });
 }
 
 pub struct RangeReader<'a> {
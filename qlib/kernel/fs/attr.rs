@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use super::super::super::auth::*;
+use super::super::super::linux::time::*;
 use super::super::super::linux_def::*;
 use super::super::kernel::time::*;
 use super::super::task::*;
@@ -224,6 +225,18 @@ impl UnstableAttr {
 
         self.StatusChangeTime = now;
     }
+
+    // RelatimeNeedsUpdate implements the relatime policy: the atime is only
+    // updated when it isn't newer than the mtime or ctime, or is a day old.
+    pub fn RelatimeNeedsUpdate(&self, now: Time) -> bool {
+        if !self.ModificationTime.Before(self.AccessTime)
+            || !self.StatusChangeTime.Before(self.AccessTime)
+        {
+            return true;
+        }
+
+        return now.Sub(self.AccessTime) >= 24 * HOUR;
+    }
 }
 
 pub fn WithCurrentTime(task: &Task, u: &UnstableAttr) -> UnstableAttr {
@@ -32,6 +32,7 @@ use super::super::super::singleton::*;
 use super::super::socket::unix::transport::unix::*;
 use super::super::task::*;
 use super::super::uid::*;
+use super::super::util::cstring::*;
 use super::super::SHARESPACE;
 use super::dentry::*;
 use super::file::*;
@@ -50,6 +51,17 @@ pub unsafe fn InitSingleton() {
     RENAME.Init(RwLock::new(()));
 }
 
+// CheckNameLen fails a path component longer than NAME_MAX before it gets to the
+// file system, which might report another error or, for a host file, none at all.
+pub fn CheckNameLen(name: &str) -> Result<()> {
+    // a non-UTF-8 byte is escaped to a longer char, NAME_MAX counts the raw bytes
+    if BytesFromString(name).len() > NAME_MAX {
+        return Err(Error::SysError(SysErr::ENAMETOOLONG));
+    }
+
+    return Ok(());
+}
+
 pub fn DirentReadDir(
     task: &Task,
     d: &Dirent,
@@ -413,6 +425,8 @@ impl Dirent {
             return Err(Error::SysError(SysErr::ENOTDIR));
         }
 
+        CheckNameLen(name)?;
+
         if name == "" || name == "." {
             return Ok(self.clone());
         } else if name == ".." {
@@ -420,6 +434,9 @@ impl Dirent {
                 return Ok(self.clone());
             }
 
+            // a mount root takes the place of the mount point in its parent, so this
+            // steps out of the mount to the parent of the mount point, never to the
+            // covered directory
             match &self.main.lock().Parent {
                 None => return Ok(self.clone()),
                 Some(ref p) => return Ok(p.clone()),
@@ -487,6 +504,102 @@ impl Dirent {
         return self.walk(task, root, name);
     }
 
+    // WalkPath resolves the slash separated path from self in one pass, it is Walk
+    // for every component but with RENAME taken once. "." and ".." are handled by
+    // walk, the symlinks are followed with at most MAX_SYMLINK_TRAVERSALS of them.
+    // A trailing slash needs the path to end at a directory.
+    pub fn WalkPath(&self, task: &Task, root: &Dirent, path: &str) -> Result<Dirent> {
+        if path.len() == 0 {
+            return Err(Error::SysError(SysErr::ENOENT));
+        }
+
+        let _a = RENAME.read();
+        let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+        let d = self.walkPath(task, root, path, &mut remainingTraversals)?;
+        d.ExtendReference();
+        return Ok(d);
+    }
+
+    fn walkPath(
+        &self,
+        task: &Task,
+        root: &Dirent,
+        path: &str,
+        remainingTraversals: &mut u32,
+    ) -> Result<Dirent> {
+        let mut current = if path.starts_with('/') {
+            root.clone()
+        } else {
+            self.clone()
+        };
+
+        for name in path.split('/').filter(|name| name.len() > 0) {
+            let inode = current.Inode();
+            if !Arc::ptr_eq(&current, root) {
+                if !inode.StableAttr().IsDir() {
+                    return Err(Error::SysError(SysErr::ENOTDIR));
+                }
+
+                inode.CheckPermission(
+                    task,
+                    &PermMask {
+                        execute: true,
+                        ..Default::default()
+                    },
+                )?;
+            }
+
+            let next = {
+                let _dm = current.dirMu.read();
+                current.walk(task, root, name)?
+            };
+
+            current = current.followLink(task, root, next, remainingTraversals)?;
+        }
+
+        if path.ends_with('/') && !current.Inode().StableAttr().IsDir() {
+            return Err(Error::SysError(SysErr::ENOTDIR));
+        }
+
+        return Ok(current);
+    }
+
+    // followLink returns the target of next, a child of self, when it is a symlink
+    fn followLink(
+        &self,
+        task: &Task,
+        root: &Dirent,
+        next: Dirent,
+        remainingTraversals: &mut u32,
+    ) -> Result<Dirent> {
+        let inode = next.Inode();
+        match inode.GetLink(task) {
+            Ok(target) => {
+                if *remainingTraversals == 0 {
+                    return Err(Error::SysError(SysErr::ELOOP));
+                }
+
+                *remainingTraversals -= 1;
+                return Ok(target);
+            }
+            Err(Error::SysError(SysErr::ENOLINK)) => return Ok(next),
+            Err(Error::ErrResolveViaReadlink) => {
+                if *remainingTraversals == 0 {
+                    return Err(Error::SysError(SysErr::ELOOP));
+                }
+
+                let target = inode.ReadLink(task)?;
+                *remainingTraversals -= 1;
+                if target.len() == 0 {
+                    return Err(Error::SysError(SysErr::ENOENT));
+                }
+
+                return self.walkPath(task, root, &target, remainingTraversals);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
     pub fn RemoveChild(&self, name: &String) {
         self.children.lock().remove(name);
     }
@@ -495,6 +608,10 @@ impl Dirent {
         return self.main.lock().IsRoot();
     }
 
+    pub fn RefCount(&self) -> usize {
+        return Arc::strong_count(&self.0);
+    }
+
     pub fn AddChild(&self, name: String, child: &Dirent) -> Option<Dirent> {
         assert!(child.IsRoot(), "Add child request the child has no parent");
         child.main.lock().Parent = Some(self.clone());
@@ -535,6 +652,8 @@ impl Dirent {
         flags: &FileFlags,
         perms: &FilePermissions,
     ) -> Result<File> {
+        CheckNameLen(name)?;
+
         let _a = RENAME.read();
         let _dm = self.dirMu.write();
 
@@ -569,6 +688,8 @@ impl Dirent {
         name: &str,
         create: &mut FnMut() -> Result<()>,
     ) -> Result<()> {
+        CheckNameLen(name)?;
+
         let _a = RENAME.read();
         let _dm = self.dirMu.write();
 
@@ -783,7 +904,20 @@ impl Dirent {
             return Err(Error::SysError(SysErr::ENOENT));
         }
 
-        let parent = self.Parent().unwrap();
+        // did we race with deletion?
+        if self.IsDeleted() {
+            return Err(Error::SysError(SysErr::ENOENT));
+        }
+
+        if inode.StableAttr().IsDir() != self.Inode().StableAttr().IsDir() {
+            return Err(Error::SysError(SysErr::ENOTDIR));
+        }
+
+        // mounting over the root of the mount namespace isn't supported
+        let parent = match self.Parent() {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(p) => p,
+        };
         let replacement = Dirent::New(inode, &self.Name());
         replacement.main.lock().mounted = true;
 
@@ -797,7 +931,8 @@ impl Dirent {
         let parent = self
             .Parent()
             .expect("unmount required the parent is not none");
-        let old = parent.AddChild(replace.Name(), replace);
+        // the covered dirent was never detached from the parent, only hidden
+        let old = parent.addChild(replace.Name(), replace);
 
         match old {
             None => panic!("mount must mount over an existing dirent"),
@@ -909,6 +1044,9 @@ impl Dirent {
         newParent: &Dirent,
         newName: &str,
     ) -> Result<()> {
+        CheckNameLen(oldName)?;
+        CheckNameLen(newName)?;
+
         let _a = RENAME.write();
 
         if Arc::ptr_eq(oldParent, newParent) {
@@ -980,6 +1118,7 @@ impl Dirent {
         }
 
         let exist;
+        let mut replacedDirent = None;
         match newParent.walk(task, root, newName) {
             Ok(replaced) => {
                 newParent.mayDelete(task, &replaced)?;
@@ -1003,6 +1142,7 @@ impl Dirent {
                 replaced.flush();
 
                 exist = true;
+                replacedDirent = Some(replaced);
             }
             Err(Error::SysError(SysErr::ENOENT)) => {
                 exist = false;
@@ -1012,7 +1152,18 @@ impl Dirent {
 
         let mut newInode = renamed.Inode();
         newInode.Rename(task, oldParent, &renamed, newParent, newName, exist)?;
-        renamed.main.lock().Name = newName.to_string();
+        // the target is only gone once the rename has replaced it
+        if let Some(replaced) = replacedDirent {
+            replaced.SetDeleted();
+        }
+
+        {
+            // a task whose cwd is the renamed dirent, or one of its descendants, builds
+            // its getcwd path through the parent chain
+            let mut main = renamed.main.lock();
+            main.Name = newName.to_string();
+            main.Parent = Some(newParent.clone());
+        }
 
         newParent.children.lock().remove(newName);
         oldParent.children.lock().remove(oldName);
@@ -1081,6 +1232,7 @@ impl Dirent {
         }
 
         let exist;
+        let mut replacedDirent = None;
         match parent.walk(task, root, newName) {
             Ok(replaced) => {
                 parent.mayDelete(task, &replaced)?;
@@ -1102,9 +1254,9 @@ impl Dirent {
 
                 replaced.DropExtendedReference();
                 replaced.flush();
-                replaced.SetDeleted();
 
                 exist = true;
+                replacedDirent = Some(replaced);
             }
             Err(Error::SysError(SysErr::ENOENT)) => {
                 exist = false;
@@ -1114,6 +1266,9 @@ impl Dirent {
 
         let mut newInode = renamed.Inode();
         newInode.Rename(task, parent, &renamed, parent, newName, exist)?;
+        if let Some(replaced) = replacedDirent {
+            replaced.SetDeleted();
+        }
 
         renamed.main.lock().Name = newName.to_string();
 
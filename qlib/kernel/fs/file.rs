@@ -61,6 +61,7 @@ use crate::qlib::kernel::fs::host::hostdirfops::HostDirFops;
 use crate::qlib::kernel::fs::inotify::Inotify;
 use crate::qlib::kernel::fs::procfs::proc::RootProcFile;
 use crate::qlib::kernel::fs::procfs::seqfile::SeqFileOperations;
+use crate::qlib::kernel::fs::procfs::task::oom_score_adj::OOMScoreAdjFileOperations;
 use crate::qlib::kernel::fs::ramfs::dir::DirFileOperation;
 use crate::qlib::kernel::fs::ramfs::socket::SocketFileOps;
 use crate::qlib::kernel::fs::ramfs::symlink::SymlinkFileOperations;
@@ -283,6 +284,7 @@ pub enum FileOpsType {
     HostDirOp,
     TTYFileOps,
     RootProcFile,
+    OOMScoreAdjFileOperations,
     SeqFileOperations,
     DirFileOperation,
     SocketFileOps,
@@ -344,6 +346,7 @@ pub enum FileOps {
     UringSocketOperations(UringSocketOperations),
     UnixSocketOperations(UnixSocketOperations),
     RootProcFile(RootProcFile),
+    OOMScoreAdjFileOperations(OOMScoreAdjFileOperations),
 }
 
 impl FileOps {
@@ -507,6 +510,7 @@ impl Drop for File {
 
             let lockUniqueID = self.UniqueId();
             lockCtx.BSD.UnlockRegion(task, lockUniqueID, &Range::Max());
+            lockCtx.Lease.Remove(lockUniqueID);
 
             // Only unregister if we are currently registered. There is nothing
             // to register if f.async is nil (this happens when async mode is
@@ -885,6 +889,7 @@ impl File {
 
             if n > 0 {
                 *offsetLock = current + n;
+                self.NotifyAccess(task);
             }
 
             return Ok(n);
@@ -899,9 +904,22 @@ impl File {
         let fops = self.FileOp.clone();
         let blocking = self.Blocking();
         let n = fops.ReadAt(task, self, dsts, offset, blocking)?;
+        if n > 0 {
+            self.NotifyAccess(task);
+        }
         return Ok(n);
     }
 
+    // NotifyAccess updates the atime after a read unless the file was opened
+    // with O_NOATIME.
+    pub fn NotifyAccess(&self, task: &Task) {
+        if self.Flags().NoAtime {
+            return;
+        }
+
+        self.Dirent.Inode().NotifyAccess(task);
+    }
+
     pub fn offsetForAppend(&self, task: &Task) -> Result<i64> {
         let inode = self.Dirent.Inode();
         let uattr = match inode.UnstableAttr(task) {
@@ -946,7 +964,18 @@ impl File {
         if seekable {
             let mut offsetLock = self.offset.Lock(task)?;
             if self.flags.lock().0.Append {
-                let (cnt, len) = fops.Append(task, self, srcs)?;
+                let end = self.offsetForAppend(task)?;
+                let (limit, ok) = self.checkLimit(task, end);
+                if ok && limit == 0 {
+                    return Err(Error::ErrExceedsFileSizeLimit);
+                }
+
+                let (cnt, len) = if ok {
+                    let iovs = Iovs(srcs).First(limit as _);
+                    fops.Append(task, self, &iovs)?
+                } else {
+                    fops.Append(task, self, srcs)?
+                };
                 *offsetLock = len;
                 return Ok(cnt);
             }
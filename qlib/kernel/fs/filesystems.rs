@@ -51,8 +51,14 @@ pub fn GetFilesystems() -> Vec<Arc<QMutex<Filesystem>>> {
 pub struct MountSourceFlags {
     pub ReadOnly: bool,
     pub NoAtime: bool,
+    // StrictAtime updates the atime on every read, otherwise the relatime
+    // policy applies
+    pub StrictAtime: bool,
     pub ForcePageCache: bool,
     pub NoExec: bool,
+    pub NoSuid: bool,
+    // CreateModeMask is AND-ed with the mode of every file created on the mount
+    pub CreateModeMask: Option<u16>,
 }
 
 pub struct FileSystems {
@@ -32,6 +32,7 @@ pub struct FileFlags {
     pub Truncate: bool,
     pub NoFollow: bool,
     pub Path: bool,
+    pub NoAtime: bool,
 }
 
 impl FileFlags {
@@ -50,6 +51,7 @@ impl FileFlags {
             Truncate: mask & Flags::O_TRUNC as u32 != 0,
             NoFollow: mask & Flags::O_NOFOLLOW as u32 != 0,
             Path: mask & Flags::O_PATH as u32 != 0,
+            NoAtime: mask & Flags::O_NOATIME as u32 != 0,
             ..Default::default()
         };
     }
@@ -121,6 +123,10 @@ impl FileFlags {
             mask |= Flags::O_NOFOLLOW;
         }
 
+        if self.NoAtime {
+            mask |= Flags::O_NOATIME;
+        }
+
         if !self.Path {
             if self.Read && self.Write {
                 mask |= Flags::O_RDWR;
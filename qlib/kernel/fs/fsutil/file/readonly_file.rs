@@ -32,9 +32,11 @@ use crate::qlib::kernel::fs::procfs::meminfo::MeminfoFileNode;
 use crate::qlib::kernel::fs::procfs::net::NetTCPReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::net::NetUDPReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::net::NetUnixReadonlyFileNode;
+use crate::qlib::kernel::fs::procfs::sys::kernel::core_pattern::CorePatternFileNode;
 use crate::qlib::kernel::fs::procfs::task::auxvec::AUXVecReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::task::comm::CommReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::task::exec_args::ExecArgReadonlyFileNode;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NamespaceFileNode;
 use crate::qlib::kernel::fs::procfs::task::uid_pid_map::IdMapReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::uptime::UptimeFileNode;
 
@@ -86,6 +88,8 @@ pub enum ReadonlyFileNode {
     CommReadonlyFileNode(CommReadonlyFileNode),
     ExecArgReadonlyFileNode(ExecArgReadonlyFileNode),
     IdMapReadonlyFileNode(IdMapReadonlyFileNode),
+    NamespaceFileNode(NamespaceFileNode),
+    CorePatternFileNode(CorePatternFileNode),
     UptimeFileNode(UptimeFileNode),
 }
 
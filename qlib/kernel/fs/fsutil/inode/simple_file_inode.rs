@@ -41,6 +41,7 @@ use crate::qlib::kernel::fs::procfs::net::NetTCP;
 use crate::qlib::kernel::fs::procfs::net::NetUDP;
 use crate::qlib::kernel::fs::procfs::net::NetUnix;
 use crate::qlib::kernel::fs::procfs::stat::StatData;
+use crate::qlib::kernel::fs::procfs::sys::kernel::core_pattern::CorePatternSimpleFileTrait;
 use crate::qlib::kernel::fs::procfs::task::auxvec::AUXVecSimpleFileTrait;
 use crate::qlib::kernel::fs::procfs::task::comm::CommSimpleFileTrait;
 use crate::qlib::kernel::fs::procfs::task::exec_args::ExecArgSimpleFileTrait;
@@ -48,11 +49,14 @@ use crate::qlib::kernel::fs::procfs::task::io::IOData;
 use crate::qlib::kernel::fs::procfs::task::maps::MapsData;
 use crate::qlib::kernel::fs::procfs::task::mounts::MountInfoFile;
 use crate::qlib::kernel::fs::procfs::task::mounts::MountsFile;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NamespaceSimpleFileTrait;
+use crate::qlib::kernel::fs::procfs::task::oom_score_adj::OOMScoreAdjSimpleFileTrait;
 use crate::qlib::kernel::fs::procfs::task::stat::TaskStatData;
 use crate::qlib::kernel::fs::procfs::task::statm::StatmData;
 use crate::qlib::kernel::fs::procfs::task::status::StatusData;
 use crate::qlib::kernel::fs::procfs::task::uid_pid_map::IdMapSimpleFileTrait;
 use crate::qlib::kernel::fs::procfs::uptime::UptimeInode;
+use crate::qlib::kernel::fs::sys::cgroup::CgroupData;
 use crate::qlib::kernel::fs::sys::devices::PossibleData;
 use crate::qlib::kernel::socket::unix::unix::Dummy;
 
@@ -87,11 +91,15 @@ pub enum SimpleFileImpl {
     MapsData(MapsData),
     MountInfoFile(MountInfoFile),
     MountsFile(MountsFile),
+    NamespaceSimpleFileTrait(NamespaceSimpleFileTrait),
+    OOMScoreAdjSimpleFileTrait(OOMScoreAdjSimpleFileTrait),
+    CorePatternSimpleFileTrait(CorePatternSimpleFileTrait),
     StatData(StatData),
     StatmData(StatmData),
     StatusData(StatusData),
     IdMapSimpleFileTrait(IdMapSimpleFileTrait),
     PossibleData(PossibleData),
+    CgroupData(CgroupData),
     Dummy(Dummy),
 }
 
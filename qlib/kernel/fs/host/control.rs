@@ -86,7 +86,7 @@ fn FdsToFiles(task: &Task, fds: &[i32]) -> Vec<File> {
 
         // Set known flags.
         file.SetFlags(task, SettableFileFlags {
-            NonBlocking: fileFlags & Flags::O_NONBLOCK != Flags::O_NONBLOCK,
+            NonBlocking: fileFlags & Flags::O_NONBLOCK == Flags::O_NONBLOCK,
             ..Default::default()
         });
 
@@ -26,6 +26,7 @@ use super::super::super::super::common::*;
 use super::super::super::super::linux_def::*;
 use crate::qlib::kernel::fs::host::dirent::Dirent64;
 //use super::super::super::super::device::*;
+use super::super::super::kernel::async_wait::*;
 use super::super::super::kernel::time::*;
 pub use super::super::super::memmgr::vma::MMappable;
 use super::super::super::socket::unix::transport::unix::*;
@@ -33,6 +34,7 @@ use super::super::super::socket::unix::unix::*;
 use super::super::super::task::*;
 use super::super::super::util::cstring::*;
 use super::super::super::Kernel::HostSpace;
+use super::super::super::IOURING;
 use super::super::super::SHARESPACE;
 use super::super::attr::*;
 use super::super::dentry::*;
@@ -46,6 +48,10 @@ use super::*;
 
 pub const MAX_FILENAME_LEN: usize = (1 << 16) - 1;
 
+// the max number of async statx a readdir fires, a huge directory would otherwise
+// take all the async op slots
+pub const STATX_PREFETCH_COUNT: usize = 256;
+
 pub struct HostDirOpIntern {
     pub mops: Arc<QMutex<MountSourceOperations>>,
     //this should be SuperOperations
@@ -55,6 +61,11 @@ pub struct HostDirOpIntern {
 
     pub overrides: BTreeMap<String, Inode>,
     pub readdirCache: Option<DentMap>,
+
+    // the async statx of the entries fired by the last readdir, see StatxPrefetch
+    pub statxPrefetch: BTreeMap<String, Future<Statx>>,
+    // the prefetched attributes of this directory, used by the first UnstableAttr
+    pub prefetchedStatx: Option<Statx>,
 }
 
 impl Default for HostDirOpIntern {
@@ -66,6 +77,8 @@ impl Default for HostDirOpIntern {
             errorcode: 0,
             overrides: BTreeMap::new(),
             readdirCache: None,
+            statxPrefetch: BTreeMap::new(),
+            prefetchedStatx: None,
         };
     }
 }
@@ -77,7 +90,7 @@ impl Drop for HostDirOpIntern {
             return;
         }
 
-        HostSpace::Close(self.HostFd);
+        CloseFd(self.HostFd);
     }
 }
 
@@ -90,7 +103,61 @@ impl HostDirOpIntern {
             errorcode: 0,
             readdirCache: None,
             overrides: BTreeMap::new(),
+            statxPrefetch: BTreeMap::new(),
+            prefetchedStatx: None,
+        };
+    }
+
+    pub fn ClearReaddirCache(&mut self) {
+        self.readdirCache = None;
+        self.statxPrefetch.clear();
+        self.prefetchedStatx = None;
+    }
+
+    // PrefetchStatx fires an async statx for the entries of a readdir, the results
+    // are collected by the lookups of the entries
+    pub fn PrefetchStatx(&mut self, task: &Task, entries: &DentMap) {
+        if !SHARESPACE.config.read().StatxPrefetch {
+            return;
+        }
+
+        self.statxPrefetch.clear();
+        let mw = MultiWait::New(task.GetTaskId());
+        let names = entries
+            .Entries
+            .keys()
+            .filter(|name| *name != "." && *name != "..")
+            .take(STATX_PREFETCH_COUNT);
+
+        for name in names {
+            let future = IOURING.AsyncStatx(
+                self.HostFd,
+                name,
+                ATType::AT_SYMLINK_NOFOLLOW,
+                StatxMask::STATX_BASIC_STATS,
+                &mw,
+            );
+            self.statxPrefetch.insert(name.clone(), future);
+        }
+    }
+
+    // TakePrefetchedStatx returns the prefetched statx of the entry name if it has
+    // completed and still describes the file the lookup has opened
+    pub fn TakePrefetchedStatx(&mut self, name: &str, fstat: &LibcStat) -> Option<Statx> {
+        let statx = match self.statxPrefetch.remove(name)?.TryWait()? {
+            Err(_) => return None,
+            Ok(s) => s,
         };
+
+        if statx.stx_mask & StatxMask::STATX_BASIC_STATS != StatxMask::STATX_BASIC_STATS
+            || statx.stx_ino != fstat.st_ino
+            || statx.stx_ctime.tv_sec != fstat.st_ctime
+            || statx.stx_ctime.tv_nsec as i64 != fstat.st_ctime_nsec
+        {
+            return None;
+        }
+
+        return Some(statx);
     }
 
     pub fn ReadDirAll(&self, _task: &Task) -> Result<DentMap> {
@@ -138,7 +205,7 @@ impl HostDirOpIntern {
                 };
 
                 let pathname = CString::FromAddr(&name[0] as *const _ as u64);
-                entries.insert(pathname.Str().unwrap().to_string(), dentry);
+                entries.insert(StringFromBytes(pathname.Slice()), dentry);
             }
         }
 
@@ -158,6 +225,7 @@ impl HostDirOpIntern {
                     Ok(entires) => entires,
                 };
 
+                self.PrefetchStatx(task, &dentryMap);
                 self.readdirCache = Some(dentryMap);
             }
 
@@ -171,6 +239,7 @@ impl HostDirOpIntern {
                 Ok(entires) => entires,
             };
 
+            self.PrefetchStatx(task, &dentryMap);
             return match dirCtx.ReadDir(task, &dentryMap) {
                 Err(e) => (offset, Err(e)),
                 Ok(count) => (offset + count as i32, Ok(0)),
@@ -244,6 +313,12 @@ impl Deref for HostDirOp {
 }
 
 impl HostDirOp {
+    // DropPrefetchedStatx drops the attributes prefetched by the readdir of the
+    // parent, they are stale once the inode is changed
+    pub fn DropPrefetchedStatx(&self) {
+        self.lock().prefetchedStatx = None;
+    }
+
     pub fn New(mops: &Arc<QMutex<MountSourceOperations>>, fd: i32, fstat: &LibcStat) -> Self {
         let intern = Arc::new(QMutex::new(HostDirOpIntern::New(mops, fd, fstat)));
 
@@ -352,8 +427,12 @@ impl InodeOperations for HostDirOp {
             Ok(d) => d,
         };
 
+        let statx = self.lock().TakePrefetchedStatx(name, &fstat);
         let ms = parent.lock().MountSource.clone();
         let inode = Inode::NewHostInode(task, &ms, fd, &fstat, writeable, false)?;
+        if let Some(statx) = statx {
+            SetPrefetchedStatx(&inode, statx);
+        }
 
         let ret = Ok(Dirent::New(&inode, name));
         return ret;
@@ -386,7 +465,7 @@ impl InodeOperations for HostDirOp {
             owner.GID.0,
         )?;
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
 
         let mountSource = dir.lock().MountSource.clone();
 
@@ -417,7 +496,7 @@ impl InodeOperations for HostDirOp {
             return Err(Error::SysError(-ret as i32));
         }
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
 
         return Ok(());
     }
@@ -435,7 +514,7 @@ impl InodeOperations for HostDirOp {
             return Err(Error::SysError(-ret as i32));
         }
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
         return Ok(());
     }
 
@@ -463,7 +542,7 @@ impl InodeOperations for HostDirOp {
             return Err(Error::SysError(-ret as i32));
         }
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
         return Ok(());
     }
 
@@ -487,7 +566,7 @@ impl InodeOperations for HostDirOp {
             return Err(Error::SysError(-ret as i32));
         }
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
 
         return Ok(());
     }
@@ -506,7 +585,7 @@ impl InodeOperations for HostDirOp {
             return Err(Error::SysError(-ret as i32));
         }
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
         return Ok(());
     }
 
@@ -519,7 +598,7 @@ impl InodeOperations for HostDirOp {
             return Err(Error::SysError(-ret as i32));
         }
 
-        self.lock().readdirCache = None;
+        self.lock().ClearReaddirCache();
         return Ok(());
     }
 
@@ -533,6 +612,7 @@ impl InodeOperations for HostDirOp {
         newname: &str,
         replacement: bool,
     ) -> Result<()> {
+        self.DropPrefetchedStatx();
         return Rename(
             task,
             dir,
@@ -585,6 +665,10 @@ impl InodeOperations for HostDirOp {
 
     fn UnstableAttr(&self, task: &Task) -> Result<UnstableAttr> {
         let mops = self.lock().mops.clone();
+        if let Some(statx) = self.lock().prefetchedStatx.take() {
+            return Ok(statx.UnstableAttr(&mops));
+        }
+
         let fd = self.HostFd();
 
         return UnstableAttr(fd, task, &mops);
@@ -596,6 +680,7 @@ impl InodeOperations for HostDirOp {
     }
 
     fn Setxattr(&self, _dir: &mut Inode, name: &str, value: &[u8], flags: u32) -> Result<()> {
+        self.DropPrefetchedStatx();
         return Setxattr(self.HostFd(), name, value, flags);
     }
 
@@ -604,6 +689,7 @@ impl InodeOperations for HostDirOp {
     }
 
     fn Removexattr(&self, _dir: &Inode, name: &str) -> Result<()> {
+        self.DropPrefetchedStatx();
         return Removexattr(self.HostFd(), name);
     }
 
@@ -612,10 +698,12 @@ impl InodeOperations for HostDirOp {
     }
 
     fn SetPermissions(&self, _task: &Task, _dir: &mut Inode, f: FilePermissions) -> bool {
+        self.DropPrefetchedStatx();
         return Fchmod(self.HostFd(), f.LinuxMode()) == 0;
     }
 
     fn SetOwner(&self, _task: &Task, _dir: &mut Inode, owner: &FileOwner) -> Result<()> {
+        self.DropPrefetchedStatx();
         let ret = FChown(self.HostFd(), owner.UID.0, owner.GID.0);
 
         if ret < 0 {
@@ -626,6 +714,7 @@ impl InodeOperations for HostDirOp {
     }
 
     fn SetTimestamps(&self, _task: &Task, _dir: &mut Inode, ts: &InterTimeSpec) -> Result<()> {
+        self.DropPrefetchedStatx();
         return SetTimestamps(self.HostFd(), ts);
     }
 
@@ -650,11 +739,11 @@ impl InodeOperations for HostDirOp {
     }
 
     fn AddLink(&self, _task: &Task) {
-        //return Err(Error::None)
+        self.DropPrefetchedStatx();
     }
 
     fn DropLink(&self, _task: &Task) {
-        //return Err(Error::None)
+        self.DropPrefetchedStatx();
     }
 
     fn IsVirtual(&self) -> bool {
@@ -673,3 +762,12 @@ impl InodeOperations for HostDirOp {
         return Err(Error::SysError(SysErr::ENODEV));
     }
 }
+
+// SetPrefetchedStatx hands the prefetched attributes to the inode of a lookup
+fn SetPrefetchedStatx(inode: &Inode, statx: Statx) {
+    match &inode.lock().InodeOp {
+        Iops::HostInodeOp(iops) => iops.lock().prefetchedStatx = Some(statx),
+        Iops::HostDirOp(iops) => iops.lock().prefetchedStatx = Some(statx),
+        _ => (),
+    }
+}
@@ -21,11 +21,13 @@ use core::any::Any;
 use super::super::super::super::addr::*;
 use super::super::super::super::bytestream::*;
 use super::super::super::super::common::*;
+use super::super::super::super::device::*;
 use super::super::super::super::linux_def::*;
 use super::super::super::super::pagetable::*;
 use super::super::super::super::range::*;
 use super::super::super::guestfdnotifier::*;
 use super::super::super::kernel::async_wait::*;
+use super::super::super::kernel::throttle::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::task::*;
 
@@ -35,6 +37,7 @@ use super::super::dirent::*;
 use super::super::file::*;
 use super::super::fsutil::file::*;
 use super::super::host::hostinodeop::*;
+use super::super::host::ioctl::*;
 use super::super::inode::*;
 
 pub enum HostFileBuf {
@@ -104,7 +107,31 @@ impl Waitable for HostFileOp {
     }
 }
 
-impl SpliceOperations for HostFileOp {}
+impl SpliceOperations for HostFileOp {
+    // WriteTo lets the host copy between two regular host files, which shares the
+    // extents on a filesystem with reflink support. Other destinations go through
+    // the generic read/write copy.
+    fn WriteTo(&self, task: &Task, _file: &File, dst: &File, opts: &SpliceOpts) -> Result<i64> {
+        let dstOp = match dst.FileOp.as_any().downcast_ref::<HostFileOp>() {
+            Some(op) => op.clone(),
+            None => return Err(Error::SysError(SysErr::ENOSYS)),
+        };
+
+        if self.InodeOp.InodeType() != InodeType::RegularFile
+            || dstOp.InodeOp.InodeType() != InodeType::RegularFile
+        {
+            return Err(Error::SysError(SysErr::ENOSYS));
+        }
+
+        return self.InodeOp.CopyFileRange(
+            task,
+            opts.SrcStart,
+            &dstOp.InodeOp,
+            opts.DstStart,
+            opts.Length,
+        );
+    }
+}
 
 impl FileOperations for HostFileOp {
     fn as_any(&self) -> &Any {
@@ -158,6 +185,15 @@ impl FileOperations for HostFileOp {
     ) -> Result<i64> {
         let hostIops = self.InodeOp.clone();
 
+        if hostIops.InodeType() == InodeType::RegularFile {
+            return DiskIO(
+                task,
+                false,
+                || hostIops.ReadAt(task, f, dsts, offset, blocking),
+                |n| *n,
+            );
+        }
+
         hostIops.ReadAt(task, f, dsts, offset, blocking)
     }
 
@@ -171,18 +207,40 @@ impl FileOperations for HostFileOp {
     ) -> Result<i64> {
         let hostIops = self.InodeOp.clone();
 
+        if hostIops.InodeType() == InodeType::RegularFile {
+            return DiskIO(
+                task,
+                true,
+                || hostIops.WriteAt(task, f, srcs, offset, blocking),
+                |n| *n,
+            );
+        }
+
         hostIops.WriteAt(task, f, srcs, offset, blocking)
     }
 
     fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
         let hostIops = self.InodeOp.clone();
 
+        if hostIops.InodeType() == InodeType::RegularFile {
+            return DiskIO(task, true, || hostIops.Append(task, f, srcs), |r| r.0);
+        }
+
         return hostIops.Append(task, f, srcs);
     }
 
     fn Fsync(&self, task: &Task, f: &File, start: i64, end: i64, syncType: SyncType) -> Result<()> {
         let hostIops = self.InodeOp.clone();
 
+        if hostIops.InodeType() == InodeType::RegularFile {
+            return DiskIO(
+                task,
+                true,
+                || hostIops.Fsync(task, f, start, end, syncType),
+                |_| 0,
+            );
+        }
+
         return hostIops.Fsync(task, f, start, end, syncType);
     }
 
@@ -199,8 +257,27 @@ impl FileOperations for HostFileOp {
         return inode.UnstableAttr(task);
     }
 
-    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
-        return Err(Error::SysError(SysErr::ENOTTY));
+    fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
+        match self.InodeOp.InodeType() {
+            // GetFile only lets the allowlisted devices be opened
+            InodeType::CharacterDevice | InodeType::BlockDevice => {
+                let attr = self.InodeOp.StableAttr();
+                if attr.DeviceFileMajor == MISC_MAJOR && attr.DeviceFileMinor == TUN_MINOR {
+                    return TunIoctl(task, self.InodeOp.HostFd(), request, val);
+                }
+
+                return PassthroughIoctl(task, self.InodeOp.HostFd(), request, val);
+            }
+            InodeType::RegularFile if request == IoCtlCmd::FS_IOC_FIEMAP => {
+                // the buffered writes have to reach the host file first
+                if self.InodeOp.BufWriteEnable() {
+                    self.InodeOp.BufWriteLock().Lock(task);
+                }
+
+                return FiemapIoctl(task, self.InodeOp.HostFd(), val);
+            }
+            _ => return Err(Error::SysError(SysErr::ENOTTY)),
+        }
     }
 
     fn IterateDir(
@@ -20,10 +20,13 @@ use alloc::sync::Weak;
 use alloc::vec::Vec;
 use core::any::Any;
 use core::ops::Deref;
+use core::sync::atomic::AtomicI32;
+use core::sync::atomic::Ordering;
 
 use super::super::super::super::addr::*;
 use super::super::super::super::auth::*;
 use super::super::super::super::common::*;
+use super::super::super::super::linux::time::HOUR;
 use super::super::super::super::linux_def::*;
 use super::super::super::super::mem::areaset::*;
 use super::super::super::super::range::*;
@@ -36,6 +39,7 @@ use super::super::super::memmgr::mapping_set::*;
 use super::super::super::memmgr::mm::*;
 pub use super::super::super::memmgr::vma::MMappable;
 use super::super::super::memmgr::*;
+use super::super::super::quring::uring_mgr::SubmitMode;
 use super::super::super::socket::unix::transport::unix::*;
 use super::super::super::task::*;
 use super::super::super::Kernel::HostSpace;
@@ -184,9 +188,21 @@ pub struct HostInodeOpIntern {
 
     pub mappable: Option<Mappable>,
     pub bufWriteLock: QAsyncLock,
+    // errno of a failed buffered write, reported by the next fsync
+    pub writebackErr: Arc<AtomicI32>,
     pub hasMappable: bool,
 
     pub isMemfd: bool,
+
+    // the attributes prefetched by the readdir of the parent, used by the first
+    // UnstableAttr, see StatxPrefetch
+    pub prefetchedStatx: Option<Statx>,
+
+    // the atime of the guest reads, kept in the guest and written back when the host
+    // fd is synced or closed, see NotifyAccess. 0 is none.
+    pub atime: Time,
+    // the file has been written after the atime, the next read takes a new one
+    pub atimeStale: bool,
 }
 
 impl Default for HostInodeOpIntern {
@@ -202,8 +218,12 @@ impl Default for HostInodeOpIntern {
             mappable: None,
             size: 0,
             bufWriteLock: QAsyncLock::default(),
+            writebackErr: Arc::new(AtomicI32::new(0)),
             hasMappable: false,
             isMemfd: false,
+            prefetchedStatx: None,
+            atime: Time(0),
+            atimeStale: false,
         };
     }
 }
@@ -232,7 +252,14 @@ impl Drop for HostInodeOpIntern {
             }
         }
 
-        HostSpace::Close(self.HostFd);
+        // the pending async accept, connect or read of a socket, pipe or tty holds the
+        // host file open
+        if self.WouldBlock {
+            IOURING.CancelFdOps(self.HostFd);
+        }
+
+        self.WritebackAtime();
+        CloseFd(self.HostFd);
     }
 }
 
@@ -256,8 +283,12 @@ impl HostInodeOpIntern {
             mappable: None,
             size: fstat.st_size,
             bufWriteLock: QAsyncLock::default(),
+            writebackErr: Arc::new(AtomicI32::new(0)),
             hasMappable: false,
             isMemfd: isMemfd,
+            prefetchedStatx: None,
+            atime: Time(0),
+            atimeStale: false,
         };
 
         if ret.CanMap() {
@@ -329,25 +360,35 @@ impl HostInodeOpIntern {
     }
 
     // map one page from file offsetFile to phyAddr
-    pub fn MapFilePage(&mut self, task: &Task, fileOffset: u64) -> Result<u64> {
-        let filesize = self.size as u64;
-        if filesize <= fileOffset {
+    // MapFilePage returns the guest physical address of the file page, and whether
+    // its chunk had to be mapped from the host first.
+    pub fn MapFilePage(&mut self, task: &Task, fileOffset: u64) -> Result<(u64, bool)> {
+        if self.size as u64 <= fileOffset {
+            // the host file may have grown through another inode, e.g. a hard link
+            let mut fstat = LibcStat::default();
+            if Fstat(self.HostFd, &mut fstat) >= 0 && fstat.st_size > self.size {
+                self.size = fstat.st_size;
+            }
+        }
+
+        if self.size as u64 <= fileOffset {
             return Err(Error::FileMapError);
         }
 
         let chunkStart = fileOffset & !HUGE_PAGE_MASK;
-        self.Fill(task, chunkStart, fileOffset + PAGE_SIZE)?;
+        let filled = self.Fill(task, chunkStart, fileOffset + PAGE_SIZE)?;
 
         let mappable = self.Mappable();
         let mappableLock = mappable.lock();
 
         let phyAddr = mappableLock.f2pmap.get(&chunkStart).unwrap();
-        return Ok(phyAddr + (fileOffset - chunkStart));
+        return Ok((phyAddr + (fileOffset - chunkStart), filled));
     }
 
     //fill the holes for the file range by mmap
     //start must be Hugepage aligned
-    fn Fill(&mut self, _task: &Task, start: u64, end: u64) -> Result<()> {
+    //return true if there was any hole
+    fn Fill(&mut self, _task: &Task, start: u64, end: u64) -> Result<bool> {
         let mappable = self.Mappable();
 
         let mut start = start;
@@ -363,10 +404,11 @@ impl HostInodeOpIntern {
             start += HUGE_PAGE_SIZE;
         }
 
+        let filled = holes.len() > 0;
         for offset in holes {
             self.MMapChunk(offset)?;
         }
-        return Ok(());
+        return Ok(filled);
     }
 
     pub fn MMapChunk(&mut self, offset: u64) -> Result<u64> {
@@ -407,7 +449,25 @@ impl HostInodeOpIntern {
         return SetMaskedAttributes(self.HostFd, mask, attr);
     }
 
-    pub fn Sync(&self) -> Result<()> {
+    // WritebackAtime sets the host atime to the atime of the guest reads
+    pub fn WritebackAtime(&mut self) {
+        if self.atime.0 == 0 {
+            return;
+        }
+
+        let ts = InterTimeSpec {
+            ATime: self.atime,
+            MTimeOmit: true,
+            ..Default::default()
+        };
+        self.atime = Time(0);
+
+        // best effort: a file the guest may only read doesn't take a new atime
+        let _ = SetTimestamps(self.HostFd, &ts);
+    }
+
+    pub fn Sync(&mut self) -> Result<()> {
+        self.WritebackAtime();
         let ret = Fsync(self.HostFd);
         if ret < 0 {
             return Err(Error::SysError(-ret));
@@ -486,6 +546,12 @@ impl Deref for HostInodeOp {
 }
 
 impl HostInodeOp {
+    // DropPrefetchedStatx drops the attributes prefetched by the readdir of the
+    // parent, they are stale once the inode is changed
+    pub fn DropPrefetchedStatx(&self) {
+        self.lock().prefetchedStatx = None;
+    }
+
     pub fn New(
         mops: &Arc<QMutex<MountSourceOperations>>,
         fd: i32,
@@ -608,6 +674,24 @@ impl HostInodeOp {
         return self.lock().BufWriteEnable();
     }
 
+    // NotifyAccess updates the atime after a guest read. The host fd is opened with
+    // O_NOATIME so that the reads of an O_NOATIME guest file don't touch it, so the
+    // relatime policy is kept here: the atime is set once after the open or a write
+    // and then at most once a day. It is only kept in the guest until WritebackAtime,
+    // a read doesn't cost a host call nor change the ctime.
+    pub fn NotifyAccess(&self, task: &Task, strictAtime: bool) {
+        let now = task.Now();
+        let mut intern = self.lock();
+        if strictAtime
+            || intern.atime.0 == 0
+            || intern.atimeStale
+            || now.Sub(intern.atime) >= 24 * HOUR
+        {
+            intern.atime = now;
+            intern.atimeStale = false;
+        }
+    }
+
     // ReadEndOffset returns an exclusive end offset for a read operation
     // so that the read does not overflow an int64 nor size.
     //
@@ -737,6 +821,9 @@ impl HostInodeOp {
             return Ok(0);
         }
 
+        // the mtime moves past the atime, so the next read sets it again
+        self.lock().atimeStale = true;
+
         let size = if size >= MemoryDef::HUGE_PAGE_SIZE as usize {
             MemoryDef::HUGE_PAGE_SIZE as usize
         } else {
@@ -760,18 +847,31 @@ impl HostInodeOp {
             };
 
             if SHARESPACE.config.read().UringIO {
-                let ret = if self.BufWriteEnable() {
+                let lock = if self.BufWriteEnable() {
                     let lock = self.BufWriteLock().Lock(task);
-                    let count = IOURING.BufFileWrite(hostIops.HostFd(), buf, offset, lock);
-                    count
+                    // the file may have been mapped while waiting for the lock, then
+                    // AddMapping didn't wait for this write and it has to go through
+                    if self.BufWriteEnable() {
+                        Some(lock)
+                    } else {
+                        None
+                    }
                 } else {
-                    IOURING.Write(
+                    None
+                };
+
+                let ret = match lock {
+                    Some(lock) => {
+                        let writebackErr = self.lock().writebackErr.clone();
+                        IOURING.BufFileWrite(hostIops.HostFd(), buf, offset, lock, writebackErr)
+                    }
+                    None => IOURING.Write(
                         task,
                         hostIops.HostFd(),
                         buf.Ptr(),
                         buf.Len() as u32,
                         offset as i64,
-                    )
+                    ),
                 };
 
                 if ret < 0 {
@@ -801,6 +901,119 @@ impl HostInodeOp {
         }
     }
 
+    // InvalidateRange drops the guest mappings of the file pages in [offset, offset+len)
+    // after the host changed them behind the guest, so that the next access of a
+    // mapping faults the new content in.
+    pub fn InvalidateRange(&self, offset: i64, len: i64) {
+        if len <= 0 || !self.lock().hasMappable {
+            return;
+        }
+
+        let start = Addr(offset as u64).RoundDown().unwrap().0;
+        let end = Addr((offset + len) as u64).RoundUp().unwrap().0;
+        let fr = Range::New(start, end - start);
+
+        // the mappable lock is released before the page tables are locked, a page fault
+        // takes them in the other order
+        let mappable = self.lock().Mappable();
+        let (mappings, chunks) = {
+            let mappableLock = mappable.lock();
+            let mappings = mappableLock.mapping.MappingsWithOffset(&fr);
+            let chunks: BTreeMap<u64, u64> = mappableLock
+                .f2pmap
+                .range(fr.Start() & !CHUNK_MASK..fr.End())
+                .map(|(offset, phyAddr)| (*offset, *phyAddr))
+                .collect();
+            (mappings, chunks)
+        };
+
+        for (m, fileStart) in &mappings {
+            let filePage = |off: u64| {
+                let offset = fileStart + off;
+                return chunks
+                    .get(&(offset & !CHUNK_MASK))
+                    .map(|phyAddr| phyAddr + (offset & CHUNK_MASK));
+            };
+            m.MappingSpace
+                .Upgrade()
+                .InvalidateFilePages(&m.AddrRange, &filePage);
+        }
+    }
+
+    // CopyFileRange copies a range of this file into dst on the host, see
+    // VMSpace::CopyFileRange. ENOSYS is returned when the host can't copy between
+    // the two files, so that the caller falls back to reading and writing.
+    pub fn CopyFileRange(
+        &self,
+        task: &Task,
+        offset: i64,
+        dst: &HostInodeOp,
+        dstOffset: i64,
+        len: i64,
+    ) -> Result<i64> {
+        // the buffered writes have to reach the host files first
+        if self.BufWriteEnable() {
+            self.BufWriteLock().Lock(task);
+        }
+
+        if dst.BufWriteEnable() {
+            dst.BufWriteLock().Lock(task);
+        }
+
+        let ret = CopyFileRange(self.HostFd(), offset, dst.HostFd(), dstOffset, len);
+        if ret < 0 {
+            match -ret as i32 {
+                SysErr::EXDEV | SysErr::EOPNOTSUPP | SysErr::ENOSYS | SysErr::EINVAL => {
+                    return Err(Error::SysError(SysErr::ENOSYS))
+                }
+                errno => return Err(Error::SysError(errno)),
+            }
+        }
+
+        dst.DropPrefetchedStatx();
+        dst.UpdateMaxLen(dstOffset + ret);
+        dst.InvalidateRange(dstOffset, ret);
+        return Ok(ret);
+    }
+
+    // CloneRange shares a range of this file with dst through the host FICLONERANGE,
+    // which fails with EOPNOTSUPP on a filesystem without reflink support.
+    pub fn CloneRange(
+        &self,
+        task: &Task,
+        offset: i64,
+        dst: &HostInodeOp,
+        dstOffset: i64,
+        len: i64,
+    ) -> Result<()> {
+        if self.BufWriteEnable() {
+            self.BufWriteLock().Lock(task);
+        }
+
+        if dst.BufWriteEnable() {
+            dst.BufWriteLock().Lock(task);
+        }
+
+        let ret = FileClone(self.HostFd(), offset, dst.HostFd(), dstOffset, len);
+        if ret < 0 {
+            return Err(Error::SysError(-ret as i32));
+        }
+
+        dst.DropPrefetchedStatx();
+        let oldSize = dst.lock().size;
+        let uattr = dst.UnstableAttr(task)?;
+        dst.lock().size = uattr.Size;
+
+        // a len of 0 clones up to the end of this file
+        let end = if len == 0 {
+            core::cmp::max(oldSize, uattr.Size)
+        } else {
+            dstOffset + len
+        };
+        dst.InvalidateRange(dstOffset, end - dstOffset);
+        return Ok(());
+    }
+
     pub fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
         let hostIops = self.clone();
 
@@ -855,6 +1068,10 @@ impl HostInodeOp {
             false
         };
 
+        if !datasync {
+            self.lock().WritebackAtime();
+        }
+
         let ret = if SHARESPACE.config.read().UringIO && self.InodeType() == InodeType::RegularFile
         {
             if self.BufWriteEnable() {
@@ -862,7 +1079,13 @@ impl HostInodeOp {
                 self.BufWriteLock().Lock(task);
             }
 
-            IOURING.Fsync(task, fd, datasync)
+            let mode = if SHARESPACE.config.read().UringTurboFsync {
+                SubmitMode::Turbo
+            } else {
+                SubmitMode::Batched
+            };
+
+            IOURING.Fsync(task, fd, datasync, mode)
         } else {
             if self.BufWriteEnable() {
                 // try to gain the lock once, release immediately
@@ -880,6 +1103,13 @@ impl HostInodeOp {
             return Err(Error::SysError(-ret as i32));
         }
 
+        // the buffered writes finished with the lock above, a failed one is
+        // reported once like the writeback errors of Linux
+        let err = self.lock().writebackErr.swap(0, Ordering::SeqCst);
+        if err != 0 {
+            return Err(Error::SysError(err));
+        }
+
         return Ok(());
     }
 
@@ -893,18 +1123,15 @@ impl HostInodeOp {
         offset: u64,
         writeable: bool,
     ) -> Result<()> {
+        // the buffered writes have to reach the host file before the mapping can see
+        // them. The file isn't written through the buffer any more once it's mapped,
+        // WriteAt checks hasMappable again under the lock.
+        let bufWrite = self.BufWriteEnable();
         self.lock().hasMappable = true;
-
-        // todo: if there is bufwrite ongoing, should we wait for it?
-        /*let _= if self.BufWriteEnable() {
-            let task = Task::Current();
-            error!("AddMapping 1");
-            let lock = self.lock().bufWriteLock.Lock(task);
-            error!("AddMapping 2");
-            Some(lock)
-        } else {
-            None
-        };*/
+        if bufWrite {
+            // try to gain the lock once, release immediately
+            self.BufWriteLock().Lock(Task::Current());
+        }
 
         let mappable = self.lock().Mappable();
         let mut mappableLock = mappable.lock();
@@ -953,16 +1180,19 @@ impl HostInodeOp {
     }
 
     // map one page from file offsetFile to phyAddr
-    pub fn MapFilePage(&self, task: &Task, fileOffset: u64) -> Result<u64> {
+    pub fn MapFilePage(&self, task: &Task, fileOffset: u64) -> Result<(u64, bool)> {
         return self.lock().MapFilePage(task, fileOffset);
     }
 
+    // MSync writes the dirty pages of the host mappings in fr back to the host
+    // file. With MsSync the host msync waits for the write back of the range, as
+    // fdatasync does, so the data is durable when it returns.
     pub fn MSync(&self, fr: &Range, msyncType: MSyncType) -> Result<()> {
         let ranges = self.GetPhyRanges(fr);
         for r in &ranges {
             let ret = HostSpace::MSync(r.Start(), r.Len() as usize, msyncType.MSyncFlags());
             if ret < 0 {
-                return Err(Error::SysError(ret as i32));
+                return Err(Error::SysError(-ret as i32));
             }
         }
 
@@ -1029,12 +1259,12 @@ impl HostInodeOp {
                         0
                     };
 
-                    let len = if fChunckRange.Contains(fr.End()) {
+                    let endOffset = if fChunckRange.Contains(fr.End()) {
                         fr.End() - fChunckRange.Start()
                     } else {
                         HUGE_PAGE_SIZE
                     };
-                    rs.push(Range::New(*phyAddr + startOffset, len));
+                    rs.push(Range::New(*phyAddr + startOffset, endOffset - startOffset));
                 }
             }
 
@@ -1141,6 +1371,7 @@ impl InodeOperations for HostInodeOp {
         newname: &str,
         replacement: bool,
     ) -> Result<()> {
+        self.DropPrefetchedStatx();
         return Rename(
             task,
             dir,
@@ -1174,6 +1405,19 @@ impl InodeOperations for HostInodeOp {
         dirent: &Dirent,
         flags: FileFlags,
     ) -> Result<File> {
+        // an O_PATH file doesn't open the device
+        match self.InodeType() {
+            InodeType::CharacterDevice | InodeType::BlockDevice if !flags.Path => {
+                let attr = self.StableAttr();
+                let allowlist = SHARESPACE.config.read().DevicePassthrough;
+                if !allowlist.Contains(attr.DeviceFileMajor, attr.DeviceFileMinor) {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+            }
+            _ => (),
+        }
+
+        self.DropPrefetchedStatx();
         let fops = self.GetHostFileOp(task);
 
         let inode = dirent.Inode();
@@ -1189,9 +1433,18 @@ impl InodeOperations for HostInodeOp {
         }
 
         let mops = self.lock().mops.clone();
-        let fd = self.HostFd();
+        let prefetched = self.lock().prefetchedStatx.take();
+        let mut uattr = match prefetched {
+            Some(statx) => statx.UnstableAttr(&mops),
+            None => UnstableAttr(self.HostFd(), task, &mops)?,
+        };
 
-        return UnstableAttr(fd, task, &mops);
+        let atime = self.lock().atime;
+        if atime.0 > uattr.AccessTime.0 {
+            uattr.AccessTime = atime;
+        }
+
+        return Ok(uattr);
     }
 
     //fn StableAttr(&self) -> &StableAttr;
@@ -1200,6 +1453,7 @@ impl InodeOperations for HostInodeOp {
     }
 
     fn Setxattr(&self, _dir: &mut Inode, name: &str, value: &[u8], flags: u32) -> Result<()> {
+        self.DropPrefetchedStatx();
         return Setxattr(self.HostFd(), name, value, flags);
     }
 
@@ -1208,6 +1462,7 @@ impl InodeOperations for HostInodeOp {
     }
 
     fn Removexattr(&self, _dir: &Inode, name: &str) -> Result<()> {
+        self.DropPrefetchedStatx();
         return Removexattr(self.HostFd(), name);
     }
 
@@ -1216,10 +1471,12 @@ impl InodeOperations for HostInodeOp {
     }
 
     fn SetPermissions(&self, _task: &Task, _dir: &mut Inode, f: FilePermissions) -> bool {
+        self.DropPrefetchedStatx();
         return Fchmod(self.HostFd(), f.LinuxMode()) == 0;
     }
 
     fn SetOwner(&self, _task: &Task, _dir: &mut Inode, owner: &FileOwner) -> Result<()> {
+        self.DropPrefetchedStatx();
         let ret = FChown(self.HostFd(), owner.UID.0, owner.GID.0);
 
         if ret < 0 {
@@ -1230,10 +1487,15 @@ impl InodeOperations for HostInodeOp {
     }
 
     fn SetTimestamps(&self, _task: &Task, _dir: &mut Inode, ts: &InterTimeSpec) -> Result<()> {
+        self.DropPrefetchedStatx();
+        if !ts.ATimeOmit {
+            self.lock().atime = Time(0);
+        }
         return SetTimestamps(self.HostFd(), ts);
     }
 
     fn Truncate(&self, task: &Task, _dir: &mut Inode, size: i64) -> Result<()> {
+        self.DropPrefetchedStatx();
         let uattr = self.UnstableAttr(task)?;
         let oldSize = uattr.Size;
         assert!(oldSize == self.lock().size);
@@ -1267,6 +1529,7 @@ impl InodeOperations for HostInodeOp {
     }
 
     fn Allocate(&self, task: &Task, _dir: &mut Inode, offset: i64, length: i64) -> Result<()> {
+        self.DropPrefetchedStatx();
         let ret = Fallocate(self.HostFd(), 0, offset, length);
 
         if ret < 0 {
@@ -1292,11 +1555,11 @@ impl InodeOperations for HostInodeOp {
     }
 
     fn AddLink(&self, _task: &Task) {
-        //return Err(Error::None)
+        self.DropPrefetchedStatx();
     }
 
     fn DropLink(&self, _task: &Task) {
-        //return Err(Error::None)
+        self.DropPrefetchedStatx();
     }
 
     fn IsVirtual(&self) -> bool {
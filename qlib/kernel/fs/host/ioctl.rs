@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::vec::Vec;
+
 use super::super::super::super::common::*;
+use super::super::super::super::linux::netdevice::*;
 use super::super::super::super::linux_def::*;
+use super::super::super::task::*;
 
 use super::tty::*;
 use super::util::*;
@@ -57,3 +61,166 @@ pub fn ioctlSetWinsize(fd: i32, w: &Winsize) -> Result<()> {
 
     return Ok(());
 }
+
+// The fields of an _IOC encoded ioctl request, see include/uapi/asm-generic/ioctl.h
+pub const IOC_NONE: u64 = 0;
+pub const IOC_WRITE: u64 = 1;
+pub const IOC_READ: u64 = 2;
+
+pub fn IocDir(request: u64) -> u64 {
+    return (request >> 30) & 0x3;
+}
+
+pub fn IocSize(request: u64) -> usize {
+    return ((request >> 16) & 0x3fff) as usize;
+}
+
+// The requests without _IOC direction which are known to take their argument by
+// value. A legacy request such as TCGETS has no direction either while it takes a
+// pointer, so the others can't be told apart and aren't forwarded.
+pub const PASSTHROUGH_SCALAR_IOCTLS: [u64; 2] = [LibcConst::BLKFLSBUF, LibcConst::BLKRRPART];
+
+// PassthroughIoctl forwards an ioctl on a passthrough device to the host. The host
+// never sees the guest pointer: the argument is copied in and out of a kernel buffer
+// as far as the _IOC direction and size of the request describe it, and a request
+// without direction is only forwarded when it is in PASSTHROUGH_SCALAR_IOCTLS.
+pub fn PassthroughIoctl(task: &Task, fd: i32, request: u64, val: u64) -> Result<()> {
+    let dir = IocDir(request);
+    if dir == IOC_NONE {
+        if !PASSTHROUGH_SCALAR_IOCTLS.contains(&request) {
+            return Err(Error::SysError(SysErr::ENOTTY));
+        }
+
+        let ret = Ioctl(fd, request, val);
+        if ret < 0 {
+            return Err(Error::SysError(-ret));
+        }
+        return Ok(());
+    }
+
+    let size = IocSize(request);
+    // at least a page, so that a driver accessing more than the encoded size of a
+    // legacy request stays inside the buffer
+    let len = core::cmp::max(size, MemoryDef::PAGE_SIZE as usize);
+    let mut buf: Vec<u8> = vec![0; len];
+    if dir & IOC_WRITE != 0 {
+        let data: Vec<u8> = task.CopyInVec(val, size)?;
+        buf[..size].copy_from_slice(&data);
+    }
+
+    let ret = Ioctl(fd, request, &mut buf[0] as *mut u8 as u64);
+    if ret < 0 {
+        return Err(Error::SysError(-ret));
+    }
+
+    if dir & IOC_READ != 0 {
+        task.CopyOutSlice(&buf[..size], val, size)?;
+    }
+
+    return Ok(());
+}
+
+// TunIoctl forwards an ioctl on /dev/net/tun. The tun requests don't all follow
+// the _IOC encoding: the interface requests carry a struct ifreq while they are
+// encoded with an int, and the flag setters take their argument by value.
+pub fn TunIoctl(task: &Task, fd: i32, request: u64, val: u64) -> Result<()> {
+    match request {
+        LibcConst::TUNSETIFF | LibcConst::TUNGETIFF => {
+            // creating or attaching an interface needs CAP_NET_ADMIN, as on Linux
+            if request == LibcConst::TUNSETIFF
+                && !task.Creds().lock().HasCapability(Capability::CAP_NET_ADMIN)
+            {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+
+            let mut ifr: IFReq = if request == LibcConst::TUNSETIFF {
+                task.CopyInObj(val)?
+            } else {
+                IFReq::default()
+            };
+
+            let ret = Ioctl(fd, request, &mut ifr as *mut IFReq as u64);
+            if ret < 0 {
+                return Err(Error::SysError(-ret));
+            }
+
+            // TUNSETIFF returns the name of the interface when a template such as
+            // "tun%d" is given
+            task.CopyOutObj(&ifr, val)?;
+            return Ok(());
+        }
+        LibcConst::TUNSETNOCSUM
+        | LibcConst::TUNSETDEBUG
+        | LibcConst::TUNSETPERSIST
+        | LibcConst::TUNSETOWNER
+        | LibcConst::TUNSETGROUP
+        | LibcConst::TUNSETLINK
+        | LibcConst::TUNSETOFFLOAD => {
+            // the host checks the capabilities of qvisor, not of the guest task. Linux
+            // lets a non-admin owner of the interface change these, but the guest
+            // credentials aren't the ones the host interface was created with.
+            match request {
+                LibcConst::TUNSETPERSIST
+                | LibcConst::TUNSETOWNER
+                | LibcConst::TUNSETGROUP
+                | LibcConst::TUNSETLINK => {
+                    if !task.Creds().lock().HasCapability(Capability::CAP_NET_ADMIN) {
+                        return Err(Error::SysError(SysErr::EPERM));
+                    }
+                }
+                _ => (),
+            }
+
+            let ret = Ioctl(fd, request, val);
+            if ret < 0 {
+                return Err(Error::SysError(-ret));
+            }
+            return Ok(());
+        }
+        // the sock_fprog holds a pointer into the guest, which the host can't follow
+        LibcConst::TUNATTACHFILTER | LibcConst::TUNDETACHFILTER => {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+        _ => return PassthroughIoctl(task, fd, request, val),
+    }
+}
+
+// FIEMAP_MAX_EXTENTS bounds the extents of one FS_IOC_FIEMAP. A caller asking for more
+// gets a partial map without FIEMAP_EXTENT_LAST and continues after the last extent.
+pub const FIEMAP_MAX_EXTENTS: usize = 1024;
+
+// FiemapIoctl forwards FS_IOC_FIEMAP: the header is copied in, the host fills the
+// extent array in a kernel buffer and the mapped extents are copied back.
+pub fn FiemapIoctl(task: &Task, fd: i32, val: u64) -> Result<()> {
+    let mut fm: Fiemap = task.CopyInObj(val)?;
+    let requested = fm.ExtentCount;
+
+    // an extent count of 0 only counts the extents
+    let count = core::cmp::min(requested as usize, FIEMAP_MAX_EXTENTS);
+    fm.ExtentCount = count as u32;
+
+    // u64 words keep the header and the extents aligned
+    let hdrSize = core::mem::size_of::<Fiemap>();
+    let size = hdrSize + count * core::mem::size_of::<FiemapExtent>();
+    let mut buf: Vec<u64> = vec![0; size / 8];
+    let addr = &mut buf[0] as *mut u64 as u64;
+    unsafe {
+        *(addr as *mut Fiemap) = fm;
+    }
+
+    let ret = Ioctl(fd, IoCtlCmd::FS_IOC_FIEMAP, addr);
+    if ret < 0 {
+        return Err(Error::SysError(-ret));
+    }
+
+    let mut fm = unsafe { *(addr as *const Fiemap) };
+    let mapped = core::cmp::min(fm.MappedExtents as usize, count);
+    let extents = unsafe {
+        core::slice::from_raw_parts((addr + hdrSize as u64) as *const FiemapExtent, mapped)
+    };
+
+    fm.ExtentCount = requested;
+    task.CopyOutObj(&fm, val)?;
+    task.CopyOutSlice(extents, val + hdrSize as u64, mapped)?;
+    return Ok(());
+}
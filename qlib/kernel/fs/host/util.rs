@@ -21,6 +21,7 @@ use super::super::super::super::auth::id::*;
 use super::super::super::super::auth::*;
 use super::super::super::super::common::*;
 use super::super::super::super::device::*;
+use super::super::super::super::fileinfo::*;
 use super::super::super::super::linux::time::*;
 use super::super::super::super::linux_def::*;
 use super::super::super::super::qmsg::qcall::TryOpenStruct;
@@ -29,6 +30,7 @@ use super::super::super::task::*;
 use super::super::super::util::cstring::*;
 
 use super::super::super::super::path;
+use super::super::super::GlobalIOMgr;
 use super::super::super::Kernel::HostSpace;
 use super::super::super::IOURING;
 use super::super::super::SHARESPACE;
@@ -37,6 +39,28 @@ use super::diriops::*;
 use super::*;
 
 impl Statx {
+    pub fn LibcStat(&self) -> LibcStat {
+        return LibcStat {
+            st_dev: MakeDeviceID(self.stx_dev_major as u16, self.stx_dev_minor) as u64,
+            st_ino: self.stx_ino,
+            st_nlink: self.stx_nlink as u64,
+            st_mode: self.stx_mode as u32,
+            st_uid: self.stx_uid,
+            st_gid: self.stx_gid,
+            st_rdev: MakeDeviceID(self.stx_rdev_major as u16, self.stx_rdev_minor) as u64,
+            st_size: self.stx_size as i64,
+            st_blksize: self.stx_blksize as i64,
+            st_blocks: self.stx_blocks as i64,
+            st_atime: self.stx_atime.tv_sec,
+            st_atime_nsec: self.stx_atime.tv_nsec as i64,
+            st_mtime: self.stx_mtime.tv_sec,
+            st_mtime_nsec: self.stx_mtime.tv_nsec as i64,
+            st_ctime: self.stx_ctime.tv_sec,
+            st_ctime_nsec: self.stx_ctime.tv_nsec as i64,
+            ..Default::default()
+        };
+    }
+
     pub fn InodeType(&self) -> InodeType {
         let x = self.stx_mode as u16 & ModeType::S_IFMT;
 
@@ -266,14 +290,25 @@ pub fn TimespecFromTimestamp(t: Time, omit: bool, setSysTime: bool) -> Timespec
     return Timespec::FromNs(t.0);
 }
 
+// A relative path with dirfd == AT_FDCWD would resolve against the qvisor process cwd
+// instead of the guest task's cwd. The guest resolves relative paths through its own
+// Dirent tree, so such a call reaching here is a bug and is rejected.
+fn HostCwdRelative(dirfd: i32, name: &str) -> bool {
+    return dirfd == ATType::AT_FDCWD && !path::IsAbs(name);
+}
+
 //if dirfd ==-100, there is no parent
 //return (fd, writeable)
 pub fn TryOpenAt(dirfd: i32, name: &str) -> Result<(i32, bool, LibcStat)> {
-    if dirfd == -100 && !path::IsAbs(name) {
+    if HostCwdRelative(dirfd, name) {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
     let name = path::Clean(name);
+    if dirfd >= 0 && SHARESPACE.config.read().UringOpenClose {
+        return UringTryOpenAt(dirfd, &name);
+    }
+
     let fstat = LibcStat::default();
     let mut tryopen = TryOpenStruct {
         fstat: &fstat,
@@ -290,8 +325,109 @@ pub fn TryOpenAt(dirfd: i32, name: &str) -> Result<(i32, bool, LibcStat)> {
     return Ok((ret as i32, tryopen.writeable, fstat));
 }
 
+// UringTryOpenAt is TryOpenAt with the statx and the opens going through io_uring.
+// The fd gets its registered file slot through io_uring as well and is queued for the
+// qvisor's fd table, so that the open doesn't exit to the host.
+fn UringTryOpenAt(dirfd: i32, name: &str) -> Result<(i32, bool, LibcStat)> {
+    let task = Task::Current();
+    let cstr = CString::New(name);
+
+    // a device which isn't passed through is only opened with O_PATH, so is a
+    // symlink, which the guest follows itself
+    let mut s: Statx = Default::default();
+    let ret = IOURING.Statx(
+        task,
+        dirfd,
+        cstr.Ptr(),
+        &mut s as *mut _ as u64,
+        ATType::AT_SYMLINK_NOFOLLOW,
+        StatxMask::STATX_TYPE,
+    );
+
+    let fmt = s.stx_mode & ModeType::S_IFMT;
+    let deviceAllowed = ret < 0
+        || (fmt != ModeType::S_IFCHR && fmt != ModeType::S_IFBLK)
+        || SHARESPACE
+            .config
+            .read()
+            .DevicePassthrough
+            .Contains(s.stx_rdev_major as u16, s.stx_rdev_minor);
+
+    let tries: &[(i32, bool)] = if deviceAllowed && fmt != ModeType::S_IFLNK {
+        &[
+            (Flags::O_RDWR, true),
+            (Flags::O_RDONLY, false),
+            (Flags::O_WRONLY, true),
+            (Flags::O_PATH, false),
+        ]
+    } else {
+        &[(Flags::O_PATH, false)]
+    };
+
+    let mut ret = 0;
+    let mut writeable = false;
+    for &(flags, w) in tries {
+        ret = IOURING.OpenAt(task, dirfd, cstr.Ptr(), Flags::O_NOFOLLOW | flags, 0);
+        writeable = w;
+        if ret >= 0 || ret == -SysErr::ENOENT as i64 {
+            break;
+        }
+    }
+
+    if ret < 0 {
+        return Err(Error::SysError(-ret as i32));
+    }
+
+    let fd = ret as i32;
+    let mut s: Statx = Default::default();
+    let ret = IOURING.Fstatx(
+        task,
+        fd,
+        &mut s as *mut _ as u64,
+        StatxMask::STATX_BASIC_STATS,
+    );
+    if ret < 0 {
+        IOURING.AsyncClose(fd);
+        return Err(Error::SysError(-ret as i32));
+    }
+
+    let fstat = s.LibcStat();
+    if fstat.IsRegularFile() && SHARESPACE.config.read().UringFixedFile {
+        let ret = IOURING.FilesUpdate(task, fd);
+        if ret < 0 {
+            // the registered file table is full
+            IOURING.AsyncClose(fd);
+            return Err(Error::SysError(SysErr::ENFILE));
+        }
+    }
+
+    if GlobalIOMgr().fdChanges.push(HostFdChange::Add(fd)).is_err() {
+        HostSpace::NewFile(fd);
+    }
+
+    return Ok((fd, writeable, fstat));
+}
+
+// CloseFd closes a host fd of a file, with UringOpenClose the qvisor drops it and
+// the close goes through io_uring
+pub fn CloseFd(fd: i32) {
+    if SHARESPACE.config.read().UringOpenClose {
+        if GlobalIOMgr()
+            .fdChanges
+            .push(HostFdChange::Release(fd))
+            .is_ok()
+            || HostSpace::ReleaseFd(fd) == 0
+        {
+            IOURING.AsyncClose(fd);
+        }
+        return;
+    }
+
+    HostSpace::Close(fd);
+}
+
 pub fn OpenAt(dirfd: i32, name: &str, flags: i32) -> Result<(i32, LibcStat)> {
-    if dirfd == -100 && !path::IsAbs(name) {
+    if HostCwdRelative(dirfd, name) {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
@@ -322,6 +458,10 @@ pub fn Fstat(fd: i32, fstat: &mut LibcStat) -> i64 {
 }
 
 pub fn Fstatat(dirfd: i32, pathname: &str, fstat: &mut LibcStat, flags: i32) -> i64 {
+    if HostCwdRelative(dirfd, pathname) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let cstr = CString::New(pathname);
     return HostSpace::Fstatat(dirfd, cstr.Ptr(), fstat as *mut _ as u64, flags);
 }
@@ -331,18 +471,30 @@ pub fn Fcntl(fd: i32, cmd: i32, arg: u64) -> i64 {
 }
 
 pub fn Mkdirat(fd: i32, name: &str, perm: u32, uid: u32, gid: u32) -> i64 {
+    if HostCwdRelative(fd, name) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let cstr = CString::New(name);
     let res = HostSpace::Mkdirat(fd, cstr.Ptr(), perm, uid, gid);
     return res;
 }
 
 pub fn Mkfifoat(fd: i32, name: &str, perm: u32, uid: u32, gid: u32) -> i64 {
+    if HostCwdRelative(fd, name) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let cstr = CString::New(name);
     let res = HostSpace::Mkfifoat(fd, cstr.Ptr(), perm, uid, gid);
     return res;
 }
 
 pub fn LinkAt(olddirfd: i32, oldpath: &str, newdirfd: i32, newpath: &str, flags: i32) -> i64 {
+    if HostCwdRelative(olddirfd, oldpath) || HostCwdRelative(newdirfd, newpath) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let oldpath = CString::New(oldpath);
     let newpath = CString::New(newpath);
 
@@ -350,6 +502,10 @@ pub fn LinkAt(olddirfd: i32, oldpath: &str, newdirfd: i32, newpath: &str, flags:
 }
 
 pub fn SymLinkAt(oldpath: &str, newdirfd: i32, newpath: &str) -> i64 {
+    if HostCwdRelative(newdirfd, newpath) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let oldpath = CString::New(oldpath);
     let newpath = CString::New(newpath);
 
@@ -357,11 +513,19 @@ pub fn SymLinkAt(oldpath: &str, newdirfd: i32, newpath: &str) -> i64 {
 }
 
 pub fn UnLinkAt(dirfd: i32, pathname: &str, flags: i32) -> i64 {
+    if HostCwdRelative(dirfd, pathname) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let cstr = CString::New(pathname);
     return HostSpace::Unlinkat(dirfd, cstr.Ptr(), flags);
 }
 
 pub fn RenameAt(olddirfd: i32, oldpath: &str, newdirfd: i32, newpath: &str) -> i64 {
+    if HostCwdRelative(olddirfd, oldpath) || HostCwdRelative(newdirfd, newpath) {
+        return -SysErr::EINVAL as i64;
+    }
+
     let oldpath = CString::New(oldpath);
     let newpath = CString::New(newpath);
 
@@ -384,18 +548,39 @@ pub fn Fallocate(fd: i32, mode: i32, offset: i64, len: i64) -> i64 {
     return HostSpace::Fallocate(fd, mode, offset, len);
 }
 
+pub fn CopyFileRange(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+    return HostSpace::CopyFileRange(srcFd, srcOffset, dstFd, dstOffset, len);
+}
+
+pub fn FileClone(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+    return HostSpace::FileClone(srcFd, srcOffset, dstFd, dstOffset, len);
+}
+
 pub fn ReadLinkAt(dirfd: i32, path: &str) -> Result<String> {
-    let mut buf: [u8; 1024] = [0; 1024];
+    if HostCwdRelative(dirfd, path) {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let mut buf: Vec<u8> = vec![0; PATH_MAX];
     let cstr = CString::New(path);
 
-    let ret = HostSpace::ReadLinkAt(dirfd, cstr.Ptr(), &mut buf[0] as *mut _ as u64, 1024);
+    let ret = HostSpace::ReadLinkAt(
+        dirfd,
+        cstr.Ptr(),
+        &mut buf[0] as *mut _ as u64,
+        PATH_MAX as u64,
+    );
 
     if ret < 0 {
         return Err(Error::SysError(-ret as i32));
     }
 
-    assert!(ret < 1024, "ReadLinkAt has no enough buffer");
-    return Ok(String::from_utf8(buf[..ret as usize].to_vec()).unwrap());
+    // a full buffer might be a truncated target
+    if ret as usize >= PATH_MAX {
+        return Err(Error::SysError(SysErr::ENAMETOOLONG));
+    }
+
+    return Ok(StringFromBytes(&buf[..ret as usize]));
 }
 
 pub fn createAt(
@@ -406,6 +591,10 @@ pub fn createAt(
     uid: u32,
     gid: u32,
 ) -> Result<(i32, LibcStat)> {
+    if HostCwdRelative(dirfd, name) {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
     let cstr = CString::New(name);
     let mut fstat = LibcStat::default();
 
@@ -530,8 +719,8 @@ pub fn Rename(
         return Err(Error::SysError(-ret as i32));
     }
 
-    oldParent.lock().readdirCache = None;
-    newParent.lock().readdirCache = None;
+    oldParent.lock().ClearReaddirCache();
+    newParent.lock().ClearReaddirCache();
     return Ok(());
 }
 
@@ -544,13 +733,7 @@ pub fn UnstableAttr(
 
     // the statx uring call sometime become very slow. todo: root cause this.
     if !uringStatx {
-        let mut s: LibcStat = Default::default();
-        let ret = Fstat(hostfd, &mut s) as i32;
-        if ret < 0 {
-            return Err(Error::SysError(-ret as i32));
-        }
-
-        return Ok(s.UnstableAttr(mo));
+        return FstatUnstableAttr(hostfd, mo);
     } else {
         let mut s: Statx = Default::default();
         let str = CString::New("");
@@ -567,10 +750,25 @@ pub fn UnstableAttr(
             return Err(Error::SysError(-ret as i32));
         }
 
+        // a filesystem may not fill every basic field, fstat gives all of them
+        if s.stx_mask & StatxMask::STATX_BASIC_STATS != StatxMask::STATX_BASIC_STATS {
+            return FstatUnstableAttr(hostfd, mo);
+        }
+
         return Ok(s.UnstableAttr(mo));
     }
 }
 
+fn FstatUnstableAttr(hostfd: i32, mo: &Arc<QMutex<MountSourceOperations>>) -> Result<UnstableAttr> {
+    let mut s: LibcStat = Default::default();
+    let ret = Fstat(hostfd, &mut s) as i32;
+    if ret < 0 {
+        return Err(Error::SysError(-ret as i32));
+    }
+
+    return Ok(s.UnstableAttr(mo));
+}
+
 pub fn Getxattr(fd: i32, name: &str) -> Result<Vec<u8>> {
     let str = CString::New(name);
     let val: &mut [u8; Xattr::XATTR_NAME_MAX] = &mut [0; Xattr::XATTR_NAME_MAX];
@@ -43,6 +43,7 @@ use super::flags::*;
 use super::host::diriops::*;
 use super::host::hostinodeop::*;
 use super::inode_overlay::*;
+use super::lease::*;
 use super::lock::*;
 use super::mount::*;
 use super::overlay::*;
@@ -360,6 +361,9 @@ pub struct LockCtx {
 
     // BSD is a set of BSD-style advisory file wide locks, see flock(2).
     pub BSD: Locks,
+
+    // Lease is the set of file leases, see fcntl(2) F_SETLEASE.
+    pub Lease: Leases,
 }
 
 #[derive(Clone)]
@@ -596,6 +600,17 @@ impl Inode {
         return res;
     }
 
+    // CreatePerms applies the create mode mask of the mount, if any, to the
+    // permissions of a new file.
+    pub fn CreatePerms(&self, perm: &FilePermissions) -> FilePermissions {
+        let mask = match self.lock().MountSource.lock().Flags.CreateModeMask {
+            None => return *perm,
+            Some(mask) => mask,
+        };
+
+        return FilePermissions::FromMode(FileMode(perm.LinuxMode() as u16 & mask));
+    }
+
     pub fn Create(
         &mut self,
         task: &Task,
@@ -604,6 +619,7 @@ impl Inode {
         flags: &FileFlags,
         perm: &FilePermissions,
     ) -> Result<File> {
+        let perm = &self.CreatePerms(perm);
         let isOverlay = self.lock().Overlay.is_some();
         if isOverlay {
             let overlay = self.lock().Overlay.as_ref().unwrap().clone();
@@ -622,6 +638,7 @@ impl Inode {
         name: &str,
         perm: &FilePermissions,
     ) -> Result<()> {
+        let perm = &self.CreatePerms(perm);
         let isOverlay = self.lock().Overlay.is_some();
         if isOverlay {
             let overlay = self.lock().Overlay.as_ref().unwrap().clone();
@@ -677,6 +694,7 @@ impl Inode {
         name: &str,
         perm: &FilePermissions,
     ) -> Result<()> {
+        let perm = &self.CreatePerms(perm);
         let isOverlay = self.lock().Overlay.is_some();
         if isOverlay {
             let overlay = self.lock().Overlay.as_ref().unwrap().clone();
@@ -759,6 +777,7 @@ impl Inode {
         data: &BoundEndpoint,
         perms: &FilePermissions,
     ) -> Result<Dirent> {
+        let perms = &self.CreatePerms(perms);
         let isOverlay = self.lock().Overlay.is_some();
         if isOverlay {
             let overlay = self.lock().Overlay.as_ref().unwrap().clone();
@@ -1016,6 +1035,26 @@ impl Inode {
         return Ok(());
     }
 
+    // NotifyAccess updates the atime after a read following the atime policy of
+    // the mount.
+    pub fn NotifyAccess(&self, task: &Task) {
+        let flags = self.lock().MountSource.lock().Flags;
+        if flags.NoAtime {
+            return;
+        }
+
+        let iops = self.lock().InodeOp.clone();
+        if let Some(iops) = iops.as_any().downcast_ref::<HostInodeOp>() {
+            iops.NotifyAccess(task, flags.StrictAtime);
+        } else if let Some(iops) = iops.as_any().downcast_ref::<TmpfsFileInodeOp>() {
+            let now = task.Now();
+            let mut uattr = iops.uattr.lock();
+            if flags.StrictAtime || uattr.RelatimeNeedsUpdate(now) {
+                uattr.AccessTime = now;
+            }
+        }
+    }
+
     pub fn CheckOwnership(&mut self, task: &Task) -> bool {
         let uattr = match self.UnstableAttr(task) {
             Err(_) => return false,
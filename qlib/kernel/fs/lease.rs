@@ -0,0 +1,235 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use super::super::super::common::*;
+use super::super::super::linux::fcntl::*;
+use super::super::super::linux::time::*;
+use super::super::super::linux_def::*;
+use super::super::kernel::fasync::*;
+use super::super::kernel::time::*;
+use super::super::kernel::timer::MonotonicNow;
+use super::super::kernel::waiter::*;
+use super::super::task::*;
+
+// LEASE_BREAK_TIME is how long a lease holder has to release or downgrade the
+// lease after a break before the lease is removed, the default of
+// /proc/sys/fs/lease-break-time.
+pub const LEASE_BREAK_TIME: i64 = 45 * SECOND;
+
+pub struct Lease {
+    // typ is F_RDLCK or F_WRLCK.
+    pub typ: i32,
+
+    // breakTo is the type the lease is being broken to, F_RDLCK or F_UNLCK.
+    pub breakTo: Option<i32>,
+
+    // deadline is the monotonic time the break is forced at.
+    pub deadline: Time,
+
+    // fasync delivers the lease break signal to the lease holder.
+    pub fasync: FileAsync,
+}
+
+#[derive(Default)]
+pub struct LeasesInternal {
+    // leases maps the unique id of the holding file to its lease.
+    pub leases: BTreeMap<u64, Lease>,
+
+    // queue is the queue of openers waiting for a lease break.
+    pub queue: Queue,
+}
+
+impl LeasesInternal {
+    // Conflicts returns the file ids whose leases conflict with an open for
+    // read or for write.
+    fn Conflicts(&self, write: bool) -> Vec<u64> {
+        let mut ret = Vec::new();
+        for (uid, l) in &self.leases {
+            let typ = match l.breakTo {
+                Some(t) => t,
+                None => l.typ,
+            };
+
+            if write || typ == F_WRLCK {
+                ret.push(*uid);
+            }
+        }
+
+        return ret;
+    }
+}
+
+// Leases is the set of file leases held on an inode, see fcntl(2) F_SETLEASE.
+//
+// Like the posix and flock locks, the leases are guest-only: they are broken by the
+// opens and truncates of the sandbox, but not taken on the host file. A host-side
+// writer, i.e. a process outside the sandbox or another sandbox sharing the host
+// directory, doesn't break a lease and isn't held up by one, so a lease holder
+// can't rely on it to see changes made to a bind mounted host file. The host fd of an inode is shared by all the
+// guest files of it, so a host lease couldn't follow the guest file it belongs to.
+#[derive(Clone, Default)]
+pub struct Leases(Arc<QMutex<LeasesInternal>>);
+
+impl Deref for Leases {
+    type Target = Arc<QMutex<LeasesInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<LeasesInternal>> {
+        &self.0
+    }
+}
+
+impl Leases {
+    // GetLease returns the lease type held by the file uid. A lease being
+    // broken reports the type it is being broken to.
+    pub fn GetLease(&self, uid: u64) -> i32 {
+        match self.lock().leases.get(&uid) {
+            None => return F_UNLCK,
+            Some(l) => match l.breakTo {
+                Some(t) => return t,
+                None => return l.typ,
+            },
+        }
+    }
+
+    pub fn SetLease(&self, uid: u64, typ: i32, fasync: &FileAsync) -> Result<()> {
+        let mut l = self.lock();
+
+        if typ == F_UNLCK {
+            l.leases.remove(&uid);
+            l.queue.Notify(EVENTMASK_ALL);
+            return Ok(());
+        }
+
+        for (other, lease) in &l.leases {
+            if *other == uid {
+                continue;
+            }
+
+            // no new lease while another one is being broken
+            if lease.breakTo.is_some() || typ == F_WRLCK || lease.typ == F_WRLCK {
+                return Err(Error::SysError(SysErr::EAGAIN));
+            }
+        }
+
+        if let Some(lease) = l.leases.get(&uid) {
+            // a lease being broken can only be downgraded
+            if lease.breakTo.is_some() && typ == F_WRLCK {
+                return Err(Error::SysError(SysErr::EAGAIN));
+            }
+        }
+
+        l.leases.insert(
+            uid,
+            Lease {
+                typ: typ,
+                breakTo: None,
+                deadline: Time(0),
+                fasync: fasync.clone(),
+            },
+        );
+
+        // a downgrade might let the waiting openers go
+        l.queue.Notify(EVENTMASK_ALL);
+        return Ok(());
+    }
+
+    // Remove drops the lease of a file which is being released.
+    pub fn Remove(&self, uid: u64) {
+        let mut l = self.lock();
+        if l.leases.remove(&uid).is_some() {
+            l.queue.Notify(EVENTMASK_ALL);
+        }
+    }
+
+    // Break breaks the leases conflicting with an open for read or write. The
+    // lease holders are signaled and the opener waits until they release or
+    // downgrade their leases, or until the lease break time runs out.
+    pub fn Break(&self, task: &Task, write: bool, nonblock: bool) -> Result<()> {
+        loop {
+            let mut l = self.lock();
+            let conflicts = l.Conflicts(write);
+            if conflicts.len() == 0 {
+                return Ok(());
+            }
+
+            let now = MonotonicNow();
+            let mut notify = Vec::new();
+            let mut deadline = i64::MAX;
+            for uid in &conflicts {
+                let lease = l.leases.get_mut(uid).unwrap();
+                if lease.breakTo.is_none() {
+                    lease.breakTo = Some(if write { F_UNLCK } else { F_RDLCK });
+                    lease.deadline = Time(now + LEASE_BREAK_TIME);
+                    notify.push(lease.fasync.clone());
+                }
+
+                if lease.deadline.0 < deadline {
+                    deadline = lease.deadline.0;
+                }
+            }
+
+            if deadline <= now {
+                // the holders didn't react in time, force the break
+                for uid in &conflicts {
+                    let lease = l.leases.get_mut(uid).unwrap();
+                    if lease.deadline.0 > now {
+                        continue;
+                    }
+
+                    if lease.breakTo == Some(F_RDLCK) {
+                        lease.typ = F_RDLCK;
+                        lease.breakTo = None;
+                    } else {
+                        l.leases.remove(uid);
+                    }
+                }
+                continue;
+            }
+
+            if nonblock {
+                core::mem::drop(l);
+                for a in notify {
+                    a.Callback(EVENT_IN);
+                }
+                return Err(Error::SysError(SysErr::EWOULDBLOCK));
+            }
+
+            l.queue
+                .EventRegister(task, &task.blocker.generalEntry, EVENTMASK_ALL);
+            core::mem::drop(l);
+
+            for a in notify {
+                a.Callback(EVENT_IN);
+            }
+
+            defer!(self
+                .lock()
+                .queue
+                .EventUnregister(task, &task.blocker.generalEntry));
+
+            match task.blocker.BlockWithMonoTimer(true, Some(Time(deadline))) {
+                Err(Error::SysError(SysErr::ETIMEDOUT)) => (),
+                Err(Error::ErrInterrupted) => return Err(Error::SysError(SysErr::ERESTARTSYS)),
+                Err(e) => return Err(e),
+                Ok(()) => (),
+            }
+        }
+    }
+}
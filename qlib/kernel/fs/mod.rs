@@ -28,6 +28,7 @@ pub mod fsutil;
 pub mod host;
 pub mod inode_overlay;
 pub mod inotify;
+pub mod lease;
 pub mod lock;
 pub mod mount;
 pub mod mount_overlay;
@@ -30,6 +30,7 @@ use super::super::super::common::*;
 use super::super::super::linux_def::*;
 use super::super::super::lrc_cache::*;
 use super::super::super::path::*;
+use super::super::kernel::namespace::*;
 use super::super::task::*;
 use super::dirent::*;
 use super::filesystems::*;
@@ -112,6 +113,7 @@ pub struct MountNsInternal {
     pub root: Dirent,
     pub mounts: QMutex<BTreeMap<u64, Arc<QMutex<Mount>>>>,
     pub mountId: AtomicU64,
+    pub ino: u64,
 }
 
 impl Default for MountNsInternal {
@@ -121,6 +123,7 @@ impl Default for MountNsInternal {
             root: Dirent::default(),
             mounts: QMutex::new(BTreeMap::new()),
             mountId: AtomicU64::new(0),
+            ino: NewNsIno(),
         };
     }
 }
@@ -147,11 +150,57 @@ impl MountNs {
             root: d,
             mounts: QMutex::new(mounts),
             mountId: AtomicU64::new(2),
+            ino: NewNsIno(),
         };
 
         return Self(Arc::new(internal));
     }
 
+    // Fork returns a copy of the mount namespace for unshare(CLONE_NEWNS). The copy
+    // has its own dirent tree over the same inodes, with the mounts made again, so
+    // that later mounts are only seen in one of the namespaces. The filesystems
+    // stay shared: unmounting a copied mount fails with EBUSY while the other
+    // namespace uses it, unless it is detached.
+    pub fn Fork(&self, task: &Task) -> Result<Self> {
+        let ns = Self::New(task, &self.root.Inode());
+        let root = ns.Root();
+
+        let mut mounts = Vec::new();
+        for (_, m) in self.mounts.lock().iter() {
+            let mut next = Some(m.clone());
+            while let Some(curr) = next {
+                let mnt = curr.lock();
+                if !mnt.IsUndo() && !mnt.IsRoot() {
+                    let (path, reachable) = mnt.root.FullName(&self.root);
+                    if reachable {
+                        mounts.push((mnt.Id, path, mnt.root.Inode()));
+                    }
+                }
+                next = mnt.prev.clone();
+            }
+        }
+
+        // in the order they were made, so that the mount points in other mounts
+        // are there once they are reached
+        mounts.sort_by_key(|m| m.0);
+        for (_, path, inode) in &mounts {
+            let d = root.WalkPath(task, &root, path)?;
+            ns.Mount(&d, inode)?;
+        }
+
+        return Ok(ns);
+    }
+
+    // Relocate returns the dirent at the path of d, a dirent of the namespace from.
+    pub fn Relocate(&self, task: &Task, from: &MountNs, d: &Dirent) -> Result<Dirent> {
+        let (path, reachable) = d.FullName(&from.root);
+        if !reachable || path == "/" {
+            return Ok(self.Root());
+        }
+
+        return self.root.WalkPath(task, &self.root, &path);
+    }
+
     pub fn ClearFsCache(&self) {
         let rootDirent = self.root.clone();
         rootDirent.inode.ClearFsCache();
@@ -207,16 +256,32 @@ impl MountNs {
         };
 
         let prev = match &orig.lock().prev {
-            None => panic!("cannot unmount initial dirent"),
+            // the initial mount can't be unmounted
+            None => return Err(Error::SysError(SysErr::EINVAL)),
             Some(prev) => prev.clone(),
         };
 
+        // the dirents and files in the mount hold the mount source. The mount root
+        // is held by the mount table and the caller, and every dirent by the dirent
+        // cache and its cached children, anything more is an open file or a working
+        // directory. The inode of each live dirent holds the mount source.
         let m = node.Inode().lock().MountSource.clone();
-        if !detachOnly && Arc::strong_count(&m) != 2 {
-            return Err(Error::SysError(SysErr::EBUSY));
+        if !detachOnly {
+            let mut descendants = 0;
+            if ExternalRefs(node, &m, 2, &mut descendants) != 0
+                || Arc::strong_count(&m) > 2 + descendants
+            {
+                return Err(Error::SysError(SysErr::EBUSY));
+            }
         }
 
-        node.UnMount(&prev.lock().root)?;
+        let covered = prev.lock().root.clone();
+        node.UnMount(&covered)?;
+        covered.ExtendReference();
+
+        // the unmount is committed, the dirents cached under the mount go away
+        node.flush();
+        node.DropExtendedReference();
 
         let isNone = match prev.lock().prev {
             None => true,
@@ -575,6 +640,9 @@ const DEFAULT_DIRENT_CACHE_SIZE: u64 = 1024;
 //#[derive(Clone)]
 pub struct MountSource {
     pub FileSystemType: String,
+    // Source is the source given to mount(2), shown in /proc/mounts. It is empty,
+    // i.e. "none", for the mounts made by the sandbox itself.
+    pub Source: String,
     pub Flags: MountSourceFlags,
     pub MountSourceOperations: Arc<QMutex<MountSourceOperations>>,
     pub fscache: LruCache<Dirent>,
@@ -585,6 +653,7 @@ impl Default for MountSource {
     fn default() -> Self {
         return Self {
             FileSystemType: "".to_string(),
+            Source: String::new(),
             Flags: MountSourceFlags::default(),
             MountSourceOperations: Arc::new(QMutex::new(SimpleMountSourceOperations::default())),
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
@@ -625,6 +694,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops.clone(),
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -640,6 +710,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops.clone(),
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -655,6 +726,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops.clone(),
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -681,6 +753,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops,
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -699,6 +772,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops,
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -717,6 +791,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops,
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -735,6 +810,7 @@ impl MountSource {
         return Self {
             Flags: flags.clone(),
             FileSystemType: fsType.to_string(),
+            Source: String::new(),
             MountSourceOperations: mops,
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -751,6 +827,7 @@ impl MountSource {
         return Self {
             Flags: MountSourceFlags::default(),
             FileSystemType: "".to_string(),
+            Source: String::new(),
             MountSourceOperations: mops,
             fscache: LruCache::New(DEFAULT_DIRENT_CACHE_SIZE),
             frozen: Vec::new(),
@@ -771,6 +848,17 @@ impl MountSource {
         self.fscache.Add(id, dirent.clone())
     }
 
+    // CachedRefs returns the references the dirent cache holds on the dirent
+    pub fn CachedRefs(&self, dirent: &Dirent) -> usize {
+        let id = dirent.ID();
+        let frozen = self.frozen.iter().filter(|d| d.ID() == id).count();
+        if self.fscache.ContainsKey(id) {
+            return frozen + 1;
+        }
+
+        return frozen;
+    }
+
     pub fn DropExtendReference(&mut self, dirent: &Dirent) {
         let id = dirent.ID();
         self.fscache.Remove(id);
@@ -781,6 +869,32 @@ impl MountSource {
     }
 }
 
+// ExternalRefs returns the references on d and the dirents under it other than
+// the held ones, the dirent cache of msrc and the parent references of their live
+// children. descendants counts the live dirents under d.
+fn ExternalRefs(
+    d: &Dirent,
+    msrc: &Arc<QMutex<MountSource>>,
+    held: usize,
+    descendants: &mut usize,
+) -> usize {
+    let mut children = Vec::new();
+    for (_, w) in d.children.lock().iter() {
+        if let Some(c) = w.Upgrade() {
+            children.push(c);
+        }
+    }
+
+    let expected = held + msrc.lock().CachedRefs(d) + children.len();
+    let mut ret = d.RefCount().saturating_sub(expected);
+    for c in &children {
+        *descendants += 1;
+        ret += ExternalRefs(c, msrc, 1, descendants);
+    }
+
+    return ret;
+}
+
 pub trait DirentOperations {
     fn Revalidate(&self, name: &str, parent: &Inode, child: &Inode) -> bool;
     fn Keep(&self, dirent: &Dirent) -> bool;
@@ -34,12 +34,14 @@ use super::super::ramfs::dir::*;
 
 use crate::qlib::kernel::fs::procfs::net::NetDirNode;
 use crate::qlib::kernel::fs::procfs::proc::ProcNode;
+use crate::qlib::kernel::fs::procfs::sys::kernel::kernel::ProcSysKernelDirNode;
 use crate::qlib::kernel::fs::procfs::sys::net::ipv4::Ipv4Node;
 use crate::qlib::kernel::fs::procfs::sys::net::net::SysNetDirNode;
 use crate::qlib::kernel::fs::procfs::sys::sys::ProcSysDirNode;
 use crate::qlib::kernel::fs::procfs::sys::vm::vm::ProcSysVMDirNode;
 use crate::qlib::kernel::fs::procfs::task::fds::FdDirNode;
 use crate::qlib::kernel::fs::procfs::task::fds::FdInfoDirNode;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NsDirNode;
 use crate::qlib::kernel::fs::procfs::task::subtasks::SubTasksNode;
 use crate::qlib::kernel::fs::procfs::task::task::TaskDirNode;
 
@@ -66,11 +68,13 @@ pub enum DirDataNode {
     SysNetDirNode(SysNetDirNode),
     ProcNode(ProcNode),
     ProcSysVMDirNode(ProcSysVMDirNode),
+    ProcSysKernelDirNode(ProcSysKernelDirNode),
     Ipv4Node(Ipv4Node),
     NetDirNode(NetDirNode),
     ProcSysDirNode(ProcSysDirNode),
     FdDirNode(FdDirNode),
     FdInfoDirNode(FdInfoDirNode),
+    NsDirNode(NsDirNode),
     SubTasksNode(SubTasksNode),
     TaskDirNode(TaskDirNode),
 }
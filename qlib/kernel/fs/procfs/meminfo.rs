@@ -15,6 +15,7 @@
 use crate::qlib::mutex::*;
 use alloc::string::ToString;
 use alloc::sync::Arc;
+use core::sync::atomic::Ordering;
 
 use super::super::attr::*;
 use super::super::dirent::*;
@@ -26,8 +27,10 @@ use super::super::mount::*;
 use super::super::super::super::auth::*;
 use super::super::super::super::common::*;
 use super::super::super::super::linux_def::*;
+use super::super::super::memmgr::overcommit::COMMITTED_AS;
 use super::super::super::task::*;
 use super::super::super::Kernel::HostSpace;
+use super::super::super::SHARESPACE;
 use super::super::fsutil::file::readonly_file::*;
 use super::super::fsutil::inode::simple_file_inode::*;
 use super::inode::*;
@@ -80,6 +83,14 @@ impl ReadonlyFileNodeTrait for MeminfoFileNode {
         s += &format!("AnonPages:      {:>8} kB\n", info.totalram / 100 / 1024);
         s += &format!("Mapped:         {:>8} kB\n", info.totalram / 100 / 1024);
         s += &format!("Shmem:                 0 kB\n");
+        s += &format!(
+            "CommitLimit:    {:>8} kB\n",
+            SHARESPACE.config.read().MemoryLimitBytes() / 1024
+        );
+        s += &format!(
+            "Committed_AS:   {:>8} kB\n",
+            COMMITTED_AS.load(Ordering::Relaxed) / 1024
+        );
 
         // it always change 0 to 2, that's weird
         //s += &format!("Shmem:          {} kB\n", 0);
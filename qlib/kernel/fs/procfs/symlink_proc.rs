@@ -39,8 +39,11 @@ use super::inode::*;
 use crate::qlib::kernel::fs::procfs::mounts::MountsNode;
 use crate::qlib::kernel::fs::procfs::proc::ProcessSelfNode;
 use crate::qlib::kernel::fs::procfs::proc::ThreadSelfNode;
+use crate::qlib::kernel::fs::procfs::task::cwd::CwdNode;
+use crate::qlib::kernel::fs::procfs::task::cwd::RootNode;
 use crate::qlib::kernel::fs::procfs::task::exe::ExeNode;
 use crate::qlib::kernel::fs::procfs::task::fds::FdNode;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NamespaceNode;
 
 #[enum_dispatch(ReadLinkNode)]
 pub trait ReadLinkNodeTrait: Send + Sync {
@@ -63,7 +66,10 @@ pub trait ReadLinkNodeTrait: Send + Sync {
 pub enum ReadLinkNode {
     MountsNode(MountsNode),
     ExeNode(ExeNode),
+    CwdNode(CwdNode),
+    RootNode(RootNode),
     FdNode(FdNode),
+    NamespaceNode(NamespaceNode),
     ThreadSelfNode(ThreadSelfNode),
     ProcessSelfNode(ProcessSelfNode),
 }
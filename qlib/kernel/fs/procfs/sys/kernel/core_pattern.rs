@@ -0,0 +1,116 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+
+use super::super::super::super::super::super::auth::*;
+use super::super::super::super::super::super::common::*;
+use super::super::super::super::super::super::linux_def::*;
+use super::super::super::super::super::super::mem::block::*;
+use super::super::super::super::super::task::*;
+use super::super::super::super::super::threadmgr::task_coredump::*;
+use super::super::super::super::attr::*;
+use super::super::super::super::dirent::*;
+use super::super::super::super::file::*;
+use super::super::super::super::flags::*;
+use super::super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::super::inode::*;
+use super::super::super::super::mount::*;
+use super::super::super::inode::*;
+
+// NewCorePattern returns /proc/sys/kernel/core_pattern, the path of the core
+// dumps, see task_coredump.rs.
+pub fn NewCorePattern(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o644)),
+        FSMagic::PROC_SUPER_MAGIC,
+        false,
+        CorePatternSimpleFileTrait {}.into(),
+    );
+    return NewProcInode(v.into(), msrc, InodeType::SpecialFile, None);
+}
+
+pub struct CorePatternSimpleFileTrait {}
+
+impl SimpleFileTrait for CorePatternSimpleFileTrait {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = ReadonlyFileOperations {
+            node: CorePatternFileNode {}.into(),
+        };
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
+
+#[derive(Clone)]
+pub struct CorePatternFileNode {}
+
+impl ReadonlyFileNodeTrait for CorePatternFileNode {
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let buf = CORE_PATTERN.lock().to_string() + "\n";
+        if offset as usize > buf.len() {
+            return Ok(0);
+        }
+
+        let n = task.CopyDataOutToIovs(&buf.as_bytes()[offset as usize..], dsts, true)?;
+
+        return Ok(n as i64);
+    }
+
+    fn WriteAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        let count = Iovs(srcs).Count();
+        if count >= CORE_PATTERN_MAX {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let mut buf = [0u8; CORE_PATTERN_MAX];
+        let len = task.CopyDataInFromIovs(&mut buf[..count], srcs, true)?;
+        let pattern = match core::str::from_utf8(&buf[..len]) {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(s) => s.trim_end_matches('\n'),
+        };
+
+        *CORE_PATTERN.lock() = pattern.to_string();
+        return Ok(count as i64);
+    }
+}
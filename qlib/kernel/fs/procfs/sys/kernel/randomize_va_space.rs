@@ -0,0 +1,35 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::sync::Arc;
+
+use super::super::super::super::super::task::*;
+use super::super::super::super::super::SHARESPACE;
+use super::super::super::super::inode::*;
+use super::super::super::super::mount::*;
+use super::super::super::inode::*;
+
+// NewRandomizeVaSpace returns /proc/sys/kernel/randomize_va_space: 2, the full
+// randomization of Linux, or 0 with DisableASLR.
+pub fn NewRandomizeVaSpace(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let level = if SHARESPACE.config.read().DisableASLR {
+        0
+    } else {
+        2
+    };
+    let contents = format!("{}\n", level);
+
+    return NewStaticProcInode(task, msrc, &Arc::new(contents.as_bytes().to_vec()));
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod kernel;
 pub mod net;
 pub mod sys;
 pub mod vm;
@@ -14,16 +14,18 @@
 
 pub mod auxvec;
 pub mod comm;
+pub mod cwd;
 pub mod exe;
 pub mod exec_args;
 pub mod fds;
 pub mod io;
 pub mod maps;
 pub mod mounts;
+pub mod namespace_symlink;
+pub mod oom_score_adj;
 pub mod stat;
 pub mod statm;
 pub mod status;
 pub mod subtasks;
 pub mod task;
 pub mod uid_pid_map;
-//pub mod namespace_symlink;
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
+use alloc::string::String;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -25,6 +26,7 @@ use super::super::super::super::threadmgr::thread::*;
 use super::super::super::attr::*;
 use super::super::super::dirent::*;
 use super::super::super::file::*;
+use super::super::super::filesystems::*;
 use super::super::super::flags::*;
 use super::super::super::fsutil::file::readonly_file::*;
 use super::super::super::fsutil::inode::simple_file_inode::*;
@@ -56,6 +58,53 @@ pub fn ForEachMount(thread: &Thread, mountns: MountNs, f: &mut FnMut(&str, &Arc<
     }
 }
 
+// MountOptions formats the per mount options the way Linux shows them in
+// /proc/mounts and /proc/self/mountinfo.
+pub fn MountOptions(flags: &MountSourceFlags) -> String {
+    let mut opts = if flags.ReadOnly { "ro" } else { "rw" }.to_string();
+
+    if flags.NoSuid {
+        opts += ",nosuid";
+    }
+
+    if flags.NoExec {
+        opts += ",noexec";
+    }
+
+    if flags.NoAtime {
+        opts += ",noatime";
+    } else if !flags.StrictAtime {
+        opts += ",relatime";
+    }
+
+    return opts;
+}
+
+pub const CGROUP_MOUNT_OPTIONS: &str = "ro,nosuid,nodev,noexec,relatime";
+
+// CgroupMountPath returns where the cgroup hierarchy of sysfs shows up if m is a
+// sysfs mount. It is listed as a cgroup2 mount, which is how the runtimes find it.
+pub fn CgroupMountPath(mountPath: &str, m: &Arc<QMutex<Mount>>) -> Option<String> {
+    let mroot = m.lock().Root();
+    let mountSource = mroot.Inode().lock().MountSource.clone();
+    if mountSource.lock().FileSystemType != "sysfs" {
+        return None;
+    }
+
+    return Some(format!("{}/fs/cgroup", mountPath.trim_end_matches('/')));
+}
+
+// MountDevice is the source of the mount, or "none" like Linux shows for the
+// pseudo filesystems.
+pub fn MountDevice(mountSource: &Arc<QMutex<MountSource>>) -> String {
+    let source = mountSource.lock().Source.clone();
+    if source.is_empty() {
+        return "none".to_string();
+    }
+
+    return source;
+}
+
 pub fn NewMountInfoFile(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     let v = NewMountInfoFileSimpleFileInode(
         task,
@@ -89,12 +138,19 @@ impl MountInfoFile {
     pub fn GenSnapshot(&self, task: &Task) -> Vec<u8> {
         info!("MountInfoFile GenSnapshot...");
         let mut ret = "".to_string();
+        let mut maxId = 0;
+        let mut sysfs = None;
 
         let mountns = task.mountNS.clone();
         ForEachMount(
             &self.thread,
             mountns,
             &mut |mountPath: &str, m: &Arc<QMutex<Mount>>| {
+                maxId = core::cmp::max(maxId, m.lock().Id);
+                if let Some(cgroup) = CgroupMountPath(mountPath, m) {
+                    sysfs = Some((m.lock().Id, cgroup));
+                }
+
                 // Format:
                 // 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
                 // (1)(2)(3)   (4)   (5)      (6)      (7)   (8) (9)   (10)         (11)
@@ -131,22 +187,10 @@ impl MountInfoFile {
                 // (6) Mount options.
                 let mountSource = mroot.Inode().lock().MountSource.clone();
                 let flags = mountSource.lock().Flags;
-                let mut opts = "rw".to_string();
-                if flags.ReadOnly {
-                    opts = "ro".to_string();
-                }
-
-                if flags.NoAtime {
-                    opts += ",noatime";
-                }
-
-                if flags.NoExec {
-                    opts += ",noexec";
-                }
-
-                ret += &format!("{} ", opts);
+                ret += &format!("{} ", MountOptions(&flags));
 
                 // (7) Optional fields: zero or more fields of the form "tag[:value]".
+                // All the mounts are private, so there is no propagation tag.
                 // (8) Separator: the end of the optional fields is marked by a single hyphen.
                 ret += "- ";
 
@@ -154,14 +198,23 @@ impl MountInfoFile {
                 ret += &format!("{} ", &mountSource.lock().FileSystemType);
 
                 // (10) Mount source: filesystem-specific information or "none".
-                ret += "none ";
+                ret += &format!("{} ", MountDevice(&mountSource));
 
-                // (11) Superblock options. Only "ro/rw" is supported for now,
-                // and is the same as the filesystem option.
-                ret += &format!("{}\n", opts);
+                // (11) Superblock options. Only "ro/rw" is supported for now.
+                ret += &format!("{}\n", if flags.ReadOnly { "ro" } else { "rw" });
             },
         );
 
+        if let Some((sysId, cgroup)) = sysfs {
+            ret += &format!(
+                "{} {} 0:0 / {} {} - cgroup2 cgroup2 ro\n",
+                maxId + 1,
+                sysId,
+                cgroup,
+                CGROUP_MOUNT_OPTIONS
+            );
+        }
+
         return ret.as_bytes().to_vec();
     }
 }
@@ -221,18 +274,21 @@ impl MountsFile {
                 let mroot = m.lock().Root();
                 let mountSource = mroot.Inode().lock().MountSource.clone();
                 let flags = mountSource.lock().Flags;
-
-                let opts = if flags.ReadOnly { "ro" } else { "rw" };
+                let fsType = mountSource.lock().FileSystemType.clone();
 
                 ret += &format!(
                     "{} {} {} {} {} {}\n",
-                    "none",
+                    MountDevice(&mountSource),
                     mountPath,
-                    mountSource.lock().FileSystemType,
-                    opts,
+                    &fsType,
+                    MountOptions(&flags),
                     0,
                     0
                 );
+
+                if let Some(cgroup) = CgroupMountPath(mountPath, m) {
+                    ret += &format!("cgroup2 {} cgroup2 {} 0 0\n", cgroup, CGROUP_MOUNT_OPTIONS);
+                }
             },
         );
 
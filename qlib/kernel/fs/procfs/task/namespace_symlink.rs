@@ -12,38 +12,186 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::sync::Arc;
-use alloc::string::String;
 use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
 
+use super::super::super::super::super::auth::*;
 use super::super::super::super::super::common::*;
+use super::super::super::super::super::device::*;
 use super::super::super::super::super::linux_def::*;
+use super::super::super::super::kernel::namespace::*;
 use super::super::super::super::task::*;
-use super::super::super::ramfs::symlink::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
 use super::super::super::dirent::*;
-use super::super::super::mount::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
 use super::super::super::inode::*;
-use super::super::super::super::threadmgr::thread::*;
+use super::super::super::mount::*;
+use super::super::super::ramfs::dir::*;
+use super::super::super::ramfs::symlink::*;
+use super::super::dir_proc::*;
+use super::super::inode::*;
 use super::super::symlink_proc::*;
 
+// the files of /proc/[pid]/ns and the CLONE_NEW* flags of their namespaces
+pub const NAMESPACE_FILES: [(&str, i32); 6] = [
+    ("ipc", CloneOp::CLONE_NEWIPC),
+    ("mnt", CloneOp::CLONE_NEWNS),
+    ("net", CloneOp::CLONE_NEWNET),
+    ("pid", CloneOp::CLONE_NEWPID),
+    ("user", CloneOp::CLONE_NEWUSER),
+    ("uts", CloneOp::CLONE_NEWUTS),
+];
+
+// NamespaceNode is /proc/[pid]/ns/<type>. Opening it gives a file referring to
+// the namespace, which can be passed to setns(2).
+#[derive(Clone)]
 pub struct NamespaceNode {
     pub thread: Thread,
+    pub typ: i32,
+}
+
+impl NamespaceNode {
+    pub fn Namespace(&self) -> Result<Namespace> {
+        match Namespace::Of(&self.thread, self.typ) {
+            None => return Err(Error::SysError(SysErr::ENOENT)),
+            Some(ns) => return Ok(ns),
+        }
+    }
+}
+
+impl ReadLinkNodeTrait for NamespaceNode {
+    fn ReadLink(&self, _link: &Symlink, _task: &Task, _dir: &Inode) -> Result<String> {
+        let ns = self.Namespace()?;
+        return Ok(format!("{}:[{}]", ns.Name(), ns.Ino()));
+    }
+
+    fn GetLink(&self, _link: &Symlink, task: &Task, dir: &Inode) -> Result<Dirent> {
+        let ns = self.Namespace()?;
+        let name = format!("{}:[{}]", ns.Name(), ns.Ino());
+        let msrc = dir.lock().MountSource.clone();
+        let inode = NewNamespaceInode(task, &ns, &msrc);
+        return Ok(Dirent::New(&inode, &name));
+    }
+}
+
+// NewNamespaceInode returns an inode of the namespace, which has the inode number
+// of the namespace so that the same namespace is always seen as the same file.
+pub fn NewNamespaceInode(task: &Task, ns: &Namespace, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let iops = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o444)),
+        FSMagic::NSFS_MAGIC,
+        false,
+        NamespaceSimpleFileTrait { ns: ns.clone() }.into(),
+    );
+
+    let sattr = StableAttr {
+        Type: InodeType::SpecialFile,
+        DeviceId: PROC_DEVICE.lock().id.DeviceID(),
+        InodeId: ns.Ino(),
+        BlockSize: 4096,
+        DeviceFileMajor: 0,
+        DeviceFileMinor: 0,
+    };
+
+    return Inode::New(iops.into(), msrc, &sattr);
+}
+
+pub struct NamespaceSimpleFileTrait {
+    pub ns: Namespace,
+}
+
+impl SimpleFileTrait for NamespaceSimpleFileTrait {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = ReadonlyFileOperations {
+            node: NamespaceFileNode { ns: self.ns.clone() }.into(),
+        };
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
+
+// NamespaceFileNode is an open namespace file, it can't be read or written.
+#[derive(Clone)]
+pub struct NamespaceFileNode {
+    pub ns: Namespace,
+}
+
+impl ReadonlyFileNodeTrait for NamespaceFileNode {}
+
+// NamespaceOfFile returns the namespace file refers to, if it is a namespace file.
+pub fn NamespaceOfFile(file: &File) -> Option<Namespace> {
+    if let FileOps::ReadonlyFileOperations(fops) = &file.FileOp {
+        if let ReadonlyFileNode::NamespaceFileNode(node) = &fops.node {
+            return Some(node.ns.clone());
+        }
+    }
+
+    return None;
 }
 
-impl ReadLinkNode for NamespaceNode {
-    fn ReadLink(&self, link: &Symlink, task: &Task, dir: &Inode) -> Result<String> {
-        return link.ReadLink(task, dir)
+#[derive(Clone)]
+pub struct NsDirNode {}
+
+impl DirDataNodeTrait for NsDirNode {
+    fn Lookup(&self, d: &Dir, task: &Task, dir: &Inode, name: &str) -> Result<Dirent> {
+        return d.Lookup(task, dir, name);
     }
 
-    fn GetLink(&self, link: &Symlink, task: &Task, dir: &Inode) -> Result<Dirent> {
-        return link.GetLink(task, dir);
+    fn GetFile(
+        &self,
+        d: &Dir,
+        task: &Task,
+        dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        return d.GetFile(task, dir, dirent, flags);
     }
 }
 
-pub fn NewExe(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
-    let node = ExeNode {
-        thread: task.Thread(),
+pub fn NewNsDir(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let mut contents = BTreeMap::new();
+    for (name, typ) in NAMESPACE_FILES.iter() {
+        let node = NamespaceNode {
+            thread: thread.clone(),
+            typ: *typ,
+        };
+
+        contents.insert(
+            name.to_string(),
+            SymlinkNode::New(task, msrc, node.into(), Some(thread.clone())),
+        );
+    }
+
+    let nsDir = DirNode {
+        dir: Dir::New(
+            task,
+            contents,
+            &ROOT_OWNER,
+            &FilePermissions::FromMode(FileMode(0o0511)),
+        ),
+        data: NsDirNode {}.into(),
     };
 
-    return SymlinkNode::New(task, msrc, node)
+    return NewProcInode(
+        nsDir.into(),
+        msrc,
+        InodeType::SpecialDirectory,
+        Some(thread.clone()),
+    );
 }
@@ -0,0 +1,251 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use core::any::Any;
+
+use super::super::super::super::super::auth::*;
+use super::super::super::super::super::common::*;
+use super::super::super::super::super::linux_def::*;
+use super::super::super::super::super::mem::block::*;
+use super::super::super::super::kernel::waiter::*;
+use super::super::super::super::memmgr::overcommit::*;
+use super::super::super::super::memmgr::vma::MMappable;
+use super::super::super::super::task::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
+use super::super::super::dentry::*;
+use super::super::super::dirent::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::inode::*;
+
+pub fn NewOOMScoreAdj(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    // owned by the task's effective uid/gid so the owner can write it
+    let owner = {
+        let creds = thread.Creds();
+        let creds = creds.lock();
+        FileOwner {
+            UID: creds.EffectiveKUID,
+            GID: creds.EffectiveKGID,
+        }
+    };
+    let v = NewOOMScoreAdjSimpleFileInode(
+        task,
+        thread,
+        &owner,
+        &FilePermissions::FromMode(FileMode(0o644)),
+        FSMagic::PROC_SUPER_MAGIC,
+    );
+    return NewProcInode(v.into(), msrc, InodeType::SpecialFile, Some(thread.clone()));
+}
+
+pub fn NewOOMScoreAdjSimpleFileInode(
+    task: &Task,
+    thread: &Thread,
+    owner: &FileOwner,
+    perms: &FilePermissions,
+    typ: u64,
+) -> SimpleFileInode {
+    return SimpleFileInode::New(
+        task,
+        owner,
+        perms,
+        typ,
+        false,
+        OOMScoreAdjSimpleFileTrait {
+            thread: thread.clone(),
+        }
+        .into(),
+    );
+}
+
+pub struct OOMScoreAdjSimpleFileTrait {
+    pub thread: Thread,
+}
+
+impl SimpleFileTrait for OOMScoreAdjSimpleFileTrait {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = OOMScoreAdjFileOperations {
+            thread: self.thread.clone(),
+        };
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
+
+// OOMScoreAdjFileOperations is the only writable file of /proc/[pid], so it has
+// its own file operations instead of the read only ones of the other files.
+#[derive(Clone)]
+pub struct OOMScoreAdjFileOperations {
+    pub thread: Thread,
+}
+
+impl Waitable for OOMScoreAdjFileOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        return mask;
+    }
+
+    fn EventRegister(&self, _task: &Task, _e: &WaitEntry, _mask: EventMask) {}
+
+    fn EventUnregister(&self, _task: &Task, _e: &WaitEntry) {}
+}
+
+impl SpliceOperations for OOMScoreAdjFileOperations {}
+
+impl FileOperations for OOMScoreAdjFileOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::OOMScoreAdjFileOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return true;
+    }
+
+    fn Seek(&self, task: &Task, f: &File, whence: i32, current: i64, offset: i64) -> Result<i64> {
+        return SeekWithDirCursor(task, f, whence, current, offset, None);
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let buf = format!("{}\n", self.thread.ThreadGroup().OOMScoreAdj());
+        if offset as usize > buf.len() {
+            return Ok(0);
+        }
+
+        let n = task.CopyDataOutToIovs(&buf.as_bytes()[offset as usize..], dsts, true)?;
+
+        return Ok(n as i64);
+    }
+
+    fn WriteAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        // like Linux, only the first bytes of the write are parsed
+        let count = Iovs(srcs).Count();
+        let mut buf = [0u8; 24];
+        let len = core::cmp::min(count, buf.len());
+        let len = task.CopyDataInFromIovs(&mut buf[..len], srcs, true)?;
+
+        let adj: i32 = match core::str::from_utf8(&buf[..len]) {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(s) => match s.trim().parse() {
+                Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+                Ok(adj) => adj,
+            },
+        };
+
+        if adj < OOM_SCORE_ADJ_MIN || adj > OOM_SCORE_ADJ_MAX {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let tg = self.thread.ThreadGroup();
+        let mut tg = tg.lock();
+
+        // anyone who can open the file can raise the value, lowering it needs
+        // CAP_SYS_RESOURCE
+        if adj < tg.oomScoreAdj && !task.Creds().HasCapability(Capability::CAP_SYS_RESOURCE) {
+            return Err(Error::SysError(SysErr::EACCES));
+        }
+
+        tg.oomScoreAdj = adj;
+        return Ok(count as i64);
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Ok(());
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for OOMScoreAdjFileOperations {}
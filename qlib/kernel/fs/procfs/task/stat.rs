@@ -110,24 +110,31 @@ impl TaskStatData {
         );
         output += &format!("0 0 " /* tty_nr tpgid */);
         output += &format!("0 " /* flags */);
-        output += &format!("0 0 0 0 " /* minflt cminflt majflt cmajflt */);
 
         let cputime = if self.tgstats {
             self.t.ThreadGroup().CPUStats()
         } else {
             self.t.CPUStats()
         };
+        let childCputime = self.t.ThreadGroup().JoinedChildCPUStats();
+        output += &format!(
+            "{} {} {} {} ",
+            cputime.MinorFaults,
+            childCputime.MinorFaults,
+            cputime.MajorFaults,
+            childCputime.MajorFaults
+        );
+
         output += &format!(
             "{} {} ",
-            ClockTFromDuration(Tsc::Scale(cputime.UserTime) * 1000),
-            ClockTFromDuration(Tsc::Scale(cputime.SysTime) * 1000)
+            ClockTFromDuration(cputime.UserTime),
+            ClockTFromDuration(cputime.SysTime)
         );
 
-        let cputime = self.t.ThreadGroup().JoinedChildCPUStats();
         output += &format!(
             "{} {} ",
-            ClockTFromDuration(Tsc::Scale(cputime.UserTime) * 1000),
-            ClockTFromDuration(Tsc::Scale(cputime.SysTime) * 1000)
+            ClockTFromDuration(childCputime.UserTime),
+            ClockTFromDuration(childCputime.SysTime)
         );
 
         output += &format!("{} {} ", self.t.Priority(), self.t.Niceness());
@@ -88,7 +88,14 @@ impl StatusData {
             }
         };
         ret += &format!("PPid:\t{}\n", ppid);
-        ret += &format!("TracerPid:\t{}\n", 0);
+        let tracerPid = match self.thread.Tracer() {
+            None => 0,
+            Some(tracer) => {
+                let tg = tracer.ThreadGroup();
+                self.pidns.IDOfThreadGroup(&tg)
+            }
+        };
+        ret += &format!("TracerPid:\t{}\n", tracerPid);
 
         let fdTbl = self.thread.lock().fdTbl.clone();
         let fds = fdTbl.Count();
@@ -100,7 +107,9 @@ impl StatusData {
 
         let vss = mm.VirtualMemorySizeLocked();
         let rss = mm.ResidentSetSizeLocked();
+        let lck = mm.LockedMemorySizeLocked();
         ret += &format!("VmSize:\t{} kB\n", vss >> 10);
+        ret += &format!("VmLck:\t{} kB\n", lck >> 10);
         ret += &format!("VmRSS:\t{} kB\n", rss >> 10);
         ret += &format!("Threads:\t{}\n", tg.Count());
 
@@ -109,6 +118,7 @@ impl StatusData {
         ret += &format!("CapPrm:\t{:016x}\n", creds.lock().PermittedCaps.0);
         ret += &format!("CapEff:\t{:016x}\n", creds.lock().EffectiveCaps.0);
         ret += &format!("CapBnd:\t{:016x}\n", creds.lock().BoundingCaps.0);
+        ret += &format!("CapAmb:\t{:016x}\n", creds.lock().AmbientCaps.0);
         ret += &format!("Seccomp:\t{}\n", 0);
 
         //ret += &format!("Mems_allowed:\t{}\n",
@@ -116,6 +126,10 @@ impl StatusData {
         ret += &format!("Mems_allowed:1\t\n");
         ret += &format!("Mems_allowed_list:0\t\n");
 
+        let cpu = self.thread.CPUStats();
+        ret += &format!("voluntary_ctxt_switches:\t{}\n", cpu.VoluntarySwitches);
+        ret += &format!("nonvoluntary_ctxt_switches:\t{}\n", cpu.InvoluntarySwitches);
+
         return ret.as_bytes().to_vec();
     }
 }
@@ -35,12 +35,15 @@ use super::super::inode::*;
 use super::super::proc::*;
 use super::auxvec::*;
 use super::comm::*;
+use super::cwd::*;
 use super::exe::*;
 use super::exec_args::*;
 use super::fds::*;
 use super::io::*;
 use super::maps::*;
 use super::mounts::*;
+use super::namespace_symlink::*;
+use super::oom_score_adj::*;
 use super::stat::*;
 use super::statm::*;
 use super::status::*;
@@ -80,11 +83,17 @@ impl ProcNode {
     ) -> Inode {
         let mut contents = BTreeMap::new();
         contents.insert("auxv".to_string(), NewAUXVec(task, thread, msrc));
+        // every process is in the root of the cgroup v2 hierarchy at /sys/fs/cgroup
+        contents.insert(
+            "cgroup".to_string(),
+            NewStaticProcInode(task, msrc, &Arc::new("0::/\n".as_bytes().to_vec())),
+        );
         contents.insert(
             "cmdline".to_string(),
             NewExecArg(task, thread, msrc, ExecArgType::CmdlineExecArg),
         );
         contents.insert("comm".to_string(), NewComm(task, thread, msrc));
+        contents.insert("cwd".to_string(), NewCwd(task, thread, msrc));
         contents.insert(
             "environ".to_string(),
             NewExecArg(task, thread, msrc, ExecArgType::EnvironExecArg),
@@ -100,6 +109,12 @@ impl ProcNode {
             NewMountInfoFile(task, thread, msrc),
         );
         contents.insert("mounts".to_string(), NewMountsFile(task, thread, msrc));
+        contents.insert("ns".to_string(), NewNsDir(task, thread, msrc));
+        contents.insert(
+            "oom_score_adj".to_string(),
+            NewOOMScoreAdj(task, thread, msrc),
+        );
+        contents.insert("root".to_string(), NewRoot(task, thread, msrc));
         contents.insert(
             "stat".to_string(),
             NewStat(task, thread, showSubtasks, self.lock().pidns.clone(), msrc),
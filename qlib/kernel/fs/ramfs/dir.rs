@@ -179,7 +179,7 @@ impl Dir {
             children: contents,
             dentryMap: DentMap::New(dmap),
 
-            fsType: FSMagic::RAMFS_MAGIC,
+            fsType: 0,
             unstable: unstable,
 
             xattrs: BTreeMap::new(),
@@ -546,7 +546,14 @@ impl InodeOperations for Dir {
     }
 
     fn StatFS(&self, _task: &Task) -> Result<FsInfo> {
-        return Err(Error::SysError(SysErr::ENOSYS));
+        if self.read().fsType == 0 {
+            return Err(Error::SysError(SysErr::ENOSYS));
+        }
+
+        return Ok(FsInfo {
+            Type: self.read().fsType,
+            ..Default::default()
+        });
     }
 
     fn Mappable(&self) -> Result<MMappable> {
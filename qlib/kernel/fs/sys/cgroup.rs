@@ -0,0 +1,164 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A read-only cgroup v2 hierarchy at /sys/fs/cgroup with the limits and the usage
+// of the sandbox, for the runtimes which size their heaps and thread pools by
+// memory.max and cpu.max. Every process is in the root cgroup.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::super::super::auth::*;
+use super::super::super::super::common::*;
+use super::super::super::super::linux_def::*;
+use super::super::super::super::usage::cpu::*;
+use super::super::super::kernel::kernel::*;
+use super::super::super::kernel::throttle::CPU_PERIOD;
+use super::super::super::task::*;
+use super::super::super::PAGE_MGR;
+use super::super::super::SHARESPACE;
+use super::super::dirent::*;
+use super::super::file::*;
+use super::super::flags::*;
+use super::super::fsutil::file::readonly_file::*;
+use super::super::fsutil::inode::simple_file_inode::*;
+use super::super::inode::*;
+use super::super::mount::*;
+use super::sys::*;
+
+pub const CGROUP2_SUPER_MAGIC: u64 = 0x63677270;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgroupFileType {
+    Controllers,
+    CpuMax,
+    CpuStat,
+    CpuWeight,
+    MemoryCurrent,
+    MemoryMax,
+    MemorySwapMax,
+}
+
+pub struct CgroupData {
+    typ: CgroupFileType,
+}
+
+impl CgroupData {
+    pub fn GenSnapshot(&self, _task: &Task) -> Vec<u8> {
+        let ret = match self.typ {
+            CgroupFileType::Controllers => "cpu memory\n".to_string(),
+            CgroupFileType::CpuMax => {
+                let config = SHARESPACE.config.read();
+                if config.CpuMaxQuotaUs == 0 {
+                    format!("max {}\n", config.CpuMaxPeriod())
+                } else {
+                    format!("{} {}\n", config.CpuMaxQuotaUs, config.CpuMaxPeriod())
+                }
+            }
+            CgroupFileType::CpuStat => {
+                let usage = SandboxCPUStats();
+                let p = CPU_PERIOD.lock();
+                format!(
+                    "usage_usec {}\nuser_usec {}\nsystem_usec {}\nnr_periods {}\nnr_throttled {}\nthrottled_usec {}\n",
+                    (usage.UserTime + usage.SysTime) / 1000,
+                    usage.UserTime / 1000,
+                    usage.SysTime / 1000,
+                    p.periods,
+                    p.throttled,
+                    p.throttledTime / 1000
+                )
+            }
+            CgroupFileType::CpuWeight => "100\n".to_string(),
+            CgroupFileType::MemoryCurrent => {
+                let pool = &PAGE_MGR.pagepool;
+                let used = (pool.TotalPages() - pool.FreePages()) * MemoryDef::PAGE_SIZE;
+                format!("{}\n", used)
+            }
+            CgroupFileType::MemoryMax => {
+                format!("{}\n", SHARESPACE.config.read().MemoryLimitBytes())
+            }
+            // the guest has no swap
+            CgroupFileType::MemorySwapMax => "0\n".to_string(),
+        };
+
+        return ret.as_bytes().to_vec();
+    }
+}
+
+impl SimpleFileTrait for CgroupData {
+    fn GetFile(
+        &self,
+        task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
+
+// SandboxCPUStats returns the cpu time of all the processes of the sandbox, the
+// exited ones are in the stats of the processes which waited for them.
+pub fn SandboxCPUStats() -> CPUStats {
+    let mut stats = CPUStats::default();
+    let kernel = match GetKernelOption() {
+        None => return stats,
+        Some(k) => k,
+    };
+
+    for tg in kernel.TaskSet().Root().ThreadGroups() {
+        stats.Accumulate(&tg.CPUStats());
+        stats.Accumulate(&tg.JoinedChildCPUStats());
+    }
+
+    return stats;
+}
+
+pub fn NewCgroupFile(task: &Task, msrc: &Arc<QMutex<MountSource>>, typ: CgroupFileType) -> Inode {
+    let data = CgroupData { typ: typ };
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o444)),
+        CGROUP2_SUPER_MAGIC,
+        false,
+        data.into(),
+    );
+    return NewFile(v.into(), msrc);
+}
+
+pub fn NewCgroupDir(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let files: [(&str, CgroupFileType); 7] = [
+        ("cgroup.controllers", CgroupFileType::Controllers),
+        ("cpu.max", CgroupFileType::CpuMax),
+        ("cpu.stat", CgroupFileType::CpuStat),
+        ("cpu.weight", CgroupFileType::CpuWeight),
+        ("memory.current", CgroupFileType::MemoryCurrent),
+        ("memory.max", CgroupFileType::MemoryMax),
+        ("memory.swap.max", CgroupFileType::MemorySwapMax),
+    ];
+
+    let mut m: BTreeMap<String, Inode> = BTreeMap::new();
+    for (name, typ) in files.iter() {
+        m.insert(name.to_string(), NewCgroupFile(task, msrc, *typ));
+    }
+
+    return NewFsDir(task, msrc, m, CGROUP2_SUPER_MAGIC);
+}
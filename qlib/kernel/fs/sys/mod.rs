@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod cgroup;
 pub mod devices;
 pub mod fs;
 pub mod sys;
@@ -26,6 +26,7 @@ use super::super::attr::*;
 use super::super::inode::*;
 use super::super::mount::*;
 use super::super::ramfs::dir::*;
+use super::cgroup::*;
 use super::devices::*;
 
 pub fn NewFile(iops: Iops, msrc: &Arc<QMutex<MountSource>>) -> Inode {
@@ -48,6 +49,17 @@ pub fn NewDir(
     task: &Task,
     msrc: &Arc<QMutex<MountSource>>,
     contents: BTreeMap<String, Inode>,
+) -> Inode {
+    return NewFsDir(task, msrc, contents, 0);
+}
+
+// NewFsDir is NewDir of a directory statfs reports as a filesystem of fsType, or
+// ENOSYS for 0
+pub fn NewFsDir(
+    task: &Task,
+    msrc: &Arc<QMutex<MountSource>>,
+    contents: BTreeMap<String, Inode>,
+    fsType: u64,
 ) -> Inode {
     let d = Dir::New(
         task,
@@ -55,6 +67,7 @@ pub fn NewDir(
         &ROOT_OWNER,
         &FilePermissions::FromMode(FileMode(0o0555)),
     );
+    d.write().fsType = fsType;
 
     let deviceId = SYS_DEVICE.lock().id.DeviceID();
     let inodeId = SYS_DEVICE.lock().NextIno();
@@ -86,7 +99,9 @@ pub fn NewSys(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     content.insert("dev".to_string(), NewDir(task, msrc, BTreeMap::new()));
     content.insert("devices".to_string(), NewDevicesDir(task, msrc));
     content.insert("firmware".to_string(), NewDir(task, msrc, BTreeMap::new()));
-    content.insert("fs".to_string(), NewDir(task, msrc, BTreeMap::new()));
+    let mut fsContent = BTreeMap::new();
+    fsContent.insert("cgroup".to_string(), NewCgroupDir(task, msrc));
+    content.insert("fs".to_string(), NewDir(task, msrc, fsContent));
     content.insert("kernel".to_string(), NewDir(task, msrc, BTreeMap::new()));
     content.insert("module".to_string(), NewDir(task, msrc, BTreeMap::new()));
     content.insert("power".to_string(), NewDir(task, msrc, BTreeMap::new()));
@@ -176,6 +176,10 @@ impl EventPoll {
     pub fn ReadEvents(&self, task: &Task, max: i32, events: &mut StackVec<Event, 64>) {
         let mut lists = self.lists.lock();
 
+        // level-triggered entries which were reported in this round. They stay ready
+        // until a later Readiness() check finds them drained.
+        let mut requeue = PollEntryList::default();
+
         let mut it = lists.Front();
         while events.Len() < max as usize {
             let entry = if let Some(entry) = it {
@@ -226,8 +230,17 @@ impl EventPoll {
             } else if flags & EDGE_TRIGGERED != 0 {
                 lists.Remove(&entry);
                 entry.lock().state = PollEntryState::Waiting;
+            } else {
+                // The host fds are registered edge-triggered, so no new host event
+                // will come for data left behind by a partial read. Keep the entry on
+                // the ready list, behind the entries not yet visited.
+                lists.Remove(&entry);
+                entry.lock().state = PollEntryState::Ready;
+                requeue.PushBack(&entry);
             }
         }
+
+        lists.PushBackList(&mut requeue);
     }
 
     // initEntryReadiness initializes the entry's state with regards to its
@@ -161,6 +161,13 @@ impl GapMgr {
         }
     }
 
+    // FirstFreeAfter returns the id AllocAfter would take, without taking it
+    pub fn FirstFreeAfter(&self, id: u64) -> Option<u64> {
+        let (gEnd, gLen) = self.map.range((Included(id), Unbounded)).next()?;
+        let start = *gEnd - (*gLen - 1);
+        return Some(core::cmp::max(start, id));
+    }
+
     pub fn AllocAfter(&mut self, id: u64) -> Option<u64> {
         let mut firstRange = None;
         for (gEnd, gLen) in self.map.range((Included(id), Unbounded)) {
@@ -283,7 +290,7 @@ impl FDTable {
     pub fn RemoveRange(&self, startfd: i32, endfd: i32) -> Vec<File> {
         let mut intern = self.data.lock();
         let mut ret = Vec::new();
-        for fd in startfd..endfd {
+        for fd in intern.FdsInRange(startfd, endfd) {
             match intern.Remove(self.id, fd) {
                 None => (),
                 Some(f) => ret.push(f),
@@ -299,7 +306,7 @@ impl FDTable {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
-        for fd in startfd..endfd {
+        for fd in intern.FdsInRange(startfd, endfd) {
             match intern.SetFlags(fd, &flags) {
                 Err(_) => (),
                 Ok(()) => (),
@@ -449,6 +456,22 @@ impl FDTable {
         return Ok(newfd);
     }
 
+    // CheckFreeFD returns EMFILE if NewFDFrom(fd) would fail for the lack of a free
+    // fd under RLIMIT_NOFILE
+    pub fn CheckFreeFD(&self, task: &Task, fd: i32) -> Result<()> {
+        let lim = task
+            .Thread()
+            .ThreadGroup()
+            .Limits()
+            .Get(LimitType::NumberOfFiles)
+            .Cur;
+
+        match self.data.lock().gaps.FirstFreeAfter(fd as u64) {
+            Some(newfd) if newfd < lim && newfd <= i32::MAX as u64 => return Ok(()),
+            _ => return Err(Error::SysError(SysErr::EMFILE)),
+        }
+    }
+
     pub fn NewFDAt(&self, task: &Task, fd: i32, file: &File, flags: &FDFlags) -> Result<()> {
         if fd < 0 {
             return Err(Error::SysError(SysErr::EBADF));
@@ -581,6 +604,17 @@ impl FDTableInternal {
         return self.descTbl.len();
     }
 
+    // FdsInRange returns the open fds in [startfd, endfd) without walking the
+    // whole range, which can be as large as [0, i32::MAX) for close_range.
+    pub fn FdsInRange(&self, startfd: i32, endfd: i32) -> Vec<i32> {
+        return self
+            .descTbl
+            .keys()
+            .cloned()
+            .filter(|fd| *fd >= startfd && *fd < endfd)
+            .collect();
+    }
+
     fn set(&mut self, uid: u64, fd: i32, file: &File, flags: &FDFlags) {
         let fdesc = Descriptor {
             file: file.clone(),
@@ -24,6 +24,7 @@ use super::super::super::linux::ipc::*;
 use super::super::super::linux_def::*;
 use super::super::task::*;
 use super::msgqueue;
+use super::namespace::*;
 use super::semaphore;
 use super::shm;
 use crate::qlib::mutex::*;
@@ -34,6 +35,7 @@ pub struct IPCNamespace {
     pub semphores: semaphore::SemRegistry,
     pub shms: shm::ShmRegistry,
     pub queues: msgqueue::MQRegistry,
+    pub ino: u64,
 }
 
 impl Default for IPCNamespace {
@@ -49,6 +51,7 @@ impl IPCNamespace {
             semphores: semaphore::SemRegistry::New(userNS),
             shms: shm::ShmRegistry::New(userNS),
             queues: msgqueue::MQRegistry::New(userNS),
+            ino: NewNsIno(),
         };
     }
 
@@ -49,6 +49,7 @@ use super::super::SHARESPACE;
 use super::cpuset::*;
 use super::fd_table::*;
 use super::ipc_namespace::*;
+use super::net_namespace::*;
 use super::platform::*;
 use super::signal_handler::*;
 use super::socket_store::*;
@@ -105,6 +106,7 @@ pub struct KernelInternal {
     pub rootUserNamespace: UserNameSpace,
     pub rootUTSNamespace: UTSNamespace,
     pub rootIPCNamespace: IPCNamespace,
+    pub rootNetNamespace: NetNamespace,
     pub applicationCores: usize,
     //pub useHostCores: bool,
 
@@ -181,6 +183,8 @@ impl KernelInternal {
             TimerListener::ITimerRealListener(Arc::new(listener)),
         );
         tg.lock().itimerRealTimer = itimer;
+        // RLIMIT_CPU is inherited, so arm its timer for the new thread group as well.
+        tg.lock().resetRlimitCPUSoftSettingLocked();
 
         return tg;
     }
@@ -200,6 +204,7 @@ impl Deref for Kernel {
 impl Kernel {
     pub fn Init(args: InitKernalArgs) -> Self {
         let cpuTicker = Arc::new(KernelCPUClockTicker::New());
+        let rootNetNamespace = NetNamespace::New(&args.RootUserNamespace, true);
         let internal = KernelInternal {
             extMu: QMutex::new(()),
             featureSet: args.FeatureSet,
@@ -207,6 +212,7 @@ impl Kernel {
             rootUserNamespace: args.RootUserNamespace,
             rootUTSNamespace: args.RootUTSNamespace,
             rootIPCNamespace: args.RootIPCNamespace,
+            rootNetNamespace: rootNetNamespace,
             applicationCores: args.ApplicationCores as usize - 1,
             mounts: QRwLock::new(BTreeMap::new()),
             sockets: SocketStore::default(),
@@ -302,6 +308,10 @@ impl Kernel {
         return self.rootIPCNamespace.clone();
     }
 
+    pub fn RootNetNamespace(&self) -> NetNamespace {
+        return self.rootNetNamespace.clone();
+    }
+
     pub fn CreateProcess(&self, args: &mut CreateProcessArgs) -> Result<(ThreadGroup, ThreadID)> {
         self.extMu.lock();
 
@@ -368,10 +378,14 @@ impl Kernel {
             Fdtbl: task.fdTbl.clone(),
             Credentials: args.Credentials.clone(),
             Niceness: 0,
-            NetworkNamespaced: false,
+            Personality: 0,
+            RSeqAddr: 0,
+            RSeqSignature: 0,
+            NetNamespace: self.RootNetNamespace(),
             AllowedCPUMask: CPUSet::NewFullCPUSet(self.applicationCores),
             UTSNamespace: args.UTSNamespace.clone(),
             IPCNamespace: args.IPCNamespace.clone(),
+            MountNamespace: mns.clone(),
             Blocker: task.blocker.clone(),
             ContainerID: args.ContainerID.to_string(),
         };
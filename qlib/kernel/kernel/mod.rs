@@ -32,6 +32,8 @@ pub mod futex;
 pub mod ipc_namespace;
 pub mod kernel;
 pub mod msgqueue;
+pub mod namespace;
+pub mod net_namespace;
 pub mod pipe;
 pub mod platform;
 pub mod semaphore;
@@ -40,4 +42,5 @@ pub mod signal_handler;
 pub mod signalfd;
 pub mod socket_store;
 pub mod syslog;
+pub mod throttle;
 pub mod uts_namespace;
@@ -0,0 +1,104 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use super::super::super::auth::userns::*;
+use super::super::super::linux_def::*;
+use super::super::fs::mount::*;
+use super::super::threadmgr::pid_namespace::*;
+use super::super::threadmgr::thread::*;
+use super::ipc_namespace::*;
+use super::net_namespace::*;
+use super::uts_namespace::*;
+
+// the inode numbers identifying the namespaces start at Linux's
+// PROC_DYNAMIC_FIRST
+static NEXT_NS_INO: AtomicU64 = AtomicU64::new(0xF000_0000);
+
+pub fn NewNsIno() -> u64 {
+    return NEXT_NS_INO.fetch_add(1, Ordering::SeqCst);
+}
+
+// Namespace is the namespace a /proc/[pid]/ns file refers to.
+#[derive(Clone)]
+pub enum Namespace {
+    Ipc(IPCNamespace),
+    Mount(MountNs),
+    Net(NetNamespace),
+    Pid(PIDNamespace),
+    User(UserNameSpace),
+    Uts(UTSNamespace),
+}
+
+impl Namespace {
+    // Of returns the namespace of thread with the CLONE_NEW* flag typ.
+    pub fn Of(thread: &Thread, typ: i32) -> Option<Self> {
+        match typ {
+            CloneOp::CLONE_NEWIPC => return Some(Self::Ipc(thread.lock().ipcns.clone())),
+            CloneOp::CLONE_NEWNS => return Some(Self::Mount(thread.MountNamespace())),
+            CloneOp::CLONE_NEWNET => return Some(Self::Net(thread.NetworkNamespace())),
+            CloneOp::CLONE_NEWPID => return Some(Self::Pid(thread.PIDNamespace())),
+            CloneOp::CLONE_NEWUSER => return Some(Self::User(thread.UserNamespace())),
+            CloneOp::CLONE_NEWUTS => return Some(Self::Uts(thread.UTSNamespace())),
+            _ => return None,
+        }
+    }
+
+    // Type returns the CLONE_NEW* flag of the namespace, as passed to setns(2).
+    pub fn Type(&self) -> i32 {
+        match self {
+            Self::Ipc(_) => return CloneOp::CLONE_NEWIPC,
+            Self::Mount(_) => return CloneOp::CLONE_NEWNS,
+            Self::Net(_) => return CloneOp::CLONE_NEWNET,
+            Self::Pid(_) => return CloneOp::CLONE_NEWPID,
+            Self::User(_) => return CloneOp::CLONE_NEWUSER,
+            Self::Uts(_) => return CloneOp::CLONE_NEWUTS,
+        }
+    }
+
+    pub fn Name(&self) -> &'static str {
+        match self {
+            Self::Ipc(_) => return "ipc",
+            Self::Mount(_) => return "mnt",
+            Self::Net(_) => return "net",
+            Self::Pid(_) => return "pid",
+            Self::User(_) => return "user",
+            Self::Uts(_) => return "uts",
+        }
+    }
+
+    pub fn Ino(&self) -> u64 {
+        match self {
+            Self::Ipc(ns) => return ns.ino,
+            Self::Mount(ns) => return ns.ino,
+            Self::Net(ns) => return ns.ino,
+            Self::Pid(ns) => return ns.lock().ino,
+            Self::User(ns) => return ns.lock().ino,
+            Self::Uts(ns) => return ns.lock().ino,
+        }
+    }
+
+    pub fn UserNamespace(&self) -> UserNameSpace {
+        match self {
+            Self::Ipc(ns) => return ns.userNS.clone(),
+            Self::Mount(ns) => return ns.UserNamespace(),
+            Self::Net(ns) => return ns.UserNamespace(),
+            Self::Pid(ns) => return ns.UserNamespace(),
+            Self::User(ns) => return ns.clone(),
+            Self::Uts(ns) => return ns.UserNamespace(),
+        }
+    }
+}
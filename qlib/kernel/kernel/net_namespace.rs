@@ -0,0 +1,76 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+use super::super::super::auth::userns::*;
+use super::abstract_socket_namespace::*;
+use super::namespace::*;
+
+// NetNamespace is a network namespace. Network namespaces aren't implemented in
+// full, all of them share the host network devices and only the abstract unix
+// socket addresses are per namespace.
+pub struct NetNamespaceInternal {
+    pub userns: UserNameSpace,
+    pub isRoot: bool,
+    pub ino: u64,
+    pub abstractSockets: AbstractSocketNamespace,
+}
+
+#[derive(Clone)]
+pub struct NetNamespace(Arc<NetNamespaceInternal>);
+
+impl Deref for NetNamespace {
+    type Target = Arc<NetNamespaceInternal>;
+
+    fn deref(&self) -> &Arc<NetNamespaceInternal> {
+        &self.0
+    }
+}
+
+impl Default for NetNamespace {
+    fn default() -> Self {
+        return Self::New(&UserNameSpace::default(), true);
+    }
+}
+
+impl NetNamespace {
+    pub fn New(userns: &UserNameSpace, isRoot: bool) -> Self {
+        let internal = NetNamespaceInternal {
+            userns: userns.clone(),
+            isRoot: isRoot,
+            ino: NewNsIno(),
+            abstractSockets: if isRoot {
+                ABSTRACT_SOCKET.clone()
+            } else {
+                AbstractSocketNamespace::default()
+            },
+        };
+
+        return Self(Arc::new(internal));
+    }
+
+    pub fn IsRoot(&self) -> bool {
+        return self.isRoot;
+    }
+
+    pub fn UserNamespace(&self) -> UserNameSpace {
+        return self.userns.clone();
+    }
+
+    pub fn AbstractSockets(&self) -> AbstractSocketNamespace {
+        return self.abstractSockets.clone();
+    }
+}
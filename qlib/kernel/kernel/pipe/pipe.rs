@@ -531,6 +531,11 @@ impl Pipe {
     //
     // Precondition: this pipe must have writers.
     pub fn Write(&self, task: &Task, src: BlockSeq) -> Result<usize> {
+        // A zero-length write is a no-op, even without readers.
+        if src.NumBytes() == 0 {
+            return Ok(0);
+        }
+
         let mut p = self.intern.lock();
 
         // Can't write to a pipe with no readers.
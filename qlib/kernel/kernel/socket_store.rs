@@ -18,18 +18,24 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ops::Deref;
 
+use super::super::super::common::*;
+use super::super::super::linux_def::*;
 use super::super::fs::file::*;
+use super::super::SHARESPACE;
 
 #[derive(Clone)]
 pub struct SocketRecord {
     pub id: u64,
     pub socket: FileWeak,
+    pub inet: bool,
 }
 
 #[derive(Default)]
 pub struct SocketStoreIntern {
     pub nextRecord: u64,
     pub sockets: BTreeMap<u64, SocketRecord>,
+    // the number of inet sockets, which is limited by Config::MaxSockets
+    pub inetCount: u64,
 }
 
 #[derive(Default)]
@@ -43,8 +49,50 @@ impl Deref for SocketStore {
     }
 }
 
+// InetSlot is a place under Config::MaxSockets taken before the socket exists,
+// e.g. by accept before it takes a connection off the backlog. It is given back
+// when dropped unless a socket is added with it.
+pub struct InetSlot {
+    store: SocketStore,
+    used: bool,
+}
+
+impl Drop for InetSlot {
+    fn drop(&mut self) {
+        if !self.used {
+            self.store.lock().inetCount -= 1;
+        }
+    }
+}
+
 impl SocketStore {
-    pub fn AddSocket(&self, sock: &File) {
+    pub fn ReserveInet(&self) -> Result<InetSlot> {
+        let maxSockets = SHARESPACE.config.read().MaxSockets;
+        let mut store = self.lock();
+        if maxSockets != 0 && store.inetCount >= maxSockets {
+            return Err(Error::SysError(SysErr::ENFILE));
+        }
+        store.inetCount += 1;
+
+        return Ok(InetSlot {
+            store: SocketStore(self.0.clone()),
+            used: false,
+        });
+    }
+
+    pub fn AddSocket(&self, sock: &File, slot: Option<InetSlot>) -> Result<()> {
+        let inet = sock.FileOp.FopsType() == FileOpsType::SocketOperations;
+        let maxSockets = SHARESPACE.config.read().MaxSockets;
+
+        // the socket takes over the count of the slot
+        let reserved = match slot {
+            Some(mut slot) if inet => {
+                slot.used = true;
+                true
+            }
+            _ => false,
+        };
+
         let mut store = self.lock();
         let rid = store.nextRecord;
 
@@ -53,20 +101,34 @@ impl SocketStore {
             panic!("SocketStore::AddSocket Socket {} added twice", sockId);
         }
 
+        if inet && !reserved {
+            if maxSockets != 0 && store.inetCount >= maxSockets {
+                return Err(Error::SysError(SysErr::ENFILE));
+            }
+            store.inetCount += 1;
+        }
+
         store.nextRecord += 1;
         store.sockets.insert(
             sockId,
             SocketRecord {
                 id: rid,
                 socket: sock.Downgrade(),
+                inet: inet,
             },
         );
+
+        return Ok(());
     }
 
     pub fn DeleteSocket(&self, sock: &File) {
         let mut store = self.lock();
         let sockId = sock.UniqueId();
-        store.sockets.remove(&sockId);
+        if let Some(record) = store.sockets.remove(&sockId) {
+            if record.inet {
+                store.inetCount -= 1;
+            }
+        }
     }
 
     pub fn ListSockets(&self) -> Vec<(u64, File)> {
@@ -0,0 +1,511 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Token bucket throttles of the sandbox's disk bandwidth and IOPS and of its
+// network bandwidth. A rate of 0 leaves the IO unthrottled. The cpu time of the
+// tasks is throttled by periods of the cpu.max quota, see CpuThrottle.
+
+use crate::qlib::mutex::*;
+use core::convert::TryInto;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use super::super::super::common::*;
+use super::super::super::linux::time::*;
+use super::super::super::linux_def::*;
+use super::super::task::*;
+use super::super::Tsc;
+use super::super::SHARESPACE;
+
+#[derive(Debug, Default)]
+pub struct TokenBucket {
+    // tokens can go negative: a request larger than the bucket is let through and
+    // the later ones wait until the debt is paid back
+    pub tokens: i64,
+    // last refill time in ns, 0 before the first use
+    pub last: i64,
+}
+
+impl TokenBucket {
+    pub const fn New() -> Self {
+        return Self { tokens: 0, last: 0 };
+    }
+
+    fn Refill(&mut self, now: i64, rate: u64, burst: u64) {
+        if self.last == 0 {
+            self.tokens = burst as i64;
+            self.last = now;
+            return;
+        }
+
+        let elapsed = now - self.last;
+        if elapsed <= 0 {
+            return;
+        }
+
+        let add = (elapsed as u128 * rate as u128 / SECOND as u128) as i64;
+        if add == 0 {
+            return;
+        }
+
+        self.tokens = core::cmp::min(self.tokens.saturating_add(add), burst as i64);
+        // only move last by the time the added tokens took, so no fraction is lost
+        self.last += (add as u128 * SECOND as u128 / rate as u128) as i64;
+    }
+
+    // Wait returns the ns until the bucket has tokens again, 0 if it has some now.
+    pub fn Wait(&mut self, now: i64, rate: u64, burst: u64) -> i64 {
+        self.Refill(now, rate, burst);
+        if self.tokens > 0 {
+            return 0;
+        }
+
+        let missing = (1 - self.tokens) as u128;
+        let wait = (missing * SECOND as u128 + rate as u128 - 1) / rate as u128;
+        return core::cmp::max(wait as i64, 1);
+    }
+
+    pub fn Take(&mut self, now: i64, rate: u64, burst: u64, n: u64) {
+        self.Refill(now, rate, burst);
+        self.tokens = self.tokens.saturating_sub(n as i64);
+    }
+}
+
+pub struct Throttle {
+    bucket: QMutex<TokenBucket>,
+}
+
+impl Throttle {
+    pub const fn New() -> Self {
+        return Self {
+            bucket: QMutex::new(TokenBucket::New()),
+        };
+    }
+
+    // Wait parks the task on its monotonic block timer until the bucket has tokens.
+    // burst 0 is one second of rate.
+    pub fn Wait(&self, task: &Task, rate: u64, burst: u64) -> Result<()> {
+        let burst = if burst == 0 { rate } else { burst };
+        loop {
+            let wait = self.bucket.lock().Wait(Task::MonoTimeNow().0, rate, burst);
+            if wait == 0 {
+                return Ok(());
+            }
+
+            match task.blocker.BlockWithMonoTimeout(false, Some(wait)).1 {
+                Err(Error::SysError(SysErr::ETIMEDOUT)) => (),
+                Err(Error::ErrInterrupted) => return Err(Error::SysError(SysErr::ERESTARTSYS)),
+                Err(e) => return Err(e),
+                Ok(()) => (),
+            }
+        }
+    }
+
+    // TryWait is Wait for a nonblocking caller, it fails with EAGAIN instead of
+    // parking the task.
+    pub fn TryWait(&self, rate: u64, burst: u64) -> Result<()> {
+        let burst = if burst == 0 { rate } else { burst };
+        if self.bucket.lock().Wait(Task::MonoTimeNow().0, rate, burst) > 0 {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
+
+        return Ok(());
+    }
+
+    // Take charges the n bytes which went through after Wait
+    pub fn Take(&self, rate: u64, burst: u64, n: u64) {
+        let burst = if burst == 0 { rate } else { burst };
+        self.bucket
+            .lock()
+            .Take(Task::MonoTimeNow().0, rate, burst, n);
+    }
+}
+
+pub static DISK_READ_THROTTLE: Throttle = Throttle::New();
+pub static DISK_WRITE_THROTTLE: Throttle = Throttle::New();
+pub static DISK_READ_IOPS_THROTTLE: Throttle = Throttle::New();
+pub static DISK_WRITE_IOPS_THROTTLE: Throttle = Throttle::New();
+
+// DiskIO runs op, a read, write or fsync of a regular file, within the configured
+// disk bandwidth and IOPS. count gets the bytes transferred out of op's result.
+// Every op is charged as one operation, fsync as a write.
+pub fn DiskIO<T>(
+    task: &Task,
+    write: bool,
+    op: impl FnOnce() -> Result<T>,
+    count: impl Fn(&T) -> i64,
+) -> Result<T> {
+    let (rate, burst, iops) = {
+        let config = SHARESPACE.config.read();
+        if write {
+            (
+                config.DiskWriteBytesPerSec,
+                config.DiskBurstBytes,
+                config.DiskWriteIops,
+            )
+        } else {
+            (
+                config.DiskReadBytesPerSec,
+                config.DiskBurstBytes,
+                config.DiskReadIops,
+            )
+        }
+    };
+
+    if rate == 0 && iops == 0 {
+        return op();
+    }
+
+    let (throttle, iopsThrottle) = if write {
+        (&DISK_WRITE_THROTTLE, &DISK_WRITE_IOPS_THROTTLE)
+    } else {
+        (&DISK_READ_THROTTLE, &DISK_READ_IOPS_THROTTLE)
+    };
+
+    if iops > 0 {
+        iopsThrottle.Wait(task, iops, 0)?;
+    }
+
+    if rate > 0 {
+        throttle.Wait(task, rate, burst)?;
+    }
+
+    let ret = op();
+
+    if iops > 0 {
+        iopsThrottle.Take(iops, 0, 1);
+    }
+
+    let ret = ret?;
+    let n = count(&ret);
+    if rate > 0 && n > 0 {
+        throttle.Take(rate, burst, n as u64);
+    }
+
+    return Ok(ret);
+}
+
+pub static NET_SEND_THROTTLE: Throttle = Throttle::New();
+pub static NET_RECV_THROTTLE: Throttle = Throttle::New();
+
+// SocketThrottle holds the network throttling state of a host socket: whether it's
+// an inet socket, the only kind which is throttled, and its own send rate set with
+// SO_MAX_PACING_RATE, which is applied on top of the sandbox wide rate.
+pub struct SocketThrottle {
+    inet: bool,
+    pacingRate: AtomicU64,
+    pacing: Throttle,
+}
+
+impl SocketThrottle {
+    pub fn New(family: i32) -> Self {
+        return Self {
+            inet: family == AFType::AF_INET || family == AFType::AF_INET6,
+            pacingRate: AtomicU64::new(0),
+            pacing: Throttle::New(),
+        };
+    }
+
+    // PacingRate returns the SO_MAX_PACING_RATE in bytes/sec, u64::MAX is unlimited
+    pub fn PacingRate(&self) -> u64 {
+        match self.pacingRate.load(Ordering::Relaxed) {
+            0 => return u64::MAX,
+            rate => return rate,
+        }
+    }
+
+    pub fn SetPacingRate(&self, rate: u64) {
+        let rate = if rate == u64::MAX { 0 } else { rate };
+        self.pacingRate.store(rate, Ordering::Relaxed);
+    }
+
+    // SetSockOpt sets SO_MAX_PACING_RATE, which is an u64 or an u32 depending on
+    // the option length. The host socket never sees the option.
+    pub fn SetSockOpt(&self, opt: &[u8]) -> Result<i64> {
+        let rate = if opt.len() >= 8 {
+            u64::from_ne_bytes(opt[..8].try_into().unwrap())
+        } else if opt.len() >= 4 {
+            match u32::from_ne_bytes(opt[..4].try_into().unwrap()) {
+                u32::MAX => u64::MAX,
+                rate => rate as u64,
+            }
+        } else {
+            return Err(Error::SysError(SysErr::EINVAL));
+        };
+
+        self.SetPacingRate(rate);
+        return Ok(0);
+    }
+
+    pub fn GetSockOpt(&self, opt: &mut [u8]) -> Result<i64> {
+        let rate = self.PacingRate();
+        if opt.len() >= 8 {
+            opt[..8].copy_from_slice(&rate.to_ne_bytes());
+            return Ok(8);
+        } else if opt.len() >= 4 {
+            let rate = core::cmp::min(rate, u32::MAX as u64) as u32;
+            opt[..4].copy_from_slice(&rate.to_ne_bytes());
+            return Ok(4);
+        }
+
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Rates(&self, send: bool) -> (u64, u64, u64) {
+        let config = SHARESPACE.config.read();
+        if send {
+            return (
+                config.NetSendBytesPerSec,
+                config.NetBurstBytes,
+                self.pacingRate.load(Ordering::Relaxed),
+            );
+        }
+
+        return (config.NetRecvBytesPerSec, config.NetBurstBytes, 0);
+    }
+
+    // Wait parks the task until the sandbox wide rate and, for a send, the pacing
+    // rate of the socket let more data through. A nonblocking socket or a
+    // MSG_DONTWAIT call gets EAGAIN instead.
+    pub fn Wait(&self, task: &Task, send: bool, nonblocking: bool) -> Result<()> {
+        if !self.inet {
+            return Ok(());
+        }
+
+        let wait = |throttle: &Throttle, rate: u64, burst: u64| {
+            if nonblocking {
+                return throttle.TryWait(rate, burst);
+            }
+            return throttle.Wait(task, rate, burst);
+        };
+
+        let (rate, burst, pacing) = self.Rates(send);
+        if rate > 0 {
+            let throttle = if send {
+                &NET_SEND_THROTTLE
+            } else {
+                &NET_RECV_THROTTLE
+            };
+            wait(throttle, rate, burst)?;
+        }
+
+        if pacing > 0 {
+            wait(&self.pacing, pacing, 0)?;
+        }
+
+        return Ok(());
+    }
+
+    // Take charges the n bytes which went through after Wait
+    pub fn Take(&self, send: bool, n: i64) {
+        if !self.inet || n <= 0 {
+            return;
+        }
+
+        let (rate, burst, pacing) = self.Rates(send);
+        if rate > 0 {
+            let throttle = if send {
+                &NET_SEND_THROTTLE
+            } else {
+                &NET_RECV_THROTTLE
+            };
+            throttle.Take(rate, burst, n as u64);
+        }
+
+        if pacing > 0 {
+            self.pacing.Take(pacing, 0, n as u64);
+        }
+    }
+}
+
+// CpuPeriod is the cpu time the tasks used in the current period of the cpu.max
+// quota, like the bandwidth control of a cgroup.
+#[derive(Debug, Default)]
+pub struct CpuPeriod {
+    // start of the current period in ns, 0 before the first use
+    pub start: i64,
+    // cpu time in ns used since start. The usage is only checked on the ticks of
+    // the tasks, so a period can go over the quota and the overrun is paid back
+    // in the next periods.
+    pub used: i64,
+    // the cpu.stat counters: the periods with a running task, the ones which used
+    // up the quota and the wall time in ns from the quota running out to the ends
+    // of their periods, however many tasks were parked
+    pub periods: u64,
+    pub throttled: u64,
+    pub throttledTime: i64,
+    // whether the current period is counted in throttled
+    throttledNow: bool,
+}
+
+impl CpuPeriod {
+    pub const fn New() -> Self {
+        return Self {
+            start: 0,
+            used: 0,
+            periods: 0,
+            throttled: 0,
+            throttledTime: 0,
+            throttledNow: false,
+        };
+    }
+
+    fn Advance(&mut self, now: i64, quota: i64, period: i64) {
+        if self.start == 0 {
+            self.start = now;
+            return;
+        }
+
+        let periods = (now - self.start) / period;
+        if periods <= 0 {
+            return;
+        }
+
+        // an idle period doesn't leave any quota for the next ones
+        self.start += periods * period;
+        self.used = core::cmp::max(self.used.saturating_sub(periods * quota), 0);
+        self.periods += 1;
+        self.throttledNow = false;
+    }
+
+    pub fn Charge(&mut self, now: i64, quota: i64, period: i64, ns: i64) {
+        self.Advance(now, quota, period);
+        self.used += ns;
+    }
+
+    // Wait returns the ns until the next period when the quota is used up, 0 if
+    // the tasks may run now.
+    pub fn Wait(&mut self, now: i64, quota: i64, period: i64) -> i64 {
+        self.Advance(now, quota, period);
+        if self.used < quota {
+            return 0;
+        }
+
+        if !self.throttledNow {
+            self.throttledNow = true;
+            self.throttled += 1;
+            self.throttledTime += self.start + period - now;
+        }
+
+        return self.start + period - now;
+    }
+}
+
+pub static CPU_PERIOD: QMutex<CpuPeriod> = QMutex::new(CpuPeriod::New());
+
+// CpuThrottle charges the cpu time the task used since its last call to the
+// cpu.max quota and parks the task until the next period once the quota is used
+// up. It is called when the task enters the kernel for a syscall or a preemption.
+pub fn CpuThrottle(task: &Task) {
+    let (quota, period) = {
+        let config = SHARESPACE.config.read();
+        if config.CpuMaxQuotaUs == 0 {
+            return;
+        }
+
+        (
+            config.CpuMaxQuotaUs as i64 * MICROSECOND,
+            config.CpuMaxPeriod() as i64 * MICROSECOND,
+        )
+    };
+
+    let ticks = {
+        let mut t = task.sched.lock();
+        let used = t.UserTicks + t.SysTicks;
+        let ticks = used - t.QuotaTicks;
+        t.QuotaTicks = used;
+        ticks
+    };
+
+    CPU_PERIOD.lock().Charge(
+        Task::MonoTimeNow().0,
+        quota,
+        period,
+        Tsc::Scale(ticks) * 1000,
+    );
+    loop {
+        let wait = CPU_PERIOD.lock().Wait(Task::MonoTimeNow().0, quota, period);
+
+        if wait == 0 {
+            return;
+        }
+
+        // a signal ends the throttling, the task is checked again on its next tick
+        match task.blocker.BlockWithMonoTimeout(false, Some(wait)).1 {
+            Err(Error::SysError(SysErr::ETIMEDOUT)) | Ok(()) => (),
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_TokenBucket() {
+        let rate = 1000;
+        let mut bucket = TokenBucket::New();
+
+        // a full burst is available at first and a bigger request goes into debt
+        assert_eq!(bucket.Wait(SECOND, rate, 500), 0);
+        bucket.Take(SECOND, rate, 500, 1500);
+        assert_eq!(bucket.tokens, -1000);
+
+        // 1001 tokens at 1000/s are needed to get back to a positive count
+        assert_eq!(bucket.Wait(SECOND, rate, 500), 1_001_000_000);
+        assert_eq!(bucket.Wait(SECOND + 500_000_000, rate, 500), 501_000_000);
+        assert_eq!(bucket.Wait(2 * SECOND + 1_000_000, rate, 500), 0);
+
+        // the bucket doesn't fill past the burst
+        assert_eq!(bucket.Wait(100 * SECOND, rate, 500), 0);
+        assert_eq!(bucket.tokens, 500);
+    }
+
+    #[test]
+    fn test_CpuPeriod() {
+        let quota = 50 * MILLISECOND;
+        let period = 100 * MILLISECOND;
+        let mut p = CpuPeriod::New();
+
+        p.Charge(SECOND, quota, period, 0);
+        p.Charge(SECOND + 10 * MILLISECOND, quota, period, 40 * MILLISECOND);
+        assert_eq!(p.Wait(SECOND + 40 * MILLISECOND, quota, period), 0);
+
+        // the quota is used up, wait until the next period
+        p.Charge(SECOND + 60 * MILLISECOND, quota, period, 30 * MILLISECOND);
+        assert_eq!(
+            p.Wait(SECOND + 60 * MILLISECOND, quota, period),
+            40 * MILLISECOND
+        );
+
+        // a second task parked in the same period doesn't add to the throttled time
+        assert_eq!(
+            p.Wait(SECOND + 70 * MILLISECOND, quota, period),
+            30 * MILLISECOND
+        );
+        assert_eq!(p.throttledTime, 40 * MILLISECOND);
+
+        // the overrun of 20ms is carried to the next period
+        assert_eq!(p.Wait(SECOND + 100 * MILLISECOND, quota, period), 0);
+        assert_eq!(p.used, 20 * MILLISECOND);
+
+        // idle periods pay the overrun back but leave no quota over
+        p.Charge(SECOND + 150 * MILLISECOND, quota, period, 60 * MILLISECOND);
+        assert_eq!(p.Wait(SECOND + 450 * MILLISECOND, quota, period), 0);
+        assert_eq!(p.start, SECOND + 400 * MILLISECOND);
+        assert_eq!(p.used, 0);
+        assert_eq!((p.periods, p.throttled), (2, 1));
+    }
+}
@@ -60,6 +60,10 @@ pub fn GetVDSOParamPageAddr() -> u64 {
     return TIME_KEEPER.read().params.GetParamPageAddr();
 }
 
+pub fn BumpVDSORngGeneration() {
+    TIME_KEEPER.read().params.BumpRngGeneration();
+}
+
 pub fn RealNow() -> i64 {
     return TIME_KEEPER.GetTime(REALTIME).expect("RealNow fail");
 }
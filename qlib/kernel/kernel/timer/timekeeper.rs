@@ -22,6 +22,7 @@ use super::super::super::super::common::*;
 use super::super::super::super::linux::time::*;
 use super::super::super::kernel::time::*;
 //use super::super::super::super::perf_tunning::*;
+use super::super::super::vcpu::*;
 use super::super::vdso::*;
 use super::calibratedClock::*;
 use super::timer::Clock;
@@ -126,6 +127,8 @@ impl Default for TimeKeeperInternal {
 impl TimeKeeperInternal {
     pub fn Init(&mut self, vdsoParamPageAddr: u64) {
         self.params.SetParamPageAddr(vdsoParamPageAddr);
+        self.params.SetGetcpuReady(HasRdtscp());
+        self.params.SetRngReady(true);
 
         // Compute the offset of the monotonic clock from the base Clocks.
         //
@@ -14,17 +14,20 @@
 
 use crate::qlib::mutex::*;
 use alloc::string::String;
-use alloc::string::ToString;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ops::Deref;
 
 use super::super::super::auth::userns::*;
+use super::namespace::*;
 
 #[derive(Default)]
 pub struct UTSNamespaceInternal {
-    pub hostName: String,
-    pub domainName: String,
+    // the names are the raw bytes given to sethostname and setdomainname
+    pub hostName: Vec<u8>,
+    pub domainName: Vec<u8>,
     pub userns: UserNameSpace,
+    pub ino: u64,
 }
 
 #[derive(Clone, Default)]
@@ -41,27 +44,28 @@ impl Deref for UTSNamespace {
 impl UTSNamespace {
     pub fn New(hostName: String, domainName: String, userns: UserNameSpace) -> Self {
         let internal = UTSNamespaceInternal {
-            hostName: hostName,
-            domainName: domainName,
+            hostName: hostName.into_bytes(),
+            domainName: domainName.into_bytes(),
             userns: userns,
+            ino: NewNsIno(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
     }
 
-    pub fn HostName(&self) -> String {
-        return self.lock().hostName.to_string();
+    pub fn HostName(&self) -> Vec<u8> {
+        return self.lock().hostName.clone();
     }
 
-    pub fn SetHostName(&self, host: String) {
+    pub fn SetHostName(&self, host: Vec<u8>) {
         self.lock().hostName = host;
     }
 
-    pub fn DomainName(&self) -> String {
-        return self.lock().domainName.to_string();
+    pub fn DomainName(&self) -> Vec<u8> {
+        return self.lock().domainName.clone();
     }
 
-    pub fn SetDomainName(&self, domain: String) {
+    pub fn SetDomainName(&self, domain: Vec<u8>) {
         self.lock().domainName = domain;
     }
 
@@ -72,9 +76,10 @@ impl UTSNamespace {
     pub fn Fork(&self, userns: &UserNameSpace) -> Self {
         let me = self.lock();
         let internal = UTSNamespaceInternal {
-            hostName: me.hostName.to_string(),
-            domainName: me.domainName.to_string(),
+            hostName: me.hostName.clone(),
+            domainName: me.domainName.clone(),
             userns: userns.clone(),
+            ino: NewNsIno(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use core::mem;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 
 use super::super::super::common::*;
 use super::super::asm::*;
@@ -34,6 +36,14 @@ pub struct VdsoParams {
     pub realtimeBaseCycles: i64,
     pub realtimeBaseRef: i64,
     pub realtimeFrequency: u64,
+
+    // getcpuReady is set if the vcpus have their id in IA32_TSC_AUX.
+    pub getcpuReady: u64,
+
+    // rngReady enables the vDSO getrandom, which reseeds the per thread states
+    // once rngGeneration changes.
+    pub rngReady: u64,
+    pub rngGeneration: u64,
 }
 
 impl VdsoParams {
@@ -150,6 +160,23 @@ impl VDSOParamPage {
         return self.paramPageAddr;
     }
 
+    pub fn SetGetcpuReady(&mut self, ready: bool) {
+        self.vdsoParams.getcpuReady = ready as u64;
+    }
+
+    pub fn SetRngReady(&mut self, ready: bool) {
+        self.vdsoParams.rngReady = ready as u64;
+    }
+
+    // BumpRngGeneration makes the vDSO getrandom states of all the processes
+    // reseed, it is called on fork as the child has a copy of the parent's states.
+    pub fn BumpRngGeneration(&self) {
+        let generation = &self.vdsoParams.rngGeneration as *const u64 as *const AtomicU64;
+        unsafe {
+            (*generation).fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
     fn IncrementSeq(&mut self) -> Result<()> {
         let next = self.seq + 1;
 
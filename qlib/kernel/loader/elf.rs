@@ -29,9 +29,12 @@ use super::super::super::linux_def::*;
 use super::super::super::platform::defs_impl::*;
 use super::super::arch::x86_64::context::*;
 use super::super::fs::file::*;
+use super::super::memmgr::vma::*;
 use super::super::memmgr::*;
 use super::super::task::*;
 use super::super::util::cstring::*;
+use super::super::SHARESPACE;
+use super::elf_cache::*;
 
 pub const ELF_MAGIC: &str = "\x7fELF";
 pub const INTERPRETER_SCRIPT_MAGIC: &str = "#!";
@@ -43,6 +46,7 @@ pub type Arch = i32;
 pub const AMD64: Arch = 0;
 
 // elfInfo contains the metadata needed to load an ELF binary.
+#[derive(Clone)]
 pub struct ElfHeadersInfo {
     // os is the target OS of the ELF.
     pub os: OS,
@@ -67,6 +71,9 @@ pub struct ElfHeadersInfo {
 
     // sharedObject is true if the ELF represents a shared object.
     pub sharedObject: bool,
+
+    // interpreter is the PT_INTERP path, None until LoadParseElf has read it.
+    pub interpreter: Option<String>,
 }
 
 // parseHeader parse the ELF header, verifying that this is a supported ELF
@@ -140,9 +147,62 @@ pub fn ParseHeader(task: &mut Task, file: &File) -> Result<ElfHeadersInfo> {
         phdrNum: phdrNum as usize,
         phdrs: phdrs,
         sharedObject: isSharedObject,
+        interpreter: None,
+    });
+}
+
+pub struct ParsedElf {
+    pub info: ElfHeadersInfo,
+    // image is the cached segment image of the binary
+    pub image: Option<ElfImage>,
+    // key is the exec cache key of a binary which has to be added to the cache
+    // once it is loaded
+    pub key: Option<ElfCacheKey>,
+    pub fileSize: i64,
+}
+
+// ParseElf returns the headers of file from the exec cache, or parses them.
+pub fn ParseElf(task: &mut Task, file: &File) -> Result<ParsedElf> {
+    let uattr = file.UnstableAttr(task)?;
+    let key = ElfCacheKey::New(&file.Dirent.Inode().StableAttr(), &uattr);
+    if let Some(key) = &key {
+        if let Some((info, image)) = ELF_CACHE.lock().Lookup(key) {
+            return Ok(ParsedElf {
+                info: info,
+                image: image,
+                key: None,
+                fileSize: uattr.Size,
+            });
+        }
+    }
+
+    return Ok(ParsedElf {
+        info: ParseHeader(task, file)?,
+        image: None,
+        key: key,
+        fileSize: uattr.Size,
     });
 }
 
+// CacheElf adds a loaded binary to the exec cache
+pub fn CacheElf(task: &mut Task, file: &File, parsed: &ParsedElf) {
+    let key = match parsed.key {
+        None => return,
+        Some(k) => k,
+    };
+
+    // without an image, only the headers are cached
+    let image = match ElfImage::New(task, file, &parsed.info) {
+        Err(e) => {
+            info!("ELF segment image fail {:?}", e);
+            None
+        }
+        Ok(image) => Some(image),
+    };
+
+    ELF_CACHE.lock().Insert(key, &parsed.info, image);
+}
+
 pub fn PHFlagsAsPerms(header: &ProgramHeader64) -> AccessType {
     let flags = header.flags;
     let mut perms = 0;
@@ -162,18 +222,29 @@ pub fn PHFlagsAsPerms(header: &ProgramHeader64) -> AccessType {
     return AccessType(perms);
 }
 
+// SegmentPages returns the range of the pages of the segment mapped from the file
+pub fn SegmentPages(header: &ProgramHeader64) -> Result<(u64, u64)> {
+    let startMem = Addr(header.virtual_addr).RoundDown()?;
+    let endMem = Addr(header.virtual_addr)
+        .AddLen(header.file_size)?
+        .RoundUp()?;
+    return Ok((startMem.0, endMem.0));
+}
+
+// MapSegment maps the segment of header from file, or from the pages at
+// imageOffset of the cached image of file
 pub fn MapSegment(
     task: &Task,
     file: &File,
     header: &ProgramHeader64,
     offset: u64,
     filesize: i64,
+    image: Option<(&ElfImage, u64)>,
 ) -> Result<()> {
     let size = header.file_size;
-    let startMem = Addr(header.virtual_addr).RoundDown()?;
-    let endMem = Addr(header.virtual_addr)
-        .AddLen(header.file_size)?
-        .RoundUp()?;
+    let (startMem, endMem) = SegmentPages(header)?;
+    let startMem = Addr(startMem);
+    let endMem = Addr(endMem);
 
     let fileOffset = Addr(header.offset).RoundDown()?;
     //info!("MapSegment fileoffset is {:x}, size is {:x}, filesize is {:x}", fileOffset.0, size, filesize);
@@ -194,13 +265,19 @@ pub fn MapSegment(
         moptions.MaxPerms = AccessType::AnyAccess();
         moptions.Private = true;
         moptions.Offset = fileOffset.0;
+        if let Some((image, imageOffset)) = image {
+            // the file stays the identity of the mapping
+            moptions.Mappable = MMappable::FromHostIops(image.iops.clone());
+            moptions.Offset = imageOffset;
+        }
 
         task.mm.MMap(task, &mut moptions)?
     };
 
     let adjust = header.virtual_addr - startMem.0;
 
-    if adjust + header.file_size < endMem.0 - startMem.0 {
+    // the tail of the last page is already zeroed in an image
+    if image.is_none() && adjust + header.file_size < endMem.0 - startMem.0 {
         let cnt = (endMem.0 - startMem.0 - (adjust + header.file_size)) as usize;
         let buf: [u8; 4096] = [0; 4096];
         let vaddr = addr + adjust + header.file_size;
@@ -300,7 +377,7 @@ pub fn ReadAll(task: &mut Task, file: &File, data: &mut [u8], offset: u64) -> Re
 pub fn LoadParseElf(
     task: &mut Task,
     file: &File,
-    info: &mut ElfHeadersInfo,
+    parsed: &mut ParsedElf,
     sharedLoadOffset: u64,
 ) -> Result<LoadedElf> {
     let mut first = true;
@@ -308,11 +385,17 @@ pub fn LoadParseElf(
     let mut end = 0;
     let mut interpreter = "".to_string();
 
-    let filesize = file.UnstableAttr(task)?.Size;
+    let filesize = parsed.fileSize;
+    let info = &mut parsed.info;
     for header in &info.phdrs {
         let headerType = header.get_type().map_err(Error::ELFLoadError)?;
         match headerType {
             Type::Interp => {
+                if let Some(cached) = &info.interpreter {
+                    interpreter = cached.clone();
+                    continue;
+                }
+
                 if header.file_size < 2 {
                     info!("Error: PT_INTERP path too small");
                     return Err(Error::SysError(SysErr::ENOEXEC));
@@ -388,10 +471,15 @@ pub fn LoadParseElf(
         }
     }
 
+    if interpreter.len() > 0 {
+        info.interpreter = Some(interpreter.clone());
+    }
+
     // Shared objects don't have fixed load addresses. We need to pick a
     // base address big enough to fit all segments, so we first create a
     // mapping for the total size just to find a region that is big enough.
     let mut offset = 0;
+    let mut entry = info.entry;
     if info.sharedObject {
         let totalSize = match Addr(end - start).RoundUp() {
             Err(_) => {
@@ -411,11 +499,11 @@ pub fn LoadParseElf(
 
         start += offset;
         end += offset;
-        info.entry += offset;
+        entry += offset;
     }
 
     // Map PT_LOAD segments.
-    for header in &info.phdrs {
+    for (i, header) in info.phdrs.iter().enumerate() {
         let headerType = header.get_type().map_err(Error::ELFLoadError)?;
         if headerType == Type::Load {
             if header.mem_size == 0 {
@@ -424,7 +512,8 @@ pub fn LoadParseElf(
                 continue;
             }
 
-            match MapSegment(task, file, header, offset, filesize) {
+            let image = parsed.image.as_ref().map(|image| (image, image.offsets[i]));
+            match MapSegment(task, file, header, offset, filesize, image) {
                 Err(e) => {
                     info!("Failed to map PT_LOAD segment: {:?}", e);
                     return Err(Error::SysError(SysErr::ENOEXEC));
@@ -448,7 +537,7 @@ pub fn LoadParseElf(
     return Ok(LoadedElf {
         os: info.os,
         arch: info.arch,
-        entry: info.entry,
+        entry: entry,
         start: start,
         end: end,
         interpreter: interpreter,
@@ -461,16 +550,23 @@ pub fn LoadParseElf(
 
 // loadInitialELF loads f into mm.
 pub fn LoadInitalElf(task: &mut Task, file: &File) -> Result<LoadedElf> {
-    let mut info = ParseHeader(task, file)?;
-
-    let l = task
-        .mm
-        .SetMmapLayout(MIN_USER_ADDR, MAX_USER_ADDR, &LimitSet::default())?;
+    let mut parsed = ParseElf(task, file)?;
+
+    let personality = task.Thread().Personality();
+    let randomize =
+        personality & Personality::ADDR_NO_RANDOMIZE == 0 && !SHARESPACE.config.read().DisableASLR;
+    let l = task.mm.SetMmapLayout(
+        MIN_USER_ADDR,
+        MAX_USER_ADDR,
+        &LimitSet::default(),
+        randomize,
+    )?;
     *task.mm.layout.lock() = l;
 
-    let loadAddr = Context64::PIELoadAddress(&l)?;
+    let loadAddr = Context64::PIELoadAddress(&l, randomize)?;
 
-    let le = LoadParseElf(task, file, &mut info, loadAddr)?;
+    let le = LoadParseElf(task, file, &mut parsed, loadAddr)?;
+    CacheElf(task, file, &parsed);
     return Ok(le);
 }
 
@@ -483,7 +579,7 @@ pub fn LoadInitalElf(task: &mut Task, file: &File) -> Result<LoadedElf> {
 // Preconditions:
 //  * f is an ELF file
 pub fn loadInterpreterELF(task: &mut Task, file: &File, initial: &LoadedElf) -> Result<LoadedElf> {
-    let mut info = match ParseHeader(task, file) {
+    let mut parsed = match ParseElf(task, file) {
         Err(e) => {
             if e == Error::SysError(SysErr::ENOEXEC) {
                 return Err(Error::SysError(SysErr::ELIBBAD));
@@ -494,25 +590,27 @@ pub fn loadInterpreterELF(task: &mut Task, file: &File, initial: &LoadedElf) ->
         Ok(i) => i,
     };
 
-    if info.os != initial.os {
+    if parsed.info.os != initial.os {
         info!(
             "Initial ELF OS {} and interpreter ELF OS {} differ",
-            initial.os, info.os
+            initial.os, parsed.info.os
         );
         return Err(Error::SysError(SysErr::ELIBBAD));
     }
 
-    if info.arch != initial.arch {
+    if parsed.info.arch != initial.arch {
         info!(
             "Initial ELF arch {} and interpreter ELF arch {} differ",
-            initial.arch, info.arch
+            initial.arch, parsed.info.arch
         );
         return Err(Error::SysError(SysErr::ELIBBAD));
     }
 
     // The interpreter is not given a load offset, as its location does not
     // affect brk.
-    return LoadParseElf(task, file, &mut info, 0);
+    let le = LoadParseElf(task, file, &mut parsed, 0)?;
+    CacheElf(task, file, &parsed);
+    return Ok(le);
 }
 
 pub fn LoadElf(task: &mut Task, file: &File) -> Result<LoadedElf> {
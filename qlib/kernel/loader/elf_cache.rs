@@ -0,0 +1,256 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The exec cache keeps the parsed ELF headers, the PT_INTERP path and the loaded
+// segments of the recently exec'ed binaries, so that a build fanning out the same
+// tool doesn't read and parse its headers nor prepare its segments on every exec.
+// An entry is only used while the mtime and the size of the binary are the ones
+// it was parsed from.
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
+use xmas_elf::program::Type;
+
+use crate::qlib::mutex::*;
+
+use super::super::super::addr::*;
+use super::super::super::common::*;
+use super::super::super::linux_def::*;
+use super::super::fd::*;
+use super::super::fs::attr::*;
+use super::super::fs::file::*;
+use super::super::fs::host::hostinodeop::*;
+use super::super::task::*;
+use super::elf::*;
+
+// ELF_CACHE_SIZE is the number of binaries the exec cache holds.
+pub const ELF_CACHE_SIZE: usize = 128;
+
+pub static ELF_CACHE: QMutex<ElfCache> = QMutex::new(ElfCache::New());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElfCacheKey {
+    pub deviceId: u64,
+    pub inodeId: u64,
+    pub mtime: i64,
+    pub size: i64,
+}
+
+impl ElfCacheKey {
+    // New returns the key of a file with the attributes, None if it isn't a
+    // regular file.
+    pub fn New(sattr: &StableAttr, uattr: &UnstableAttr) -> Option<Self> {
+        if !sattr.IsRegular() {
+            return None;
+        }
+
+        return Some(Self {
+            deviceId: sattr.DeviceId,
+            inodeId: sattr.InodeId,
+            mtime: uattr.ModificationTime.0,
+            size: uattr.Size,
+        });
+    }
+}
+
+// ElfImage holds the pages of the PT_LOAD segments of a binary in a memfd, as
+// MapSegment maps them from the file, with the tail of the last page of each
+// segment zeroed. The segments of a cached binary are mapped private from it,
+// so its processes share the pages copy-on-write and the exec neither reads
+// nor zeroes anything.
+#[derive(Clone)]
+pub struct ElfImage {
+    pub iops: HostInodeOp,
+    // offsets[i] is the offset of the pages of the segment of phdrs[i] in iops
+    pub offsets: Vec<u64>,
+}
+
+// ELF_IMAGE_CHUNK is the size of the reads which copy a segment into an image.
+const ELF_IMAGE_CHUNK: usize = 64 * 1024;
+
+impl ElfImage {
+    pub fn New(task: &mut Task, file: &File, info: &ElfHeadersInfo) -> Result<Self> {
+        let mut offsets = Vec::with_capacity(info.phdrs.len());
+        let mut size = 0;
+        for header in &info.phdrs {
+            offsets.push(size);
+            if header.get_type() == Ok(Type::Load) && header.mem_size != 0 {
+                let (startMem, endMem) = SegmentPages(header)?;
+                size += endMem - startMem;
+            }
+        }
+
+        let iops = HostInodeOp::NewMemfdIops(size as i64)?;
+        let mut buf = vec![0; ELF_IMAGE_CHUNK];
+        for (i, header) in info.phdrs.iter().enumerate() {
+            if header.get_type() != Ok(Type::Load) || header.mem_size == 0 {
+                continue;
+            }
+
+            let (startMem, endMem) = SegmentPages(header)?;
+            let fileOffset = Addr(header.offset).RoundDown()?.0;
+            // the bytes from dataEnd on are zeroed, see MapSegment
+            let dataEnd = header.virtual_addr - startMem + header.file_size;
+            let mut off = 0;
+            while off < endMem - startMem {
+                let len = core::cmp::min(ELF_IMAGE_CHUNK as u64, endMem - startMem - off) as usize;
+                let data = &mut buf[..len];
+                data.fill(0);
+                if off < dataEnd {
+                    let n = core::cmp::min(len as u64, dataEnd - off) as usize;
+                    ReadAll(task, file, &mut data[..n], fileOffset + off)?;
+                }
+
+                let mut written = 0;
+                while written < len {
+                    let iovs = [IoVec::NewFromSlice(&data[written..])];
+                    let n = IOWriteAt(iops.HostFd(), &iovs, offsets[i] + off + written as u64)?;
+                    if n <= 0 {
+                        return Err(Error::SysError(SysErr::EIO));
+                    }
+                    written += n as usize;
+                }
+
+                off += len as u64;
+            }
+        }
+
+        return Ok(Self {
+            iops: iops,
+            offsets: offsets,
+        });
+    }
+}
+
+struct ElfCacheEntry {
+    key: ElfCacheKey,
+    info: ElfHeadersInfo,
+    image: Option<ElfImage>,
+    lastUse: u64,
+}
+
+pub struct ElfCache {
+    // entries is keyed by (deviceId, inodeId)
+    entries: BTreeMap<(u64, u64), ElfCacheEntry>,
+    clock: u64,
+}
+
+impl ElfCache {
+    pub const fn New() -> Self {
+        return Self {
+            entries: BTreeMap::new(),
+            clock: 0,
+        };
+    }
+
+    // Lookup returns the headers and the segment image cached for key. A stale
+    // entry of the same inode is dropped.
+    pub fn Lookup(&mut self, key: &ElfCacheKey) -> Option<(ElfHeadersInfo, Option<ElfImage>)> {
+        let id = (key.deviceId, key.inodeId);
+        match self.entries.get_mut(&id) {
+            None => return None,
+            Some(e) if e.key == *key => {
+                self.clock += 1;
+                e.lastUse = self.clock;
+                return Some((e.info.clone(), e.image.clone()));
+            }
+            Some(_) => (),
+        }
+
+        self.entries.remove(&id);
+        return None;
+    }
+
+    pub fn Insert(&mut self, key: ElfCacheKey, info: &ElfHeadersInfo, image: Option<ElfImage>) {
+        let id = (key.deviceId, key.inodeId);
+        if !self.entries.contains_key(&id) && self.entries.len() >= ELF_CACHE_SIZE {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.lastUse)
+                .map(|(id, _)| *id);
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+
+        self.clock += 1;
+        self.entries.insert(
+            id,
+            ElfCacheEntry {
+                key: key,
+                info: info.clone(),
+                image: image,
+                lastUse: self.clock,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn headers(entry: u64) -> ElfHeadersInfo {
+        return ElfHeadersInfo {
+            os: LINUX_OS,
+            arch: AMD64,
+            entry: entry,
+            phdrAddr: 0x40,
+            phdrSize: 56,
+            phdrNum: 0,
+            phdrs: Vec::new(),
+            sharedObject: true,
+            interpreter: Some("/lib64/ld-linux-x86-64.so.2".to_string()),
+        };
+    }
+
+    fn key(inodeId: u64, mtime: i64) -> ElfCacheKey {
+        return ElfCacheKey {
+            deviceId: 1,
+            inodeId: inodeId,
+            mtime: mtime,
+            size: 4096,
+        };
+    }
+
+    #[test]
+    fn test_ElfCacheStale() {
+        let mut cache = ElfCache::New();
+        cache.Insert(key(1, 100), &headers(0x1000), None);
+        assert_eq!(cache.Lookup(&key(1, 100)).unwrap().0.entry, 0x1000);
+
+        // a rewritten binary misses and drops the old headers
+        assert!(cache.Lookup(&key(1, 200)).is_none());
+        assert!(cache.Lookup(&key(1, 100)).is_none());
+    }
+
+    #[test]
+    fn test_ElfCacheEvictsLRU() {
+        let mut cache = ElfCache::New();
+        for i in 0..ELF_CACHE_SIZE as u64 {
+            cache.Insert(key(i, 0), &headers(i), None);
+        }
+
+        // inode 0 is used again, so inode 1 is the least recently used
+        assert!(cache.Lookup(&key(0, 0)).is_some());
+        cache.Insert(key(1000, 0), &headers(1000), None);
+        assert!(cache.Lookup(&key(0, 0)).is_some());
+        assert!(cache.Lookup(&key(1, 0)).is_none());
+        assert!(cache.Lookup(&key(1000, 0)).is_some());
+    }
+}
@@ -19,6 +19,7 @@ use alloc::vec::Vec;
 use super::super::super::addr::*;
 use super::super::super::auxv::*;
 use super::super::super::common::*;
+use super::super::super::cpuid::*;
 use super::super::super::linux_def::*;
 use super::super::super::path::*;
 use super::super::super::range::*;
@@ -308,6 +309,18 @@ pub fn Load(
     return Ok((entry, usersp, kernelsp));
 }
 
+// MinSigStackSize is the size of the largest signal frame, like Linux's
+// init_sigframe_size(): the rt_sigframe, the extended state with its trailing
+// magic and their alignment padding.
+pub fn MinSigStackSize() -> u64 {
+    const RT_SIGFRAME_SIZE: u64 = 440;
+    const FRAME_PADDING: u64 = 2 * 64;
+
+    let (xsave, _) = HostFeatureSet().ExtendedStateSize();
+    let size = RT_SIGFRAME_SIZE + FRAME_PADDING + xsave as u64 + Task::FP_XSTATE_MAGIC2_SIZE as u64;
+    return (size + 15) & !15;
+}
+
 //return: user stack sp
 pub fn SetupUserStack(
     task: &Task,
@@ -329,6 +342,16 @@ pub fn SetupUserStack(
 
     let execfn = stack.PushStr(task, argv[0].as_str())?;
 
+    let creds = task.Thread().Credentials();
+    let userns = creds.lock().UserNamespace.clone();
+    let uid = creds.lock().RealKUID.In(&userns).OrOverflow().0;
+    let euid = creds.lock().EffectiveKUID.In(&userns).OrOverflow().0;
+    let gid = creds.lock().RealKGID.In(&userns).OrOverflow().0;
+    let egid = creds.lock().EffectiveKGID.In(&userns).OrOverflow().0;
+
+    // AT_HWCAP is the CPUID.1:EDX feature word on x86
+    let (_, _, _, hwcap) = HostID(1, 0);
+
     /*auxv vector*/
     let mut auxv = Vec::new();
     auxv.push(AuxEntry {
@@ -351,25 +374,28 @@ pub fn SetupUserStack(
         Key: AuxVec::AT_RANDOM,
         Val: randAddr,
     });
-    auxv.push(AuxEntry {
-        Key: AuxVec::AT_SECURE,
-        Val: 0,
-    });
+    // an exec passes AT_SECURE in extraAuxv, see updateCredsForExecLocked
+    if !extraAuxv.iter().any(|e| e.Key == AuxVec::AT_SECURE) {
+        auxv.push(AuxEntry {
+            Key: AuxVec::AT_SECURE,
+            Val: 0,
+        });
+    }
     auxv.push(AuxEntry {
         Key: AuxVec::AT_EGID,
-        Val: 0,
+        Val: egid as u64,
     });
     auxv.push(AuxEntry {
         Key: AuxVec::AT_GID,
-        Val: 0,
+        Val: gid as u64,
     });
     auxv.push(AuxEntry {
         Key: AuxVec::AT_EUID,
-        Val: 0,
+        Val: euid as u64,
     });
     auxv.push(AuxEntry {
         Key: AuxVec::AT_UID,
-        Val: 0,
+        Val: uid as u64,
     });
     auxv.push(AuxEntry {
         Key: AuxVec::AT_FLAGS,
@@ -385,7 +411,11 @@ pub fn SetupUserStack(
     });
     auxv.push(AuxEntry {
         Key: AuxVec::AT_HWCAP,
-        Val: 0xbfebfbff,
+        Val: hwcap as u64,
+    });
+    auxv.push(AuxEntry {
+        Key: AuxVec::AT_MINSIGSTKSZ,
+        Val: MinSigStackSize(),
     });
     auxv.push(AuxEntry {
         Key: AuxVec::AT_SYSINFO_EHDR,
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 pub mod elf;
+pub mod elf_cache;
 pub mod interpreter;
 pub mod loader;
 pub mod vdso;
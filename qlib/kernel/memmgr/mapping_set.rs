@@ -330,6 +330,31 @@ impl AreaSet<MappingsOfRange> {
         return ranges;
     }
 
+    // MappingsWithOffset returns the mappings of offsets in mr, each with the offset
+    // its AddrRange starts at.
+    pub fn MappingsWithOffset(&self, mr: &Range) -> Vec<(MappingOfRange, u64)> {
+        let mut ranges = Vec::new();
+        let mut seg = self.LowerBoundSeg(mr.Start());
+        while seg.Ok() && seg.Range().Start() < mr.End() {
+            let segMR = seg.Range();
+            let subset = segMR.Intersect(mr);
+            for m in seg.Value().lock().iter() {
+                let region = SubsetMapping(
+                    &segMR,
+                    &subset,
+                    &m.MappingSpace,
+                    m.AddrRange.Start(),
+                    m.Writeable,
+                );
+                ranges.push((region, subset.Start()));
+            }
+
+            seg = seg.NextSeg();
+        }
+
+        return ranges;
+    }
+
     // InvalidateAll calls MappingSpace.Invalidate for all mappings of s.
     pub fn InvalidateAll(&mut self, task: &Task, invalidatePrivate: bool) {
         let mut seg = self.FirstSeg();
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::sync::Arc;
@@ -29,6 +30,7 @@ use crate::qlib::mutex::*;
 use super::super::super::addr::*;
 use super::super::super::auxv::*;
 use super::super::super::common::*;
+use super::super::super::control_msg::MemStatMapping;
 use super::super::super::limits::*;
 use super::super::super::linux_def::*;
 use super::super::super::mem::areaset::*;
@@ -49,6 +51,7 @@ use super::super::PAGE_MGR;
 //use super::super::asm::*;
 use super::arch::*;
 use super::metadata::*;
+use super::overcommit::*;
 use super::syscalls::*;
 use super::vma::*;
 use super::*;
@@ -229,6 +232,7 @@ impl MemoryManager {
             id: None,
             numaPolicy: 0,
             numaNodemask: 0,
+            rss: Arc::new(VmaRss::default()),
         };
 
         let gap = vmas.FindGap(MemoryDef::PHY_LOWER_ADDR);
@@ -418,6 +422,9 @@ impl MemoryManager {
                 vma.mappable
                     .RemoveMapping(self, &r, vma.offset, vma.CanWriteMappableLocked())?;
             }
+            if vma.Accountable() {
+                VmUnacct(r.Len());
+            }
             let vgap = mapping.vmas.Remove(&vseg);
             vseg = vgap.NextSeg();
         }
@@ -455,6 +462,7 @@ impl MemoryManager {
 
                 pt.pt.MUnmap(r.Start(), r.Len())?;
                 pt.curRSS -= r.Len();
+                vma.rss.Reset(0, 0);
             }
             //let vgap = mapping.vmas.Remove(&vseg);
             vseg = vgap.NextSeg();
@@ -484,6 +492,9 @@ impl MemoryManager {
                 if vma.mlockMode != MLockMode::MlockNone {
                     mapping.lockedAS -= r.Len();
                 }
+                if vma.Accountable() {
+                    VmUnacct(r.Len());
+                }
 
                 let mut pt = self.pagetable.write();
 
@@ -531,6 +542,38 @@ impl MemoryManager {
         }
     }
 
+    // InvalidateFilePages unmaps the pages in ar which still map the file pages, filePage
+    // returns the address of the file page at an offset in ar. The next access faults
+    // them in again. Other pages, e.g. the copies of a private mapping, are kept.
+    pub fn InvalidateFilePages(&self, ar: &Range, filePage: &dyn Fn(u64) -> Option<u64>) {
+        let _ml = self.MappingWriteLock();
+        let mut unmapped = false;
+        {
+            let mapping = self.mapping.lock();
+            let mut pt = self.pagetable.write();
+            let mut addr = ar.Start();
+            while addr < ar.End() {
+                match pt.pt.VirtualToPhy(addr) {
+                    Ok((phyAddr, _)) if Some(phyAddr) == filePage(addr - ar.Start()) => {
+                        pt.pt.MUnmap(addr, MemoryDef::PAGE_SIZE).unwrap();
+                        pt.curRSS -= MemoryDef::PAGE_SIZE;
+                        let vseg = mapping.vmas.FindSeg(addr);
+                        if vseg.Ok() {
+                            vseg.Value().rss.Unmap(phyAddr);
+                        }
+                        unmapped = true;
+                    }
+                    _ => (),
+                }
+                addr += MemoryDef::PAGE_SIZE;
+            }
+        }
+
+        if unmapped {
+            self.TlbShootdown();
+        }
+    }
+
     pub fn AddRssLock(&self, ar: &Range) {
         let mut pt = self.pagetable.write();
         pt.curRSS += ar.Len();
@@ -677,24 +720,162 @@ impl MemoryManager {
         return ret.as_bytes().to_vec();
     }
 
+    // ResidentBytes is the size of the user pages mapped in the page table, which the
+    // oom killer ranks processes by. It's counted as the pages get mapped, so it
+    // doesn't walk the page table.
+    pub fn ResidentBytes(&self) -> u64 {
+        return self.pagetable.read().pt.MappedPages() * MemoryDef::PAGE_SIZE;
+    }
+
+    // MemStatSnapshot returns the per vma memory usage from the vma rss counts,
+    // only the stale ones are recounted from the page table. A page swapped out
+    // by hibernation is still mapped and counts as resident.
+    pub fn MemStatSnapshot(&self, task: &Task) -> Vec<MemStatMapping> {
+        let _ml = self.MappingReadLock();
+        let mapping = self.mapping.lock();
+        let pt = self.pagetable.read();
+
+        let mut ret = Vec::new();
+        let mut vseg = mapping.vmas.FirstSeg();
+        while vseg.Ok() {
+            let vma = vseg.Value();
+            if vma.kernel {
+                vseg = vseg.NextSeg();
+                continue;
+            }
+
+            let r = vseg.Range();
+            let name = if vma.hint.len() != 0 {
+                vma.hint.to_string()
+            } else {
+                match &vma.id {
+                    None => "".to_string(),
+                    Some(ref id) => id.MappedName(task),
+                }
+            };
+
+            let mut stat = MemStatMapping {
+                start: r.Start(),
+                end: r.End(),
+                perms: vma.realPerms.String() + if vma.private { "p" } else { "s" },
+                name: name,
+                ..Default::default()
+            };
+
+            let (resident, file) = match vma.rss.Get() {
+                Some(counts) => counts,
+                None => {
+                    let mut resident = 0;
+                    let mut file = 0;
+                    let mut addr = r.Start();
+                    while addr < r.End() {
+                        if let Ok((phyAddr, _)) = pt.pt.VirtualToPhy(addr) {
+                            resident += MemoryDef::PAGE_SIZE;
+                            if VmaRss::IsFilePage(phyAddr) {
+                                file += MemoryDef::PAGE_SIZE;
+                            }
+                        }
+                        addr += MemoryDef::PAGE_SIZE;
+                    }
+
+                    vma.rss.Reset(resident, file);
+                    (resident, file)
+                }
+            };
+
+            stat.resident = resident;
+            stat.file = file;
+            stat.anon = resident - file;
+            ret.push(stat);
+            vseg = vseg.NextSeg();
+        }
+
+        return ret;
+    }
+
+    // CoreDumpVmas returns the user mappings with the number of bytes a core dump
+    // writes from their start. Like Linux's default coredump_filter, these are the
+    // anonymous and written private memory plus the ELF header of file mappings.
+    pub fn CoreDumpVmas(&self) -> Vec<(Range, AccessType, u64)> {
+        let _ml = self.MappingReadLock();
+        let mapping = self.mapping.lock();
+
+        let mut ret = Vec::new();
+        let mut vseg = mapping.vmas.FirstSeg();
+        while vseg.Ok() {
+            let vma = vseg.Value();
+            let r = vseg.Range();
+            if !vma.kernel {
+                let dumpLen = if !vma.realPerms.Read() {
+                    0
+                } else if vma.id.is_none() || (vma.private && vma.realPerms.Write()) {
+                    r.Len()
+                } else if vma.offset == 0 {
+                    MemoryDef::PAGE_SIZE
+                } else {
+                    0
+                };
+
+                ret.push((r, vma.realPerms, dumpLen));
+            }
+
+            vseg = vseg.NextSeg();
+        }
+
+        return ret;
+    }
+
+    // ReadResidentPage copies the page at addr to buf if it is in the page table,
+    // the page is not faulted in otherwise.
+    pub fn ReadResidentPage(&self, addr: u64, buf: &mut [u8]) -> bool {
+        let _ml = self.MappingReadLock();
+        let pt = self.pagetable.read();
+        match pt.pt.ResidentEntry(addr) {
+            None => return false,
+            Some((phyAddr, _)) => {
+                let len = core::cmp::min(buf.len(), MemoryDef::PAGE_SIZE as usize);
+                unsafe {
+                    core::ptr::copy_nonoverlapping(phyAddr as *const u8, buf.as_mut_ptr(), len);
+                }
+                return true;
+            }
+        }
+    }
+
     pub fn SetExecutable(&self, dirent: &Dirent) {
         self.metadata.lock().executable = Some(dirent.clone());
     }
 
-    pub fn MinCore(&self, _task: &Task, r: &Range) -> Vec<u8> {
+    pub fn MinCore(&self, _task: &Task, r: &Range) -> Result<Vec<u8>> {
         let _ml = self.MappingReadLock();
 
+        // "ENOMEM addr to addr + length contained unmapped memory." - mincore(2)
+        {
+            let mapping = self.mapping.lock();
+            let mut addr = r.Start();
+            while addr < r.End() {
+                let vseg = mapping.vmas.FindSeg(addr);
+                if !vseg.Ok() {
+                    return Err(Error::SysError(SysErr::ENOMEM));
+                }
+
+                addr = vseg.Range().End();
+            }
+        }
+
+        let pagetable = self.pagetable.read();
         let mut res = Vec::with_capacity((r.Len() / MemoryDef::PAGE_SIZE) as usize);
         let mut addr = r.Start();
         while addr < r.End() {
-            match self.VirtualToPhyLocked(addr) {
-                Ok(_) => res.push(1),
-                Err(_) => res.push(0),
+            if pagetable.pt.IsResident(addr) {
+                res.push(1);
+            } else {
+                res.push(0);
             }
             addr += MemoryDef::PAGE_SIZE;
         }
 
-        return res;
+        return Ok(res);
     }
 
     pub fn mlockedBytesRangeLocked(&self, mr: &Range) -> u64 {
@@ -713,6 +894,27 @@ impl MemoryManager {
         return total;
     }
 
+    // MlockedPhyPages adds the resident physical pages backing mlocked vmas to
+    // pages, so that they can be kept out of swap.
+    pub fn MlockedPhyPages(&self, pages: &mut BTreeSet<u64>) {
+        let mapping = self.mapping.lock();
+        let pt = self.pagetable.read();
+        let mut vseg = mapping.vmas.FirstSeg();
+        while vseg.Ok() {
+            if vseg.Value().mlockMode != MLockMode::MlockNone {
+                let r = vseg.Range();
+                let mut addr = r.Start();
+                while addr < r.End() {
+                    if let Some(phyAddr) = pt.pt.ResidentPhyAddr(addr) {
+                        pages.insert(phyAddr);
+                    }
+                    addr += MemoryDef::PAGE_SIZE;
+                }
+            }
+            vseg = vseg.NextSeg();
+        }
+    }
+
     // MLock implements the semantics of Linux's mlock()/mlock2()/munlock(),
     // depending on mode.
     pub fn Mlock(&self, task: &Task, addr: u64, len: u64, mode: MLockMode) -> Result<()> {
@@ -788,9 +990,6 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::ENOMEM));
         }
 
-        // todo: populate the pagetable
-        // if mode == MLockMode::MlockEager {}
-
         let mut vseg = mapping.vmas.FindSeg(ar.Start());
         while vseg.Ok() && vseg.Range().Start() < ar.End() {
             let vma = vseg.Value();
@@ -800,6 +999,18 @@ impl MemoryManager {
                 return Err(Error::SysError(SysErr::ENOMEM));
             }
 
+            // Fault in the locked range now so that it is resident, and so kept
+            // out of swap by MlockedPhyPages.
+            if mode == MLockMode::MlockEager {
+                let vr = vseg.Range();
+                let mr = ar.Intersect(&vr);
+                let mut addr = mr.Start();
+                while addr < mr.End() {
+                    self.InstallPageLocked(task, &vma, addr, &vr)?;
+                    addr += MemoryDef::PAGE_SIZE;
+                }
+            }
+
             if let Some(iops) = vma.mappable.HostIops() {
                 let mr = ar.Intersect(&vseg.Range());
                 let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
@@ -961,8 +1172,9 @@ impl MemoryManager {
         minUserAddr: u64,
         maxUserAddr: u64,
         r: &LimitSet,
+        randomize: bool,
     ) -> Result<MmapLayout> {
-        let layout = Context64::NewMmapLayout(minUserAddr, maxUserAddr, r)?;
+        let layout = Context64::NewMmapLayout(minUserAddr, maxUserAddr, r, randomize)?;
         *self.layout.lock() = layout;
         return Ok(layout);
     }
@@ -1016,19 +1228,22 @@ impl MemoryManager {
             Some(data) => data,
         };
 
-        return self.InstallPageLocked(task, &vma, pageAddr, &range);
+        self.InstallPageLocked(task, &vma, pageAddr, &range)?;
+        return Ok(());
     }
 
+    // InstallPageLocked maps the page at pageAddr in, it returns true if the page
+    // had to be mapped from the host, see Task::AccountPageFault.
     pub fn InstallPageLocked(
         &self,
         task: &Task,
         vma: &VMA,
         pageAddr: u64,
         range: &Range,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         match self.VirtualToPhyLocked(pageAddr) {
             Err(_) => (),
-            Ok(_) => return Ok(()),
+            Ok(_) => return Ok(false),
         }
 
         if !vma.effectivePerms.Any() {
@@ -1040,7 +1255,7 @@ impl MemoryManager {
             Some(iops) => {
                 let vmaOffset = pageAddr - range.Start();
                 let fileOffset = vmaOffset + vma.offset; // offset in the file
-                let phyAddr = iops.MapFilePage(task, fileOffset)?;
+                let (phyAddr, major) = iops.MapFilePage(task, fileOffset)?;
                 //error!("fault 2.1, vma.mappable.is_some() is {}, vaddr is {:x}, paddr is {:x}",
                 //      vma.mappable.is_some(), pageAddr, phyAddr);
 
@@ -1051,21 +1266,21 @@ impl MemoryManager {
                     if writeable {
                         let page = { super::super::PAGE_MGR.AllocPage(true).unwrap() };
                         CopyPage(page, phyAddr);
-                        self.MapPageWriteLocked(pageAddr, page, exec);
+                        self.MapPageWriteLocked(vma, pageAddr, page, exec);
                         super::super::PAGE_MGR.DerefPage(page);
                     } else {
-                        self.MapPageReadLocked(pageAddr, phyAddr, exec);
+                        self.MapPageReadLocked(vma, pageAddr, phyAddr, exec);
                     }
                 } else {
                     let writeable = vma.effectivePerms.Write();
                     if writeable {
-                        self.MapPageWriteLocked(pageAddr, phyAddr, exec);
+                        self.MapPageWriteLocked(vma, pageAddr, phyAddr, exec);
                     } else {
-                        self.MapPageReadLocked(pageAddr, phyAddr, exec);
+                        self.MapPageReadLocked(vma, pageAddr, phyAddr, exec);
                     }
                 }
 
-                return Ok(());
+                return Ok(major);
             }
             None => {
                 // for mmappable socket
@@ -1078,12 +1293,12 @@ impl MemoryManager {
                         let phyAddr = phyAddr + fileOffset;
                         let writeable = vma.effectivePerms.Write();
                         if writeable {
-                            self.MapPageWriteLocked(pageAddr, phyAddr, exec);
+                            self.MapPageWriteLocked(vma, pageAddr, phyAddr, exec);
                         } else {
-                            self.MapPageWriteLocked(pageAddr, phyAddr, exec);
+                            self.MapPageWriteLocked(vma, pageAddr, phyAddr, exec);
                         }
 
-                        return Ok(());
+                        return Ok(false);
                     }
                     None => (),
                 }
@@ -1094,39 +1309,38 @@ impl MemoryManager {
                 let phyAddr = super::super::PAGE_MGR.AllocPage(true).unwrap();
                 let writeable = vma.effectivePerms.Write();
                 if writeable {
-                    self.MapPageWriteLocked(pageAddr, phyAddr, exec);
+                    self.MapPageWriteLocked(vma, pageAddr, phyAddr, exec);
                 } else {
-                    self.MapPageReadLocked(pageAddr, phyAddr, exec);
+                    self.MapPageReadLocked(vma, pageAddr, phyAddr, exec);
                 }
 
                 super::super::PAGE_MGR.DerefPage(phyAddr);
-                return Ok(());
+                return Ok(false);
             }
         }
     }
 
-    pub fn MapPageWriteLocked(&self, vAddr: u64, pAddr: u64, exec: bool) {
-        let pt = self.pagetable.write();
-        pt.pt
-            .MapPage(
-                Addr(vAddr),
-                Addr(pAddr),
-                PageOpts::New(true, true, exec).Val(),
-                &*PAGE_MGR,
-            )
-            .unwrap();
+    pub fn MapPageWriteLocked(&self, vma: &VMA, vAddr: u64, pAddr: u64, exec: bool) {
+        self.MapVmaPageLocked(vma, vAddr, pAddr, PageOpts::New(true, true, exec).Val());
     }
 
-    pub fn MapPageReadLocked(&self, vAddr: u64, pAddr: u64, exec: bool) {
+    pub fn MapPageReadLocked(&self, vma: &VMA, vAddr: u64, pAddr: u64, exec: bool) {
+        self.MapVmaPageLocked(vma, vAddr, pAddr, PageOpts::New(true, false, exec).Val());
+    }
+
+    // MapVmaPageLocked maps the page and counts it in the rss of vma. The count is
+    // updated under the page table lock so that a recount neither misses nor
+    // doubles it.
+    fn MapVmaPageLocked(&self, vma: &VMA, vAddr: u64, pAddr: u64, flags: PageTableFlags) {
         let pt = self.pagetable.write();
+        if let Ok((oldAddr, _)) = pt.pt.VirtualToPhy(vAddr) {
+            vma.rss.Unmap(oldAddr);
+        }
+
         pt.pt
-            .MapPage(
-                Addr(vAddr),
-                Addr(pAddr),
-                PageOpts::New(true, false, exec).Val(),
-                &*PAGE_MGR,
-            )
+            .MapPage(Addr(vAddr), Addr(pAddr), flags, &*PAGE_MGR)
             .unwrap();
+        vma.rss.Map(pAddr);
     }
 
     pub fn EnableWriteLocked(&self, addr: u64, exec: bool) {
@@ -1149,7 +1363,7 @@ impl MemoryManager {
         let exec = vma.effectivePerms.Exec();
         let page = { super::super::PAGE_MGR.AllocPage(false).unwrap() };
         CopyPage(page, phyAddr);
-        self.MapPageWriteLocked(pageAddr, page, exec);
+        self.MapPageWriteLocked(vma, pageAddr, page, exec);
     }
 
     pub fn CopyOnWrite(&self, pageAddr: u64, vma: &VMA) {
@@ -1160,6 +1374,53 @@ impl MemoryManager {
         //PerfGofrom(PerfType::PageFault);
     }
 
+    // CopyDataOutForce writes to the memory of a traced process like Linux's
+    // FOLL_FORCE: a private page which isn't writable, e.g. program text, is
+    // replaced by a private copy before the write.
+    pub fn CopyDataOutForce(&self, task: &Task, from: u64, vaddr: u64, len: usize) -> Result<()> {
+        let rl = self.MappingReadLock();
+
+        let mut offset = 0;
+        while offset < len as u64 {
+            let addr = vaddr + offset;
+            let pageOffset = addr & (MemoryDef::PAGE_SIZE - 1);
+            let n = core::cmp::min(len as u64 - offset, MemoryDef::PAGE_SIZE - pageOffset);
+            self.FixPermissionLocked(task, &rl, addr, n, false, false)?;
+
+            let (vma, _) = match self.GetVmaAndRangeLocked(addr) {
+                None => return Err(Error::SysError(SysErr::EFAULT)),
+                Some(data) => data,
+            };
+
+            let (mut phyAddr, permission) = self.VirtualToPhyLocked(addr)?;
+            if !permission.Write() {
+                if !vma.private {
+                    return Err(Error::SysError(SysErr::EFAULT));
+                }
+
+                rl.Upgrade();
+                let page = { super::super::PAGE_MGR.AllocPage(false).unwrap() };
+                CopyPage(page, phyAddr - pageOffset);
+                self.MapPageReadLocked(&vma, addr - pageOffset, page, vma.effectivePerms.Exec());
+                self.TlbShootdown();
+                rl.Downgrade();
+                phyAddr = page + pageOffset;
+            }
+
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    (from + offset) as *const u8,
+                    phyAddr as *mut u8,
+                    n as usize,
+                );
+            }
+
+            offset += n;
+        }
+
+        return Ok(());
+    }
+
     pub fn V2P(
         &self,
         task: &Task,
@@ -1428,6 +1689,7 @@ impl MemoryManager {
                         &perms,
                         true,
                     )?;
+                    vma.rss.MarkStale();
                 }
             }
             Some(iops) => {
@@ -1450,11 +1712,24 @@ impl MemoryManager {
                         &currPerm,
                         precommit,
                     )?;
+                    vma.rss.MarkStale();
                 }
                 self.AddRssLock(ar);
             }
         }
 
+        // MAP_POPULATE faults the pages in now. It's best effort as in Linux, a page
+        // which can't be installed is left to the page fault.
+        if precommit && !vdso && vma.effectivePerms.Any() {
+            let mut addr = ar.Start();
+            while addr < ar.End() {
+                if self.InstallPageLocked(task, &vma, addr, &segAr).is_err() {
+                    break;
+                }
+                addr += MemoryDef::PAGE_SIZE;
+            }
+        }
+
         return Ok(());
     }
 
@@ -1569,6 +1844,8 @@ impl MemoryManager {
                 }
 
                 vma.mlockMode = MLockMode::MlockNone;
+                // the child maps the same pages but counts them on its own
+                vma.rss = vma.rss.Copy();
 
                 if vma.kernel == false {
                     //info!("vma kernel is {}, private is {}, hint is {}", vma.kernel, vma.private, vma.hint);
@@ -1590,6 +1867,11 @@ impl MemoryManager {
                     }
                 }
 
+                // on failure, dropping mm2 uncharges the vmas inserted so far
+                if vma.Accountable() {
+                    VmEnoughMemory(vmaAR.Len())?;
+                }
+
                 dstvgap = mappingInternal2
                     .vmas
                     .Insert(&dstvgap, &vmaAR, vma)
@@ -18,6 +18,7 @@ pub mod mapping_set;
 pub mod memmap;
 pub mod metadata;
 pub mod mm;
+pub mod overcommit;
 pub mod pma;
 pub mod pmamgr;
 pub mod syscalls;
@@ -0,0 +1,153 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use crate::qlib::mutex::*;
+
+use super::super::super::common::*;
+use super::super::super::config::OvercommitMode;
+use super::super::super::linux_def::*;
+use super::super::kernel::kernel::*;
+use super::super::threadmgr::thread_group::*;
+use super::super::SignalDef::*;
+use super::super::PAGE_MGR;
+use super::super::SHARESPACE;
+
+// COMMITTED_AS is the size of the private writable mappings of all processes, the
+// commit charge the Strict overcommit mode holds against the memory limit.
+pub static COMMITTED_AS: AtomicU64 = AtomicU64::new(0);
+
+// the range of /proc/[pid]/oom_score_adj, OOM_SCORE_ADJ_MIN disables the oom
+// killing of a process
+pub const OOM_SCORE_ADJ_MIN: i32 = -1000;
+pub const OOM_SCORE_ADJ_MAX: i32 = 1000;
+
+// OOM_LIMIT_PAGES is the memory limit of the Heuristic mode in pages, u64::MAX in
+// the other modes and without a MemoryLimit, where the oom killer is off. It is set
+// up by InitOvercommit, so that the page faults don't take the config lock.
+static OOM_LIMIT_PAGES: AtomicU64 = AtomicU64::new(u64::MAX);
+
+// OOM_VICTIM is the last process the oom killer sent SIGKILL to.
+static OOM_VICTIM: QMutex<Option<ThreadGroupWeak>> = QMutex::new(None);
+
+// VmEnoughMemory charges len bytes of new private writable mappings, like Linux's
+// security_vm_enough_memory_mm(). Only the Strict mode fails it.
+pub fn VmEnoughMemory(len: u64) -> Result<()> {
+    let committed = COMMITTED_AS.fetch_add(len, Ordering::SeqCst) + len;
+
+    let config = SHARESPACE.config.read();
+    if config.Overcommit == OvercommitMode::Strict && committed > config.MemoryLimitBytes() {
+        COMMITTED_AS.fetch_sub(len, Ordering::SeqCst);
+        return Err(Error::SysError(SysErr::ENOMEM));
+    }
+
+    return Ok(());
+}
+
+pub fn VmUnacct(len: u64) {
+    COMMITTED_AS.fetch_sub(len, Ordering::SeqCst);
+}
+
+pub fn InitOvercommit() {
+    let config = SHARESPACE.config.read();
+    if config.Overcommit == OvercommitMode::Heuristic && config.MemoryLimit != 0 {
+        let limit = config.MemoryLimitBytes() / MemoryDef::PAGE_SIZE;
+        OOM_LIMIT_PAGES.store(limit, Ordering::Relaxed);
+    }
+}
+
+// OOMCheck is called on user page faults before a page gets allocated. In the
+// Heuristic mode, the oom killer kills a process once the pages allocated from the
+// page pool are over the memory limit. A page shared by several processes or
+// mappings is allocated once, the host file mappings and the kernel heap don't
+// come from the pool.
+pub fn OOMCheck() {
+    let limit = OOM_LIMIT_PAGES.load(Ordering::Relaxed);
+    let used = PAGE_MGR.UsedPages();
+    if used > limit {
+        OOMKill(used * MemoryDef::PAGE_SIZE, limit * MemoryDef::PAGE_SIZE);
+    }
+}
+
+fn OOMKill(used: u64, limit: u64) {
+    let mut victim = OOM_VICTIM.lock();
+
+    // the last victim frees its memory once it exits, don't kill another one
+    // before it has started to
+    if let Some(tg) = victim.as_ref().and_then(|v| v.Upgrade()) {
+        if !tg.lock().exiting {
+            return;
+        }
+    }
+
+    let kernel = match GetKernelOption() {
+        None => return,
+        Some(k) => k,
+    };
+
+    let root = kernel.TaskSet().Root();
+    let mut selected = None;
+    let mut maxScore = 0;
+    for tg in root.ThreadGroups() {
+        let adj = {
+            let tg = tg.lock();
+            if tg.exiting || tg.oomScoreAdj == OOM_SCORE_ADJ_MIN {
+                continue;
+            }
+            tg.oomScoreAdj
+        };
+
+        let leader = match tg.Leader() {
+            None => continue,
+            Some(t) => t,
+        };
+
+        let resident = leader.MemoryManager().ResidentBytes();
+        let score = Badness(resident, adj, limit);
+        if selected.is_none() || score > maxScore {
+            selected = Some((tg, leader));
+            maxScore = score;
+        }
+    }
+
+    let (tg, leader) = match selected {
+        None => return,
+        Some(s) => s,
+    };
+
+    error!(
+        "oom killer: {} KB in use over the limit of {} KB, kill process {} ({}) with badness {} KB",
+        used / 1024,
+        limit / 1024,
+        root.IDOfThreadGroup(&tg),
+        leader.Name(),
+        maxScore / 1024
+    );
+
+    let info = SignalInfo {
+        Signo: Signal::SIGKILL,
+        ..Default::default()
+    };
+    tg.SendSignal(&info).ok();
+    *victim = Some(tg.Downgrade());
+}
+
+// Badness is the oom score of a process, like Linux's oom_badness(): its resident
+// bytes, moved by adj thousandths of the memory limit.
+pub fn Badness(resident: u64, adj: i32, limit: u64) -> i64 {
+    let adj = adj as i64 * (limit / 1000) as i64;
+    return (resident as i64).saturating_add(adj);
+}
@@ -113,6 +113,10 @@ impl PageMgr {
         return self.pagepool.FreePage(addr);
     }
 
+    pub fn UsedPages(&self) -> u64 {
+        return self.pagepool.UsedPages();
+    }
+
     pub fn VsyscallPages(&self) -> Arc<Vec<u64>> {
         let pages = {
             let mut pages = self.vsyscallPages.lock();
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::u64;
 
@@ -23,6 +24,7 @@ use super::super::super::linux_def::*;
 use super::super::super::range::*;
 use super::super::kernel::futex::*;
 use super::super::memmgr::mm::*;
+use super::super::memmgr::overcommit::*;
 use super::super::memmgr::vma::*;
 use super::super::task::*;
 use super::*;
@@ -85,6 +87,16 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
+        // MAP_FIXED_NOREPLACE fails on any existing mapping in the range
+        if opts.Fixed && !opts.Unmap {
+            if let Ok(r) = Addr(opts.Addr).ToRange(opts.Length) {
+                let vgap = self.mapping.lock().vmas.FindGap(r.Start());
+                if !vgap.Ok() || !vgap.Range().IsSupersetOf(&r) {
+                    return Err(Error::SysError(SysErr::EEXIST));
+                }
+            }
+        }
+
         if opts.GrowsDown && opts.Mappable.HostIops().is_some() {
             return Err(Error::SysError(SysErr::EINVAL));
         }
@@ -101,12 +113,8 @@ impl MemoryManager {
     pub fn MapStack(&self, task: &Task) -> Result<Range> {
         let _ml = self.MappingWriteLock();
 
-        // maxStackSize is the maximum supported process stack size in bytes.
-        //
-        // This limit exists because stack growing isn't implemented, so the entire
-        // process stack must be mapped up-front.
-        const MAX_STACK_SIZE: u64 = 128 << 20; //128 MB
-
+        // The initial stack is mapped up to DEFAULT_STACK_SOFT_LIMIT. Beyond that it
+        // grows on demand up to RLIMIT_STACK, see GrowStackLocked.
         let lim = task
             .Thread()
             .ThreadGroup()
@@ -171,6 +179,55 @@ impl MemoryManager {
         return Ok(ar);
     }
 
+    // GrowStackLocked extends the growsDown vma right above addr down to the page
+    // containing addr, as long as the vma stays within RLIMIT_STACK and a guard gap
+    // is kept above the vma below it. It returns the grown vma and its range.
+    //
+    // Preconditions: mm.MappingWriteLock must be locked.
+    pub fn GrowStackLocked(&self, task: &Task, addr: u64) -> Option<(VMA, Range)> {
+        let start = Addr(addr).RoundDown().ok()?.0;
+
+        let mut mapping = self.mapping.lock();
+        let gap = mapping.vmas.FindGap(start);
+        if !gap.Ok() {
+            return None;
+        }
+
+        let next = gap.NextSeg();
+        if !next.Ok() || !next.Value().growsDown {
+            return None;
+        }
+
+        let lim = task
+            .Thread()
+            .ThreadGroup()
+            .Limits()
+            .Get(LimitType::Stack)
+            .Cur;
+        if next.Range().End() - start > lim {
+            return None;
+        }
+
+        let prev = gap.PrevSeg();
+        if prev.Ok() && prev.Range().End() + GUARD_BYTES > start {
+            return None;
+        }
+
+        let vma = next.Value();
+        let ar = Range::New(start, next.Range().Start() - start);
+        if vma.Accountable() && VmEnoughMemory(ar.Len()).is_err() {
+            return None;
+        }
+
+        mapping.usageAS += ar.Len();
+        if vma.mlockMode != MLockMode::MlockNone {
+            mapping.lockedAS += ar.Len();
+        }
+
+        let vseg = mapping.vmas.Insert(&gap, &ar, vma);
+        return Some((vseg.Value(), vseg.Range()));
+    }
+
     // MUnmap implements the semantics of Linux's munmap(2).
     pub fn MUnmap(&self, _task: &Task, addr: u64, length: u64) -> Result<()> {
         let _ml = self.MappingWriteLock();
@@ -384,6 +441,12 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
+        // a copy, or a move which grows the mapping, adds to the commit charge
+        let grow = newAR.Len().saturating_sub(oldAR.Len());
+        if vma.Accountable() && grow > 0 {
+            VmEnoughMemory(grow)?;
+        }
+
         // Inform the Mappable, if any, of the new mapping.
         let offsetat = vseg.MappableOffsetAt(oldAR.Start());
         vma.mappable
@@ -400,6 +463,7 @@ impl MemoryManager {
             if vma.mappable.HostIops().is_some() {
                 vma.offset = vseg.MappableOffsetAt(oldAR.Start());
             }
+            vma.rss = Arc::new(VmaRss::default());
 
             let gap = self.mapping.lock().vmas.FindGap(newAR.Start());
             let vseg = self.mapping.lock().vmas.Insert(&gap, &newAR, vma);
@@ -508,9 +572,18 @@ impl MemoryManager {
             vseg = mapping.vmas.Isolate(&vseg, &ar);
             // Update vma permissions.
             let mut vma = vseg.Value();
+            let accountable = vma.Accountable();
             vma.realPerms = *realPerms;
             vma.effectivePerms = effectivePerms;
 
+            // unlike Linux, which keeps the charge of a mapping which has been
+            // writable, the charge follows the write permission
+            if !accountable && vma.Accountable() {
+                VmEnoughMemory(vseg.Range().Len())?;
+            } else if accountable && !vma.Accountable() {
+                VmUnacct(vseg.Range().Len());
+            }
+
             vseg.SetValue(vma);
             let range = vseg.Range();
 
@@ -815,6 +888,10 @@ impl MemoryManager {
         return self.mapping.lock().usageAS;
     }
 
+    pub fn LockedMemorySizeLocked(&self) -> u64 {
+        return self.mapping.lock().lockedAS;
+    }
+
     pub fn VirtualMemorySize(&self) -> u64 {
         let _ml = self.MappingReadLock();
         return self.VirtualMemorySizeLocked();
@@ -16,6 +16,9 @@ use alloc::string::String;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use core::fmt;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 
 use super::super::super::addr::*;
 use super::super::super::common::*;
@@ -29,6 +32,7 @@ use super::super::kernel::shm::*;
 use super::super::task::*;
 use super::arch::*;
 use super::mm::*;
+use super::overcommit::*;
 use super::*;
 use crate::qlib::bytestream::*;
 
@@ -62,7 +66,8 @@ impl MemoryManager {
         let mut gap = mapping.vmas.LowerBoundGap(bounds.Start());
 
         while gap.Ok() && gap.Range().Start() < bounds.End() {
-            let gr = gap.Range().Intersect(bounds);
+            // Leave the guard gap below a growsDown vma free so it can still grow.
+            let gr = gap.AvailableRange().Intersect(bounds);
             if gr.Len() > length {
                 // Can we shift up to match the alignment?
                 let offset = gr.Start() % alignment;
@@ -93,7 +98,7 @@ impl MemoryManager {
         let mut gap = mapping.vmas.UpperBoundGap(bounds.End());
 
         while gap.Ok() && gap.Range().End() > bounds.Start() {
-            let gr = gap.Range().Intersect(bounds);
+            let gr = gap.AvailableRange().Intersect(bounds);
             if gr.Len() > length {
                 // Can we shift up to match the alignment?
                 let start = gr.End() - length;
@@ -108,7 +113,7 @@ impl MemoryManager {
                 return Ok(start);
             }
 
-            let tmp = gap.NextGap();
+            let tmp = gap.PrevGap();
             gap = tmp;
         }
 
@@ -299,15 +304,28 @@ impl MemoryManager {
             self.RemoveVMAsLocked(&ar)?;
         }
 
+        let accountable = !opts.Kernel && opts.Private && opts.Perms.Write();
+        if accountable {
+            VmEnoughMemory(opts.Length)?;
+        }
+
         let mut mapping = self.mapping.lock();
         let gap = mapping.vmas.FindGap(ar.Start());
 
-        opts.Mappable.AddMapping(
+        match opts.Mappable.AddMapping(
             self,
             &ar,
             opts.Offset,
             !opts.Private && opts.MaxPerms.Write(),
-        )?;
+        ) {
+            Err(e) => {
+                if accountable {
+                    VmUnacct(opts.Length);
+                }
+                return Err(e);
+            }
+            Ok(()) => (),
+        }
 
         let vma = VMA {
             mappable: opts.Mappable.clone(),
@@ -325,6 +343,7 @@ impl MemoryManager {
             id: opts.Mapping.clone(),
             numaPolicy: 0,
             numaNodemask: 0,
+            rss: Arc::new(VmaRss::default()),
         };
 
         mapping.usageAS += opts.Length;
@@ -534,6 +553,107 @@ pub struct VMA {
 
     // numaNodemask is the NUMA nodemask for this vma set by mbind().
     pub numaNodemask: u64,
+
+    pub rss: Arc<VmaRss>,
+}
+
+// VmaRss counts the pages mapped in a vma as they are mapped and unmapped, so that
+// the memory stats don't have to walk the page table. When the counts can't be
+// carried over, e.g. after the vma is split, they are stale and the next
+// MemStatSnapshot recounts them from the page table.
+#[derive(Debug, Default)]
+pub struct VmaRss {
+    pub resident: AtomicU64,
+    // the part of resident which maps host file pages
+    pub file: AtomicU64,
+    pub stale: AtomicBool,
+}
+
+impl VmaRss {
+    pub fn Stale() -> Arc<Self> {
+        let rss = Self::default();
+        rss.stale.store(true, Ordering::Relaxed);
+        return Arc::new(rss);
+    }
+
+    pub fn IsFilePage(phyAddr: u64) -> bool {
+        return MemoryDef::FILE_MAP_OFFSET <= phyAddr
+            && phyAddr < MemoryDef::FILE_MAP_OFFSET + MemoryDef::FILE_MAP_SIZE;
+    }
+
+    pub fn IsStale(&self) -> bool {
+        return self.stale.load(Ordering::Relaxed);
+    }
+
+    pub fn MarkStale(&self) {
+        self.stale.store(true, Ordering::Relaxed);
+    }
+
+    // Map and Unmap are called under the page table write lock, a stale count is
+    // left to the recount.
+    pub fn Map(&self, phyAddr: u64) {
+        if self.IsStale() {
+            return;
+        }
+
+        self.resident
+            .fetch_add(MemoryDef::PAGE_SIZE, Ordering::Relaxed);
+        if Self::IsFilePage(phyAddr) {
+            self.file.fetch_add(MemoryDef::PAGE_SIZE, Ordering::Relaxed);
+        }
+    }
+
+    pub fn Unmap(&self, phyAddr: u64) {
+        if self.IsStale() {
+            return;
+        }
+
+        self.resident
+            .fetch_sub(MemoryDef::PAGE_SIZE, Ordering::Relaxed);
+        if Self::IsFilePage(phyAddr) {
+            self.file.fetch_sub(MemoryDef::PAGE_SIZE, Ordering::Relaxed);
+        }
+    }
+
+    pub fn Reset(&self, resident: u64, file: u64) {
+        self.resident.store(resident, Ordering::Relaxed);
+        self.file.store(file, Ordering::Relaxed);
+        self.stale.store(false, Ordering::Relaxed);
+    }
+
+    // Get returns (resident, file), or None if the counts are stale
+    pub fn Get(&self) -> Option<(u64, u64)> {
+        if self.IsStale() {
+            return None;
+        }
+
+        return Some((
+            self.resident.load(Ordering::Relaxed),
+            self.file.load(Ordering::Relaxed),
+        ));
+    }
+
+    pub fn Copy(&self) -> Arc<Self> {
+        return match self.Get() {
+            None => Self::Stale(),
+            Some((resident, file)) => {
+                let rss = Self::default();
+                rss.Reset(resident, file);
+                Arc::new(rss)
+            }
+        };
+    }
+
+    pub fn Merge(&self, other: &Self) -> Arc<Self> {
+        return match (self.Get(), other.Get()) {
+            (Some((r1, f1)), Some((r2, f2))) => {
+                let rss = Self::default();
+                rss.Reset(r1 + r2, f1 + f2);
+                Arc::new(rss)
+            }
+            _ => Self::Stale(),
+        };
+    }
 }
 
 impl fmt::Debug for VMA {
@@ -569,6 +689,7 @@ impl VMA {
             id: self.id.clone(),
             numaPolicy: 0,
             numaNodemask: 0,
+            rss: self.rss.Copy(),
         };
 
         return copy;
@@ -584,6 +705,12 @@ impl VMA {
     pub fn CanWriteMappableLocked(&self) -> bool {
         !self.private && self.maxPerms.Write()
     }
+
+    // Accountable returns whether the vma counts in the overcommit charge, like
+    // Linux's VM_ACCOUNT, see overcommit::VmEnoughMemory.
+    pub fn Accountable(&self) -> bool {
+        return !self.kernel && self.private && self.realPerms.Write();
+    }
 }
 
 impl AreaSeg<VMA> {
@@ -684,15 +811,80 @@ impl AreaValue for VMA {
             return None;
         }
 
-        return Some(vma1.Copy());
+        let mut vma = vma1.Copy();
+        vma.rss = vma1.rss.Merge(&vma2.rss);
+        return Some(vma);
     }
 
     fn Split(&self, r: &Range, split: u64) -> (VMA, VMA) {
         let v = self;
+        let mut v1 = v.clone();
         let mut v2 = v.clone();
 
         v2.offset += split - r.Start();
 
-        return (v.clone(), v2);
+        // the pages of each half are only known from the page table
+        v1.rss = VmaRss::Stale();
+        v2.rss = VmaRss::Stale();
+
+        return (v1, v2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_VmaRssTouchedPages() {
+        let vma = VMA {
+            rss: Arc::new(VmaRss::default()),
+            ..Default::default()
+        };
+
+        // touching 5 anonymous pages and 3 file pages, then dropping one of each
+        for i in 0..5 {
+            vma.rss
+                .Map(MemoryDef::HEAP_OFFSET + i * MemoryDef::PAGE_SIZE);
+        }
+        for i in 0..3 {
+            vma.rss
+                .Map(MemoryDef::FILE_MAP_OFFSET + i * MemoryDef::PAGE_SIZE);
+        }
+        assert_eq!(
+            vma.rss.Get(),
+            Some((8 * MemoryDef::PAGE_SIZE, 3 * MemoryDef::PAGE_SIZE))
+        );
+
+        vma.rss.Unmap(MemoryDef::HEAP_OFFSET);
+        vma.rss.Unmap(MemoryDef::FILE_MAP_OFFSET);
+        assert_eq!(
+            vma.rss.Get(),
+            Some((6 * MemoryDef::PAGE_SIZE, 2 * MemoryDef::PAGE_SIZE))
+        );
+
+        // the halves of a split are recounted, a merge of counted vmas adds up
+        let r = Range::New(0x400000, 0x10000);
+        let (v1, v2) = vma.Split(&r, 0x408000);
+        assert_eq!(v1.rss.Get(), None);
+        assert_eq!(v2.rss.Get(), None);
+        v1.rss.Reset(MemoryDef::PAGE_SIZE, 0);
+        v2.rss.Reset(2 * MemoryDef::PAGE_SIZE, MemoryDef::PAGE_SIZE);
+        let merged = v1
+            .Merge(
+                &Range::New(0x400000, 0x8000),
+                &Range::New(0x408000, 0x8000),
+                &v2,
+            )
+            .unwrap();
+        assert_eq!(
+            merged.rss.Get(),
+            Some((3 * MemoryDef::PAGE_SIZE, MemoryDef::PAGE_SIZE))
+        );
+
+        // a stale count isn't updated until it is recounted
+        v1.rss.MarkStale();
+        v1.rss.Map(MemoryDef::HEAP_OFFSET);
+        assert_eq!(v1.rss.Get(), None);
     }
 }
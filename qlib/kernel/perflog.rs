@@ -15,10 +15,15 @@
 use crate::qlib::mutex::*;
 use alloc::collections::btree_map::BTreeMap;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::mem;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 
+use super::super::control_msg::SyscallStatEntry;
 use super::super::perf_tunning::*;
 use super::super::singleton::*;
+use super::super::SysCallID;
 use super::task::*;
 use super::uid::*;
 
@@ -115,3 +120,174 @@ impl Task {
         }
     }
 }
+
+pub static SYSCALL_STATS: Singleton<SyscallStats> = Singleton::<SyscallStats>::New();
+
+// slots [0, UnknowSyscall) hold the linux syscalls, the quark extension syscalls
+// (sys_socket_produce..EXTENSION_MAX) are packed after them
+pub const SYSCALL_STAT_SIZE: usize = SysCallID::UnknowSyscall as usize
+    + (SysCallID::EXTENSION_MAX as usize - SysCallID::sys_socket_produce as usize);
+
+#[derive(Debug)]
+pub struct SyscallStat {
+    pub count: AtomicU64,
+    pub total: AtomicU64,
+    pub min: AtomicU64,
+    pub max: AtomicU64,
+}
+
+impl Default for SyscallStat {
+    fn default() -> Self {
+        return Self {
+            count: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+        };
+    }
+}
+
+impl SyscallStat {
+    pub fn Record(&self, cycles: u64) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(cycles, Ordering::Relaxed);
+        self.min.fetch_min(cycles, Ordering::Relaxed);
+        self.max.fetch_max(cycles, Ordering::Relaxed);
+    }
+
+    pub fn Reset(&self) {
+        self.count.store(0, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+        self.min.store(u64::MAX, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+    }
+}
+
+// SyscallStats accumulates the per syscall call count and latency, the same as "strace -c"
+#[derive(Debug)]
+pub struct SyscallStats {
+    pub data: Vec<SyscallStat>,
+}
+
+impl Default for SyscallStats {
+    fn default() -> Self {
+        let mut data = Vec::with_capacity(SYSCALL_STAT_SIZE);
+        for _ in 0..SYSCALL_STAT_SIZE {
+            data.push(SyscallStat::default());
+        }
+
+        return Self { data: data };
+    }
+}
+
+impl SyscallStats {
+    pub fn Idx(nr: u64) -> Option<usize> {
+        if nr < SysCallID::UnknowSyscall as u64 {
+            return Some(nr as usize);
+        }
+
+        if SysCallID::sys_socket_produce as u64 <= nr && nr < SysCallID::EXTENSION_MAX as u64 {
+            return Some(
+                SysCallID::UnknowSyscall as usize
+                    + (nr - SysCallID::sys_socket_produce as u64) as usize,
+            );
+        }
+
+        return None;
+    }
+
+    pub fn Nr(idx: usize) -> u64 {
+        if idx < SysCallID::UnknowSyscall as usize {
+            return idx as u64;
+        }
+
+        return (idx - SysCallID::UnknowSyscall as usize) as u64
+            + SysCallID::sys_socket_produce as u64;
+    }
+
+    // cycles is the tsc count used by the syscall
+    pub fn Record(&self, nr: u64, cycles: u64) {
+        match Self::Idx(nr) {
+            None => (),
+            Some(idx) => self.data[idx].Record(cycles),
+        }
+    }
+
+    pub fn Reset(&self) {
+        for stat in &self.data {
+            stat.Reset();
+        }
+    }
+
+    // scale converts the tsc count to the report unit
+    pub fn Snapshot(&self, scale: fn(i64) -> i64) -> Vec<SyscallStatEntry> {
+        let mut ret = Vec::new();
+        for idx in 0..self.data.len() {
+            let stat = &self.data[idx];
+            let count = stat.count.load(Ordering::Relaxed);
+            if count == 0 {
+                continue;
+            }
+
+            let nr = Self::Nr(idx);
+            let callId: SysCallID = unsafe { mem::transmute(nr) };
+            let total = scale(stat.total.load(Ordering::Relaxed) as i64) as u64;
+            ret.push(SyscallStatEntry {
+                nr: nr,
+                name: format!("{:?}", callId),
+                count: count,
+                total: total,
+                min: scale(stat.min.load(Ordering::Relaxed) as i64) as u64,
+                max: scale(stat.max.load(Ordering::Relaxed) as i64) as u64,
+                avg: total / count,
+            })
+        }
+
+        return ret;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn NoScale(val: i64) -> i64 {
+        return val;
+    }
+
+    #[test]
+    fn test_SyscallStats() {
+        let stats = SyscallStats::default();
+        let read = SysCallID::sys_read as u64;
+        let write = SysCallID::sys_write as u64;
+        let proxy = SysCallID::sys_proxy as u64;
+
+        stats.Record(read, 10);
+        stats.Record(read, 30);
+        stats.Record(read, 20);
+        stats.Record(write, 5);
+        stats.Record(proxy, 7);
+        stats.Record(SysCallID::UnknowSyscall as u64, 100);
+
+        let snapshot = stats.Snapshot(NoScale);
+        assert_eq!(snapshot.len(), 3);
+
+        assert_eq!(snapshot[0].nr, read);
+        assert_eq!(snapshot[0].name, "sys_read");
+        assert_eq!(snapshot[0].count, 3);
+        assert_eq!(snapshot[0].total, 60);
+        assert_eq!(snapshot[0].min, 10);
+        assert_eq!(snapshot[0].max, 30);
+        assert_eq!(snapshot[0].avg, 20);
+
+        assert_eq!(snapshot[1].nr, write);
+        assert_eq!(snapshot[1].count, 1);
+
+        assert_eq!(snapshot[2].nr, proxy);
+        assert_eq!(snapshot[2].name, "sys_proxy");
+        assert_eq!(snapshot[2].total, 7);
+
+        stats.Reset();
+        assert_eq!(stats.Snapshot(NoScale).len(), 0);
+    }
+}
@@ -13,11 +13,13 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
 use alloc::collections::vec_deque::VecDeque;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::marker::Send;
 use core::ops::Deref;
+use core::sync::atomic::AtomicI32;
 use core::sync::atomic::Ordering;
 use enum_dispatch::enum_dispatch;
 
@@ -39,6 +41,7 @@ use super::super::kernel::waiter::*;
 use super::super::socket::hostinet::socket::*;
 use super::super::socket::hostinet::uring_socket::*;
 use super::super::task::*;
+use super::super::util::cstring::*;
 use super::super::IOURING;
 use super::super::SHARESPACE;
 use crate::qlib::kernel::kernel::kernel::GetKernel;
@@ -80,6 +83,9 @@ pub enum AsyncOps {
     AsyncSend(AsyncSend),
     PollHostEpollWait(PollHostEpollWait),
     AsyncConnect(AsyncConnect),
+    AsyncCancel(AsyncCancel),
+    AsyncClose(AsyncClose),
+    AsyncFilesUpdate(AsyncFilesUpdate),
     None(AsyncNone),
 }
 
@@ -119,11 +125,25 @@ impl AsyncOps {
             AsyncOps::AsyncSend(_) => return 21,
             AsyncOps::PollHostEpollWait(_) => return 22,
             AsyncOps::AsyncConnect(_) => return 23,
+            AsyncOps::AsyncCancel(_) => return 24,
+            AsyncOps::AsyncClose(_) => return 25,
+            AsyncOps::AsyncFilesUpdate(_) => return 26,
             AsyncOps::None(_) => (),
         };
 
         return 0;
     }
+
+    // the host fd of the pending operation which has to be cancelled before the fd is closed.
+    // The sends hold the socket until they are done, so they never outlive it.
+    pub fn CancelableFd(&self) -> Option<i32> {
+        match self {
+            AsyncOps::AsyncAccept(op) => return Some(op.fd),
+            AsyncOps::AsyncFileRead(op) => return Some(op.fd),
+            AsyncOps::AsyncConnect(op) => return Some(op.fd),
+            _ => return None,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -135,6 +155,12 @@ pub struct UringAsyncMgr {
     // That's weird rust compiler behavior. So we have to store the idx here
     // and wait for qkernel to clear it.
     pub freeids: QMutex<VecDeque<u16>>,
+
+    // the slots of the in-flight cancelable ops of each host fd
+    pub fdOps: QMutex<BTreeMap<i32, Vec<u16>>>,
+    // the slots with a pending AsyncCancel, the value is true once the op has completed.
+    // Such a slot is freed by the cancel so that the cancel can't hit the next op of the slot.
+    pub cancelling: QMutex<BTreeMap<u16, bool>>,
 }
 
 unsafe impl Sync for UringAsyncMgr {}
@@ -152,6 +178,8 @@ impl UringAsyncMgr {
             ops: ops,
             ids: QMutex::new(ids),
             freeids: QMutex::new(VecDeque::new()),
+            fdOps: QMutex::new(BTreeMap::new()),
+            cancelling: QMutex::new(BTreeMap::new()),
         };
     }
 
@@ -178,9 +206,64 @@ impl UringAsyncMgr {
     }
 
     pub fn SetOps(&self, id: usize, ops: AsyncOps) -> squeue::Entry {
+        if let Some(fd) = ops.CancelableFd() {
+            self.fdOps.lock().entry(fd).or_default().push(id as u16);
+        }
         *self.ops[id].lock() = ops;
         return self.ops[id].lock().SEntry().user_data(id as u64);
     }
+
+    pub fn IsCancelling(&self, id: usize) -> bool {
+        return self.cancelling.lock().contains_key(&(id as u16));
+    }
+
+    // OpDone is called when the op of the slot has completed and won't be resubmitted.
+    // It returns false if the slot has to be kept for a pending cancel.
+    pub fn OpDone(&self, id: usize) -> bool {
+        let fd = self.ops[id].lock().CancelableFd();
+        let mut fdOps = self.fdOps.lock();
+        if let Some(fd) = fd {
+            if let Some(ids) = fdOps.get_mut(&fd) {
+                ids.retain(|&x| x as usize != id);
+                if ids.len() == 0 {
+                    fdOps.remove(&fd);
+                }
+            }
+        }
+
+        match self.cancelling.lock().get_mut(&(id as u16)) {
+            None => return true,
+            Some(done) => {
+                *done = true;
+                return false;
+            }
+        }
+    }
+
+    // StartCancel takes the in-flight ops of the host fd and keeps their slots until
+    // the cancels complete
+    pub fn StartCancel(&self, fd: i32) -> Vec<u16> {
+        let mut fdOps = self.fdOps.lock();
+        let ids = match fdOps.remove(&fd) {
+            None => return Vec::new(),
+            Some(ids) => ids,
+        };
+
+        let mut cancelling = self.cancelling.lock();
+        for id in &ids {
+            cancelling.insert(*id, false);
+        }
+
+        return ids;
+    }
+
+    // CancelDone frees the cancelled slot if its op has completed before the cancel
+    pub fn CancelDone(&self, id: usize) {
+        let done = self.cancelling.lock().remove(&(id as u16));
+        if done == Some(true) {
+            self.FreeSlot(id);
+        }
+    }
 }
 
 pub struct AsyncEventfdWrite {
@@ -306,9 +389,103 @@ impl AsyncOpsTrait for AsyncTimerRemove {
     }
 }
 
+pub struct AsyncCancel {
+    pub userData: u64,
+}
+
+impl AsyncCancel {
+    pub fn New(userData: u64) -> Self {
+        return Self { userData: userData };
+    }
+}
+
+impl AsyncOpsTrait for AsyncCancel {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = opcode::AsyncCancel::new(self.userData);
+
+        return op.build();
+    }
+
+    fn Process(&mut self, result: i32) -> bool {
+        // ENOENT: the op has completed before the cancel arrives
+        // EALREADY: the op is running and can't be cancelled, it will complete soon
+        if result < 0 && result != -SysErr::ENOENT && result != -SysErr::EALREADY {
+            error!(
+                "AsyncCancel userdata {} fail with error {}",
+                self.userData, result
+            );
+        }
+
+        IOURING.asyncMgr.CancelDone(self.userData as usize);
+        return false;
+    }
+}
+
+pub struct AsyncClose {
+    pub fd: i32,
+}
+
+impl AsyncClose {
+    pub fn New(fd: i32) -> Self {
+        return Self { fd: fd };
+    }
+}
+
+impl AsyncOpsTrait for AsyncClose {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = opcode::Close::new(types::Fd(self.fd));
+
+        return op.build();
+    }
+
+    fn Process(&mut self, result: i32) -> bool {
+        // the unregister linked before it has failed, the fd still has to be closed
+        if result == -SysErr::ECANCELED {
+            return true;
+        }
+
+        // the fd is already gone from the qvisor's tables, there is nobody to report to
+        if result < 0 {
+            error!("AsyncClose fd {} fail with error {}", self.fd, result);
+        }
+        return false;
+    }
+}
+
+pub struct AsyncFilesUpdate {
+    pub fd: i32,
+    // the new registered file of the slot of fd, it has to live until the completion
+    pub fds: [i32; 1],
+}
+
+impl AsyncFilesUpdate {
+    pub fn New(fd: i32, newfd: i32) -> Self {
+        return Self {
+            fd: fd,
+            fds: [newfd],
+        };
+    }
+}
+
+impl AsyncOpsTrait for AsyncFilesUpdate {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = opcode::FilesUpdate::new(&self.fds[0] as *const _, 1).offset(self.fd);
+
+        return op.build();
+    }
+
+    fn Process(&mut self, result: i32) -> bool {
+        if result < 0 {
+            error!("AsyncFilesUpdate fd {} fail with error {}", self.fd, result);
+        }
+        return false;
+    }
+}
+
 pub struct AsyncStatx {
     pub dirfd: i32,
-    pub pathname: u64,
+    // the name has to live until the completion, the statx can't borrow it
+    pub name: CString,
     pub future: Future<linux_def::Statx>,
     pub flags: i32,
     pub mask: u32,
@@ -319,7 +496,7 @@ pub struct AsyncStatx {
 impl AsyncStatx {
     pub fn New(
         dirfd: i32,
-        pathname: u64,
+        name: &str,
         flags: i32,
         mask: u32,
         future: Future<linux_def::Statx>,
@@ -328,7 +505,7 @@ impl AsyncStatx {
         mw.AddWait();
         return Self {
             dirfd,
-            pathname,
+            name: CString::New(name),
             future,
             flags,
             mask,
@@ -342,7 +519,7 @@ impl AsyncOpsTrait for AsyncStatx {
     fn SEntry(&self) -> squeue::Entry {
         let op = opcode::Statx::new(
             types::Fd(self.dirfd),
-            self.pathname as *const _,
+            self.name.Ptr() as *const _,
             &self.statx as *const _ as u64 as *mut types::statx,
         )
         .flags(self.flags)
@@ -443,6 +620,7 @@ pub struct AsyncBufWrite {
     pub buf: DataBuff,
     pub offset: i64,
     pub lockGuard: Option<QAsyncLockGuard>,
+    pub writebackErr: Arc<AtomicI32>,
 }
 
 impl AsyncOpsTrait for AsyncBufWrite {
@@ -463,25 +641,38 @@ impl AsyncOpsTrait for AsyncBufWrite {
     }
 
     fn Process(&mut self, result: i32) -> bool {
-        assert!(
-            result as usize == self.buf.Len(),
-            "result is {}, self.buf.len() is {}, fd is {}",
-            result,
-            self.buf.Len(),
-            self.fd
-        );
+        // the writer is gone already, keep the error for its next fsync. A short
+        // write lost the rest of the data, so it is an error too.
+        if result < 0 || result as usize != self.buf.Len() {
+            error!(
+                "AsyncBufWrite fail, result is {}, self.buf.len() is {}, fd is {}",
+                result,
+                self.buf.Len(),
+                self.fd
+            );
+            let err = if result < 0 { -result } else { SysErr::EIO };
+            self.writebackErr.store(err, Ordering::SeqCst);
+        }
+
         self.lockGuard = None;
         return false;
     }
 }
 
 impl AsyncBufWrite {
-    pub fn New(fd: i32, buf: DataBuff, offset: i64, lockGuard: QAsyncLockGuard) -> Self {
+    pub fn New(
+        fd: i32,
+        buf: DataBuff,
+        offset: i64,
+        lockGuard: QAsyncLockGuard,
+        writebackErr: Arc<AtomicI32>,
+    ) -> Self {
         return Self {
             fd,
             buf,
             offset,
             lockGuard: Some(lockGuard),
+            writebackErr,
         };
     }
 }
@@ -721,6 +912,11 @@ impl AsyncOpsTrait for AsyncAccept {
     }
 
     fn Process(&mut self, result: i32) -> bool {
+        if result == -SysErr::ECANCELED {
+            // the listening socket has been closed, nobody is waiting for the result
+            return false;
+        }
+
         if result < 0 {
             self.acceptQueue.lock().SetErr(-result);
             self.queue
@@ -742,8 +938,13 @@ impl AsyncOpsTrait for AsyncAccept {
 
         /**************************hibernate wakeu end **************************/
 
-        NewSocket(result);
-        let sockBuf = SocketBuff(Arc::new(SocketBuffIntern::default()));
+        if NewSocket(result) < 0 {
+            // the host has closed the new connection, keep accepting
+            error!("AsyncAccept fail to register accepted fd {}", result);
+            return true;
+        }
+
+        let sockBuf = self.acceptQueue.lock().NewSocketBuff();
         let hasSpace = self.acceptQueue.EnqSocket(
             result,
             self.addr,
@@ -799,6 +1000,11 @@ impl AsyncOpsTrait for AsyncFileRead {
     }
 
     fn Process(&mut self, result: i32) -> bool {
+        if result == -SysErr::ECANCELED {
+            // the file has been closed, nobody is waiting for the result
+            return false;
+        }
+
         if result < 0 {
             self.buf.SetErr(-result);
             self.queue
@@ -1403,6 +1609,11 @@ impl AsyncOpsTrait for AsyncConnect {
     }
 
     fn Process(&mut self, result: i32) -> bool {
+        // the socket is being closed, its state is not touched
+        if result == -SysErr::ECANCELED {
+            return false;
+        }
+
         let socket = match self.socket.Upgrade() {
             None => return false,
             Some(s) => s,
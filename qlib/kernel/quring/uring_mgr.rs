@@ -14,6 +14,7 @@
 
 use alloc::sync::Arc;
 use core::sync::atomic;
+use core::sync::atomic::AtomicI32;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering;
 
@@ -93,6 +94,19 @@ impl IoUring {
         return self.SubmitAndWait(0);
     }
 
+    // Account the new entry and io_uring_enter the host ring right away from this vcpu
+    // instead of waiting for the next host submit point.
+    pub fn SubmitImmediate(&self) -> Result<usize> {
+        self.pendingCnt.fetch_add(1, Ordering::Release);
+
+        let ret = HostSpace::IoUringSubmit();
+        if ret < 0 {
+            return Err(Error::SysError(-ret as i32));
+        }
+
+        return Ok(ret as usize);
+    }
+
     pub fn Enter(&self, to_submit: u32, min_complete: u32, flags: u32) -> Result<usize> {
         let ret = HostSpace::IoUringEnter(to_submit, min_complete, flags);
         if ret < 0 {
@@ -118,6 +132,19 @@ impl IoUring {
 
 pub type IOUringRef = ObjectRef<QUring>;
 
+// How an entry pushed to the submission queue reaches the host kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmitMode {
+    // Leave the entry for the host IO thread or an idle vcpu to submit at its next poll.
+    // One io_uring_enter covers every entry queued in the meantime, which gives the best
+    // throughput but adds up to one poll round of latency to each op.
+    Batched,
+    // Submit the entry right away through a hypercall. A lone latency critical op (e.g.
+    // the fsync on a commit path) doesn't wait for the poller, but every op pays a VM exit
+    // plus its own io_uring_enter, so this is a loss under high op rates.
+    Turbo,
+}
+
 #[derive(Default)]
 pub struct QUring {
     pub uringsAddr: AtomicU64,
@@ -164,6 +191,17 @@ impl QUring {
         return idx;
     }
 
+    // CancelFdOps cancels the in-flight async ops (accept, connect and read) of the host fd.
+    // The host io_uring holds a reference of the file for the pending op, so the fd can't be
+    // released by close until the op completes. It must be called before the host fd is closed
+    // so that the fd number can't be reused by another op.
+    pub fn CancelFdOps(&self, fd: i32) {
+        for idx in self.asyncMgr.StartCancel(fd) {
+            let ops = AsyncCancel::New(idx as u64);
+            self.AUCall(AsyncOps::AsyncCancel(ops));
+        }
+    }
+
     pub fn Timeout(&self, expire: i64, timeout: i64) -> usize {
         let ops = AsyncTimeout::New(expire, timeout);
         let idx = self.AUCall(AsyncOps::AsyncTimeout(ops));
@@ -266,25 +304,25 @@ impl QUring {
     pub fn AsyncStatx(
         &self,
         dirfd: i32,
-        pathname: u64,
+        name: &str,
         flags: i32,
         mask: u32,
         mw: &MultiWait,
     ) -> Future<Statx> {
         let future = Future::New(Statx::default());
-        let ops = AsyncStatx::New(dirfd, pathname, flags, mask, future.clone(), mw);
+        let ops = AsyncStatx::New(dirfd, name, flags, mask, future.clone(), mw);
 
         self.AUCall(AsyncOps::AsyncStatx(ops));
         return future;
     }
 
-    pub fn Fsync(&self, task: &Task, fd: i32, dataSyncOnly: bool) -> i64 {
+    pub fn Fsync(&self, task: &Task, fd: i32, dataSyncOnly: bool, mode: SubmitMode) -> i64 {
         let msg = UringOp::Fsync(FsyncOp {
             fd: fd,
             dataSyncOnly: dataSyncOnly,
         });
 
-        return self.UCall(task, msg);
+        return self.UCallWithMode(task, msg, mode);
     }
 
     pub fn Statx(
@@ -302,11 +340,67 @@ impl QUring {
             statxBuf: statxBuf,
             flags: flags,
             mask: mask,
+            // only the regular files are registered, a dirfd never is
+            fixedFile: SHARESPACE.config.read().UringFixedFile
+                && flags & ATType::AT_EMPTY_PATH != 0,
+        });
+
+        return self.UCall(task, msg);
+    }
+
+    // Fstatx is the statx of a fd which isn't a registered file (yet)
+    pub fn Fstatx(&self, task: &Task, fd: i32, statxBuf: u64, mask: u32) -> i64 {
+        let empty: [u8; 1] = [0];
+        let msg = UringOp::Statx(StatxOp {
+            dirfd: fd,
+            pathname: &empty[0] as *const _ as u64,
+            statxBuf: statxBuf,
+            flags: ATType::AT_EMPTY_PATH,
+            mask: mask,
+            fixedFile: false,
         });
 
         return self.UCall(task, msg);
     }
 
+    // FilesUpdate makes fd the registered file of its own fd number
+    pub fn FilesUpdate(&self, task: &Task, fd: i32) -> i64 {
+        let fds: [i32; 1] = [fd];
+        let msg = UringOp::FilesUpdate(FilesUpdateOp {
+            fd: fd,
+            fds: &fds[0] as *const _ as u64,
+        });
+
+        return self.UCall(task, msg);
+    }
+
+    pub fn OpenAt(&self, task: &Task, dirfd: i32, pathname: u64, flags: i32, mode: u32) -> i64 {
+        let msg = UringOp::OpenAt(OpenAtOp {
+            dirfd: dirfd,
+            pathname: pathname,
+            flags: flags,
+            mode: mode,
+        });
+
+        return self.UCall(task, msg);
+    }
+
+    // AsyncClose closes fd, the registered file slot of fd is emptied before as it
+    // holds the file open too
+    pub fn AsyncClose(&self, fd: i32) {
+        let ops = AsyncClose::New(fd);
+        if !SHARESPACE.config.read().UringFixedFile {
+            self.AUCall(AsyncOps::AsyncClose(ops));
+            return;
+        }
+
+        let unregister = AsyncFilesUpdate::New(fd, -1);
+        self.AUCallLinked(
+            AsyncOps::AsyncFilesUpdate(unregister),
+            AsyncOps::AsyncClose(ops),
+        );
+    }
+
     pub fn AsyncConnect(fd: i32, socket: &UringSocketOperations, sockAddr: &[u8]) -> Result<()> {
         let connectop = AsyncConnect::New(fd, socket, sockAddr);
         IOURING.AUCall(AsyncOps::AsyncConnect(connectop));
@@ -449,9 +543,10 @@ impl QUring {
         buf: DataBuff,
         offset: i64,
         lockGuard: QAsyncLockGuard,
+        writebackErr: Arc<AtomicI32>,
     ) -> i64 {
         let len = buf.Len() as i64;
-        let writeop = AsyncBufWrite::New(fd, buf, offset, lockGuard);
+        let writeop = AsyncBufWrite::New(fd, buf, offset, lockGuard, writebackErr);
 
         IOURING.AUCall(AsyncOps::AsyncBufWrite(writeop));
         return len;
@@ -477,13 +572,19 @@ impl QUring {
             let rerun = {
                 let mut ops = self.asyncMgr.ops[idx].lock();
                 //error!("uring process2: call is {:?}, idx {}", ops.Type(), idx);
-                ops.ProcessResult(ret, idx)
+                if self.asyncMgr.IsCancelling(idx) {
+                    // the fd is being closed, the op is not resubmitted
+                    ops.Process(ret);
+                    false
+                } else {
+                    ops.ProcessResult(ret, idx)
+                }
             };
 
             if super::super::Shutdown() {
                 return;
             }
-            if !rerun {
+            if !rerun && self.asyncMgr.OpDone(idx) {
                 self.asyncMgr.FreeSlot(idx);
             }
         }
@@ -492,6 +593,10 @@ impl QUring {
     }
 
     pub fn UCall(&self, task: &Task, msg: UringOp) -> i64 {
+        return self.UCallWithMode(task, msg, SubmitMode::Batched);
+    }
+
+    pub fn UCallWithMode(&self, task: &Task, msg: UringOp, mode: SubmitMode) -> i64 {
         let call = UringCall {
             taskId: task.GetTaskId(),
             ret: 0,
@@ -499,7 +604,7 @@ impl QUring {
         };
 
         {
-            self.UringCall(&call);
+            self.UringCall(&call, mode);
         }
 
         Wait();
@@ -624,14 +729,18 @@ impl QUring {
         return CPULocal::NextUringIdx(cnt);
     }
 
-    pub fn UringCall(&self, call: &UringCall) {
+    pub fn UringCall(&self, call: &UringCall, mode: SubmitMode) {
         let entry = call.SEntry();
         let entry = entry.user_data(call.Ptr());
 
-        self.UringPush(entry);
+        self.UringPushWithMode(entry, mode);
     }
 
     pub fn UringPush(&self, entry: squeue::Entry) {
+        self.UringPushWithMode(entry, SubmitMode::Batched);
+    }
+
+    pub fn UringPushWithMode(&self, entry: squeue::Entry, mode: SubmitMode) {
         if super::super::SHARESPACE.config.read().UringBuf {
             let mut s = self.IOUring().submitq.lock();
             s.push_back(entry);
@@ -656,7 +765,16 @@ impl QUring {
             }
         }
 
-        self.IOUring().Submit().expect("QUringIntern::submit fail");
+        match mode {
+            SubmitMode::Batched => {
+                self.IOUring().Submit().expect("QUringIntern::submit fail");
+            }
+            SubmitMode::Turbo => {
+                self.IOUring()
+                    .SubmitImmediate()
+                    .expect("QUringIntern::submit immediate fail");
+            }
+        }
         return;
     }
 
@@ -57,6 +57,8 @@ impl UringCall {
             UringOp::Fsync(ref msg) => return msg.SEntry(),
             UringOp::Splice(ref msg) => return msg.SEntry(),
             UringOp::Accept(ref msg) => return msg.SEntry(),
+            UringOp::OpenAt(ref msg) => return msg.SEntry(),
+            UringOp::FilesUpdate(ref msg) => return msg.SEntry(),
         };
 
         panic!("UringCall SEntry UringOp::None")
@@ -73,6 +75,8 @@ pub enum UringOp {
     Fsync(FsyncOp),
     Splice(SpliceOp),
     Accept(AcceptOp),
+    OpenAt(OpenAtOp),
+    FilesUpdate(FilesUpdateOp),
 }
 
 impl Default for UringOp {
@@ -142,6 +146,8 @@ pub struct StatxOp {
     pub statxBuf: u64,
     pub flags: i32,
     pub mask: u32,
+    // the dirfd is a registered file
+    pub fixedFile: bool,
 }
 
 impl StatxOp {
@@ -154,7 +160,7 @@ impl StatxOp {
         .flags(self.flags)
         .mask(self.mask);
 
-        if SHARESPACE.config.read().UringFixedFile {
+        if self.fixedFile {
             return op.build().flags(squeue::Flags::FIXED_FILE);
         } else {
             return op.build();
@@ -162,6 +168,39 @@ impl StatxOp {
     }
 }
 
+#[derive(Clone, Debug, Copy)]
+pub struct OpenAtOp {
+    pub dirfd: i32,
+    pub pathname: u64,
+    pub flags: i32,
+    pub mode: u32,
+}
+
+impl OpenAtOp {
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = Openat::new(types::Fd(self.dirfd), self.pathname as *const _)
+            .flags(self.flags)
+            .mode(self.mode);
+
+        return op.build();
+    }
+}
+
+// FilesUpdateOp sets the registered file slot of fd to the fd at fds, -1 empties it
+#[derive(Clone, Debug, Copy)]
+pub struct FilesUpdateOp {
+    pub fd: i32,
+    pub fds: u64,
+}
+
+impl FilesUpdateOp {
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = FilesUpdate::new(self.fds as *const _, 1).offset(self.fd);
+
+        return op.build();
+    }
+}
+
 #[derive(Clone, Debug, Copy)]
 pub struct FsyncOp {
     pub fd: i32,
@@ -39,6 +39,7 @@ use super::super::super::guestfdnotifier::*;
 use super::super::super::kernel::async_wait::*;
 use super::super::super::kernel::fd_table::*;
 use super::super::super::kernel::kernel::GetKernel;
+use super::super::super::kernel::socket_store::InetSlot;
 use super::super::super::kernel::time::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::task::*;
@@ -112,6 +113,7 @@ pub fn newAsyncSocketFile(
     nonblock: bool,
     state: SockState,
     addr: Option<Vec<u8>>,
+    slot: Option<InetSlot>,
 ) -> Result<File> {
     let dirent = NewSocketDirent(task, SOCKET_DEVICE.clone(), fd)?;
     let inode = dirent.Inode();
@@ -138,7 +140,7 @@ pub fn newAsyncSocketFile(
         s.into(),
     );
 
-    GetKernel().sockets.AddSocket(&file);
+    GetKernel().sockets.AddSocket(&file, slot)?;
     return Ok(file);
 }
 
@@ -506,6 +508,11 @@ impl SockOperations for AsyncSocketOperations {
         flags: i32,
         blocking: bool,
     ) -> Result<i64> {
+        // like Linux, the fd and the socket are checked for before a connection
+        // is taken off the backlog, which keeps it there on EMFILE or ENFILE
+        task.fdTbl.CheckFreeFD(task, 0)?;
+        let slot = GetKernel().sockets.ReserveInet()?;
+
         let mut acceptItem = AcceptItem::default();
         if !blocking {
             let ai = self.IOAccept();
@@ -572,6 +579,7 @@ impl SockOperations for AsyncSocketOperations {
             flags & SocketFlags::SOCK_NONBLOCK != 0,
             state,
             Some(remoteAddr.to_vec()),
+            Some(slot),
         )?;
 
         let fdFlags = FDFlags {
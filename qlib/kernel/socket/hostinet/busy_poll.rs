@@ -0,0 +1,90 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::sync::atomic::AtomicI64;
+use core::sync::atomic::Ordering;
+
+use super::super::super::super::common::*;
+use super::super::super::super::linux::time::MICROSECOND;
+use super::super::super::super::linux_def::*;
+use super::super::super::kernel::timer::MonotonicNow;
+use super::super::super::task::*;
+
+// MAX_BUSY_POLL_USEC bounds the spin of one read whatever SO_BUSY_POLL is set to,
+// as the spin holds the vcpu. It plays the part of the net.core.busy_poll sysctl,
+// which bounds the busy polling of poll(2) and select(2) on Linux.
+pub const MAX_BUSY_POLL_USEC: i64 = 100;
+
+// BusyPoll is the SO_BUSY_POLL setting of a host socket. Busy polling happens in the
+// guest readiness path, the host socket never sees the option.
+#[derive(Default)]
+pub struct BusyPoll {
+    // SO_BUSY_POLL in microseconds
+    usec: AtomicI64,
+}
+
+impl BusyPoll {
+    pub fn GetSockOpt(&self, opt: &mut [u8]) -> Result<i64> {
+        if opt.len() < SocketSize::SIZEOF_INT32 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let val = self.usec.load(Ordering::Relaxed) as i32;
+        opt[..SocketSize::SIZEOF_INT32].copy_from_slice(&val.to_ne_bytes());
+        return Ok(SocketSize::SIZEOF_INT32 as i64);
+    }
+
+    pub fn SetSockOpt(&self, task: &Task, opt: &[u8]) -> Result<i64> {
+        if opt.len() < SocketSize::SIZEOF_INT32 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let val: i32 = task.CopyInObj::<i32>(&opt[0] as *const _ as u64)?;
+        if val < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        if val as i64 > self.usec.load(Ordering::Relaxed)
+            && !task.Creds().lock().HasCapability(Capability::CAP_NET_ADMIN)
+        {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        self.usec.store(val as i64, Ordering::Relaxed);
+        return Ok(0);
+    }
+
+    // Poll spins for up to the SO_BUSY_POLL budget waiting for readable to return
+    // true, which saves the sleep and wakeup of a short wait. It gives up early when
+    // the task is interrupted, so that the blocking wait reports the signal.
+    pub fn Poll(&self, task: &Task, readable: impl Fn() -> bool) -> bool {
+        let usec = core::cmp::min(self.usec.load(Ordering::Relaxed), MAX_BUSY_POLL_USEC);
+        if usec == 0 {
+            return false;
+        }
+
+        let deadline = MonotonicNow() + usec * MICROSECOND;
+        loop {
+            if readable() {
+                return true;
+            }
+
+            if task.blocker.Interrupted(false) || MonotonicNow() >= deadline {
+                return false;
+            }
+
+            core::hint::spin_loop();
+        }
+    }
+}
@@ -24,6 +24,7 @@ use core::sync::atomic::Ordering;
 
 //use super::super::*;
 use super::super::super::super::common::*;
+use super::super::super::super::linux::socket::SO_MAX_PACING_RATE;
 use super::super::super::super::linux::time::Timeval;
 use super::super::super::super::linux_def::*;
 use super::super::super::super::socket_buf::*;
@@ -38,6 +39,8 @@ use super::super::super::guestfdnotifier::*;
 use super::super::super::kernel::async_wait::*;
 use super::super::super::kernel::fd_table::*;
 use super::super::super::kernel::kernel::GetKernel;
+use super::super::super::kernel::socket_store::InetSlot;
+use super::super::super::kernel::throttle::*;
 use super::super::super::kernel::time::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::task::*;
@@ -56,6 +59,7 @@ pub fn newHostSocketFile(
     stype: i32,
     nonblock: bool,
     addr: Option<Vec<u8>>,
+    slot: Option<InetSlot>,
 ) -> Result<File> {
     let dirent = NewSocketDirent(task, SOCKET_DEVICE.clone(), fd)?;
     let inode = dirent.Inode();
@@ -74,7 +78,7 @@ pub fn newHostSocketFile(
         s.into(),
     );
 
-    GetKernel().sockets.AddSocket(&file);
+    GetKernel().sockets.AddSocket(&file, slot)?;
     return Ok(file);
 }
 
@@ -88,6 +92,7 @@ pub struct HostSocketOperationsIntern {
     pub remoteAddr: QMutex<Option<SockAddr>>,
     pub hostops: HostInodeOp,
     passInq: AtomicBool,
+    throttle: SocketThrottle,
 }
 
 #[derive(Clone)]
@@ -123,6 +128,7 @@ impl HostSocketOperations {
             remoteAddr: QMutex::new(addr),
             hostops: hostops,
             passInq: AtomicBool::new(false),
+            throttle: SocketThrottle::New(family),
         };
 
         let ret = Self(Arc::new(ret));
@@ -242,15 +248,18 @@ impl FileOperations for HostSocketOperations {
     fn ReadAt(
         &self,
         task: &Task,
-        _f: &File,
+        f: &File,
         dsts: &mut [IoVec],
         _offset: i64,
         _blocking: bool,
     ) -> Result<i64> {
+        self.throttle.Wait(task, false, f.Flags().NonBlocking)?;
+
         let size = IoVec::NumBytes(dsts);
         let buf = DataBuff::New(size);
         let iovs = buf.Iovs(size);
         let ret = IORead(self.fd, &iovs)?;
+        self.throttle.Take(false, ret);
 
         // handle partial memcopy
         task.CopyDataOutToIovs(&buf.buf[0..ret as usize], dsts, false)?;
@@ -260,7 +269,7 @@ impl FileOperations for HostSocketOperations {
     fn WriteAt(
         &self,
         task: &Task,
-        _f: &File,
+        f: &File,
         srcs: &[IoVec],
         _offset: i64,
         _blocking: bool,
@@ -270,11 +279,15 @@ impl FileOperations for HostSocketOperations {
             return Ok(0);
         }
 
+        self.throttle.Wait(task, true, f.Flags().NonBlocking)?;
+
         let size = IoVec::NumBytes(srcs);
         let mut buf = DataBuff::New(size);
         let len = task.CopyDataInFromIovs(&mut buf.buf, srcs, true)?;
         let iovs = buf.Iovs(len);
-        return IOWrite(self.fd, &iovs);
+        let ret = IOWrite(self.fd, &iovs)?;
+        self.throttle.Take(true, ret);
+        return Ok(ret);
     }
 
     fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
@@ -443,6 +456,11 @@ impl SockOperations for HostSocketOperations {
         flags: i32,
         blocking: bool,
     ) -> Result<i64> {
+        // like Linux, the fd and the socket are checked for before a connection
+        // is taken off the backlog, which keeps it there on EMFILE or ENFILE
+        task.fdTbl.CheckFreeFD(task, 0)?;
+        let slot = GetKernel().sockets.ReserveInet()?;
+
         let mut acceptItem = AcceptItem::default();
         if !blocking {
             let ai = self.IOAccept();
@@ -507,6 +525,7 @@ impl SockOperations for HostSocketOperations {
             self.stype,
             flags & SocketFlags::SOCK_NONBLOCK != 0,
             Some(remoteAddr.to_vec()),
+            Some(slot),
         )?;
 
         let fdFlags = FDFlags {
@@ -644,6 +663,10 @@ impl SockOperations for HostSocketOperations {
         return Ok(optlen as i64)
         */
 
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_MAX_PACING_RATE {
+            return self.throttle.GetSockOpt(opt);
+        }
+
         let mut optLen = opt.len();
         let res = if optLen == 0 {
             Kernel::HostSpace::GetSockOpt(
@@ -691,6 +714,10 @@ impl SockOperations for HostSocketOperations {
             }
         }
 
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_MAX_PACING_RATE {
+            return self.throttle.SetSockOpt(opt);
+        }
+
         // TCP_INQ is bound to buffer implementation
         if (level as u64) == LibcConst::SOL_TCP && (name as u64) == LibcConst::TCP_INQ {
             let val = unsafe { *(&opt[0] as *const _ as u64 as *const i32) };
@@ -777,6 +804,13 @@ impl SockOperations for HostSocketOperations {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
+        // a peek leaves the data in the socket, it's charged when it's read
+        let peek = flags & MsgType::MSG_PEEK != 0;
+        if !peek {
+            self.throttle
+                .Wait(task, false, flags & MsgType::MSG_DONTWAIT != 0)?;
+        }
+
         let size = IoVec::NumBytes(dsts);
         let buf = DataBuff::New(size);
         let iovs = buf.Iovs(size);
@@ -838,6 +872,10 @@ impl SockOperations for HostSocketOperations {
             return Err(Error::SysError(-res as i32));
         }
 
+        if !peek {
+            self.throttle.Take(false, res as i64);
+        }
+
         let msgFlags = msgHdr.msgFlags & !MsgType::MSG_CTRUNC;
         let senderAddr = if senderRequested
             // for tcp connect, recvmsg get nameLen=0 msg
@@ -881,6 +919,9 @@ impl SockOperations for HostSocketOperations {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
+        self.throttle
+            .Wait(task, true, flags & MsgType::MSG_DONTWAIT != 0)?;
+
         let size = IoVec::NumBytes(srcs);
         let mut buf = DataBuff::New(size);
         let len = task.CopyDataInFromIovs(&mut buf.buf, srcs, true)?;
@@ -946,6 +987,7 @@ impl SockOperations for HostSocketOperations {
             return Err(Error::SysError(-res as i32));
         }
 
+        self.throttle.Take(true, res as i64);
         return Ok(res as i64);
     }
 
@@ -24,14 +24,17 @@ use crate::qlib::kernel::task::Task;
 use crate::qlib::linux_def::*;
 use crate::qlib::socket_buf::*;
 
-// input: client side queue, server side queue
+// input: client side queue, server side queue, page counts of the client to server
+// and the server to client streams
 // return: (client side socket, server side socket)
 pub fn LoopbackSocketPair(
     clientQueue: Queue,
     serverQueue: Queue,
+    clientToServerPages: u64,
+    serverToClientPages: u64,
 ) -> (LoopbackSocket, LoopbackSocket) {
-    let buf1 = ByteStream::Init(MemoryDef::DEFAULT_BUF_PAGE_COUNT);
-    let buf2 = ByteStream::Init(MemoryDef::DEFAULT_BUF_PAGE_COUNT);
+    let buf1 = ByteStream::Init(clientToServerPages);
+    let buf2 = ByteStream::Init(serverToClientPages);
 
     let clientSockBuf = SocketBuff::New(buf1.clone(), buf2.clone());
     let serverSockBuf = SocketBuff::New(buf2, buf1);
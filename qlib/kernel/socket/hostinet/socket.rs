@@ -30,6 +30,7 @@ use core::sync::atomic::Ordering;
 use super::super::super::super::common::*;
 use super::super::super::super::fileinfo::*;
 use super::super::super::super::linux::netdevice::*;
+use super::super::super::super::linux::socket::SO_BUSY_POLL;
 use super::super::super::super::linux::time::Timeval;
 use super::super::super::super::linux_def::*;
 use super::super::super::super::mem::block::*;
@@ -45,6 +46,7 @@ use super::super::super::guestfdnotifier::*;
 use super::super::super::kernel::async_wait::*;
 use super::super::super::kernel::fd_table::*;
 use super::super::super::kernel::kernel::GetKernel;
+use super::super::super::kernel::socket_store::InetSlot;
 use super::super::super::kernel::time::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::quring::QUring;
@@ -61,6 +63,7 @@ use super::super::super::SHARESPACE;
 use super::super::control::*;
 use super::super::socket::*;
 use super::super::unix::transport::unix::*;
+use super::busy_poll::*;
 use super::hostsocket::*;
 use super::rdma_socket::*;
 use super::uring_socket::*;
@@ -79,6 +82,7 @@ fn newSocketFile(
     nonblock: bool,
     socketBuf: SocketBufType,
     addr: Option<Vec<u8>>,
+    slot: Option<InetSlot>,
 ) -> Result<File> {
     let dirent = NewSocketDirent(task, SOCKET_DEVICE.clone(), fd)?;
     let inode = dirent.Inode();
@@ -105,7 +109,7 @@ fn newSocketFile(
         s.into(),
     );
 
-    GetKernel().sockets.AddSocket(&file);
+    GetKernel().sockets.AddSocket(&file, slot)?;
     return Ok(file);
 }
 
@@ -203,6 +207,7 @@ pub struct SocketOperationsIntern {
     pub enableAsyncAccept: AtomicBool,
     pub hostops: HostInodeOp,
     passInq: AtomicBool,
+    busyPoll: BusyPoll,
     pub tcpRDMA: bool,
     pub udpRDMA: bool,
 }
@@ -260,6 +265,7 @@ impl SocketOperations {
             enableAsyncAccept: AtomicBool::new(false),
             hostops: hostops,
             passInq: AtomicBool::new(false),
+            busyPoll: BusyPoll::default(),
             tcpRDMA,
             udpRDMA,
         };
@@ -1111,6 +1117,11 @@ impl SockOperations for SocketOperations {
         flags: i32,
         blocking: bool,
     ) -> Result<i64> {
+        // like Linux, the fd and the socket are checked for before a connection
+        // is taken off the backlog, which keeps it there on EMFILE or ENFILE
+        task.fdTbl.CheckFreeFD(task, 0)?;
+        let slot = GetKernel().sockets.ReserveInet()?;
+
         let mut acceptItem = AcceptItem::default();
         if !blocking {
             let ai = self.AcceptData();
@@ -1178,6 +1189,7 @@ impl SockOperations for SocketOperations {
             flags & SocketFlags::SOCK_NONBLOCK != 0,
             sockBuf,
             Some(remoteAddr.to_vec()),
+            Some(slot),
         )?;
 
         let fdFlags = FDFlags {
@@ -1480,6 +1492,10 @@ impl SockOperations for SocketOperations {
         return Ok(optlen as i64)
         */
 
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_BUSY_POLL {
+            return self.busyPoll.GetSockOpt(opt);
+        }
+
         let mut optLen = opt.len();
         let res = if optLen == 0 {
             Kernel::HostSpace::GetSockOpt(
@@ -1566,6 +1582,10 @@ impl SockOperations for SocketOperations {
             }
         }
 
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_BUSY_POLL {
+            return self.busyPoll.SetSockOpt(task, opt);
+        }
+
         // TCP_INQ is bound to buffer implementation
         if (level as u64) == LibcConst::SOL_TCP && (name as u64) == LibcConst::TCP_INQ {
             let val: i32 = task.CopyInObj::<i32>(&opt[0] as *const _ as u64)?;
@@ -1787,6 +1807,7 @@ impl SockOperations for SocketOperations {
             self.EventRegister(task, &general, EVENT_READ);
             defer!(self.EventUnregister(task, &general));
 
+            let mut polled = false;
             'main: loop {
                 loop {
                     match self.ReadFromBuf(task, socketType.clone(), iovs, peek) {
@@ -1829,6 +1850,16 @@ impl SockOperations for SocketOperations {
                     };
                 }
 
+                if !polled {
+                    polled = true;
+                    if self
+                        .busyPoll
+                        .Poll(task, || self.Readiness(task, EVENT_READ) != 0)
+                    {
+                        continue 'main;
+                    }
+                }
+
                 match task.blocker.BlockWithMonoTimer(true, deadline) {
                     Err(e) => {
                         if count > 0 {
@@ -2467,6 +2498,7 @@ impl Provider for SocketProvider {
                 nonblocking,
                 socketType,
                 None,
+                None,
             )?;
             //TODO: UDP
             if (self.family == AFType::AF_INET || self.family == AFType::AF_INET6)
@@ -2497,6 +2529,7 @@ impl Provider for SocketProvider {
                 Queue::default(),
                 socketType,
                 None,
+                None,
             )?;
         } else {
             file = newHostSocketFile(
@@ -2506,6 +2539,7 @@ impl Provider for SocketProvider {
                 stype & SocketType::SOCK_TYPE_MASK,
                 nonblocking,
                 None,
+                None,
             )?;
         }
 
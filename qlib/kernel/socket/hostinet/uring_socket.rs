@@ -27,6 +27,8 @@ use core::sync::atomic::AtomicU16;
 use core::sync::atomic::Ordering;
 
 use super::super::super::super::common::*;
+use super::super::super::super::linux::socket::SO_BUSY_POLL;
+use super::super::super::super::linux::socket::SO_MAX_PACING_RATE;
 use super::super::super::super::linux::time::Timeval;
 use super::super::super::super::linux_def::*;
 use super::super::super::super::mem::block::*;
@@ -40,6 +42,8 @@ use super::super::super::fs::host::hostinodeop::*;
 use super::super::super::guestfdnotifier::*;
 use super::super::super::kernel::fd_table::*;
 use super::super::super::kernel::kernel::GetKernel;
+use super::super::super::kernel::socket_store::InetSlot;
+use super::super::super::kernel::throttle::*;
 use super::super::super::kernel::time::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::quring::QUring;
@@ -52,6 +56,7 @@ use super::super::super::SHARESPACE;
 use super::super::control::*;
 use super::super::socket::*;
 use super::super::unix::transport::unix::*;
+use super::busy_poll::*;
 use crate::qlib::bytestream::*;
 use crate::qlib::kernel::kernel::abstract_socket_namespace::*;
 use crate::qlib::kernel::kernel::waiter::Queue;
@@ -68,6 +73,7 @@ pub fn newUringSocketFile(
     queue: Queue,
     socketType: UringSocketType,
     addr: Option<Vec<u8>>,
+    slot: Option<InetSlot>,
 ) -> Result<File> {
     let dirent = NewSocketDirent(task, SOCKET_DEVICE.clone(), fd)?;
     let inode = dirent.Inode();
@@ -94,7 +100,7 @@ pub fn newUringSocketFile(
         s.into(),
     );
 
-    GetKernel().sockets.AddSocket(&file);
+    GetKernel().sockets.AddSocket(&file, slot)?;
     return Ok(file);
 }
 
@@ -177,6 +183,12 @@ pub struct UringSocketOperationsIntern {
     pub loopbackPort: AtomicU16,
     pub hostops: HostInodeOp,
     passInq: AtomicBool,
+    // SO_RCVBUF/SO_SNDBUF as reported by getsockopt, 0 until they are set. They size
+    // the SocketBuff of the connections made after that.
+    rcvBuf: AtomicI32,
+    sndBuf: AtomicI32,
+    busyPoll: BusyPoll,
+    throttle: SocketThrottle,
 }
 
 #[derive(Clone)]
@@ -276,6 +288,10 @@ impl UringSocketOperations {
             loopbackPort: AtomicU16::new(0),
             hostops: hostops,
             passInq: AtomicBool::new(false),
+            rcvBuf: AtomicI32::new(0),
+            sndBuf: AtomicI32::new(0),
+            busyPoll: BusyPoll::default(),
+            throttle: SocketThrottle::New(family),
         };
 
         let ret = Self(Arc::new(ret));
@@ -391,9 +407,23 @@ impl UringSocketOperations {
         }
     }
 
+    pub fn BufSize(&self) -> (i32, i32) {
+        return (
+            self.rcvBuf.load(Ordering::Relaxed),
+            self.sndBuf.load(Ordering::Relaxed),
+        );
+    }
+
+    pub fn SetBufSize(&self, rcvBuf: i32, sndBuf: i32) {
+        self.rcvBuf.store(rcvBuf, Ordering::Relaxed);
+        self.sndBuf.store(sndBuf, Ordering::Relaxed);
+    }
+
     pub fn PostConnect(&self) {
-        let socketBuf = SocketBuff(Arc::new(SocketBuffIntern::Init(
-            MemoryDef::DEFAULT_BUF_PAGE_COUNT,
+        let (rcvBuf, sndBuf) = self.BufSize();
+        let socketBuf = SocketBuff(Arc::new(SocketBuffIntern::InitWithSize(
+            SockBufPages(rcvBuf),
+            SockBufPages(sndBuf),
         )));
         *self.socketType.lock() = UringSocketType::Uring(socketBuf.clone());
         QUring::BufSockInit(self.fd, self.queue.clone(), socketBuf, true).unwrap();
@@ -586,7 +616,7 @@ impl FileOperations for UringSocketOperations {
     fn ReadAt(
         &self,
         task: &Task,
-        _f: &File,
+        f: &File,
         dsts: &mut [IoVec],
         _offset: i64,
         _blocking: bool,
@@ -597,6 +627,7 @@ impl FileOperations for UringSocketOperations {
                 /*if self.SocketBuf().RClosed() {
                     return Err(Error::SysError(SysErr::ESPIPE))
                 }*/
+                self.throttle.Wait(task, false, f.Flags().NonBlocking)?;
                 let ret = QUring::RingFileRead(
                     task,
                     self.fd,
@@ -606,10 +637,13 @@ impl FileOperations for UringSocketOperations {
                     true,
                     false,
                 )?;
+                self.throttle.Take(false, ret);
                 return Ok(ret);
             }
             UringSocketType::Loopback(loopback) => {
+                self.throttle.Wait(task, false, f.Flags().NonBlocking)?;
                 let count = loopback.Readv(task, dsts, false)?;
+                self.throttle.Take(false, count);
                 return Ok(count);
             }
             _ => {
@@ -621,7 +655,7 @@ impl FileOperations for UringSocketOperations {
     fn WriteAt(
         &self,
         task: &Task,
-        _f: &File,
+        f: &File,
         srcs: &[IoVec],
         _offset: i64,
         _blocking: bool,
@@ -638,10 +672,15 @@ impl FileOperations for UringSocketOperations {
                     return Err(Error::SysError(SysErr::EPIPE));
                 }
 
-                return QUring::SocketSend(task, self.fd, self.queue.clone(), buf, srcs, self);
+                self.throttle.Wait(task, true, f.Flags().NonBlocking)?;
+                let ret = QUring::SocketSend(task, self.fd, self.queue.clone(), buf, srcs, self)?;
+                self.throttle.Take(true, ret);
+                return Ok(ret);
             }
             UringSocketType::Loopback(loopback) => {
+                self.throttle.Wait(task, true, f.Flags().NonBlocking)?;
                 let count = loopback.Writev(task, srcs)?;
+                self.throttle.Take(true, count);
                 return Ok(count);
             }
             _ => {
@@ -762,8 +801,19 @@ impl SockOperations for UringSocketOperations {
                         None => (), //return Err(Error::SysError(SysErr::ECONNREFUSED)),
                         Some(q) => {
                             let serverQueue = Queue::default();
-                            let (clientSock, serverSock) =
-                                LoopbackSocketPair(self.queue.clone(), serverQueue.clone());
+                            // each stream is the send buffer of one end and the receive
+                            // buffer of the other, it takes the larger of the two
+                            let (rcvBuf, sndBuf) = self.BufSize();
+                            let (serverRcvBuf, serverSndBuf) = {
+                                let q = q.lock();
+                                (q.rcvBuf, q.sndBuf)
+                            };
+                            let (clientSock, serverSock) = LoopbackSocketPair(
+                                self.queue.clone(),
+                                serverQueue.clone(),
+                                core::cmp::max(SockBufPages(sndBuf), SockBufPages(serverRcvBuf)),
+                                core::cmp::max(SockBufPages(serverSndBuf), SockBufPages(rcvBuf)),
+                            );
                             *self.socketType.lock() = UringSocketType::Loopback(clientSock);
                             let addr = SockAddrInet {
                                 Family: AFType::AF_INET as u16,
@@ -840,6 +890,11 @@ impl SockOperations for UringSocketOperations {
         flags: i32,
         blocking: bool,
     ) -> Result<i64> {
+        // like Linux, the fd and the socket are checked for before a connection
+        // is taken off the backlog, which keeps it there on EMFILE or ENFILE
+        task.fdTbl.CheckFreeFD(task, 0)?;
+        let slot = GetKernel().sockets.ReserveInet()?;
+
         let acceptItem;
         if !blocking {
             let ai = self.AcceptData();
@@ -906,8 +961,15 @@ impl SockOperations for UringSocketOperations {
             acceptItem.queue.clone(),
             sockBuf,
             Some(remoteAddr.to_vec()),
+            Some(slot),
         )?;
 
+        let (rcvBuf, sndBuf) = self.BufSize();
+        file.FileOp
+            .UringSocketOperations()
+            .unwrap()
+            .SetBufSize(rcvBuf, sndBuf);
+
         let fdFlags = FDFlags {
             CloseOnExec: flags & SocketFlags::SOCK_CLOEXEC != 0,
         };
@@ -970,6 +1032,9 @@ impl SockOperations for UringSocketOperations {
             _ => panic!("uring socket listen on wrong type {:?}", socketBuf), // panic?
         };
 
+        let (rcvBuf, sndBuf) = self.BufSize();
+        acceptQueue.lock().SetBufSize(rcvBuf, sndBuf);
+
         let res = Kernel::HostSpace::Listen(self.fd, backlog, asyncAccept);
 
         if res < 0 {
@@ -1145,11 +1210,38 @@ impl SockOperations for UringSocketOperations {
                         return Ok(4);
                     }
                 }
+                LibcConst::SO_RCVBUF | LibcConst::SO_SNDBUF => {
+                    let (rcvBuf, sndBuf) = self.BufSize();
+                    let size = if name as u64 == LibcConst::SO_RCVBUF {
+                        rcvBuf
+                    } else {
+                        sndBuf
+                    };
+
+                    // not set yet, the host socket has the default
+                    if size != 0 {
+                        if opt.len() < 4 {
+                            return Err(Error::SysError(SysErr::EINVAL));
+                        }
+                        unsafe {
+                            *(&opt[0] as *const _ as u64 as *mut i32) = size;
+                        }
+                        return Ok(4);
+                    }
+                }
                 _ => (),
             },
             _ => (),
         };
 
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_BUSY_POLL {
+            return self.busyPoll.GetSockOpt(opt);
+        }
+
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_MAX_PACING_RATE {
+            return self.throttle.GetSockOpt(opt);
+        }
+
         let mut optLen = opt.len();
         let res = if optLen == 0 {
             Kernel::HostSpace::GetSockOpt(
@@ -1233,6 +1325,38 @@ impl SockOperations for UringSocketOperations {
             }
         }
 
+        // the SocketBuff of a connection is sized when it's set up, a change on an
+        // established connection only reaches the host socket
+        if (level as u64) == LibcConst::SOL_SOCKET
+            && ((name as u64) == LibcConst::SO_RCVBUF || (name as u64) == LibcConst::SO_SNDBUF)
+        {
+            if opt.len() < 4 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let val: i32 = task.CopyInObj::<i32>(&opt[0] as *const _ as u64)?;
+            let (mut rcvBuf, mut sndBuf) = self.BufSize();
+            if (name as u64) == LibcConst::SO_RCVBUF {
+                rcvBuf = SockBufSize(val, SOCK_MIN_RCVBUF);
+            } else {
+                sndBuf = SockBufSize(val, SOCK_MIN_SNDBUF);
+            }
+
+            self.SetBufSize(rcvBuf, sndBuf);
+            if let UringSocketType::TCPUringlServer(q) = self.SocketType() {
+                q.lock().SetBufSize(rcvBuf, sndBuf);
+            }
+        }
+
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_BUSY_POLL {
+            return self.busyPoll.SetSockOpt(task, opt);
+        }
+
+        // pacing is done by the guest's throttle, see SocketThrottle
+        if (level as u64) == LibcConst::SOL_SOCKET && name == SO_MAX_PACING_RATE {
+            return self.throttle.SetSockOpt(opt);
+        }
+
         // TCP_INQ is bound to buffer implementation
         if (level as u64) == LibcConst::SOL_TCP && (name as u64) == LibcConst::TCP_INQ {
             let val: i32 = task.CopyInObj::<i32>(&opt[0] as *const _ as u64)?;
@@ -1345,6 +1469,11 @@ impl SockOperations for UringSocketOperations {
             return Ok((0 as i64, retFlags, senderAddr, controlData));
         }
 
+        // a peek leaves the data in the socket, it's charged when it's read
+        if !peek {
+            self.throttle.Wait(task, false, dontwait)?;
+        }
+
         let len = IoVec::NumBytes(dsts);
         let data = if trunc { Some(Iovs(dsts).Data()) } else { None };
 
@@ -1366,6 +1495,9 @@ impl SockOperations for UringSocketOperations {
                     };
 
                     let (retFlags, controlData) = self.prepareControlMessage(controlDataLen);
+                    if !peek {
+                        self.throttle.Take(false, count);
+                    }
                     return Ok((count, retFlags, senderAddr, controlData));
                 }
             }
@@ -1375,6 +1507,7 @@ impl SockOperations for UringSocketOperations {
         self.EventRegister(task, &general, EVENT_READ);
         defer!(self.EventUnregister(task, &general));
 
+        let mut polled = false;
         'main: loop {
             loop {
                 match self.ReadFromBuf(task, &buf, iovs, peek) {
@@ -1417,6 +1550,16 @@ impl SockOperations for UringSocketOperations {
                 };
             }
 
+            if !polled {
+                polled = true;
+                if self
+                    .busyPoll
+                    .Poll(task, || self.Readiness(task, EVENT_READ) != 0)
+                {
+                    continue 'main;
+                }
+            }
+
             match task.blocker.BlockWithMonoTimer(true, deadline) {
                 Err(e) => {
                     if count > 0 {
@@ -1451,6 +1594,10 @@ impl SockOperations for UringSocketOperations {
                 .ZeroDataOutToIovs(task, &data.unwrap(), count as usize, false)?;
         }
 
+        if !peek {
+            self.throttle.Take(false, count);
+        }
+
         let (retFlags, controlData) = self.prepareControlMessage(controlDataLen);
         return Ok((count as i64, retFlags, senderAddr, controlData));
     }
@@ -1474,6 +1621,7 @@ impl SockOperations for UringSocketOperations {
         }*/
 
         let dontwait = flags & MsgType::MSG_DONTWAIT != 0;
+        self.throttle.Wait(task, true, dontwait)?;
 
         let len = Iovs(srcs).Count();
         let mut count = 0;
@@ -1481,26 +1629,28 @@ impl SockOperations for UringSocketOperations {
         let mut tmp;
 
         if dontwait {
-            return self.WriteToBuf(task, &buf, srcs);
+            let ret = self.WriteToBuf(task, &buf, srcs)?;
+            self.throttle.Take(true, ret);
+            return Ok(ret);
         }
 
         let general = task.blocker.generalEntry.clone();
         self.EventRegister(task, &general, EVENT_WRITE);
         defer!(self.EventUnregister(task, &general));
 
-        loop {
+        let count = 'main: loop {
             loop {
                 match self.WriteToBuf(task, &buf, srcs) {
                     Err(Error::SysError(SysErr::EWOULDBLOCK)) => {
                         if flags & MsgType::MSG_DONTWAIT != 0 {
                             if count > 0 {
-                                return Ok(count);
+                                break 'main count;
                             }
                             return Err(Error::SysError(SysErr::EWOULDBLOCK));
                         }
 
                         if count > 0 {
-                            return Ok(count);
+                            break 'main count;
                         }
 
                         if flags & MsgType::MSG_DONTWAIT != 0 {
@@ -1511,7 +1661,7 @@ impl SockOperations for UringSocketOperations {
                     }
                     Err(e) => {
                         if count > 0 {
-                            return Ok(count);
+                            break 'main count;
                         }
 
                         return Err(e);
@@ -1519,7 +1669,7 @@ impl SockOperations for UringSocketOperations {
                     Ok(n) => {
                         count += n;
                         if count == len as i64 {
-                            return Ok(count);
+                            break 'main count;
                         }
                         tmp = Iovs(srcs).DropFirst(n as usize);
                         srcs = &mut tmp;
@@ -1530,19 +1680,22 @@ impl SockOperations for UringSocketOperations {
             match task.blocker.BlockWithMonoTimer(true, deadline) {
                 Err(Error::SysError(SysErr::ETIMEDOUT)) => {
                     if count > 0 {
-                        return Ok(count);
+                        break 'main count;
                     }
                     return Err(Error::SysError(SysErr::EWOULDBLOCK));
                 }
                 Err(e) => {
                     if count > 0 {
-                        return Ok(count);
+                        break 'main count;
                     }
                     return Err(e);
                 }
                 _ => (),
             }
-        }
+        };
+
+        self.throttle.Take(true, count);
+        return Ok(count);
     }
 
     fn SetRecvTimeout(&self, ns: i64) {
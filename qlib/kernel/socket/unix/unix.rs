@@ -47,6 +47,7 @@ use super::super::super::kernel::kernel::GetKernel;
 use super::super::super::kernel::time::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::task::*;
+use super::super::super::util::cstring::*;
 //use super::super::super::super::mem::io::*;
 use super::super::super::super::mem::seq::*;
 use super::super::super::super::path::*;
@@ -73,7 +74,7 @@ pub fn NewUnixSocket(task: &Task, ep: BoundEndpoint, stype: i32) -> Result<File>
         UnixSocketOperations::New(ep, stype).into(),
     );
 
-    GetKernel().sockets.AddSocket(&file);
+    GetKernel().sockets.AddSocket(&file, None)?;
 
     return Ok(file);
 }
@@ -180,6 +181,8 @@ pub struct UnixSocketOperationsInner {
     pub send: AtomicI64,
     pub recv: AtomicI64,
     pub name: QMutex<Option<Vec<u8>>>,
+    // the abstract socket namespace an abstract name is bound in
+    pub abstractNs: QMutex<Option<AbstractSocketNamespace>>,
 }
 
 impl UnixSocketOperations {
@@ -190,6 +193,7 @@ impl UnixSocketOperations {
             send: AtomicI64::new(0),
             recv: AtomicI64::new(0),
             name: QMutex::new(None),
+            abstractNs: QMutex::new(None),
         };
 
         return Self(Arc::new(ret));
@@ -328,7 +332,9 @@ impl Drop for UnixSocketOperationsInner {
             None => (),
             Some(ref name) => {
                 if name[0] == 0 {
-                    ABSTRACT_SOCKET.Remove(name, &self.ep);
+                    if let Some(ns) = self.abstractNs.lock().as_ref() {
+                        ns.Remove(name, &self.ep);
+                    }
                 } else {
                     UNIX_SOCKET_PINS.Unpin(name);
                 }
@@ -533,7 +539,8 @@ pub fn ExtractEndpoint(task: &Task, sockAddr: &[u8]) -> Result<BoundEndpoint> {
 
     // Is it abstract?
     if path[0] == 0 {
-        let ep = match ABSTRACT_SOCKET.BoundEndpoint(&path) {
+        let ns = task.Thread().NetworkNamespace().AbstractSockets();
+        let ep = match ns.BoundEndpoint(&path) {
             None => return Err(Error::SysError(SysErr::ECONNREFUSED)),
             Some(ep) => ep,
         };
@@ -541,7 +548,7 @@ pub fn ExtractEndpoint(task: &Task, sockAddr: &[u8]) -> Result<BoundEndpoint> {
         return Ok(ep);
     }
 
-    let path = String::from_utf8(path).unwrap();
+    let path = StringFromBytes(&path);
 
     // Find the node in the filesystem.
     let root = task.fsContext.RootDirectory();
@@ -641,17 +648,19 @@ impl SockOperations for UnixSocketOperations {
         info!("Bind p is {:?}", &p);
         let bep = self.ep.clone();
 
-        let addr = SockAddrUnix::New(core::str::from_utf8(&p).expect("Bind to string fail"));
+        let addr = SockAddrUnix::New(&StringFromBytes(&p));
         self.ep.Bind(&addr)?;
 
         let root = task.fsContext.RootDirectory();
 
         // Is it abstract?
         if p[0] == 0 {
-            ABSTRACT_SOCKET.Bind(p.clone(), &bep)?;
+            let ns = task.Thread().NetworkNamespace().AbstractSockets();
+            ns.Bind(p.clone(), &bep)?;
+            *(self.abstractNs.lock()) = Some(ns);
             *(self.name.lock()) = Some(p);
         } else {
-            let p = String::from_utf8(p).unwrap();
+            let p = StringFromBytes(&p);
 
             let cwd = task.fsContext.WorkDirectory();
 
@@ -19,6 +19,7 @@ use core::mem;
 use super::super::auxv::*;
 use super::super::common::*;
 use super::task::*;
+use super::util::cstring::*;
 
 // StackLayout describes the location of the arguments and environment on the
 // stack.
@@ -88,9 +89,9 @@ impl Stack {
     }
 
     pub fn PushStr(&mut self, task: &Task, str: &str) -> Result<u64> {
-        let len = str.len();
-        self.sp = self.sp - len as u64 - 1;
-        task.CopyOutString(self.sp, len + 1, str)?;
+        let cstr = CString::New(str);
+        self.sp = self.sp - cstr.Len() as u64;
+        task.CopyOutSlice(cstr.Slice(), self.sp, cstr.Len())?;
         return Ok(self.sp);
     }
 
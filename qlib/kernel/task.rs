@@ -19,6 +19,7 @@ use alloc::sync::Arc;
 use core::mem;
 use core::ops::Deref;
 use core::ptr;
+use core::sync::atomic::AtomicBool;
 use core::sync::atomic::Ordering;
 
 //use super::arch::x86_64::arch_x86::*;
@@ -191,6 +192,10 @@ pub struct Task {
     pub ioUsage: IO,
     pub sched: TaskSchedInfo,
     pub exiting: bool,
+    // ptraceSyscall is true if the task was last resumed by PTRACE_SYSCALL, so
+    // that it stops on the entry and the exit of the next syscall. It's only
+    // written under the signal mutex, the syscall path reads it without a lock.
+    pub ptraceSyscall: AtomicBool,
 
     pub perfcounters: Option<Arc<Counters>>,
 
@@ -312,6 +317,7 @@ impl Task {
             ioUsage: IO::default(),
             sched: TaskSchedInfo::default(),
             exiting: false,
+            ptraceSyscall: AtomicBool::new(false),
             perfcounters: None,
             guard: Guard::default(),
         };
@@ -344,6 +350,38 @@ impl Task {
         t.State = state;
     }
 
+    // AccountContextSwitch counts a switch of the vcpu from the task to another
+    // one. Like Linux, it is voluntary when the task blocks, and involuntary when
+    // the task is still runnable: its time slice is used up or it yields.
+    pub fn AccountContextSwitch(&self, voluntary: bool) {
+        if self.taskId == CPULocal::WaitTask() || self.exiting == true {
+            return;
+        }
+
+        let mut t = self.sched.lock();
+        if voluntary {
+            t.YieldCount += 1;
+        } else {
+            t.InvoluntaryCount += 1;
+        }
+    }
+
+    // AccountPageFault counts a page fault of the task. A major fault is one
+    // which had to go to the host for the page: mapping a chunk of the file in
+    // or swapping the page in.
+    pub fn AccountPageFault(&self, major: bool) {
+        if self.taskId == CPULocal::WaitTask() || self.exiting == true {
+            return;
+        }
+
+        let mut t = self.sched.lock();
+        if major {
+            t.MajorFaults += 1;
+        } else {
+            t.MinorFaults += 1;
+        }
+    }
+
     pub fn AccountTaskLeave(&self, state: SchedState) {
         //print!("AccountTaskLeave current task is {:x}, state is {:?}", self.taskId, state);
         if self.taskId == CPULocal::WaitTask() || self.exiting == true {
@@ -400,7 +438,9 @@ impl Task {
 
         let stopCount = thread.lock().stopCount.clone();
         self.AccountTaskEnter(SchedState::Stopped);
+        thread.lock().stopped = true;
         self.blocker.WaitGroupWait(self, &stopCount);
+        thread.lock().stopped = false;
         self.AccountTaskLeave(SchedState::Stopped)
     }
 
@@ -668,6 +708,7 @@ impl Task {
                     ioUsage: ioUsage,
                     sched: TaskSchedInfo::default(),
                     exiting: false,
+                    ptraceSyscall: AtomicBool::new(false),
                     perfcounters: perfcounters,
                     guard: Guard::default(),
                 },
@@ -771,6 +812,7 @@ impl Task {
                     ioUsage: dummyTask.ioUsage.clone(),
                     sched: TaskSchedInfo::default(),
                     exiting: false,
+                    ptraceSyscall: AtomicBool::new(false),
                     perfcounters: None,
                     guard: Guard::default(),
                 },
@@ -188,6 +188,12 @@ pub fn ProcessOne() -> bool {
 }
 
 pub fn Wait() {
+    WaitSwitch(true);
+}
+
+// WaitSwitch runs the next ready task on the vcpu. voluntary is false when the
+// current task is still runnable, see Task::AccountContextSwitch.
+fn WaitSwitch(voluntary: bool) {
     CPULocal::Myself().ToSearch(&SHARESPACE);
     let start = TSC.Rdtsc();
 
@@ -204,6 +210,7 @@ pub fn Wait() {
                 if !Task::Current().context.savefpsate {
                     Task::Current().SaveFp();
                 }
+                Task::Current().AccountContextSwitch(voluntary);
                 switch(current, newTask);
             }
 
@@ -223,6 +230,7 @@ pub fn Wait() {
                     if !Task::Current().context.savefpsate {
                         Task::Current().SaveFp();
                     }
+                    Task::Current().AccountContextSwitch(voluntary);
                     switch(current, waitTask);
                     break;
                 }
@@ -249,22 +257,70 @@ pub fn SwitchToNewTask() -> ! {
     panic!("SwitchToNewTask end impossible");
 }
 
+// the max number of vcpu queues an idle vcpu tries to steal from before it goes
+// back to polling, besides the queue of the new tasks
+pub const STEAL_ATTEMPTS: usize = 3;
+
 impl Scheduler {
     pub fn Steal(&self, vcpuId: usize) -> Option<TaskId> {
         if self.GlobalReadyTaskCnt() == 0 {
             return None;
         }
 
+        // with SoftCPUAffinity, a task can run outside of its affinity while all
+        // the vcpus it is allowed on are busy
+        let soft = SHARESPACE.config.read().SoftCPUAffinity;
+        let canRun = |t: TaskId| {
+            let context = t.Context();
+            return context.AllowedOn(vcpuId) || (soft && self.AllRunning(context.CPUMask()));
+        };
+
         let vcpuCount = self.vcpuCnt;
-        match self.queue[0].Steal() {
+        match self.queue[0].Steal(&canRun) {
+            None => (),
+            Some(t) => return Some(t),
+        }
+
+        // the idle vcpus start at different queues, so that they don't all go for
+        // the same queue lock, and the busiest queue is tried first
+        let start = TSC.Rdtsc() as usize;
+        let mut busiest = 0;
+        let mut busiestLen = 0;
+        for i in 0..vcpuCount {
+            let idx = (start + i) % vcpuCount;
+            if idx == 0 || idx == vcpuId {
+                continue;
+            }
+
+            let len = self.queue[idx].Len();
+            if len > busiestLen {
+                busiest = idx;
+                busiestLen = len;
+            }
+        }
+
+        if busiestLen == 0 {
+            return None;
+        }
+
+        match self.queue[busiest].Steal(&canRun) {
             None => (),
             Some(t) => return Some(t),
         }
 
-        // skip the current vcpu
-        for i in 1..vcpuCount {
-            let idx = (i + vcpuId) % vcpuCount;
-            match self.queue[idx].Steal() {
+        let mut attempts = 1;
+        for i in 0..vcpuCount {
+            if attempts >= STEAL_ATTEMPTS {
+                break;
+            }
+
+            let idx = (start + i) % vcpuCount;
+            if idx == 0 || idx == vcpuId || idx == busiest || self.queue[idx].Len() == 0 {
+                continue;
+            }
+
+            attempts += 1;
+            match self.queue[idx].Steal(&canRun) {
                 None => (),
                 Some(t) => return Some(t),
             }
@@ -277,7 +333,7 @@ impl Scheduler {
     pub fn GetNext(&self) -> Option<TaskId> {
         let vcpuId = CPULocal::CpuId() as usize;
 
-        match self.queue[vcpuId].Next() {
+        match self.queue[vcpuId].Next(vcpuId) {
             None => (),
             Some((t, global)) => {
                 //error!("Next ... {:x?}/{}", t, global);
@@ -333,12 +389,45 @@ impl Scheduler {
     }
 }
 
+// Migrate moves the current task to a vcpu it is allowed on
+pub fn Migrate() {
+    if Task::Current()
+        .context
+        .AllowedOn(CPULocal::CpuId() as usize)
+    {
+        return;
+    }
+
+    SHARESPACE.scheduler.Schedule(Task::TaskId(), false);
+    WaitSwitch(false);
+}
+
+// MigrateTask forces another task off its vcpu if the vcpu is out of the task's
+// affinity and waits until the task has been switched out of it
+pub fn MigrateTask(taskId: TaskId) {
+    loop {
+        let vcpuId = match SHARESPACE.scheduler.RunningOn(taskId) {
+            None => return,
+            Some(id) => id,
+        };
+
+        if taskId.Context().AllowedOn(vcpuId) {
+            return;
+        }
+
+        SHARESPACE.scheduler.VcpuArr[vcpuId].InterruptMigrate();
+        HostSpace::InterruptVcpu(vcpuId as u64);
+        Yield();
+        core::hint::spin_loop();
+    }
+}
+
 pub fn Yield() {
     if SHARESPACE.scheduler.GlobalReadyTaskCnt() == 0 {
         return;
     }
     SHARESPACE.scheduler.Schedule(Task::TaskId(), false);
-    Wait();
+    WaitSwitch(false);
 }
 
 pub fn NewTask(taskId: TaskId) {
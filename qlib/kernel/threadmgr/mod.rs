@@ -20,11 +20,14 @@ pub mod session;
 pub mod task_acct;
 pub mod task_block;
 pub mod task_clone;
+pub mod task_coredump;
 pub mod task_exec;
 pub mod task_exit;
 pub mod task_futex;
 pub mod task_identity;
 pub mod task_log;
+pub mod task_ptrace;
+pub mod task_rseq;
 pub mod task_run;
 pub mod task_sched;
 pub mod task_signals;
@@ -21,6 +21,7 @@ use core::ops::Deref;
 use super::super::super::auth::userns::*;
 use super::super::super::common::*;
 use super::super::super::linux_def::*;
+use super::super::kernel::namespace::*;
 use super::processgroup::*;
 use super::session::*;
 use super::thread::*;
@@ -49,6 +50,7 @@ pub struct PIDNamespaceInternal {
     pub pgids: BTreeMap<ProcessGroup, ProcessGroupID>,
     //ProcessGroup uid to ProcessGroup id of this namespace
     pub exiting: bool,
+    pub ino: u64,
 }
 
 #[derive(Clone, Default)]
@@ -85,6 +87,7 @@ impl PIDNamespace {
             processGroups: BTreeMap::new(),
             pgids: BTreeMap::new(),
             exiting: false,
+            ino: NewNsIno(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
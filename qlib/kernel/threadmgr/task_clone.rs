@@ -23,6 +23,9 @@ use super::super::super::linux_def::*;
 use super::super::super::task_mgr::*;
 use super::super::arch::x86_64::context::*;
 use super::super::kernel::ipc_namespace::*;
+use super::super::kernel::namespace::*;
+use super::super::kernel::net_namespace::*;
+use super::super::kernel::timer::BumpVDSORngGeneration;
 use super::super::threadmgr::task_start::*;
 use super::super::threadmgr::thread::*;
 use super::super::SignalDef::*;
@@ -90,6 +93,10 @@ pub struct SharingOptions {
     // If NewIPCNamespace is true, the task should have an independent IPC
     // namespace.
     pub NewIPCNamespace: bool,
+
+    // If NewMountNamespace is true, the task should have an independent mount
+    // namespace. Only Task.Unshare supports it.
+    pub NewMountNamespace: bool,
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -171,6 +178,7 @@ impl CloneOptions {
                 NewFSContext: flags & CloneOp::CLONE_FS == 0,
                 NewUTSNamespace: flags & CloneOp::CLONE_NEWUTS != 0,
                 NewIPCNamespace: flags & CloneOp::CLONE_NEWIPC != 0,
+                NewMountNamespace: false,
             },
 
             Stack: cStack,
@@ -264,10 +272,16 @@ impl Thread {
             ipcns = IPCNamespace::New(&userns);
         }
 
+        let mut netns = t.netns.clone();
+        if opts.sharingOption.NewNetworkNamespace {
+            netns = NetNamespace::New(&userns, false);
+        }
+
         let mut memoryMgr = t.memoryMgr.clone();
         if opts.sharingOption.NewAddressSpace {
             let newMM = memoryMgr.Fork()?;
             memoryMgr = newMM;
+            BumpVDSORngGeneration();
         }
 
         let vforkParent = if opts.Vfork { Some(self.clone()) } else { None };
@@ -304,6 +318,7 @@ impl Thread {
             let kernel = t.k.clone();
             let limit = tg.lock().limits.clone();
             let cid = tg.lock().containerID.clone();
+            let oomScoreAdj = tg.lock().oomScoreAdj;
             tg = kernel.newThreadGroup(
                 &pidns,
                 &sh,
@@ -312,8 +327,16 @@ impl Thread {
                 &cid,
                 &None,
             );
+            tg.lock().oomScoreAdj = oomScoreAdj;
         }
 
+        // a thread sharing the address space has to register its own struct rseq
+        let (rseqAddr, rseqSignature) = if opts.sharingOption.NewAddressSpace {
+            (t.rseqAddr, t.rseqSignature)
+        } else {
+            (0, 0)
+        };
+
         let mut cfg = TaskConfig {
             TaskId: stackAddr,
             Kernel: t.k.clone(),
@@ -326,10 +349,14 @@ impl Thread {
             Fdtbl: fdTbl,
             Credentials: creds.clone(),
             Niceness: t.niceness,
-            NetworkNamespaced: false,
+            Personality: t.personality,
+            RSeqAddr: rseqAddr,
+            RSeqSignature: rseqSignature,
+            NetNamespace: netns,
             AllowedCPUMask: t.allowedCPUMask.Copy(),
             UTSNamespace: utsns,
             IPCNamespace: ipcns,
+            MountNamespace: t.mountns.clone(),
             Blocker: Blocker::New(stackAddr),
             ContainerID: t.containerID.to_string(),
         };
@@ -340,10 +367,6 @@ impl Thread {
             cfg.InheritParent = Some(self.clone())
         }
 
-        if opts.sharingOption.NewNetworkNamespace {
-            cfg.NetworkNamespaced = true;
-        }
-
         let pidns = tg.PIDNamespace();
         let ts = pidns.lock().owner.clone();
 
@@ -453,6 +476,7 @@ impl Task {
             cTask.context.fs = tls;
         }
 
+        cTask.context.SetCPUMask(self.context.CPUMask());
         taskMgr::NewTask(TaskId::New(cTask.taskId));
 
         return Ok(pid);
@@ -475,6 +499,7 @@ impl Task {
             let creds = nt.lock().creds.clone();
             let utsns = nt.lock().utsns.clone();
             let ipcns = nt.lock().ipcns.clone();
+            let mountns = nt.lock().mountns.clone();
             let fsContext = nt.lock().fsc.clone();
             let fdTbl = nt.lock().fdTbl.clone();
             let blocker = nt.lock().blocker.clone();
@@ -510,7 +535,7 @@ impl Task {
                     tidInfo: Default::default(),
                     isWaitThread: false,
                     signalStack: signalStack,
-                    mountNS: task.mountNS.clone(),
+                    mountNS: mountns,
                     // Arc::new(QMutex::new(Default::default())),
                     creds: creds,
                     utsns: utsns,
@@ -589,14 +614,24 @@ impl Task {
 
         let creds = self.creds.clone();
         let haveCapSysAdmin = t.HasCapability(Capability::CAP_SYS_ADMIN);
-        if opts.NewPIDNamespace {
+        if opts.NewMountNamespace {
             if !haveCapSysAdmin {
                 return Err(Error::SysError(SysErr::EPERM));
             }
 
-            let userns = creds.lock().UserNamespace.clone();
-            let pidns = tg.PIDNamespace();
-            t.lock().childPIDNamespace = Some(pidns.NewChild(&userns));
+            // the root and the cwd move to the same paths in the new namespace.
+            // The fs context is forked so that the tasks sharing it stay in the
+            // old one.
+            let mns = self.mountNS.Fork(self)?;
+            let fsc = self.fsContext.Fork();
+            fsc.SetRootDirectory(&mns.Relocate(self, &self.mountNS, &fsc.RootDirectory())?);
+            fsc.SetWorkDirectory(&mns.Relocate(self, &self.mountNS, &fsc.WorkDirectory())?);
+            self.fsContext = fsc;
+            self.mountNS = mns;
+
+            let mut tlock = t.lock();
+            tlock.fsc = self.fsContext.clone();
+            tlock.mountns = self.mountNS.clone();
         }
 
         let mut tlock = t.lock();
@@ -605,7 +640,8 @@ impl Task {
                 return Err(Error::SysError(SysErr::EPERM));
             }
 
-            tlock.netns = true;
+            let userns = creds.lock().UserNamespace.clone();
+            tlock.netns = NetNamespace::New(&userns, false);
         }
 
         if opts.NewUTSNamespace {
@@ -643,6 +679,61 @@ impl Task {
 
         return Ok(());
     }
+
+    // SetNamespace moves the task into ns, see setns(2).
+    pub fn SetNamespace(&mut self, ns: &Namespace) -> Result<()> {
+        let t = self.Thread();
+        let creds = self.creds.clone();
+        if !t.HasCapability(Capability::CAP_SYS_ADMIN)
+            || !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &ns.UserNamespace())
+        {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        match ns {
+            Namespace::Mount(mns) => {
+                if !t.HasCapability(Capability::CAP_SYS_CHROOT) {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                // like Linux, the fs context of a multithreaded task is shared
+                // by its threads, which would be left in the old namespace
+                let tg = t.lock().tg.clone();
+                let signallock = tg.lock().signalLock.clone();
+                {
+                    let _s = signallock.lock();
+                    if tg.lock().tasksCount != 1 {
+                        return Err(Error::SysError(SysErr::EINVAL));
+                    }
+                }
+
+                // the root and the cwd move to the root of the namespace
+                let root = mns.Root();
+                self.fsContext.SetRootDirectory(&root);
+                self.fsContext.SetWorkDirectory(&root);
+                self.mountNS = mns.clone();
+                t.lock().mountns = mns.clone();
+            }
+            Namespace::Net(netns) => {
+                t.lock().netns = netns.clone();
+            }
+            Namespace::Uts(utsns) => {
+                self.utsns = utsns.clone();
+                t.lock().utsns = utsns.clone();
+            }
+            Namespace::Ipc(ipcns) => {
+                self.ipcns = ipcns.clone();
+                t.lock().ipcns = ipcns.clone();
+            }
+            // the children of a task can't be put into another pid namespace, and
+            // the user namespace can't be changed apart from the credentials
+            Namespace::Pid(_) | Namespace::User(_) => {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+        }
+
+        return Ok(());
+    }
 }
 
 pub fn CreateCloneTask(fromTask: &Task, toTask: &mut Task, userSp: u64) {
@@ -0,0 +1,559 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// ELF core dumps of the processes killed by a signal whose default action is
+// SignalAction::CORE. Like Linux, a core is only written when RLIMIT_CORE is not 0
+// and the process is dumpable, it is truncated at RLIMIT_CORE. The path comes from
+// /proc/sys/kernel/core_pattern, relative paths are resolved from the cwd of the
+// process and pipe patterns aren't supported. The notes hold the registers of the
+// thread which took the signal only, the other threads are being killed.
+
+use crate::qlib::mutex::*;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use super::super::super::addr::*;
+use super::super::super::common::*;
+use super::super::super::limits::*;
+use super::super::super::linux_def::*;
+use super::super::super::path::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::memmgr::metadata::*;
+use super::super::task::*;
+use super::super::SignalDef::*;
+
+lazy_static! {
+    pub static ref CORE_PATTERN: QMutex<String> = QMutex::new("core".to_string());
+}
+
+// CORENAME_MAX_SIZE in Linux
+pub const CORE_PATTERN_MAX: usize = 128;
+
+pub const ET_CORE: u16 = 4;
+pub const EM_X86_64: u16 = 62;
+pub const PT_LOAD: u32 = 1;
+pub const PT_NOTE: u32 = 4;
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+pub const NT_PRSTATUS: u32 = 1;
+pub const NT_PRPSINFO: u32 = 3;
+pub const NT_AUXV: u32 = 6;
+
+pub const ELF_HEADER_SIZE: u64 = 64;
+pub const ELF_PHDR_SIZE: u64 = 56;
+pub const ELF_SHDR_SIZE: u64 = 64;
+// e_phnum of a core with more program headers than it holds, the number is in
+// sh_info of the only section header then
+pub const PN_XNUM: u16 = 0xffff;
+// sizeof(struct elf_prstatus) and sizeof(struct elf_prpsinfo) on x86_64
+pub const PRSTATUS_SIZE: usize = 336;
+pub const PRPSINFO_SIZE: usize = 136;
+
+fn Put16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn Put32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn Put64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn PutNote(buf: &mut Vec<u8>, typ: u32, desc: &[u8]) {
+    let name = b"CORE\0";
+    Put32(buf, name.len() as u32);
+    Put32(buf, desc.len() as u32);
+    Put32(buf, typ);
+    buf.extend_from_slice(name);
+    buf.resize((buf.len() + 3) & !3, 0);
+    buf.extend_from_slice(desc);
+    buf.resize((buf.len() + 3) & !3, 0);
+}
+
+// PutXnumShdr puts the SHT_NULL section header which holds the number of program
+// headers in sh_info
+fn PutXnumShdr(buf: &mut Vec<u8>, phnum: u32) {
+    // sh_name, sh_type, sh_flags, sh_addr, sh_offset, sh_size
+    Put32(buf, 0);
+    Put32(buf, 0);
+    Put64(buf, 0);
+    Put64(buf, 0);
+    Put64(buf, 0);
+    Put64(buf, 0);
+    // sh_link, sh_info, sh_addralign, sh_entsize
+    Put32(buf, 0);
+    Put32(buf, phnum);
+    Put64(buf, 0);
+    Put64(buf, 0);
+}
+
+fn PutPhdr(
+    buf: &mut Vec<u8>,
+    typ: u32,
+    flags: u32,
+    offset: u64,
+    vaddr: u64,
+    filesz: u64,
+    memsz: u64,
+) {
+    Put32(buf, typ);
+    Put32(buf, flags);
+    Put64(buf, offset);
+    Put64(buf, vaddr);
+    // p_paddr
+    Put64(buf, 0);
+    Put64(buf, filesz);
+    Put64(buf, memsz);
+    // p_align
+    Put64(
+        buf,
+        if typ == PT_NOTE {
+            4
+        } else {
+            MemoryDef::PAGE_SIZE
+        },
+    );
+}
+
+// CoreWriter writes the core file sequentially, up to RLIMIT_CORE
+struct CoreWriter<'a> {
+    task: &'a Task,
+    file: File,
+    offset: u64,
+    limit: u64,
+}
+
+impl<'a> CoreWriter<'a> {
+    // Write returns false once the limit is reached
+    fn Write(&mut self, data: &[u8]) -> Result<bool> {
+        let mut data = data;
+        while data.len() > 0 {
+            if self.offset >= self.limit {
+                return Ok(false);
+            }
+
+            let len = core::cmp::min(data.len() as u64, self.limit - self.offset) as usize;
+            let iov = IoVec::NewFromAddr(data.as_ptr() as u64, len);
+            let n = self.file.Pwritev(self.task, &[iov], self.offset as i64)?;
+            if n <= 0 {
+                return Err(Error::SysError(SysErr::EIO));
+            }
+
+            self.offset += n as u64;
+            data = &data[n as usize..];
+        }
+
+        return Ok(true);
+    }
+
+    // Skip leaves a hole for the pages which aren't resident
+    fn Skip(&mut self, len: u64) -> bool {
+        if self.offset + len > self.limit {
+            self.offset = self.limit;
+            return false;
+        }
+
+        self.offset += len;
+        return true;
+    }
+}
+
+// ExpandCorePattern expands the core(5) specifiers %%, %p, %P, %i, %I, %u, %g, %s,
+// %t, %h and %e, the others are dropped.
+pub fn ExpandCorePattern(task: &Task, pattern: &str, signo: i32) -> String {
+    let thread = task.Thread();
+    let tg = thread.ThreadGroup();
+    let pidns = thread.PIDNamespace();
+    let root = pidns.Owner().Root();
+    let creds = thread.Credentials();
+    let userns = creds.lock().UserNamespace.clone();
+
+    let mut path = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('%') => path.push('%'),
+            Some('p') => path += &pidns.IDOfThreadGroup(&tg).to_string(),
+            Some('P') => path += &root.IDOfThreadGroup(&tg).to_string(),
+            Some('i') => path += &pidns.IDOfTask(&thread).to_string(),
+            Some('I') => path += &root.IDOfTask(&thread).to_string(),
+            Some('u') => {
+                let uid = creds.lock().RealKUID.In(&userns).OrOverflow();
+                path += &uid.0.to_string();
+            }
+            Some('g') => {
+                let gid = creds.lock().RealKGID.In(&userns).OrOverflow();
+                path += &gid.0.to_string();
+            }
+            Some('s') => path += &signo.to_string(),
+            Some('t') => path += &Task::RealTimeNow().Seconds().to_string(),
+            Some('h') => {
+                // the nodename as uname shows it, up to the first NUL
+                let host = thread.UTSNamespace().HostName();
+                let end = host.iter().position(|&c| c == 0).unwrap_or(host.len());
+                path += &String::from_utf8_lossy(&host[..end]);
+            }
+            // the comm can't hold a '/'
+            Some('e') => path += &thread.Name().replace('/', "!"),
+            _ => (),
+        }
+    }
+
+    return path;
+}
+
+impl Task {
+    // CoreDump writes the core of the process for the signal in info, it returns
+    // whether a core was written.
+    pub fn CoreDump(&self, info: &SignalInfo) -> bool {
+        let thread = self.Thread();
+        let limit = thread.ThreadGroup().Limits().Get(LimitType::Core).Cur;
+        if limit == 0 || thread.MemoryManager().Dumpability() == NOT_DUMPABLE {
+            return false;
+        }
+
+        let pattern = CORE_PATTERN.lock().to_string();
+        if pattern.len() == 0 || pattern.starts_with('|') {
+            info!("core dump: pattern {:?} isn't supported", pattern);
+            return false;
+        }
+
+        let path = ExpandCorePattern(self, &pattern, info.Signo);
+        let file = match self.CreateCoreFile(&path) {
+            Err(e) => {
+                info!("core dump: can't create {}: {:?}", path, e);
+                return false;
+            }
+            Ok(f) => f,
+        };
+
+        let mut w = CoreWriter {
+            task: self,
+            file: file,
+            offset: 0,
+            limit: limit,
+        };
+
+        match self.WriteCore(&mut w, info) {
+            Err(e) => {
+                info!("core dump: writing {} fails with {:?}", path, e);
+                return false;
+            }
+            Ok(()) => {
+                info!("core dump: {} bytes written to {}", w.offset, path);
+                return true;
+            }
+        }
+    }
+
+    // CreateCoreFile creates the core file, or truncates the existing regular file,
+    // without following a symlink at path. As in Linux, an existing file has to be
+    // owned by the dumping user and must not have other links.
+    fn CreateCoreFile(&self, path: &str) -> Result<File> {
+        let root = self.fsContext.RootDirectory();
+        let cwd = self.fsContext.WorkDirectory();
+        let mnt = self.mountNS.clone();
+        let (dir, name) = SplitLast(path);
+
+        let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+        let parent = mnt.FindDirent(self, &root, Some(cwd), dir, &mut remainingTraversals, true)?;
+        if !parent.Inode().StableAttr().IsDir() {
+            return Err(Error::SysError(SysErr::ENOTDIR));
+        }
+
+        let mut flags = FileFlags::FromFlags(Flags::O_WRONLY as u32);
+        flags.PWrite = true;
+        flags.LargeFile = true;
+
+        let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+        match mnt.FindDirent(
+            self,
+            &root,
+            Some(parent.clone()),
+            name,
+            &mut remainingTraversals,
+            false,
+        ) {
+            Ok(d) => {
+                let mut inode = d.Inode();
+                if !inode.StableAttr().IsRegular() {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                let attr = inode.UnstableAttr(self)?;
+                if attr.Links > 1 || attr.Owner.UID != self.Creds().lock().EffectiveKUID {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                inode.CheckPermission(
+                    self,
+                    &PermMask {
+                        write: true,
+                        ..Default::default()
+                    },
+                )?;
+                inode.Truncate(self, &d, 0)?;
+                return inode.GetFile(self, &d, &flags);
+            }
+            // some lookups of missing files fail with EPERM, as in createAt
+            Err(Error::SysError(SysErr::ENOENT)) | Err(Error::SysError(SysErr::EPERM)) => {
+                parent.Inode().CheckPermission(
+                    self,
+                    &PermMask {
+                        write: true,
+                        execute: true,
+                        ..Default::default()
+                    },
+                )?;
+
+                let perms = FilePermissions::FromMode(FileMode(0o600));
+                return parent.Create(self, &root, name, &flags, &perms);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    fn WriteCore(&self, w: &mut CoreWriter, info: &SignalInfo) -> Result<()> {
+        let mm = self.Thread().MemoryManager();
+        let vmas = mm.CoreDumpVmas();
+        let notes = self.CoreNotes(info);
+
+        let phnum = vmas.len() as u64 + 1;
+        let xnum = phnum >= PN_XNUM as u64;
+        let shoff = ELF_HEADER_SIZE + phnum * ELF_PHDR_SIZE;
+        let notesOffset = if xnum { shoff + ELF_SHDR_SIZE } else { shoff };
+        let dataOffset = Addr(notesOffset + notes.len() as u64).RoundUp()?.0;
+
+        let mut hdr = Vec::with_capacity(notesOffset as usize);
+        hdr.extend_from_slice(b"\x7fELF");
+        // ELFCLASS64, ELFDATA2LSB, EV_CURRENT, ELFOSABI_NONE
+        hdr.extend_from_slice(&[2, 1, 1, 0]);
+        hdr.resize(16, 0);
+        Put16(&mut hdr, ET_CORE);
+        Put16(&mut hdr, EM_X86_64);
+        Put32(&mut hdr, 1);
+        // e_entry, e_phoff, e_shoff
+        Put64(&mut hdr, 0);
+        Put64(&mut hdr, ELF_HEADER_SIZE);
+        Put64(&mut hdr, if xnum { shoff } else { 0 });
+        // e_flags, e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+        Put32(&mut hdr, 0);
+        Put16(&mut hdr, ELF_HEADER_SIZE as u16);
+        Put16(&mut hdr, ELF_PHDR_SIZE as u16);
+        if xnum {
+            Put16(&mut hdr, PN_XNUM);
+            Put16(&mut hdr, ELF_SHDR_SIZE as u16);
+            Put16(&mut hdr, 1);
+        } else {
+            Put16(&mut hdr, phnum as u16);
+            Put16(&mut hdr, 0);
+            Put16(&mut hdr, 0);
+        }
+        Put16(&mut hdr, 0);
+
+        PutPhdr(&mut hdr, PT_NOTE, 0, notesOffset, 0, notes.len() as u64, 0);
+        let mut offset = dataOffset;
+        for (r, perms, dumpLen) in &vmas {
+            let mut flags = 0;
+            if perms.Read() {
+                flags |= PF_R;
+            }
+            if perms.Write() {
+                flags |= PF_W;
+            }
+            if perms.Exec() {
+                flags |= PF_X;
+            }
+
+            PutPhdr(
+                &mut hdr,
+                PT_LOAD,
+                flags,
+                offset,
+                r.Start(),
+                *dumpLen,
+                r.Len(),
+            );
+            offset += *dumpLen;
+        }
+
+        if xnum {
+            PutXnumShdr(&mut hdr, phnum as u32);
+        }
+
+        if !w.Write(&hdr)? || !w.Write(&notes)? {
+            return Ok(());
+        }
+
+        if !w.Skip(dataOffset - w.offset) {
+            return Ok(());
+        }
+
+        let mut page = Vec::with_capacity(MemoryDef::PAGE_SIZE as usize);
+        page.resize(MemoryDef::PAGE_SIZE as usize, 0);
+        for (r, _, dumpLen) in &vmas {
+            let mut addr = r.Start();
+            while addr < r.Start() + *dumpLen {
+                let more = if mm.ReadResidentPage(addr, &mut page) {
+                    w.Write(&page)?
+                } else {
+                    w.Skip(MemoryDef::PAGE_SIZE)
+                };
+
+                if !more {
+                    return Ok(());
+                }
+
+                addr += MemoryDef::PAGE_SIZE;
+            }
+        }
+
+        // a hole at the end doesn't extend the file
+        let dirent = w.file.Dirent.clone();
+        let mut inode = dirent.Inode();
+        if inode.UnstableAttr(self)?.Size < w.offset as i64 {
+            inode.Truncate(self, &dirent, w.offset as i64)?;
+        }
+
+        return Ok(());
+    }
+
+    // CoreNotes returns the NT_PRSTATUS, NT_PRPSINFO and NT_AUXV notes
+    fn CoreNotes(&self, info: &SignalInfo) -> Vec<u8> {
+        let thread = self.Thread();
+        let tg = thread.ThreadGroup();
+        let pidns = thread.PIDNamespace();
+        let pid = pidns.IDOfThreadGroup(&tg);
+        let ppid = match thread.Parent() {
+            None => 0,
+            Some(p) => pidns.IDOfThreadGroup(&p.ThreadGroup()),
+        };
+        let pgrp = match tg.ProcessGroup() {
+            None => 0,
+            Some(pg) => pidns.IDOfProcessGroup(&pg),
+        };
+        let sid = match tg.Session() {
+            None => 0,
+            Some(s) => pidns.IDOfSession(&s),
+        };
+
+        let regs = *self.GetPtRegs();
+        let mut prstatus = Vec::with_capacity(PRSTATUS_SIZE);
+        // struct elf_siginfo, pr_cursig
+        Put32(&mut prstatus, info.Signo as u32);
+        Put32(&mut prstatus, info.Code as u32);
+        Put32(&mut prstatus, info.Errno as u32);
+        Put16(&mut prstatus, info.Signo as u16);
+        Put16(&mut prstatus, 0);
+        // pr_sigpend, pr_sighold
+        Put64(&mut prstatus, thread.PendingSignals().0);
+        Put64(&mut prstatus, thread.SignalMask().0);
+        Put32(&mut prstatus, pidns.IDOfTask(&thread) as u32);
+        Put32(&mut prstatus, ppid as u32);
+        Put32(&mut prstatus, pgrp as u32);
+        Put32(&mut prstatus, sid as u32);
+        // pr_utime, pr_stime, pr_cutime, pr_cstime
+        prstatus.resize(112, 0);
+        // pr_reg, in the order of struct user_regs_struct
+        for v in [
+            regs.r15,
+            regs.r14,
+            regs.r13,
+            regs.r12,
+            regs.rbp,
+            regs.rbx,
+            regs.r11,
+            regs.r10,
+            regs.r9,
+            regs.r8,
+            regs.rax,
+            regs.rcx,
+            regs.rdx,
+            regs.rsi,
+            regs.rdi,
+            regs.orig_rax,
+            regs.rip,
+            regs.cs,
+            regs.eflags,
+            regs.rsp,
+            regs.ss,
+            self.context.fs,
+            // gs_base, ds, es, fs, gs
+            0,
+            0,
+            0,
+            0,
+            0,
+        ] {
+            Put64(&mut prstatus, v);
+        }
+        // pr_fpvalid
+        prstatus.resize(PRSTATUS_SIZE, 0);
+
+        let mut prpsinfo = Vec::with_capacity(PRPSINFO_SIZE);
+        // pr_state, pr_sname, pr_zomb, pr_nice, pr_flag
+        prpsinfo.extend_from_slice(&[0, b'R', 0, 0]);
+        prpsinfo.resize(16, 0);
+        let creds = thread.Credentials();
+        let userns = creds.lock().UserNamespace.clone();
+        Put32(
+            &mut prpsinfo,
+            creds.lock().RealKUID.In(&userns).OrOverflow().0,
+        );
+        Put32(
+            &mut prpsinfo,
+            creds.lock().RealKGID.In(&userns).OrOverflow().0,
+        );
+        Put32(&mut prpsinfo, pid as u32);
+        Put32(&mut prpsinfo, ppid as u32);
+        Put32(&mut prpsinfo, pgrp as u32);
+        Put32(&mut prpsinfo, sid as u32);
+        let mut fname = thread.Name().into_bytes();
+        fname.resize(16, 0);
+        fname[15] = 0;
+        prpsinfo.extend_from_slice(&fname);
+        // pr_psargs, the comm as Linux falls back to when argv can't be read
+        prpsinfo.extend_from_slice(&fname);
+        prpsinfo.resize(PRPSINFO_SIZE, 0);
+
+        let mut auxv = Vec::new();
+        {
+            let mm = thread.MemoryManager();
+            let metadata = mm.metadata.lock();
+            for e in &metadata.auxv {
+                Put64(&mut auxv, e.Key as u64);
+                Put64(&mut auxv, e.Val);
+            }
+        }
+        // AT_NULL
+        Put64(&mut auxv, 0);
+        Put64(&mut auxv, 0);
+
+        let mut notes = Vec::new();
+        PutNote(&mut notes, NT_PRSTATUS, &prstatus);
+        PutNote(&mut notes, NT_PRPSINFO, &prpsinfo);
+        PutNote(&mut notes, NT_AUXV, &auxv);
+        return notes;
+    }
+}
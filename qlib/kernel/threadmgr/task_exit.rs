@@ -44,6 +44,9 @@ pub struct ExitStatus {
     // Signo is the signal that caused the exit. If the exit was not caused by
     // a signal, Signo is 0.
     pub Signo: i32,
+
+    // CoreDumped is true if a core was written for the signal in Signo
+    pub CoreDumped: bool,
 }
 
 impl ExitStatus {
@@ -51,6 +54,7 @@ impl ExitStatus {
         return ExitStatus {
             Code: code,
             Signo: signo,
+            CoreDumped: false,
         };
     }
 
@@ -63,7 +67,8 @@ impl ExitStatus {
     // Status returns the numeric representation of the ExitStatus returned by e.g.
     // the wait4() system call.
     pub fn Status(&self) -> u32 {
-        return (((self.Code as u32) & 0xff) << 8) | ((self.Signo as u32) & 0xff);
+        let core = if self.CoreDumped { WaitStatus::CORE } else { 0 };
+        return (((self.Code as u32) & 0xff) << 8) | ((self.Signo as u32) & 0xff) | core;
     }
 
     // ShellExitCode returns the numeric exit code that Bash would return for an
@@ -246,7 +251,8 @@ impl Thread {
     // ptrace.)
     //
     // Preconditions: The caller must be running on the task goroutine.
-    pub fn PrepareGroupExit(&self, es: ExitStatus) {
+    // PrepareGroupExit returns false if the thread group is already exiting
+    pub fn PrepareGroupExit(&self, es: ExitStatus) -> bool {
         let tg = self.lock().tg.clone();
         let lock = tg.lock().signalLock.clone();
         let _s = lock.lock();
@@ -265,7 +271,7 @@ impl Thread {
             // kernel/exit.c:do_group_exit() =>
             // include/linux/sched.h:signal_group_exit()).
             self.lock().exitStatus = tg.lock().exitStatus;
-            return;
+            return false;
         }
 
         tg.lock().exiting = true;
@@ -277,6 +283,19 @@ impl Thread {
                 sibling.lock().killLocked();
             }
         }
+
+        return true;
+    }
+
+    // SetCoreDumped marks the exit status of the thread group, set up by
+    // PrepareGroupExit, as the one of a process which dumped core.
+    pub fn SetCoreDumped(&self) {
+        let tg = self.lock().tg.clone();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        tg.lock().exitStatus.CoreDumped = true;
+        self.lock().exitStatus.CoreDumped = true;
     }
 
     // exitThreadGroup transitions t to TaskExitInitiated, indicating to t's thread
@@ -492,7 +511,7 @@ impl Thread {
         let children: Vec<Thread> = parent.lock().children.iter().cloned().collect();
         for child in &children {
             let child = child.clone();
-            if !opts.matchesTask(&child, &pidns) {
+            if !opts.matchesTask(&child, &pidns, false) {
                 continue;
             }
 
@@ -521,6 +540,14 @@ impl Thread {
                 continue;
             }
 
+            // If the waiter is the tracer of the child, the stops of the child
+            // are reported as ptrace-stops below.
+            let tracerTg = child.Tracer().map(|t| t.ThreadGroup());
+            if tracerTg == Some(parenttg.clone()) {
+                anyWaitableTasks = true;
+                continue;
+            }
+
             anyWaitableTasks = true;
             if opts.Events & EVENT_CHILD_GROUP_STOP != 0 {
                 let wr = self.waitCollectChildGroupStopLocked(&child, opts);
@@ -537,9 +564,105 @@ impl Thread {
             }
         }
 
+        let tracees: Vec<Thread> = parent.lock().ptraceTracees.iter().cloned().collect();
+        for tracee in &tracees {
+            if !opts.matchesTask(tracee, &pidns, true) {
+                continue;
+            }
+
+            anyWaitableTasks = true;
+            if opts.Events & EVENT_EXIT != 0 && !tracee.lock().exitTracerAcked {
+                let wr = self.waitCollectTraceeExitLocked(tracee, opts);
+                if wr.is_some() {
+                    return (wr, anyWaitableTasks);
+                }
+            }
+
+            if opts.Events & EVENT_TRACEE_STOP == 0
+                || tracee.lock().exitState >= TaskExitState::TaskExitInitiated
+            {
+                continue;
+            }
+
+            let wr = self.waitCollectTraceeStopLocked(tracee, opts);
+            if wr.is_some() {
+                return (wr, anyWaitableTasks);
+            }
+        }
+
         return (None, anyWaitableTasks);
     }
 
+    pub fn waitCollectTraceeStopLocked(
+        &self,
+        target: &Thread,
+        opts: &WaitOptions,
+    ) -> Option<WaitResult> {
+        let targetTg = target.ThreadGroup();
+        let lock = targetTg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        let code = {
+            let t = target.lock();
+            if !t.ptraceStoppedLocked() || t.ptraceCode == 0 {
+                return None;
+            }
+            t.ptraceCode
+        };
+
+        let pid = self.PIDNamespace().IDOfTaskLocked(target);
+
+        let creds = target.Credentials();
+        let userns = self.UserNamespace();
+        let uid = creds.lock().RealKUID.In(&userns).OrOverflow();
+
+        if opts.ConsumeEvent {
+            target.lock().ptraceCode = 0;
+        }
+
+        return Some(WaitResult {
+            Thread: target.clone(),
+            TID: pid,
+            UID: uid,
+            Event: EVENT_TRACEE_STOP,
+            Status: ((code as u32) & 0xff) << 8 | 0x7f,
+        });
+    }
+
+    pub fn waitCollectTraceeExitLocked(
+        &self,
+        target: &Thread,
+        opts: &WaitOptions,
+    ) -> Option<WaitResult> {
+        if !target.lock().exitTracerNotified {
+            return None;
+        }
+
+        let pid = self.PIDNamespace().IDOfTaskLocked(target);
+
+        let creds = target.Credentials();
+        let userns = self.UserNamespace();
+        let uid = creds.lock().RealKUID.In(&userns).OrOverflow();
+
+        let status = target.lock().exitStatus.Status();
+        if opts.ConsumeEvent {
+            target.lock().exitTracerAcked = true;
+            let tracer = target.lock().ptraceTracer.take();
+            if let Some(tracer) = tracer {
+                tracer.lock().ptraceTracees.remove(target);
+            }
+            target.exitNotifyLocked();
+        }
+
+        return Some(WaitResult {
+            Thread: target.clone(),
+            TID: pid,
+            UID: uid,
+            Event: EVENT_EXIT,
+            Status: status,
+        });
+    }
+
     pub fn waitCollectZombieLocked(
         &self,
         target: &Thread,
@@ -618,8 +741,8 @@ impl Thread {
 
                 // Update t's child max resident set size. The size will be the maximum
                 // of this thread's size and all its childrens' sizes.
-                let maxRSS = tglock.maxRSS;
-                if maxRSS < targettglock.maxRSS {
+                let childMaxRSS = tglock.childMaxRSS;
+                if childMaxRSS < targettglock.maxRSS {
                     tglock.childMaxRSS = targettglock.maxRSS;
                 }
 
@@ -733,7 +856,32 @@ impl Thread {
         let exitTracerNotified = t.lock().exitTracerNotified;
         if !exitTracerNotified {
             t.lock().exitTracerNotified = true;
-            t.lock().exitTracerAcked = true;
+            let tg = t.lock().tg.clone();
+            let leader = tg.lock().leader.Upgrade();
+            let parentTg = t.lock().parent.clone().map(|p| p.ThreadGroup());
+            match t.Tracer() {
+                None => t.lock().exitTracerAcked = true,
+                Some(tracer) => {
+                    let tracerTg = tracer.ThreadGroup();
+                    if Some(t.clone()) == leader && parentTg == Some(tracerTg.clone()) {
+                        // The parent reports the exit of the leader when the
+                        // tracer is in the thread group of the parent.
+                        t.lock().exitTracerAcked = true;
+                        t.forgetTracerLocked(0);
+                    } else {
+                        let lock = tracerTg.lock().signalLock.clone();
+                        {
+                            let _s = lock.lock();
+                            let info = t.exitNotificationSignal(Signal(Signal::SIGCHLD), &tracer);
+                            tracer.sendSignalLocked(&info, true).ok();
+                        }
+                        tracerTg
+                            .lock()
+                            .eventQueue
+                            .Notify(EVENT_EXIT | EVENT_TRACEE_STOP);
+                    }
+                }
+            }
         }
 
         let exitTracerAcked = t.lock().exitTracerAcked;
@@ -890,7 +1038,10 @@ impl Thread {
         info.SigChld().uid = kuid.In(&userns).OrOverflow().0;
 
         let signaled = self.lock().exitStatus.Signaled();
-        if signaled {
+        if signaled && self.lock().exitStatus.CoreDumped {
+            info.Code = SignalInfo::CLD_DUMPED;
+            info.SigChld().status = self.lock().exitStatus.Signo;
+        } else if signaled {
             info.Code = SignalInfo::CLD_KILLED;
             info.SigChld().status = self.lock().exitStatus.Signo;
         } else {
@@ -949,6 +1100,7 @@ impl Thread {
         self.ExitRobustList(task);
 
         self.UnstopVforkParent();
+        self.exitPtrace();
 
         // If this is the last task to exit from the thread group, release the
         // thread group's resources.
@@ -1159,7 +1311,9 @@ pub struct WaitOptions {
 
 impl WaitOptions {
     // Preconditions: The TaskSet mutex must be locked (for reading or writing).
-    pub fn matchesTask(&self, t: &Thread, pidns: &PIDNamespace) -> bool {
+    //
+    // Tracees are waitable regardless of __WCLONE, like in Linux.
+    pub fn matchesTask(&self, t: &Thread, pidns: &PIDNamespace, tracee: bool) -> bool {
         if self.SpecificTID != 0 {
             // && self.SpecificTID != *pidns.lock().tids.get(t).unwrap() {
             let id = match pidns.lock().tids.get(t) {
@@ -1183,6 +1337,10 @@ impl WaitOptions {
             return false;
         }
 
+        if tracee {
+            return true;
+        }
+
         let leader = tg.lock().leader.Upgrade();
         if Some(t.clone()) == leader && tg.lock().terminationSignal.0 == Signal::SIGCHLD {
             return self.NonCloneTasks;
@@ -59,8 +59,9 @@ impl ThreadInternal {
             // regardless of the setting of the "keep capabilities" flag.)
             if !newcreds.KeepCaps {
                 newcreds.PermittedCaps = CapSet(0);
-                newcreds.EffectiveCaps = CapSet(0);
             }
+            newcreds.EffectiveCaps = CapSet(0);
+            newcreds.AmbientCaps = CapSet(0);
         }
 
         // """
@@ -89,8 +90,10 @@ impl ThreadInternal {
     }
 
     pub fn setKGIDsUncheckedLocked(&mut self, newR: KGID, newE: KGID, newS: KGID) {
+        let oldE = self.creds.lock().EffectiveKGID;
+        // the creds are shared with the tasks forked from this one
+        self.creds = self.creds.Fork();
         let creds = self.creds.clone();
-        let oldE = creds.lock().EffectiveKGID;
         creds.lock().RealKGID = newR;
         creds.lock().EffectiveKGID = newE;
         creds.lock().SavedKGID = newS;
@@ -111,9 +114,9 @@ impl ThreadInternal {
     // disables the features we don't support anyway, is always set. This
     // drastically simplifies this function.
     //
-    // - We don't implement AT_SECURE, because no_new_privs always being set means
-    // that the conditions that require AT_SECURE never arise. (Compare Linux's
-    // security/commoncap.c:cap_bprm_set_creds() and cap_bprm_secureexec().)
+    // - The exec is secure (AT_SECURE) if it resets an effective UID or GID that
+    // differs from the real one, since the permitted capabilities can't grow.
+    // (Compare Linux's security/commoncap.c:cap_bprm_creds_from_file().)
     //
     // - We don't check for CAP_SYS_ADMIN in prctl(PR_SET_SECCOMP), since
     // seccomp-bpf is also allowed if the task has no_new_privs set.
@@ -122,8 +125,10 @@ impl ThreadInternal {
     // since no_new_privs being set has the same effect as the presence of an
     // unprivileged tracer.
     //
+    // It returns the AT_SECURE value of the new program.
+    //
     // Preconditions: t.mu must be locked.
-    pub fn updateCredsForExecLocked(&mut self) {
+    pub fn updateCredsForExecLocked(&mut self) -> bool {
         // """
         // During an execve(2), the kernel calculates the new capabilities of
         // the process using the following algorithm:
@@ -166,7 +171,10 @@ impl ThreadInternal {
         // effective capability sets, except those masked out by the capability
         // bounding set.
         // """ - capabilities(7)
-        // (ambient capability sets omitted)
+        //
+        // Privileged executables aren't implemented, so the ambient set is always
+        // preserved and "P'(permitted) |= P'(ambient)", "P'(effective) =
+        // F(effective) ? P'(permitted) : P'(ambient)".
         //
         // As the last paragraph implies, the case of "a set-user-ID root program
         // is being executed" also includes the case where (namespace) root is
@@ -229,7 +237,8 @@ impl ThreadInternal {
         // is a no-op. So we can just do C1 and C2 unconditionally.
         let EffectiveKGID = self.creds.lock().EffectiveKGID;
         let RealKGID = self.creds.lock().RealKGID;
-        if EffectiveKUID != RealKUID || EffectiveKGID != RealKGID {
+        let secure = EffectiveKUID != RealKUID || EffectiveKGID != RealKGID;
+        if secure {
             self.creds.lock().EffectiveKUID = RealKUID;
             self.creds.lock().EffectiveKGID = RealKGID;
             self.parentDeathSignal = Signal(0);
@@ -240,12 +249,14 @@ impl ThreadInternal {
         // the above.)
         self.creds.lock().SavedKUID = RealKUID;
         self.creds.lock().SavedKGID = RealKGID;
+        let AmbientCaps = self.creds.lock().AmbientCaps;
         self.creds.lock().PermittedCaps.0 &= newPermitted.0;
+        self.creds.lock().PermittedCaps.0 |= AmbientCaps.0;
         if fileEffective {
             let PermittedCaps = self.creds.lock().PermittedCaps;
             self.creds.lock().EffectiveCaps = PermittedCaps
         } else {
-            self.creds.lock().EffectiveCaps = CapSet(0);
+            self.creds.lock().EffectiveCaps = AmbientCaps;
         }
 
         // prctl(2): The "keep capabilities" value will be reset to 0 on subsequent
@@ -254,6 +265,7 @@ impl ThreadInternal {
 
         // "The bounding set is inherited at fork(2) from the thread's parent, and
         // is preserved across an execve(2)". So we're done.
+        return secure;
     }
 
     pub fn setUserNamespace(&mut self, ns: &UserNameSpace) -> Result<()> {
@@ -390,7 +402,7 @@ impl Thread {
         // ID is set to a value not equal to the previous real user ID, the saved
         // set-user-ID will be set to the new effective user ID."
         let mut newS = creds.lock().SavedKUID;
-        if r.Ok() || (e.Ok() && newE != effective) {
+        if r.Ok() || (e.Ok() && newE != real) {
             newS = newE;
         }
 
@@ -450,7 +462,7 @@ impl Thread {
         let r = creds.lock().RealKGID;
         let s = creds.lock().SavedKGID;
 
-        if kgid != r || kgid != s {
+        if kgid != r && kgid != s {
             return Err(Error::SysError(SysErr::EPERM));
         }
 
@@ -494,7 +506,7 @@ impl Thread {
         }
 
         let mut newS = SavedKGID;
-        if r.Ok() || (e.Ok() && newE != EffectiveKGID) {
+        if r.Ok() || (e.Ok() && newE != RealKGID) {
             newS = newE;
         }
 
@@ -580,7 +592,7 @@ impl Thread {
         let PermittedCaps = t.creds.lock().PermittedCaps;
         let BoundingCaps = t.creds.lock().BoundingCaps;
         if !t.creds.HasCapability(Capability::CAP_SETPCAP)
-            && (inheritable.0 & !(inheritable.0 | PermittedCaps.0)) != 0
+            && (inheritable.0 & !(InheritableCaps.0 | PermittedCaps.0)) != 0
         {
             return Err(Error::SysError(SysErr::EPERM));
         }
@@ -602,6 +614,9 @@ impl Thread {
         t.creds.lock().PermittedCaps = permitted;
         t.creds.lock().InheritableCaps = inheritable;
         t.creds.lock().EffectiveCaps = effective;
+        // "The ambient capability set obeys the invariant that no capability can
+        // ever be ambient if it is not both permitted and inheritable."
+        t.creds.lock().AmbientCaps.0 &= permitted.0 & inheritable.0;
 
         let task = Task::GetTask(t.taskId);
         task.creds = t.creds.clone();
@@ -620,6 +635,35 @@ impl Thread {
         return Ok(());
     }
 
+    // SetAmbientCapability implements prctl(PR_CAP_AMBIENT_RAISE/LOWER).
+    pub fn SetAmbientCapability(&self, cp: u64, raise: bool) -> Result<()> {
+        let mut t = self.lock();
+        let cap = CapSetOf(cp);
+        if raise {
+            // "The capability can't be raised unless it is already present in both
+            // the permitted and the inheritable sets of the thread."
+            let creds = t.creds.lock();
+            if cap.0 & creds.PermittedCaps.0 & creds.InheritableCaps.0 == 0 {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+        }
+
+        t.creds = t.creds.Fork();
+        if raise {
+            t.creds.lock().AmbientCaps.0 |= cap.0;
+        } else {
+            t.creds.lock().AmbientCaps.0 &= !cap.0;
+        }
+
+        return Ok(());
+    }
+
+    pub fn ClearAmbientCapabilities(&self) {
+        let mut t = self.lock();
+        t.creds = t.creds.Fork();
+        t.creds.lock().AmbientCaps = CapSet(0);
+    }
+
     pub fn SetUserNamespace(&self, ns: &UserNameSpace) -> Result<()> {
         let mut t = self.lock();
 
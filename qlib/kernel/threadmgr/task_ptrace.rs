@@ -0,0 +1,716 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// ptrace(2) within the sandbox, a subset of gVisor's kernel/ptrace.go. A tracer
+// attaches with PTRACE_TRACEME or PTRACE_ATTACH, the tracee then enters a
+// signal-delivery-stop for each signal it dequeues and the tracer learns about
+// the stops through wait. While the tracee is stopped, the tracer can access its
+// registers and memory and resume it with PTRACE_CONT, PTRACE_SINGLESTEP,
+// PTRACE_SYSCALL or PTRACE_DETACH. PTRACE_SEIZE, the PTRACE_O_* options but
+// PTRACE_O_TRACESYSGOOD and the ptrace event stops aren't supported.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
+
+use super::super::super::common::*;
+use super::super::super::linux::ptrace::*;
+use super::super::super::linux_def::*;
+use super::super::super::task_mgr::*;
+use super::super::arch::x86_64::arch_x86::*;
+use super::super::memmgr::metadata::*;
+use super::super::task::*;
+use super::super::SignalDef::*;
+use super::task_clone::*;
+use super::task_exit::*;
+use super::task_signals::*;
+use super::task_stop::*;
+use super::thread::*;
+
+// PtraceStop is a TaskStop placed on tasks in a ptrace-stop.
+pub struct PtraceStop {}
+
+impl TaskStop for PtraceStop {
+    fn Type(&self) -> TaskStopType {
+        return TaskStopType::PTRACESTOP;
+    }
+
+    fn Killable(&self) -> bool {
+        return true;
+    }
+}
+
+impl ThreadInternal {
+    pub fn ptraceStoppedLocked(&self) -> bool {
+        match &self.stop {
+            None => return false,
+            Some(s) => return s.Type() == TaskStopType::PTRACESTOP,
+        }
+    }
+
+    // beginPtraceStopLocked enters a ptrace-stop that reports code to the
+    // tracer. It returns false if the task has been killed or is already
+    // stopped.
+    //
+    // Preconditions: The TaskSet mutex must be locked. The signal mutex must be
+    // locked.
+    pub fn beginPtraceStopLocked(&mut self, code: i32, info: Option<Box<SignalInfo>>) -> bool {
+        if self.killedLocked() || self.stop.is_some() {
+            return false;
+        }
+
+        self.ptraceCode = code;
+        self.ptraceSiginfo = info;
+        self.beginInternalStopLocked(&Arc::new(PtraceStop {}));
+        return true;
+    }
+}
+
+impl Thread {
+    // Tracer returns t's ptrace tracer.
+    pub fn Tracer(&self) -> Option<Thread> {
+        return self.lock().ptraceTracer.clone();
+    }
+
+    // CanTrace checks that t is permitted to access target's state, as defined
+    // by ptrace(2), subsection "Ptrace access mode checking", with
+    // PTRACE_MODE_REALCREDS.
+    pub fn CanTrace(&self, target: &Thread) -> bool {
+        // "If the calling thread and the target thread are in the same thread
+        // group, access is always allowed." - ptrace(2)
+        if self.ThreadGroup() == target.ThreadGroup() {
+            return true;
+        }
+
+        let targetCreds = target.Credentials();
+        let (userns, uids, gids) = {
+            let c = targetCreds.lock();
+            (
+                c.UserNamespace.clone(),
+                [c.RealKUID, c.EffectiveKUID, c.SavedKUID],
+                [c.RealKGID, c.EffectiveKGID, c.SavedKGID],
+            )
+        };
+
+        if self.HasCapabilityIn(Capability::CAP_SYS_PTRACE, &userns) {
+            return true;
+        }
+
+        // "If the caller's real UID and real GID match the real, effective and
+        // saved UIDs and GIDs of the target, access is allowed, unless the target
+        // isn't dumpable."
+        let creds = self.Credentials();
+        let (uid, gid) = {
+            let c = creds.lock();
+            (c.RealKUID, c.RealKGID)
+        };
+
+        if uids.iter().any(|u| *u != uid) || gids.iter().any(|g| *g != gid) {
+            return false;
+        }
+
+        return target.MemoryManager().Dumpability() != NOT_DUMPABLE;
+    }
+
+    // PtraceTraceme makes t's parent its tracer.
+    pub fn PtraceTraceme(&self) -> Result<()> {
+        let ts = self.TaskSet();
+        let _l = ts.WriteLock();
+
+        if self.lock().ptraceTracer.is_some() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let parent = match self.lock().parent.clone() {
+            // In Linux, only init doesn't have a parent, and init is assumed
+            // never to invoke PTRACE_TRACEME.
+            None => return Err(Error::SysError(SysErr::EPERM)),
+            Some(p) => p,
+        };
+
+        if !parent.CanTrace(self) {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        if parent.lock().exitState != TaskExitState::TaskExitNone {
+            // Fail silently, as Linux does.
+            return Ok(());
+        }
+
+        self.lock().ptraceTracer = Some(parent.clone());
+        parent.lock().ptraceTracees.insert(self.clone());
+        return Ok(());
+    }
+
+    // PtraceAttach makes t the tracer of target and stops target with SIGSTOP.
+    pub fn PtraceAttach(&self, target: &Thread) -> Result<()> {
+        let targetTg = target.ThreadGroup();
+        if self.ThreadGroup() == targetTg {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let ts = self.TaskSet();
+        let _l = ts.WriteLock();
+
+        if !self.CanTrace(target) {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        if target.lock().ptraceTracer.is_some() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        // Attaching to zombies and dead tasks is not permitted; the exit
+        // notification logic relies on this.
+        if target.lock().exitState >= TaskExitState::TaskExitZombie {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        target.lock().ptraceTracer = Some(self.clone());
+        self.lock().ptraceTracees.insert(target.clone());
+
+        let groupStopSignal = {
+            let lock = targetTg.lock().signalLock.clone();
+            let _s = lock.lock();
+
+            target.sendSignalLocked(
+                &SignalInfo {
+                    Signo: Signal::SIGSTOP,
+                    Code: SignalInfo::SIGNAL_INFO_USER,
+                    ..Default::default()
+                },
+                false,
+            )?;
+
+            // Undocumented Linux feature: if the tracee is already group-stopped
+            // (and consequently will not report the SIGSTOP just sent), the stop
+            // becomes a ptrace-stop reporting the group stop signal.
+            let sig = targetTg.lock().groupStopSignal;
+            let mut t = target.lock();
+            let groupStopped = match &t.stop {
+                None => false,
+                Some(s) => s.Type() == TaskStopType::GROUPSTOP,
+            };
+
+            if groupStopped {
+                t.stop = Some(Arc::new(PtraceStop {}));
+                t.ptraceCode = sig.0;
+                t.ptraceSiginfo = None;
+                Some(sig)
+            } else {
+                None
+            }
+        };
+
+        if let Some(sig) = groupStopSignal {
+            self.signalStop(target, SignalInfo::CLD_TRAPPED, sig.0);
+            self.ThreadGroup()
+                .lock()
+                .eventQueue
+                .Notify(EVENT_TRACEE_STOP);
+        }
+
+        return Ok(());
+    }
+
+    // forgetTracerLocked detaches t from its tracer. If t is in a ptrace-stop,
+    // it is resumed with the signal sig.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn forgetTracerLocked(&self, sig: i32) {
+        let tracer = match self.lock().ptraceTracer.take() {
+            None => return,
+            Some(t) => t,
+        };
+        tracer.lock().ptraceTracees.remove(self);
+
+        {
+            let tg = self.ThreadGroup();
+            let lock = tg.lock().signalLock.clone();
+            let _s = lock.lock();
+
+            let mut t = self.lock();
+            if t.exitState == TaskExitState::TaskExitNone {
+                TaskId::New(t.taskId)
+                    .GetTask()
+                    .ptraceSyscall
+                    .store(false, Ordering::Release);
+            }
+            t.ptraceSysGood = false;
+            if t.ptraceStoppedLocked() {
+                if t.ptraceSinglestep {
+                    t.ptraceSinglestep = false;
+                    TaskId::New(t.taskId).GetTask().GetPtRegs().eflags &= !EFLAGS_TF;
+                }
+
+                t.ptraceCode = sig;
+                t.endInternalStopLocked();
+            }
+        }
+
+        // If t is a zombie whose exit the tracer hasn't acknowledged, its
+        // parent gets the exit notification now.
+        let exitTracerNotified = self.lock().exitTracerNotified;
+        let exitTracerAcked = self.lock().exitTracerAcked;
+        if exitTracerNotified && !exitTracerAcked {
+            self.lock().exitTracerAcked = true;
+            self.exitNotifyLocked();
+        }
+    }
+
+    // exitPtrace is called in the exit path to detach all of t's tracees.
+    pub fn exitPtrace(&self) {
+        let ts = self.TaskSet();
+        let _l = ts.WriteLock();
+
+        let tracees: Vec<Thread> = self.lock().ptraceTracees.iter().cloned().collect();
+        for tracee in &tracees {
+            tracee.forgetTracerLocked(0);
+        }
+    }
+
+    // ptraceResume ends the ptrace-stop of t for PTRACE_CONT, PTRACE_SINGLESTEP
+    // or PTRACE_SYSCALL, injecting the signal sig.
+    pub fn ptraceResume(&self, req: i64, sig: u64) -> Result<()> {
+        if sig != 0 && !Signal(sig as i32).IsValid() {
+            return Err(Error::SysError(SysErr::EIO));
+        }
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        let mut t = self.lock();
+        if !t.ptraceStoppedLocked() {
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+
+        let singlestep = req == PTRACE_SINGLESTEP;
+        let task = TaskId::New(t.taskId).GetTask();
+        task.ptraceSyscall
+            .store(req == PTRACE_SYSCALL, Ordering::Release);
+        let regs = task.GetPtRegs();
+        if singlestep {
+            regs.eflags |= EFLAGS_TF;
+        } else if t.ptraceSinglestep {
+            regs.eflags &= !EFLAGS_TF;
+        }
+
+        t.ptraceSinglestep = singlestep;
+        t.ptraceCode = sig as i32;
+        t.endInternalStopLocked();
+        return Ok(());
+    }
+}
+
+impl Task {
+    // PtraceGroupStop reports the group stop of a traced task to its tracer as
+    // a signal-delivery-stop, like Linux does for PTRACE_ATTACHed tracees, and
+    // waits for the tracer to resume the task. It returns None if the task
+    // isn't traced.
+    pub fn PtraceGroupStop(&self, sig: Signal, notifyParent: bool) -> Option<TaskRunState> {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+
+        {
+            let ts = tg.TaskSet();
+            let _r = ts.ReadLock();
+
+            let tracer = t.Tracer()?;
+            let sig = if sig.0 == 0 {
+                Signal(Signal::SIGSTOP)
+            } else {
+                sig
+            };
+
+            let began = {
+                let lock = tg.lock().signalLock.clone();
+                let _s = lock.lock();
+                t.lock().beginPtraceStopLocked(sig.0, None)
+            };
+
+            if began {
+                tracer.signalStop(&t, SignalInfo::CLD_TRAPPED, sig.0);
+                tracer
+                    .ThreadGroup()
+                    .lock()
+                    .eventQueue
+                    .Notify(EVENT_TRACEE_STOP);
+            }
+
+            // For consistency with Linux, if the parent and (thread group
+            // leader's) tracer are in the same thread group, deduplicate
+            // notifications.
+            let leader = tg.lock().leader.Upgrade().unwrap();
+            let parent = leader.lock().parent.clone();
+            if let Some(parent) = parent {
+                let ptg = parent.ThreadGroup();
+                if notifyParent && ptg != tracer.ThreadGroup() {
+                    parent.signalStop(&leader, SignalInfo::CLD_STOPPED, sig.0);
+                    ptg.lock().eventQueue.Notify(EVENT_CHILD_GROUP_STOP);
+                }
+            }
+        }
+
+        self.DoStop();
+        return Some(TaskRunState::RunInterrupt);
+    }
+
+    // PtraceSignalDeliveryStop enters a signal-delivery-stop for the dequeued
+    // signal info and delivers the signal the tracer resumes the task with.
+    pub fn PtraceSignalDeliveryStop(&mut self, info: Box<SignalInfo>) -> TaskRunState {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+
+        let stopped = {
+            let ts = tg.TaskSet();
+            let _r = ts.ReadLock();
+
+            match t.Tracer() {
+                None => false,
+                Some(tracer) => {
+                    let began = {
+                        let _s = lock.lock();
+                        t.lock()
+                            .beginPtraceStopLocked(info.Signo, Some(info.clone()))
+                    };
+
+                    if began {
+                        tracer.signalStop(&t, SignalInfo::CLD_TRAPPED, info.Signo);
+                        tracer
+                            .ThreadGroup()
+                            .lock()
+                            .eventQueue
+                            .Notify(EVENT_TRACEE_STOP);
+                    }
+                    began
+                }
+            }
+        };
+
+        let mut info = info;
+        if stopped {
+            self.DoStop();
+
+            let sig = {
+                let _s = lock.lock();
+                let mut tl = t.lock();
+                tl.ptraceSiginfo = None;
+                if tl.killedLocked() {
+                    return TaskRunState::RunInterrupt;
+                }
+                tl.ptraceCode
+            };
+
+            // The tracer suppressed the signal.
+            if sig == 0 {
+                return TaskRunState::RunInterrupt;
+            }
+
+            if sig != info.Signo {
+                let sender = match t.Tracer() {
+                    Some(tracer) => tracer,
+                    None => t.clone(),
+                };
+                info = Box::new(SignalInfoNoInfo(Signal(sig), &sender, &t));
+            }
+
+            // If the new signal is blocked, requeue it.
+            let _s = lock.lock();
+            let mask = t.lock().signalMask;
+            if SignalSet::New(Signal(sig)).0 & mask.0 != 0 {
+                t.sendSignalLocked(&info, false).ok();
+                return TaskRunState::RunInterrupt;
+            }
+        }
+
+        let act = {
+            let _s = lock.lock();
+            let sh = tg.lock().signalHandlers.clone();
+            sh.DequeAct(Signal(info.Signo))
+        };
+
+        return self.ThreadDeliverSignal(&info, &act);
+    }
+
+    // PtraceSyscallEnter enters a syscall-enter-stop if the task was resumed by
+    // PTRACE_SYSCALL. It returns true if the task has stopped, the tracer may
+    // then have changed the syscall number and the arguments in the registers.
+    pub fn PtraceSyscallEnter(&mut self) -> bool {
+        if !self.ptraceSyscall.load(Ordering::Acquire) {
+            return false;
+        }
+
+        // like Linux, the return value is ENOSYS until the syscall has run, so
+        // that a syscall skipped by the tracer fails
+        self.SetReturn(-SysErr::ENOSYS as u64);
+        return self.ptraceSyscallStop();
+    }
+
+    // PtraceSyscallExit enters a syscall-exit-stop if the task was resumed by
+    // PTRACE_SYSCALL.
+    pub fn PtraceSyscallExit(&mut self) {
+        if !self.ptraceSyscall.load(Ordering::Acquire) {
+            return;
+        }
+
+        self.ptraceSyscallStop();
+    }
+
+    // ptraceSyscallStop reports a syscall-stop to the tracer with SIGTRAP, or
+    // SIGTRAP | 0x80 with PTRACE_O_TRACESYSGOOD, and waits for the tracer to
+    // resume the task. A signal the tracer resumes the task with is sent to the
+    // task, like Linux's ptrace_report_syscall().
+    fn ptraceSyscallStop(&mut self) -> bool {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+
+        let stopped = {
+            let ts = tg.TaskSet();
+            let _r = ts.ReadLock();
+
+            let tracer = match t.Tracer() {
+                None => return false,
+                Some(tracer) => tracer,
+            };
+
+            let (began, code) = {
+                let _s = lock.lock();
+                let mut tl = t.lock();
+                let code = if tl.ptraceSysGood {
+                    Signal::SIGTRAP | 0x80
+                } else {
+                    Signal::SIGTRAP
+                };
+
+                let info = SignalInfo {
+                    Signo: Signal::SIGTRAP,
+                    Code: code,
+                    ..Default::default()
+                };
+                (tl.beginPtraceStopLocked(code, Some(Box::new(info))), code)
+            };
+
+            if began {
+                tracer.signalStop(&t, SignalInfo::CLD_TRAPPED, code);
+                tracer
+                    .ThreadGroup()
+                    .lock()
+                    .eventQueue
+                    .Notify(EVENT_TRACEE_STOP);
+            }
+            began
+        };
+
+        if !stopped {
+            return false;
+        }
+
+        self.DoStop();
+
+        let _s = lock.lock();
+        let sig = {
+            let mut tl = t.lock();
+            tl.ptraceSiginfo = None;
+            if tl.killedLocked() {
+                return true;
+            }
+            tl.ptraceCode
+        };
+
+        if sig != 0 {
+            t.sendSignalLocked(&SignalInfo::SignalInfoPriv(Signal(sig)), false)
+                .ok();
+        }
+
+        return true;
+    }
+
+    // Ptrace implements the ptrace system call.
+    pub fn Ptrace(&self, req: i64, pid: ThreadID, addr: u64, data: u64) -> Result<i64> {
+        let t = self.Thread();
+
+        // PTRACE_TRACEME ignores all other arguments.
+        if req == PTRACE_TRACEME {
+            t.PtraceTraceme()?;
+            return Ok(0);
+        }
+
+        let target = match t.PIDNamespace().TaskWithID(pid) {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(target) => target,
+        };
+
+        if req == PTRACE_ATTACH {
+            t.PtraceAttach(&target)?;
+            return Ok(0);
+        }
+
+        // All other requests need t to be the tracer of target, and all but
+        // PTRACE_KILL need target to be in a ptrace-stop.
+        let ts = t.TaskSet();
+        {
+            let _r = ts.ReadLock();
+            if target.Tracer() != Some(t.clone()) {
+                return Err(Error::SysError(SysErr::ESRCH));
+            }
+
+            let tg = target.ThreadGroup();
+            let lock = tg.lock().signalLock.clone();
+            let _s = lock.lock();
+            if req != PTRACE_KILL && !target.lock().ptraceStoppedLocked() {
+                return Err(Error::SysError(SysErr::ESRCH));
+            }
+        }
+
+        let targetTask = TaskId::New(target.lock().taskId).GetTask();
+        match req {
+            PTRACE_KILL => {
+                target.SendSignal(&SignalInfo {
+                    Signo: Signal::SIGKILL,
+                    Code: SignalInfo::SIGNAL_INFO_USER,
+                    ..Default::default()
+                })?;
+            }
+            PTRACE_PEEKTEXT | PTRACE_PEEKDATA => {
+                let mut word: u64 = 0;
+                let mm = target.MemoryManager();
+                if mm
+                    .CopyDataInManual(self, addr, &mut word as *mut _ as u64, 8, false)
+                    .is_err()
+                {
+                    return Err(Error::SysError(SysErr::EIO));
+                }
+
+                self.CopyOutObj(&word, data)?;
+            }
+            PTRACE_POKETEXT | PTRACE_POKEDATA => {
+                let mm = target.MemoryManager();
+                if mm
+                    .CopyDataOutForce(self, &data as *const _ as u64, addr, 8)
+                    .is_err()
+                {
+                    return Err(Error::SysError(SysErr::EIO));
+                }
+            }
+            PTRACE_GETREGS => {
+                let regs = targetTask.PtraceGetRegs();
+                self.CopyOutObj(&regs, data)?;
+            }
+            PTRACE_SETREGS => {
+                let regs: PtraceRegs = self.CopyInObj(data)?;
+                targetTask.PtraceSetRegs(&regs)?;
+            }
+            PTRACE_GETSIGINFO => {
+                let info = {
+                    let tg = target.ThreadGroup();
+                    let lock = tg.lock().signalLock.clone();
+                    let _s = lock.lock();
+                    target.lock().ptraceSiginfo.clone()
+                };
+
+                match info {
+                    None => return Err(Error::SysError(SysErr::EINVAL)),
+                    Some(info) => self.CopyOutObj(&*info, data)?,
+                }
+            }
+            PTRACE_CONT | PTRACE_SINGLESTEP | PTRACE_SYSCALL => target.ptraceResume(req, data)?,
+            PTRACE_SETOPTIONS => {
+                if data & !PTRACE_O_TRACESYSGOOD != 0 {
+                    return Err(Error::SysError(SysErr::EINVAL));
+                }
+
+                let tg = target.ThreadGroup();
+                let lock = tg.lock().signalLock.clone();
+                let _s = lock.lock();
+                target.lock().ptraceSysGood = data & PTRACE_O_TRACESYSGOOD != 0;
+            }
+            PTRACE_DETACH => {
+                if data != 0 && !Signal(data as i32).IsValid() {
+                    return Err(Error::SysError(SysErr::EIO));
+                }
+
+                let _l = ts.WriteLock();
+                target.forgetTracerLocked(data as i32);
+            }
+            _ => return Err(Error::SysError(SysErr::EIO)),
+        }
+
+        return Ok(0);
+    }
+
+    pub fn PtraceGetRegs(&self) -> PtraceRegs {
+        let regs = self.GetPtRegs();
+        return PtraceRegs {
+            r15: regs.r15,
+            r14: regs.r14,
+            r13: regs.r13,
+            r12: regs.r12,
+            rbp: regs.rbp,
+            rbx: regs.rbx,
+            r11: regs.r11,
+            r10: regs.r10,
+            r9: regs.r9,
+            r8: regs.r8,
+            rax: regs.rax,
+            rcx: regs.rcx,
+            rdx: regs.rdx,
+            rsi: regs.rsi,
+            rdi: regs.rdi,
+            orig_rax: regs.orig_rax,
+            rip: regs.rip,
+            cs: regs.cs,
+            eflags: regs.eflags,
+            rsp: regs.rsp,
+            ss: regs.ss,
+            fs_base: self.context.fs,
+            ..Default::default()
+        };
+    }
+
+    // PtraceSetRegs sets the registers of a stopped tracee. Like Linux, only
+    // the EFLAGS_PTRACE_MUTABLE flags can be changed and the segment
+    // selectors are kept.
+    pub fn PtraceSetRegs(&mut self, r: &PtraceRegs) -> Result<()> {
+        if !IsValidSegmentBase(r.fs_base) {
+            return Err(Error::SysError(SysErr::EIO));
+        }
+
+        let regs = self.GetPtRegs();
+        regs.r15 = r.r15;
+        regs.r14 = r.r14;
+        regs.r13 = r.r13;
+        regs.r12 = r.r12;
+        regs.rbp = r.rbp;
+        regs.rbx = r.rbx;
+        regs.r11 = r.r11;
+        regs.r10 = r.r10;
+        regs.r9 = r.r9;
+        regs.r8 = r.r8;
+        regs.rax = r.rax;
+        regs.rcx = r.rcx;
+        regs.rdx = r.rdx;
+        regs.rsi = r.rsi;
+        regs.rdi = r.rdi;
+        regs.orig_rax = r.orig_rax;
+        regs.rip = r.rip;
+        regs.eflags = (regs.eflags & !EFLAGS_PTRACE_MUTABLE) | (r.eflags & EFLAGS_PTRACE_MUTABLE);
+        regs.rsp = r.rsp;
+        self.context.fs = r.fs_base;
+        return Ok(());
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Restartable sequences, a subset of gVisor's kernel/rseq.go. A thread registers
+// a struct rseq with rseq(2) and points its rseq_cs at the descriptor of a
+// critical section before entering it. If the thread is preempted, migrated or
+// gets a signal while its ip is in the critical section, it resumes at the abort
+// handler instead. The cpu ids of the struct rseq are updated before the thread
+// returns to the application on another vcpu.
+
+use super::super::super::common::*;
+use super::super::super::linux::rseq::*;
+use super::super::super::linux_def::*;
+use super::super::task::*;
+use super::super::SignalDef::*;
+
+impl Task {
+    // SetRSeq registers the struct rseq at addr for the task.
+    pub fn SetRSeq(&mut self, addr: u64, length: u32, signature: u32) -> Result<()> {
+        let t = self.Thread();
+        let (oldAddr, oldSignature) = {
+            let t = t.lock();
+            (t.rseqAddr, t.rseqSignature)
+        };
+
+        if oldAddr != 0 {
+            if oldAddr != addr || length != RSEQ_SIZE {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            if oldSignature != signature {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+
+            return Err(Error::SysError(SysErr::EBUSY));
+        }
+
+        if length != RSEQ_SIZE || addr % RSEQ_ALIGN != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let cpu = self.CPU();
+        self.writeRSeqCPU(addr, cpu)
+            .map_err(|_| Error::SysError(SysErr::EFAULT))?;
+
+        let mut t = t.lock();
+        t.rseqAddr = addr;
+        t.rseqSignature = signature;
+        t.rseqCPU = cpu;
+        return Ok(());
+    }
+
+    // ClearRSeq unregisters the struct rseq at addr.
+    pub fn ClearRSeq(&mut self, addr: u64, length: u32, signature: u32) -> Result<()> {
+        let t = self.Thread();
+        let (oldAddr, oldSignature) = {
+            let t = t.lock();
+            (t.rseqAddr, t.rseqSignature)
+        };
+
+        if oldAddr == 0 || oldAddr != addr || length != RSEQ_SIZE {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        if oldSignature != signature {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        self.writeRSeqCPU(addr, RSEQ_CPU_ID_UNINITIALIZED)
+            .map_err(|_| Error::SysError(SysErr::EFAULT))?;
+
+        let mut t = t.lock();
+        t.rseqAddr = 0;
+        t.rseqSignature = 0;
+        t.rseqCPU = RSEQ_CPU_ID_UNINITIALIZED;
+        return Ok(());
+    }
+
+    // RSeqNotifyResume is called before the task returns to the application,
+    // with preempted set if it was preempted in the application. The critical
+    // section is aborted if the task was preempted or has moved to another vcpu,
+    // and the new vcpu is written to the struct rseq. A struct rseq the task
+    // can't access gets it a SIGSEGV, like in Linux.
+    pub fn RSeqNotifyResume(&mut self, preempted: bool) {
+        let (addr, lastCPU) = {
+            let t = self.Thread();
+            let t = t.lock();
+            (t.rseqAddr, t.rseqCPU)
+        };
+
+        if addr == 0 {
+            return;
+        }
+
+        let cpu = self.CPU();
+        if !preempted && cpu == lastCPU {
+            return;
+        }
+
+        let mut res = self.RSeqAbort();
+        if res.is_ok() && cpu != lastCPU {
+            res = self.writeRSeqCPU(addr, cpu);
+            if res.is_ok() {
+                self.Thread().lock().rseqCPU = cpu;
+            }
+        }
+
+        if res.is_err() {
+            let thread = self.Thread();
+            thread.forceSignal(Signal(Signal::SIGSEGV), false);
+            thread
+                .SendSignal(&SignalInfoPriv(Signal::SIGSEGV))
+                .expect("RSeqNotifyResume send signal fail");
+        }
+    }
+
+    // RSeqAbort moves the ip to the abort handler of the current critical
+    // section if it is in it. It is also called when a signal is delivered, so
+    // that the signal handler returns to the abort handler.
+    pub fn RSeqAbort(&mut self) -> Result<()> {
+        let (addr, signature) = {
+            let t = self.Thread();
+            let t = t.lock();
+            (t.rseqAddr, t.rseqSignature)
+        };
+
+        if addr == 0 {
+            return Ok(());
+        }
+
+        let csAddr: u64 = self.CopyInObj(addr + RSEQ_CS_OFFSET)?;
+        if csAddr == 0 {
+            return Ok(());
+        }
+
+        let cs: RSeqCriticalSection = self.CopyInObj(csAddr)?;
+        // the RSEQ_CS_FLAG_NO_RESTART_* flags are deprecated and refused by Linux
+        if cs.version != 0 || cs.flags != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let end = match cs.start.checked_add(cs.postCommitOffset) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(e) => e,
+        };
+
+        if cs.start <= cs.abort && cs.abort < end {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let pt = self.GetPtRegs();
+        if pt.rip < cs.start || pt.rip >= end {
+            // the critical section is over, clear it like Linux does
+            return self.CopyOutObj(&(0 as u64), addr + RSEQ_CS_OFFSET);
+        }
+
+        let abortSignature: u32 = self.CopyInObj(cs.abort.wrapping_sub(4))?;
+        if abortSignature != signature {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        self.CopyOutObj(&(0 as u64), addr + RSEQ_CS_OFFSET)?;
+        pt.rip = cs.abort;
+        return Ok(());
+    }
+
+    fn writeRSeqCPU(&self, addr: u64, cpu: i32) -> Result<()> {
+        // cpu_id_start stays a valid cpu after the unregistration
+        let cpuIdStart = if cpu < 0 { 0 } else { cpu as u32 };
+        let ids: [u32; 2] = [cpuIdStart, cpu as u32];
+        return self.CopyOutObj(&ids, addr + RSEQ_CPU_ID_START_OFFSET);
+    }
+}
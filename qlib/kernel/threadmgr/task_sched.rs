@@ -21,6 +21,7 @@ use super::super::super::common::*;
 use super::super::super::limits::*;
 use super::super::super::linux::time::*;
 use super::super::super::linux_def::*;
+use super::super::super::task_mgr::*;
 use super::super::super::usage::cpu::*;
 use super::super::super::vcpu_mgr::*;
 use super::super::kernel::cpuset::*;
@@ -33,6 +34,7 @@ use super::super::threadmgr::thread::*;
 use super::super::threadmgr::thread_group::*;
 use super::super::SignalDef::*;
 use super::super::Tsc;
+use super::super::SHARESPACE;
 use super::super::TSC;
 use super::task_exit::*;
 use super::task_stop::*;
@@ -95,10 +97,22 @@ pub struct TaskSchedInfoInternal {
     // SysTicks is the amount of time the task has spent executing
     pub SysTicks: i64,
 
-    // yieldCount is the number of times the task  has called
-    // Task.InterruptibleSleepStart, Task.UninterruptibleSleepStart, or
-    // Task.Yield(), voluntarily ceasing execution.
+    // YieldCount is the number of times the task has blocked, voluntarily
+    // ceasing execution.
     pub YieldCount: u64,
+
+    // InvoluntaryCount is the number of times the task has been switched out
+    // while still runnable, see Task::AccountContextSwitch.
+    pub InvoluntaryCount: u64,
+
+    // MinorFaults and MajorFaults count the page faults of the task, see
+    // Task::AccountPageFault.
+    pub MinorFaults: u64,
+    pub MajorFaults: u64,
+
+    // QuotaTicks is the part of UserTicks and SysTicks already charged to the
+    // cpu.max quota, see CpuThrottle.
+    pub QuotaTicks: i64,
 }
 
 impl TaskSchedInfoInternal {
@@ -154,6 +168,9 @@ impl ThreadInternal {
             UserTime: Tsc::Scale(userTime) * 1000,
             SysTime: Tsc::Scale(sysTime) * 1000,
             VoluntarySwitches: tsched.YieldCount,
+            InvoluntarySwitches: tsched.InvoluntaryCount,
+            MinorFaults: tsched.MinorFaults,
+            MajorFaults: tsched.MajorFaults,
         };
     }
 
@@ -220,8 +237,6 @@ impl ThreadInternal {
 
 impl Thread {
     pub fn NotifyRlimitCPUUpdated(&self) {
-        //todo: fix this.
-        info!("NotifyRlimitCPUUpdated: no more ticket, need fix");
         let ticker = self.lock().k.cpuClockTicker.clone();
         ticker.Atomically(|| {
             let tg = self.lock().tg.clone();
@@ -231,19 +246,15 @@ impl Thread {
             let lock = tg.lock().signalLock.clone();
             let _s = lock.lock();
 
-            let rlimitCPU = tg.lock().limits.Get(LimitType::CPU);
-            tg.lock().rlimitCPUSoftSetting = Setting {
-                Enabled: rlimitCPU.Cur != INFINITY,
-                Next: Time::FromNs(rlimitCPU.Cur as i64 * SECOND),
-                Period: SECOND,
-            };
+            tg.lock().resetRlimitCPUSoftSettingLocked();
 
+            let rlimitCPU = tg.lock().limits.Get(LimitType::CPU);
             if rlimitCPU.Max != INFINITY {
                 // Check if tg is already over the hard limit.
                 let now = TSC.Rdtsc();
                 let tgcpu = tg.cpuStatsAtLocked(now);
                 let tgProfNow = Time::FromNs(tgcpu.UserTime + tgcpu.SysTime);
-                if !tgProfNow.Before(Time::FromNs(rlimitCPU.Max as i64)) {
+                if !tgProfNow.Before(RlimitCPUTime(rlimitCPU.Max)) {
                     self.sendSignalLocked(
                         &SignalInfo::SignalInfoPriv(Signal(Signal::SIGKILL)),
                         true,
@@ -251,8 +262,6 @@ impl Thread {
                     .unwrap();
                 }
             }
-
-            tg.lock().updateCPUTimersEnabledLocked();
         });
     }
 
@@ -306,6 +315,10 @@ impl Thread {
         let mut t = self.lock();
         t.allowedCPUMask = mask.Copy();
         t.cpu = assignCPU(&mask, rootTID);
+        TaskId::New(t.taskId)
+            .GetTask()
+            .context
+            .SetCPUMask(VcpuMask(&mask));
         return Ok(());
     }
 
@@ -324,6 +337,18 @@ impl Thread {
         self.lock().niceness = n;
     }
 
+    pub fn Personality(&self) -> u32 {
+        return self.lock().personality;
+    }
+
+    // SetPersonality sets t's personality and returns the previous one.
+    pub fn SetPersonality(&self, persona: u32) -> u32 {
+        let mut t = self.lock();
+        let old = t.personality;
+        t.personality = persona;
+        return old;
+    }
+
     // NumaPolicy returns t's current numa policy.
     pub fn NumaPolicy(&self) -> (i32, u64) {
         let t = self.lock();
@@ -356,6 +381,34 @@ fn assignCPU(allowed: &CPUSet, tid: ThreadID) -> i32 {
     return cpu;
 }
 
+// VcpuMask returns the mask of the vcpus the scheduler may run a task with the
+// allowed CPUs on, 0 is any vcpu. CPU n runs on vcpu n, but vcpu 0 doesn't run
+// tasks and a mask only holds the first 64 vcpus, so CPU 0 and the CPUs from 64
+// on are spread over the vcpus 1 to 63.
+pub fn VcpuMask(allowed: &CPUSet) -> u64 {
+    let vcpuCnt = core::cmp::min(SHARESPACE.scheduler.vcpuCnt, 64);
+    if vcpuCnt < 2 {
+        return 0;
+    }
+
+    let mut mask = 0;
+    allowed.ForEachCPU(|c| {
+        let vcpu = if c >= 1 && c < vcpuCnt {
+            c
+        } else {
+            1 + c % (vcpuCnt - 1)
+        };
+        mask |= 1u64 << vcpu;
+    });
+
+    let all = (u64::MAX >> (64 - vcpuCnt)) & !1;
+    if mask == all {
+        return 0;
+    }
+
+    return mask;
+}
+
 impl Task {
     pub fn CPU(&self) -> i32 {
         /*let k = self.Thread().lock().k.clone();
@@ -370,7 +423,32 @@ impl Task {
     }
 }
 
+// RlimitCPUTime converts an RLIMIT_CPU value in seconds to a CPU clock time,
+// saturating instead of overflowing for large limits.
+pub fn RlimitCPUTime(secs: u64) -> Time {
+    if secs > i64::MAX as u64 {
+        return MAX_TIME;
+    }
+
+    return Time::FromUnix(secs as i64, 0);
+}
+
 impl ThreadGroupInternal {
+    // resetRlimitCPUSoftSettingLocked rearms the RLIMIT_CPU soft limit timer
+    // from the current limits: SIGXCPU at the soft limit, then once per second.
+    //
+    // Preconditions: The signal mutex must be locked.
+    pub fn resetRlimitCPUSoftSettingLocked(&mut self) {
+        let rlimitCPU = self.limits.Get(LimitType::CPU);
+        self.rlimitCPUSoftSetting = Setting {
+            Enabled: rlimitCPU.Cur != INFINITY,
+            Next: RlimitCPUTime(rlimitCPU.Cur),
+            Period: SECOND,
+        };
+
+        self.updateCPUTimersEnabledLocked();
+    }
+
     // Preconditions: The signal mutex must be locked.
     pub fn updateCPUTimersEnabledLocked(&mut self) {
         let rlimitCPU = self.limits.Get(LimitType::CPU);
@@ -451,10 +529,10 @@ impl TaskClock {
     pub fn Now(&self) -> Time {
         let stats = self.t.Upgrade().unwrap().CPUStats();
         if self.includeSys {
-            return Time::FromNs(Tsc::Scale(stats.UserTime + stats.SysTime) * 1000);
+            return Time::FromNs(stats.UserTime + stats.SysTime);
         }
 
-        return Time::FromNs(Tsc::Scale(stats.UserTime) * 1000);
+        return Time::FromNs(stats.UserTime);
     }
 
     pub fn WallTimeUntil(&self, t: Time, now: Time) -> Duration {
@@ -488,10 +566,10 @@ impl ThreadGroupClock {
         let stats = self.tg.Upgrade().unwrap().CPUStats();
         if self.includeSys {
             //error!("ThreadGroupClock usertime is {:x}, SysTime is {:x}", stats.UserTime, stats.SysTime);
-            return Time::FromNs(Tsc::Scale(stats.UserTime + stats.SysTime) * 1000);
+            return Time::FromNs(stats.UserTime + stats.SysTime);
         }
 
-        return Time::FromNs(Tsc::Scale(stats.UserTime) * 1000);
+        return Time::FromNs(stats.UserTime);
     }
 
     pub fn WallTimeUntil(&self, t: Time, now: Time) -> Duration {
@@ -615,8 +693,9 @@ impl KernelCPUClockTicker {
             let tasks: Vec<Thread> = tg.lock().tasks.iter().cloned().collect();
             for t in &tasks {
                 let tsched = t.lock().TaskSchedInfo();
-                tgUserTime += tsched.userTicksAt(now) as i64; // * CLOCK_TICK;
-                tgSysTime += tsched.sysTicksAt(now) as i64; // * CLOCK_TICK;
+                // exitedCPUStats is in ns, the live tasks are in cpu cycles
+                tgUserTime += Tsc::Scale(tsched.userTicksAt(now)) * 1000;
+                tgSysTime += Tsc::Scale(tsched.sysTicksAt(now)) * 1000;
 
                 if tsched.State == SchedState::RunningApp {
                     // Considered by ITIMER_VIRT, ITIMER_PROF, and RLIMIT_CPU
@@ -638,8 +717,8 @@ impl KernelCPUClockTicker {
                 }
             }
 
-            let tgVirtNow = Time::FromNs(Tsc::Scale(tgUserTime) * 1000);
-            let tgProfNow = Time::FromNs(Tsc::Scale(tgUserTime + tgSysTime) * 1000);
+            let tgVirtNow = Time::FromNs(tgUserTime);
+            let tgProfNow = Time::FromNs(tgUserTime + tgSysTime);
 
             // All of the following are standard (not real-time) signals, which are
             // automatically deduplicated, so we ignore the number of expirations.
@@ -695,8 +774,7 @@ impl KernelCPUClockTicker {
 
                 // RLIMIT_CPU hard limit
                 let rlimitCPUMax = tg.lock().limits.Get(LimitType::CPU).Max;
-                if rlimitCPUMax != INFINITY && !tgProfNow.Before(Time::FromSec(rlimitCPUMax as i64))
-                {
+                if rlimitCPUMax != INFINITY && !tgProfNow.Before(RlimitCPUTime(rlimitCPUMax)) {
                     profReceiver
                         .clone()
                         .unwrap()
@@ -980,6 +980,10 @@ impl Task {
             // Drop the signal mutex so we can take the TaskSet mutex.
             core::mem::drop(locker);
 
+            if let Some(state) = task.PtraceGroupStop(sig, notifyParent) {
+                return state;
+            }
+
             let _r = owner.read();
             let leader = tg.lock().leader.Upgrade().unwrap();
             if leader.lock().parent.is_none() {
@@ -1020,6 +1024,17 @@ impl Task {
             tg.lock().groupStopDequeued = true;
         }
 
+        if info.Signo != Signal::SIGKILL && t.lock().ptraceTracer.is_some() {
+            // The tracer might change this signal into a stop signal, in which
+            // case any SIGCONT received after the signal was originally dequeued
+            // should cancel it. This is consistent with Linux.
+            tg.lock().groupStopDequeued = true;
+            // This is unconditional in ptrace_stop().
+            t.lock().trapStopPending = false;
+            core::mem::drop(locker);
+            return task.PtraceSignalDeliveryStop(info);
+        }
+
         let sh = tg.lock().signalHandlers.clone();
         let act = sh.DequeAct(Signal(info.Signo));
         core::mem::drop(locker);
@@ -1080,11 +1095,18 @@ impl Task {
                     _ => (),
                 }*/
                 //Emit(&Event::UncaughtSignal(ucs)).unwrap();
-                self.Thread().PrepareGroupExit(ExitStatus {
-                    Code: 0,
+                let thread = self.Thread();
+                let first = thread.PrepareGroupExit(ExitStatus {
                     Signo: info.Signo,
+                    ..Default::default()
                 });
 
+                // the siblings are being killed, the memory stays as long as this
+                // thread doesn't exit
+                if first && sigact == SignalAction::CORE && self.CoreDump(info) {
+                    thread.SetCoreDumped();
+                }
+
                 return TaskRunState::RunExit;
             }
             SignalAction::STOP => self.Thread().initiateGroupStop(info),
@@ -1132,6 +1154,10 @@ impl Task {
     pub const XFEATURE_MASK_FPSSE: u64 = 0x3;
 
     pub fn deliverSignalToHandler(&mut self, info: &SignalInfo, sigAct: &SigAct) -> Result<()> {
+        // the handler returns to the abort handler of an interrupted rseq
+        // critical section
+        self.RSeqAbort()?;
+
         let pt = self.GetPtRegs();
         let mut userStack = Stack::New(pt.rsp - 128); // red zone
 
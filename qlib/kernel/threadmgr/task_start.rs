@@ -16,11 +16,13 @@ use alloc::string::String;
 
 //use super::super::syscalls::util::KLoadBinary;
 use super::super::super::auth::*;
+use super::super::fs::mount::*;
 use super::super::kernel::cpuset::*;
 use super::super::kernel::fd_table::*;
 use super::super::kernel::fs_context::*;
 use super::super::kernel::ipc_namespace::*;
 use super::super::kernel::kernel::*;
+use super::super::kernel::net_namespace::*;
 use super::super::kernel::uts_namespace::*;
 use super::super::memmgr::mm::*;
 use super::super::SignalDef::*;
@@ -64,9 +66,16 @@ pub struct TaskConfig {
     // Niceness is the niceness of the new task.
     pub Niceness: i32,
 
-    // If NetworkNamespaced is true, the new task should observe a non-root
-    // network namespace.
-    pub NetworkNamespaced: bool,
+    // Personality is the personality(2) of the new task.
+    pub Personality: u32,
+
+    // RSeqAddr and RSeqSignature are the rseq(2) registration of the new task,
+    // which a fork inherits.
+    pub RSeqAddr: u64,
+    pub RSeqSignature: u32,
+
+    // NetNamespace is the network namespace of the new task.
+    pub NetNamespace: NetNamespace,
 
     // AllowedCPUMask contains the cpus that this task can run on.
     pub AllowedCPUMask: CPUSet,
@@ -77,6 +86,9 @@ pub struct TaskConfig {
     // IPCNamespace is the IPCNamespace of the new task.
     pub IPCNamespace: IPCNamespace,
 
+    // MountNamespace is the mount namespace of the new task.
+    pub MountNamespace: MountNs,
+
     pub Blocker: Blocker,
 
     pub ContainerID: String,
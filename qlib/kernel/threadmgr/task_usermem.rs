@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use alloc::slice;
-use alloc::str;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -803,7 +802,7 @@ impl MemoryManager {
                 _ => (),
             }
 
-            let strlen = str.len();
+            let strlen = BytesFromString(&str).len();
             v.push(str);
             maxTotalSize -= (strlen as i32) + 1;
         }
@@ -830,31 +829,14 @@ impl MemoryManager {
 
         for i in 0..data.len() {
             if data[i] == 0 {
-                match str::from_utf8(&data[0..i]) {
-                    Ok(str) => {
-                        return (str.to_string(), Ok(()));
-                    }
-                    _ => {
-                        return (
-                            "".to_string(),
-                            Err(Error::Common("Invalid from_utf8".to_string())),
-                        )
-                    }
-                }
+                return (StringFromBytes(&data[0..i]), Ok(()));
             }
         }
 
-        match str::from_utf8(&data[0..maxlen]) {
-            Ok(str) => {
-                return (str.to_string(), Err(Error::SysError(SysErr::ENAMETOOLONG)));
-            }
-            _ => {
-                return (
-                    "".to_string(),
-                    Err(Error::Common("Invalid from_utf8".to_string())),
-                )
-            }
-        }
+        return (
+            StringFromBytes(&data[0..maxlen]),
+            Err(Error::SysError(SysErr::ENAMETOOLONG)),
+        );
     }
 
     // check whether the address range is legal.
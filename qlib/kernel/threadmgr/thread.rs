@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
+use alloc::boxed::Box;
 use alloc::collections::btree_set::BTreeSet;
 use alloc::string::String;
 use alloc::string::ToString;
@@ -24,11 +25,13 @@ use core::ops::Deref;
 use super::super::super::auth::*;
 use super::super::super::linux_def::*;
 use super::super::super::usage::io::*;
+use super::super::fs::mount::*;
 use super::super::kernel::cpuset::*;
 use super::super::kernel::fd_table::*;
 use super::super::kernel::fs_context::*;
 use super::super::kernel::ipc_namespace::*;
 use super::super::kernel::kernel::*;
+use super::super::kernel::net_namespace::*;
 use super::super::kernel::time::*;
 use super::super::kernel::uts_namespace::*;
 use super::super::kernel::waiter::queue::*;
@@ -80,6 +83,7 @@ pub struct ThreadInternal {
 
     pub utsns: UTSNamespace,
     pub ipcns: IPCNamespace,
+    pub mountns: MountNs,
 
     pub SignalQueue: Queue,
 
@@ -201,6 +205,56 @@ pub struct ThreadInternal {
     // trapNotifyPending is protected by the signal mutex.
     pub trapNotifyPending: bool,
 
+    // ptraceTracer is the task that is ptrace-attached to this one. If
+    // ptraceTracer is None, this task is not being traced.
+    //
+    // ptraceTracer is protected by the TaskSet mutex.
+    pub ptraceTracer: Option<Thread>,
+
+    // ptraceTracees is the set of tasks that this task is ptrace-attached to.
+    //
+    // ptraceTracees is protected by the TaskSet mutex.
+    pub ptraceTracees: BTreeSet<Thread>,
+
+    // ptraceCode is the signal of the ptrace-stop this task is in until the
+    // stop is reported by wait, after which it is 0. When the tracer resumes
+    // the task, ptraceCode is the signal the tracer injects, 0 for none.
+    //
+    // ptraceCode is protected by the signal mutex.
+    pub ptraceCode: i32,
+
+    // ptraceSiginfo is the value returned to the tracer by PTRACE_GETSIGINFO
+    // during a signal-delivery-stop.
+    //
+    // ptraceSiginfo is protected by the signal mutex.
+    pub ptraceSiginfo: Option<Box<SignalInfo>>,
+
+    // ptraceSinglestep is true if the task was last resumed by
+    // PTRACE_SINGLESTEP, so that the trap flag must be cleared when it stops.
+    //
+    // ptraceSinglestep is protected by the signal mutex.
+    pub ptraceSinglestep: bool,
+
+    // If ptraceSysGood is true, syscall-stops are reported with SIGTRAP | 0x80
+    // (PTRACE_O_TRACESYSGOOD).
+    //
+    // ptraceSysGood is protected by the signal mutex.
+    pub ptraceSysGood: bool,
+
+    // rseqAddr is the address of the struct rseq registered with rseq(2), 0 if
+    // there is none. Its critical section is aborted when the task is preempted,
+    // migrated or gets a signal, and its cpu ids are updated on the return to
+    // the application.
+    //
+    // rseqAddr, rseqSignature and rseqCPU are exclusive to the task goroutine.
+    pub rseqAddr: u64,
+
+    // rseqSignature is the value the abort handlers have to be preceded by.
+    pub rseqSignature: u32,
+
+    // rseqCPU is the cpu last written to the struct rseq, -1 if none was.
+    pub rseqCPU: i32,
+
     //pub containerID: String,
 
     // This is mostly a fake cpumask just for sched_set/getaffinity as we
@@ -240,13 +294,18 @@ pub struct ThreadInternal {
     pub numaPolicy: i32,
     pub numaNodeMask: u64,
 
-    // If netns is true, the task is in a non-root network namespace. Network
-    // namespaces aren't currently implemented in full; being in a network
-    // namespace simply prevents the task from observing any network devices
-    // (including loopback) or using abstract socket addresses (see unix(7)).
+    // personality is the execution domain set with personality(2). Only
+    // ADDR_NO_RANDOMIZE changes anything: the next exec gets a layout without
+    // randomization. It's inherited by the children and kept across exec.
+    pub personality: u32,
+
+    // netns is the network namespace of the task. Network namespaces aren't
+    // currently implemented in full; a non-root network namespace only has its
+    // own abstract socket addresses (see unix(7)), the network devices are
+    // shared with the host.
     //
     // netns is protected by mu. netns is owned by the task goroutine.
-    pub netns: bool,
+    pub netns: NetNamespace,
 
     // parentDeathSignal is sent to this task's thread group when its parent exits.
     //
@@ -265,6 +324,10 @@ pub struct ThreadInternal {
     // non-zero if the task goroutine should stop.
     pub stopCount: WaitGroup,
 
+    // stopped is true while the task waits in DoStop, when its user registers are
+    // saved and it doesn't run until stopCount drops to zero.
+    pub stopped: bool,
+
     // exitStatus is the task's exit status.
     //
     // exitStatus is protected by the signal mutex.
@@ -426,6 +489,14 @@ impl Thread {
         return self.lock().utsns.clone();
     }
 
+    pub fn MountNamespace(&self) -> MountNs {
+        return self.lock().mountns.clone();
+    }
+
+    pub fn NetworkNamespace(&self) -> NetNamespace {
+        return self.lock().netns.clone();
+    }
+
     pub fn MemoryManager(&self) -> MemoryManager {
         return self.lock().memoryMgr.clone();
     }
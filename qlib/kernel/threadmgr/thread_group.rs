@@ -276,6 +276,12 @@ pub struct ThreadGroupInternal {
     pub containerID: String,
     pub execId: Option<String>,
 
+    // oomScoreAdj is added to the badness of the thread group when the oom killer
+    // picks a victim, see /proc/[pid]/oom_score_adj. It is inherited on fork.
+    //
+    // oomScoreAdj is protected by the ThreadGroup lock.
+    pub oomScoreAdj: i32,
+
     // root track whether this threadgroup is directly started by container provisioning
     pub root: bool,
     pub timerMu: Arc<QMutex<()>>,
@@ -591,6 +597,10 @@ impl ThreadGroup {
         return self.lock().limits.clone();
     }
 
+    pub fn OOMScoreAdj(&self) -> i32 {
+        return self.lock().oomScoreAdj;
+    }
+
     pub fn release(&self) {
         // Timers must be destroyed without holding the TaskSet or signal mutexes
         // since timers send signals with Timer.mu locked.
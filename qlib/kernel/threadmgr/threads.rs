@@ -22,6 +22,7 @@ use spin::*;
 
 use super::super::super::auth::userns::*;
 use super::super::super::common::*;
+use super::super::super::linux::rseq::*;
 use super::super::super::linux_def::*;
 use super::super::super::usage::io::*;
 use super::super::kernel::kernel::*;
@@ -183,6 +184,7 @@ impl TaskSet {
             creds: cfg.Credentials.clone(),
             utsns: cfg.UTSNamespace.clone(),
             ipcns: cfg.IPCNamespace.clone(),
+            mountns: cfg.MountNamespace.clone(),
             SignalQueue: Queue::default(),
             tg: tg.clone(),
             parent: cfg.Parent.clone(),
@@ -202,15 +204,26 @@ impl TaskSet {
             groupStopAcknowledged: false,
             trapStopPending: false,
             trapNotifyPending: false,
+            ptraceTracer: None,
+            ptraceTracees: BTreeSet::new(),
+            ptraceCode: 0,
+            ptraceSiginfo: None,
+            ptraceSinglestep: false,
+            ptraceSysGood: false,
+            rseqAddr: cfg.RSeqAddr,
+            rseqSignature: cfg.RSeqSignature,
+            rseqCPU: RSEQ_CPU_ID_UNINITIALIZED,
             allowedCPUMask: cfg.AllowedCPUMask.Copy(),
             cpu: 0,
             niceness: cfg.Niceness,
             numaPolicy: 0,
             numaNodeMask: 0,
-            netns: false,
+            personality: cfg.Personality,
+            netns: cfg.NetNamespace.clone(),
             parentDeathSignal: Signal::default(),
             stop: None,
             stopCount: WaitGroup::default(),
+            stopped: false,
             exitStatus: ExitStatus::default(),
             exitState: TaskExitState::default(),
             exitTracerNotified: false,
@@ -233,6 +246,8 @@ impl TaskSet {
             let ioUsage = t.lock().ioUsage.clone();
             task.thread = Some(t.clone());
             task.ioUsage = ioUsage;
+            // the task has been accounting its cpu time and faults since it started
+            t.lock().sched = task.sched.clone();
         }
 
         {
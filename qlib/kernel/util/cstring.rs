@@ -17,8 +17,73 @@ use alloc::string::String;
 use alloc::vec::Vec;
 
 use super::super::super::common::*;
+use super::super::super::linux_def::*;
 use super::super::task::*;
 
+// Linux filenames are arbitrary bytes while the kernel keeps them in Strings. A
+// byte which isn't part of valid UTF-8 is stored as the private use char
+// ESCAPE_BASE + byte, in U+F780..U+F7FF. A valid char which falls in that range is
+// escaped byte by byte as well, so that BytesFromString(StringFromBytes(b)) == b
+// holds for any b.
+const ESCAPE_BASE: u32 = 0xF700;
+
+fn IsEscape(c: char) -> bool {
+    return c as u32 >= ESCAPE_BASE + 0x80 && c as u32 <= ESCAPE_BASE + 0xff;
+}
+
+fn PushEscaped(s: &mut String, bytes: &[u8]) {
+    for b in bytes {
+        s.push(char::from_u32(ESCAPE_BASE + *b as u32).unwrap());
+    }
+}
+
+pub fn StringFromBytes(bytes: &[u8]) -> String {
+    let mut ret = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while rest.len() > 0 {
+        let (valid, invalidLen) = match str::from_utf8(rest) {
+            Ok(s) => (s, 0),
+            Err(e) => {
+                let len = e.error_len().unwrap_or(rest.len() - e.valid_up_to());
+                (str::from_utf8(&rest[..e.valid_up_to()]).unwrap(), len)
+            }
+        };
+
+        for c in valid.chars() {
+            if IsEscape(c) {
+                let mut buf = [0; 4];
+                PushEscaped(&mut ret, c.encode_utf8(&mut buf).as_bytes());
+            } else {
+                ret.push(c);
+            }
+        }
+
+        let end = valid.len() + invalidLen;
+        PushEscaped(&mut ret, &rest[valid.len()..end]);
+        rest = &rest[end..];
+    }
+
+    return ret;
+}
+
+pub fn BytesFromString(s: &str) -> Vec<u8> {
+    if !s.chars().any(IsEscape) {
+        return s.as_bytes().to_vec();
+    }
+
+    let mut ret = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        if IsEscape(c) {
+            ret.push((c as u32 - ESCAPE_BASE) as u8);
+        } else {
+            let mut buf = [0; 4];
+            ret.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+
+    return ret;
+}
+
 #[derive(Debug)]
 pub struct CString {
     pub data: Vec<u8>,
@@ -26,12 +91,7 @@ pub struct CString {
 
 impl CString {
     pub fn New(s: &str) -> Self {
-        let s = s.as_bytes();
-        let mut data = Vec::with_capacity(s.len() + 1);
-        for i in 0..s.len() {
-            data.push(s[i])
-        }
-
+        let mut data = BytesFromString(s);
         data.push(0);
         return Self { data };
     }
@@ -64,7 +124,7 @@ impl CString {
         return &self.data[..];
     }
 
-    pub const MAX_STR_LEN: usize = 4096;
+    pub const MAX_STR_LEN: usize = PATH_MAX;
     pub fn ToString(task: &Task, addr: u64) -> Result<String> {
         return Self::ToStringWithLen(task, addr, Self::MAX_STR_LEN);
     }
@@ -81,3 +141,31 @@ impl CString {
         return task.CopyInString(addr, len);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_NonUtf8RoundTrip() {
+        let names: [&[u8]; 5] = [
+            b"plain",
+            b"caf\xe9",
+            b"\xff\xfe/\xc3",
+            // a valid char in the escape range
+            "\u{f7e9}x".as_bytes(),
+            b"\xef\x9f\xa9\xe9",
+        ];
+
+        for name in names.iter() {
+            let s = StringFromBytes(name);
+            assert_eq!(&BytesFromString(&s)[..], *name);
+        }
+
+        assert_eq!(StringFromBytes(b"plain"), "plain");
+        assert_ne!(
+            StringFromBytes(b"\xe9"),
+            StringFromBytes("\u{f7e9}".as_bytes())
+        );
+    }
+}
@@ -17,6 +17,7 @@ use core::sync::atomic::Ordering;
 
 use super::asm::*;
 //use super::IOURING;
+use super::super::cpuid::*;
 use super::super::singleton::*;
 use super::super::vcpu_mgr::*;
 use super::SHARESPACE;
@@ -51,6 +52,20 @@ pub enum MSR {
     /* Auxiliary TSC */
 }
 
+// HasRdtscp returns true if the vcpu has rdtscp.
+pub fn HasRdtscp() -> bool {
+    let (_, _, _, edx) = HostID(0x8000_0001, 0);
+    return edx & (1 << 27) != 0;
+}
+
+// SetTscAux sets the IA32_TSC_AUX of the vcpu to its id, like Linux's
+// setup_getcpu(), so that the vDSO getcpu reads it with rdtscp.
+pub fn SetTscAux(vcpuId: u64) {
+    if HasRdtscp() {
+        WriteMsr(MSR::MSR_TSC_AUX as u32, vcpuId);
+    }
+}
+
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
 #[repr(u16)]
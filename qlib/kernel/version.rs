@@ -28,6 +28,16 @@ pub const LINUX_RELEASE: &'static str = "4.4.0";
 // LinuxVersion is the version info advertised by gVisor.
 pub const LINUX_VERSION: &'static str = "#1 SMP Sun Jan 10 15:06:54 PST 2016";
 
+impl Arch {
+    // Machine is the machine name of uname().
+    pub fn Machine(&self) -> &'static str {
+        match self {
+            Self::AMD64 => return "x86_64",
+            Self::ARM64 => return "aarch64",
+        }
+    }
+}
+
 pub struct Version {
     // Operating system name (e.g. "Linux").
     pub OS: &'static str,
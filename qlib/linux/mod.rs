@@ -20,6 +20,8 @@ pub mod limits;
 pub mod membarrier;
 pub mod msgqueue;
 pub mod netdevice;
+pub mod ptrace;
+pub mod rseq;
 pub mod rusage;
 pub mod sem;
 pub mod shm;
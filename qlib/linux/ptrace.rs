@@ -0,0 +1,66 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// ptrace(2) requests, from include/uapi/linux/ptrace.h.
+pub const PTRACE_TRACEME: i64 = 0;
+pub const PTRACE_PEEKTEXT: i64 = 1;
+pub const PTRACE_PEEKDATA: i64 = 2;
+pub const PTRACE_POKETEXT: i64 = 4;
+pub const PTRACE_POKEDATA: i64 = 5;
+pub const PTRACE_CONT: i64 = 7;
+pub const PTRACE_KILL: i64 = 8;
+pub const PTRACE_SINGLESTEP: i64 = 9;
+pub const PTRACE_GETREGS: i64 = 12;
+pub const PTRACE_SETREGS: i64 = 13;
+pub const PTRACE_ATTACH: i64 = 16;
+pub const PTRACE_DETACH: i64 = 17;
+pub const PTRACE_SYSCALL: i64 = 24;
+pub const PTRACE_SETOPTIONS: i64 = 0x4200;
+pub const PTRACE_GETSIGINFO: i64 = 0x4202;
+
+// PTRACE_SETOPTIONS options.
+pub const PTRACE_O_TRACESYSGOOD: u64 = 1;
+
+// PtraceRegs is struct user_regs_struct, from arch/x86/include/asm/user_64.h.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PtraceRegs {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
@@ -0,0 +1,39 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// rseq(2) flags, from include/uapi/linux/rseq.h.
+pub const RSEQ_FLAG_UNREGISTER: i32 = 1 << 0;
+
+// The cpu_id of a struct rseq that isn't registered.
+pub const RSEQ_CPU_ID_UNINITIALIZED: i32 = -1;
+
+// RSEQ_SIZE is the size of the original struct rseq, the only one supported.
+pub const RSEQ_SIZE: u32 = 32;
+pub const RSEQ_ALIGN: u64 = 32;
+
+// The offsets of the fields of struct rseq: the u32 cpu_id_start and cpu_id are
+// followed by the u64 address of the current struct rseq_cs.
+pub const RSEQ_CPU_ID_START_OFFSET: u64 = 0;
+pub const RSEQ_CS_OFFSET: u64 = 8;
+
+// RSeqCriticalSection is struct rseq_cs, from include/uapi/linux/rseq.h.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RSeqCriticalSection {
+    pub version: u32,
+    pub flags: u32,
+    pub start: u64,
+    pub postCommitOffset: u64,
+    pub abort: u64,
+}
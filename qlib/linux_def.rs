@@ -551,6 +551,22 @@ pub struct LibcStatfs {
     pub Spare: [u64; 4],
 }
 
+// Statfs mount flags, from include/linux/statfs.h.
+pub struct StatfsFlags {}
+
+impl StatfsFlags {
+    pub const ST_RDONLY: u64 = 0x0001;
+    pub const ST_NOSUID: u64 = 0x0002;
+    pub const ST_NODEV: u64 = 0x0004;
+    pub const ST_NOEXEC: u64 = 0x0008;
+    pub const ST_SYNCHRONOUS: u64 = 0x0010;
+    pub const ST_VALID: u64 = 0x0020;
+    pub const ST_MANDLOCK: u64 = 0x0040;
+    pub const ST_NOATIME: u64 = 0x0400;
+    pub const ST_NODIRATIME: u64 = 0x0800;
+    pub const ST_RELATIME: u64 = 0x1000;
+}
+
 // Filesystem types used in statfs(2).
 // See linux/magic.h.
 pub struct FSMagic {}
@@ -559,6 +575,7 @@ impl FSMagic {
     pub const ANON_INODE_FS_MAGIC: u64 = 0x09041934;
     pub const DEVPTS_SUPER_MAGIC: u64 = 0x00001cd1;
     pub const EXT_SUPER_MAGIC: u64 = 0xef53;
+    pub const NSFS_MAGIC: u64 = 0x6e736673;
     pub const OVERLAYFS_SUPER_MAGIC: u64 = 0x794c7630;
     pub const PIPEFS_MAGIC: u64 = 0x50495045;
     pub const PROC_SUPER_MAGIC: u64 = 0x9fa0;
@@ -873,7 +890,7 @@ impl FilePermissions {
             m |= ModeType::S_ISUID;
         }
 
-        if self.SetUid {
+        if self.SetGid {
             m |= ModeType::S_ISGID;
         }
 
@@ -1055,6 +1072,8 @@ impl LibcConst {
     pub const AF_UNSPECTXA: u64 = 0x80;
     pub const AF_UNSPECW: u64 = 0x0;
     pub const AF_UNSPECX: u64 = 0x8;
+    pub const BLKFLSBUF: u64 = 0x1261;
+    pub const BLKRRPART: u64 = 0x125f;
     pub const CLONE_CHILD_CLEARTID: u64 = 0x200000;
     pub const CLONE_CHILD_SETTID: u64 = 0x1000000;
     pub const CLONE_DETACHED: u64 = 0x400000;
@@ -1450,6 +1469,7 @@ impl LibcConst {
     pub const MNT_DETACH: u64 = 0x2;
     pub const MNT_EXPIRE: u64 = 0x4;
     pub const MNT_FORCE: u64 = 0x1;
+    pub const UMOUNT_NOFOLLOW: u64 = 0x8;
     pub const MSG_CMSG_CLOEXEC: u64 = 0x40000000;
     pub const MSG_CONFIRM: u64 = 0x800;
     pub const MSG_CTRUNC: u64 = 0x8;
@@ -2131,6 +2151,8 @@ impl Cmd {
     pub const F_GETSIG: i32 = 11;
     pub const F_SETOWN_EX: i32 = 15;
     pub const F_GETOWN_EX: i32 = 16;
+    pub const F_SETLEASE: i32 = 1024;
+    pub const F_GETLEASE: i32 = 1024 + 1;
     pub const F_DUPFD_CLOEXEC: i32 = 1024 + 6;
     pub const F_SETPIPE_SZ: i32 = 1024 + 7;
     pub const F_GETPIPE_SZ: i32 = 1024 + 8;
@@ -2683,6 +2705,44 @@ impl IoCtlCmd {
     pub const SIOCSPGRP: u64 = 0x00008902;
     pub const FIOGETOWN: u64 = 0x00008903;
     pub const SIOCGPGRP: u64 = 0x00008904;
+    pub const FICLONE: u64 = 0x40049409;
+    pub const FICLONERANGE: u64 = 0x4020940d;
+    pub const FS_IOC_FIEMAP: u64 = 0xc020660b;
+}
+
+// FileCloneRange is struct file_clone_range, the argument of FICLONERANGE.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileCloneRange {
+    pub SrcFd: i64,
+    pub SrcOffset: u64,
+    pub SrcLength: u64,
+    pub DestOffset: u64,
+}
+
+// Fiemap is the header of struct fiemap, the argument of FS_IOC_FIEMAP. The array of
+// ExtentCount FiemapExtent follows it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fiemap {
+    pub Start: u64,
+    pub Length: u64,
+    pub Flags: u32,
+    pub MappedExtents: u32,
+    pub ExtentCount: u32,
+    pub Reserved: u32,
+}
+
+// FiemapExtent is struct fiemap_extent.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FiemapExtent {
+    pub Logical: u64,
+    pub Physical: u64,
+    pub Length: u64,
+    pub Reserved64: [u64; 2],
+    pub Flags: u32,
+    pub Reserved: [u32; 3],
 }
 
 #[derive(Clone, PartialEq, Copy, Debug)]
@@ -2836,6 +2896,26 @@ impl MAdviseOp {
     pub const MADV_DONTCHGME: i32 = 201;
 }
 
+pub struct Personality {}
+
+impl Personality {
+    pub const PER_LINUX: u32 = 0x0000;
+    pub const UNAME26: u32 = 0x0020000;
+    pub const ADDR_NO_RANDOMIZE: u32 = 0x0040000;
+    pub const FDPIC_FUNCPTRS: u32 = 0x0080000;
+    pub const MMAP_PAGE_ZERO: u32 = 0x0100000;
+    pub const ADDR_COMPAT_LAYOUT: u32 = 0x0200000;
+    pub const READ_IMPLIES_EXEC: u32 = 0x0400000;
+    pub const ADDR_LIMIT_32BIT: u32 = 0x0800000;
+    pub const SHORT_INODE: u32 = 0x1000000;
+    pub const WHOLE_SECONDS: u32 = 0x2000000;
+    pub const STICKY_TIMEOUTS: u32 = 0x4000000;
+    pub const ADDR_LIMIT_3GB: u32 = 0x8000000;
+
+    // personality(0xffffffff) only returns the current personality
+    pub const QUERY: u32 = 0xffffffff;
+}
+
 pub struct CloneOp {}
 
 impl CloneOp {
@@ -3043,6 +3123,7 @@ impl MmapFlags {
     pub const MAP_NONBLOCK: u64 = 1 << 16;
     pub const MAP_STACK: u64 = 1 << 17;
     pub const MAP_HUGETLB: u64 = 1 << 18;
+    pub const MAP_FIXED_NOREPLACE: u64 = 1 << 20;
 
     pub const MAP_SOCKT_READ: u64 = 1 << 31;
 }
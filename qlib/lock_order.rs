@@ -0,0 +1,480 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Lock order tracking of the QMutexIntern locks. It is built for the unit tests
+// and with the "lock_order" feature, which also makes QMutex a QMutexIntern, so
+// that every QMutex of the kernel and the qvisor is tracked.
+//
+// Taking a lock while holding other ones records that the held locks go before
+// it. A lock taken after another one which, directly or through other locks, was
+// taken before it is a lock order inversion which can deadlock: it is logged with
+// the places of the two lock() calls. The tracking works on the lock instances
+// and its tables have a fixed size, locks over the capacity aren't tracked.
+
+#[cfg(any(test, feature = "lock_order"))]
+use core::panic::Location;
+#[cfg(any(test, feature = "lock_order"))]
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(any(test, feature = "lock_order"))]
+use spin::Mutex;
+
+#[cfg(any(test, feature = "lock_order"))]
+pub const LOCK_CLASS_COUNT: usize = 4096;
+// the max number of locks a lock is recorded to go before
+#[cfg(any(test, feature = "lock_order"))]
+pub const LOCK_EDGE_COUNT: usize = 8;
+#[cfg(any(test, feature = "lock_order"))]
+pub const LOCK_HOLDER_COUNT: usize = 256;
+#[cfg(any(test, feature = "lock_order"))]
+pub const LOCK_HELD_COUNT: usize = 16;
+
+#[cfg(any(test, feature = "lock_order"))]
+const TOMBSTONE: u64 = u64::MAX;
+
+#[cfg(any(test, feature = "lock_order"))]
+#[derive(Clone, Copy)]
+struct LockClass {
+    // the address of the lock, 0 for an empty slot
+    addr: u64,
+    // bumped when the slot is reused, an edge to an older generation is stale
+    gen: u16,
+    // where the lock was last taken
+    site: Option<&'static Location<'static>>,
+    edgeCnt: usize,
+    // (slot, generation) of the locks taken while holding this one
+    edges: [(u16, u16); LOCK_EDGE_COUNT],
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+impl LockClass {
+    const fn New() -> Self {
+        return Self {
+            addr: 0,
+            gen: 0,
+            site: None,
+            edgeCnt: 0,
+            edges: [(0, 0); LOCK_EDGE_COUNT],
+        };
+    }
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+#[derive(Clone, Copy)]
+struct LockHolder {
+    // the task or thread holding the locks, 0 for an empty slot
+    id: u64,
+    cnt: usize,
+    // (lock address, class slot)
+    held: [(u64, u16); LOCK_HELD_COUNT],
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+impl LockHolder {
+    const fn New() -> Self {
+        return Self {
+            id: 0,
+            cnt: 0,
+            held: [(0, 0); LOCK_HELD_COUNT],
+        };
+    }
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+pub struct LockOrder {
+    classes: [LockClass; LOCK_CLASS_COUNT],
+    holders: [LockHolder; LOCK_HOLDER_COUNT],
+    // the scratch space of Reaches, kept here instead of on the small kernel stacks
+    visited: [u64; LOCK_CLASS_COUNT / 64],
+    stack: [u16; LOCK_CLASS_COUNT],
+}
+
+// the violation found by LockOrder::Acquire, reported after the tracker lock is
+// released as the logging takes QMutex locks too
+#[cfg(any(test, feature = "lock_order"))]
+pub enum LockOrderViolation {
+    // (the place of the lock, where it was taken the first time)
+    Recursive(
+        &'static Location<'static>,
+        Option<&'static Location<'static>>,
+    ),
+    // (the place of the lock, the place of the held lock, where the held lock was
+    // taken after it)
+    Inversion(
+        &'static Location<'static>,
+        Option<&'static Location<'static>>,
+        Option<&'static Location<'static>>,
+    ),
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+impl LockOrder {
+    pub const fn New() -> Self {
+        return Self {
+            classes: [LockClass::New(); LOCK_CLASS_COUNT],
+            holders: [LockHolder::New(); LOCK_HOLDER_COUNT],
+            visited: [0; LOCK_CLASS_COUNT / 64],
+            stack: [0; LOCK_CLASS_COUNT],
+        };
+    }
+
+    fn Hash(addr: u64) -> usize {
+        return ((addr >> 3).wrapping_mul(0x9E3779B97F4A7C15) >> 52) as usize % LOCK_CLASS_COUNT;
+    }
+
+    fn FindClass(&self, addr: u64) -> Option<usize> {
+        let start = Self::Hash(addr);
+        for i in 0..LOCK_CLASS_COUNT {
+            let slot = (start + i) % LOCK_CLASS_COUNT;
+            match self.classes[slot].addr {
+                0 => return None,
+                a if a == addr => return Some(slot),
+                _ => (),
+            }
+        }
+
+        return None;
+    }
+
+    fn Class(&mut self, addr: u64) -> Option<usize> {
+        if let Some(slot) = self.FindClass(addr) {
+            return Some(slot);
+        }
+
+        let start = Self::Hash(addr);
+        for i in 0..LOCK_CLASS_COUNT {
+            let slot = (start + i) % LOCK_CLASS_COUNT;
+            let class = &mut self.classes[slot];
+            if class.addr == 0 || class.addr == TOMBSTONE {
+                class.addr = addr;
+                class.gen = class.gen.wrapping_add(1);
+                class.site = None;
+                class.edgeCnt = 0;
+                return Some(slot);
+            }
+        }
+
+        return None;
+    }
+
+    fn Holder(&mut self, id: u64, alloc: bool) -> Option<usize> {
+        let mut free = None;
+        for i in 0..LOCK_HOLDER_COUNT {
+            if self.holders[i].id == id {
+                return Some(i);
+            }
+
+            if free.is_none() && self.holders[i].id == 0 {
+                free = Some(i);
+            }
+        }
+
+        if !alloc {
+            return None;
+        }
+
+        let i = free?;
+        self.holders[i].id = id;
+        self.holders[i].cnt = 0;
+        return Some(i);
+    }
+
+    fn Live(&self, edge: (u16, u16)) -> bool {
+        return self.classes[edge.0 as usize].gen == edge.1
+            && self.classes[edge.0 as usize].addr != TOMBSTONE;
+    }
+
+    fn AddEdge(&mut self, from: usize, to: usize) {
+        let edge = (to as u16, self.classes[to].gen);
+        let cnt = self.classes[from].edgeCnt;
+        let mut stale = None;
+        for i in 0..cnt {
+            let e = self.classes[from].edges[i];
+            if e == edge {
+                return;
+            }
+
+            if stale.is_none() && !self.Live(e) {
+                stale = Some(i);
+            }
+        }
+
+        let class = &mut self.classes[from];
+        match stale {
+            Some(i) => class.edges[i] = edge,
+            None if cnt < LOCK_EDGE_COUNT => {
+                class.edges[cnt] = edge;
+                class.edgeCnt += 1;
+            }
+            None => (),
+        }
+    }
+
+    // Reaches returns whether the lock in slot to was taken after the one in slot
+    // from, directly or through other locks
+    fn Reaches(&mut self, from: usize, to: usize) -> bool {
+        self.visited = [0; LOCK_CLASS_COUNT / 64];
+        let mut top = 1;
+        self.stack[0] = from as u16;
+        self.visited[from / 64] |= 1 << (from % 64);
+
+        while top > 0 {
+            top -= 1;
+            let class = self.classes[self.stack[top] as usize];
+            for i in 0..class.edgeCnt {
+                let e = class.edges[i];
+                if !self.Live(e) {
+                    continue;
+                }
+
+                let next = e.0 as usize;
+                if next == to {
+                    return true;
+                }
+
+                if self.visited[next / 64] & (1 << (next % 64)) == 0 {
+                    self.visited[next / 64] |= 1 << (next % 64);
+                    self.stack[top] = next as u16;
+                    top += 1;
+                }
+            }
+        }
+
+        return false;
+    }
+
+    // Acquire records that holder takes the lock at addr. A try lock can't
+    // deadlock, it is only recorded as held.
+    pub fn Acquire(
+        &mut self,
+        holder: u64,
+        addr: u64,
+        site: &'static Location<'static>,
+        tryLock: bool,
+    ) -> Option<LockOrderViolation> {
+        let class = self.Class(addr)?;
+        let h = self.Holder(holder, true)?;
+        let mut violation = None;
+
+        if !tryLock {
+            for i in 0..self.holders[h].cnt {
+                let (heldAddr, held) = self.holders[h].held[i];
+                if heldAddr == addr {
+                    violation = Some(LockOrderViolation::Recursive(
+                        site,
+                        self.classes[class].site,
+                    ));
+                    continue;
+                }
+
+                let held = held as usize;
+                if violation.is_none() && self.Reaches(class, held) {
+                    violation = Some(LockOrderViolation::Inversion(
+                        site,
+                        self.classes[held].site,
+                        self.classes[class].site,
+                    ));
+                }
+
+                self.AddEdge(held, class);
+            }
+        }
+
+        self.classes[class].site = Some(site);
+        let holder = &mut self.holders[h];
+        if holder.cnt < LOCK_HELD_COUNT {
+            holder.held[holder.cnt] = (addr, class as u16);
+            holder.cnt += 1;
+        }
+
+        return violation;
+    }
+
+    pub fn Release(&mut self, holder: u64, addr: u64) {
+        let h = match self.Holder(holder, false) {
+            None => return,
+            Some(h) => h,
+        };
+
+        let holder = &mut self.holders[h];
+        for i in (0..holder.cnt).rev() {
+            if holder.held[i].0 == addr {
+                holder.held.copy_within(i + 1..holder.cnt, i);
+                holder.cnt -= 1;
+                break;
+            }
+        }
+
+        if holder.cnt == 0 {
+            holder.id = 0;
+        }
+    }
+
+    // Free forgets the lock at addr, which is dropped, so that the order of the
+    // lock which reuses the address starts anew
+    pub fn Free(&mut self, addr: u64) {
+        let mut slot = match self.FindClass(addr) {
+            None => return,
+            Some(s) => s,
+        };
+
+        self.classes[slot].addr = TOMBSTONE;
+        self.classes[slot].edgeCnt = 0;
+
+        // the tombstones in front of an empty slot end no probe sequence, empty
+        // them so that the misses don't go through the whole table
+        while self.classes[(slot + 1) % LOCK_CLASS_COUNT].addr == 0
+            && self.classes[slot].addr == TOMBSTONE
+        {
+            self.classes[slot].addr = 0;
+            slot = (slot + LOCK_CLASS_COUNT - 1) % LOCK_CLASS_COUNT;
+        }
+    }
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+static LOCK_ORDER: Mutex<LockOrder> = Mutex::new(LockOrder::New());
+
+#[cfg(any(test, feature = "lock_order"))]
+pub static LOCK_ORDER_VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+// the logging of a violation takes locks too, which aren't reported
+#[cfg(any(test, feature = "lock_order"))]
+static LOCK_ORDER_REPORTING: AtomicBool = AtomicBool::new(false);
+
+// the tests run in parallel threads of one process, each of them counts the
+// violations of its own threads instead of reading LOCK_ORDER_VIOLATIONS
+#[cfg(test)]
+std::thread_local! {
+    pub static THREAD_LOCK_ORDER_VIOLATIONS: core::cell::Cell<u64> = core::cell::Cell::new(0);
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+pub fn LockAcquire(holder: u64, addr: u64, site: &'static Location<'static>, tryLock: bool) {
+    let violation = match LOCK_ORDER.lock().Acquire(holder, addr, site, tryLock) {
+        None => return,
+        Some(v) => v,
+    };
+
+    LOCK_ORDER_VIOLATIONS.fetch_add(1, Ordering::SeqCst);
+    #[cfg(test)]
+    THREAD_LOCK_ORDER_VIOLATIONS.with(|cnt| cnt.set(cnt.get() + 1));
+    if LOCK_ORDER_REPORTING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    match violation {
+        LockOrderViolation::Recursive(site, first) => {
+            error!(
+                "lock order: the lock taken at {} is already held, taken at {:?}",
+                site, first
+            );
+        }
+        LockOrderViolation::Inversion(site, held, before) => {
+            error!(
+                "lock order: the lock taken at {} while holding the lock taken at {:?}, was taken before it at {:?}",
+                site, held, before
+            );
+        }
+    }
+
+    LOCK_ORDER_REPORTING.store(false, Ordering::SeqCst);
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+pub fn LockRelease(holder: u64, addr: u64) {
+    LOCK_ORDER.lock().Release(holder, addr);
+}
+
+#[cfg(any(test, feature = "lock_order"))]
+pub fn LockFree(addr: u64) {
+    LOCK_ORDER.lock().Free(addr);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mutex::*;
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    // two threads take two locks in opposite orders, one after the other so that
+    // they don't deadlock, and the second one is flagged
+    #[test]
+    fn test_LockOrderInversion() {
+        let a = Arc::new(QMutexIntern::<u64>::new(0));
+        let b = Arc::new(QMutexIntern::<u64>::new(0));
+
+        let (a1, b1) = (a.clone(), b.clone());
+        let violations = thread::spawn(move || {
+            let _a = a1.lock();
+            let _b = b1.lock();
+            return THREAD_LOCK_ORDER_VIOLATIONS.with(|cnt| cnt.get());
+        })
+        .join()
+        .unwrap();
+        assert_eq!(violations, 0);
+
+        let violations = thread::spawn(move || {
+            let _b = b.lock();
+            let _a = a.lock();
+            return THREAD_LOCK_ORDER_VIOLATIONS.with(|cnt| cnt.get());
+        })
+        .join()
+        .unwrap();
+        assert_eq!(violations, 1);
+    }
+
+    #[test]
+    fn test_LockOrderTracker() {
+        let mut lo = Box::new(LockOrder::New());
+        let site = Location::caller();
+
+        assert!(lo.Acquire(1, 0x1000, site, false).is_none());
+        assert!(lo.Acquire(1, 0x2000, site, false).is_none());
+        lo.Release(1, 0x2000);
+        lo.Release(1, 0x1000);
+
+        // a try lock can't deadlock
+        assert!(lo.Acquire(2, 0x2000, site, false).is_none());
+        assert!(lo.Acquire(2, 0x1000, site, true).is_none());
+        lo.Release(2, 0x1000);
+        lo.Release(2, 0x2000);
+
+        // the order goes through a third lock
+        assert!(lo.Acquire(3, 0x2000, site, false).is_none());
+        assert!(lo.Acquire(3, 0x3000, site, false).is_none());
+        lo.Release(3, 0x3000);
+        lo.Release(3, 0x2000);
+        assert!(lo.Acquire(3, 0x3000, site, false).is_none());
+        assert!(matches!(
+            lo.Acquire(3, 0x1000, site, false),
+            Some(LockOrderViolation::Inversion(..))
+        ));
+        lo.Release(3, 0x1000);
+        lo.Release(3, 0x3000);
+
+        // a lock at the address of a dropped one starts without an order
+        lo.Free(0x1000);
+        assert!(lo.Acquire(4, 0x2000, site, false).is_none());
+        assert!(lo.Acquire(4, 0x1000, site, false).is_none());
+        lo.Release(4, 0x1000);
+        lo.Release(4, 0x2000);
+
+        assert!(lo.Acquire(5, 0x4000, site, false).is_none());
+        assert!(matches!(
+            lo.Acquire(5, 0x4000, site, false),
+            Some(LockOrderViolation::Recursive(..))
+        ));
+    }
+}
@@ -111,6 +111,8 @@ impl PageBlockAllocIntern {
 #[derive(Debug, Default)]
 pub struct PageBlockAlloc {
     pub freeCount: AtomicU64,
+    // the number of the allocated pages, a page shared by several mappings counts once
+    pub usedCount: AtomicU64,
     pub data: QMutex<PageBlockAllocIntern>,
 }
 
@@ -123,6 +125,19 @@ impl PageBlockAlloc {
         self.data.lock().PrintPages();
     }
 
+    // TotalPages returns the number of pages in the page blocks the allocator holds
+    pub fn TotalPages(&self) -> u64 {
+        return self.data.lock().pageBlocks.len() as u64 * BLOCK_PAGE_COUNT;
+    }
+
+    pub fn FreePages(&self) -> u64 {
+        return self.freeCount.load(Ordering::Acquire);
+    }
+
+    pub fn UsedPages(&self) -> u64 {
+        return self.usedCount.load(Ordering::Relaxed);
+    }
+
     pub fn Alloc(&self) -> Result<u64> {
         let mut al = self.data.lock();
 
@@ -133,6 +148,7 @@ impl PageBlockAlloc {
                 .fetch_add(BLOCK_PAGE_COUNT - 1, Ordering::Release);
             let (addr, _) = newpb.Alloc();
             al.Insert(newpb);
+            self.usedCount.fetch_add(1, Ordering::Relaxed);
             return Ok(addr);
         } else {
             PageBlock::FromAddr(al.pageBlockList)
@@ -143,12 +159,14 @@ impl PageBlockAlloc {
             al.UnlinkPageBlock(pb);
         }
         self.freeCount.fetch_sub(1, Ordering::Release);
+        self.usedCount.fetch_add(1, Ordering::Relaxed);
         return Ok(addr);
     }
 
     pub fn FreePage(&self, addr: u64) -> Result<()> {
         let pb = PageBlock::FromPageAddr(addr);
         let action = pb.FreePage(addr)?;
+        self.usedCount.fetch_sub(1, Ordering::Relaxed);
 
         // try to swap in the page in case it is freed before swap in
         // todo: if disable this, system is not stable. root cause this.
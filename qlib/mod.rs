@@ -39,6 +39,7 @@ pub mod fileinfo;
 pub mod limits;
 pub mod linux;
 pub mod loader;
+pub mod lock_order;
 pub mod lockfreebytestream;
 pub mod lrc_cache;
 pub mod mem;
@@ -505,7 +506,7 @@ pub enum SysCallID {
     sys_statx,
 
     syscall_333,
-    syscall_334,
+    sys_rseq,
     syscall_335,
     syscall_336,
     syscall_337,
@@ -26,14 +26,24 @@ use spin::*;
 
 use super::kernel::uid::*;
 use super::linux_def::QOrdering;
+#[cfg(any(test, feature = "lock_order"))]
+use super::lock_order::*;
 
 //use super::super::asm::*;
 
 pub struct Spin;
 
+#[cfg(not(feature = "lock_order"))]
 pub type QMutex<T> = Mutex<T>;
+#[cfg(not(feature = "lock_order"))]
 pub type QMutexGuard<'a, T> = MutexGuard<'a, T>;
 
+// the lock order of the QMutexIntern locks is tracked, see lock_order.rs
+#[cfg(feature = "lock_order")]
+pub type QMutex<T> = QMutexIntern<T>;
+#[cfg(feature = "lock_order")]
+pub type QMutexGuard<'a, T> = QMutexInternGuard<'a, T>;
+
 //pub type QMutex<T> = QMutexIntern<T>;
 //pub type QMutexGuard<'a, T> = QMutexInternGuard<'a, T>;
 
@@ -56,8 +66,8 @@ pub struct QMutexInternGuard<'a, T: ?Sized + 'a> {
     data: &'a mut T,
 }
 
-//unsafe impl<T: ?Sized + Send> Sync for QMutexIntern<T> {}
-//unsafe impl<T: ?Sized + Send> Send for QMutexIntern<T> {}
+unsafe impl<T: ?Sized + Send> Sync for QMutexIntern<T> {}
+unsafe impl<T: ?Sized + Send> Send for QMutexIntern<T> {}
 
 impl<T, R> QMutexIntern<T, R> {
     #[inline(always)]
@@ -140,10 +150,13 @@ impl<T: ?Sized> QMutexIntern<T> {
     }
 
     #[inline(always)]
+    #[cfg_attr(any(test, feature = "lock_order"), track_caller)]
     pub fn lock(&self) -> QMutexInternGuard<T> {
         // Can fail to lock even if the spinlock is not locked. May be more efficient than `try_lock`
         // when called in a loop.
         let id = Self::GetID();
+        #[cfg(any(test, feature = "lock_order"))]
+        LockAcquire(id, self.MutexId(), core::panic::Location::caller(), false);
         /*if id < 0x4040000000 {
             raw!(0x122, id, &self.lock as * const _ as u64, 0);
         }*/
@@ -193,12 +206,15 @@ impl<T: ?Sized> QMutexIntern<T> {
     }
 
     #[inline(always)]
+    #[cfg_attr(any(test, feature = "lock_order"), track_caller)]
     pub fn try_lock(&self) -> Option<QMutexInternGuard<T>> {
         let id = Self::GetID();
 
         super::super::asm::mfence();
         let val = self.CmpExchg(0, id);
         if val == 0 {
+            #[cfg(any(test, feature = "lock_order"))]
+            LockAcquire(id, self.MutexId(), core::panic::Location::caller(), true);
             Some(QMutexInternGuard {
                 lock: &self.lock,
                 data: unsafe { &mut *self.data.get() },
@@ -220,6 +236,13 @@ impl<T: ?Sized + fmt::Debug> fmt::Debug for QMutexIntern<T> {
     }
 }
 
+#[cfg(any(test, feature = "lock_order"))]
+impl<T: ?Sized, R> Drop for QMutexIntern<T, R> {
+    fn drop(&mut self) {
+        LockFree(&self.lock as *const _ as u64);
+    }
+}
+
 impl<T: ?Sized + Default, R> Default for QMutexIntern<T, R> {
     fn default() -> Self {
         Self::new(Default::default())
@@ -260,6 +283,11 @@ impl<'a, T: ?Sized> DerefMut for QMutexInternGuard<'a, T> {
 impl<'a, T: ?Sized> Drop for QMutexInternGuard<'a, T> {
     /// The dropping of the QMutexInternGuard will release the lock it was created from.
     fn drop(&mut self) {
+        #[cfg(any(test, feature = "lock_order"))]
+        LockRelease(
+            QMutexIntern::<(), Spin>::GetID(),
+            self.lock as *const _ as u64,
+        );
         self.lock.store(0, QOrdering::RELEASE);
 
         //WriteOnce(self.lock as * const _ as u64, 0);
@@ -45,6 +45,8 @@ pub struct PageTables {
     pub tlbshootdown: AtomicBool,
     pub freePages: QMutex<Vec<u64>>,
     pub hibernateLock: QMutex<()>,
+    // the number of the 4K pages mapped by the table, swapped out ones included
+    pub mappedPages: AtomicU64,
 }
 
 impl PageTables {
@@ -56,9 +58,25 @@ impl PageTables {
             tlbshootdown: AtomicBool::new(false),
             freePages: Default::default(),
             hibernateLock: Default::default(),
+            mappedPages: AtomicU64::new(0),
         })
     }
 
+    pub fn MappedPages(&self) -> u64 {
+        return self.mappedPages.load(Ordering::Relaxed);
+    }
+
+    fn AddMapped(&self) {
+        self.mappedPages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn SubMapped(&self) {
+        let dec = |v: u64| Some(v.saturating_sub(1));
+        self.mappedPages
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, dec)
+            .ok();
+    }
+
     pub fn TlbShootdown(&self) -> bool {
         return self.tlbshootdown.swap(false, Ordering::SeqCst);
     }
@@ -79,6 +97,7 @@ impl PageTables {
             tlbshootdown: AtomicBool::new(false),
             freePages: Default::default(),
             hibernateLock: Default::default(),
+            mappedPages: AtomicU64::new(0),
         };
     }
 
@@ -89,6 +108,7 @@ impl PageTables {
             tlbshootdown: AtomicBool::new(false),
             freePages: Default::default(),
             hibernateLock: Default::default(),
+            mappedPages: AtomicU64::new(0),
         };
     }
 
@@ -272,6 +292,58 @@ impl PageTables {
         }
     }
 
+    // IsResident returns whether the page containing vaddr is mapped and present.
+    // Unlike VirtualToEntry, it doesn't swap in a swapped out page.
+    pub fn IsResident(&self, vaddr: u64) -> bool {
+        return self.ResidentPhyAddr(vaddr).is_some();
+    }
+
+    // ResidentPhyAddr returns the physical page backing vaddr if it is present,
+    // without swapping it in.
+    pub fn ResidentPhyAddr(&self, vaddr: u64) -> Option<u64> {
+        return self.ResidentEntry(vaddr).map(|(phyAddr, _)| phyAddr);
+    }
+
+    // ResidentEntry returns the physical page and the pte flags of vaddr if it is
+    // present, without swapping it in.
+    pub fn ResidentEntry(&self, vaddr: u64) -> Option<(u64, PageTableFlags)> {
+        let vaddr = VirtAddr::new(vaddr);
+
+        let p4Idx = vaddr.p4_index();
+        let p3Idx = vaddr.p3_index();
+        let p2Idx = vaddr.p2_index();
+        let p1Idx = vaddr.p1_index();
+
+        let pt: *mut PageTable = self.GetRoot() as *mut PageTable;
+
+        unsafe {
+            let pgdEntry = &(*pt)[p4Idx];
+            if pgdEntry.is_unused() {
+                return None;
+            }
+
+            let pudTbl = pgdEntry.addr().as_u64() as *const PageTable;
+            let pudEntry = &(*pudTbl)[p3Idx];
+            if pudEntry.is_unused() {
+                return None;
+            }
+
+            let pmdTbl = pudEntry.addr().as_u64() as *const PageTable;
+            let pmdEntry = &(*pmdTbl)[p2Idx];
+            if pmdEntry.is_unused() {
+                return None;
+            }
+
+            let pteTbl = pmdEntry.addr().as_u64() as *const PageTable;
+            let pteEntry = &(*pteTbl)[p1Idx];
+            if pteEntry.flags() & PageTableFlags::PRESENT != PageTableFlags::PRESENT {
+                return None;
+            }
+
+            return Some((pteEntry.addr().as_u64(), pteEntry.flags()));
+        }
+    }
+
     pub fn VirtualToPhy(&self, vaddr: u64) -> Result<(u64, AccessType)> {
         let pteEntry = self.VirtualToEntry(vaddr)?;
         if pteEntry.is_unused() {
@@ -381,6 +453,7 @@ impl PageTables {
             pagePool.Ref(phyAddr.0).unwrap();
             if !pteEntry.is_unused() {
                 self.freeEntry(pteEntry, pagePool)?;
+                self.SubMapped();
 
                 /*let addr = pteEntry.addr().as_u64();
                 let bit9 = pteEntry.flags() & PageTableFlags::BIT_9 == PageTableFlags::BIT_9;
@@ -392,6 +465,7 @@ impl PageTables {
             }
 
             pteEntry.set_addr(PhysAddr::new(phyAddr.0), flags);
+            self.AddMapped();
             Invlpg(vaddr.0);
         }
 
@@ -757,7 +831,7 @@ impl PageTables {
                                     //info!("pagetable::Unmap Error: paddr {:x}, vaddr is {:x}, error is {:x?}",
                                     //    pteEntry.addr().as_u64(), start, e);
                                 }
-                                Ok(_) => (),
+                                Ok(_) => self.SubMapped(),
                             }
 
                             Invlpg(start);
@@ -1100,6 +1174,7 @@ impl PageTables {
         start: u64,
         len: u64,
         pages: &mut BTreeSet<u64>,
+        locked: &BTreeSet<u64>,
         updatePageEntry: bool,
     ) -> Result<()> {
         let end = start + len;
@@ -1112,7 +1187,7 @@ impl PageTables {
             Addr(MemoryDef::PHY_LOWER_ADDR),
             |entry: &mut PageTableEntry, _virtualAddr| {
                 let phyAddr = entry.addr().as_u64();
-                if start <= phyAddr && phyAddr < end {
+                if start <= phyAddr && phyAddr < end && !locked.contains(&phyAddr) {
                     let mut flags = entry.flags();
                     let needInsert = flags & PageTableFlags::BIT_9 != PageTableFlags::BIT_9;
                     if updatePageEntry && needInsert {
@@ -1136,7 +1211,7 @@ impl PageTables {
             Addr(MemoryDef::LOWER_TOP),
             |entry, _virtualAddr| {
                 let phyAddr = entry.addr().as_u64();
-                if start <= phyAddr && phyAddr < end {
+                if start <= phyAddr && phyAddr < end && !locked.contains(&phyAddr) {
                     let mut flags = entry.flags();
                     let needInsert = flags & PageTableFlags::BIT_9 != PageTableFlags::BIT_9;
                     if updatePageEntry && needInsert {
@@ -1387,10 +1462,16 @@ impl PageTables {
                                     pteEntry.set_flags(PageTableFlags::PRESENT | PageTableFlags::BIT_9);
                                 }*/
                                 res = self.freeEntry(pteEntry, pagePool)?;
+                                if !kernel {
+                                    self.SubMapped();
+                                }
                             }
 
                             //info!("set addr: vaddr is {:x}, paddr is {:x}, flags is {:b}", curAddr.0, phyAddr.0, flags.bits());
                             pteEntry.set_addr(PhysAddr::new(newAddr), flags);
+                            if !kernel {
+                                self.AddMapped();
+                            }
                             Invlpg(curAddr.0);
                             curAddr = curAddr.AddLen(MemoryDef::PAGE_SIZE_4K)?;
 
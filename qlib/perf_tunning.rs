@@ -48,6 +48,7 @@ pub fn PerfPrint() {
 pub struct Counter {
     pub count: AtomicU64,
     pub lastVal: AtomicU64,
+    pub calls: AtomicU64,
 }
 
 impl Default for Counter {
@@ -55,11 +56,16 @@ impl Default for Counter {
         return Self {
             count: AtomicU64::new(0),
             lastVal: AtomicU64::new(0),
+            calls: AtomicU64::new(0),
         };
     }
 }
 
 impl Counter {
+    pub fn Calls(&self) -> u64 {
+        return self.calls.load(Ordering::Relaxed);
+    }
+
     pub fn Enter(&self) {
         self.lastVal.store(TSC.Rdtsc() as u64, Ordering::SeqCst);
     }
@@ -131,6 +137,9 @@ impl Counters {
             state.push(typ);
         };
 
+        self.data[typ as usize]
+            .calls
+            .fetch_add(1, Ordering::Relaxed);
         if let Some(t) = top {
             self.Leave(t)
         }
@@ -174,22 +183,45 @@ impl Counters {
     }
 }
 
-#[derive(Default)]
 pub struct CounterSet {
     pub data: [Counters; Self::PERM_COUNTER_SET_SIZE],
 }
 
+impl Default for CounterSet {
+    fn default() -> Self {
+        return Self {
+            data: core::array::from_fn(|_| Counters::default()),
+        };
+    }
+}
+
 impl CounterSet {
     pub fn Goto(&self, typ: PerfType) {
         let id = self.GetPerfId();
+        if id >= Self::PERM_COUNTER_SET_SIZE {
+            return;
+        }
         self.data[id].Goto(id, typ);
     }
 
     pub fn Gofrom(&self, typ: PerfType) {
         let id = self.GetPerfId();
+        if id >= Self::PERM_COUNTER_SET_SIZE {
+            return;
+        }
         self.data[id].Gofrom(id, typ);
     }
 
+    // Calls returns how many times the typ has been entered by all the counters
+    pub fn Calls(&self, typ: PerfType) -> u64 {
+        let mut sum = 0;
+        for id in 0..Self::PERM_COUNTER_SET_SIZE {
+            sum += self.data[id].data[typ as usize].Calls();
+        }
+
+        return sum;
+    }
+
     pub fn Stop(&self) {
         for id in 0..Self::PERM_COUNTER_SET_SIZE {
             self.data[id].Stop();
@@ -241,3 +273,38 @@ impl CounterSet {
         error!("{}", line);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_CounterSetCalls() {
+        let counts = CounterSet::default();
+        let types = [
+            PerfType::Read,
+            PerfType::Write,
+            PerfType::ReadAt,
+            PerfType::WriteAt,
+            PerfType::Accept,
+            PerfType::Connect,
+            PerfType::RecvMsg,
+            PerfType::SendMsg,
+        ];
+
+        for typ in types.iter() {
+            assert_eq!(counts.Calls(*typ), 0);
+            counts.Goto(*typ);
+            counts.Gofrom(*typ);
+            assert_eq!(counts.Calls(*typ), 1);
+        }
+
+        counts.Goto(PerfType::Read);
+        counts.Goto(PerfType::Accept);
+        counts.Gofrom(PerfType::Accept);
+        counts.Gofrom(PerfType::Read);
+        assert_eq!(counts.Calls(PerfType::Read), 2);
+        assert_eq!(counts.Calls(PerfType::Accept), 2);
+        assert_eq!(counts.Calls(PerfType::Write), 1);
+    }
+}
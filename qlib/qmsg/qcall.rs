@@ -30,6 +30,8 @@ pub enum Msg {
 
     //Syscall
     Fallocate(Fallocate),
+    CopyFileRange(CopyFileRange),
+    FileClone(FileClone),
     RenameAt(RenameAt),
     Ftruncate(Ftruncate),
     Seek(Seek),
@@ -100,8 +102,11 @@ pub enum Msg {
     NonBlockingPoll(NonBlockingPoll),
     NewTmpfsFile(NewTmpfsFile),
     IoUringEnter(IoUringEnter),
+    IoUringSubmit(IoUringSubmit),
     Statm(Statm),
     NewSocket(NewSocket),
+    NewFile(NewFile),
+    ReleaseFd(ReleaseFd),
     HostEpollWaitProcess(HostEpollWaitProcess),
     EventfdWrite(EventfdWrite),
     ReadControlMsg(ReadControlMsg),
@@ -110,6 +115,7 @@ pub enum Msg {
     Rdtsc(Rdtsc),
     SetTscOffset(SetTscOffset),
     TlbShootdown(TlbShootdown),
+    InterruptVcpu(InterruptVcpu),
     Sysinfo(Sysinfo),
     ReadDir(ReadDir),
     FSetXattr(FSetXattr),
@@ -180,6 +186,11 @@ pub struct TlbShootdown {
     pub vcpuMask: u64,
 }
 
+#[derive(Clone, Default, Debug)]
+pub struct InterruptVcpu {
+    pub vcpuId: u64,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct Sysinfo {
     pub addr: u64,
@@ -219,6 +230,9 @@ pub struct IoUringEnter {
     pub flags: u32,
 }
 
+#[derive(Clone, Default, Debug, Copy)]
+pub struct IoUringSubmit {}
+
 #[derive(Clone, Default, Debug)]
 pub struct InitPara {
     pub KernelPageTableRoot: u64,
@@ -249,6 +263,24 @@ pub struct Fallocate {
     pub len: i64,
 }
 
+#[derive(Clone, Default, Debug)]
+pub struct CopyFileRange {
+    pub srcFd: i32,
+    pub srcOffset: i64,
+    pub dstFd: i32,
+    pub dstOffset: i64,
+    pub len: i64,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct FileClone {
+    pub srcFd: i32,
+    pub srcOffset: i64,
+    pub dstFd: i32,
+    pub dstOffset: i64,
+    pub len: i64,
+}
+
 // get vss/rss from /proc/self/statm
 #[derive(Clone, Default, Debug)]
 pub struct StatmInfo {
@@ -793,6 +825,20 @@ pub struct NewSocket {
     pub fd: i32,
 }
 
+// NewFile registers a host fd the guest has opened through io_uring, when the
+// HostFdChange queue is full
+#[derive(Clone, Default, Debug)]
+pub struct NewFile {
+    pub fd: i32,
+}
+
+// ReleaseFd drops a host fd from the qvisor's tables without closing it, the
+// guest closes it through io_uring
+#[derive(Clone, Default, Debug)]
+pub struct ReleaseFd {
+    pub fd: i32,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct HostEpollWaitProcess {}
 
@@ -30,6 +30,33 @@ use crate::qlib::kernel::kernel::waiter::Queue;
 use crate::qlib::kernel::socket::hostinet::loopbacksocket::LoopbackSocket;
 use crate::qlib::kernel::Kernel::HostSpace;
 
+// the smallest SO_RCVBUF and SO_SNDBUF values, as in Linux
+pub const SOCK_MIN_RCVBUF: i32 = 2304;
+pub const SOCK_MIN_SNDBUF: i32 = 4608;
+// the largest SO_RCVBUF and SO_SNDBUF values taken, before doubling
+pub const SOCK_MAX_BUF: u32 = 4 << 20;
+
+// SockBufSize returns the SO_RCVBUF/SO_SNDBUF value kept for a setsockopt of val.
+// Like Linux it's doubled to account for the bookkeeping overhead, and getsockopt
+// reports the doubled value.
+pub fn SockBufSize(val: i32, min: i32) -> i32 {
+    let val = core::cmp::min(val as u32, SOCK_MAX_BUF) as i32;
+    return core::cmp::max(val * 2, min);
+}
+
+// SockBufPages returns the page count of the ring buffer backing a SockBufSize
+// value, 0 being the default size. The ring holds the size the user asked for,
+// rounded up to a power of two pages.
+pub fn SockBufPages(size: i32) -> u64 {
+    if size == 0 {
+        return MemoryDef::DEFAULT_BUF_PAGE_COUNT;
+    }
+
+    let bytes = size as u64 / 2;
+    let pages = (bytes + MemoryDef::PAGE_SIZE - 1) / MemoryDef::PAGE_SIZE;
+    return core::cmp::max(pages, 1).next_power_of_two();
+}
+
 #[derive(Clone, Default)]
 pub struct SocketBuffWeak(pub Weak<SocketBuffIntern>);
 
@@ -128,6 +155,10 @@ impl SocketBuffIntern {
     }
 
     pub fn Init(pageCount: u64) -> Self {
+        return Self::InitWithSize(pageCount, pageCount);
+    }
+
+    pub fn InitWithSize(readPageCount: u64, writePageCount: u64) -> Self {
         return Self {
             wClosed: AtomicBool::new(false),
             rClosed: AtomicBool::new(false),
@@ -137,8 +168,10 @@ impl SocketBuffIntern {
                 let addr = 0 as *mut AtomicU64;
                 &mut (*addr)
             },
-            readBuf: ByteStream(Arc::new(QMutex::new(ByteStreamIntern::Init(pageCount)))),
-            writeBuf: ByteStream(Arc::new(QMutex::new(ByteStreamIntern::Init(pageCount)))),
+            readBuf: ByteStream(Arc::new(QMutex::new(ByteStreamIntern::Init(readPageCount)))),
+            writeBuf: ByteStream(Arc::new(QMutex::new(ByteStreamIntern::Init(
+                writePageCount,
+            )))),
         };
     }
 
@@ -367,6 +400,8 @@ impl AcceptQueue {
             error: 0,
             total: 0,
             queue: queue,
+            rcvBuf: 0,
+            sndBuf: 0,
         };
 
         return Self(Arc::new(QMutex::new(inner)));
@@ -412,6 +447,9 @@ pub struct AcceptQueueIntern {
     pub error: i32,
     pub total: u64,
     pub queue: Queue,
+    // SO_RCVBUF/SO_SNDBUF of the listening socket, inherited by the connections
+    pub rcvBuf: i32,
+    pub sndBuf: i32,
 }
 
 impl fmt::Debug for AcceptQueueIntern {
@@ -441,6 +479,19 @@ impl AcceptQueueIntern {
         self.queueLen = len;
     }
 
+    pub fn SetBufSize(&mut self, rcvBuf: i32, sndBuf: i32) {
+        self.rcvBuf = rcvBuf;
+        self.sndBuf = sndBuf;
+    }
+
+    // NewSocketBuff returns the buffer of an accepted connection
+    pub fn NewSocketBuff(&self) -> SocketBuff {
+        return SocketBuff(Arc::new(SocketBuffIntern::InitWithSize(
+            SockBufPages(self.rcvBuf),
+            SockBufPages(self.sndBuf),
+        )));
+    }
+
     pub fn HasSpace(&self) -> bool {
         return self.aiQueue.len() < self.queueLen;
     }
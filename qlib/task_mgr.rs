@@ -80,6 +80,8 @@ pub struct Context {
     // job queue id
     pub queueId: AtomicUsize,
     pub links: Links,
+    // the vcpus the task may run on, from sched_setaffinity. 0 is any vcpu.
+    pub cpuMask: AtomicU64,
 }
 
 impl Context {
@@ -101,6 +103,7 @@ impl Context {
             X86fpstate: Some(Default::default()),
             queueId: AtomicUsize::new(0),
             links: Links::default(),
+            cpuMask: AtomicU64::new(0),
         };
     }
 
@@ -111,6 +114,30 @@ impl Context {
     pub fn SetReady(&self, val: u64) {
         return self.ready.store(val, Ordering::SeqCst);
     }
+
+    pub fn CPUMask(&self) -> u64 {
+        return self.cpuMask.load(Ordering::Acquire);
+    }
+
+    pub fn SetCPUMask(&self, mask: u64) {
+        self.cpuMask.store(mask, Ordering::Release);
+    }
+
+    // AllowedOn returns whether the task may run on the vcpu
+    pub fn AllowedOn(&self, vcpuId: usize) -> bool {
+        let mask = self.CPUMask();
+        return mask == 0 || mask & VcpuBit(vcpuId) != 0;
+    }
+}
+
+// VcpuBit returns the bit of the vcpu in a cpu mask. A mask only holds the first
+// 64 vcpus, a vcpu from 64 on has no bit and is never in a mask, see VcpuMask.
+pub fn VcpuBit(vcpuId: usize) -> u64 {
+    if vcpuId >= 64 {
+        return 0;
+    }
+
+    return 1 << vcpuId;
 }
 
 #[derive(Default)]
@@ -198,11 +225,44 @@ impl Scheduler {
         return cnt;
     }
 
+    // RunningOn returns the vcpu which is running the task
+    pub fn RunningOn(&self, task: TaskId) -> Option<usize> {
+        for i in 1..self.vcpuCnt {
+            if self.VcpuArr[i].currentTask.load(Ordering::SeqCst) == task.Addr() {
+                return Some(i);
+            }
+        }
+
+        return None;
+    }
+
+    // AllRunning returns whether every vcpu in mask is running a task
+    pub fn AllRunning(&self, mask: u64) -> bool {
+        for i in 1..self.vcpuCnt {
+            if mask & VcpuBit(i) != 0 && self.VcpuArr[i].State() != VcpuState::Running {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
     pub fn ScheduleQ(&self, task: TaskId, vcpuId: u64, cpuAff: bool) {
         if self.queue[vcpuId as usize].Enqueue(task, cpuAff) {
             self.IncReadyTaskCount();
         }
 
+        // the owner of the queue skips a task it isn't allowed to run, wake an
+        // allowed vcpu to steal it
+        let mask = task.Context().CPUMask();
+        if mask != 0 && mask & VcpuBit(vcpuId as usize) == 0 {
+            for i in 1..self.vcpuCnt {
+                if mask & VcpuBit(i) != 0 && self.WakeIdleCPU(i) {
+                    return;
+                }
+            }
+        }
+
         //error!("ScheduleQ task {:x?}, vcpuId {}", task, vcpuId);
         if vcpuId == 0 {
             self.WakeOne();
@@ -260,6 +320,8 @@ impl Scheduler {
     }
 }
 
+pub const STEAL_SCAN_COUNT: usize = 8;
+
 #[derive(Debug)]
 pub struct TaskQueueIntern {
     pub workingTask: TaskId,
@@ -297,22 +359,32 @@ impl TaskQueue {
         };
     }
 
-    // used by the vcpu owner to get next task
-    pub fn Next(&self) -> Option<(TaskId, bool)> {
+    // used by the vcpu owner to get next task. The tasks not allowed on vcpuId
+    // are moved to the back of the queue for the allowed vcpus to steal.
+    pub fn Next(&self, vcpuId: usize) -> Option<(TaskId, bool)> {
         let mut data = self.data.lock();
         if data.workingTaskReady {
             data.workingTaskReady = false;
             return Some((data.workingTask, false));
         }
 
-        match data.queue.pop_front() {
-            None => return None,
-            Some(taskId) => {
-                self.queueSize.fetch_sub(1, Ordering::Release);
-                data.workingTask = taskId;
-                return Some((taskId, true));
+        for _ in 0..data.queue.len() {
+            match data.queue.pop_front() {
+                None => return None,
+                Some(taskId) => {
+                    if !taskId.Context().AllowedOn(vcpuId) {
+                        data.queue.push_back(taskId);
+                        continue;
+                    }
+
+                    self.queueSize.fetch_sub(1, Ordering::Release);
+                    data.workingTask = taskId;
+                    return Some((taskId, true));
+                }
             }
         }
+
+        return None;
     }
 
     pub fn ResetWorkingTask(&self) -> Option<TaskId> {
@@ -339,8 +411,9 @@ impl TaskQueue {
         }
     }
 
-    // try to steal task from other vcpu's queue
-    pub fn Steal(&self) -> Option<TaskId> {
+    // try to steal task from other vcpu's queue, only the first STEAL_SCAN_COUNT
+    // tasks are looked at so that the owner doesn't wait long for its queue lock
+    pub fn Steal(&self, canRun: impl Fn(TaskId) -> bool) -> Option<TaskId> {
         if self.queueSize.load(Ordering::Acquire) == 0 {
             return None;
         }
@@ -348,11 +421,12 @@ impl TaskQueue {
         match self.data.try_lock() {
             None => return None,
             Some(mut data) => {
-                for _ in 0..data.queue.len() {
+                let cnt = core::cmp::min(data.queue.len(), STEAL_SCAN_COUNT);
+                for _ in 0..cnt {
                     match data.queue.pop_front() {
                         None => panic!("TaskQueue none task"),
                         Some(taskId) => {
-                            if taskId.GetTask().context.Ready() != 0 {
+                            if taskId.GetTask().context.Ready() != 0 && canRun(taskId) {
                                 self.queueSize.fetch_sub(1, Ordering::Release);
                                 return Some(taskId);
                             }
@@ -22,6 +22,15 @@ pub struct CPUStats {
     // VoluntarySwitches is the number of times control has been voluntarily
     // ceded due to blocking, etc.
     pub VoluntarySwitches: u64,
+
+    // InvoluntarySwitches is the number of times control has been taken away
+    // from a runnable task, when its time slice is used up or it yields.
+    pub InvoluntarySwitches: u64,
+
+    // MinorFaults and MajorFaults are the page faults served without and with
+    // a host mapping or swap in of the page.
+    pub MinorFaults: u64,
+    pub MajorFaults: u64,
 }
 
 impl CPUStats {
@@ -29,5 +38,8 @@ impl CPUStats {
         self.UserTime += s2.UserTime;
         self.SysTime += s2.SysTime;
         self.VoluntarySwitches += s2.VoluntarySwitches;
+        self.InvoluntarySwitches += s2.InvoluntarySwitches;
+        self.MinorFaults += s2.MinorFaults;
+        self.MajorFaults += s2.MajorFaults;
     }
 }
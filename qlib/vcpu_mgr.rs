@@ -142,6 +142,7 @@ impl CPULocal {
 
     pub const TLB_SHOOTDOWN_MASK: u64 = 1 << 0;
     pub const THREAD_TIMEOUT: u64 = 1 << 1;
+    pub const MIGRATE: u64 = 1 << 2;
 
     pub fn InterruptTlbShootdown(&self) {
         self.SetInterruptMask(Self::TLB_SHOOTDOWN_MASK);
@@ -158,4 +159,12 @@ impl CPULocal {
     pub fn InterruptByThreadTimeout(mask: u64) -> bool {
         return mask & Self::THREAD_TIMEOUT != 0;
     }
+
+    pub fn InterruptMigrate(&self) {
+        self.SetInterruptMask(Self::MIGRATE);
+    }
+
+    pub fn InterruptByMigrate(mask: u64) -> bool {
+        return mask & Self::MIGRATE != 0;
+    }
 }
@@ -1,4 +1,5 @@
 use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::Ordering;
 use std::fmt;
 use std::sync::mpsc::channel;
@@ -26,11 +27,11 @@ use super::qlib::rdma_svc_cli::*;
 use super::qlib::task_mgr::*;
 use super::qlib::vcpu_mgr::*;
 use super::qlib::*;
+use super::vmspace::uringMgr::UringMgr;
 use super::vmspace::*;
 use super::ThreadId;
 use super::FD_NOTIFIER;
 use super::QUARK_CONFIG;
-use super::URING_MGR;
 use super::VMS;
 
 impl std::error::Error for Error {}
@@ -168,6 +169,17 @@ impl ShareSpace {
         return 0;
     }
 
+    // InterruptVcpu kicks the vcpu out of the user space to take the interrupts
+    // set in its interrupt mask, a vcpu in the kernel takes them on its own
+    pub fn InterruptVcpu(&self, vcpuId: usize) -> u64 {
+        if self.scheduler.VcpuArr[vcpuId].GetMode() == VcpuMode::User {
+            let vcpu = VMS.lock().vcpus[vcpuId].clone();
+            vcpu.interrupt(None);
+        }
+
+        return 0;
+    }
+
     pub fn Yield() {
         use std::{thread, time};
         let dur = time::Duration::new(0, 1000);
@@ -211,6 +223,14 @@ pub enum PerfType {
     BusyWait,
     IdleWait,
     BufWrite,
+    Read,
+    Write,
+    ReadAt,
+    WriteAt,
+    Accept,
+    Connect,
+    RecvMsg,
+    SendMsg,
     End,
     User, //work around for kernel clone
     Idle, //work around for kernel clone
@@ -221,9 +241,13 @@ pub enum PerfType {
 }
 
 impl CounterSet {
-    pub const PERM_COUNTER_SET_SIZE: usize = 1;
+    // the vcpu threads and the io threads of the qvisor
+    pub const PERM_COUNTER_SET_SIZE: usize = 2 * MAX_VCPU_COUNT;
+
+    // the host calls are run by the vcpu and io threads concurrently, each of them
+    // needs its own counters
     pub fn GetPerfId(&self) -> usize {
-        0
+        return PERF_ID.with(|id| *id);
     }
 
     pub fn PerfType(&self) -> &str {
@@ -231,6 +255,21 @@ impl CounterSet {
     }
 }
 
+// NEXT_PERF_ID hands out the CounterSet slots, a thread past the end of the table
+// isn't counted
+static NEXT_PERF_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local!(static PERF_ID: usize = {
+    let id = NEXT_PERF_ID.fetch_add(1, Ordering::Relaxed);
+    if id >= CounterSet::PERM_COUNTER_SET_SIZE {
+        error!(
+            "perf: no counters left for thread {}, its host calls aren't counted",
+            id
+        );
+    }
+    id
+});
+
 pub fn switch(_from: TaskId, _to: TaskId) {}
 
 pub fn OpenAt(_task: &Task, _dirFd: i32, _addr: u64, _flags: u32) -> Result<i32> {
@@ -288,10 +327,7 @@ pub fn NewSocket(fd: i32) -> i64 {
 }
 
 pub fn UringWake(minCompleted: u64) {
-    URING_MGR
-        .lock()
-        .Wake(minCompleted as _)
-        .expect("qlib::HYPER CALL_URING_WAKE fail");
+    UringMgr::GlobalWake(minCompleted as _).expect("qlib::HYPER CALL_URING_WAKE fail");
 }
 
 impl HostSpace {
@@ -147,6 +147,9 @@ pub struct KVMVcpu {
 
     pub autoStart: bool,
     pub interrupting: Mutex<(bool, Vec<Sender<()>>)>,
+    // the vm exits the guest asked for, the host kicks (Intr, IrqWindowOpen) are not
+    // counted. See VcpuWatchdog.
+    pub exitCount: AtomicU64,
 }
 
 //for pub shareSpace: * mut Mutex<ShareSpace>
@@ -226,6 +229,7 @@ impl KVMVcpu {
             shareSpaceAddr: shareSpaceAddr,
             autoStart: autoStart,
             interrupting: Mutex::new((false, vec![])),
+            exitCount: AtomicU64::new(0),
         });
     }
 
@@ -484,24 +488,24 @@ impl KVMVcpu {
                             VcpuExit::Intr
                         }
                     } else {
-                        let regs = self
-                            .vcpu
-                            .get_regs()
-                            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
-
-                        error!("vcpu error regs is {:x?}, ioerror: {:?}", regs, e);
-                        backtracer::trace(regs.rip, regs.rsp, regs.rbp, &mut |frame| {
-                            print!("host frame is {:#x?}", frame);
-                            true
-                        });
-
-                        panic!("kvm virtual cpu[{}] run failed: Error {:?}", self.id, e)
+                        self.Crash(&format!("run failed: {:?}", e));
                     }
                 }
             };
             self.state
                 .store(KVMVcpuState::HOST as u64, Ordering::Release);
 
+            if let Some(reason) = CrashReason(&kvmRet) {
+                self.Crash(&reason);
+            }
+
+            match kvmRet {
+                VcpuExit::Intr | VcpuExit::IrqWindowOpen => (),
+                _ => {
+                    self.exitCount.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
             match kvmRet {
                 VcpuExit::IoIn(addr, data) => {
                     info!(
@@ -515,10 +519,7 @@ impl KVMVcpu {
                         .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
                     if vcpu_sregs.cs.dpl != 0x0 {
                         // call from user space
-                        panic!(
-                            "Get VcpuExit::IoIn from guest user space, Abort, vcpu_sregs is {:#x?}",
-                            vcpu_sregs
-                        )
+                        self.Crash(&format!("io in {:#x} from the guest user space", addr));
                     }
                 }
                 VcpuExit::IoOut(addr, data) => {
@@ -534,7 +535,7 @@ impl KVMVcpu {
                         .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
                     if vcpu_sregs.cs.dpl != 0x0 {
                         // call from user space
-                        panic!("Get VcpuExit::IoOut from guest user space, Abort, vcpu_sregs is {:#x?}", vcpu_sregs)
+                        self.Crash(&format!("io out {:#x} from the guest user space", addr));
                     }
 
                     let regs = self
@@ -584,9 +585,7 @@ impl KVMVcpu {
                         qlib::HYPERCALL_URING_WAKE => {
                             let minComplete = para1 as usize;
 
-                            URING_MGR
-                                .lock()
-                                .Wake(minComplete)
+                            UringMgr::GlobalWake(minComplete)
                                 .expect("qlib::HYPER CALL_URING_WAKE fail");
                         }
                         qlib::HYPERCALL_RELEASE_VCPU => {
@@ -612,7 +611,8 @@ impl KVMVcpu {
                             let msg = unsafe { &*(addr as *const Print) };
 
                             eprintln!("Application error: {}", msg.str);
-                            ::std::process::exit(1);
+                            super::print::LOG.Clear();
+                            ::std::process::exit(GUEST_CRASH_EXIT_STATUS);
                         }
 
                         qlib::HYPERCALL_WAKEUP_VCPU => {
@@ -687,6 +687,8 @@ impl KVMVcpu {
 
                                 if res == -1 {
                                     call.res = errno::errno().0 as i64;
+                                } else if RecordReplay::Enabled() {
+                                    call.res = RECORD_REPLAY.lock().Time(clockId, ts.ToNs()?);
                                 } else {
                                     call.res = ts.ToNs()?;
                                 }
@@ -786,25 +788,9 @@ impl KVMVcpu {
                         _ => info!("Unknow hyper call!!!!! address is {}", addr),
                     }
                 }
-                VcpuExit::MmioRead(addr, _data) => {
-                    panic!(
-                        "CPU[{}] Received an MMIO Read Request for the address {:#x}.",
-                        self.id, addr,
-                    );
-                }
-                VcpuExit::MmioWrite(addr, _data) => {
-                    panic!(
-                        "[{}] Received an MMIO Write Request to the address {:#x}.",
-                        self.id, addr,
-                    );
-                }
                 VcpuExit::Hlt => {
                     error!("in hlt....");
                 }
-                VcpuExit::FailEntry => {
-                    info!("get fail entry***********************************");
-                    break;
-                }
                 VcpuExit::Exception => {
                     info!("get exception");
                 }
@@ -888,31 +874,16 @@ impl KVMVcpu {
                     //         .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
                 }
                 r => {
-                    let vcpu_sregs = self
-                        .vcpu
-                        .get_sregs()
-                        .map_err(|e| Error::IOError(format!("vcpu::error is {:?}", e)))?;
-                    let regs = self
-                        .vcpu
-                        .get_regs()
-                        .map_err(|e| Error::IOError(format!("vcpu::error is {:?}", e)))?;
-
-                    error!("Panic: CPU[{}] Unexpected exit reason: {:?}, regs is {:#x?}, sregs is {:#x?}",
-                        self.id, r, regs, vcpu_sregs);
-
-                    backtracer::trace(regs.rip, regs.rsp, regs.rbp, &mut |frame| {
-                        print!("Unexpected exit frame is {:#x?}", frame);
-                        true
-                    });
-                    unsafe {
-                        libc::exit(0);
-                    }
+                    self.Crash(&format!("unexpected exit reason {:?}", r));
                 }
             }
         }
+    }
 
-        //let mut vcpu_regs = self.vcpu_fd.get_regs()?;
-        Ok(())
+    // Crash stops the sandbox when the vcpu gets a vm exit or a fault the qvisor
+    // can't service, see CrashVcpu.
+    pub fn Crash(&self, reason: &str) -> ! {
+        CrashVcpu(self.id, &self.vcpu, reason);
     }
 
     pub fn VcpuWait(&self) -> i64 {
@@ -1074,7 +1045,7 @@ pub const VCPU_WAIT_CYCLES: i64 = 1_000_000; // 1ms
 
 impl CPULocal {
     pub fn Init(&mut self, vcpuId: usize) {
-        let epfd = unsafe { epoll_create1(0) };
+        let epfd = unsafe { epoll_create1(libc::EPOLL_CLOEXEC) };
 
         if epfd == -1 {
             panic!(
@@ -1273,3 +1244,137 @@ extern "C" fn handleSigChild(signal: i32) {
         }
     }
 }
+
+// CrashReason returns why the guest can't go on after the vm exit, None if the
+// qvisor services the exit.
+pub fn CrashReason(exit: &VcpuExit) -> Option<String> {
+    match exit {
+        VcpuExit::MmioRead(addr, _data) => return Some(format!("mmio read of {:#x}", addr)),
+        VcpuExit::MmioWrite(addr, _data) => return Some(format!("mmio write to {:#x}", addr)),
+        VcpuExit::FailEntry => return Some("vm entry failed".to_string()),
+        // the guest got a fault while delivering a double fault
+        VcpuExit::Shutdown => return Some("triple fault".to_string()),
+        _ => return None,
+    }
+}
+
+// CrashVcpu stops the sandbox when the guest crashed the vcpu. The registers go
+// to the log and the sandbox exits with GUEST_CRASH_EXIT_STATUS, so that it shows
+// up as a crashed container rather than a crashed runtime.
+pub fn CrashVcpu(id: usize, vcpu: &kvm_ioctls::VcpuFd, reason: &str) -> ! {
+    let regs = vcpu.get_regs().unwrap_or_default();
+    let sregs = vcpu.get_sregs().unwrap_or_default();
+    let isUser = (sregs.ss.selector & 0x3) != 0;
+
+    error!(
+        "vcpu {} crashed: {}, rip is {:#x}, user mode is {}, regs is {:#x?}, sregs is {:#x?}",
+        id, reason, regs.rip, isUser, regs, sregs
+    );
+    // the frame pointer of a crashed kernel can be garbage as well
+    if !isUser && regs.rbp > 0xfff {
+        backtracer::trace(regs.rip, regs.rsp, regs.rbp, &mut |frame| {
+            print!("crash frame is {:#x?}", frame);
+            true
+        });
+    }
+
+    eprintln!(
+        "Guest error: vcpu {} crashed: {} at rip {:#x}",
+        id, reason, regs.rip
+    );
+    super::print::LOG.Clear();
+    unsafe {
+        libc::exit(GUEST_CRASH_EXIT_STATUS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kvm_ioctls::Kvm;
+    use std::path::Path;
+
+    // TripleFault runs a real mode guest which can't deliver its int3 as the
+    // interrupt vector table is empty, and handles its vm exits like KVMVcpu::run
+    fn TripleFault() -> ! {
+        let kvm = Kvm::new().expect("open /dev/kvm");
+        let vm = kvm.create_vm().expect("create vm");
+
+        let size = 0x1000;
+        let mem = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert!(mem != libc::MAP_FAILED);
+        // int3
+        unsafe { *(mem as *mut u8) = 0xcc };
+
+        let region = kvm_userspace_memory_region {
+            slot: 0,
+            guest_phys_addr: 0x1000,
+            memory_size: size as u64,
+            userspace_addr: mem as u64,
+            flags: 0,
+        };
+        unsafe {
+            vm.set_user_memory_region(region)
+                .expect("set memory region")
+        };
+
+        let vcpu = vm.create_vcpu(0).expect("create vcpu");
+        let mut sregs = vcpu.get_sregs().unwrap();
+        sregs.cs.base = 0;
+        sregs.cs.selector = 0;
+        sregs.idt.base = 0;
+        sregs.idt.limit = 0;
+        vcpu.set_sregs(&sregs).unwrap();
+
+        let mut regs = vcpu.get_regs().unwrap();
+        regs.rip = 0x1000;
+        regs.rflags = 2;
+        vcpu.set_regs(&regs).unwrap();
+
+        loop {
+            let exit = vcpu.run().expect("run vcpu");
+            if let Some(reason) = CrashReason(&exit) {
+                CrashVcpu(0, &vcpu, &reason);
+            }
+        }
+    }
+
+    const GUEST_CRASH_CHILD_ENV: &str = "QUARK_GUEST_CRASH_CHILD";
+
+    // the guest crash exits the whole process, test_GuestCrashExitStatus runs it in
+    // a copy of the test binary
+    #[test]
+    #[ignore]
+    fn test_GuestCrashChild() {
+        if std::env::var_os(GUEST_CRASH_CHILD_ENV).is_none() {
+            println!("skipped, it is run by test_GuestCrashExitStatus");
+            return;
+        }
+
+        TripleFault();
+    }
+
+    // needs kvm and the log directory of the qvisor, run with --ignored
+    #[test]
+    #[ignore]
+    fn test_GuestCrashExitStatus() {
+        assert!(Path::new("/dev/kvm").exists(), "needs /dev/kvm");
+        assert!(Path::new("/var/log/quark").exists(), "needs /var/log/quark");
+
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(&["test_GuestCrashChild", "--ignored", "--test-threads=1"])
+            .env(GUEST_CRASH_CHILD_ENV, "1")
+            .status()
+            .expect("run the test binary");
+        assert_eq!(status.code(), Some(GUEST_CRASH_EXIT_STATUS));
+    }
+}
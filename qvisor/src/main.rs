@@ -95,6 +95,8 @@ use self::vmspace::hibernate::*;
 use self::vmspace::host_pma_keeper::*;
 use self::vmspace::hostfdnotifier::*;
 use self::vmspace::kernel_io_thread::*;
+use self::vmspace::record_replay::*;
+use self::vmspace::uring_fallback::*;
 //use crate::qlib::mem::bitmap_allocator::BitmapAllocatorWrapper;
 
 use self::vmspace::uringMgr::*;
@@ -145,15 +147,19 @@ lazy_static! {
         config.Load();
         Mutex::new(config)
     };
+    // the allowlist doesn't change after the start, TryOpenAt checks it without the lock
+    pub static ref DEVICE_PASSTHROUGH: DeviceAllowlist = QUARK_CONFIG.lock().DevicePassthrough;
     pub static ref URING_MGR: Arc<Mutex<UringMgr>> = {
-        let uringQueueSize = if QUARK_CONFIG.lock().UringBuf {
-            1024
-        } else {
-            64
-        };
+        let (sqSize, cqSize) = QUARK_CONFIG
+            .lock()
+            .UringSizes()
+            .expect("configuration wrong UringSqSize/UringCqSize");
 
-        Arc::new(Mutex::new(UringMgr::New(uringQueueSize)))
+        Arc::new(Mutex::new(UringMgr::New(sqSize, cqSize)))
     };
+    pub static ref URING_FALLBACK: Mutex<UringFallback> = Mutex::new(UringFallback::New());
+    pub static ref RECORD_REPLAY: Mutex<RecordReplay> =
+        Mutex::new(RecordReplay::New(QUARK_CONFIG.lock().RecordReplay));
     pub static ref KERNEL_IO_THREAD: KIOThread = KIOThread::New();
     pub static ref GLOCK: Mutex<()> = Mutex::new(());
     pub static ref SANDBOX: Mutex<Sandbox> = Mutex::new(Sandbox::default());
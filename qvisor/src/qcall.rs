@@ -64,6 +64,24 @@ impl KVMVcpu {
             Msg::Fallocate(msg) => {
                 ret = super::VMSpace::Fallocate(msg.fd, msg.mode, msg.offset, msg.len) as u64;
             }
+            Msg::CopyFileRange(msg) => {
+                ret = super::VMSpace::CopyFileRange(
+                    msg.srcFd,
+                    msg.srcOffset,
+                    msg.dstFd,
+                    msg.dstOffset,
+                    msg.len,
+                ) as u64;
+            }
+            Msg::FileClone(msg) => {
+                ret = super::VMSpace::FileClone(
+                    msg.srcFd,
+                    msg.srcOffset,
+                    msg.dstFd,
+                    msg.dstOffset,
+                    msg.len,
+                ) as u64;
+            }
             Msg::RenameAt(msg) => {
                 ret = super::VMSpace::RenameAt(msg.olddirfd, msg.oldpath, msg.newdirfd, msg.newpath)
                     as u64;
@@ -335,21 +353,31 @@ impl KVMVcpu {
                 ret = super::VMSpace::NewTmpfsFile(msg.typ, msg.addr) as u64;
             }
             Msg::IoUringEnter(msg) => {
-                ret = match URING_MGR
-                    .lock()
-                    .Enter(msg.toSubmit, msg.minComplete, msg.flags)
-                {
+                ret = match UringMgr::GlobalEnter(msg.toSubmit, msg.minComplete, msg.flags) {
                     Ok(v) => v as u64,
                     Err(Error::SysError(v)) => -v as i64 as u64,
                     _ => panic!("UringMgr Enter fail"),
                 }
             }
+            Msg::IoUringSubmit(_msg) => {
+                ret = match IOURING.IOUring().HostSubmit() {
+                    Ok(v) => v as u64,
+                    Err(Error::SysError(v)) => -v as i64 as u64,
+                    _ => panic!("IoUringSubmit fail"),
+                }
+            }
             Msg::Statm(msg) => {
                 ret = super::VMSpace::Statm(msg.buf) as u64;
             }
             Msg::NewSocket(msg) => {
                 ret = super::VMSpace::NewSocket(msg.fd) as u64;
             }
+            Msg::NewFile(msg) => {
+                ret = super::VMSpace::NewFile(msg.fd) as u64;
+            }
+            Msg::ReleaseFd(msg) => {
+                ret = super::VMSpace::ReleaseFd(msg.fd) as u64;
+            }
             Msg::HostEpollWaitProcess(_) => {
                 ret = super::VMSpace::HostEpollWaitProcess() as u64;
             }
@@ -384,6 +412,9 @@ impl KVMVcpu {
             Msg::TlbShootdown(msg) => {
                 ret = SHARE_SPACE.TlbShootdown(msg.vcpuMask);
             }
+            Msg::InterruptVcpu(msg) => {
+                ret = SHARE_SPACE.InterruptVcpu(msg.vcpuId as usize);
+            }
             Msg::HostMemoryBarrier(_) => {
                 VMSpace::HostMemoryBarrier();
             }
@@ -74,6 +74,15 @@ impl CmdCmd {
     }
 }
 
+// the settings of the config file which don't fit in the Copy Config
+#[derive(Debug, Default, Deserialize)]
+struct StringConfig {
+    #[serde(default)]
+    DevicePassthrough: Vec<String>,
+    #[serde(default)]
+    KernelRelease: String,
+}
+
 impl Config {
     pub const CONFIG_FILE: &'static str = "/etc/quark/config.json";
 
@@ -86,6 +95,15 @@ impl Config {
 
         let config = serde_json::from_str(&contents).expect("configuration wrong format");
         *self = config;
+
+        let strings: StringConfig =
+            serde_json::from_str(&contents).expect("configuration wrong format");
+        self.DevicePassthrough = DeviceAllowlist::Parse(&strings.DevicePassthrough)
+            .expect("configuration wrong DevicePassthrough");
+        self.KernelRelease = KernelRelease::Parse(&strings.KernelRelease)
+            .expect("configuration wrong KernelRelease");
+        self.UringSizes()
+            .expect("configuration wrong UringSqSize/UringCqSize");
         return true;
     }
 
@@ -23,9 +23,11 @@ use super::config;
 use super::config::*;
 use super::create::*;
 use super::delete::*;
+use super::dump::*;
 use super::exec::*;
 use super::kill::*;
 use super::list::*;
+use super::mem_stat::*;
 use super::pause::*;
 use super::ps::*;
 use super::resume::*;
@@ -33,6 +35,7 @@ use super::run::*;
 use super::sandbox::*;
 use super::start::*;
 use super::state::*;
+use super::syscall_stat::*;
 use super::wait::*;
 
 fn id_validator(val: String) -> core::result::Result<(), String> {
@@ -184,6 +187,9 @@ pub fn Parse() -> Result<Arguments> {
         .subcommand(DeleteCmd::SubCommand(&common))
         .subcommand(StateCmd::SubCommand(&common))
         .subcommand(SandboxCmd::SubCommand(&common))
+        .subcommand(SyscallStatCmd::SubCommand(&common))
+        .subcommand(MemStatCmd::SubCommand(&common))
+        .subcommand(DumpCmd::SubCommand(&common))
         .get_matches_from(get_args());
 
     let level = match matches.occurrences_of("v") {
@@ -275,6 +281,18 @@ pub fn Parse() -> Result<Arguments> {
             config: gConfig,
             cmd: Command::SandboxCmd(SandboxCmd::Init(&cmd_matches)?),
         },
+        ("syscallstat", Some(cmd_matches)) => Arguments {
+            config: gConfig,
+            cmd: Command::SyscallStatCmd(SyscallStatCmd::Init(&cmd_matches)?),
+        },
+        ("memstat", Some(cmd_matches)) => Arguments {
+            config: gConfig,
+            cmd: Command::MemStatCmd(MemStatCmd::Init(&cmd_matches)?),
+        },
+        ("dump", Some(cmd_matches)) => Arguments {
+            config: gConfig,
+            cmd: Command::DumpCmd(DumpCmd::Init(&cmd_matches)?),
+        },
         // We should never reach here because clap already enforces this
         _ => panic!("command not recognized"),
     };
@@ -305,6 +323,9 @@ pub enum Command {
     DeleteCmd(DeleteCmd),
     StateCmd(StateCmd),
     SandboxCmd(SandboxCmd),
+    SyscallStatCmd(SyscallStatCmd),
+    MemStatCmd(MemStatCmd),
+    DumpCmd(DumpCmd),
 }
 
 pub fn Run(args: &mut Arguments) -> Result<()> {
@@ -324,5 +345,8 @@ pub fn Run(args: &mut Arguments) -> Result<()> {
         Command::DeleteCmd(cmd) => return cmd.Run(&mut args.config),
         Command::StateCmd(cmd) => return cmd.Run(&mut args.config),
         Command::SandboxCmd(cmd) => return cmd.Run(&mut args.config),
+        Command::SyscallStatCmd(cmd) => return cmd.Run(&mut args.config),
+        Command::MemStatCmd(cmd) => return cmd.Run(&mut args.config),
+        Command::DumpCmd(cmd) => return cmd.Run(&mut args.config),
     }
 }
@@ -0,0 +1,167 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::fs::File;
+use std::io;
+use std::io::Write;
+
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::control_msg::*;
+use super::super::cmd::config::*;
+use super::super::container::container::*;
+use super::command::*;
+
+#[derive(Debug)]
+pub struct DumpCmd {
+    pub id: String,
+    pub tid: i32,
+    pub stackBytes: u64,
+    pub output: String,
+}
+
+impl DumpCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let tidStr = cmd_matches.value_of("tid").unwrap();
+        let tid = match tidStr.parse::<i32>() {
+            Ok(tid) if tid > 0 => tid,
+            _ => return Err(Error::Common(format!("invalid tid {}", tidStr))),
+        };
+
+        let stackStr = cmd_matches.value_of("stack").unwrap();
+        let stackBytes = match stackStr.parse::<u64>() {
+            Ok(n) => n,
+            Err(_) => return Err(Error::Common(format!("invalid stack size {}", stackStr))),
+        };
+
+        return Ok(Self {
+            id: cmd_matches.value_of("id").unwrap().to_string(),
+            tid: tid,
+            stackBytes: stackBytes,
+            output: cmd_matches.value_of("output").unwrap_or("").to_string(),
+        });
+    }
+
+    pub fn SubCommand<'a, 'b>(common: &CommonArgs<'a, 'b>) -> App<'a, 'b> {
+        return SubCommand::with_name("dump")
+            .setting(AppSettings::ColoredHelp)
+            .arg(&common.id_arg)
+            .arg(
+                Arg::with_name("tid")
+                    .help("id of the thread in the sandbox pid namespace")
+                    .long("tid")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("stack")
+                    .help("bytes of the stack to dump from the stack pointer, at most 1MB")
+                    .long("stack")
+                    .default_value("4096")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .help("file to write the dump to, stdout by default")
+                    .long("output")
+                    .takes_value(true),
+            )
+            .about("dump stops a thread and dumps its registers and stack");
+    }
+
+    pub fn Run(&self, gCfg: &GlobalConfig) -> Result<()> {
+        let container = Container::Load(&gCfg.RootDir, &self.id)?;
+
+        let dump = container.DumpThread(self.tid, self.stackBytes)?;
+        let res = if self.output.len() == 0 {
+            WriteThreadDump(&dump, &mut io::stdout())
+        } else {
+            File::create(&self.output).and_then(|mut f| WriteThreadDump(&dump, &mut f))
+        };
+
+        return res.map_err(|e| Error::IOError(format!("io::error is {:?}", e)));
+    }
+}
+
+// WriteThreadDump writes the registers, one per line, and a hexdump of the stack
+pub fn WriteThreadDump(dump: &ThreadDump, w: &mut dyn Write) -> io::Result<()> {
+    writeln!(w, "thread {} ({})", dump.tid, dump.name)?;
+    for (name, val) in &dump.regs {
+        writeln!(w, "{:<8} 0x{:016x}", name, val)?;
+    }
+
+    writeln!(
+        w,
+        "\nstack 0x{:x}, {} bytes",
+        dump.stackAddr,
+        dump.stack.len()
+    )?;
+    for (i, line) in dump.stack.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+            .collect();
+        writeln!(
+            w,
+            "{:016x}  {:<47}  |{}|",
+            dump.stackAddr + i as u64 * 16,
+            hex.join(" "),
+            ascii
+        )?;
+    }
+
+    return w.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_WriteThreadDump() {
+        let mut stack = b"quark".to_vec();
+        stack.extend_from_slice(&0x401136u64.to_le_bytes());
+        stack.resize(20, 0xff);
+        let dump = ThreadDump {
+            tid: 7,
+            name: "spin".to_string(),
+            regs: vec![
+                ("rip".to_string(), 0x401136),
+                ("rsp".to_string(), 0x7ffc0000fff0),
+            ],
+            stackAddr: 0x7ffc0000fff0,
+            stack: stack,
+        };
+
+        let mut out = Vec::new();
+        WriteThreadDump(&dump, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines[0], "thread 7 (spin)");
+        assert_eq!(lines[1], "rip      0x0000000000401136");
+        assert_eq!(lines[2], "rsp      0x00007ffc0000fff0");
+        assert_eq!(lines[4], "stack 0x7ffc0000fff0, 20 bytes");
+        assert_eq!(
+            lines[5],
+            "00007ffc0000fff0  71 75 61 72 6b 36 11 40 00 00 00 00 00 ff ff ff  |quark6.@........|"
+        );
+        assert_eq!(
+            lines[6],
+            "00007ffc00010000  ff ff ff ff                                      |....|"
+        );
+    }
+}
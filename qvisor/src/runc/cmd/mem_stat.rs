@@ -0,0 +1,113 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::io::Write;
+use tabwriter::TabWriter;
+
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::control_msg::*;
+use super::super::cmd::config::*;
+use super::super::container::container::*;
+use super::command::*;
+
+#[derive(Debug)]
+pub struct MemStatCmd {
+    pub id: String,
+    pub mappings: bool,
+}
+
+impl MemStatCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        return Ok(Self {
+            id: cmd_matches.value_of("id").unwrap().to_string(),
+            mappings: cmd_matches.is_present("mappings"),
+        });
+    }
+
+    pub fn SubCommand<'a, 'b>(common: &CommonArgs<'a, 'b>) -> App<'a, 'b> {
+        return SubCommand::with_name("memstat")
+            .setting(AppSettings::ColoredHelp)
+            .arg(&common.id_arg)
+            .arg(
+                Arg::with_name("mappings")
+                    .help("also display the usage of each mapping")
+                    .long("mappings"),
+            )
+            .about("memstat displays the memory usage of the processes in the sandbox, in KB");
+    }
+
+    pub fn Run(&self, gCfg: &GlobalConfig) -> Result<()> {
+        let container = Container::Load(&gCfg.RootDir, &self.id)?;
+
+        let stat = container.MemStat()?;
+        PrintMemStatToTable(&stat, self.mappings);
+
+        return Ok(());
+    }
+}
+
+pub fn PrintMemStatToTable(stat: &MemStat, mappings: bool) {
+    let mut tw = TabWriter::new(vec![]).minwidth(10).padding(3);
+
+    write!(
+        &mut tw,
+        "allocator total {} free {}\n\n",
+        stat.allocatorTotal >> 10,
+        stat.allocatorFree >> 10
+    )
+    .unwrap();
+
+    write!(&mut tw, "PID\tMAPPED\tRSS\tFILE\tANON\tCMD\n").unwrap();
+    for p in &stat.processes {
+        write!(
+            &mut tw,
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            p.pid,
+            p.mapped >> 10,
+            p.resident >> 10,
+            p.file >> 10,
+            p.anon >> 10,
+            p.cmd
+        )
+        .unwrap();
+    }
+
+    if mappings {
+        for p in &stat.processes {
+            write!(&mut tw, "\n{} {}\n", p.pid, p.cmd).unwrap();
+            write!(&mut tw, "ADDRESS\tPERMS\tSIZE\tRSS\tFILE\tANON\tNAME\n").unwrap();
+            for m in &p.mappings {
+                write!(
+                    &mut tw,
+                    "{:x}-{:x}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    m.start,
+                    m.end,
+                    m.perms,
+                    (m.end - m.start) >> 10,
+                    m.resident >> 10,
+                    m.file >> 10,
+                    m.anon >> 10,
+                    m.name
+                )
+                .unwrap();
+            }
+        }
+    }
+    tw.flush().unwrap();
+
+    let written = String::from_utf8(tw.into_inner().unwrap()).unwrap();
+    println!("{}", written);
+}
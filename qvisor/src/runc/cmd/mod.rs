@@ -18,9 +18,11 @@ pub mod command;
 pub mod config;
 pub mod create;
 pub mod delete;
+pub mod dump;
 pub mod exec;
 pub mod kill;
 pub mod list;
+pub mod mem_stat;
 pub mod pause;
 pub mod ps;
 pub mod resume;
@@ -28,4 +30,5 @@ pub mod run;
 pub mod sandbox;
 pub mod start;
 pub mod state;
+pub mod syscall_stat;
 pub mod wait;
@@ -0,0 +1,91 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::io::Write;
+use tabwriter::TabWriter;
+
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::control_msg::*;
+use super::super::cmd::config::*;
+use super::super::container::container::*;
+use super::command::*;
+
+#[derive(Debug)]
+pub struct SyscallStatCmd {
+    pub id: String,
+    pub reset: bool,
+}
+
+impl SyscallStatCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        return Ok(Self {
+            id: cmd_matches.value_of("id").unwrap().to_string(),
+            reset: cmd_matches.is_present("reset"),
+        });
+    }
+
+    pub fn SubCommand<'a, 'b>(common: &CommonArgs<'a, 'b>) -> App<'a, 'b> {
+        return SubCommand::with_name("syscallstat")
+            .setting(AppSettings::ColoredHelp)
+            .arg(&common.id_arg)
+            .arg(
+                Arg::with_name("reset")
+                    .help("reset the statistic after it is displayed")
+                    .long("reset"),
+            )
+            .about(
+                "syscallstat displays the per syscall count and latency summary, like strace -c",
+            );
+    }
+
+    pub fn Run(&self, gCfg: &GlobalConfig) -> Result<()> {
+        let container = Container::Load(&gCfg.RootDir, &self.id)?;
+
+        let mut stats = container.SyscallStat(self.reset)?;
+        stats.sort_by(|a, b| b.total.cmp(&a.total));
+        PrintSyscallStatToTable(&stats);
+
+        return Ok(());
+    }
+}
+
+pub fn PrintSyscallStatToTable(stats: &[SyscallStatEntry]) {
+    let mut tw = TabWriter::new(vec![]).minwidth(10).padding(3);
+
+    let mut sum = 0;
+    for s in stats {
+        sum += s.total;
+    }
+
+    write!(&mut tw, "% TIME\tUSECS\tCALLS\tMIN\tMAX\tAVG\tSYSCALL\n").unwrap();
+    for s in stats {
+        let percent = if sum == 0 {
+            0.0
+        } else {
+            s.total as f64 * 100.0 / sum as f64
+        };
+        write!(
+            &mut tw,
+            "{:.2}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            percent, s.total, s.count, s.min, s.max, s.avg, s.name
+        )
+        .unwrap();
+    }
+    tw.flush().unwrap();
+
+    let written = String::from_utf8(tw.into_inner().unwrap()).unwrap();
+    println!("{}", written);
+}
@@ -913,6 +913,27 @@ impl Container {
         return self.Sandbox.as_ref().unwrap().Processes(&self.ID);
     }
 
+    pub fn SyscallStat(&self, reset: bool) -> Result<Vec<SyscallStatEntry>> {
+        self.RequireStatus(
+            "get syscall statistic of",
+            &[Status::Running, Status::Paused],
+        )?;
+        return self.Sandbox.as_ref().unwrap().SyscallStat(reset);
+    }
+
+    pub fn MemStat(&self) -> Result<MemStat> {
+        self.RequireStatus(
+            "get memory statistic of",
+            &[Status::Running, Status::Paused],
+        )?;
+        return self.Sandbox.as_ref().unwrap().MemStat();
+    }
+
+    pub fn DumpThread(&self, tid: i32, stackBytes: u64) -> Result<ThreadDump> {
+        self.RequireStatus("dump thread of", &[Status::Running, Status::Paused])?;
+        return self.Sandbox.as_ref().unwrap().DumpThread(tid, stackBytes);
+    }
+
     // Start starts running the containerized process inside the sandbox.
     pub fn Start(&mut self) -> Result<()> {
         info!("Start container {}", &self.ID);
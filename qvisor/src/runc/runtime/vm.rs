@@ -49,6 +49,7 @@ use super::super::super::qlib::task_mgr::*;
 use super::super::super::qlib::ShareSpace;
 use super::super::super::runc::runtime::loader::*;
 use super::super::super::syncmgr;
+use super::super::super::vmspace::uringMgr::UringMgr;
 use super::super::super::vmspace::*;
 use super::super::super::SHARE_SPACE;
 use super::super::super::SHARE_SPACE_STRUCT;
@@ -62,6 +63,10 @@ lazy_static! {
     static ref DUMP: AtomicU64 = AtomicU64::new(0);
 }
 
+// the exit status of the sandbox when the guest crashes the vcpu, as for a process
+// killed by SIGSEGV, see KVMVcpu::Crash
+pub const GUEST_CRASH_EXIT_STATUS: i32 = 128 + Signal::SIGSEGV;
+
 #[inline]
 pub fn IsRunning() -> bool {
     return EXIT_STATUS.load(Ordering::Relaxed) == -1;
@@ -80,6 +85,11 @@ pub fn Dump(id: usize) -> bool {
     return DUMP.load(Ordering::Acquire) & (0x1 << id) > 0;
 }
 
+pub fn SetDump(id: usize) {
+    assert!(id < MAX_VCPU_COUNT);
+    DUMP.fetch_or(0x1 << id, Ordering::Release);
+}
+
 pub fn SetDumpAll() {
     DUMP.store(u64::MAX, Ordering::Release);
 }
@@ -176,7 +186,8 @@ impl VirtualMachine {
         controlSock: i32,
         rdmaSvcCliSock: i32,
         podId: [u8; 64],
-    ) {
+    ) -> Result<()> {
+        UringMgr::DetectFallback();
         SHARE_SPACE_STRUCT
             .lock()
             .Init(cpuCount, controlSock, rdmaSvcCliSock, podId);
@@ -194,7 +205,7 @@ impl VirtualMachine {
         let logfd = super::super::super::print::LOG.Logfd();
         URING_MGR.lock().Init();
 
-        URING_MGR.lock().Addfd(logfd).unwrap();
+        URING_MGR.lock().Addfd(logfd)?;
 
         for i in 0..cpuCount {
             let addr = MemoryDef::KVM_IOEVENTFD_BASEADDR + (i as u64) * 8;
@@ -206,14 +217,12 @@ impl VirtualMachine {
         }
 
         KERNEL_IO_THREAD.Init(sharespace.scheduler.VcpuArr[0].eventfd);
+        KERNEL_IO_THREAD.SetupEpoll()?;
         URING_MGR
             .lock()
             .SetupEventfd(sharespace.scheduler.VcpuArr[0].eventfd);
-        URING_MGR
-            .lock()
-            .Addfd(sharespace.HostHostEpollfd())
-            .unwrap();
-        URING_MGR.lock().Addfd(controlSock).unwrap();
+        URING_MGR.lock().Addfd(sharespace.HostHostEpollfd())?;
+        URING_MGR.lock().Addfd(controlSock)?;
         sharespace.SetIOUringsAddr(URING_MGR.lock().IOUringsAddr());
         IOURING.SetValue(sharespace.GetIOUringAddr());
 
@@ -234,6 +243,7 @@ impl VirtualMachine {
         let syncPrint = sharespace.config.read().SyncPrint();
         super::super::super::print::SetSyncPrint(syncPrint);
         error!("VM::InitShareSpace, after call init 2");
+        return Ok(());
     }
 
     pub fn Init(args: Args /*args: &Args, kvmfd: i32*/) -> Result<Self> {
@@ -370,7 +380,7 @@ impl VirtualMachine {
             vms.args = Some(args);
         }
 
-        Self::InitShareSpace(&vm_fd, cpuCount, controlSock, rdmaSvcCliSock, podId);
+        Self::InitShareSpace(&vm_fd, cpuCount, controlSock, rdmaSvcCliSock, podId)?;
 
         info!("before loadKernel");
 
@@ -467,6 +477,22 @@ impl VirtualMachine {
             );
         }
 
+        let (watchdogMs, watchdogPanic) = {
+            let config = QUARK_CONFIG.lock();
+            (config.VcpuWatchdogMs, config.VcpuWatchdogPanic)
+        };
+        if watchdogMs > 0 {
+            let vcpus = self.vcpus.clone();
+            threads.push(
+                thread::Builder::new()
+                    .name("watchdog".to_string())
+                    .spawn(move || {
+                        watchdog::VcpuWatchdog::Run(vcpus, watchdogMs, watchdogPanic);
+                    })
+                    .unwrap(),
+            );
+        }
+
         for t in threads {
             t.join().expect("the working threads has panicked");
         }
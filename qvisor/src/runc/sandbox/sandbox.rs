@@ -341,6 +341,54 @@ impl Sandbox {
         }
     }
 
+    pub fn SyscallStat(&self, reset: bool) -> Result<Vec<SyscallStatEntry>> {
+        info!("Getting syscall statistic in sandbox {}", self.ID);
+        let client = self.SandboxConnect()?;
+
+        let req = UCallReq::SyscallStat(SyscallStatArgs { reset: reset });
+
+        let resp = client.Call(&req)?;
+        match resp {
+            UCallResp::SyscallStatResp(stats) => Ok(stats),
+            resp => {
+                panic!("SyscallStat get unknow resp {:?}", resp);
+            }
+        }
+    }
+
+    pub fn MemStat(&self) -> Result<MemStat> {
+        info!("Getting memory statistic in sandbox {}", self.ID);
+        let client = self.SandboxConnect()?;
+
+        let req = UCallReq::MemStat;
+
+        let resp = client.Call(&req)?;
+        match resp {
+            UCallResp::MemStatResp(stat) => Ok(stat),
+            resp => {
+                panic!("MemStat get unknow resp {:?}", resp);
+            }
+        }
+    }
+
+    pub fn DumpThread(&self, tid: i32, stackBytes: u64) -> Result<ThreadDump> {
+        info!("Dumping thread {} in sandbox {}", tid, self.ID);
+        let client = self.SandboxConnect()?;
+
+        let req = UCallReq::DumpThread(DumpThreadArgs {
+            tid: tid,
+            stackBytes: stackBytes,
+        });
+
+        let resp = client.Call(&req)?;
+        match resp {
+            UCallResp::DumpThreadResp(dump) => Ok(dump),
+            resp => {
+                panic!("DumpThread get unknow resp {:?}", resp);
+            }
+        }
+    }
+
     pub fn StartRootContainer(&self) -> Result<()> {
         let client = self.SandboxConnect()?;
 
@@ -39,6 +39,28 @@ pub enum UCallReq {
     CreateSubContainer(CreateArgs),
     StartSubContainer(StartArgs),
     WaitAll,
+    SyscallStat(SyscallStatArgs),
+    MemStat,
+    DumpThread(DumpThreadArgs),
+    Batch(Vec<UCallReq>),
+}
+
+impl UCallReq {
+    // Requests which pass no fds and are answered by the sandbox right away can be sent
+    // together in one Batch. The waits and DumpThread block until the sandbox has
+    // something to report, so they would hold up the whole batch.
+    pub fn Batchable(&self) -> bool {
+        match self {
+            UCallReq::Pause
+            | UCallReq::Unpause
+            | UCallReq::Ps(_)
+            | UCallReq::Signal(_)
+            | UCallReq::ContainerDestroy(_)
+            | UCallReq::SyscallStat(_)
+            | UCallReq::MemStat => return true,
+            _ => return false,
+        }
+    }
 }
 
 impl FileDescriptors for UCallReq {
@@ -41,9 +41,8 @@ impl UCallClient {
         self.sock.WriteAll(&reqArr)?;
 
         let (len, _fds) = self.sock.ReadLen()?;
-        let mut buf: [u8; UCALL_BUF_LEN] = [0; UCALL_BUF_LEN];
-
-        assert!(len < UCALL_BUF_LEN, "UCallClient::Call resp is too long");
+        // the response such as the syscall statistic might be longer than UCALL_BUF_LEN
+        let mut buf: Vec<u8> = vec![0; len];
         self.sock.ReadAll(&mut buf[0..len])?;
         let resp: UCallResp = serde_json::from_slice(&buf[0..len])
             .map_err(|e| Error::Common(format!("UCallClient deser error is {:?}", e)))?;
@@ -54,6 +53,31 @@ impl UCallClient {
         return Ok(resp);
     }
 
+    // Send the requests in one message, the responses come back in the same order. A
+    // failed request gets a UCallRespErr in its slot and doesn't stop the others.
+    pub fn CallBatch(&self, reqs: Vec<UCallReq>) -> Result<Vec<UCallResp>> {
+        let count = reqs.len();
+        let resp = self.Call(&UCallReq::Batch(reqs))?;
+        match resp {
+            UCallResp::BatchResp(resps) => {
+                if resps.len() != count {
+                    return Err(Error::Common(format!(
+                        "UCallClient batch expects {} responses, got {}",
+                        count,
+                        resps.len()
+                    )));
+                }
+                return Ok(resps);
+            }
+            resp => {
+                return Err(Error::Common(format!(
+                    "UCallClient batch unexpected response {:?}",
+                    resp
+                )))
+            }
+        }
+    }
+
     pub fn StreamCall(&self, req: &UCallReq) -> Result<()> {
         let reqArr = serde_json::to_vec(req)
             .map_err(|e| Error::Common(format!("UCallClient ser error is {:?}", e)))?;
@@ -20,7 +20,6 @@ use super::super::qlib::linux_def::*;
 use super::super::qlib::loader;
 use super::super::runc::container::container::*;
 use super::super::vmspace::*;
-use super::super::URING_MGR;
 use super::ucall::*;
 use super::usocket::*;
 
@@ -37,8 +36,17 @@ pub fn ReadControlMsg(fd: i32) -> Result<ControlMsg> {
         }
     };
 
-    let msg = ProcessReqHandler(&mut req, &fds);
-    return msg;
+    let msg = match ProcessReqHandler(&mut req, &fds) {
+        Ok(msg) => msg,
+        Err(e) => {
+            let err = UCallResp::UCallRespErr(format!("{:?}", e));
+            usock.SendResp(&err)?;
+            usock.Drop();
+            return Err(e);
+        }
+    };
+
+    return Ok(msg);
 }
 
 pub fn RootContainerStartHandler(start: &RootContainerStart) -> Result<ControlMsg> {
@@ -70,9 +78,23 @@ pub fn ExecProcessHandler(execArgs: &mut ExecArgs, fds: &[i32]) -> Result<Contro
         let osfd = execArgs.Fds[i];
         //VMSpace::UnblockFd(osfd);
 
-        let hostfd = GlobalIOMgr().AddFile(osfd);
-        URING_MGR.lock().Addfd(osfd).unwrap();
-        process.Stdiofds[i] = hostfd;
+        let hostfd = VMSpace::AddHostFd(osfd, false, true);
+        if hostfd < 0 {
+            // AddHostFd has closed osfd, drop the ones added before it and close the rest
+            for j in 0..i {
+                GlobalIOMgr().RemoveFd(execArgs.Fds[j]);
+                unsafe {
+                    libc::close(execArgs.Fds[j]);
+                }
+            }
+            for j in i + 1..execArgs.Fds.len() {
+                unsafe {
+                    libc::close(execArgs.Fds[j]);
+                }
+            }
+            return Err(Error::SysError(-hostfd as i32));
+        }
+        process.Stdiofds[i] = hostfd as i32;
     }
 
     let msg = ControlMsg::New(Payload::ExecProcess(process));
@@ -157,6 +179,39 @@ pub fn StartSubContainerHandler(args: &mut StartArgs) -> Result<ControlMsg> {
     return Ok(msg);
 }
 
+pub fn SyscallStatHandler(args: &SyscallStatArgs) -> Result<ControlMsg> {
+    let msg = ControlMsg::New(Payload::SyscallStat(args.clone()));
+    return Ok(msg);
+}
+
+pub fn MemStatHandler() -> Result<ControlMsg> {
+    let msg = ControlMsg::New(Payload::MemStat);
+    return Ok(msg);
+}
+
+pub fn DumpThreadHandler(args: &DumpThreadArgs) -> Result<ControlMsg> {
+    let msg = ControlMsg::New(Payload::DumpThread(args.clone()));
+    return Ok(msg);
+}
+
+pub fn BatchHandler(reqs: &mut Vec<UCallReq>) -> Result<ControlMsg> {
+    let mut payloads = Vec::with_capacity(reqs.len());
+    for req in reqs.iter_mut() {
+        if !req.Batchable() {
+            return Err(Error::Common(format!(
+                "UCallReq {:?} can't be sent in a batch",
+                req
+            )));
+        }
+
+        let msg = ProcessReqHandler(req, &[])?;
+        payloads.push(msg.payload);
+    }
+
+    let msg = ControlMsg::New(Payload::Batch(payloads));
+    return Ok(msg);
+}
+
 pub fn ProcessReqHandler(req: &mut UCallReq, fds: &[i32]) -> Result<ControlMsg> {
     let msg = match req {
         UCallReq::RootContainerStart(start) => RootContainerStartHandler(start)?,
@@ -171,6 +226,10 @@ pub fn ProcessReqHandler(req: &mut UCallReq, fds: &[i32]) -> Result<ControlMsg>
         UCallReq::CreateSubContainer(args) => CreateSubContainerHandler(args, fds)?,
         UCallReq::StartSubContainer(args) => StartSubContainerHandler(args)?,
         UCallReq::WaitAll => WaitAll()?,
+        UCallReq::SyscallStat(args) => SyscallStatHandler(args)?,
+        UCallReq::MemStat => MemStatHandler()?,
+        UCallReq::DumpThread(args) => DumpThreadHandler(args)?,
+        UCallReq::Batch(reqs) => BatchHandler(reqs)?,
     };
 
     return Ok(msg);
@@ -254,7 +254,18 @@ impl USocket {
         let (len, fds) = self.ReadLen()?;
         let mut buf: [u8; UCALL_BUF_LEN] = [0; UCALL_BUF_LEN];
 
-        assert!(len < UCALL_BUF_LEN, "UCallClient::Call req is too long");
+        if len >= UCALL_BUF_LEN {
+            for fd in &fds {
+                unsafe {
+                    close(*fd);
+                }
+            }
+            return Err(Error::Common(format!(
+                "UCallSrv req len {} is longer than {}",
+                len, UCALL_BUF_LEN
+            )));
+        }
+
         self.ReadAll(&mut buf[0..len])?;
         let req: UCallReq = serde_json::from_slice(&buf[0..len])
             .map_err(|e| Error::Common(format!("UCallSrv deser error is {:?}", e)))?;
@@ -274,3 +285,102 @@ impl USocket {
         return Ok(());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::ucall_client::*;
+    use super::*;
+
+    fn SocketPair() -> (USocket, USocket) {
+        let mut fds = [0; 2];
+        let ret = unsafe { socketpair(AF_UNIX, SOCK_STREAM, 0, &mut fds[0] as *mut i32) };
+        assert_eq!(ret, 0);
+        return (USocket { socket: fds[0] }, USocket { socket: fds[1] });
+    }
+
+    #[test]
+    fn test_GetReqTooLong() {
+        let (cli, srv) = SocketPair();
+
+        let req = serde_json::to_vec(&UCallReq::Ps("abc".to_string())).unwrap();
+        cli.WriteLen(req.len(), &[]).unwrap();
+        cli.WriteAll(&req).unwrap();
+        match srv.GetReq() {
+            Ok((UCallReq::Ps(cid), fds)) => {
+                assert_eq!(cid, "abc");
+                assert_eq!(fds.len(), 0);
+            }
+            r => panic!("GetReq returns {:?}", r),
+        }
+
+        // an oversized request is an error for the server, not a panic
+        cli.WriteLen(UCALL_BUF_LEN, &[]).unwrap();
+        assert!(srv.GetReq().is_err());
+
+        unsafe {
+            close(cli.socket);
+            close(srv.socket);
+        }
+    }
+
+    #[test]
+    fn test_BatchInOrder() {
+        let (cli, srv) = SocketPair();
+
+        let server = thread::spawn(move || {
+            let (req, fds) = srv.GetReq().unwrap();
+            assert_eq!(fds.len(), 0);
+            let reqs = match req {
+                UCallReq::Batch(reqs) => reqs,
+                r => panic!("GetReq returns {:?}", r),
+            };
+            assert_eq!(reqs.len(), 3);
+            let mut resps = Vec::new();
+            for req in reqs {
+                let resp = match req {
+                    UCallReq::Pause => UCallResp::PauseResp,
+                    UCallReq::Ps(cid) => UCallResp::UCallRespErr(cid),
+                    UCallReq::Unpause => UCallResp::UnpauseResp,
+                    r => panic!("unexpected req {:?}", r),
+                };
+                resps.push(resp);
+            }
+            srv.SendResp(&UCallResp::BatchResp(resps)).unwrap();
+            unsafe {
+                close(srv.socket);
+            }
+        });
+
+        let client = UCallClient { sock: cli };
+        let reqs = vec![
+            UCallReq::Pause,
+            UCallReq::Ps("abc".to_string()),
+            UCallReq::Unpause,
+        ];
+        let resps = client.CallBatch(reqs).unwrap();
+        server.join().unwrap();
+
+        // one response per request, in the order of the requests, and a failed request
+        // only fills its own slot
+        assert_eq!(resps.len(), 3);
+        assert!(matches!(resps[0], UCallResp::PauseResp));
+        match &resps[1] {
+            UCallResp::UCallRespErr(s) => assert_eq!(s, "abc"),
+            r => panic!("second resp is {:?}", r),
+        }
+        assert!(matches!(resps[2], UCallResp::UnpauseResp));
+
+        unsafe {
+            close(client.sock.socket);
+        }
+    }
+
+    #[test]
+    fn test_Batchable() {
+        assert!(UCallReq::Ps("abc".to_string()).Batchable());
+        assert!(UCallReq::MemStat.Batchable());
+        assert!(!UCallReq::WaitContainer("abc".to_string()).Batchable());
+        assert!(!UCallReq::WaitAll.Batchable());
+        assert!(!UCallReq::Batch(Vec::new()).Batchable());
+    }
+}
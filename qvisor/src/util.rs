@@ -39,6 +39,12 @@ pub fn SysRet(ret: i64) -> i64 {
     return ret;
 }
 
+// The internal host fds must not leak into a helper the qvisor forks/execs.
+pub fn IsCloexec(fd: i32) -> bool {
+    let flags = unsafe { fcntl(fd, F_GETFD) };
+    return flags >= 0 && flags & FD_CLOEXEC != 0;
+}
+
 pub fn GetNoRet(ret: i32) -> Result<()> {
     if ret == -1 {
         return Err(Error::SysError(errno::errno().0));
@@ -54,6 +60,7 @@ pub struct Cond {
 
 impl Cond {
     pub fn New() -> Result<Self> {
+        // no EFD_CLOEXEC: the Cond is serialized to the exec'd sandbox process and used there
         let res = unsafe { eventfd(0, 0) };
 
         let eventfd = GetRet(res)?;
@@ -114,7 +121,7 @@ pub struct Epoll {
 
 impl Epoll {
     pub fn New() -> Result<Self> {
-        let epollfd = unsafe { epoll_create1(0) };
+        let epollfd = unsafe { epoll_create1(EPOLL_CLOEXEC) };
 
         if epollfd == -1 {
             info!("USrvSocket epoll_create fail");
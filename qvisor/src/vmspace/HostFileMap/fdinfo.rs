@@ -21,6 +21,7 @@ use crate::qlib::kernel::GlobalIOMgr;
 use crate::qlib::socket_buf::*;
 
 use super::super::super::util::*;
+use super::super::fault_inject::*;
 use super::super::qlib::common::*;
 use super::super::qlib::rdmasocket::*;
 use super::super::FD_NOTIFIER;
@@ -33,6 +34,10 @@ impl FdInfo {
     }
 
     pub fn BufWrite(fd: i32, addr: u64, len: usize, offset: isize) -> i64 {
+        if let Some(ret) = Fault(FaultSite::Write, fd) {
+            return ret;
+        }
+
         let ret = unsafe {
             if offset < 0 {
                 write(fd as c_int, addr as *const c_void, len as size_t)
@@ -76,12 +81,20 @@ impl FdInfo {
     }
 
     pub fn Write(fd: i32, iovs: u64, iovcnt: i32) -> i64 {
+        if let Some(ret) = Fault(FaultSite::Write, fd) {
+            return ret;
+        }
+
         let ret = unsafe { writev(fd as c_int, iovs as *const iovec, iovcnt) as i64 };
 
         return SysRet(ret as i64);
     }
 
     pub fn Append(fd: i32, iovs: u64, iovcnt: i32, fileLenAddr: u64) -> i64 {
+        if let Some(ret) = Fault(FaultSite::Write, fd) {
+            return ret;
+        }
+
         let end = unsafe { lseek(fd as c_int, 0, libc::SEEK_END) };
 
         if end < 0 {
@@ -127,6 +140,10 @@ impl FdInfo {
     }
 
     pub fn ReadAt(fd: i32, iovs: u64, iovcnt: i32, offset: u64) -> i64 {
+        if let Some(ret) = Fault(FaultSite::Read, fd) {
+            return ret;
+        }
+
         let ret = unsafe {
             if offset as i64 == -1 {
                 readv(fd as c_int, iovs as *const iovec, iovcnt) as i64
@@ -139,6 +156,10 @@ impl FdInfo {
     }
 
     pub fn WriteAt(fd: i32, iovs: u64, iovcnt: i32, offset: u64) -> i64 {
+        if let Some(ret) = Fault(FaultSite::Write, fd) {
+            return ret;
+        }
+
         let ret = unsafe {
             if offset as i64 == -1 {
                 writev(fd as c_int, iovs as *const iovec, iovcnt) as i64
@@ -219,9 +240,7 @@ impl FdInfo {
             return SysRet(newfd as i64);
         }
 
-        let hostfd = GlobalIOMgr().AddSocket(newfd);
-        URING_MGR.lock().Addfd(newfd).unwrap();
-        return SysRet(hostfd as i64);
+        return VMSpace::AddHostFd(newfd, true, true);
     }
 
     pub fn Connect(sockfd: i32, addr: u64, addrlen: u32) -> i64 {
@@ -20,17 +20,20 @@ pub mod socket_info;
 use core::sync::atomic::AtomicI32;
 use libc::*;
 use spin::Mutex;
+use spin::MutexGuard;
 
 use crate::qlib::fileinfo::*;
 
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::ringbuf::*;
 use super::super::qlib::SysCallID;
 use super::super::*;
 use super::syscall::*;
 
 const START_FD: i32 = 0; //stdin:0, stdout:1, stderr:2
 const MAX_FD: i32 = 65535; //skip stdin, stdout, stderr
+const FD_CHANGE_QUEUE_SIZE: usize = 4096;
 
 //map between guest/process fd to host fd
 
@@ -58,6 +61,7 @@ impl IOMgr {
             eventfd: eventfd,
             epollfd: AtomicI32::new(0),
             fdTbl: Mutex::new(FdTbl::New()),
+            fdChanges: QRingQueue::New(FD_CHANGE_QUEUE_SIZE),
         };
 
         res.DrainPipe()?;
@@ -86,18 +90,47 @@ impl IOMgr {
         }
     }
 
+    // FdTbl locks the table with the fds the guest has opened and closed through
+    // io_uring applied. The changes are queued in the order of the opens and closes,
+    // a fd the guest has closed can't be reused before its Release is in the queue.
+    pub fn FdTbl(&self) -> MutexGuard<FdTbl> {
+        let mut released = Vec::new();
+        {
+            let mut tbl = self.fdTbl.lock();
+            while let Some(change) = self.fdChanges.Pop() {
+                match change {
+                    HostFdChange::Add(fd) => {
+                        tbl.AddFile(fd).unwrap();
+                    }
+                    HostFdChange::Release(fd) => {
+                        if let Some(info) = tbl.Remove(fd) {
+                            info.lock().fd = -1;
+                            released.push(info);
+                        }
+                    }
+                }
+            }
+        }
+
+        // dropping a FdInfo takes the table lock
+        drop(released);
+        return self.fdTbl.lock();
+    }
+
+    pub fn GetFdInfo(&self, fd: i32) -> Option<FdInfo> {
+        return self.FdTbl().Get(fd);
+    }
+
     //return guest fd
     pub fn AddFile(&self, fd: i32) -> i32 {
-        self.fdTbl
-            .lock()
+        self.FdTbl()
             .AddFile(fd)
             .expect("hostfdMap: guest fd alloc fail");
         return fd;
     }
 
     pub fn AddSocket(&self, fd: i32) -> i32 {
-        self.fdTbl
-            .lock()
+        self.FdTbl()
             .AddSocket(fd)
             .expect("hostfdMap: guest fd alloc fail");
         return fd;
@@ -105,12 +138,12 @@ impl IOMgr {
 
     //ret: true: exist, false: not exist
     pub fn RemoveFd(&self, fd: i32) -> Option<FdInfo> {
-        let fdInfo = self.fdTbl.lock().Remove(fd);
+        let fdInfo = self.FdTbl().Remove(fd);
         return fdInfo;
     }
 
     pub fn GetFdByHost(&self, fd: i32) -> Option<i32> {
-        if self.fdTbl.lock().Contains(fd) {
+        if self.FdTbl().Contains(fd) {
             return Some(fd);
         }
 
@@ -118,7 +151,7 @@ impl IOMgr {
     }
 
     pub fn AddWait(&self, fd: i32, mask: EventMask) {
-        let fdInfo = self.GetByHost(fd);
+        let fdInfo = self.GetFdInfo(fd);
         match fdInfo {
             None => (),
             Some(fdInfo) => {
@@ -128,7 +161,7 @@ impl IOMgr {
     }
 
     pub fn RemoveWait(&self, fd: i32, mask: EventMask) {
-        let fdInfo = self.GetByHost(fd);
+        let fdInfo = self.GetFdInfo(fd);
         match fdInfo {
             None => (),
             Some(fdInfo) => {
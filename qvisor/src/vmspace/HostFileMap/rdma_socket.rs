@@ -99,7 +99,7 @@ impl RDMAServerSock {
             };
 
             let rdmaSocket = RDMADataSock::New(fd, socketBuf.clone(), rdmaType);
-            let fdInfo = GlobalIOMgr().GetByHost(fd).unwrap();
+            let fdInfo = GlobalIOMgr().GetFdInfo(fd).unwrap();
             *fdInfo.lock().sockInfo.lock() = SockInfo::RDMADataSocket(rdmaSocket);
 
             URING_MGR.lock().Addfd(fd).unwrap();
@@ -0,0 +1,313 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Fault injection for the host syscalls done on behalf of the guest, so that the
+// error paths can be tested without a failing disk. It is only built for the unit
+// tests and with the "fault_inject" feature, where the faults are loaded from
+// QUARK_FAULT_INJECT, e.g. "read=EIO:1,write=ENOSPC" makes the next host read fail
+// with EIO and every host write fail with ENOSPC. "read=EIO:1@name" only fails the
+// next read of a host file whose path ends with name. uring_read and uring_write
+// target the io_uring reads and writes, which only go through the qvisor with
+// UringFallback. Without the feature Fault() is always None. The unit tests inject
+// with InjectFault, which only hits the host calls of the calling test's thread.
+
+#[cfg(any(test, feature = "fault_inject"))]
+use std::collections::BTreeMap;
+
+#[cfg(any(test, feature = "fault_inject"))]
+use lazy_static::lazy_static;
+#[cfg(any(test, feature = "fault_inject"))]
+use spin::Mutex;
+
+#[cfg(any(test, feature = "fault_inject"))]
+use super::super::qlib::linux_def::*;
+
+#[cfg(any(test, feature = "fault_inject"))]
+pub const FAULT_INJECT_ENV: &str = "QUARK_FAULT_INJECT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FaultSite {
+    Read,
+    Write,
+    UringRead,
+    UringWrite,
+}
+
+#[cfg(any(test, feature = "fault_inject"))]
+impl FaultSite {
+    pub fn Parse(name: &str) -> Option<Self> {
+        match name {
+            "read" => return Some(Self::Read),
+            "write" => return Some(Self::Write),
+            "uring_read" => return Some(Self::UringRead),
+            "uring_write" => return Some(Self::UringWrite),
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "fault_inject"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    pub errno: i32,
+    // None: fail every call
+    pub count: Option<u64>,
+    // only fail the calls on the fds whose host path ends with it
+    pub path: Option<String>,
+}
+
+#[cfg(any(test, feature = "fault_inject"))]
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    faults: BTreeMap<FaultSite, Fault>,
+}
+
+#[cfg(any(test, feature = "fault_inject"))]
+impl FaultInjector {
+    pub fn ParseErrno(name: &str) -> Option<i32> {
+        let errno = match name {
+            "EIO" => SysErr::EIO,
+            "ENOSPC" => SysErr::ENOSPC,
+            "EDQUOT" => SysErr::EDQUOT,
+            "EFBIG" => SysErr::EFBIG,
+            "EROFS" => SysErr::EROFS,
+            "EINTR" => SysErr::EINTR,
+            "EAGAIN" => SysErr::EAGAIN,
+            "ENOMEM" => SysErr::ENOMEM,
+            _ => name.parse().ok()?,
+        };
+
+        return Some(errno);
+    }
+
+    // Parse parses a comma separated list of site=ERRNO[:count][@path].
+    pub fn Parse(spec: &str) -> core::result::Result<Self, String> {
+        let mut injector = Self::default();
+        for item in spec.split(',').map(|s| s.trim()).filter(|s| s.len() > 0) {
+            let (site, fault) = match item.split_once('=') {
+                Some(f) => f,
+                None => return Err(format!("fault {:?} isn't site=ERRNO[:count][@path]", item)),
+            };
+
+            let (fault, path) = match fault.split_once('@') {
+                Some((_, "")) => return Err(format!("empty fault path in {:?}", item)),
+                Some((fault, path)) => (fault, Some(path.to_string())),
+                None => (fault, None),
+            };
+
+            let site = match FaultSite::Parse(site) {
+                Some(s) => s,
+                None => return Err(format!("unknown fault site {:?}", site)),
+            };
+
+            let (errno, count) = match fault.split_once(':') {
+                Some((errno, count)) => match count.parse() {
+                    Ok(c) if c > 0 => (errno, Some(c)),
+                    Ok(_) | Err(_) => return Err(format!("bad fault count {:?}", count)),
+                },
+                None => (fault, None),
+            };
+
+            let errno = match Self::ParseErrno(errno) {
+                Some(e) if e > 0 => e,
+                _ => return Err(format!("bad fault errno {:?}", errno)),
+            };
+
+            injector.faults.insert(site, Fault { errno, count, path });
+        }
+
+        return Ok(injector);
+    }
+
+    pub fn Inject(&mut self, site: FaultSite, errno: i32, count: Option<u64>) {
+        self.faults.insert(
+            site,
+            Fault {
+                errno,
+                count,
+                path: None,
+            },
+        );
+    }
+
+    fn FdPath(fd: i32) -> String {
+        return match std::fs::read_link(format!("/proc/self/fd/{}", fd)) {
+            Ok(p) => p.to_string_lossy().into_owned(),
+            Err(_) => String::new(),
+        };
+    }
+
+    // Take returns the negative errno the call on fd at site has to fail with.
+    pub fn Take(&mut self, site: FaultSite, fd: i32) -> Option<i64> {
+        let fault = self.faults.get_mut(&site)?;
+        if let Some(path) = &fault.path {
+            if !Self::FdPath(fd).ends_with(path.as_str()) {
+                return None;
+            }
+        }
+
+        let errno = fault.errno;
+        let used = match &mut fault.count {
+            None => false,
+            Some(c) => {
+                *c = c.saturating_sub(1);
+                *c == 0
+            }
+        };
+
+        if used {
+            self.faults.remove(&site);
+        }
+
+        return Some(-errno as i64);
+    }
+}
+
+#[cfg(any(test, feature = "fault_inject"))]
+lazy_static! {
+    pub static ref FAULT_INJECTOR: Mutex<FaultInjector> = Mutex::new(FaultInjector::FromEnv());
+}
+
+#[cfg(any(test, feature = "fault_inject"))]
+impl FaultInjector {
+    pub fn FromEnv() -> Self {
+        let spec = match std::env::var(FAULT_INJECT_ENV) {
+            Ok(s) => s,
+            Err(_) => return Self::default(),
+        };
+
+        match Self::Parse(&spec) {
+            Ok(injector) => return injector,
+            Err(e) => {
+                error!("{}: {}, no fault is injected", FAULT_INJECT_ENV, e);
+                return Self::default();
+            }
+        }
+    }
+}
+
+// the tests run in parallel threads of one process, so each of them gets its own
+// injector instead of FAULT_INJECTOR
+#[cfg(test)]
+thread_local! {
+    static TEST_FAULT_INJECTOR: core::cell::RefCell<FaultInjector> =
+        core::cell::RefCell::new(FaultInjector::default());
+}
+
+#[cfg(test)]
+pub fn InjectFault(site: FaultSite, errno: i32, count: Option<u64>) {
+    TEST_FAULT_INJECTOR.with(|injector| injector.borrow_mut().Inject(site, errno, count));
+}
+
+#[cfg(test)]
+pub fn Fault(site: FaultSite, fd: i32) -> Option<i64> {
+    return TEST_FAULT_INJECTOR.with(|injector| injector.borrow_mut().Take(site, fd));
+}
+
+#[cfg(all(not(test), feature = "fault_inject"))]
+pub fn Fault(site: FaultSite, fd: i32) -> Option<i64> {
+    return FAULT_INJECTOR.lock().Take(site, fd);
+}
+
+#[cfg(not(any(test, feature = "fault_inject")))]
+#[inline(always)]
+pub fn Fault(_site: FaultSite, _fd: i32) -> Option<i64> {
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::qlib::fileinfo::*;
+    use super::*;
+    use libc::iovec;
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+
+    #[test]
+    fn test_FaultParse() {
+        let mut injector = FaultInjector::Parse("read=EIO:2, write=28").unwrap();
+        assert_eq!(
+            injector.Take(FaultSite::Read, -1),
+            Some(-SysErr::EIO as i64)
+        );
+        assert_eq!(
+            injector.Take(FaultSite::Read, -1),
+            Some(-SysErr::EIO as i64)
+        );
+        assert_eq!(injector.Take(FaultSite::Read, -1), None);
+        for _ in 0..3 {
+            assert_eq!(
+                injector.Take(FaultSite::Write, -1),
+                Some(-SysErr::ENOSPC as i64)
+            );
+        }
+
+        assert!(FaultInjector::Parse("open=EIO").is_err());
+        assert!(FaultInjector::Parse("read=EWHAT").is_err());
+        assert!(FaultInjector::Parse("read=EIO:x").is_err());
+        assert!(FaultInjector::Parse("read=EIO:0").is_err());
+        assert!(FaultInjector::Parse("read").is_err());
+        assert!(FaultInjector::Parse("read=EIO@").is_err());
+    }
+
+    // a fault with a path only hits the fds of the files with that path
+    #[test]
+    fn test_FaultPath() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = std::fs::File::create(dir.path().join("fault_target")).unwrap();
+        let other = std::fs::File::create(dir.path().join("fault_other")).unwrap();
+
+        let mut injector = FaultInjector::Parse("uring_read=EIO:1@fault_target").unwrap();
+        assert_eq!(injector.Take(FaultSite::UringRead, other.as_raw_fd()), None);
+        assert_eq!(
+            injector.Take(FaultSite::UringRead, target.as_raw_fd()),
+            Some(-SysErr::EIO as i64)
+        );
+        assert_eq!(
+            injector.Take(FaultSite::UringRead, target.as_raw_fd()),
+            None
+        );
+    }
+
+    // the guest gets the injected errno from a host read and the next read works
+    #[test]
+    fn test_FaultReadEIO() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"data").unwrap();
+
+        let mut buf = [0u8; 4];
+        let iov = iovec {
+            iov_base: buf.as_mut_ptr() as *mut _,
+            iov_len: buf.len(),
+        };
+        let iovs = &iov as *const _ as u64;
+        let fd = file.as_raw_fd();
+
+        InjectFault(FaultSite::Read, SysErr::EIO, Some(1));
+        assert_eq!(FdInfo::ReadAt(fd, iovs, 1, 0), -SysErr::EIO as i64);
+        assert_eq!(FdInfo::ReadAt(fd, iovs, 1, 0), 4);
+        assert_eq!(&buf, b"data");
+    }
+
+    // a fault injected by one test doesn't leak into the host calls of another thread
+    #[test]
+    fn test_FaultPerThread() {
+        InjectFault(FaultSite::Write, SysErr::ENOSPC, Some(1));
+        std::thread::spawn(|| assert_eq!(Fault(FaultSite::Write, -1), None))
+            .join()
+            .unwrap();
+        assert_eq!(Fault(FaultSite::Write, -1), Some(-SysErr::ENOSPC as i64));
+        assert_eq!(Fault(FaultSite::Write, -1), None);
+    }
+}
@@ -76,7 +76,20 @@ impl HiberMgr {
         return Ok(map);
     }
 
+    // MlockedPages returns the physical pages that some memory manager has mlocked.
+    // A page shared with another memory manager is kept resident for all of them.
+    pub fn MlockedPages(&self) -> BTreeSet<u64> {
+        let intern = self.lock();
+        let mut locked = BTreeSet::new();
+        for (_, mm) in &intern.memmgrs {
+            mm.Upgrade().MlockedPhyPages(&mut locked);
+        }
+
+        return locked;
+    }
+
     pub fn SwapOutUserPages(&self, start: u64, len: u64) -> Result<()> {
+        let locked = self.MlockedPages();
         let mut intern = self.lock();
         let mut map = BTreeSet::new();
         for (_, mm) in &intern.memmgrs {
@@ -84,7 +97,7 @@ impl HiberMgr {
             mm.pagetable
                 .write()
                 .pt
-                .SwapOutPages(start, len, &mut map, true)
+                .SwapOutPages(start, len, &mut map, &locked, true)
                 .unwrap();
         }
 
@@ -115,13 +128,14 @@ impl HiberMgr {
     }
 
     pub fn ReapSwapOut(&self, start: u64, len: u64, map: &mut BTreeSet<u64>) -> Result<()> {
+        let locked = self.MlockedPages();
         let mut intern = self.lock();
         for (_, mm) in &intern.memmgrs {
             let mm = mm.Upgrade();
             mm.pagetable
                 .write()
                 .pt
-                .SwapOutPages(start, len, map, false)
+                .SwapOutPages(start, len, map, &locked, false)
                 .unwrap();
         }
 
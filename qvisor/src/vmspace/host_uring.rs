@@ -27,6 +27,7 @@ use super::super::qlib::uring::*;
 use super::super::util::*;
 use super::super::*;
 use super::syscall::*;
+use super::uring_fallback::UringFallback;
 use crate::vmspace::kernel::GlobalIOMgr;
 
 impl Mmap {
@@ -137,6 +138,56 @@ impl IoUring {
         self.submitter().submit()
     }
 
+    // Fallback builds the ring of the io_uring fallback mode. There is no host ring,
+    // only the submitq/completeq pair shared by the guest and UringFallback.
+    pub fn Fallback() -> IoUring {
+        return IoUring {
+            fd: Fd(-1),
+            submitq: QMutex::new(VecDeque::with_capacity(16)),
+            ..Default::default()
+        };
+    }
+
+    fn FallbackDrain(&self, fallback: &mut UringFallback) -> usize {
+        let mut count = 0;
+        loop {
+            let entry = match self.submitq.lock().pop_front() {
+                None => break,
+                Some(e) => e,
+            };
+
+            fallback.Submit(entry);
+            count += 1;
+        }
+
+        return count;
+    }
+
+    // FallbackSubmit hands the queued entries to UringFallback and runs them. One
+    // thread at a time runs them, outside URING_FALLBACK, and picks up the entries
+    // the other threads queued meanwhile before it hands the work back.
+    pub fn FallbackSubmit(&self) -> usize {
+        let (mut count, mut work) = {
+            let mut fallback = URING_FALLBACK.lock();
+            let count = self.FallbackDrain(&mut fallback);
+            if fallback.processing || (count == 0 && !fallback.Ready()) {
+                return count;
+            }
+
+            (count, fallback.Take())
+        };
+
+        loop {
+            count += work.Process(&self.completeq);
+
+            let mut fallback = URING_FALLBACK.lock();
+            count += self.FallbackDrain(&mut fallback);
+            if !fallback.Merge(&mut work) {
+                return count;
+            }
+        }
+    }
+
     pub fn CopyCompleteEntry(&self) -> usize {
         let mut count = 0;
 
@@ -163,6 +214,10 @@ impl IoUring {
 
     #[inline]
     pub fn HostSubmit(&self) -> Result<usize> {
+        if QUARK_CONFIG.lock().UringFallback {
+            return Ok(self.FallbackSubmit());
+        }
+
         if QUARK_CONFIG.lock().UringBuf {
             self.CopyCompleteEntry();
 
@@ -36,7 +36,7 @@ pub struct HostFdNotifier {
 
 impl HostFdNotifier {
     pub fn New() -> Self {
-        let epfd = unsafe { epoll_create1(0) };
+        let epfd = unsafe { epoll_create1(libc::EPOLL_CLOEXEC) };
 
         if epfd == -1 {
             panic!(
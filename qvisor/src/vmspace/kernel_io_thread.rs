@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::sync::atomic::AtomicI32;
+use core::sync::atomic::AtomicI64;
 use core::sync::atomic::Ordering;
 use libc::*;
 
@@ -19,22 +21,35 @@ use super::super::kvm_vcpu::*;
 use super::super::qlib::common::*;
 use super::super::qlib::kernel::kernel::timer::TIMER_STORE;
 use super::super::qlib::kernel::GlobalRDMASvcCli;
+use super::super::qlib::kernel::Scale;
 use super::super::qlib::kernel::ASYNC_PROCESS;
 use super::super::qlib::kernel::IOURING;
 use super::super::qlib::kernel::TSC;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::ShareSpace;
+use super::super::util::*;
 use super::super::*;
 
 pub struct KIOThread {
     pub eventfd: i32,
+    // the epoll fd Wait blocks on, set up by SetupEpoll
+    pub epfd: AtomicI32,
+    // tsc of the last round which found some work
+    pub lastActive: AtomicI64,
+    // IOThreadParkMs of the config, 0 doesn't park
+    pub parkMs: u64,
 }
 
 pub const IO_WAIT_CYCLES: i64 = 100_000_000; // 1ms
 
 impl KIOThread {
     pub fn New() -> Self {
-        return Self { eventfd: 0 };
+        return Self {
+            eventfd: 0,
+            epfd: AtomicI32::new(-1),
+            lastActive: AtomicI64::new(0),
+            parkMs: QUARK_CONFIG.lock().IOThreadParkMs,
+        };
     }
 
     pub fn Init(&self, eventfd: i32) {
@@ -64,13 +79,35 @@ impl KIOThread {
         return count;
     }
 
-    pub fn Process(sharespace: &ShareSpace) {
+    // An idle sandbox (no completions, no host epoll events and no ready task for
+    // IOThreadParkMs) stops busy polling and goes straight back to epoll_wait, so it
+    // doesn't hold a host core. Any new work still wakes it through the vcpu0 eventfd or
+    // the host epollfd, and the first round which finds work unparks it. A ready task
+    // keeps it polling, the vcpus running it are likely to submit io soon.
+    pub fn Parked(&self, sharespace: &ShareSpace) -> bool {
+        if self.parkMs == 0 {
+            return false;
+        }
+
+        if sharespace.scheduler.GlobalReadyTaskCnt() > 0 {
+            self.lastActive.store(TSC.Rdtsc(), Ordering::Relaxed);
+            return false;
+        }
+
+        let idle = TSC.Rdtsc() - self.lastActive.load(Ordering::Relaxed);
+        return Scale(idle) / 1000 >= self.parkMs as i64;
+    }
+
+    pub fn Process(&self, sharespace: &ShareSpace) {
         let mut start = TSC.Rdtsc();
 
         while !sharespace.Shutdown() {
             let count = Self::ProcessOnce(sharespace);
             if count > 0 {
-                start = TSC.Rdtsc()
+                start = TSC.Rdtsc();
+                self.lastActive.store(start, Ordering::Relaxed);
+            } else if self.Parked(sharespace) {
+                break;
             }
 
             if TSC.Rdtsc() - start >= IO_WAIT_CYCLES {
@@ -79,86 +116,67 @@ impl KIOThread {
         }
     }
 
-    pub fn Wait(&self, sharespace: &ShareSpace) -> Result<()> {
-        let epfd = unsafe { epoll_create1(0) };
-
-        if epfd == -1 {
-            panic!(
-                "CPULocal::Init {} create epollfd fail, error is {}",
-                0,
-                errno::errno().0
-            );
-        }
-
+    fn EpollAdd(epfd: i32, fd: i32) -> Result<()> {
         let mut ev = epoll_event {
             events: EVENT_READ as u32 | EPOLLET as u32,
-            u64: self.eventfd as u64,
+            u64: fd as u64,
         };
 
-        super::VMSpace::UnblockFd(self.eventfd);
+        let ret = unsafe { epoll_ctl(epfd, EPOLL_CTL_ADD, fd, &mut ev as *mut epoll_event) };
+        return GetNoRet(ret);
+    }
 
-        let ret = unsafe {
-            epoll_ctl(
-                epfd,
-                EPOLL_CTL_ADD,
-                self.eventfd,
-                &mut ev as *mut epoll_event,
-            )
+    // NewEpoll returns an epoll fd watching fds
+    fn NewEpoll(fds: &[i32]) -> Result<i32> {
+        let epfd = match GetRet(unsafe { epoll_create1(libc::EPOLL_CLOEXEC) }) {
+            Ok(fd) => fd,
+            Err(e) => {
+                error!("KIOThread create epollfd fail, error is {:?}", e);
+                return Err(e);
+            }
         };
 
-        if ret == -1 {
-            panic!(
-                "CPULocal::Init {} add eventfd fail, error is {}",
-                0,
-                errno::errno().0
-            );
+        for &fd in fds {
+            match Self::EpollAdd(epfd, fd) {
+                Ok(()) => (),
+                Err(e) => {
+                    error!("KIOThread add fd {} fail, error is {:?}", fd, e);
+                    unsafe {
+                        libc::close(epfd);
+                    }
+                    return Err(e);
+                }
+            }
         }
 
-        let mut ev = epoll_event {
-            events: EVENT_READ as u32 | EPOLLET as u32,
-            u64: FD_NOTIFIER.Epollfd() as u64,
-        };
+        return Ok(epfd);
+    }
 
-        let ret = unsafe {
-            epoll_ctl(
-                epfd,
-                EPOLL_CTL_ADD,
-                FD_NOTIFIER.Epollfd(),
-                &mut ev as *mut epoll_event,
-            )
-        };
+    // SetupEpoll is called when the vm is set up, so that a failure (e.g. EMFILE on a
+    // crowded host) fails the sandbox start with an error instead of a vcpu thread.
+    pub fn SetupEpoll(&self) -> Result<()> {
+        // the eventfd is the vcpu0 one, created by CPULocal::Init
+        debug_assert!(IsCloexec(self.eventfd));
+        super::VMSpace::UnblockFd(self.eventfd);
 
-        if ret == -1 {
-            panic!(
-                "CPULocal::Init {} add host epollfd fail, error is {}",
-                0,
-                errno::errno().0
-            );
+        let mut fds = vec![self.eventfd, FD_NOTIFIER.Epollfd()];
+        if QUARK_CONFIG.lock().EnableRDMA {
+            fds.push(GlobalRDMASvcCli().cliEventFd);
         }
 
-        if QUARK_CONFIG.lock().EnableRDMA {
-            let mut ev = epoll_event {
-                events: EVENT_READ as u32 | EPOLLET as u32,
-                u64: GlobalRDMASvcCli().cliEventFd as u64,
-            };
+        // the fds and the deadlines the pending io_uring fallback entries wait for
+        if QUARK_CONFIG.lock().UringFallback {
+            fds.push(URING_FALLBACK.lock().epollfd);
+        }
 
-            let ret = unsafe {
-                epoll_ctl(
-                    epfd,
-                    EPOLL_CTL_ADD,
-                    GlobalRDMASvcCli().cliEventFd,
-                    &mut ev as *mut epoll_event,
-                )
-            };
+        let epfd = Self::NewEpoll(&fds)?;
+        self.epfd.store(epfd, Ordering::Release);
+        return Ok(());
+    }
 
-            if ret == -1 {
-                panic!(
-                    "CPULocal::Init {} add host epollfd fail, error is {}",
-                    0,
-                    errno::errno().0
-                );
-            }
-        }
+    pub fn Wait(&self, sharespace: &ShareSpace) -> Result<()> {
+        let epfd = self.epfd.load(Ordering::Acquire);
+        assert!(epfd >= 0, "KIOThread::Wait before SetupEpoll");
 
         let mut events = [epoll_event { events: 0, u64: 0 }; 3];
 
@@ -176,7 +194,7 @@ impl KIOThread {
                     .store(0, Ordering::Release);
             }
 
-            Self::Process(sharespace);
+            self.Process(sharespace);
 
             let ret =
                 unsafe { libc::read(self.eventfd, &mut data as *mut _ as *mut libc::c_void, 8) };
@@ -250,3 +268,29 @@ impl KIOThread {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_NewEpollError() {
+        let efd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        assert!(efd >= 0);
+
+        let epfd = KIOThread::NewEpoll(&[efd]).unwrap();
+        unsafe {
+            libc::close(epfd);
+        }
+
+        // a bad fd is returned as an error, not a panic
+        match KIOThread::NewEpoll(&[efd, -1]) {
+            Err(Error::SysError(errno)) => assert_eq!(errno, SysErr::EBADF),
+            r => panic!("NewEpoll with a bad fd returns {:?}", r),
+        }
+
+        unsafe {
+            libc::close(efd);
+        }
+    }
+}
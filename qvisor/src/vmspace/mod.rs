@@ -14,6 +14,7 @@
 
 pub mod HostFileMap;
 //pub mod TimerMgr;
+pub mod fault_inject;
 pub mod hibernate;
 pub mod host_pma_keeper;
 pub mod host_uring;
@@ -21,9 +22,12 @@ pub mod hostfdnotifier;
 pub mod kernel_io_thread;
 pub mod limits;
 pub mod random;
+pub mod record_replay;
 pub mod syscall;
 pub mod time;
 pub mod uringMgr;
+pub mod uring_fallback;
+pub mod watchdog;
 
 use core::arch::asm;
 use core::sync::atomic;
@@ -31,6 +35,7 @@ use core::sync::atomic::AtomicU64;
 use lazy_static::lazy_static;
 use libc::*;
 use serde_json;
+use std::borrow::Cow;
 use std::env::temp_dir;
 use std::fs;
 use std::marker::Send;
@@ -53,10 +58,13 @@ use super::namespace::MountNs;
 use super::qlib::addr::Addr;
 use super::qlib::common::{Error, Result};
 use super::qlib::control_msg::*;
+use super::qlib::device::DecodeDeviceId;
 use super::qlib::kernel::util::cstring::*;
 use super::qlib::kernel::SignalProcess;
 use super::qlib::linux::membarrier::*;
 use super::qlib::linux_def::*;
+// libc has a PATH_MAX too
+use super::qlib::linux_def::PATH_MAX;
 use super::qlib::pagetable::PageTables;
 use super::qlib::perf_tunning::*;
 use super::qlib::qmsg::*;
@@ -132,7 +140,7 @@ impl VMSpace {
     }
 
     pub fn GetFdInfo(hostfd: i32) -> Option<FdInfo> {
-        return GlobalIOMgr().GetByHost(hostfd);
+        return GlobalIOMgr().GetFdInfo(hostfd);
     }
 
     pub fn ReadDir(dirfd: i32, addr: u64, len: usize, reset: bool) -> i64 {
@@ -231,15 +239,16 @@ impl VMSpace {
         for i in 0..process.Stdiofds.len() {
             let osfd = unsafe { dup(i as i32) as i32 };
 
-            URING_MGR.lock().Addfd(osfd).unwrap();
-
             if osfd < 0 {
-                return osfd as i64;
+                return Self::GetRet(osfd as i64);
             }
 
-            let hostfd = GlobalIOMgr().AddFile(osfd);
+            let hostfd = Self::AddHostFd(osfd, false, true);
+            if hostfd < 0 {
+                return hostfd;
+            }
 
-            process.Stdiofds[i] = hostfd;
+            process.Stdiofds[i] = hostfd as i32;
         }
         process.Root = format!("/{}", &process.ID);
         //process.Root = "/".to_string();
@@ -265,6 +274,9 @@ impl VMSpace {
         let path = format!("/tmp/memfd_{}", uid);
         let cstr = CString::New(&path);
 
+        // the guest's close-on-exec is tracked in its own fd table, the host fd is always
+        // close-on-exec
+        let flags = flags | MfdType::MFD_CLOEXEC;
         let nr = SysCallID::sys_memfd_create as usize;
         let fd =
             unsafe { syscall2(nr, cstr.Ptr() as *const c_char as usize, flags as usize) as i32 };
@@ -297,6 +309,101 @@ impl VMSpace {
         return Self::GetRet(ret as i64);
     }
 
+    // CopyFileRange copies len bytes between two host files and returns the count
+    // copied. A clone is tried first, so that a filesystem with reflink support
+    // (btrfs, XFS) shares the extents instead of copying the data.
+    pub fn CopyFileRange(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+        let srcFd = match Self::GetOsfd(srcFd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        let dstFd = match Self::GetOsfd(dstFd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        let ret = Self::CloneRange(srcFd, srcOffset, dstFd, dstOffset, len);
+        if ret >= 0 {
+            return ret;
+        }
+
+        let mut srcOffset = srcOffset;
+        let mut dstOffset = dstOffset;
+        let ret = unsafe {
+            copy_file_range(
+                srcFd,
+                &mut srcOffset,
+                dstFd,
+                &mut dstOffset,
+                len as usize,
+                0,
+            )
+        };
+
+        return Self::GetRet(ret as i64);
+    }
+
+    // CloneRange clones the source range into the destination with FICLONERANGE. The
+    // range has to be block aligned, except that it may end at the end of the source
+    // file, so a range the ioctl can't take is left to copy_file_range.
+    fn CloneRange(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+        let mut st: stat = unsafe { core::mem::zeroed() };
+        let ret = unsafe { fstat(srcFd, &mut st) };
+        if ret < 0 {
+            return Self::GetRet(ret as i64);
+        }
+
+        if srcOffset >= st.st_size {
+            return 0;
+        }
+
+        let len = core::cmp::min(len, st.st_size - srcOffset);
+        let blockSize = st.st_blksize as i64;
+        if blockSize <= 0
+            || srcOffset % blockSize != 0
+            || dstOffset % blockSize != 0
+            || (len % blockSize != 0 && srcOffset + len != st.st_size)
+        {
+            return -SysErr::EINVAL as i64;
+        }
+
+        let ret = Self::Ficlonerange(srcFd, srcOffset, dstFd, dstOffset, len);
+        if ret < 0 {
+            return ret;
+        }
+
+        return len;
+    }
+
+    // FileClone serves the FICLONE and FICLONERANGE ioctls of the guest, a len of 0
+    // clones up to the end of the source file.
+    pub fn FileClone(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+        let srcFd = match Self::GetOsfd(srcFd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        let dstFd = match Self::GetOsfd(dstFd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        return Self::Ficlonerange(srcFd, srcOffset, dstFd, dstOffset, len);
+    }
+
+    fn Ficlonerange(srcFd: i32, srcOffset: i64, dstFd: i32, dstOffset: i64, len: i64) -> i64 {
+        let range = FileCloneRange {
+            SrcFd: srcFd as i64,
+            SrcOffset: srcOffset as u64,
+            SrcLength: len as u64,
+            DestOffset: dstOffset as u64,
+        };
+
+        let ret = unsafe { ioctl(dstFd, IoCtlCmd::FICLONERANGE, &range as *const _ as u64) };
+        return Self::GetRet(ret as i64);
+    }
+
     pub fn RenameAt(olddirfd: i32, oldpath: u64, newdirfd: i32, newpath: u64) -> i64 {
         let olddirfd = {
             if olddirfd > 0 {
@@ -343,99 +450,147 @@ impl VMSpace {
         return Self::GetRet(ret as i64);
     }
 
-    pub fn GetStr(string: u64) -> &'static str {
+    // StrLen returns the length of the nul terminated string at addr, or None if there
+    // is no terminator in the first PATH_MAX bytes. It reads one byte at a time and stops
+    // at the terminator, so it never touches the memory after a short string.
+    pub fn StrLen(string: u64) -> Option<usize> {
         let ptr = string as *const u8;
-        let slice = unsafe { slice::from_raw_parts(ptr, 1024) };
-
-        let len = {
-            let mut res: usize = 0;
-            for i in 0..1024 {
-                if slice[i] == 0 {
-                    res = i;
-                    break;
-                }
+        for i in 0..PATH_MAX {
+            if unsafe { *ptr.add(i) } == 0 {
+                return Some(i);
             }
+        }
+
+        return None;
+    }
 
-            res
+    // GetStr is for logging only: filenames are arbitrary bytes, so the bytes which
+    // are not valid UTF-8 are shown as U+FFFD.
+    pub fn GetStr(string: u64) -> Cow<'static, str> {
+        let len = match Self::StrLen(string) {
+            Some(len) => len,
+            None => return Cow::Borrowed("<longer than PATH_MAX>"),
         };
 
-        return str::from_utf8(&slice[0..len]).unwrap();
+        let slice = unsafe { slice::from_raw_parts(string as *const u8, len) };
+        return String::from_utf8_lossy(slice);
     }
 
-    pub fn GetStrWithLen(string: u64, len: u64) -> &'static str {
+    pub fn GetStrWithLen(string: u64, len: u64) -> Cow<'static, str> {
         let ptr = string as *const u8;
         let slice = unsafe { slice::from_raw_parts(ptr, len as usize) };
 
-        return str::from_utf8(&slice[0..len as usize]).unwrap();
+        return String::from_utf8_lossy(slice);
     }
 
     pub fn GetStrLen(string: u64) -> i64 {
-        let ptr = string as *const u8;
-        let slice = unsafe { slice::from_raw_parts(ptr, 1024) };
-
-        let len = {
-            let mut res: usize = 0;
-            for i in 0..1024 {
-                if slice[i] == 0 {
-                    res = i;
-                    break;
-                }
-            }
+        match Self::StrLen(string) {
+            Some(len) => return (len + 1) as i64,
+            None => return -SysErr::ENAMETOOLONG as i64,
+        }
+    }
 
-            res
-        };
+    // DeviceAllowed returns false for a character or block device node which is not
+    // in the DevicePassthrough allowlist. Such a node is only opened with O_PATH, so
+    // that looking it up doesn't open the host device.
+    pub fn DeviceAllowed(st: &stat) -> bool {
+        let fmt = st.st_mode & libc::S_IFMT;
+        if fmt != libc::S_IFCHR && fmt != libc::S_IFBLK {
+            return true;
+        }
 
-        return (len + 1) as i64;
+        let (major, minor) = DecodeDeviceId(st.st_rdev as u32);
+        return DEVICE_PASSTHROUGH.Contains(major, minor);
     }
 
-    pub unsafe fn TryOpenHelper(dirfd: i32, name: u64) -> (i32, bool) {
-        let flags = Flags::O_NOFOLLOW;
-        let ret = libc::openat(
-            dirfd,
-            name as *const c_char,
-            (flags | Flags::O_RDWR) as i32,
-            0,
-        );
-        if ret > 0 {
-            return (ret, true);
+    // OpenNoAtime opens name with O_NOATIME, so that the guest reads don't touch the
+    // host atime, see HostInodeOp::NotifyAccess. The host only allows it to the owner
+    // of the file, others get the file opened without it.
+    unsafe fn OpenNoAtime(dirfd: i32, name: *const c_char, flags: i32) -> i32 {
+        let flags = flags | Flags::O_NOFOLLOW;
+        let ret = libc::openat(dirfd, name, flags | Flags::O_NOATIME, 0);
+        if ret < 0 && errno::errno().0 == SysErr::EPERM {
+            return libc::openat(dirfd, name, flags, 0);
         }
 
-        let err = Self::GetRet(ret as i64) as i32;
-        if err == -SysErr::ENOENT {
-            return (-SysErr::ENOENT, false);
+        return ret;
+    }
+
+    // OpenRWHelper opens name for read and write, or for what the host allows of them.
+    unsafe fn OpenRWHelper(dirfd: i32, name: *const c_char) -> (i32, bool) {
+        let ret = Self::OpenNoAtime(dirfd, name, Flags::O_RDWR);
+        if ret >= 0 {
+            return (ret, true);
         }
 
-        let ret = libc::openat(
-            dirfd,
-            name as *const c_char,
-            (flags | Flags::O_RDONLY) as i32,
-            0,
-        );
-        if ret > 0 {
+        let ret = Self::OpenNoAtime(dirfd, name, Flags::O_RDONLY);
+        if ret >= 0 {
             return (ret, false);
         }
 
-        let ret = libc::openat(
-            dirfd,
-            name as *const c_char,
-            (flags | Flags::O_WRONLY) as i32,
-            0,
-        );
-        if ret > 0 {
+        let ret = Self::OpenNoAtime(dirfd, name, Flags::O_WRONLY);
+        if ret >= 0 {
             return (ret, true);
         }
 
-        let ret = libc::openat(
-            dirfd,
-            name as *const c_char,
-            flags as i32 | Flags::O_PATH,
-            0,
-        );
-        if ret > 0 {
-            return (ret, false);
+        return (Self::GetRet(ret as i64) as i32, false);
+    }
+
+    const OPEN_RETRIES: usize = 3;
+
+    // TryOpenHelper opens the host file behind an inode. The O_NOFOLLOW isn't the
+    // guest's: a symlink gets its own inode and the guest follows it, or fails with
+    // ELOOP for its O_NOFOLLOW, so only an O_PATH fd of the link itself is opened.
+    // A device node out of the DevicePassthrough allowlist gets an O_PATH fd as well,
+    // and so does a file the host doesn't let us open, the guest still needs its inode.
+    // The other errors of the open are returned. A file swapped after the fstatat is
+    // closed right away and the lookup is done again.
+    pub unsafe fn TryOpenHelper(dirfd: i32, name: u64) -> (i32, bool) {
+        let name = name as *const c_char;
+        for _ in 0..Self::OPEN_RETRIES {
+            let mut st: stat = core::mem::zeroed();
+            let ret = libc::fstatat(dirfd, name, &mut st, libc::AT_SYMLINK_NOFOLLOW);
+            if ret < 0 {
+                return (Self::GetRet(ret as i64) as i32, false);
+            }
+
+            let pathOnly = st.st_mode & libc::S_IFMT == libc::S_IFLNK || !Self::DeviceAllowed(&st);
+            let (mut fd, mut writeable) = if pathOnly {
+                (-SysErr::EACCES, false)
+            } else {
+                Self::OpenRWHelper(dirfd, name)
+            };
+
+            if fd == -SysErr::EACCES || fd == -SysErr::EPERM {
+                let ret = libc::openat(dirfd, name, Flags::O_NOFOLLOW | Flags::O_PATH, 0);
+                fd = Self::GetRet(ret as i64) as i32;
+                writeable = false;
+            }
+
+            if fd == -SysErr::ELOOP || fd == -SysErr::ENOENT {
+                continue;
+            }
+
+            if fd < 0 {
+                return (fd, false);
+            }
+
+            let mut fdst: stat = core::mem::zeroed();
+            let ret = libc::fstat(fd, &mut fdst);
+            if ret < 0 {
+                let err = Self::GetRet(ret as i64) as i32;
+                libc::close(fd);
+                return (err, false);
+            }
+
+            if fdst.st_dev == st.st_dev && fdst.st_ino == st.st_ino {
+                return (fd, writeable);
+            }
+
+            libc::close(fd);
         }
 
-        return (Self::GetRet(ret as i64) as i32, false);
+        return (-SysErr::EAGAIN, false);
     }
 
     pub fn TryOpenAt(dirfd: i32, name: u64, addr: u64) -> i64 {
@@ -470,13 +625,7 @@ impl VMSpace {
         }
 
         tryOpenAt.writeable = writeable;
-        let hostfd = GlobalIOMgr().AddFile(fd);
-
-        if tryOpenAt.fstat.IsRegularFile() {
-            URING_MGR.lock().Addfd(hostfd).unwrap();
-        }
-
-        return hostfd as i64;
+        return Self::AddHostFd(fd, false, tryOpenAt.fstat.IsRegularFile());
     }
 
     pub fn OpenAt(dirfd: i32, name: u64, flags: i32, addr: u64) -> i64 {
@@ -496,15 +645,10 @@ impl VMSpace {
             unsafe {
                 libc::close(fd);
             }
+            return Self::GetRet(ret as i64);
         }
 
-        let hostfd = GlobalIOMgr().AddFile(fd);
-
-        if tryOpenAt.fstat.IsRegularFile() {
-            URING_MGR.lock().Addfd(hostfd).unwrap();
-        }
-
-        return Self::GetRet(fd as i64);
+        return Self::AddHostFd(fd, false, tryOpenAt.fstat.IsRegularFile());
     }
 
     pub fn CreateAt(
@@ -528,36 +672,49 @@ impl VMSpace {
             }
         };
 
-        unsafe {
-            let osfd = libc::openat(
-                dirfd,
-                fileName as *const c_char,
-                flags as c_int,
-                mode as c_int,
-            );
-            if osfd <= 0 {
-                return Self::GetRet(osfd as i64) as i32;
-            }
-
-            let ret = libc::fchown(osfd, uid, gid);
-            if ret < 0 {
-                libc::close(osfd);
-                return Self::GetRet(ret as i64) as i32;
-            }
+        let osfd = unsafe { Self::CreateHelper(dirfd, fileName, flags, mode, uid, gid, fstatAddr) };
+        if osfd < 0 {
+            return osfd;
+        }
 
-            let ret = libc::fstat(osfd, fstatAddr as *mut stat) as i64;
+        return Self::AddHostFd(osfd, false, true) as i32;
+    }
 
-            if ret < 0 {
-                libc::close(osfd);
-                return Self::GetRet(ret as i64) as i32;
-            }
+    // CreateHelper creates the host file of CreateAt with the guest's owner and
+    // writes its fstat to fstatAddr
+    pub unsafe fn CreateHelper(
+        dirfd: i32,
+        fileName: u64,
+        flags: i32,
+        mode: i32,
+        uid: u32,
+        gid: u32,
+        fstatAddr: u64,
+    ) -> i32 {
+        let osfd = libc::openat(
+            dirfd,
+            fileName as *const c_char,
+            flags as c_int,
+            mode as c_int,
+        );
+        if osfd < 0 {
+            return Self::GetRet(osfd as i64) as i32;
+        }
 
-            let hostfd = GlobalIOMgr().AddFile(osfd);
+        let ret = libc::fchown(osfd, uid, gid);
+        if ret < 0 {
+            libc::close(osfd);
+            return Self::GetRet(ret as i64) as i32;
+        }
 
-            URING_MGR.lock().Addfd(osfd).unwrap();
+        let ret = libc::fstat(osfd, fstatAddr as *mut stat) as i64;
 
-            return hostfd;
+        if ret < 0 {
+            libc::close(osfd);
+            return Self::GetRet(ret as i64) as i32;
         }
+
+        return osfd;
     }
 
     pub fn Close(fd: i32) -> i64 {
@@ -613,11 +770,18 @@ impl VMSpace {
     }
 
     pub fn IORead(fd: i32, iovs: u64, iovcnt: i32) -> i64 {
+        PerfGoto(PerfType::Read);
+        defer!(PerfGofrom(PerfType::Read));
+
         let fd = match Self::GetOsfd(fd) {
             Some(fd) => fd,
             None => return -SysErr::EBADF as i64,
         };
 
+        if let Some(ret) = fault_inject::Fault(fault_inject::FaultSite::Read) {
+            return ret;
+        }
+
         let ret = unsafe { readv(fd as c_int, iovs as *const iovec, iovcnt) as i64 };
 
         return Self::GetRet(ret as i64);
@@ -659,6 +823,9 @@ impl VMSpace {
     }
 
     pub fn IOWrite(fd: i32, iovs: u64, iovcnt: i32) -> i64 {
+        PerfGoto(PerfType::Write);
+        defer!(PerfGofrom(PerfType::Write));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -687,6 +854,9 @@ impl VMSpace {
     }
 
     pub fn IOReadAt(fd: i32, iovs: u64, iovcnt: i32, offset: u64) -> i64 {
+        PerfGoto(PerfType::ReadAt);
+        defer!(PerfGofrom(PerfType::ReadAt));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -696,6 +866,9 @@ impl VMSpace {
     }
 
     pub fn IOWriteAt(fd: i32, iovs: u64, iovcnt: i32, offset: u64) -> i64 {
+        PerfGoto(PerfType::WriteAt);
+        defer!(PerfGofrom(PerfType::WriteAt));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -705,6 +878,9 @@ impl VMSpace {
     }
 
     pub fn IOAccept(fd: i32, addr: u64, addrlen: u64) -> i64 {
+        PerfGoto(PerfType::Accept);
+        defer!(PerfGofrom(PerfType::Accept));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -714,12 +890,36 @@ impl VMSpace {
     }
 
     pub fn NewSocket(fd: i32) -> i64 {
-        GlobalIOMgr().AddSocket(fd);
-        URING_MGR.lock().Addfd(fd).unwrap();
+        let ret = Self::AddHostFd(fd, true, true);
+        if ret < 0 {
+            return ret;
+        }
+
+        return 0;
+    }
+
+    // NewFile and ReleaseFd are the HostFdChange of a fd the guest has opened or
+    // closed through io_uring, for when the change queue is full. The guest has
+    // already updated the registered file slot of the fd itself.
+    pub fn NewFile(fd: i32) -> i64 {
+        return GlobalIOMgr().AddFile(fd) as i64;
+    }
+
+    pub fn ReleaseFd(fd: i32) -> i64 {
+        let info = match GlobalIOMgr().RemoveFd(fd) {
+            None => return -SysErr::EBADF as i64,
+            Some(info) => info,
+        };
+
+        // dropping the FdInfo closes its fd
+        info.lock().fd = -1;
         return 0;
     }
 
     pub fn IOConnect(fd: i32, addr: u64, addrlen: u32) -> i64 {
+        PerfGoto(PerfType::Connect);
+        defer!(PerfGofrom(PerfType::Connect));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -729,6 +929,9 @@ impl VMSpace {
     }
 
     pub fn IORecvMsg(fd: i32, msghdr: u64, flags: i32) -> i64 {
+        PerfGoto(PerfType::RecvMsg);
+        defer!(PerfGofrom(PerfType::RecvMsg));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -747,6 +950,9 @@ impl VMSpace {
     }
 
     pub fn IOSendMsg(fd: i32, msghdr: u64, flags: i32) -> i64 {
+        PerfGoto(PerfType::SendMsg);
+        defer!(PerfGofrom(PerfType::SendMsg));
+
         let fdInfo = match Self::GetFdInfo(fd) {
             Some(info) => info,
             None => return -SysErr::EBADF as i64,
@@ -961,6 +1167,37 @@ impl VMSpace {
         return Self::GetRet(ret);
     }
 
+    // Track a host fd just opened for the guest. When the uring registered file table
+    // can't take it, the fd is dropped and closed and the open fails with an errno
+    // instead of crashing the sandbox.
+    pub fn AddHostFd(fd: i32, isSocket: bool, uringFixedFile: bool) -> i64 {
+        let hostfd = if isSocket {
+            GlobalIOMgr().AddSocket(fd)
+        } else {
+            GlobalIOMgr().AddFile(fd)
+        };
+
+        if uringFixedFile {
+            match URING_MGR.lock().Addfd(fd) {
+                Ok(()) => (),
+                Err(e) => {
+                    error!("AddHostFd fd {} register fail with error {:?}", fd, e);
+                    GlobalIOMgr().RemoveFd(fd);
+                    unsafe {
+                        libc::close(fd);
+                    }
+
+                    match e {
+                        Error::SysError(errno) => return -errno as i64,
+                        _ => return -SysErr::ENFILE as i64,
+                    }
+                }
+            }
+        }
+
+        return hostfd as i64;
+    }
+
     pub fn GetRet(ret: i64) -> i64 {
         if ret == -1 {
             //info!("get error, errno is {}", errno::errno().0);
@@ -1109,9 +1346,7 @@ impl VMSpace {
             return Self::GetRet(fd as i64);
         }
 
-        let hostfd = GlobalIOMgr().AddSocket(fd);
-        URING_MGR.lock().Addfd(fd).unwrap();
-        return Self::GetRet(hostfd as i64);
+        return Self::AddHostFd(fd, true, true);
     }
 
     pub fn GetSockName(sockfd: i32, addr: u64, addrlen: u64) -> i64 {
@@ -1243,6 +1478,13 @@ impl VMSpace {
         unsafe {
             let res = syscall2(nr, tv as usize, tz as usize) as i64;
             //error!("finish GetTimeOfDay");
+            if res == 0 && tv != 0 && RecordReplay::Enabled() {
+                let tv = &mut *(tv as *mut timeval);
+                let live = tv.tv_sec * 1_000_000_000 + tv.tv_usec * 1000;
+                let ns = RECORD_REPLAY.lock().Time(CLOCK_REALTIME, live);
+                tv.tv_sec = ns / 1_000_000_000;
+                tv.tv_usec = ns % 1_000_000_000 / 1000;
+            }
             return res;
         }
     }
@@ -1786,3 +2028,90 @@ impl PostRDMAConnect {
         return msgRef;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    // OpenWithoutStdin opens and creates files in dir with the host fd 0 closed, so
+    // that the first fd the host hands out is 0
+    fn OpenWithoutStdin(dir: &str) {
+        let file = CString::new(format!("{}/file", dir)).unwrap();
+        let link = CString::new(format!("{}/link", dir)).unwrap();
+
+        unsafe {
+            libc::close(0);
+
+            let mut st: stat = core::mem::zeroed();
+            let fd = VMSpace::CreateHelper(
+                -100,
+                file.as_ptr() as u64,
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                0o644,
+                libc::getuid(),
+                libc::getgid(),
+                &mut st as *mut _ as u64,
+            );
+            assert_eq!(fd, 0);
+            assert_eq!(st.st_mode & libc::S_IFMT, libc::S_IFREG);
+            assert_eq!(libc::write(0, b"fd0".as_ptr() as _, 3), 3);
+            libc::close(0);
+
+            // the O_PATH fd of a symlink is what TryOpenHelper returns
+            assert_eq!(libc::symlink(file.as_ptr(), link.as_ptr()), 0);
+            assert_eq!(
+                VMSpace::TryOpenHelper(-100, link.as_ptr() as u64),
+                (0, false)
+            );
+            libc::close(0);
+
+            // a file is opened for read and write right away, without an O_PATH fd
+            let (fd, writeable) = VMSpace::TryOpenHelper(-100, file.as_ptr() as u64);
+            assert!(fd == 0 && writeable);
+            assert_eq!(libc::fcntl(0, libc::F_GETFL) & libc::O_PATH, 0);
+            let mut buf = [0u8; 8];
+            assert_eq!(libc::pread(fd, buf.as_mut_ptr() as _, buf.len(), 0), 3);
+            assert_eq!(&buf[..3], b"fd0");
+            libc::close(0);
+
+            // a device out of the allowlist only gets an O_PATH fd
+            let null = CString::new("/dev/null").unwrap();
+            if !DEVICE_PASSTHROUGH.Contains(1, 3) {
+                assert_eq!(
+                    VMSpace::TryOpenHelper(-100, null.as_ptr() as u64),
+                    (0, false)
+                );
+                assert_ne!(libc::fcntl(0, libc::F_GETFL) & libc::O_PATH, 0);
+                libc::close(0);
+            }
+
+            // the errors of the open are reported
+            let missing = CString::new(format!("{}/missing", dir)).unwrap();
+            assert_eq!(
+                VMSpace::TryOpenHelper(-100, missing.as_ptr() as u64),
+                (-SysErr::ENOENT, false)
+            );
+        }
+    }
+
+    #[test]
+    fn test_OpenHostFd0() {
+        let dir = temp_dir().join(format!("open_fd0_{}", std::process::id()));
+        fs::create_dir(&dir).unwrap();
+
+        // fd 0 is closed in a child, another test thread could take it otherwise
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            let res = std::panic::catch_unwind(|| OpenWithoutStdin(dir.to_str().unwrap()));
+            unsafe { libc::_exit(if res.is_ok() { 0 } else { 1 }) };
+        }
+
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 0);
+    }
+}
@@ -19,6 +19,7 @@ use rand_seeder::Seeder;
 use std::slice;
 
 use super::super::qlib::auxv::*;
+use super::super::RECORD_REPLAY;
 
 pub struct RandGen {
     rng: Pcg64,
@@ -33,9 +34,12 @@ impl RandGen {
             let auxvRandAddr = unsafe { getauxval(AuxVec::AT_RANDOM as u64) };
 
             let slice = unsafe { slice::from_raw_parts(auxvRandAddr as *mut u8, 16) };
+            let mut seed = [0u8; 16];
+            seed.copy_from_slice(slice);
+            let seed = RECORD_REPLAY.lock().Seed(seed);
 
             return RandGen {
-                rng: Seeder::from(slice).make_rng(),
+                rng: Seeder::from(&seed[..]).make_rng(),
             };
         } else {
             error!("use fake random");
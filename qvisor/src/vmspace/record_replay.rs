@@ -0,0 +1,240 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Record/replay of the host side nondeterminism the guest observes: the seed of
+// RandGen (which drives getrandom and the vcpu mapping) and the clock readings
+// served through HYPERCALL_GETTIME and GetTimeOfDay. The guest's own TSC reads
+// and the interleaving of vcpus are not controlled, so a replay reproduces the
+// random and host clock sequences, not the whole execution.
+
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use super::super::qlib::config::RecordReplayMode;
+
+pub const RECORD_REPLAY_LOG: &str = "/var/log/quark/replay.log";
+
+// mirrors mode != Off so that the clock hypercalls skip the RECORD_REPLAY lock when
+// record/replay is off
+static RECORD_REPLAY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayEvent {
+    Seed([u8; 16]),
+    // (clockId, ns)
+    Time(i32, i64),
+}
+
+impl ReplayEvent {
+    pub fn Format(&self) -> String {
+        match self {
+            Self::Seed(seed) => {
+                let hex: String = seed.iter().map(|b| format!("{:02x}", b)).collect();
+                return format!("seed {}", hex);
+            }
+            Self::Time(clockId, ns) => return format!("time {} {}", clockId, ns),
+        }
+    }
+
+    pub fn Parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        match fields.next()? {
+            "seed" => {
+                let hex = fields.next()?;
+                if hex.len() != 32 {
+                    return None;
+                }
+
+                let mut seed = [0u8; 16];
+                for i in 0..seed.len() {
+                    seed[i] = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+                }
+
+                return Some(Self::Seed(seed));
+            }
+            "time" => {
+                let clockId = fields.next()?.parse().ok()?;
+                let ns = fields.next()?.parse().ok()?;
+                return Some(Self::Time(clockId, ns));
+            }
+            _ => return None,
+        }
+    }
+}
+
+pub struct RecordReplay {
+    mode: RecordReplayMode,
+    writer: Option<BufWriter<File>>,
+    events: VecDeque<ReplayEvent>,
+}
+
+impl RecordReplay {
+    pub fn New(mode: RecordReplayMode) -> Self {
+        let mut rr = Self {
+            mode: mode,
+            writer: None,
+            events: VecDeque::new(),
+        };
+
+        match mode {
+            RecordReplayMode::Off => (),
+            RecordReplayMode::Record => match File::create(RECORD_REPLAY_LOG) {
+                Ok(f) => rr.writer = Some(BufWriter::new(f)),
+                Err(e) => {
+                    error!(
+                        "RecordReplay: can't create {}: {:?}, recording is disabled",
+                        RECORD_REPLAY_LOG, e
+                    );
+                    rr.mode = RecordReplayMode::Off;
+                }
+            },
+            RecordReplayMode::Replay => match File::open(RECORD_REPLAY_LOG) {
+                Ok(f) => rr.events = Self::Load(BufReader::new(f)),
+                Err(e) => {
+                    error!(
+                        "RecordReplay: can't open {}: {:?}, replay is disabled",
+                        RECORD_REPLAY_LOG, e
+                    );
+                    rr.mode = RecordReplayMode::Off;
+                }
+            },
+        }
+
+        RECORD_REPLAY_ENABLED.store(rr.mode != RecordReplayMode::Off, Ordering::Release);
+        return rr;
+    }
+
+    #[inline]
+    pub fn Enabled() -> bool {
+        return RECORD_REPLAY_ENABLED.load(Ordering::Acquire);
+    }
+
+    pub fn Load(reader: impl BufRead) -> VecDeque<ReplayEvent> {
+        let mut events = VecDeque::new();
+        for line in reader.lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(_) => break,
+            };
+
+            match ReplayEvent::Parse(&line) {
+                Some(e) => events.push_back(e),
+                None => error!("RecordReplay: skip malformed line {:?}", line),
+            }
+        }
+
+        return events;
+    }
+
+    fn Record(&mut self, event: ReplayEvent) {
+        if let Some(w) = &mut self.writer {
+            // flush every event so that the log survives a crashing guest
+            let _ = writeln!(w, "{}", event.Format()).and_then(|_| w.flush());
+        }
+    }
+
+    // Diverged is called when the next logged event doesn't match the request.
+    // The rest of the run falls back to live values.
+    fn Diverged(&mut self, event: Option<ReplayEvent>) {
+        error!(
+            "RecordReplay: replay diverged at {:?}, continue with live values",
+            event
+        );
+        self.mode = RecordReplayMode::Off;
+        RECORD_REPLAY_ENABLED.store(false, Ordering::Release);
+        self.events.clear();
+    }
+
+    // Seed returns the RandGen seed: live is logged in record mode and replaced by
+    // the logged seed in replay mode.
+    pub fn Seed(&mut self, live: [u8; 16]) -> [u8; 16] {
+        match self.mode {
+            RecordReplayMode::Off => return live,
+            RecordReplayMode::Record => {
+                self.Record(ReplayEvent::Seed(live));
+                return live;
+            }
+            RecordReplayMode::Replay => match self.events.pop_front() {
+                Some(ReplayEvent::Seed(seed)) => return seed,
+                e => {
+                    self.Diverged(e);
+                    return live;
+                }
+            },
+        }
+    }
+
+    // Time returns the clock reading handed to the guest, see Seed.
+    pub fn Time(&mut self, clockId: i32, live: i64) -> i64 {
+        match self.mode {
+            RecordReplayMode::Off => return live,
+            RecordReplayMode::Record => {
+                self.Record(ReplayEvent::Time(clockId, live));
+                return live;
+            }
+            RecordReplayMode::Replay => match self.events.pop_front() {
+                Some(ReplayEvent::Time(id, ns)) if id == clockId => return ns,
+                e => {
+                    self.Diverged(e);
+                    return live;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ReplayFeedsRecordedValues() {
+        let recorded = [
+            ReplayEvent::Seed([7; 16]),
+            ReplayEvent::Time(0, 1_700_000_000_123_456_789),
+            ReplayEvent::Time(1, 42),
+        ];
+
+        let log: String = recorded.iter().map(|e| e.Format() + "\n").collect();
+        let mut rr = RecordReplay {
+            mode: RecordReplayMode::Replay,
+            writer: None,
+            events: RecordReplay::Load(log.as_bytes()),
+        };
+
+        assert_eq!(rr.Seed([0; 16]), [7; 16]);
+        assert_eq!(rr.Time(0, 5), 1_700_000_000_123_456_789);
+        assert_eq!(rr.Time(1, 5), 42);
+
+        // the log is used up, so live values come through
+        assert_eq!(rr.Time(1, 5), 5);
+    }
+
+    #[test]
+    fn test_ReplayDivergence() {
+        let log = ReplayEvent::Time(0, 10).Format() + "\n" + &ReplayEvent::Time(0, 20).Format();
+        let mut rr = RecordReplay {
+            mode: RecordReplayMode::Replay,
+            writer: None,
+            events: RecordReplay::Load(log.as_bytes()),
+        };
+
+        // a clock id mismatch stops the replay for the rest of the run
+        assert_eq!(rr.Time(1, 5), 5);
+        assert_eq!(rr.Time(0, 6), 6);
+    }
+}
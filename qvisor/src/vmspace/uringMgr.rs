@@ -16,6 +16,8 @@ use alloc::vec::Vec;
 
 use super::super::print::*;
 use super::super::qlib::common::*;
+use super::super::qlib::kernel::IOURING;
+use super::super::qlib::linux_def::*;
 use super::super::qlib::uring::sys::sys::*;
 use super::super::qlib::uring::*;
 
@@ -29,6 +31,7 @@ pub struct UringMgr {
     pub fds: Vec<i32>,
     pub ring: Option<IoUring>,
     pub uringSize: usize,
+    pub cqSize: usize,
 }
 
 impl Drop for UringMgr {
@@ -40,7 +43,7 @@ impl Drop for UringMgr {
 pub const FDS_SIZE: usize = 1024 * 16;
 
 impl UringMgr {
-    pub fn New(size: usize) -> Self {
+    pub fn New(sqSize: u32, cqSize: u32) -> Self {
         let fdsSize = if QUARK_CONFIG.lock().UringFixedFile {
             FDS_SIZE
         } else {
@@ -57,7 +60,8 @@ impl UringMgr {
             eventfd: 0,
             fds: fds,
             ring: None,
-            uringSize: size,
+            uringSize: sqSize as usize,
+            cqSize: cqSize as usize,
         };
 
         return ret;
@@ -85,12 +89,54 @@ impl UringMgr {
         return self.ring.as_ref().unwrap().Addr();
     }
 
-    pub fn Init(&mut self) {
-        let ring = Builder::default()
-            .setup_cqsize(self.uringSize as u32 * 2)
+    // Probe checks that the host lets us set up an io_uring. Old kernels don't have
+    // io_uring_setup and locked down hosts block it with seccomp.
+    pub fn Probe() -> Result<()> {
+        let mut params = sys::io_uring_params::default();
+        let fd = IOUringSetup(1, &mut params as *mut _ as u64);
+        if fd < 0 {
+            return Err(Error::SysError(-fd as i32));
+        }
+
+        unsafe {
+            libc::close(fd as i32);
+        }
+
+        return Ok(());
+    }
+
+    // DetectFallback turns on the io_uring fallback mode when it's configured or when
+    // the host can't set up a ring. It has to run before the config is copied to the
+    // share space.
+    pub fn DetectFallback() {
+        if !QUARK_CONFIG.lock().UringFallback {
+            match Self::Probe() {
+                Ok(()) => return,
+                Err(e) => error!(
+                    "io_uring is not available on the host: {:?}, fall back to synchronous io",
+                    e
+                ),
+            }
+        }
+
+        QUARK_CONFIG.lock().EnableUringFallback();
+    }
+
+    // Build sets up a ring with the sizes checked by Config::UringSizes
+    pub fn Build(sqSize: u32, cqSize: u32) -> Result<IoUring> {
+        return Builder::default()
+            .setup_cqsize(cqSize)
             .setup_clamp()
-            .build(self.uringSize as u32)
-            .expect("InitUring fail");
+            .build(sqSize);
+    }
+
+    pub fn Init(&mut self) {
+        if QUARK_CONFIG.lock().UringFallback {
+            self.ring = Some(IoUring::Fallback());
+            return;
+        }
+
+        let ring = Self::Build(self.uringSize as u32, self.cqSize as u32).expect("InitUring fail");
         self.uringfd = ring.fd.0;
         self.ring = Some(ring);
 
@@ -106,11 +152,34 @@ impl UringMgr {
 
     pub fn SetupEventfd(&mut self, eventfd: i32) {
         self.eventfd = eventfd;
+        if QUARK_CONFIG.lock().UringFallback {
+            URING_FALLBACK.lock().SetEventfd(eventfd);
+            return;
+        }
 
         self.Register(IORING_REGISTER_EVENTFD, &self.eventfd as *const _ as u64, 1)
             .expect("InitUring register eventfd fail");
     }
 
+    // GlobalEnter and GlobalWake run the fallback mode's entries without holding
+    // URING_MGR, so that a blocking op doesn't hold up the other vcpus.
+    pub fn GlobalEnter(toSumbit: u32, minComplete: u32, flags: u32) -> Result<i32> {
+        if QUARK_CONFIG.lock().UringFallback {
+            return Ok(IOURING.IOUring().FallbackSubmit() as i32);
+        }
+
+        return URING_MGR.lock().Enter(toSumbit, minComplete, flags);
+    }
+
+    pub fn GlobalWake(minComplete: usize) -> Result<()> {
+        if QUARK_CONFIG.lock().UringFallback {
+            IOURING.IOUring().FallbackSubmit();
+            return Ok(());
+        }
+
+        return URING_MGR.lock().Wake(minComplete);
+    }
+
     pub fn Enter(&mut self, toSumbit: u32, minComplete: u32, flags: u32) -> Result<i32> {
         let ret = IOUringEnter(self.uringfd, toSumbit, minComplete, flags);
         if ret < 0 {
@@ -165,13 +234,19 @@ impl UringMgr {
     }
 
     pub fn Addfd(&mut self, fd: i32) -> Result<()> {
+        // there is no registered file table without a host ring
+        if QUARK_CONFIG.lock().UringFallback {
+            return Ok(());
+        }
+
         if !QUARK_CONFIG.lock().UringFixedFile {
             return Ok(());
         }
 
         if fd as usize >= self.fds.len() {
+            // the registered file table is full, let the caller fail the open
             error!("Addfd out of bound fd {}", fd);
-            panic!("Addfd out of bound fd {}", fd)
+            return Err(Error::SysError(SysErr::ENFILE));
         }
         self.fds[fd as usize] = fd;
 
@@ -185,6 +260,10 @@ impl UringMgr {
     }
 
     pub fn Removefd(&mut self, fd: i32) -> Result<()> {
+        if QUARK_CONFIG.lock().UringFallback {
+            return Ok(());
+        }
+
         if !QUARK_CONFIG.lock().UringFixedFile {
             return Ok(());
         }
@@ -204,3 +283,19 @@ impl UringMgr {
         return self.Register(IORING_REGISTER_FILES_UPDATE, &fu as *const _ as u64, 1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_RingCapacity() {
+        if UringMgr::Probe().is_err() {
+            return;
+        }
+
+        let ring = UringMgr::Build(256, 1024).unwrap();
+        assert_eq!(ring.sq.lock().capacity(), 256);
+        assert_eq!(ring.cq.lock().capacity(), 1024);
+    }
+}
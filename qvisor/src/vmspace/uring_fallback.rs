@@ -0,0 +1,895 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Stand-in for the host io_uring on hosts which can't set one up (old kernels,
+// seccomp profiles blocking io_uring_setup). The guest keeps queueing its sqes in
+// IoUring::submitq, HostSubmit hands them to UringFallback, which runs each one
+// with the plain syscall once its fd is ready and posts the result to completeq.
+// The entries which can't complete yet (accept, poll, socket io, timeouts) stay
+// pending. Their fds are registered in epollfd and their deadlines arm timerfd, and
+// epollfd is watched by the io thread, so they are only retried when one of them
+// may make progress.
+
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+
+use crossbeam_queue::ArrayQueue;
+
+use super::super::qlib::linux_def::*;
+use super::super::qlib::uring::cqueue;
+use super::super::qlib::uring::squeue;
+use super::super::qlib::uring::sys::sys::*;
+use super::fault_inject::*;
+use super::time::HostTime;
+
+// how long to wait before retrying the entries which can't be waited on with epoll,
+// and the completions which didn't fit in a full completeq, in ns
+pub const FALLBACK_RETRY_INTERVAL: i64 = 1_000_000;
+
+const LINK_FLAGS: u8 = (1 << IOSQE_IO_LINK_BIT) as u8 | (1 << IOSQE_IO_HARDLINK_BIT) as u8;
+
+#[derive(Clone, Copy)]
+pub struct FallbackEntry {
+    pub sqe: io_uring_sqe,
+    pub started: bool,
+    // cancelled by ASYNC_CANCEL or TIMEOUT_REMOVE
+    pub cancelled: bool,
+    // a connect got EINPROGRESS and waits for the socket to become writable
+    pub connecting: bool,
+    // monotonic ns when a timeout fires or when the linked timeout cancels the op
+    pub deadline: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    Pending,
+    Done(i32),
+}
+
+impl FallbackEntry {
+    pub fn New(entry: squeue::Entry) -> Self {
+        return Self {
+            sqe: entry.0,
+            started: false,
+            cancelled: false,
+            connecting: false,
+            deadline: None,
+        };
+    }
+
+    pub fn Opcode(&self) -> u32 {
+        return self.sqe.opcode as u32;
+    }
+
+    pub fn UserData(&self) -> u64 {
+        return self.sqe.user_data;
+    }
+
+    // Deadline returns when the timeout or link timeout entry expires
+    pub fn Deadline(&self, now: i64) -> i64 {
+        let ts = unsafe { *(self.sqe.__bindgen_anon_2.addr as *const __kernel_timespec) };
+        let ns = ts.tv_sec * 1_000_000_000 + ts.tv_nsec;
+        let flags = unsafe { self.sqe.__bindgen_anon_3.timeout_flags };
+        if flags & IORING_TIMEOUT_ABS != 0 {
+            return ns;
+        }
+
+        return now + ns;
+    }
+
+    // WaitOn returns the fds and the poll events a pending entry waits for
+    fn WaitOn(&self) -> Vec<(i32, u32)> {
+        let fd = self.sqe.fd;
+        let pollin = libc::POLLIN as u32;
+        let pollout = libc::POLLOUT as u32;
+        match self.Opcode() {
+            IORING_OP_READ | IORING_OP_READV | IORING_OP_ACCEPT | IORING_OP_RECV
+            | IORING_OP_RECVMSG => return vec![(fd, pollin)],
+            IORING_OP_WRITE | IORING_OP_WRITEV | IORING_OP_SEND | IORING_OP_SENDMSG => {
+                return vec![(fd, pollout)]
+            }
+            IORING_OP_CONNECT if self.connecting => return vec![(fd, pollout)],
+            IORING_OP_POLL_ADD => {
+                let events = unsafe { self.sqe.__bindgen_anon_3.poll32_events };
+                return vec![(fd, events)];
+            }
+            IORING_OP_SPLICE => {
+                let fdIn = unsafe { self.sqe.__bindgen_anon_4.__bindgen_anon_1.splice_fd_in };
+                return vec![(fdIn, pollin), (fd, pollout)];
+            }
+            _ => return Vec::new(),
+        }
+    }
+
+    fn Ready(fd: i32, events: i16) -> bool {
+        let mut pfd = libc::pollfd {
+            fd: fd,
+            events: events,
+            revents: 0,
+        };
+
+        let ret = unsafe { libc::poll(&mut pfd, 1, 0) };
+        return ret > 0 && pfd.revents != 0;
+    }
+
+    fn Result(ret: isize) -> Step {
+        if ret >= 0 {
+            return Step::Done(ret as i32);
+        }
+
+        let errno = errno::errno().0;
+        if errno == SysErr::EAGAIN || errno == SysErr::EINTR {
+            return Step::Pending;
+        }
+
+        return Step::Done(-errno);
+    }
+
+    // Execute runs the entry if it can complete without blocking
+    fn Execute(&mut self, now: i64) -> Step {
+        let sqe = self.sqe;
+        let fd = sqe.fd;
+        unsafe {
+            let addr = sqe.__bindgen_anon_2.addr;
+            let off = sqe.__bindgen_anon_1.off;
+            let len = sqe.len as usize;
+            let msgFlags = sqe.__bindgen_anon_3.msg_flags as i32 | libc::MSG_DONTWAIT;
+
+            match self.Opcode() {
+                IORING_OP_NOP => return Step::Done(0),
+                IORING_OP_TIMEOUT => {
+                    if now >= self.deadline.unwrap() {
+                        return Step::Done(-SysErr::ETIME);
+                    }
+                    return Step::Pending;
+                }
+                IORING_OP_READ | IORING_OP_READV => {
+                    if !Self::Ready(fd, libc::POLLIN) {
+                        return Step::Pending;
+                    }
+
+                    if let Some(ret) = Fault(FaultSite::UringRead, fd) {
+                        return Step::Done(ret as i32);
+                    }
+
+                    let ret = match (self.Opcode(), off as i64) {
+                        (IORING_OP_READ, -1) => libc::read(fd, addr as _, len),
+                        (IORING_OP_READ, _) => libc::pread(fd, addr as _, len, off as _),
+                        (_, -1) => libc::readv(fd, addr as _, len as _),
+                        _ => libc::preadv(fd, addr as _, len as _, off as _),
+                    };
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_WRITE | IORING_OP_WRITEV => {
+                    if !Self::Ready(fd, libc::POLLOUT) {
+                        return Step::Pending;
+                    }
+
+                    if let Some(ret) = Fault(FaultSite::UringWrite, fd) {
+                        return Step::Done(ret as i32);
+                    }
+
+                    let ret = match (self.Opcode(), off as i64) {
+                        (IORING_OP_WRITE, -1) => libc::write(fd, addr as _, len),
+                        (IORING_OP_WRITE, _) => libc::pwrite(fd, addr as _, len, off as _),
+                        (_, -1) => libc::writev(fd, addr as _, len as _),
+                        _ => libc::pwritev(fd, addr as _, len as _, off as _),
+                    };
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_FSYNC => {
+                    let ret = if sqe.__bindgen_anon_3.fsync_flags & IORING_FSYNC_DATASYNC != 0 {
+                        libc::fdatasync(fd)
+                    } else {
+                        libc::fsync(fd)
+                    };
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_POLL_ADD => {
+                    let mut pfd = libc::pollfd {
+                        fd: fd,
+                        events: sqe.__bindgen_anon_3.poll32_events as i16,
+                        revents: 0,
+                    };
+
+                    if libc::poll(&mut pfd, 1, 0) <= 0 || pfd.revents == 0 {
+                        return Step::Pending;
+                    }
+                    return Step::Done(pfd.revents as u16 as i32);
+                }
+                IORING_OP_RECV => {
+                    let ret = libc::recv(fd, addr as _, len, msgFlags);
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_SEND => {
+                    let ret = libc::send(fd, addr as _, len, msgFlags);
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_RECVMSG => {
+                    let ret = libc::recvmsg(fd, addr as _, msgFlags);
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_SENDMSG => {
+                    let ret = libc::sendmsg(fd, addr as _, msgFlags);
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_ACCEPT => {
+                    if !Self::Ready(fd, libc::POLLIN) {
+                        return Step::Pending;
+                    }
+
+                    let ret = libc::accept4(
+                        fd,
+                        addr as _,
+                        sqe.__bindgen_anon_1.addr2 as _,
+                        sqe.__bindgen_anon_3.accept_flags as i32,
+                    );
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_CONNECT => {
+                    if !self.connecting {
+                        let ret = libc::connect(fd, addr as _, off as _);
+                        if ret < 0 && errno::errno().0 == SysErr::EINPROGRESS {
+                            self.connecting = true;
+                            return Step::Pending;
+                        }
+                        return Self::Result(ret as isize);
+                    }
+
+                    if !Self::Ready(fd, libc::POLLOUT) {
+                        return Step::Pending;
+                    }
+
+                    let mut err: i32 = 0;
+                    let mut errLen = core::mem::size_of::<i32>() as libc::socklen_t;
+                    let ret = libc::getsockopt(
+                        fd,
+                        libc::SOL_SOCKET,
+                        libc::SO_ERROR,
+                        &mut err as *mut _ as _,
+                        &mut errLen,
+                    );
+                    if ret < 0 {
+                        return Self::Result(ret as isize);
+                    }
+                    return Step::Done(-err);
+                }
+                IORING_OP_STATX => {
+                    let ret = libc::syscall(
+                        libc::SYS_statx,
+                        fd,
+                        addr,
+                        sqe.__bindgen_anon_3.statx_flags,
+                        sqe.len,
+                        off,
+                    );
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_OPENAT => {
+                    let ret = libc::openat(
+                        fd,
+                        addr as _,
+                        sqe.__bindgen_anon_3.open_flags as i32,
+                        sqe.len,
+                    );
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_CLOSE => {
+                    let ret = libc::close(fd);
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_EPOLL_CTL => {
+                    let ret = libc::epoll_ctl(fd, sqe.len as i32, off as i32, addr as _);
+                    return Self::Result(ret as isize);
+                }
+                IORING_OP_SPLICE => {
+                    let mut offIn = sqe.__bindgen_anon_2.splice_off_in as i64;
+                    let mut offOut = off as i64;
+                    let offInPtr = if offIn == -1 {
+                        core::ptr::null_mut()
+                    } else {
+                        &mut offIn as *mut i64
+                    };
+                    let offOutPtr = if offOut == -1 {
+                        core::ptr::null_mut()
+                    } else {
+                        &mut offOut as *mut i64
+                    };
+
+                    let ret = libc::splice(
+                        sqe.__bindgen_anon_4.__bindgen_anon_1.splice_fd_in,
+                        offInPtr,
+                        fd,
+                        offOutPtr,
+                        len,
+                        sqe.__bindgen_anon_3.splice_flags | libc::SPLICE_F_NONBLOCK,
+                    );
+                    return Self::Result(ret as isize);
+                }
+                _ => {
+                    error!("UringFallback: unsupported opcode {}", self.Opcode());
+                    return Step::Done(-SysErr::EINVAL);
+                }
+            }
+        }
+    }
+}
+
+pub struct UringFallback {
+    // the linked entries of a chain run one after another, only the head is in flight
+    pub chains: VecDeque<VecDeque<FallbackEntry>>,
+    // the start of a chain whose last entry hasn't been submitted yet
+    pub link: VecDeque<FallbackEntry>,
+    // the completions which didn't fit in completeq, posted before any new one
+    pub overflow: VecDeque<(u64, i32)>,
+    // the fds registered in epollfd for the pending heads and their poll events
+    pub registered: BTreeMap<i32, u32>,
+    // the chains are taken out by the thread which runs them
+    pub processing: bool,
+    // written after new completions, as the eventfd registered to a host ring
+    pub eventfd: i32,
+    pub epollfd: i32,
+    // armed to the earliest deadline of the pending entries
+    pub timerfd: i32,
+}
+
+impl UringFallback {
+    pub fn New() -> Self {
+        let epollfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        assert!(
+            epollfd >= 0,
+            "UringFallback create epollfd fail, errno is {}",
+            errno::errno().0
+        );
+
+        let timerfd = unsafe {
+            libc::timerfd_create(
+                libc::CLOCK_MONOTONIC,
+                libc::TFD_NONBLOCK | libc::TFD_CLOEXEC,
+            )
+        };
+        assert!(
+            timerfd >= 0,
+            "UringFallback create timerfd fail, errno is {}",
+            errno::errno().0
+        );
+
+        let mut ev = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: timerfd as u64,
+        };
+        let ret = unsafe { libc::epoll_ctl(epollfd, libc::EPOLL_CTL_ADD, timerfd, &mut ev) };
+        assert!(ret == 0, "UringFallback add timerfd fail");
+
+        return Self {
+            chains: VecDeque::new(),
+            link: VecDeque::new(),
+            overflow: VecDeque::new(),
+            registered: BTreeMap::new(),
+            processing: false,
+            eventfd: 0,
+            epollfd: epollfd,
+            timerfd: timerfd,
+        };
+    }
+
+    pub fn SetEventfd(&mut self, eventfd: i32) {
+        self.eventfd = eventfd;
+    }
+
+    pub fn Submit(&mut self, entry: squeue::Entry) {
+        let entry = FallbackEntry::New(entry);
+        let linked = entry.sqe.flags & LINK_FLAGS != 0;
+        self.link.push_back(entry);
+        if !linked {
+            let chain = core::mem::take(&mut self.link);
+            self.chains.push_back(chain);
+        }
+    }
+
+    pub fn Pending(&self) -> usize {
+        return self.chains.len();
+    }
+
+    // Ready returns whether any of the pending entries may make progress, i.e. one of
+    // their fds got ready, a deadline passed or there are completions left to post.
+    pub fn Ready(&self) -> bool {
+        if self.overflow.len() > 0 {
+            return true;
+        }
+
+        if self.chains.is_empty() {
+            return false;
+        }
+
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 16];
+        let cnt = unsafe { libc::epoll_wait(self.epollfd, &mut events[0], 16, 0) };
+        if cnt <= 0 {
+            return false;
+        }
+
+        for ev in &events[..cnt as usize] {
+            if ev.u64 == self.timerfd as u64 {
+                let mut val: u64 = 0;
+                unsafe {
+                    libc::read(self.timerfd, &mut val as *mut _ as *mut libc::c_void, 8);
+                }
+            }
+        }
+
+        return true;
+    }
+
+    // Take moves the pending work out, so that it can be run without holding the lock
+    // which guards the submission. The entries submitted meanwhile are picked up by Merge.
+    pub fn Take(&mut self) -> Self {
+        self.processing = true;
+        return Self {
+            chains: core::mem::take(&mut self.chains),
+            link: VecDeque::new(),
+            overflow: core::mem::take(&mut self.overflow),
+            registered: core::mem::take(&mut self.registered),
+            processing: true,
+            eventfd: self.eventfd,
+            epollfd: self.epollfd,
+            timerfd: self.timerfd,
+        };
+    }
+
+    // Merge moves the chains submitted while work was being run into work and returns
+    // true, or hands work back and returns false when there are none.
+    pub fn Merge(&mut self, work: &mut Self) -> bool {
+        if self.chains.is_empty() {
+            self.chains = core::mem::take(&mut work.chains);
+            self.overflow = core::mem::take(&mut work.overflow);
+            self.registered = core::mem::take(&mut work.registered);
+            self.processing = false;
+            return false;
+        }
+
+        work.chains.extend(self.chains.drain(..));
+        return true;
+    }
+
+    // Cancel marks the entry with user data target as cancelled. The entry completes
+    // with ECANCELED when its chain is processed next.
+    fn Cancel(&mut self, target: u64) -> i32 {
+        for chain in &mut self.chains {
+            for entry in chain.iter_mut() {
+                if entry.UserData() == target && !entry.cancelled {
+                    entry.cancelled = true;
+                    return 0;
+                }
+            }
+        }
+
+        return -SysErr::ENOENT;
+    }
+
+    // Advance runs the chain at idx as far as it gets and returns whether it's done
+    fn Advance(&mut self, idx: usize, now: i64, completions: &mut Vec<(u64, i32)>) -> bool {
+        loop {
+            let mut head = self.chains[idx][0];
+            if !head.started {
+                head.started = true;
+                if head.Opcode() == IORING_OP_TIMEOUT {
+                    head.deadline = Some(head.Deadline(now));
+                } else if let Some(next) = self.chains[idx].get(1) {
+                    if next.Opcode() == IORING_OP_LINK_TIMEOUT {
+                        head.deadline = Some(next.Deadline(now));
+                    }
+                }
+            }
+
+            let mut step = if head.cancelled {
+                Step::Done(-SysErr::ECANCELED)
+            } else {
+                match head.Opcode() {
+                    IORING_OP_TIMEOUT_REMOVE | IORING_OP_ASYNC_CANCEL => {
+                        let target = unsafe { head.sqe.__bindgen_anon_2.addr };
+                        Step::Done(self.Cancel(target))
+                    }
+                    _ => head.Execute(now),
+                }
+            };
+
+            // the link timeout expired before the op could complete
+            let mut timedOut = false;
+            if step == Step::Pending && head.Opcode() != IORING_OP_TIMEOUT {
+                if let Some(deadline) = head.deadline {
+                    if now >= deadline {
+                        step = Step::Done(-SysErr::ECANCELED);
+                        timedOut = true;
+                    }
+                }
+            }
+
+            let chain = &mut self.chains[idx];
+            chain[0] = head;
+            let res = match step {
+                Step::Pending => return false,
+                Step::Done(res) => res,
+            };
+
+            completions.push((head.UserData(), res));
+            chain.pop_front();
+
+            let mut failed = res < 0;
+            if let Some(next) = chain.front() {
+                if next.Opcode() == IORING_OP_LINK_TIMEOUT {
+                    let res = if timedOut {
+                        -SysErr::ETIME
+                    } else {
+                        -SysErr::ECANCELED
+                    };
+                    completions.push((next.UserData(), res));
+                    failed = timedOut || failed;
+                    chain.pop_front();
+                }
+            }
+
+            // a failed entry breaks the rest of its chain unless it's hard linked
+            if failed && head.sqe.flags & (1 << IOSQE_IO_HARDLINK_BIT) as u8 == 0 {
+                for entry in chain.drain(..) {
+                    completions.push((entry.UserData(), -SysErr::ECANCELED));
+                }
+            }
+
+            if chain.is_empty() {
+                return true;
+            }
+        }
+    }
+
+    // Post moves the completions from overflow to completeq until it is full and
+    // returns how many got posted
+    fn Post(&mut self, completeq: &ArrayQueue<cqueue::Entry>) -> usize {
+        let mut count = 0;
+        while let Some((userData, res)) = self.overflow.pop_front() {
+            let cqe = cqueue::Entry(io_uring_cqe {
+                user_data: userData,
+                res: res,
+                flags: 0,
+            });
+
+            if completeq.push(cqe).is_err() {
+                self.overflow.push_front((userData, res));
+                break;
+            }
+            count += 1;
+        }
+
+        return count;
+    }
+
+    fn EpollCtl(&self, op: i32, fd: i32, events: u32) -> i32 {
+        let mut ev = libc::epoll_event {
+            events: events,
+            u64: fd as u64,
+        };
+
+        let ret = unsafe { libc::epoll_ctl(self.epollfd, op, fd, &mut ev) };
+        if ret < 0 {
+            return -errno::errno().0;
+        }
+
+        return 0;
+    }
+
+    // Rearm registers the fds the pending heads wait on and arms timerfd to their
+    // earliest deadline. The entries which can't be waited on (e.g. a regular file
+    // which returned EAGAIN) are retried after FALLBACK_RETRY_INTERVAL.
+    fn Rearm(&mut self, now: i64, mut retry: bool) {
+        let mut wanted: BTreeMap<i32, u32> = BTreeMap::new();
+        let mut deadline: Option<i64> = None;
+        for chain in &self.chains {
+            let head = &chain[0];
+            let waits = head.WaitOn();
+            if waits.is_empty() && head.deadline.is_none() {
+                retry = true;
+            }
+
+            for (fd, events) in waits {
+                *wanted.entry(fd).or_insert(0) |= events;
+            }
+
+            if let Some(d) = head.deadline {
+                deadline = Some(deadline.map_or(d, |cur| cur.min(d)));
+            }
+        }
+
+        for fd in self.registered.keys() {
+            if !wanted.contains_key(fd) {
+                // the fd might be closed already, which removed it from epollfd
+                self.EpollCtl(libc::EPOLL_CTL_DEL, *fd, 0);
+            }
+        }
+
+        // the fd number may have been closed and reused since it got registered, in
+        // which case MOD fails with ENOENT
+        let mut registered = BTreeMap::new();
+        for (fd, events) in wanted {
+            let mut ret = self.EpollCtl(libc::EPOLL_CTL_MOD, fd, events);
+            if ret == -SysErr::ENOENT {
+                ret = self.EpollCtl(libc::EPOLL_CTL_ADD, fd, events);
+            }
+
+            if ret < 0 {
+                retry = true;
+                continue;
+            }
+
+            registered.insert(fd, events);
+        }
+        self.registered = registered;
+
+        if retry {
+            let d = now + FALLBACK_RETRY_INTERVAL;
+            deadline = Some(deadline.map_or(d, |cur| cur.min(d)));
+        }
+
+        let mut spec: libc::itimerspec = unsafe { core::mem::zeroed() };
+        if let Some(d) = deadline {
+            // a zero it_value disarms the timer, an expired deadline fires right away
+            let d = d.max(1);
+            spec.it_value.tv_sec = d / 1_000_000_000;
+            spec.it_value.tv_nsec = d % 1_000_000_000;
+        }
+
+        unsafe {
+            libc::timerfd_settime(
+                self.timerfd,
+                libc::TFD_TIMER_ABSTIME,
+                &spec,
+                core::ptr::null_mut(),
+            );
+        }
+    }
+
+    // Process runs whatever can make progress and posts the completions to completeq.
+    // The completions which don't fit stay in overflow and no new entry runs until
+    // they are posted. It returns the number of posted completions.
+    pub fn Process(&mut self, completeq: &ArrayQueue<cqueue::Entry>) -> usize {
+        let now = HostTime::Monotime().unwrap();
+        let mut count = self.Post(completeq);
+
+        if self.overflow.is_empty() {
+            let mut completions = Vec::new();
+            let mut idx = 0;
+            while idx < self.chains.len() {
+                if self.Advance(idx, now, &mut completions) {
+                    self.chains.remove(idx);
+                } else {
+                    idx += 1;
+                }
+            }
+
+            self.overflow.extend(completions);
+            count += self.Post(completeq);
+        }
+
+        // the guest doesn't notify the host when it drains completeq, so retry later
+        let full = self.overflow.len() > 0;
+        self.Rearm(now, full);
+
+        if count > 0 && self.eventfd > 0 {
+            let val: u64 = 1;
+            unsafe {
+                libc::write(self.eventfd, &val as *const _ as *const libc::c_void, 8);
+            }
+        }
+
+        return count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+    use std::os::unix::io::AsRawFd;
+
+    use super::super::super::qlib::uring::opcode::{self, types};
+    use super::*;
+
+    fn Run(fallback: &mut UringFallback) -> Vec<(u64, i32)> {
+        let completeq = ArrayQueue::new(16);
+        fallback.Process(&completeq);
+
+        let mut ret = Vec::new();
+        while let Some(cqe) = completeq.pop() {
+            ret.push((cqe.user_data(), cqe.result()));
+        }
+
+        return ret;
+    }
+
+    #[test]
+    fn test_FallbackFileIO() {
+        let path = std::env::temp_dir().join(format!("uring_fallback_{}", std::process::id()));
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        let fd = file.as_raw_fd();
+
+        let mut fallback = UringFallback::New();
+        let data = b"hello fallback";
+        fallback.Submit(
+            opcode::Write::new(types::Fd(fd), data.as_ptr(), data.len() as u32)
+                .offset(0)
+                .build()
+                .user_data(1),
+        );
+        fallback.Submit(opcode::Fsync::new(types::Fd(fd)).build().user_data(2));
+        assert_eq!(Run(&mut fallback), vec![(1, data.len() as i32), (2, 0)]);
+
+        let mut buf = [0u8; 32];
+        fallback.Submit(
+            opcode::Read::new(types::Fd(fd), buf.as_mut_ptr(), buf.len() as u32)
+                .offset(0)
+                .build()
+                .user_data(3),
+        );
+        assert_eq!(Run(&mut fallback), vec![(3, data.len() as i32)]);
+        assert_eq!(&buf[..data.len()], data);
+
+        // a failing read completes with the negative errno, not as a short read
+        InjectFault(FaultSite::UringRead, SysErr::EIO, Some(1));
+        fallback.Submit(
+            opcode::Read::new(types::Fd(fd), buf.as_mut_ptr(), buf.len() as u32)
+                .offset(0)
+                .build()
+                .user_data(4),
+        );
+        assert_eq!(Run(&mut fallback), vec![(4, -SysErr::EIO)]);
+
+        InjectFault(FaultSite::UringWrite, SysErr::ENOSPC, Some(1));
+        fallback.Submit(
+            opcode::Write::new(types::Fd(fd), data.as_ptr(), data.len() as u32)
+                .offset(0)
+                .build()
+                .user_data(5),
+        );
+        assert_eq!(Run(&mut fallback), vec![(5, -SysErr::ENOSPC)]);
+
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_FallbackOpenClose() {
+        let path = std::env::temp_dir().join(format!("uring_open_{}", std::process::id()));
+        std::fs::write(&path, b"opened").unwrap();
+        let name = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let mut fallback = UringFallback::New();
+        fallback.Submit(
+            opcode::Openat::new(types::Fd(libc::AT_FDCWD), name.as_ptr())
+                .flags(libc::O_RDONLY | libc::O_NOFOLLOW)
+                .build()
+                .user_data(1),
+        );
+        let ret = Run(&mut fallback);
+        assert_eq!(ret.len(), 1);
+        let fd = ret[0].1;
+        assert!(fd >= 0);
+
+        let mut buf = [0u8; 16];
+        let cnt = unsafe { libc::read(fd, buf.as_mut_ptr() as _, buf.len()) };
+        assert_eq!(&buf[..cnt as usize], b"opened");
+
+        fallback.Submit(opcode::Close::new(types::Fd(fd)).build().user_data(2));
+        assert_eq!(Run(&mut fallback), vec![(2, 0)]);
+        assert_eq!(unsafe { libc::fcntl(fd, libc::F_GETFD) }, -1);
+
+        fallback.Submit(
+            opcode::Openat::new(types::Fd(libc::AT_FDCWD), name.as_ptr())
+                .flags(libc::O_RDONLY | libc::O_CREAT | libc::O_EXCL)
+                .mode(0o600)
+                .build()
+                .user_data(3),
+        );
+        assert_eq!(Run(&mut fallback), vec![(3, -SysErr::EEXIST)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_FallbackAccept() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+
+        let mut fallback = UringFallback::New();
+        fallback.Submit(
+            opcode::Accept::new(
+                types::Fd(listener.as_raw_fd()),
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+            )
+            .build()
+            .user_data(7),
+        );
+
+        // nobody connected yet, the accept stays pending and waits on the listener
+        assert!(Run(&mut fallback).is_empty());
+        assert_eq!(fallback.Pending(), 1);
+        assert!(!fallback.Ready());
+
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(b"x").unwrap();
+        assert!(fallback.Ready());
+
+        let completions = Run(&mut fallback);
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].0, 7);
+        assert!(
+            completions[0].1 >= 0,
+            "accept fails with {}",
+            completions[0].1
+        );
+        assert_eq!(fallback.Pending(), 0);
+
+        unsafe {
+            libc::close(completions[0].1);
+        }
+    }
+
+    #[test]
+    fn test_FallbackOverflow() {
+        let mut fallback = UringFallback::New();
+        for userData in 1..4 {
+            fallback.Submit(opcode::Nop::new().build().user_data(userData));
+        }
+
+        // the completions which don't fit stay queued instead of getting lost
+        let completeq = ArrayQueue::new(2);
+        assert_eq!(fallback.Process(&completeq), 2);
+        assert_eq!(fallback.overflow.len(), 1);
+        assert!(fallback.Ready());
+
+        assert_eq!(completeq.pop().unwrap().user_data(), 1);
+        assert_eq!(completeq.pop().unwrap().user_data(), 2);
+        assert_eq!(fallback.Process(&completeq), 1);
+        assert_eq!(completeq.pop().unwrap().user_data(), 3);
+        assert!(fallback.overflow.is_empty());
+    }
+
+    #[test]
+    fn test_FallbackLinkTimeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let mut fallback = UringFallback::New();
+        let ts = types::Timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        fallback.Submit(
+            opcode::PollAdd::new(types::Fd(listener.as_raw_fd()), libc::POLLIN as u32)
+                .build()
+                .user_data(1)
+                .flags(squeue::Flags::IO_LINK),
+        );
+        fallback.Submit(opcode::LinkTimeout::new(&ts).build().user_data(2));
+
+        // the listener never gets readable, so the zero timeout cancels the poll
+        assert_eq!(
+            Run(&mut fallback),
+            vec![(1, -SysErr::ECANCELED), (2, -SysErr::ETIME)]
+        );
+    }
+}
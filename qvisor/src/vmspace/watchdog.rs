@@ -0,0 +1,160 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The vcpu watchdog reports a vcpu which stays in the guest without making
+// progress for VcpuWatchdogMs, e.g. a guest kernel loop or a deadlock with the
+// interrupts off. The progress of a vcpu is its vm exits other than the host kicks
+// and its task switches, a guest app in a busy loop still makes progress through
+// the preemption every CLOCK_TICK. A stuck vcpu gets kicked to dump its registers
+// and kernel stack, see KVMVcpu::dump.
+
+use alloc::sync::Arc;
+use core::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::super::kvm_vcpu::*;
+use super::super::qlib::linux_def::*;
+use super::super::runc::runtime::vm::*;
+use super::super::SHARE_SPACE;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct VcpuWatch {
+    progress: u64,
+    // the time in ms of the last progress
+    since: u64,
+    reported: bool,
+}
+
+pub struct VcpuWatchdog {
+    timeoutMs: u64,
+    vcpus: Vec<VcpuWatch>,
+}
+
+impl VcpuWatchdog {
+    pub fn New(vcpuCnt: usize, timeoutMs: u64) -> Self {
+        return Self {
+            timeoutMs: timeoutMs,
+            vcpus: vec![VcpuWatch::default(); vcpuCnt],
+        };
+    }
+
+    // Check samples the progress counter of the vcpu at nowMs and returns true when
+    // the vcpu has been in the guest for timeoutMs without progress. A stuck vcpu is
+    // reported once until it leaves the guest or makes progress again.
+    pub fn Check(&mut self, vcpuId: usize, inGuest: bool, progress: u64, nowMs: u64) -> bool {
+        let watch = &mut self.vcpus[vcpuId];
+        if !inGuest || progress != watch.progress {
+            *watch = VcpuWatch {
+                progress: progress,
+                since: nowMs,
+                reported: false,
+            };
+            return false;
+        }
+
+        if watch.reported || nowMs - watch.since < self.timeoutMs {
+            return false;
+        }
+
+        watch.reported = true;
+        return true;
+    }
+
+    pub fn Run(vcpus: Vec<Arc<KVMVcpu>>, timeoutMs: u64, panic: bool) {
+        let mut watchdog = Self::New(vcpus.len(), timeoutMs);
+        let period = Duration::from_millis(core::cmp::max(timeoutMs / 4, 1));
+        let start = Instant::now();
+
+        while IsRunning() {
+            thread::sleep(period);
+            let now = start.elapsed().as_millis() as u64;
+
+            // vcpu 0 runs the io loop, see CheckVcpuTimeout
+            for vcpu in &vcpus[1..] {
+                let cpuLocal = &SHARE_SPACE.scheduler.VcpuArr[vcpu.id];
+                let progress = vcpu.exitCount.load(Ordering::Relaxed)
+                    + cpuLocal.switchCount.load(Ordering::Relaxed);
+                let inGuest = vcpu.state.load(Ordering::Acquire) == KVMVcpuState::GUEST as u64;
+                if !watchdog.Check(vcpu.id, inGuest, progress, now) {
+                    continue;
+                }
+
+                error!(
+                    "vcpu watchdog: vcpu {} made no progress for {} ms, mode {:?}, task {:#x}",
+                    vcpu.id,
+                    timeoutMs,
+                    cpuLocal.GetMode(),
+                    cpuLocal.currentTask.load(Ordering::Relaxed)
+                );
+
+                SetDump(vcpu.id);
+                vcpu.Signal(Signal::SIGCHLD);
+
+                if panic {
+                    // give the vcpu the time to dump its registers
+                    for _ in 0..100 {
+                        if !Dump(vcpu.id) {
+                            break;
+                        }
+                        thread::sleep(Duration::from_millis(10));
+                    }
+
+                    error!("vcpu watchdog: stop the sandbox");
+                    super::super::print::LOG.Clear();
+                    unsafe {
+                        libc::exit(GUEST_CRASH_EXIT_STATUS);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_WatchdogFiresOnStuckVcpu() {
+        let mut watchdog = VcpuWatchdog::New(2, 100);
+
+        // vcpu 1 loops in the guest from 0 ms on and is reported within the window
+        assert!(!watchdog.Check(1, true, 5, 0));
+        assert!(!watchdog.Check(1, true, 5, 50));
+        assert!(!watchdog.Check(1, true, 5, 99));
+        assert!(watchdog.Check(1, true, 5, 100));
+
+        // only once
+        assert!(!watchdog.Check(1, true, 5, 200));
+
+        // progress restarts the window
+        assert!(!watchdog.Check(1, true, 6, 210));
+        assert!(!watchdog.Check(1, true, 6, 300));
+        assert!(watchdog.Check(1, true, 6, 310));
+    }
+
+    #[test]
+    fn test_WatchdogIgnoresVcpuInHost() {
+        let mut watchdog = VcpuWatchdog::New(2, 100);
+
+        // a vcpu waiting in the host for work is idle, not stuck
+        for now in 0..10 {
+            assert!(!watchdog.Check(1, false, 0, now * 100));
+        }
+
+        assert!(!watchdog.Check(1, true, 0, 1000));
+        assert!(watchdog.Check(1, true, 0, 1100));
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// close a listening socket while another thread is blocked in accept.
+// the pending accept must be cancelled so that the port can be bound again,
+// and the blocked accept fails with EINVAL, never with the ECANCELED of the
+// cancelled host op. closing a connected socket cancels its pending read, so
+// that the peer sees the EOF.
+#include <stdio.h>
+#include <unistd.h>
+#include <string.h>
+#include <errno.h>
+#include <poll.h>
+#include <pthread.h>
+#include <sys/socket.h>
+#include <arpa/inet.h>
+
+#define PORT 8089
+
+int listen_fd;
+int accept_ret;
+int accept_errno;
+
+int Listen() {
+    struct sockaddr_in address;
+    int opt = 1;
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        printf("socket fail, errno is %d\n", errno);
+        return -1;
+    }
+
+    setsockopt(fd, SOL_SOCKET, SO_REUSEADDR, &opt, sizeof(opt));
+    address.sin_family = AF_INET;
+    address.sin_addr.s_addr = INADDR_ANY;
+    address.sin_port = htons(PORT);
+    if (bind(fd, (struct sockaddr *)&address, sizeof(address)) < 0) {
+        printf("bind fail, errno is %d\n", errno);
+        close(fd);
+        return -1;
+    }
+
+    if (listen(fd, 3) < 0) {
+        printf("listen fail, errno is %d\n", errno);
+        close(fd);
+        return -1;
+    }
+
+    return fd;
+}
+
+void *Acceptor(void *arg) {
+    accept_ret = accept(listen_fd, NULL, NULL);
+    accept_errno = errno;
+    printf("accept returns %d, errno is %d\n", accept_ret, accept_errno);
+    return NULL;
+}
+
+// the peer of a closed connection reads the EOF
+int CloseConnected() {
+    struct sockaddr_in address;
+    memset(&address, 0, sizeof(address));
+    address.sin_family = AF_INET;
+    address.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    address.sin_port = htons(PORT);
+
+    int lfd = Listen();
+    int cfd = socket(AF_INET, SOCK_STREAM, 0);
+    if (lfd < 0 || cfd < 0 ||
+        connect(cfd, (struct sockaddr *)&address, sizeof(address)) < 0) {
+        printf("connect fail, errno is %d\n", errno);
+        return 1;
+    }
+
+    int sfd = accept(lfd, NULL, NULL);
+    if (sfd < 0) {
+        printf("accept fail, errno is %d\n", errno);
+        return 1;
+    }
+
+    close(sfd);
+    close(lfd);
+
+    struct pollfd pfd = {.fd = cfd, .events = POLLIN};
+    char c;
+    if (poll(&pfd, 1, 2000) != 1 || recv(cfd, &c, 1, 0) != 0) {
+        printf("the peer doesn't see the EOF of the closed socket\n");
+        return 1;
+    }
+
+    close(cfd);
+    return 0;
+}
+
+int main() {
+    pthread_t thread;
+
+    listen_fd = Listen();
+    if (listen_fd < 0) {
+        return 1;
+    }
+
+    pthread_create(&thread, NULL, Acceptor, NULL);
+    sleep(1);
+
+    shutdown(listen_fd, SHUT_RDWR);
+    close(listen_fd);
+    pthread_join(thread, NULL);
+
+    if (accept_ret != -1 || accept_errno != EINVAL) {
+        printf("accept after close returns %d errno %d, expect EINVAL\n",
+               accept_ret, accept_errno);
+        return 1;
+    }
+
+    // the listening host socket is released only after the pending accept is cancelled
+    int fd = Listen();
+    if (fd < 0) {
+        printf("relisten fail after close\n");
+        return 1;
+    }
+
+    close(fd);
+
+    if (CloseConnected() != 0) {
+        return 1;
+    }
+
+    printf("accept cancel test pass\n");
+    return 0;
+}
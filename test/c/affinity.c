@@ -0,0 +1,143 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Pin threads with sched_setaffinity and check that they only run on the pinned
+// cpu while other threads keep all the cpus busy: the calling thread, a thread
+// created after the pinning which inherits the mask and a thread pinned by
+// another thread while it runs. A pin to cpu 0, which quark's vcpu 0 doesn't run
+// tasks on, has to keep the thread on a single cpu as well.
+
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define ROUNDS 2000
+
+static volatile int stop;
+static volatile int pinned;
+static int target;
+
+static void *busy(void *arg) {
+    (void)arg;
+    while (!stop) {
+    }
+    return NULL;
+}
+
+// check that the calling thread stays on the target cpu
+static void check_cpu(const char *who) {
+    for (int i = 0; i < ROUNDS; i++) {
+        unsigned cpu = 0;
+        CHECK(syscall(SYS_getcpu, &cpu, NULL, NULL) == 0, "getcpu");
+        int vcpu = sched_getcpu();
+        if ((int)cpu != target || vcpu != target) {
+            fprintf(stderr, "%s runs on cpu %u/%d, pinned to %d\n", who, cpu,
+                    vcpu, target);
+            exit(1);
+        }
+
+        if (i % 2) {
+            sched_yield();
+        } else {
+            usleep(10);
+        }
+    }
+}
+
+static void *inherit(void *arg) {
+    (void)arg;
+    cpu_set_t set;
+    CHECK(sched_getaffinity(0, sizeof(set), &set) == 0, "sched_getaffinity");
+    CHECK(CPU_COUNT(&set) == 1 && CPU_ISSET(target, &set), "inherited mask");
+    check_cpu("child");
+    return NULL;
+}
+
+static void *remote(void *arg) {
+    (void)arg;
+    while (!pinned) {
+    }
+    check_cpu("remote");
+    return NULL;
+}
+
+int main(void) {
+    int ncpu = sysconf(_SC_NPROCESSORS_ONLN);
+    if (ncpu < 2) {
+        printf("PASS\n");
+        return 0;
+    }
+
+    // a cpu other than the current one if possible, and not cpu 0
+    int cur = sched_getcpu();
+    target = ncpu - 1;
+    if (target == cur && ncpu > 2) {
+        target = ncpu - 2;
+    }
+
+    int nbusy = ncpu * 2;
+    pthread_t busyThreads[nbusy];
+    for (int i = 0; i < nbusy; i++) {
+        CHECK(pthread_create(&busyThreads[i], NULL, busy, NULL) == 0,
+              "pthread_create");
+    }
+
+    pthread_t remoteThread;
+    CHECK(pthread_create(&remoteThread, NULL, remote, NULL) == 0,
+          "pthread_create");
+
+    cpu_set_t set;
+    CPU_ZERO(&set);
+    CPU_SET(target, &set);
+    CHECK(pthread_setaffinity_np(remoteThread, sizeof(set), &set) == 0,
+          "pthread_setaffinity_np");
+    pinned = 1;
+
+    CHECK(sched_setaffinity(0, sizeof(set), &set) == 0, "sched_setaffinity");
+    CHECK(sched_getcpu() == target, "not moved to the pinned cpu");
+    check_cpu("main");
+
+    pthread_t child;
+    CHECK(pthread_create(&child, NULL, inherit, NULL) == 0, "pthread_create");
+    CHECK(pthread_join(child, NULL) == 0, "pthread_join");
+    CHECK(pthread_join(remoteThread, NULL) == 0, "pthread_join");
+
+    CPU_ZERO(&set);
+    CPU_SET(0, &set);
+    CHECK(sched_setaffinity(0, sizeof(set), &set) == 0, "sched_setaffinity 0");
+    CHECK(sched_getaffinity(0, sizeof(set), &set) == 0, "sched_getaffinity");
+    CHECK(CPU_COUNT(&set) == 1 && CPU_ISSET(0, &set), "mask of cpu 0");
+    target = sched_getcpu();
+    check_cpu("cpu 0");
+
+    stop = 1;
+    for (int i = 0; i < nbusy; i++) {
+        CHECK(pthread_join(busyThreads[i], NULL) == 0, "pthread_join");
+    }
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// The mmap base, the stack and the load address of a PIE program change between
+// runs with ASLR on (randomize_va_space != 0), and stay the same with it off.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+struct layout {
+    unsigned long mmapAddr;
+    unsigned long stackAddr;
+    unsigned long textAddr;
+};
+
+static int child() {
+    int onStack;
+    void *m = mmap(NULL, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(m != MAP_FAILED, "mmap");
+    printf("%lx %lx %lx\n", (unsigned long)m, (unsigned long)&onStack, (unsigned long)&child);
+    return 0;
+}
+
+static void run(struct layout *l) {
+    int pipefd[2];
+    CHECK(pipe(pipefd) == 0, "pipe");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        close(pipefd[0]);
+        dup2(pipefd[1], 1);
+        execl("/proc/self/exe", "aslr", "child", NULL);
+        exit(2);
+    }
+
+    close(pipefd[1]);
+    char out[256];
+    ssize_t n = read(pipefd[0], out, sizeof(out) - 1);
+    CHECK(n > 0, "read child output");
+    out[n] = 0;
+    close(pipefd[0]);
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+    CHECK(sscanf(out, "%lx %lx %lx", &l->mmapAddr, &l->stackAddr, &l->textAddr) == 3,
+          "parse child output");
+}
+
+int main(int argc, char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        return child();
+    }
+
+    FILE *f = fopen("/proc/sys/kernel/randomize_va_space", "r");
+    CHECK(f != NULL, "open randomize_va_space");
+    int level;
+    CHECK(fscanf(f, "%d", &level) == 1, "read randomize_va_space");
+    fclose(f);
+
+    struct layout first, second;
+    run(&first);
+    run(&second);
+
+    if (level == 0) {
+        CHECK(first.mmapAddr == second.mmapAddr, "mmap base changed without ASLR");
+        CHECK(first.stackAddr == second.stackAddr, "stack changed without ASLR");
+        CHECK(first.textAddr == second.textAddr, "load address changed without ASLR");
+    } else {
+        CHECK(first.mmapAddr != second.mmapAddr, "mmap base not randomized");
+        CHECK(first.stackAddr != second.stackAddr, "stack not randomized");
+        CHECK(first.textAddr != second.textAddr, "load address not randomized");
+    }
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// AT_FDCWD relative *at calls resolve against the task cwd after chdir.
+
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+int main() {
+  mkdir("/tmp/at_fdcwd", 0755);
+  if (chdir("/tmp/at_fdcwd") != 0) {
+    perror("chdir");
+    return 1;
+  }
+
+  int fd = openat(AT_FDCWD, "file", O_CREAT | O_RDWR, 0644);
+  if (fd < 0) {
+    perror("openat");
+    goto fail;
+  }
+  close(fd);
+
+  if (mkdirat(AT_FDCWD, "dir", 0755) != 0) {
+    perror("mkdirat");
+    goto fail;
+  }
+
+  struct stat st;
+  if (stat("/tmp/at_fdcwd/file", &st) != 0 || !S_ISREG(st.st_mode)) {
+    printf("file is not created under the cwd\n");
+    goto fail;
+  }
+  if (fstatat(AT_FDCWD, "dir", &st, 0) != 0 || !S_ISDIR(st.st_mode)) {
+    printf("fstatat of dir under the cwd failed\n");
+    goto fail;
+  }
+
+  if (renameat(AT_FDCWD, "file", AT_FDCWD, "dir/file") != 0) {
+    perror("renameat");
+    goto fail;
+  }
+  if (faccessat(AT_FDCWD, "dir/file", F_OK, 0) != 0) {
+    perror("faccessat");
+    goto fail;
+  }
+
+  if (unlinkat(AT_FDCWD, "dir/file", 0) != 0 ||
+      unlinkat(AT_FDCWD, "dir", AT_REMOVEDIR) != 0) {
+    perror("unlinkat");
+    goto fail;
+  }
+
+  chdir("/");
+  rmdir("/tmp/at_fdcwd");
+  printf("at_fdcwd test pass\n");
+  return 0;
+
+fail:
+  printf("at_fdcwd test fail\n");
+  return 1;
+}
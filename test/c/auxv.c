@@ -0,0 +1,141 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Check the auxiliary vector of /proc/self/auxv against getauxval and the
+// process: the program headers, AT_RANDOM, the credentials, AT_HWCAP, the
+// vDSO and AT_SECURE of a program exec'ed with an effective UID different from
+// the real one.
+
+#define _GNU_SOURCE
+#include <cpuid.h>
+#include <elf.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+static Elf64_auxv_t auxv[128];
+static int auxv_len;
+
+static void read_auxv(void) {
+    int fd = open("/proc/self/auxv", O_RDONLY);
+    CHECK(fd >= 0, "open /proc/self/auxv");
+    ssize_t n = read(fd, auxv, sizeof(auxv));
+    CHECK(n > 0 && n % sizeof(Elf64_auxv_t) == 0, "read /proc/self/auxv");
+    close(fd);
+
+    auxv_len = n / sizeof(Elf64_auxv_t);
+    CHECK(auxv[auxv_len - 1].a_type == AT_NULL, "the auxv isn't terminated");
+}
+
+static unsigned long lookup(unsigned long type) {
+    for (int i = 0; i < auxv_len; i++) {
+        if (auxv[i].a_type == type)
+            return auxv[i].a_un.a_val;
+    }
+
+    printf("FAIL: auxv entry %lu is missing\n", type);
+    exit(1);
+}
+
+static void check_entries(void) {
+    static const unsigned long required[] = {
+        AT_PHDR,   AT_PHENT,  AT_PHNUM,  AT_PAGESZ, AT_BASE,
+        AT_FLAGS,  AT_ENTRY,  AT_UID,    AT_EUID,   AT_GID,
+        AT_EGID,   AT_PLATFORM, AT_CLKTCK, AT_SECURE, AT_RANDOM,
+        AT_EXECFN, AT_SYSINFO_EHDR,
+    };
+    // glibc replaces AT_HWCAP with its own x86 feature bits, check it below
+    for (unsigned i = 0; i < sizeof(required) / sizeof(required[0]); i++)
+        CHECK(lookup(required[i]) == getauxval(required[i]),
+              "auxv entry differs from getauxval");
+
+    CHECK(lookup(AT_PAGESZ) == (unsigned long)getpagesize(), "AT_PAGESZ");
+    CHECK(lookup(AT_PHENT) == sizeof(Elf64_Phdr), "AT_PHENT");
+
+    Elf64_Phdr *phdr = (Elf64_Phdr *)lookup(AT_PHDR);
+    int loads = 0;
+    for (unsigned long i = 0; i < lookup(AT_PHNUM); i++) {
+        if (phdr[i].p_type == PT_LOAD)
+            loads++;
+    }
+    CHECK(loads > 0, "no PT_LOAD header at AT_PHDR");
+
+    extern int main();
+    CHECK(lookup(AT_ENTRY) != 0 && lookup(AT_ENTRY) != (unsigned long)main,
+          "AT_ENTRY");
+
+    CHECK(lookup(AT_UID) == getuid() && lookup(AT_EUID) == geteuid(),
+          "AT_UID and AT_EUID");
+    CHECK(lookup(AT_GID) == getgid() && lookup(AT_EGID) == getegid(),
+          "AT_GID and AT_EGID");
+    CHECK(lookup(AT_SECURE) == 0, "AT_SECURE");
+
+    unsigned char *random = (unsigned char *)lookup(AT_RANDOM);
+    int nonzero = 0;
+    for (int i = 0; i < 16; i++)
+        nonzero |= random[i];
+    CHECK(nonzero, "the AT_RANDOM bytes are zero");
+
+    unsigned a, b, c, d;
+    __cpuid(1, a, b, c, d);
+    CHECK(lookup(AT_HWCAP) == d, "AT_HWCAP isn't CPUID.1:EDX");
+    CHECK(lookup(AT_MINSIGSTKSZ) >= 1024, "AT_MINSIGSTKSZ");
+
+    CHECK(strcmp((char *)lookup(AT_PLATFORM), "x86_64") == 0, "AT_PLATFORM");
+    CHECK(strstr((char *)lookup(AT_EXECFN), "auxv") != NULL, "AT_EXECFN");
+    CHECK(memcmp((void *)lookup(AT_SYSINFO_EHDR), ELFMAG, SELFMAG) == 0,
+          "AT_SYSINFO_EHDR isn't an ELF");
+}
+
+int main(int argc, char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "secure") == 0)
+        return getauxval(AT_SECURE) == 1 ? 0 : 1;
+
+    read_auxv();
+    check_entries();
+
+    // an exec with an effective UID that isn't the real one is secure
+    if (getuid() == 0) {
+        pid_t pid = fork();
+        CHECK(pid >= 0, "fork");
+        if (pid == 0) {
+            if (setresuid(0, 65534, 0) != 0)
+                _exit(2);
+            execl("/proc/self/exe", argv[0], "secure", NULL);
+            _exit(3);
+        }
+
+        int status;
+        CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+        CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0,
+              "AT_SECURE after a setresuid exec");
+    }
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/socket.h>
+#include <time.h>
+#include <unistd.h>
+
+#ifndef SO_BUSY_POLL
+#define SO_BUSY_POLL 46
+#endif
+
+#define ROUNDS 200
+
+static int client;
+
+static long now_ns() {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000000000L + ts.tv_nsec;
+}
+
+// sender writes the send time once per millisecond
+static void *sender(void *arg) {
+    for (int i = 0; i < ROUNDS; i++) {
+        usleep(1000);
+        long t = now_ns();
+        if (write(client, &t, sizeof(t)) != sizeof(t)) {
+            perror("write");
+            return NULL;
+        }
+    }
+    return NULL;
+}
+
+// average_latency returns the mean time in ns between a send and its recv
+static long average_latency(int server) {
+    pthread_t th;
+    long total = 0;
+
+    pthread_create(&th, NULL, sender, NULL);
+    for (int i = 0; i < ROUNDS; i++) {
+        long t;
+        if (recv(server, &t, sizeof(t), MSG_WAITALL) != sizeof(t)) {
+            perror("recv");
+            return -1;
+        }
+        total += now_ns() - t;
+    }
+    pthread_join(th, NULL);
+    return total / ROUNDS;
+}
+
+int main() {
+    struct sockaddr_in addr = {.sin_family = AF_INET, .sin_addr.s_addr = htonl(INADDR_LOOPBACK)};
+    socklen_t len = sizeof(addr);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0 || bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        listen(listener, 1) < 0 || getsockname(listener, (struct sockaddr *)&addr, &len) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    client = socket(AF_INET, SOCK_STREAM, 0);
+    if (client < 0 || connect(client, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+
+    int server = accept(listener, NULL, NULL);
+    if (server < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    long slept = average_latency(server);
+
+    int val = 50;
+    if (setsockopt(server, SOL_SOCKET, SO_BUSY_POLL, &val, sizeof(val)) < 0) {
+        perror("setsockopt SO_BUSY_POLL");
+        return 1;
+    }
+
+    val = 0;
+    len = sizeof(val);
+    if (getsockopt(server, SOL_SOCKET, SO_BUSY_POLL, &val, &len) < 0 || val != 50) {
+        fprintf(stderr, "SO_BUSY_POLL reads back %d, want 50\n", val);
+        return 1;
+    }
+
+    val = -1;
+    if (setsockopt(server, SOL_SOCKET, SO_BUSY_POLL, &val, sizeof(val)) == 0 || errno != EINVAL) {
+        fprintf(stderr, "negative SO_BUSY_POLL is accepted\n");
+        return 1;
+    }
+
+    long polled = average_latency(server);
+    // latency depends on the host load, so it's reported rather than checked
+    printf("wakeup latency %ld ns sleeping, %ld ns busy polling\n", slept, polled);
+
+    int ep = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN | EPOLLWAKEUP, .data.fd = server};
+    if (ep < 0 || epoll_ctl(ep, EPOLL_CTL_ADD, server, &ev) < 0) {
+        perror("epoll_ctl EPOLLWAKEUP");
+        return 1;
+    }
+
+    if (write(client, "x", 1) != 1 || epoll_wait(ep, &ev, 1, 1000) != 1 || ev.data.fd != server) {
+        fprintf(stderr, "epoll_wait didn't report the EPOLLWAKEUP entry\n");
+        return 1;
+    }
+
+    close(ep);
+    close(server);
+    close(client);
+    close(listener);
+    printf("busy poll works\n");
+    return 0;
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <linux/capability.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define BIT(cap) (1u << (cap))
+
+static struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+
+static void get(struct __user_cap_data_struct *data) {
+    CHECK(syscall(SYS_capget, &hdr, data) == 0, "capget");
+}
+
+static int set(struct __user_cap_data_struct *data) {
+    return syscall(SYS_capset, &hdr, data);
+}
+
+static void run(void (*fn)()) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        fn();
+        exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+}
+
+static void reexec(const char *mode) {
+    char *argv[] = {"cap_ambient", (char *)mode, NULL};
+    execv("/proc/self/exe", argv);
+    CHECK(0, "execv");
+}
+
+// a capability dropped from the bounding set can't come back through
+// capset or execve, even for root
+static void bounding() {
+    struct __user_cap_data_struct data[2];
+    CHECK(prctl(PR_CAPBSET_READ, CAP_CHOWN) == 1, "CAP_CHOWN in the bounding set");
+    errno = 0;
+    CHECK(prctl(PR_CAPBSET_READ, 1000) == -1 && errno == EINVAL, "PR_CAPBSET_READ bad cap");
+
+    CHECK(prctl(PR_CAPBSET_DROP, CAP_CHOWN) == 0, "PR_CAPBSET_DROP");
+    CHECK(prctl(PR_CAPBSET_READ, CAP_CHOWN) == 0, "CAP_CHOWN dropped from the bounding set");
+
+    get(data);
+    data[0].inheritable |= BIT(CAP_CHOWN);
+    errno = 0;
+    CHECK(set(data) == -1 && errno == EPERM, "inheritable outside the bounding set");
+    reexec("bounding");
+}
+
+static void boundingExec() {
+    struct __user_cap_data_struct data[2];
+    get(data);
+    CHECK(!(data[0].permitted & BIT(CAP_CHOWN)), "root exec limited by the bounding set");
+    CHECK(data[0].permitted & BIT(CAP_KILL), "other caps kept");
+    CHECK(prctl(PR_CAPBSET_READ, CAP_CHOWN) == 0, "bounding set kept across exec");
+
+    // dropping needs CAP_SETPCAP
+    data[0].effective &= ~BIT(CAP_SETPCAP);
+    CHECK(set(data) == 0, "drop CAP_SETPCAP");
+    errno = 0;
+    CHECK(prctl(PR_CAPBSET_DROP, CAP_KILL) == -1 && errno == EPERM, "PR_CAPBSET_DROP without CAP_SETPCAP");
+}
+
+// an ambient capability survives the exec of a regular binary by a non-root user
+static void ambient() {
+    struct __user_cap_data_struct data[2];
+    CHECK(prctl(PR_SET_KEEPCAPS, 1) == 0, "PR_SET_KEEPCAPS");
+    CHECK(setresgid(1000, 1000, 1000) == 0, "setresgid");
+    CHECK(setresuid(1000, 1000, 1000) == 0, "setresuid");
+
+    // the cap has to be permitted and inheritable before it can be raised
+    errno = 0;
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE, CAP_NET_RAW, 0, 0) == -1 && errno == EPERM,
+          "raise without inheritable");
+    get(data);
+    data[0].effective = BIT(CAP_NET_RAW);
+    data[0].inheritable = BIT(CAP_NET_RAW) | BIT(CAP_NET_BIND_SERVICE);
+    CHECK(set(data) == 0, "capset inheritable");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE, CAP_NET_RAW, 0, 0) == 0, "raise CAP_NET_RAW");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE, CAP_NET_BIND_SERVICE, 0, 0) == 0,
+          "raise CAP_NET_BIND_SERVICE");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET, CAP_NET_RAW, 0, 0) == 1, "IS_SET");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_LOWER, CAP_NET_BIND_SERVICE, 0, 0) == 0, "lower");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET, CAP_NET_BIND_SERVICE, 0, 0) == 0,
+          "lowered cap is not set");
+    errno = 0;
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE, CAP_NET_RAW, 1, 0) == -1 && errno == EINVAL,
+          "nonzero unused argument");
+    reexec("ambient");
+}
+
+static void ambientExec() {
+    struct __user_cap_data_struct data[2];
+    get(data);
+    CHECK(getuid() == 1000 && geteuid() == 1000, "still unprivileged");
+    CHECK(data[0].permitted == BIT(CAP_NET_RAW), "permitted is the ambient set");
+    CHECK(data[0].effective == BIT(CAP_NET_RAW), "effective is the ambient set");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET, CAP_NET_RAW, 0, 0) == 1, "ambient kept");
+
+    // lowering the inheritable set takes the cap out of the ambient set
+    data[0].inheritable = 0;
+    CHECK(set(data) == 0, "capset clear inheritable");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_IS_SET, CAP_NET_RAW, 0, 0) == 0,
+          "ambient follows inheritable");
+    CHECK(prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_CLEAR_ALL, 0, 0, 0) == 0, "CLEAR_ALL");
+}
+
+int main(int argc, char **argv) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "bounding") == 0) {
+            boundingExec();
+        } else {
+            ambientExec();
+        }
+        return 0;
+    }
+
+    if (geteuid() != 0) {
+        printf("SKIP: needs root\n");
+        return 0;
+    }
+
+    run(bounding);
+    run(ambient);
+
+    printf("PASS\n");
+    return 0;
+}
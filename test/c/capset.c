@@ -0,0 +1,137 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <linux/capability.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define TEST_FILE "/tmp/capset_test"
+#define BIT(cap) (1u << (cap))
+
+static struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+
+static void get(struct __user_cap_data_struct *data) {
+    CHECK(syscall(SYS_capget, &hdr, data) == 0, "capget");
+}
+
+static int set(struct __user_cap_data_struct *data) {
+    return syscall(SYS_capset, &hdr, data);
+}
+
+static void child() {
+    struct __user_cap_data_struct data[2];
+    get(data);
+    CHECK(data[0].effective & BIT(CAP_CHOWN), "root has CAP_CHOWN");
+    CHECK(chown(TEST_FILE, 1000, 1000) == 0, "chown with CAP_CHOWN");
+
+    // drop CAP_CHOWN from the effective set only, it can be raised again
+    data[0].effective &= ~BIT(CAP_CHOWN);
+    CHECK(set(data) == 0, "capset drop effective");
+    get(data);
+    CHECK(!(data[0].effective & BIT(CAP_CHOWN)), "capget reflects the effective drop");
+    CHECK(data[0].permitted & BIT(CAP_CHOWN), "still permitted");
+    errno = 0;
+    CHECK(chown(TEST_FILE, 0, 0) == -1 && errno == EPERM, "chown without CAP_CHOWN");
+
+    data[0].effective |= BIT(CAP_CHOWN);
+    CHECK(set(data) == 0, "capset raise effective");
+    CHECK(chown(TEST_FILE, 0, 0) == 0, "chown after raising it again");
+
+    // the effective set must be a subset of the permitted set
+    data[0].permitted &= ~BIT(CAP_CHOWN);
+    errno = 0;
+    CHECK(set(data) == -1 && errno == EPERM, "effective not in permitted");
+
+    // once dropped from the permitted set it is gone for good
+    data[0].effective &= ~BIT(CAP_CHOWN);
+    CHECK(set(data) == 0, "capset drop permitted");
+    get(data);
+    CHECK(!(data[0].permitted & BIT(CAP_CHOWN)), "capget reflects the permitted drop");
+    data[0].permitted |= BIT(CAP_CHOWN);
+    errno = 0;
+    CHECK(set(data) == -1 && errno == EPERM, "can't raise permitted");
+    errno = 0;
+    CHECK(chown(TEST_FILE, 1000, 1000) == -1 && errno == EPERM, "chown after dropping permitted");
+
+    // without CAP_SETPCAP, inheritable is limited to inheritable | permitted
+    get(data);
+    data[0].effective &= ~BIT(CAP_SETPCAP);
+    CHECK(set(data) == 0, "capset drop CAP_SETPCAP");
+    data[0].inheritable |= BIT(CAP_CHOWN);
+    errno = 0;
+    CHECK(set(data) == -1 && errno == EPERM, "inheritable not in permitted");
+    data[0].inheritable = BIT(CAP_KILL);
+    CHECK(set(data) == 0, "inheritable from permitted");
+    get(data);
+    CHECK(data[0].inheritable == BIT(CAP_KILL), "capget reflects inheritable");
+
+    // another task's caps can't be set
+    hdr.pid = getppid();
+    errno = 0;
+    CHECK(set(data) == -1 && errno == EPERM, "capset another pid");
+    exit(0);
+}
+
+int main() {
+    if (geteuid() != 0) {
+        printf("SKIP: needs root\n");
+        return 0;
+    }
+
+    unlink(TEST_FILE);
+    FILE *f = fopen(TEST_FILE, "w");
+    CHECK(f != NULL, "create");
+    fclose(f);
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        child();
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    unlink(TEST_FILE);
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+
+    // the child's drops don't leak into the parent
+    struct __user_cap_data_struct data[2];
+    get(data);
+    CHECK(data[0].permitted & BIT(CAP_CHOWN), "parent keeps CAP_CHOWN");
+
+    // a version probe reports the preferred version
+    struct __user_cap_header_struct probe = {0, 0};
+    CHECK(syscall(SYS_capget, &probe, NULL) == 0, "capget version probe");
+    CHECK(probe.version == _LINUX_CAPABILITY_VERSION_3, "preferred version");
+    probe.version = 0;
+    errno = 0;
+    CHECK(syscall(SYS_capset, &probe, data) == -1 && errno == EINVAL, "capset bad version");
+    CHECK(probe.version == _LINUX_CAPABILITY_VERSION_3, "capset writes the version back");
+
+    printf("PASS\n");
+    return 0;
+}
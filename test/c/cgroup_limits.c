@@ -0,0 +1,128 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Find the cgroup v2 hierarchy the way the container aware runtimes do, through
+// /proc/self/mountinfo and /proc/self/cgroup, and check the limit files. e.g.
+// "./cgroup_limits 536870912 '50000 100000'" checks memory.max and cpu.max
+// against the sandbox config, without arguments only their format is checked.
+// statfs of the hierarchy has to report a cgroup2 filesystem.
+
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/vfs.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            fprintf(stderr, "%s\n", msg);                                      \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+static char dir[4096];
+
+static void find_cgroup(void) {
+    char line[4096];
+    char mnt[2048] = "";
+    FILE *f = fopen("/proc/self/mountinfo", "r");
+    CHECK(f != NULL, "open mountinfo");
+    while (fgets(line, sizeof(line), f)) {
+        char point[2048];
+        char *sep = strstr(line, " - ");
+        if (sep && strncmp(sep + 3, "cgroup2 ", 8) == 0 &&
+            sscanf(line, "%*s %*s %*s %*s %2047s", point) == 1) {
+            strcpy(mnt, point);
+        }
+    }
+    fclose(f);
+    CHECK(mnt[0] != 0, "no cgroup2 mount in mountinfo");
+
+    char path[2048] = "";
+    f = fopen("/proc/self/cgroup", "r");
+    CHECK(f != NULL, "open /proc/self/cgroup");
+    while (fgets(line, sizeof(line), f)) {
+        if (strncmp(line, "0::", 3) == 0) {
+            sscanf(line + 3, "%2047s", path);
+        }
+    }
+    fclose(f);
+    CHECK(path[0] == '/', "no cgroup v2 path in /proc/self/cgroup");
+
+    snprintf(dir, sizeof(dir), "%s%s", mnt, strcmp(path, "/") ? path : "");
+}
+
+static void read_file(const char *name, char *buf, size_t len) {
+    char path[8192];
+    snprintf(path, sizeof(path), "%s/%s", dir, name);
+    FILE *f = fopen(path, "r");
+    if (f == NULL) {
+        fprintf(stderr, "open %s failed\n", path);
+        exit(1);
+    }
+    size_t n = fread(buf, 1, len - 1, f);
+    buf[n] = 0;
+    fclose(f);
+    if (n > 0 && buf[n - 1] == '\n') {
+        buf[n - 1] = 0;
+    }
+}
+
+static int is_limit(const char *s) {
+    if (strcmp(s, "max") == 0) {
+        return 1;
+    }
+    char *end;
+    strtoull(s, &end, 10);
+    return end != s && *end == 0;
+}
+
+int main(int argc, char **argv) {
+    find_cgroup();
+
+    struct statfs sfs;
+    CHECK(statfs(dir, &sfs) == 0, "statfs");
+    CHECK(sfs.f_type == 0x63677270, "not a cgroup2 filesystem");
+
+    char memMax[256], memCurrent[256], cpuMax[256], cpuStat[4096];
+    read_file("memory.max", memMax, sizeof(memMax));
+    read_file("memory.current", memCurrent, sizeof(memCurrent));
+    read_file("cpu.max", cpuMax, sizeof(cpuMax));
+    read_file("cpu.stat", cpuStat, sizeof(cpuStat));
+    printf("%s: memory.max %s, memory.current %s, cpu.max %s\n", dir, memMax,
+           memCurrent, cpuMax);
+
+    CHECK(is_limit(memMax), "bad memory.max");
+    CHECK(is_limit(memCurrent) && strcmp(memCurrent, "max"),
+          "bad memory.current");
+    CHECK(strncmp(cpuStat, "usage_usec ", 11) == 0, "bad cpu.stat");
+
+    char quota[128];
+    unsigned long long period;
+    CHECK(sscanf(cpuMax, "%127s %llu", quota, &period) == 2 &&
+              is_limit(quota) && period > 0,
+          "bad cpu.max");
+
+    if (argc > 1) {
+        CHECK(strcmp(memMax, argv[1]) == 0, "memory.max isn't the config");
+    }
+
+    if (argc > 2) {
+        CHECK(strcmp(cpuMax, argv[2]) == 0, "cpu.max isn't the config");
+    }
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef CLOSE_RANGE_CLOEXEC
+#define CLOSE_RANGE_CLOEXEC (1U << 2)
+#endif
+
+static int is_open(int fd) {
+    return fcntl(fd, F_GETFD) >= 0 || errno != EBADF;
+}
+
+// child is the exec-ed image: argv is "child cloexecfd keptfd rangefd"
+static int child(char **argv) {
+    int cloexecfd = atoi(argv[2]), keptfd = atoi(argv[3]), rangefd = atoi(argv[4]);
+
+    if (is_open(cloexecfd)) {
+        fprintf(stderr, "FD_CLOEXEC fd %d survived exec\n", cloexecfd);
+        return 1;
+    }
+    if (is_open(rangefd)) {
+        fprintf(stderr, "close_range CLOEXEC fd %d survived exec\n", rangefd);
+        return 1;
+    }
+    if (!is_open(keptfd)) {
+        fprintf(stderr, "fd %d without FD_CLOEXEC was closed on exec\n", keptfd);
+        return 1;
+    }
+    return 0;
+}
+
+int main(int argc, char **argv) {
+    if (argc == 5 && strcmp(argv[1], "child") == 0) {
+        return child(argv);
+    }
+
+    int cloexecfd = open("/proc/self/exe", O_RDONLY);
+    int keptfd = open("/proc/self/exe", O_RDONLY);
+    if (cloexecfd < 0 || keptfd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    if (fcntl(cloexecfd, F_SETFD, FD_CLOEXEC) < 0) {
+        perror("F_SETFD");
+        return 1;
+    }
+    if (fcntl(cloexecfd, F_GETFD) != FD_CLOEXEC || fcntl(keptfd, F_GETFD) != 0) {
+        fprintf(stderr, "F_GETFD doesn't reflect F_SETFD\n");
+        return 1;
+    }
+
+    // the flag is per descriptor: a dup of the flagged fd doesn't inherit it
+    int dupfd = dup(cloexecfd);
+    if (fcntl(dupfd, F_GETFD) != 0) {
+        fprintf(stderr, "dup inherited FD_CLOEXEC\n");
+        return 1;
+    }
+
+    // close_range over an open ended range, the way most callers use it
+    int rangefd = dup(keptfd);
+    if (syscall(SYS_close_range, rangefd, ~0U, CLOSE_RANGE_CLOEXEC) < 0) {
+        perror("close_range");
+        return 1;
+    }
+    if (fcntl(rangefd, F_GETFD) != FD_CLOEXEC) {
+        fprintf(stderr, "close_range didn't set FD_CLOEXEC\n");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        char a[16], b[16], c[16];
+        snprintf(a, sizeof(a), "%d", cloexecfd);
+        snprintf(b, sizeof(b), "%d", keptfd);
+        snprintf(c, sizeof(c), "%d", rangefd);
+        execl("/proc/self/exe", argv[0], "child", a, b, c, (char *)NULL);
+        perror("execl");
+        exit(1);
+    }
+
+    int status;
+    waitpid(pid, &status, 0);
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        return 1;
+    }
+
+    printf("cloexec works\n");
+    return 0;
+}
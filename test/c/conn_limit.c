@@ -0,0 +1,118 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Sandbox wide socket limit. Run "conn_limit <limit>" with the MaxSockets of
+// /etc/quark/config.json, e.g. for "MaxSockets": 16 run "conn_limit 16". The test
+// connects to a listener of its own until socket() or accept() fails with ENFILE
+// and checks that this happens at exactly <limit> open inet sockets, that unix
+// sockets aren't counted and that a connection accept can't take stays in the
+// backlog until a closed socket makes room for it.
+
+#include <arpa/inet.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+#define MAX_FDS 4096
+
+int fds[MAX_FDS];
+int nfds = 0;
+int pending = -1;
+
+int main(int argc, char **argv) {
+    CHECK(argc == 2, "usage: conn_limit <limit>");
+    int limit = atoi(argv[1]);
+    CHECK(limit >= 2 && limit < MAX_FDS, "limit has to be in [2, 4096)");
+
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t len = sizeof(addr);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    CHECK(listener >= 0, "socket listener");
+    CHECK(bind(listener, (struct sockaddr *)&addr, sizeof(addr)) == 0, "bind");
+    CHECK(listen(listener, 64) == 0, "listen");
+    CHECK(getsockname(listener, (struct sockaddr *)&addr, &len) == 0, "getsockname");
+    nfds = 1;
+
+    for (;;) {
+        CHECK(nfds <= limit, "more sockets than the limit are open");
+
+        int c = socket(AF_INET, SOCK_STREAM, 0);
+        if (c < 0) {
+            CHECK(errno == ENFILE, "socket over the limit doesn't fail with ENFILE");
+            break;
+        }
+        fds[nfds++] = c;
+        CHECK(connect(c, (struct sockaddr *)&addr, sizeof(addr)) == 0, "connect");
+
+        int a = accept(listener, NULL, NULL);
+        if (a < 0) {
+            CHECK(errno == ENFILE, "accept over the limit doesn't fail with ENFILE");
+            pending = c;
+            break;
+        }
+        fds[nfds++] = a;
+    }
+
+    CHECK(nfds == limit, "the limit is hit before all sockets are open");
+
+    // unix sockets don't count
+    int sv[2];
+    CHECK(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0, "socketpair at the limit");
+    close(sv[0]);
+    close(sv[1]);
+
+    errno = 0;
+    CHECK(socket(AF_INET6, SOCK_DGRAM, 0) < 0 && errno == ENFILE, "udp socket at the limit");
+
+    // when the limit was hit by socket(), make room for a connection accept can't take
+    if (pending < 0) {
+        close(fds[--nfds]);
+        pending = socket(AF_INET, SOCK_STREAM, 0);
+        CHECK(pending >= 0, "socket after close");
+        fds[nfds++] = pending;
+        CHECK(connect(pending, (struct sockaddr *)&addr, sizeof(addr)) == 0, "connect");
+        errno = 0;
+        CHECK(accept(listener, NULL, NULL) < 0 && errno == ENFILE, "accept at the limit");
+    }
+
+    const char *msg = "ping";
+    CHECK(write(pending, msg, 4) == 4, "write on the pending connection");
+
+    // a closed socket makes room again and the pending connection is still there
+    close(fds[1]);
+    int a = accept(listener, NULL, NULL);
+    CHECK(a >= 0, "accept after close");
+
+    char buf[8] = {0};
+    CHECK(read(a, buf, sizeof(buf)) == 4 && strcmp(buf, msg) == 0, "read the pending connection");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Copies a file with copy_file_range and checks the data and the offsets. On btrfs
+// or XFS the copy is expected to share the extents of the source, which FIEMAP
+// reports with FIEMAP_EXTENT_SHARED; elsewhere that check is skipped.
+
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/ioctl.h>
+#include <sys/mman.h>
+#include <sys/vfs.h>
+#include <linux/fiemap.h>
+#include <linux/fs.h>
+
+#define SRC "/tmp/copy_file_range_src"
+#define DST "/tmp/copy_file_range_dst"
+#define LEN (1024 * 1024)
+#define BTRFS_SUPER_MAGIC 0x9123683e
+#define XFS_SUPER_MAGIC 0x58465342
+
+// sharedExtents returns 1 if all extents of fd are shared, 0 if not and -1 if FIEMAP
+// isn't available
+static int sharedExtents(int fd) {
+    char buf[sizeof(struct fiemap) + 32 * sizeof(struct fiemap_extent)];
+    struct fiemap *fm = (struct fiemap *)buf;
+    memset(buf, 0, sizeof(buf));
+    fm->fm_length = FIEMAP_MAX_OFFSET;
+    fm->fm_flags = FIEMAP_FLAG_SYNC;
+    fm->fm_extent_count = 32;
+    if (ioctl(fd, FS_IOC_FIEMAP, fm) < 0 || fm->fm_mapped_extents == 0) {
+        return -1;
+    }
+
+    for (int i = 0; i < fm->fm_mapped_extents; i++) {
+        if (!(fm->fm_extents[i].fe_flags & FIEMAP_EXTENT_SHARED)) {
+            return 0;
+        }
+    }
+
+    return 1;
+}
+
+int main() {
+    char *data = malloc(LEN), *got = malloc(LEN);
+    for (int i = 0; i < LEN; i++) {
+        data[i] = i * 7 + i / 4096;
+    }
+
+    int src = open(SRC, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    int dst = open(DST, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (src < 0 || dst < 0 || write(src, data, LEN) != LEN) {
+        printf("prepare files fail: %s\n", strerror(errno));
+        return 1;
+    }
+    fsync(src);
+
+    // copy the first half with the file offsets, the second with explicit ones
+    lseek(src, 0, SEEK_SET);
+    ssize_t n = copy_file_range(src, NULL, dst, NULL, LEN / 2, 0);
+    if (n != LEN / 2 || lseek(src, 0, SEEK_CUR) != LEN / 2 || lseek(dst, 0, SEEK_CUR) != LEN / 2) {
+        printf("copy_file_range returns %zd: %s\n", n, strerror(errno));
+        return 1;
+    }
+
+    loff_t inOff = LEN / 2, outOff = LEN / 2;
+    n = copy_file_range(src, &inOff, dst, &outOff, LEN, 0);
+    if (n != LEN / 2 || inOff != LEN || outOff != LEN || lseek(dst, 0, SEEK_CUR) != LEN / 2) {
+        printf("copy_file_range with offsets returns %zd: %s\n", n, strerror(errno));
+        return 1;
+    }
+
+    // at the end of the source nothing is copied
+    if (copy_file_range(src, &inOff, dst, &outOff, LEN, 0) != 0) {
+        printf("copy_file_range at eof should return 0\n");
+        return 1;
+    }
+
+    if (pread(dst, got, LEN, 0) != LEN || memcmp(got, data, LEN) != 0) {
+        printf("the copy differs from the source\n");
+        return 1;
+    }
+
+    // a shared mapping of the destination sees the copied data, a page a private
+    // mapping has written to keeps its own copy
+    char *shared = mmap(NULL, 8192, PROT_READ, MAP_SHARED, dst, 0);
+    char *private = mmap(NULL, 8192, PROT_READ | PROT_WRITE, MAP_PRIVATE, dst, 0);
+    if (shared == MAP_FAILED || private == MAP_FAILED) {
+        printf("mmap fail: %s\n", strerror(errno));
+        return 1;
+    }
+    // fault the pages in before the copy
+    volatile char c = shared[0] + shared[4096] + private[4096];
+    (void)c;
+    private[0] = 'p';
+
+    inOff = LEN - 8192;
+    outOff = 0;
+    if (copy_file_range(src, &inOff, dst, &outOff, 8192, 0) != 8192) {
+        printf("copy_file_range into the mapped range fail: %s\n", strerror(errno));
+        return 1;
+    }
+
+    if (memcmp(shared, data + LEN - 8192, 8192) != 0 ||
+        memcmp(private + 4096, data + LEN - 4096, 4096) != 0 || private[0] != 'p') {
+        printf("the mappings of the destination don't see the copy\n");
+        return 1;
+    }
+    munmap(shared, 8192);
+    munmap(private, 8192);
+
+    inOff = 0;
+    outOff = 4096;
+    if (copy_file_range(src, &inOff, src, &outOff, 8192, 0) >= 0 || errno != EINVAL) {
+        printf("overlapping copy_file_range should fail with EINVAL: %s\n", strerror(errno));
+        return 1;
+    }
+
+    if (copy_file_range(src, NULL, dst, NULL, 1, 1) >= 0 || errno != EINVAL) {
+        printf("copy_file_range with flags should fail with EINVAL\n");
+        return 1;
+    }
+
+    struct statfs fs;
+    fstatfs(dst, &fs);
+    if (fs.f_type == BTRFS_SUPER_MAGIC || fs.f_type == XFS_SUPER_MAGIC) {
+        fsync(dst);
+        int shared = sharedExtents(dst);
+        if (shared == 0) {
+            printf("the copy doesn't share extents with the source\n");
+            return 1;
+        }
+
+        printf("reflink copy %s\n", shared == 1 ? "shares extents" : "done, FIEMAP unavailable");
+    } else {
+        printf("skip the extent check: no reflink support\n");
+    }
+
+    close(src);
+    close(dst);
+    unlink(SRC);
+    unlink(DST);
+    printf("copy_file_range works\n");
+    return 0;
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A process killed by SIGSEGV with RLIMIT_CORE set writes an ELF core with the
+// registers of the faulting thread and its written memory, and none is written
+// with RLIMIT_CORE 0 or PR_SET_DUMPABLE 0. An existing core file with another
+// link or of another user isn't overwritten. A process with more than 65535
+// vmas gets a core with e_phnum PN_XNUM.
+
+#include <elf.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/prctl.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                      \
+    do {                                                      \
+        if (!(cond)) {                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);      \
+            exit(1);                                          \
+        }                                                     \
+    } while (0)
+
+#define R12_MAGIC 0x1122334455667788ULL
+#define R13_MAGIC 0x5152535455565758ULL
+
+#define MANY_VMAS 70000
+
+static char marker[8192];
+
+// crash sets r12 and r13 to known values and reads address 0
+static pid_t crash(int coreLimit, int dumpable, int manyVmas)
+{
+    pid_t pid = fork();
+    if (pid != 0) {
+        return pid;
+    }
+
+    struct rlimit rl;
+    getrlimit(RLIMIT_CORE, &rl);
+    rl.rlim_cur = coreLimit ? rl.rlim_max : 0;
+    setrlimit(RLIMIT_CORE, &rl);
+    prctl(PR_SET_DUMPABLE, dumpable);
+
+    if (manyVmas) {
+        // every other page is read only, so each page is a vma of its own
+        long page = sysconf(_SC_PAGESIZE);
+        char *p = mmap(NULL, MANY_VMAS * page, PROT_READ | PROT_WRITE,
+                       MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+        if (p == MAP_FAILED) {
+            _exit(2);
+        }
+        for (int i = 0; i < MANY_VMAS; i += 2) {
+            mprotect(p + i * page, page, PROT_READ);
+        }
+    }
+
+    for (size_t i = 0; i < sizeof(marker); i++) {
+        marker[i] = (char)(i * 7 + 1);
+    }
+
+    __asm__ volatile("mov %0, %%r12\n\t"
+                     "mov %1, %%r13\n\t"
+                     "xor %%eax, %%eax\n\t"
+                     "mov (%%rax), %%rax\n\t"
+                     :
+                     : "r"(R12_MAGIC), "r"(R13_MAGIC)
+                     : "r12", "r13", "rax", "memory");
+    _exit(0);
+}
+
+static char *readCore(pid_t pid, size_t *len)
+{
+    char path[64];
+    int fd = open("core", O_RDONLY);
+    if (fd < 0) {
+        snprintf(path, sizeof(path), "core.%d", pid);
+        fd = open(path, O_RDONLY);
+    }
+    if (fd < 0) {
+        return NULL;
+    }
+
+    struct stat st;
+    CHECK(fstat(fd, &st) == 0, "fstat core");
+    char *buf = malloc(st.st_size);
+    CHECK(buf != NULL, "malloc");
+    size_t off = 0;
+    while (off < (size_t)st.st_size) {
+        ssize_t n = read(fd, buf + off, st.st_size - off);
+        CHECK(n > 0, "read core");
+        off += n;
+    }
+    close(fd);
+    unlink("core");
+    unlink(path);
+    *len = off;
+    return buf;
+}
+
+static void checkCore(const char *core, size_t len, pid_t pid, int manyVmas)
+{
+    const Elf64_Ehdr *eh = (const Elf64_Ehdr *)core;
+    CHECK(len >= sizeof(*eh) && memcmp(eh->e_ident, ELFMAG, SELFMAG) == 0, "ELF magic");
+    CHECK(eh->e_ident[EI_CLASS] == ELFCLASS64, "ELFCLASS64");
+    CHECK(eh->e_type == ET_CORE, "ET_CORE");
+    CHECK(eh->e_machine == EM_X86_64, "EM_X86_64");
+
+    size_t phnum = eh->e_phnum;
+    if (manyVmas) {
+        CHECK(eh->e_phnum == PN_XNUM, "e_phnum PN_XNUM");
+        CHECK(eh->e_shnum == 1 && eh->e_shentsize == sizeof(Elf64_Shdr), "one section header");
+        CHECK(eh->e_shoff + sizeof(Elf64_Shdr) <= len, "section header in the file");
+        phnum = ((const Elf64_Shdr *)(core + eh->e_shoff))->sh_info;
+        CHECK(phnum > MANY_VMAS, "sh_info");
+    } else {
+        CHECK(eh->e_phnum != PN_XNUM && eh->e_shnum == 0, "no section header");
+    }
+    CHECK(eh->e_phoff + phnum * sizeof(Elf64_Phdr) <= len, "phdrs in the file");
+
+    const Elf64_Phdr *ph = (const Elf64_Phdr *)(core + eh->e_phoff);
+    int prstatus = 0;
+    for (size_t i = 0; i < phnum; i++) {
+        if (ph[i].p_type == PT_NOTE) {
+            CHECK(ph[i].p_offset + ph[i].p_filesz <= len, "notes in the file");
+            size_t off = ph[i].p_offset;
+            while (off + sizeof(Elf64_Nhdr) <= ph[i].p_offset + ph[i].p_filesz) {
+                const Elf64_Nhdr *nh = (const Elf64_Nhdr *)(core + off);
+                const char *name = core + off + sizeof(*nh);
+                const char *desc = name + ((nh->n_namesz + 3) & ~3);
+                if (nh->n_type == NT_PRSTATUS && strcmp(name, "CORE") == 0) {
+                    CHECK(nh->n_descsz == 336, "prstatus size");
+                    CHECK(*(const short *)(desc + 12) == SIGSEGV, "pr_cursig");
+                    CHECK(*(const int *)(desc + 32) == pid, "pr_pid");
+                    const uint64_t *regs = (const uint64_t *)(desc + 112);
+                    CHECK(regs[3] == R12_MAGIC, "r12 in prstatus");
+                    CHECK(regs[2] == R13_MAGIC, "r13 in prstatus");
+                    CHECK(regs[10] == 0, "rax in prstatus");
+                    CHECK(regs[16] != 0 && regs[19] != 0, "rip and rsp in prstatus");
+                    prstatus = 1;
+                }
+                off = (desc - core) + ((nh->n_descsz + 3) & ~3);
+            }
+        }
+    }
+    CHECK(prstatus, "NT_PRSTATUS note");
+
+    // the marker may span the data and bss segments
+    for (size_t j = 0; j < sizeof(marker); j++) {
+        uint64_t addr = (uint64_t)&marker[j];
+        int found = 0;
+        for (size_t i = 0; i < phnum && !found; i++) {
+            if (ph[i].p_type == PT_LOAD && addr >= ph[i].p_vaddr &&
+                addr < ph[i].p_vaddr + ph[i].p_filesz) {
+                CHECK(ph[i].p_offset + ph[i].p_filesz <= len, "segment in the file");
+                CHECK(core[ph[i].p_offset + addr - ph[i].p_vaddr] == (char)(j * 7 + 1),
+                      "memory in the core");
+                found = 1;
+            }
+        }
+        CHECK(found, "written memory segment");
+    }
+}
+
+// keptCore checks that the existing core file wasn't overwritten
+static void keptCore(pid_t pid, const char *msg)
+{
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && !WCOREDUMP(status), msg);
+
+    struct stat st;
+    CHECK(stat("core", &st) == 0 && st.st_size == 4, msg);
+}
+
+int main()
+{
+    char pattern[256] = {0};
+    int fd = open("/proc/sys/kernel/core_pattern", O_RDONLY);
+    CHECK(fd >= 0, "open core_pattern");
+    CHECK(read(fd, pattern, sizeof(pattern) - 1) > 0, "read core_pattern");
+    close(fd);
+
+    struct rlimit rl;
+    CHECK(getrlimit(RLIMIT_CORE, &rl) == 0, "getrlimit");
+    if (strcmp(pattern, "core\n") != 0 || rl.rlim_max == 0) {
+        printf("core_pattern %s or the RLIMIT_CORE hard limit 0 isn't supported, skip\n", pattern);
+        printf("PASS\n");
+        return 0;
+    }
+
+    char dir[] = "/tmp/coredumpXXXXXX";
+    CHECK(mkdtemp(dir) != NULL, "mkdtemp");
+    CHECK(chdir(dir) == 0, "chdir");
+
+    int status;
+    size_t len;
+    pid_t pid = crash(1, 1, 0);
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && WTERMSIG(status) == SIGSEGV, "killed by SIGSEGV");
+    CHECK(WCOREDUMP(status), "WCOREDUMP");
+    char *core = readCore(pid, &len);
+    CHECK(core != NULL, "core file");
+    checkCore(core, len, pid, 0);
+    free(core);
+
+    pid = crash(1, 1, 1);
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && WCOREDUMP(status), "WCOREDUMP with many vmas");
+    core = readCore(pid, &len);
+    CHECK(core != NULL, "core file with many vmas");
+    checkCore(core, len, pid, 1);
+    free(core);
+
+    fd = open("core", O_CREAT | O_WRONLY, 0600);
+    CHECK(fd >= 0 && write(fd, "keep", 4) == 4, "create core");
+    close(fd);
+    CHECK(link("core", "core.link") == 0, "link core");
+    keptCore(crash(1, 1, 0), "core with another link is overwritten");
+    unlink("core.link");
+    if (getuid() == 0) {
+        CHECK(chown("core", 65534, 65534) == 0, "chown core");
+        keptCore(crash(1, 1, 0), "core of another user is overwritten");
+    }
+    unlink("core");
+
+    pid = crash(0, 1, 0);
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && !WCOREDUMP(status), "no WCOREDUMP with RLIMIT_CORE 0");
+    CHECK(readCore(pid, &len) == NULL, "no core with RLIMIT_CORE 0");
+
+    pid = crash(1, 0, 0);
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && !WCOREDUMP(status), "no WCOREDUMP when not dumpable");
+    CHECK(readCore(pid, &len) == NULL, "no core when not dumpable");
+
+    chdir("/");
+    rmdir(dir);
+    printf("PASS\n");
+    return 0;
+}
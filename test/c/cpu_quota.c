@@ -0,0 +1,84 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Busy loop in a few threads and print the cores they used, e.g. with a cpu.max
+// quota of CpuMaxQuotaUs 50000 for CpuMaxPeriodUs 100000 "./cpu_quota 0.5"
+// checks that about half a core is used. Without an expected value it only
+// prints the usage.
+
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define THREADS 2
+#define RUN_NS 3000000000LL
+
+static long long now_ns(clockid_t clock) {
+    struct timespec ts;
+    clock_gettime(clock, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+static void *busy(void *arg) {
+    long long end = *(long long *)arg;
+    volatile unsigned long n = 0;
+    while (now_ns(CLOCK_MONOTONIC) < end) {
+        for (int i = 0; i < 10000; i++) {
+            n++;
+        }
+    }
+    return NULL;
+}
+
+int main(int argc, char **argv) {
+    long long start = now_ns(CLOCK_MONOTONIC);
+    long long cpuStart = now_ns(CLOCK_PROCESS_CPUTIME_ID);
+    long long end = start + RUN_NS;
+
+    pthread_t threads[THREADS];
+    for (int i = 0; i < THREADS; i++) {
+        CHECK(pthread_create(&threads[i], NULL, busy, &end) == 0,
+              "pthread_create");
+    }
+
+    for (int i = 0; i < THREADS; i++) {
+        CHECK(pthread_join(threads[i], NULL) == 0, "pthread_join");
+    }
+
+    double wall = now_ns(CLOCK_MONOTONIC) - start;
+    double cpu = now_ns(CLOCK_PROCESS_CPUTIME_ID) - cpuStart;
+    double cores = cpu / wall;
+    printf("%d threads used %.2f cores\n", THREADS, cores);
+
+    if (argc > 1) {
+        double want = atof(argv[1]);
+        if (cores < want * 0.8 || cores > want * 1.2) {
+            fprintf(stderr, "used %.2f cores, the quota is %.2f\n", cores, want);
+            exit(1);
+        }
+    }
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,78 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// create_mode_mask expects a tmpfs mounted at /masked with the mount option
+// create_mode_mask=0700 and is skipped when the mount isn't there.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define MASKED_DIR "/masked"
+
+static void check_mode(const char *path, mode_t want) {
+    struct stat st;
+    if (stat(path, &st) < 0) {
+        perror("stat");
+        exit(1);
+    }
+
+    if ((st.st_mode & 07777) != want) {
+        fprintf(stderr, "%s: mode %o, want %o\n", path, st.st_mode & 07777, want);
+        exit(1);
+    }
+}
+
+int main() {
+    struct stat st;
+    if (stat(MASKED_DIR, &st) < 0) {
+        printf("create_mode_mask: %s isn't mounted, skip\n", MASKED_DIR);
+        return 0;
+    }
+
+    umask(0);
+
+    int fd = open(MASKED_DIR "/file", O_CREAT | O_EXCL | O_WRONLY, 0666);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    close(fd);
+    check_mode(MASKED_DIR "/file", 0600);
+
+    if (mkdir(MASKED_DIR "/dir", 0777) < 0) {
+        perror("mkdir");
+        return 1;
+    }
+    check_mode(MASKED_DIR "/dir", 0700);
+
+    // the mask only narrows the requested mode
+    fd = open(MASKED_DIR "/file2", O_CREAT | O_EXCL | O_WRONLY, 0400);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    close(fd);
+    check_mode(MASKED_DIR "/file2", 0400);
+
+    unlink(MASKED_DIR "/file");
+    unlink(MASKED_DIR "/file2");
+    rmdir(MASKED_DIR "/dir");
+
+    printf("create_mode_mask works\n");
+    return 0;
+}
@@ -0,0 +1,129 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// A thread blocking on a pipe counts voluntary context switches, threads spinning
+// on more cpus than there are count involuntary ones. Both show up in getrusage
+// and in /proc/thread-self/status.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define ROUNDS 20
+#define SPIN_MS 500
+
+static int pipefd[2];
+
+struct result {
+    struct rusage ru;
+    long statusVoluntary;
+    long statusInvoluntary;
+};
+
+static void readStatus(struct result *r) {
+    char buf[4096];
+    FILE *f = fopen("/proc/thread-self/status", "r");
+    CHECK(f != NULL, "open /proc/thread-self/status");
+    r->statusVoluntary = -1;
+    r->statusInvoluntary = -1;
+    while (fgets(buf, sizeof(buf), f) != NULL) {
+        sscanf(buf, "voluntary_ctxt_switches: %ld", &r->statusVoluntary);
+        sscanf(buf, "nonvoluntary_ctxt_switches: %ld", &r->statusInvoluntary);
+    }
+    fclose(f);
+}
+
+static void *blocker(void *arg) {
+    struct result *r = arg;
+    char c;
+    for (int i = 0; i < ROUNDS; i++) {
+        CHECK(read(pipefd[0], &c, 1) == 1, "read pipe");
+    }
+    CHECK(getrusage(RUSAGE_THREAD, &r->ru) == 0, "getrusage blocker");
+    readStatus(r);
+    return NULL;
+}
+
+static void *spinner(void *arg) {
+    struct result *r = arg;
+    struct timespec start, now;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    volatile unsigned long n = 0;
+    do {
+        for (int i = 0; i < 100000; i++) {
+            n++;
+        }
+        clock_gettime(CLOCK_MONOTONIC, &now);
+    } while ((now.tv_sec - start.tv_sec) * 1000 + (now.tv_nsec - start.tv_nsec) / 1000000 <
+             SPIN_MS);
+    CHECK(getrusage(RUSAGE_THREAD, &r->ru) == 0, "getrusage spinner");
+    readStatus(r);
+    return NULL;
+}
+
+int main() {
+    CHECK(pipe(pipefd) == 0, "pipe");
+
+    struct result blocked;
+    pthread_t t;
+    CHECK(pthread_create(&t, NULL, blocker, &blocked) == 0, "pthread_create blocker");
+    for (int i = 0; i < ROUNDS; i++) {
+        usleep(5000);
+        CHECK(write(pipefd[1], "x", 1) == 1, "write pipe");
+    }
+    CHECK(pthread_join(t, NULL) == 0, "pthread_join blocker");
+    CHECK(blocked.ru.ru_nvcsw >= ROUNDS / 2, "voluntary switches of the blocked thread");
+    CHECK(blocked.statusVoluntary >= blocked.ru.ru_nvcsw, "voluntary_ctxt_switches");
+
+    // more spinning threads than cpus, so that they preempt each other
+    int nspinners = 2 * sysconf(_SC_NPROCESSORS_ONLN);
+    struct result *spun = calloc(nspinners, sizeof(struct result));
+    pthread_t *threads = calloc(nspinners, sizeof(pthread_t));
+    CHECK(spun != NULL && threads != NULL, "calloc");
+    for (int i = 0; i < nspinners; i++) {
+        CHECK(pthread_create(&threads[i], NULL, spinner, &spun[i]) == 0, "pthread_create spinner");
+    }
+
+    long involuntary = 0, statusInvoluntary = 0;
+    for (int i = 0; i < nspinners; i++) {
+        CHECK(pthread_join(threads[i], NULL) == 0, "pthread_join spinner");
+        involuntary += spun[i].ru.ru_nivcsw;
+        statusInvoluntary += spun[i].statusInvoluntary;
+    }
+    CHECK(involuntary > 0, "involuntary switches of the spinning threads");
+    CHECK(statusInvoluntary >= involuntary, "nonvoluntary_ctxt_switches");
+
+    struct rusage self;
+    CHECK(getrusage(RUSAGE_SELF, &self) == 0, "getrusage self");
+    CHECK(self.ru_nvcsw >= blocked.ru.ru_nvcsw, "voluntary switches of the process");
+    CHECK(self.ru_nivcsw >= involuntary, "involuntary switches of the process");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Opens a host character device which is passed through to the sandbox, by default
+// /dev/null bind mounted at /passthrough/null, e.g.
+//   docker run -v /dev/null:/passthrough/null ...
+// with "DevicePassthrough": ["1:3"] in /etc/quark/config.json. With "deny" as the
+// second argument the device is expected not to be in the allowlist.
+
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/stat.h>
+#include <sys/sysmacros.h>
+
+int main(int argc, char *argv[]) {
+    const char *path = argc > 1 ? argv[1] : "/passthrough/null";
+    int deny = argc > 2 && strcmp(argv[2], "deny") == 0;
+
+    struct stat st;
+    if (stat(path, &st) < 0) {
+        printf("stat %s fail: %s\n", path, strerror(errno));
+        return 1;
+    }
+
+    if (!S_ISCHR(st.st_mode) || major(st.st_rdev) != 1 || minor(st.st_rdev) != 3) {
+        printf("%s is not a char device 1:3\n", path);
+        return 1;
+    }
+
+    // O_PATH doesn't open the device, so it works either way
+    int pfd = open(path, O_PATH);
+    if (pfd < 0) {
+        printf("open %s O_PATH fail: %s\n", path, strerror(errno));
+        return 1;
+    }
+    close(pfd);
+
+    int fd = open(path, O_RDWR);
+    if (deny) {
+        if (fd >= 0 || errno != EPERM) {
+            printf("open %s should fail with EPERM, got %d: %s\n", path, fd, strerror(errno));
+            return 1;
+        }
+
+        printf("open %s is denied\n", path);
+        return 0;
+    }
+
+    if (fd < 0) {
+        printf("open %s fail: %s\n", path, strerror(errno));
+        return 1;
+    }
+
+    char buf[64];
+    memset(buf, 'a', sizeof(buf));
+    ssize_t n = write(fd, buf, sizeof(buf));
+    if (n != sizeof(buf)) {
+        printf("write %s returns %zd: %s\n", path, n, strerror(errno));
+        return 1;
+    }
+
+    n = read(fd, buf, sizeof(buf));
+    if (n != 0) {
+        printf("read %s returns %zd, expect eof\n", path, n);
+        return 1;
+    }
+
+    close(fd);
+    printf("device %s passthrough works\n", path);
+    return 0;
+}
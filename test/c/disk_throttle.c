@@ -0,0 +1,77 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Disk write throttle, run with the write rate of /etc/quark/config.json in
+// bytes/sec, e.g. for
+//   "DiskWriteBytesPerSec": 1048576, "DiskBurstBytes": 0
+// run "disk_throttle 1048576". Writing 4 seconds worth of data has to take about
+// 3 seconds, as the first second goes through as the burst.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static double now(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec + ts.tv_nsec / 1e9;
+}
+
+int main(int argc, char *argv[]) {
+    if (argc < 2) {
+        printf("usage: %s <write bytes/sec>\n", argv[0]);
+        return 1;
+    }
+
+    long rate = atol(argv[1]);
+    CHECK(rate > 0, "rate");
+
+    char path[] = "/tmp/disk_throttle_XXXXXX";
+    int fd = mkstemp(path);
+    CHECK(fd >= 0, "mkstemp");
+    unlink(path);
+
+    static char buf[64 * 1024];
+    memset(buf, 'x', sizeof(buf));
+
+    long total = 4 * rate;
+    double start = now();
+    for (long written = 0; written < total;) {
+        ssize_t n = write(fd, buf, sizeof(buf));
+        CHECK(n > 0, "write");
+        written += n;
+    }
+    double elapsed = now() - start;
+
+    double expected = (double)(total - rate) / rate;
+    printf("wrote %ld bytes in %.2fs, %.0f bytes/sec\n", total, elapsed, total / elapsed);
+    CHECK(elapsed > expected * 0.9, "write rate over the limit");
+    CHECK(elapsed < expected * 1.5 + 1, "write rate far under the limit");
+
+    close(fd);
+    printf("PASS\n");
+    return 0;
+}
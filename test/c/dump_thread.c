@@ -0,0 +1,139 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Keeps a thread spinning in user space with known values in r12 and r13 and a
+// marker at the top of its stack, prints its tid and waits for a dump of it, e.g.
+//   docker run -v /tmp/dump:/dump --runtime=quark ... dump_thread /dump/out
+//   quark dump --tid <tid> --stack 64 --output /tmp/dump/out <container id>
+// then checks the registers and the stack in the dump.
+// usage: dump_thread <dump_file> [timeout_s]
+
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+// "QUARKDMP" in memory
+#define STACK_MARKER 0x504d444b52415551ULL
+#define R13_VALUE 0x1122334455667788ULL
+
+static volatile int spinTid = 0;
+
+static void *Spin(void *arg) {
+    spinTid = syscall(SYS_gettid);
+    __asm__ volatile(
+        "mov %0, %%r12\n"
+        "mov %1, %%r13\n"
+        "push %%r12\n"
+        "1: pause\n"
+        "jmp 1b\n"
+        :
+        : "r"(STACK_MARKER), "r"(R13_VALUE)
+        : "r12", "r13", "memory");
+    return NULL;
+}
+
+// read_dump waits for the dump file to be written and reads it into buf
+static int read_dump(const char *path, char *buf, size_t size, int timeout) {
+    for (int i = 0; i < timeout * 10; i++) {
+        FILE *f = fopen(path, "r");
+        if (f != NULL) {
+            size_t n = fread(buf, 1, size - 1, f);
+            fclose(f);
+            buf[n] = 0;
+            // the dump is complete once the stack hexdump is there
+            char *stack = strstr(buf, "\nstack ");
+            if (stack != NULL && strchr(stack + 1, '|') != NULL) {
+                return 0;
+            }
+        }
+        usleep(100000);
+    }
+
+    return -1;
+}
+
+int main(int argc, char *argv[]) {
+    if (argc < 2) {
+        printf("usage: %s <dump_file> [timeout_s]\n", argv[0]);
+        return 1;
+    }
+    int timeout = argc > 2 ? atoi(argv[2]) : 60;
+
+    pthread_t thread;
+    pthread_create(&thread, NULL, Spin, NULL);
+    while (spinTid == 0) {
+        usleep(1000);
+    }
+    printf("tid %d\n", spinTid);
+    fflush(stdout);
+
+    static char buf[64 * 1024];
+    if (read_dump(argv[1], buf, sizeof(buf), timeout) != 0) {
+        printf("no dump in %s after %ds\n", argv[1], timeout);
+        return 1;
+    }
+
+    int tid = 0;
+    unsigned long long r12 = 0, r13 = 0, rsp = 0, stackAddr = 0;
+    int stackLen = 0;
+    char *marker = NULL;
+    for (char *line = strtok(buf, "\n"); line != NULL; line = strtok(NULL, "\n")) {
+        if (strncmp(line, "thread ", 7) == 0) {
+            sscanf(line, "thread %d", &tid);
+        } else if (strncmp(line, "r12 ", 4) == 0) {
+            sscanf(line, "r12 0x%llx", &r12);
+        } else if (strncmp(line, "r13 ", 4) == 0) {
+            sscanf(line, "r13 0x%llx", &r13);
+        } else if (strncmp(line, "rsp ", 4) == 0) {
+            sscanf(line, "rsp 0x%llx", &rsp);
+        } else if (strncmp(line, "stack ", 6) == 0) {
+            sscanf(line, "stack 0x%llx, %d bytes", &stackAddr, &stackLen);
+        } else if (stackLen > 0 && marker == NULL) {
+            // the first hexdump line, its ascii column starts at the stack pointer
+            marker = strchr(line, '|');
+        }
+    }
+
+    int failed = 0;
+    if (tid != spinTid) {
+        printf("thread %d, expected %d\n", tid, spinTid);
+        failed = 1;
+    }
+    if (r12 != STACK_MARKER || r13 != R13_VALUE) {
+        printf("r12 0x%llx r13 0x%llx, expected 0x%llx 0x%llx\n", r12, r13, STACK_MARKER,
+               R13_VALUE);
+        failed = 1;
+    }
+    if (rsp == 0 || stackAddr != rsp || stackLen < 8) {
+        printf("stack 0x%llx, %d bytes, rsp 0x%llx\n", stackAddr, stackLen, rsp);
+        failed = 1;
+    }
+    if (marker == NULL || strncmp(marker + 1, "QUARKDMP", 8) != 0) {
+        printf("stack doesn't start with the marker: %s\n", marker ? marker : "(none)");
+        failed = 1;
+    }
+
+    if (failed) {
+        printf("dump_thread failed\n");
+        return 1;
+    }
+
+    printf("dump_thread passed\n");
+    return 0;
+}
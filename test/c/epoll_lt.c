@@ -0,0 +1,145 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A level-triggered socket must stay readable in epoll after a partial read,
+// an edge-triggered one must not be reported again until new data arrives.
+
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#define PORT 8090
+#define DATA_LEN 128
+
+static void fail(const char *msg) {
+  perror(msg);
+  exit(1);
+}
+
+static int wait_readable(int epfd, int timeout) {
+  struct epoll_event ev;
+  int n = epoll_wait(epfd, &ev, 1, timeout);
+  if (n < 0)
+    fail("epoll_wait");
+  return n == 1 && (ev.events & EPOLLIN);
+}
+
+static int connected_pair(int *client, int *server) {
+  int listener = socket(AF_INET, SOCK_STREAM, 0);
+  if (listener < 0)
+    fail("socket");
+
+  int opt = 1;
+  setsockopt(listener, SOL_SOCKET, SO_REUSEADDR, &opt, sizeof(opt));
+
+  struct sockaddr_in addr;
+  memset(&addr, 0, sizeof(addr));
+  addr.sin_family = AF_INET;
+  addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+  addr.sin_port = htons(PORT);
+
+  if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+    fail("bind");
+  if (listen(listener, 1) < 0)
+    fail("listen");
+
+  *client = socket(AF_INET, SOCK_STREAM, 0);
+  if (connect(*client, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+    fail("connect");
+
+  *server = accept(listener, NULL, NULL);
+  if (*server < 0)
+    fail("accept");
+
+  close(listener);
+  return 0;
+}
+
+static int check(int edge) {
+  int client, server;
+  char buf[DATA_LEN];
+
+  connected_pair(&client, &server);
+
+  int epfd = epoll_create1(0);
+  if (epfd < 0)
+    fail("epoll_create1");
+
+  struct epoll_event ev;
+  ev.events = EPOLLIN | (edge ? EPOLLET : 0);
+  ev.data.fd = server;
+  if (epoll_ctl(epfd, EPOLL_CTL_ADD, server, &ev) < 0)
+    fail("epoll_ctl");
+
+  memset(buf, 'a', sizeof(buf));
+  if (write(client, buf, DATA_LEN) != DATA_LEN)
+    fail("write");
+
+  if (!wait_readable(epfd, 1000)) {
+    printf("epoll %s: socket not reported readable\n", edge ? "ET" : "LT");
+    return 1;
+  }
+
+  // drain only half of the data
+  int got = 0;
+  while (got < DATA_LEN / 2) {
+    int n = read(server, buf, DATA_LEN / 2 - got);
+    if (n <= 0)
+      fail("read");
+    got += n;
+  }
+
+  int again = wait_readable(epfd, 100);
+  if (edge && again) {
+    printf("epoll ET: socket reported again without new data\n");
+    return 1;
+  }
+  if (!edge && !again) {
+    printf("epoll LT: socket not readable after partial read\n");
+    return 1;
+  }
+
+  // drain the rest, the level-triggered socket should go quiet
+  while (got < DATA_LEN) {
+    int n = read(server, buf, DATA_LEN - got);
+    if (n <= 0)
+      fail("read");
+    got += n;
+  }
+
+  if (wait_readable(epfd, 0)) {
+    printf("epoll %s: drained socket still readable\n", edge ? "ET" : "LT");
+    return 1;
+  }
+
+  close(epfd);
+  close(client);
+  close(server);
+  return 0;
+}
+
+int main() {
+  if (check(0) != 0 || check(1) != 0) {
+    printf("epoll lt test fail\n");
+    return 1;
+  }
+
+  printf("epoll lt test pass\n");
+  return 0;
+}
@@ -0,0 +1,123 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Exec a copy of a binary many times with the exec cache used and with it
+// missed, and print the average latency of a fork and exec of both, e.g.
+// "./exec_benchmark /bin/true 1000". The cache is missed by setting a new mtime
+// on the copy before every exec, which isn't counted in the latency. With the
+// cache, the execs after the first one don't read and parse the ELF headers nor
+// prepare the segments again.
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static long long now_ns(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+static int copy(const char *src, const char *dst) {
+    int in = open(src, O_RDONLY);
+    int out = open(dst, O_CREAT | O_TRUNC | O_WRONLY, 0755);
+    if (in < 0 || out < 0) {
+        perror("open");
+        return -1;
+    }
+
+    char buf[65536];
+    ssize_t n;
+    while ((n = read(in, buf, sizeof(buf))) > 0) {
+        if (write(out, buf, n) != n) {
+            perror("write");
+            return -1;
+        }
+    }
+    close(in);
+    close(out);
+    return n < 0 ? -1 : 0;
+}
+
+// run execs bin count times and returns the average latency in ns, -1 on error
+static long long run(const char *bin, int count, int miss) {
+    long long total = 0;
+    for (int i = 0; i < count; i++) {
+        if (miss) {
+            struct timespec ts[2] = {{.tv_nsec = UTIME_OMIT}, {.tv_sec = 1000 + i}};
+            if (utimensat(AT_FDCWD, bin, ts, 0) < 0) {
+                perror("utimensat");
+                return -1;
+            }
+        }
+
+        long long start = now_ns();
+        pid_t pid = fork();
+        if (pid < 0) {
+            perror("fork");
+            return -1;
+        }
+        if (pid == 0) {
+            execl(bin, bin, NULL);
+            _exit(127);
+        }
+
+        int status;
+        if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) ||
+            WEXITSTATUS(status) == 127) {
+            printf("exec of %s failed\n", bin);
+            return -1;
+        }
+        total += now_ns() - start;
+    }
+
+    return total / count;
+}
+
+int main(int argc, char *argv[]) {
+    const char *bin = argc > 1 ? argv[1] : "/bin/true";
+    int count = argc > 2 ? atoi(argv[2]) : 1000;
+    if (count <= 0) {
+        printf("bad exec count %s\n", argv[2]);
+        return 1;
+    }
+
+    char path[] = "/tmp/exec_benchmark_XXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    close(fd);
+    if (copy(bin, path) < 0 || chmod(path, 0755) < 0) {
+        return 1;
+    }
+
+    long long missed = run(path, count, 1);
+    // the first exec of the hit run fills the cache
+    long long hit = missed < 0 ? -1 : run(path, count, 0);
+    unlink(path);
+    if (hit < 0) {
+        return 1;
+    }
+
+    printf("%d execs of %s: cache missed %lld us, cache used %lld us, %.2fx\n", count,
+           bin, missed / 1000, hit / 1000, (double)missed / (hit > 0 ? hit : 1));
+    return 0;
+}
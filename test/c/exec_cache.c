@@ -0,0 +1,81 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Exec a binary, rewrite it in place with another binary and exec it again: the
+// exec cache must not serve the headers of the old binary.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define BIN "/tmp/exec_cache_bin"
+
+// install copies src over BIN, keeping the inode of BIN.
+static void install(const char *src) {
+    char buf[65536];
+    int in = open(src, O_RDONLY);
+    CHECK(in >= 0, "open the source binary");
+    int out = open(BIN, O_WRONLY | O_CREAT | O_TRUNC, 0755);
+    CHECK(out >= 0, "open the binary");
+
+    ssize_t n;
+    while ((n = read(in, buf, sizeof(buf))) > 0)
+        CHECK(write(out, buf, n) == n, "write the binary");
+    CHECK(n == 0, "read the source binary");
+    close(in);
+    close(out);
+}
+
+static int run(void) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        execl(BIN, BIN, NULL);
+        _exit(127);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status), "the binary didn't exit");
+    return WEXITSTATUS(status);
+}
+
+int main() {
+    install("/bin/true");
+    for (int i = 0; i < 3; i++)
+        CHECK(run() == 0, "exit status of true");
+
+    // the file timestamps are taken from a coarse clock, make sure that the
+    // rewrite changes the mtime
+    usleep(50000);
+    install("/bin/false");
+    for (int i = 0; i < 3; i++)
+        CHECK(run() == 1, "exit status of false after the rewrite");
+
+    unlink(BIN);
+    printf("PASS\n");
+    return 0;
+}
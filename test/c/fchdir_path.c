@@ -0,0 +1,86 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// fchdir to a directory opened with O_PATH, directly and through a symlink.
+
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static int fchdir_and_check(const char *path, const char *want) {
+  int fd = open(path, O_PATH);
+  if (fd < 0) {
+    perror("open O_PATH");
+    return 1;
+  }
+
+  if (fchdir(fd) != 0) {
+    perror("fchdir");
+    return 1;
+  }
+  close(fd);
+
+  char buf[PATH_MAX];
+  if (getcwd(buf, sizeof(buf)) == NULL || strcmp(buf, want) != 0) {
+    printf("getcwd after fchdir(%s) is %s, expect %s\n", path, buf, want);
+    return 1;
+  }
+
+  // relative open resolves against the new cwd
+  int f = open("file", O_RDONLY);
+  if (f < 0) {
+    perror("relative open");
+    return 1;
+  }
+  close(f);
+  return 0;
+}
+
+int main() {
+  mkdir("/tmp/fchdir_path", 0755);
+  int f = open("/tmp/fchdir_path/file", O_CREAT | O_WRONLY, 0644);
+  close(f);
+  symlink("/tmp/fchdir_path", "/tmp/fchdir_path_link");
+
+  chdir("/");
+  if (fchdir_and_check("/tmp/fchdir_path", "/tmp/fchdir_path") != 0)
+    goto fail;
+
+  chdir("/");
+  if (fchdir_and_check("/tmp/fchdir_path_link", "/tmp/fchdir_path") != 0)
+    goto fail;
+
+  // O_PATH | O_NOFOLLOW on the symlink refers to the link itself
+  int lfd = open("/tmp/fchdir_path_link", O_PATH | O_NOFOLLOW);
+  if (lfd < 0 || fchdir(lfd) == 0) {
+    printf("fchdir to a symlink O_PATH fd should fail\n");
+    goto fail;
+  }
+  close(lfd);
+
+  chdir("/");
+  unlink("/tmp/fchdir_path_link");
+  unlink("/tmp/fchdir_path/file");
+  rmdir("/tmp/fchdir_path");
+  printf("fchdir path test pass\n");
+  return 0;
+
+fail:
+  printf("fchdir path test fail\n");
+  return 1;
+}
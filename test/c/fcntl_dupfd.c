@@ -0,0 +1,71 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+int main() {
+    int fd = open("/proc/self/exe", O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    // a minfd above every open fd
+    int minfd = fd + 100;
+    int dupfd = fcntl(fd, F_DUPFD, minfd);
+    if (dupfd != minfd) {
+        fprintf(stderr, "F_DUPFD returned %d, want %d\n", dupfd, minfd);
+        return 1;
+    }
+
+    if (fcntl(dupfd, F_GETFD) & FD_CLOEXEC) {
+        fprintf(stderr, "F_DUPFD set FD_CLOEXEC\n");
+        return 1;
+    }
+
+    // the duplicate shares the file offset
+    if (lseek(fd, 42, SEEK_SET) != 42 || lseek(dupfd, 0, SEEK_CUR) != 42) {
+        fprintf(stderr, "F_DUPFD fd doesn't share the offset\n");
+        return 1;
+    }
+
+    // the lowest free fd >= minfd is taken
+    int dupfd2 = fcntl(fd, F_DUPFD_CLOEXEC, minfd);
+    if (dupfd2 != minfd + 1) {
+        fprintf(stderr, "F_DUPFD_CLOEXEC returned %d, want %d\n", dupfd2, minfd + 1);
+        return 1;
+    }
+
+    if (!(fcntl(dupfd2, F_GETFD) & FD_CLOEXEC)) {
+        fprintf(stderr, "F_DUPFD_CLOEXEC didn't set FD_CLOEXEC\n");
+        return 1;
+    }
+
+    struct rlimit rl;
+    getrlimit(RLIMIT_NOFILE, &rl);
+    if (fcntl(fd, F_DUPFD, (int)rl.rlim_cur) >= 0 || errno != EINVAL) {
+        fprintf(stderr, "F_DUPFD at RLIMIT_NOFILE: errno %d, want EINVAL\n", errno);
+        return 1;
+    }
+
+    close(dupfd2);
+    close(dupfd);
+    close(fd);
+    printf("fcntl_dupfd works\n");
+    return 0;
+}
@@ -0,0 +1,122 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/socket.h>
+#include <time.h>
+#include <unistd.h>
+
+#define DELAY_MS 200
+
+static int client;
+
+static void *delayed_write(void *arg) {
+    usleep(DELAY_MS * 1000);
+    if (write(client, "x", 1) != 1) {
+        perror("write");
+        exit(1);
+    }
+    return NULL;
+}
+
+static long now_ms() {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000 + ts.tv_nsec / 1000000;
+}
+
+static void set_nonblock(int fd, int on) {
+    int flags = fcntl(fd, F_GETFL);
+    if (flags < 0) {
+        perror("fcntl");
+        exit(1);
+    }
+    flags = on ? flags | O_NONBLOCK : flags & ~O_NONBLOCK;
+    if (fcntl(fd, F_SETFL, flags) < 0) {
+        perror("fcntl");
+        exit(1);
+    }
+    if (!!(fcntl(fd, F_GETFL) & O_NONBLOCK) != on) {
+        fprintf(stderr, "F_GETFL doesn't reflect O_NONBLOCK=%d\n", on);
+        exit(1);
+    }
+}
+
+int main() {
+    struct sockaddr_in addr = {.sin_family = AF_INET, .sin_addr.s_addr = htonl(INADDR_LOOPBACK)};
+    socklen_t len = sizeof(addr);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0 || bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        listen(listener, 1) < 0 || getsockname(listener, (struct sockaddr *)&addr, &len) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    // accept on an O_NONBLOCK listener doesn't wait for a connection
+    set_nonblock(listener, 1);
+    if (accept(listener, NULL, NULL) >= 0 || errno != EAGAIN) {
+        fprintf(stderr, "nonblocking accept: errno %d\n", errno);
+        return 1;
+    }
+    set_nonblock(listener, 0);
+
+    client = socket(AF_INET, SOCK_STREAM, 0);
+    if (client < 0 || connect(client, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+
+    int server = accept(listener, NULL, NULL);
+    if (server < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    char c;
+    set_nonblock(server, 1);
+    if (read(server, &c, 1) >= 0 || errno != EAGAIN) {
+        fprintf(stderr, "nonblocking read: errno %d\n", errno);
+        return 1;
+    }
+
+    set_nonblock(server, 0);
+    pthread_t t;
+    pthread_create(&t, NULL, delayed_write, NULL);
+
+    long start = now_ms();
+    if (read(server, &c, 1) != 1) {
+        perror("read");
+        return 1;
+    }
+    long elapsed = now_ms() - start;
+    pthread_join(t, NULL);
+
+    if (elapsed < DELAY_MS / 2) {
+        fprintf(stderr, "blocking read returned after %ld ms\n", elapsed);
+        return 1;
+    }
+
+    close(server);
+    close(client);
+    close(listener);
+    printf("fcntl_nonblock works\n");
+    return 0;
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Clones a file with FICLONE and a block with FICLONERANGE and checks the clones
+// read like the source. The test is skipped when the filesystem has no reflink
+// support, where both ioctls fail with EOPNOTSUPP (or EXDEV/EINVAL).
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/ioctl.h>
+#include <sys/mman.h>
+#include <linux/fs.h>
+
+#define SRC "/tmp/ficlone_src"
+#define DST "/tmp/ficlone_dst"
+#define LEN (256 * 1024)
+
+static int cleanup(int ret) {
+    unlink(SRC);
+    unlink(DST);
+    return ret;
+}
+
+int main() {
+    char *data = malloc(LEN), *got = malloc(LEN);
+    for (int i = 0; i < LEN; i++) {
+        data[i] = i * 13 + i / 4096;
+    }
+
+    int src = open(SRC, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    int dst = open(DST, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (src < 0 || dst < 0 || write(src, data, LEN) != LEN) {
+        printf("prepare files fail: %s\n", strerror(errno));
+        return cleanup(1);
+    }
+    fsync(src);
+
+    // a read only destination can't be cloned into
+    int ro = open(DST, O_RDONLY);
+    if (ioctl(ro, FICLONE, src) >= 0 || errno != EBADF) {
+        printf("FICLONE into a read only fd should fail with EBADF: %s\n", strerror(errno));
+        return cleanup(1);
+    }
+    close(ro);
+
+    if (ioctl(dst, FICLONE, src) < 0) {
+        if (errno == EOPNOTSUPP || errno == EXDEV || errno == EINVAL) {
+            printf("skip: no reflink support: %s\n", strerror(errno));
+            return cleanup(0);
+        }
+
+        printf("FICLONE fail: %s\n", strerror(errno));
+        return cleanup(1);
+    }
+
+    if (pread(dst, got, LEN, 0) != LEN || memcmp(got, data, LEN) != 0) {
+        printf("the FICLONE clone differs from the source\n");
+        return cleanup(1);
+    }
+
+    // a shared mapping of the destination sees the clone, which has to replace the
+    // page the mapping faulted in before
+    char *shared = mmap(NULL, 4096, PROT_READ, MAP_SHARED, dst, 0);
+    if (shared == MAP_FAILED || memcmp(shared, data, 4096) != 0) {
+        printf("mmap of the clone fail: %s\n", strerror(errno));
+        return cleanup(1);
+    }
+
+    // clone the second block of the source over the first one of the destination
+    struct file_clone_range range = {
+        .src_fd = src,
+        .src_offset = 4096,
+        .src_length = 4096,
+        .dest_offset = 0,
+    };
+    if (ioctl(dst, FICLONERANGE, &range) < 0) {
+        printf("FICLONERANGE fail: %s\n", strerror(errno));
+        return cleanup(1);
+    }
+
+    if (pread(dst, got, 4096, 0) != 4096 || memcmp(got, data + 4096, 4096) != 0) {
+        printf("the FICLONERANGE clone differs from the source\n");
+        return cleanup(1);
+    }
+
+    if (memcmp(shared, data + 4096, 4096) != 0) {
+        printf("the mapping of the destination doesn't see the FICLONERANGE clone\n");
+        return cleanup(1);
+    }
+    munmap(shared, 4096);
+
+    close(src);
+    close(dst);
+    printf("FICLONE works\n");
+    return cleanup(0);
+}
@@ -0,0 +1,97 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Punches a hole in the middle of a file and checks FIEMAP reports the extents
+// around it but none inside, first counting the extents with fm_extent_count 0.
+
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/ioctl.h>
+#include <linux/fiemap.h>
+#include <linux/fs.h>
+
+#define PATH "/tmp/fiemap_test"
+#define BLOCK (64 * 1024)
+
+int main() {
+    char data[BLOCK];
+    memset(data, 'q', sizeof(data));
+
+    int fd = open(PATH, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    for (int i = 0; i < 3; i++) {
+        if (write(fd, data, BLOCK) != BLOCK) {
+            printf("write fail: %s\n", strerror(errno));
+            return 1;
+        }
+    }
+    fsync(fd);
+    unlink(PATH);
+
+    if (fallocate(fd, FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE, BLOCK, BLOCK) < 0) {
+        printf("skip: punch hole fail: %s\n", strerror(errno));
+        return 0;
+    }
+
+    struct fiemap count;
+    memset(&count, 0, sizeof(count));
+    count.fm_length = FIEMAP_MAX_OFFSET;
+    count.fm_flags = FIEMAP_FLAG_SYNC;
+    if (ioctl(fd, FS_IOC_FIEMAP, &count) < 0) {
+        if (errno == EOPNOTSUPP || errno == ENOTTY) {
+            printf("skip: no FIEMAP: %s\n", strerror(errno));
+            return 0;
+        }
+
+        printf("FIEMAP count fail: %s\n", strerror(errno));
+        return 1;
+    }
+
+    if (count.fm_mapped_extents < 2) {
+        printf("FIEMAP counts %u extents, expect at least 2\n", count.fm_mapped_extents);
+        return 1;
+    }
+
+    int n = count.fm_mapped_extents;
+    struct fiemap *fm = calloc(1, sizeof(struct fiemap) + n * sizeof(struct fiemap_extent));
+    fm->fm_length = FIEMAP_MAX_OFFSET;
+    fm->fm_extent_count = n;
+    if (ioctl(fd, FS_IOC_FIEMAP, fm) < 0 || fm->fm_mapped_extents != n || fm->fm_extent_count != n) {
+        printf("FIEMAP fail: %u extents: %s\n", fm->fm_mapped_extents, strerror(errno));
+        return 1;
+    }
+
+    unsigned long long covered = 0;
+    for (int i = 0; i < n; i++) {
+        struct fiemap_extent *e = &fm->fm_extents[i];
+        if (e->fe_logical < 2 * BLOCK && e->fe_logical + e->fe_length > BLOCK) {
+            printf("extent %d [%llu, +%llu) overlaps the hole\n", i, e->fe_logical, e->fe_length);
+            return 1;
+        }
+        covered += e->fe_length;
+    }
+
+    if (!(fm->fm_extents[n - 1].fe_flags & FIEMAP_EXTENT_LAST) || covered != 2 * BLOCK) {
+        printf("the extents cover %llu bytes, expect %d\n", covered, 2 * BLOCK);
+        return 1;
+    }
+
+    close(fd);
+    printf("FIEMAP reports the hole\n");
+    return 0;
+}
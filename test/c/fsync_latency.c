@@ -0,0 +1,128 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Measures the latency of a lone fsync under no other load, after a one byte
+// write and on a file without dirty data. The second one is mostly the cost of
+// getting the fsync to the host and back, which is what "UringTurboFsync" in
+// config.json cuts down. Run it first with UringTurboFsync false and a baseline
+// file which doesn't exist yet, the clean average of the batched run is written
+// to it. Then run it with UringTurboFsync true and the same baseline file, the
+// test fails unless the turbo average is at most max_pct percent (90 by default)
+// of the batched one. Without a baseline file it only reports the averages.
+// usage: fsync_latency [path] [baseline_file] [max_pct]
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+#include <unistd.h>
+
+#define ROUNDS 200
+#define DEFAULT_MAX_PCT 90
+
+static long long now_ns() {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+// measure returns the average fsync latency in ns, with a one byte write
+// before every fsync if dirty is set
+static long long measure(int fd, int dirty) {
+    long long total = 0, min = -1, max = 0;
+    for (int i = 0; i < ROUNDS; i++) {
+        char c = (char)i;
+        if (dirty && pwrite(fd, &c, 1, i) != 1) {
+            perror("pwrite");
+            return -1;
+        }
+
+        long long start = now_ns();
+        if (fsync(fd) != 0) {
+            perror("fsync");
+            return -1;
+        }
+        long long d = now_ns() - start;
+
+        total += d;
+        if (min < 0 || d < min)
+            min = d;
+        if (d > max)
+            max = d;
+    }
+
+    long long avg = total / ROUNDS;
+    printf("%s fsync latency us: avg %lld min %lld max %lld\n",
+           dirty ? "dirty" : "clean", avg / 1000, min / 1000, max / 1000);
+    return avg;
+}
+
+int main(int argc, char *argv[]) {
+    const char *path = argc > 1 ? argv[1] : "/tmp/fsync_latency.dat";
+    const char *baseline = argc > 2 ? argv[2] : NULL;
+    long long maxPct = argc > 3 ? atoll(argv[3]) : DEFAULT_MAX_PCT;
+
+    int fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    long long avg = 0;
+    for (int dirty = 1; dirty >= 0; dirty--) {
+        avg = measure(fd, dirty);
+        if (avg < 0)
+            return 1;
+    }
+
+    close(fd);
+    unlink(path);
+
+    if (baseline == NULL) {
+        printf("fsync latency test pass\n");
+        return 0;
+    }
+
+    FILE *f = fopen(baseline, "r");
+    if (f == NULL) {
+        f = fopen(baseline, "w");
+        if (f == NULL) {
+            perror("fopen");
+            return 1;
+        }
+        fprintf(f, "%lld\n", avg);
+        fclose(f);
+        printf("fsync latency baseline %lld ns written to %s\n", avg, baseline);
+        return 0;
+    }
+
+    long long batched = 0;
+    if (fscanf(f, "%lld", &batched) != 1 || batched <= 0) {
+        printf("fsync latency test fail: no baseline in %s\n", baseline);
+        fclose(f);
+        return 1;
+    }
+    fclose(f);
+
+    printf("fsync latency clean avg is %lld%% of the baseline\n", avg * 100 / batched);
+    if (avg * 100 > batched * maxPct) {
+        printf("fsync latency test fail: clean avg %lld ns > %lld%% of %lld ns\n",
+               avg, maxPct, batched);
+        return 1;
+    }
+
+    printf("fsync latency test pass\n");
+    return 0;
+}
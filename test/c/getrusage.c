@@ -0,0 +1,99 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// getrusage reports the cpu time and the max rss of the process, the calling
+// thread and the reaped children.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define ALLOC_MB 64
+
+static long usec(struct timeval *tv) {
+    return tv->tv_sec * 1000000 + tv->tv_usec;
+}
+
+// spin for about ms of cpu time
+static void spin(int ms) {
+    struct timespec start, now;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    volatile unsigned long n = 0;
+    do {
+        for (int i = 0; i < 100000; i++) {
+            n++;
+        }
+        clock_gettime(CLOCK_MONOTONIC, &now);
+    } while ((now.tv_sec - start.tv_sec) * 1000 + (now.tv_nsec - start.tv_nsec) / 1000000 < ms);
+}
+
+static void touch(size_t mb) {
+    char *p = malloc(mb << 20);
+    CHECK(p != NULL, "malloc");
+    memset(p, 1, mb << 20);
+}
+
+int main() {
+    struct rusage ru;
+
+    spin(200);
+    touch(ALLOC_MB);
+
+    CHECK(getrusage(RUSAGE_SELF, &ru) == 0, "getrusage RUSAGE_SELF");
+    CHECK(usec(&ru.ru_utime) > 0, "ru_utime of RUSAGE_SELF is 0");
+    CHECK(ru.ru_maxrss >= ALLOC_MB * 1024, "ru_maxrss of RUSAGE_SELF below the allocation");
+
+    CHECK(getrusage(RUSAGE_THREAD, &ru) == 0, "getrusage RUSAGE_THREAD");
+    CHECK(usec(&ru.ru_utime) > 0, "ru_utime of RUSAGE_THREAD is 0");
+
+    CHECK(getrusage(RUSAGE_CHILDREN, &ru) == 0, "getrusage RUSAGE_CHILDREN");
+    long childUtime = usec(&ru.ru_utime);
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        spin(200);
+        touch(2 * ALLOC_MB);
+        exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+
+    CHECK(getrusage(RUSAGE_CHILDREN, &ru) == 0, "getrusage RUSAGE_CHILDREN after wait");
+    CHECK(usec(&ru.ru_utime) > childUtime, "ru_utime of RUSAGE_CHILDREN didn't grow");
+    CHECK(ru.ru_maxrss >= 2 * ALLOC_MB * 1024, "ru_maxrss of RUSAGE_CHILDREN below the allocation");
+
+    CHECK(getrusage(-3, &ru) == -1 && errno == EINVAL, "getrusage with a bad who");
+
+    printf("PASS\n");
+    return 0;
+}
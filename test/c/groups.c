@@ -0,0 +1,102 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <grp.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define GROUP_FILE "/tmp/groups_test_member"
+#define OTHER_FILE "/tmp/groups_test_other"
+
+static void create(const char *path, gid_t gid) {
+    int fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0640);
+    CHECK(fd >= 0, "create");
+    CHECK(write(fd, "data", 4) == 4, "write");
+    close(fd);
+    CHECK(chown(path, 0, gid) == 0, "chown");
+    CHECK(chmod(path, 0640) == 0, "chmod");
+}
+
+static void child() {
+    gid_t groups[] = {1234, 1235};
+    CHECK(setgroups(2, groups) == 0, "setgroups");
+
+    gid_t got[8];
+    CHECK(getgroups(0, NULL) == 2, "getgroups count");
+    errno = 0;
+    CHECK(getgroups(1, got) == -1 && errno == EINVAL, "getgroups short list");
+    CHECK(getgroups(8, got) == 2 && got[0] == 1234 && got[1] == 1235, "getgroups list");
+
+    CHECK(setgid(2000) == 0, "setgid");
+    CHECK(setuid(2000) == 0, "setuid");
+
+    // the group bits apply through the supplementary group
+    int fd = open(GROUP_FILE, O_RDONLY);
+    CHECK(fd >= 0, "read through a supplementary group");
+    close(fd);
+    CHECK(access(GROUP_FILE, R_OK) == 0, "access R_OK");
+    errno = 0;
+    CHECK(open(GROUP_FILE, O_WRONLY) == -1 && errno == EACCES, "group has no write bit");
+    errno = 0;
+    CHECK(open(OTHER_FILE, O_RDONLY) == -1 && errno == EACCES, "not a member of the other group");
+
+    // changing the groups needs CAP_SETGID
+    errno = 0;
+    CHECK(setgroups(0, NULL) == -1 && errno == EPERM, "setgroups without CAP_SETGID");
+    exit(0);
+}
+
+int main() {
+    if (geteuid() != 0) {
+        printf("SKIP: needs root\n");
+        return 0;
+    }
+
+    create(GROUP_FILE, 1234);
+    create(OTHER_FILE, 5678);
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        child();
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    unlink(GROUP_FILE);
+    unlink(OTHER_FILE);
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+
+    // dropping all the supplementary groups
+    CHECK(setgroups(0, NULL) == 0, "setgroups 0");
+    CHECK(getgroups(0, NULL) == 0, "no groups left");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,170 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Leaves the sandbox idle for longer than IOThreadParkMs, then checks that a new
+// connection is still accepted within a bound. With host_stat, the /proc/<pid>/stat
+// of the qvisor process as the sandbox sees it (e.g. through a bind mount of the
+// host /proc), it also checks that the host cpu time of the sandbox over the idle
+// period stays under max_cpu_pct percent (5 by default) of one cpu.
+// usage: idle_wakeup [idle_seconds] [max_accept_ms] [host_stat] [max_cpu_pct]
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define PORT 8091
+#define DEFAULT_MAX_CPU_PCT 5
+
+struct report {
+    long long sent;
+    long long cpuMs;
+};
+
+static long long now_ms() {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000LL + ts.tv_nsec / 1000000;
+}
+
+// host_cpu_ms returns utime + stime of the /proc/<pid>/stat file path in ms
+static long long host_cpu_ms(const char *path) {
+    char buf[1024];
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        perror("open host_stat");
+        return -1;
+    }
+    ssize_t n = read(fd, buf, sizeof(buf) - 1);
+    close(fd);
+    if (n <= 0) {
+        perror("read host_stat");
+        return -1;
+    }
+    buf[n] = 0;
+
+    // the fields after the command name, which may hold spaces and parentheses
+    char *p = strrchr(buf, ')');
+    unsigned long long utime = 0, stime = 0;
+    if (p == NULL ||
+        sscanf(p + 2, "%*c %*d %*d %*d %*d %*d %*u %*u %*u %*u %*u %llu %llu",
+               &utime, &stime) != 2) {
+        printf("can't parse %s\n", path);
+        return -1;
+    }
+
+    return (long long)(utime + stime) * 1000 / sysconf(_SC_CLK_TCK);
+}
+
+int main(int argc, char *argv[]) {
+    int idle = argc > 1 ? atoi(argv[1]) : 5;
+    long long maxMs = argc > 2 ? atoll(argv[2]) : 100;
+    const char *hostStat = argc > 3 ? argv[3] : NULL;
+    long long maxCpuPct = argc > 4 ? atoll(argv[4]) : DEFAULT_MAX_CPU_PCT;
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0) {
+        perror("socket");
+        return 1;
+    }
+
+    int opt = 1;
+    setsockopt(listener, SOL_SOCKET, SO_REUSEADDR, &opt, sizeof(opt));
+
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = htons(PORT);
+
+    if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        listen(listener, 1) < 0) {
+        perror("bind/listen");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        struct report r = {0, 0};
+        long long cpuStart = 0;
+        // give the parent the time to block in accept before the idle period
+        sleep(1);
+        if (hostStat != NULL && (cpuStart = host_cpu_ms(hostStat)) < 0)
+            exit(1);
+
+        sleep(idle);
+        if (hostStat != NULL) {
+            long long cpuEnd = host_cpu_ms(hostStat);
+            if (cpuEnd < 0)
+                exit(1);
+            r.cpuMs = cpuEnd - cpuStart;
+        }
+
+        r.sent = now_ms();
+        int fd = socket(AF_INET, SOCK_STREAM, 0);
+        if (connect(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+            perror("connect");
+            exit(1);
+        }
+        if (write(fd, &r, sizeof(r)) != sizeof(r)) {
+            perror("write");
+            exit(1);
+        }
+        close(fd);
+        exit(0);
+    }
+
+    int conn = accept(listener, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    struct report r;
+    if (read(conn, &r, sizeof(r)) != sizeof(r)) {
+        perror("read");
+        return 1;
+    }
+    long long delay = now_ms() - r.sent;
+
+    int status = 0;
+    waitpid(pid, &status, 0);
+    close(conn);
+    close(listener);
+
+    printf("wakeup after %d s idle took %lld ms\n", idle, delay);
+    if (delay > maxMs || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("idle wakeup test fail\n");
+        return 1;
+    }
+
+    if (hostStat != NULL) {
+        printf("host cpu time over %d s idle: %lld ms\n", idle, r.cpuMs);
+        if (r.cpuMs * 100 > idle * 1000LL * maxCpuPct) {
+            printf("idle wakeup test fail: host cpu over %lld%% of one cpu\n",
+                   maxCpuPct);
+            return 1;
+        }
+    }
+
+    printf("idle wakeup test pass\n");
+    return 0;
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// An illegal instruction or a privileged io instruction in a guest process kills
+// only that process (SIGILL, SIGSEGV), the sandbox keeps running.
+
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static void ud2(void) { __asm__ volatile("ud2"); }
+
+static void outb(void) { __asm__ volatile("outb %%al, $0x80" ::"a"(0)); }
+
+static int run(void (*fn)(void)) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        fn();
+        _exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status), "child isn't killed by a signal");
+    return WTERMSIG(status);
+}
+
+int main(void) {
+    CHECK(run(ud2) == SIGILL, "ud2 doesn't raise SIGILL");
+    CHECK(run(outb) == SIGSEGV, "outb doesn't raise SIGSEGV");
+
+    // the sandbox is still alive
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork after the crashes");
+    if (pid == 0) {
+        _exit(7);
+    }
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 7, "exit status");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,74 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Disk IOPS throttle, run with the write IOPS of /etc/quark/config.json, e.g. for
+//   "DiskWriteIops": 200
+// run "iops_throttle 200". 4 seconds worth of small writes and fsyncs have to take
+// about 3 seconds, as the first second goes through as the burst.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static double now(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec + ts.tv_nsec / 1e9;
+}
+
+int main(int argc, char *argv[]) {
+    if (argc < 2) {
+        printf("usage: %s <write iops>\n", argv[0]);
+        return 1;
+    }
+
+    long iops = atol(argv[1]);
+    CHECK(iops > 0, "iops");
+
+    char path[] = "/tmp/iops_throttle_XXXXXX";
+    int fd = mkstemp(path);
+    CHECK(fd >= 0, "mkstemp");
+    unlink(path);
+
+    long total = 4 * iops;
+    double start = now();
+    for (long i = 0; i < total; i++) {
+        if (i % 8 == 7) {
+            CHECK(fsync(fd) == 0, "fsync");
+        } else {
+            CHECK(write(fd, "x", 1) == 1, "write");
+        }
+    }
+    double elapsed = now() - start;
+
+    double expected = (double)(total - iops) / iops;
+    printf("%ld ops in %.2fs, %.0f ops/sec\n", total, elapsed, total / elapsed);
+    CHECK(elapsed > expected * 0.9, "op rate over the limit");
+    CHECK(elapsed < expected * 1.5 + 1, "op rate far under the limit");
+
+    close(fd);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// readv/writev reject more than IOV_MAX iovecs with EINVAL and IOV_MAX works.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+#define NIOV (IOV_MAX + 1)
+
+static char data[NIOV];
+static struct iovec iov[NIOV];
+
+static void setIov(char *buf) {
+    for (int i = 0; i < NIOV; i++) {
+        iov[i].iov_base = buf + i;
+        iov[i].iov_len = 1;
+    }
+}
+
+int main() {
+    const char *path = "/tmp/iov_max_test";
+    char buf[NIOV];
+
+    for (int i = 0; i < NIOV; i++) {
+        data[i] = 'a' + i % 26;
+    }
+
+    unlink(path);
+    int fd = open(path, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    CHECK(fd >= 0, "open");
+
+    // one iovec over IOV_MAX is rejected before anything is written
+    setIov(data);
+    errno = 0;
+    CHECK(writev(fd, iov, NIOV) == -1 && errno == EINVAL, "writev IOV_MAX + 1");
+    CHECK(pwritev(fd, iov, NIOV, 0) == -1 && errno == EINVAL, "pwritev IOV_MAX + 1");
+    CHECK(lseek(fd, 0, SEEK_END) == 0, "nothing written");
+
+    CHECK(writev(fd, iov, IOV_MAX) == IOV_MAX, "writev IOV_MAX");
+    CHECK(write(fd, data + IOV_MAX, 1) == 1, "write");
+
+    memset(buf, 0, sizeof(buf));
+    setIov(buf);
+    errno = 0;
+    CHECK(preadv(fd, iov, NIOV, 0) == -1 && errno == EINVAL, "preadv IOV_MAX + 1");
+    CHECK(lseek(fd, 0, SEEK_SET) == 0, "lseek");
+    CHECK(readv(fd, iov, NIOV) == -1 && errno == EINVAL, "readv IOV_MAX + 1");
+    CHECK(readv(fd, iov, IOV_MAX) == IOV_MAX, "readv IOV_MAX");
+    CHECK(memcmp(buf, data, IOV_MAX) == 0, "readv data");
+    CHECK(preadv(fd, iov, IOV_MAX, 1) == IOV_MAX, "preadv IOV_MAX");
+    CHECK(memcmp(buf, data + 1, IOV_MAX) == 0, "preadv data");
+    close(fd);
+    unlink(path);
+
+    int p[2];
+    CHECK(pipe(p) == 0, "pipe");
+    setIov(data);
+    errno = 0;
+    CHECK(writev(p[1], iov, NIOV) == -1 && errno == EINVAL, "pipe writev IOV_MAX + 1");
+    CHECK(writev(p[1], iov, IOV_MAX) == IOV_MAX, "pipe writev IOV_MAX");
+    memset(buf, 0, sizeof(buf));
+    setIov(buf);
+    CHECK(readv(p[0], iov, IOV_MAX) == IOV_MAX, "pipe readv IOV_MAX");
+    CHECK(memcmp(buf, data, IOV_MAX) == 0, "pipe data");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,118 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// F_SETLEASE, F_GETLEASE and the lease break between two processes of the sandbox.
+// The leases are guest-only, a host process opening the file doesn't break them.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define TEST_FILE "/tmp/lease_test"
+
+static volatile sig_atomic_t broken = 0;
+
+static void on_sigio(int sig) {
+    broken = 1;
+}
+
+int main() {
+    int fd = open(TEST_FILE, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    close(fd);
+
+    signal(SIGIO, on_sigio);
+
+    sigset_t block, orig;
+    sigemptyset(&block);
+    sigaddset(&block, SIGIO);
+    sigprocmask(SIG_BLOCK, &block, &orig);
+
+    fd = open(TEST_FILE, O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    if (fcntl(fd, F_SETLEASE, F_RDLCK) < 0) {
+        perror("F_SETLEASE");
+        return 1;
+    }
+    if (fcntl(fd, F_GETLEASE) != F_RDLCK) {
+        fprintf(stderr, "F_GETLEASE doesn't return F_RDLCK\n");
+        return 1;
+    }
+
+    // a read lease doesn't conflict with another reader
+    int rfd = open(TEST_FILE, O_RDONLY);
+    sigset_t pending;
+    sigpending(&pending);
+    if (rfd < 0 || sigismember(&pending, SIGIO)) {
+        fprintf(stderr, "a read open broke the read lease\n");
+        return 1;
+    }
+    close(rfd);
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        // a nonblocking conflicting open starts the break and fails
+        int wfd = open(TEST_FILE, O_WRONLY | O_NONBLOCK);
+        if (wfd >= 0 || errno != EWOULDBLOCK) {
+            fprintf(stderr, "nonblocking open: fd %d errno %d\n", wfd, errno);
+            exit(1);
+        }
+
+        // a blocking one waits until the lease is released
+        wfd = open(TEST_FILE, O_WRONLY);
+        if (wfd < 0) {
+            perror("child open");
+            exit(1);
+        }
+        exit(0);
+    }
+
+    while (!broken) {
+        sigsuspend(&orig);
+    }
+
+    // while being broken, the lease reports the type it's broken to
+    if (fcntl(fd, F_GETLEASE) != F_UNLCK) {
+        fprintf(stderr, "F_GETLEASE during a break: %d\n", fcntl(fd, F_GETLEASE));
+        return 1;
+    }
+
+    if (fcntl(fd, F_SETLEASE, F_UNLCK) < 0) {
+        perror("F_SETLEASE F_UNLCK");
+        return 1;
+    }
+
+    int status;
+    waitpid(pid, &status, 0);
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        return 1;
+    }
+
+    close(fd);
+    unlink(TEST_FILE);
+    printf("lease works\n");
+    return 0;
+}
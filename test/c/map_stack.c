@@ -0,0 +1,91 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// A MAP_STACK region works as a thread stack, and a MAP_GROWSDOWN mapping
+// grows downward when the page right below it is touched.
+
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define STACK_SIZE (1 << 20)
+
+static void *thread_fn(void *arg) {
+  char buf[64 * 1024];
+  memset(buf, 1, sizeof(buf));
+  *(int *)arg = buf[100];
+  return NULL;
+}
+
+int main() {
+  long page = sysconf(_SC_PAGESIZE);
+
+  void *stack = mmap(NULL, STACK_SIZE, PROT_READ | PROT_WRITE,
+                     MAP_PRIVATE | MAP_ANONYMOUS | MAP_STACK, -1, 0);
+  if (stack == MAP_FAILED) {
+    perror("mmap MAP_STACK");
+    goto fail;
+  }
+
+  pthread_attr_t attr;
+  pthread_attr_init(&attr);
+  pthread_attr_setstack(&attr, stack, STACK_SIZE);
+
+  int result = 0;
+  pthread_t t;
+  if (pthread_create(&t, &attr, thread_fn, &result) != 0) {
+    printf("pthread_create on MAP_STACK stack failed\n");
+    goto fail;
+  }
+  pthread_join(t, NULL);
+  if (result != 1) {
+    printf("thread on MAP_STACK stack returned %d\n", result);
+    goto fail;
+  }
+
+  // place the mapping at the top of a free hole so the guard gap below it is
+  // clear of other mappings
+  char *hole = mmap(NULL, 8 << 20, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+  if (hole == MAP_FAILED) {
+    perror("mmap hole");
+    goto fail;
+  }
+  munmap(hole, 8 << 20);
+
+  char *gd = mmap(hole + (8 << 20) - 4 * page, 4 * page, PROT_READ | PROT_WRITE,
+                  MAP_PRIVATE | MAP_ANONYMOUS | MAP_GROWSDOWN | MAP_FIXED, -1, 0);
+  if (gd == MAP_FAILED) {
+    perror("mmap MAP_GROWSDOWN");
+    goto fail;
+  }
+
+  gd[0] = 1;
+  // touching the page below the mapping extends it
+  gd[-1] = 2;
+  if (gd[-1] != 2) {
+    printf("MAP_GROWSDOWN mapping didn't grow\n");
+    goto fail;
+  }
+
+  printf("map stack test pass\n");
+  return 0;
+
+fail:
+  printf("map stack test fail\n");
+  return 1;
+}
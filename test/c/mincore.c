@@ -0,0 +1,90 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// mincore reports touched pages of a file mapping as resident, leaves
+// untouched anonymous pages out, and fails on misaligned or unmapped ranges.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define PAGES 32
+
+int main() {
+  long page = sysconf(_SC_PAGESIZE);
+  unsigned char vec[PAGES];
+
+  int fd = open("/tmp/mincore_file", O_CREAT | O_TRUNC | O_RDWR, 0644);
+  if (fd < 0 || ftruncate(fd, PAGES * page) != 0) {
+    perror("create file");
+    goto fail;
+  }
+
+  char *file = mmap(NULL, PAGES * page, PROT_READ, MAP_SHARED, fd, 0);
+  if (file == MAP_FAILED) {
+    perror("mmap file");
+    goto fail;
+  }
+
+  volatile char c = file[0] + file[16 * page];
+  (void)c;
+  if (mincore(file, PAGES * page, vec) != 0) {
+    perror("mincore file");
+    goto fail;
+  }
+  if (!(vec[0] & 1) || !(vec[16] & 1)) {
+    printf("touched file pages are not resident: %d %d\n", vec[0], vec[16]);
+    goto fail;
+  }
+
+  char *anon = mmap(NULL, PAGES * page, PROT_READ | PROT_WRITE,
+                    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+  anon[0] = 1;
+  anon[16 * page] = 1;
+  if (mincore(anon, PAGES * page, vec) != 0) {
+    perror("mincore anon");
+    goto fail;
+  }
+  if (!(vec[0] & 1) || !(vec[16] & 1) || (vec[12] & 1) || (vec[PAGES - 1] & 1)) {
+    printf("anon residency is wrong: %d %d %d %d\n", vec[0], vec[16], vec[12],
+           vec[PAGES - 1]);
+    goto fail;
+  }
+
+  if (mincore(anon + 1, page, vec) != -1 || errno != EINVAL) {
+    printf("misaligned mincore should fail with EINVAL\n");
+    goto fail;
+  }
+
+  munmap(anon + 8 * page, page);
+  if (mincore(anon, PAGES * page, vec) != -1 || errno != ENOMEM) {
+    printf("mincore over a hole should fail with ENOMEM\n");
+    goto fail;
+  }
+
+  munmap(file, PAGES * page);
+  close(fd);
+  unlink("/tmp/mincore_file");
+  printf("mincore test pass\n");
+  return 0;
+
+fail:
+  printf("mincore test fail\n");
+  return 1;
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// mlock makes the range resident and accounts it in VmLck, MADV_DONTNEED on a
+// locked range fails with EINVAL, and munlock drops the accounting again.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define PAGES 16
+
+static long vm_lck_kb() {
+  FILE *f = fopen("/proc/self/status", "r");
+  char line[256];
+  long kb = -1;
+  while (f && fgets(line, sizeof(line), f)) {
+    if (sscanf(line, "VmLck: %ld kB", &kb) == 1)
+      break;
+  }
+  if (f)
+    fclose(f);
+  return kb;
+}
+
+int main() {
+  long page = sysconf(_SC_PAGESIZE);
+  unsigned char vec[PAGES];
+
+  char *p = mmap(NULL, PAGES * page, PROT_READ | PROT_WRITE,
+                 MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+  if (p == MAP_FAILED) {
+    perror("mmap");
+    goto fail;
+  }
+
+  long before = vm_lck_kb();
+  if (mlock(p, PAGES * page) != 0) {
+    perror("mlock");
+    goto fail;
+  }
+
+  if (vm_lck_kb() - before != PAGES * page / 1024) {
+    printf("VmLck grew by %ld kB\n", vm_lck_kb() - before);
+    goto fail;
+  }
+
+  mincore(p, PAGES * page, vec);
+  for (int i = 0; i < PAGES; i++) {
+    if (!(vec[i] & 1)) {
+      printf("locked page %d is not resident\n", i);
+      goto fail;
+    }
+  }
+
+  if (madvise(p, PAGES * page, MADV_DONTNEED) != -1 || errno != EINVAL) {
+    printf("MADV_DONTNEED on locked memory should fail with EINVAL\n");
+    goto fail;
+  }
+
+  if (munlock(p, PAGES * page) != 0 || vm_lck_kb() != before) {
+    printf("munlock didn't drop VmLck\n");
+    goto fail;
+  }
+
+  if (madvise(p, PAGES * page, MADV_DONTNEED) != 0) {
+    perror("madvise after munlock");
+    goto fail;
+  }
+
+  printf("mlock test pass\n");
+  return 0;
+
+fail:
+  printf("mlock test fail\n");
+  return 1;
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// MAP_FIXED_NOREPLACE maps at the exact address when the range is free and fails
+// with EEXIST, leaving the old mapping alone, when any page of it is mapped.
+
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#ifndef MAP_FIXED_NOREPLACE
+#define MAP_FIXED_NOREPLACE 0x100000
+#endif
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+int main(void) {
+    long page = sysconf(_SC_PAGESIZE);
+
+    // reserve 4 pages, keep the middle 2 mapped and free the others
+    char *base = mmap(NULL, 4 * page, PROT_READ | PROT_WRITE,
+                      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(base != MAP_FAILED, "mmap");
+    CHECK(munmap(base, page) == 0, "munmap first page");
+    CHECK(munmap(base + 3 * page, page) == 0, "munmap last page");
+    char *mapped = base + page;
+    memset(mapped, 'a', 2 * page);
+
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE;
+    void *p = mmap(mapped, 2 * page, PROT_READ | PROT_WRITE, flags, -1, 0);
+    CHECK(p == MAP_FAILED && errno == EEXIST, "mapping over a mapping");
+
+    p = mmap(base, 2 * page, PROT_READ | PROT_WRITE, flags, -1, 0);
+    CHECK(p == MAP_FAILED && errno == EEXIST, "mapping over a part of a mapping");
+    CHECK(mapped[0] == 'a' && mapped[2 * page - 1] == 'a', "old mapping is clobbered");
+
+    p = mmap(base + 3 * page, page, PROT_READ | PROT_WRITE, flags, -1, 0);
+    CHECK(p == base + 3 * page, "mapping at a free address");
+    CHECK(((char *)p)[0] == 0, "new mapping isn't zeroed");
+    ((char *)p)[0] = 'b';
+
+    p = mmap(base + 1, page, PROT_READ, flags, -1, 0);
+    CHECK(p == MAP_FAILED && errno == EINVAL, "unaligned address");
+
+    printf("PASS\n");
+    return 0;
+}
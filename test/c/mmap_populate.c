@@ -0,0 +1,79 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// MAP_POPULATE makes the pages of a file and an anonymous mapping resident before
+// mmap returns, mincore reports them without touching the memory.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+#define PAGES 64
+
+static void check_resident(void *addr, long page, const char *msg) {
+    unsigned char vec[PAGES];
+    CHECK(mincore(addr, PAGES * page, vec) == 0, "mincore");
+    for (int i = 0; i < PAGES; i++) {
+        if (!(vec[i] & 1)) {
+            printf("FAIL: %s: page %d isn't resident\n", msg, i);
+            exit(1);
+        }
+    }
+}
+
+int main(void) {
+    long page = sysconf(_SC_PAGESIZE);
+    char path[] = "/tmp/mmap_populate_XXXXXX";
+    int fd = mkstemp(path);
+    CHECK(fd >= 0, "mkstemp");
+    unlink(path);
+
+    char *buf = malloc(PAGES * page);
+    CHECK(buf != NULL, "malloc");
+    memset(buf, 'q', PAGES * page);
+    CHECK(write(fd, buf, PAGES * page) == PAGES * page, "write");
+
+    char *p = mmap(NULL, PAGES * page, PROT_READ, MAP_SHARED | MAP_POPULATE, fd, 0);
+    CHECK(p != MAP_FAILED, "mmap file");
+    check_resident(p, page, "shared file mapping");
+    CHECK(p[0] == 'q' && p[PAGES * page - 1] == 'q', "file content");
+
+    char *q = mmap(NULL, PAGES * page, PROT_READ | PROT_WRITE,
+                   MAP_PRIVATE | MAP_POPULATE, fd, 0);
+    CHECK(q != MAP_FAILED, "mmap private file");
+    check_resident(q, page, "private file mapping");
+    q[0] = 'w';
+    CHECK(p[0] == 'q', "private write reaches the file");
+
+    char *a = mmap(NULL, PAGES * page, PROT_READ | PROT_WRITE,
+                   MAP_PRIVATE | MAP_ANONYMOUS | MAP_POPULATE, -1, 0);
+    CHECK(a != MAP_FAILED, "mmap anonymous");
+    check_resident(a, page, "anonymous mapping");
+    CHECK(a[0] == 0 && a[PAGES * page - 1] == 0, "anonymous pages aren't zeroed");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Two processes mapping the same file MAP_SHARED, each through its own open file,
+// see each other's stores, and read() sees them too. A write() before the mmap is
+// visible through the mapping, and the file grown through a hard link can be
+// accessed through a mapping made before.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+int main(void) {
+    long page = sysconf(_SC_PAGESIZE);
+    char path[] = "/tmp/mmap_shared_XXXXXX";
+    int fd = mkstemp(path);
+    CHECK(fd >= 0, "mkstemp");
+    CHECK(write(fd, "written", 7) == 7, "write");
+    CHECK(ftruncate(fd, page) == 0, "ftruncate");
+
+    // map 2 pages, the second one is past the end of the file for now
+    char *p = mmap(NULL, 2 * page, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    CHECK(p != MAP_FAILED, "mmap");
+    CHECK(memcmp(p, "written", 7) == 0, "write() isn't visible in the mapping");
+
+    int toChild[2], toParent[2];
+    CHECK(pipe(toChild) == 0 && pipe(toParent) == 0, "pipe");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        close(toChild[1]);
+        close(toParent[0]);
+        int cfd = open(path, O_RDWR);
+        CHECK(cfd >= 0, "child open");
+        char *c = mmap(NULL, page, PROT_READ | PROT_WRITE, MAP_SHARED, cfd, 0);
+        CHECK(c != MAP_FAILED, "child mmap");
+
+        strcpy(c + 100, "from child");
+        CHECK(write(toParent[1], "1", 1) == 1, "child notify");
+
+        char b;
+        CHECK(read(toChild[0], &b, 1) == 1, "child wait");
+        CHECK(strcmp(c + 200, "from parent") == 0, "parent store isn't visible in child");
+
+        // grow the file through a hard link
+        char linkPath[64];
+        snprintf(linkPath, sizeof(linkPath), "%s.link", path);
+        CHECK(link(path, linkPath) == 0, "link");
+        int lfd = open(linkPath, O_RDWR);
+        CHECK(lfd >= 0, "open link");
+        CHECK(pwrite(lfd, "grown", 5, page) == 5, "pwrite link");
+        close(lfd);
+        unlink(linkPath);
+        CHECK(write(toParent[1], "2", 1) == 1, "child notify 2");
+        _exit(0);
+    }
+
+    close(toChild[0]);
+    close(toParent[1]);
+    char b;
+    CHECK(read(toParent[0], &b, 1) == 1, "parent wait");
+    CHECK(strcmp(p + 100, "from child") == 0, "child store isn't visible in parent");
+
+    char buf[16];
+    CHECK(pread(fd, buf, 10, 100) == 10 && memcmp(buf, "from child", 10) == 0,
+          "child store isn't visible to read()");
+
+    strcpy(p + 200, "from parent");
+    CHECK(write(toChild[1], "1", 1) == 1, "parent notify");
+
+    CHECK(read(toParent[0], &b, 1) == 1, "parent wait 2");
+    CHECK(memcmp(p + page, "grown", 5) == 0, "grown part isn't visible");
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+
+    unlink(path);
+    printf("PASS\n");
+    return 0;
+}
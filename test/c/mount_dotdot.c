@@ -0,0 +1,101 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define PARENT "/tmp/mount_dotdot_test"
+#define MOUNT_POINT PARENT "/sub"
+
+static int same(struct stat *a, struct stat *b) {
+    return a->st_dev == b->st_dev && a->st_ino == b->st_ino;
+}
+
+int main() {
+    struct stat parent, covered, root, st;
+    char cwd[256];
+
+    mkdir(PARENT, 0755);
+    mkdir(MOUNT_POINT, 0755);
+    CHECK(stat(PARENT, &parent) == 0, "stat parent");
+    CHECK(stat(MOUNT_POINT, &covered) == 0, "stat covered dir");
+
+    if (mount("tmpfs", MOUNT_POINT, "tmpfs", 0, NULL) < 0) {
+        if (errno == EPERM) {
+            printf("mount_dotdot: no permission to mount, skip\n");
+            return 0;
+        }
+        CHECK(0, "mount");
+    }
+
+    // walking into the mount point lands on the mounted root
+    CHECK(stat(MOUNT_POINT, &root) == 0, "stat mount root");
+    CHECK(!same(&root, &covered), "mount point walks onto the mounted root");
+    CHECK(stat(PARENT "/sub/.", &st) == 0 && same(&st, &root), "sub/. is the mounted root");
+    CHECK(stat(PARENT "/sub/../sub", &st) == 0 && same(&st, &root), "sub/../sub is the mounted root");
+
+    // ".." out of the mount root is the parent of the mount point
+    CHECK(stat(PARENT "/sub/..", &st) == 0 && same(&st, &parent), "sub/.. is the parent");
+
+    CHECK(chdir(MOUNT_POINT) == 0, "chdir into the mount");
+    CHECK(stat(".", &st) == 0 && same(&st, &root), "cwd is the mounted root");
+    CHECK(stat("..", &st) == 0 && same(&st, &parent), ".. from the cwd is the parent");
+    CHECK(mkdir("dir", 0755) == 0, "mkdir in the mount");
+    CHECK(stat("dir/../..", &st) == 0 && same(&st, &parent), "dir/../.. crosses the mount");
+
+    int fd = open(".", O_RDONLY | O_DIRECTORY);
+    CHECK(fd >= 0, "open the mount root");
+    CHECK(fstatat(fd, "..", &st, 0) == 0 && same(&st, &parent), "fstatat .. from the mount root");
+
+    CHECK(chdir("..") == 0, "cd ..");
+    CHECK(getcwd(cwd, sizeof(cwd)) != NULL && strcmp(cwd, PARENT) == 0, "cwd after cd ..");
+    CHECK(stat(".", &st) == 0 && same(&st, &parent), "cd .. leaves the mount");
+
+    CHECK(fchdir(fd) == 0, "fchdir back into the mount");
+    CHECK(getcwd(cwd, sizeof(cwd)) != NULL && strcmp(cwd, MOUNT_POINT) == 0, "cwd in the mount");
+    close(fd);
+
+    // a directory can't be mounted over a regular file
+    int file = open(PARENT "/file", O_CREAT | O_WRONLY, 0644);
+    CHECK(file >= 0, "create file");
+    close(file);
+    errno = 0;
+    CHECK(mount("tmpfs", PARENT "/file", "tmpfs", 0, NULL) == -1 && errno == ENOTDIR,
+          "mount over a file");
+
+    CHECK(chdir("/") == 0, "chdir /");
+    CHECK(rmdir(MOUNT_POINT "/dir") == 0, "rmdir in the mount");
+    CHECK(umount(MOUNT_POINT) == 0, "umount");
+    CHECK(stat(MOUNT_POINT, &st) == 0 && same(&st, &covered), "the covered dir is back");
+    unlink(PARENT "/file");
+    rmdir(MOUNT_POINT);
+    rmdir(PARENT);
+
+    printf("PASS\n");
+    return 0;
+}
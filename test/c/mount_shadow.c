@@ -0,0 +1,97 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define MOUNT_POINT "/tmp/mount_shadow_test"
+
+static void create(const char *path, const char *data) {
+    int fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+    CHECK(fd >= 0, "create");
+    CHECK(write(fd, data, strlen(data)) == strlen(data), "write");
+    close(fd);
+}
+
+static void content(const char *path, const char *data) {
+    char buf[64] = {0};
+    int fd = open(path, O_RDONLY);
+    CHECK(fd >= 0, path);
+    CHECK(read(fd, buf, sizeof(buf)) == strlen(data) && strcmp(buf, data) == 0, "content");
+    close(fd);
+}
+
+static int exists(const char *path) {
+    struct stat st;
+    return stat(path, &st) == 0;
+}
+
+int main() {
+    mkdir(MOUNT_POINT, 0755);
+    create(MOUNT_POINT "/orig", "orig");
+
+    if (mount("tmpfs", MOUNT_POINT, "tmpfs", 0, NULL) < 0) {
+        if (errno == EPERM) {
+            printf("mount_shadow: no permission to mount, skip\n");
+            return 0;
+        }
+        CHECK(0, "mount");
+    }
+
+    // the mount hides the original contents
+    errno = 0;
+    CHECK(!exists(MOUNT_POINT "/orig") && errno == ENOENT, "orig hidden by the mount");
+    create(MOUNT_POINT "/first", "first");
+    mkdir(MOUNT_POINT "/dir", 0755);
+    create(MOUNT_POINT "/dir/nested", "nested");
+    content(MOUNT_POINT "/dir/nested", "nested");
+
+    // a second mount on top hides the first one
+    CHECK(mount("tmpfs", MOUNT_POINT, "tmpfs", 0, NULL) == 0, "mount again");
+    CHECK(!exists(MOUNT_POINT "/first"), "first hidden by the second mount");
+    create(MOUNT_POINT "/second", "second");
+
+    CHECK(umount(MOUNT_POINT) == 0, "umount second");
+    CHECK(!exists(MOUNT_POINT "/second"), "no stale entry from the second mount");
+    content(MOUNT_POINT "/first", "first");
+    content(MOUNT_POINT "/dir/nested", "nested");
+
+    // the cached dirents of the mount don't keep it busy
+    CHECK(umount(MOUNT_POINT) == 0, "umount first");
+    CHECK(!exists(MOUNT_POINT "/first"), "no stale entry from the first mount");
+    CHECK(!exists(MOUNT_POINT "/dir/nested"), "no stale nested entry");
+    content(MOUNT_POINT "/orig", "orig");
+
+    errno = 0;
+    CHECK(umount(MOUNT_POINT) == -1 && errno == EINVAL, "umount of a plain directory");
+
+    unlink(MOUNT_POINT "/orig");
+    rmdir(MOUNT_POINT);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// msync(MS_SYNC) writes the stores to a shared file mapping back to the file,
+// also for a range which starts and ends inside the 2MB host chunks backing the
+// mapping. MS_ASYNC and MS_INVALIDATE are checked for their errors.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+#define MB (1024 * 1024)
+#define SIZE (4 * MB)
+
+int main(void) {
+    long page = sysconf(_SC_PAGESIZE);
+    char path[] = "/tmp/msync_XXXXXX";
+    int fd = mkstemp(path);
+    CHECK(fd >= 0, "mkstemp");
+    CHECK(ftruncate(fd, SIZE) == 0, "ftruncate");
+
+    char *p = mmap(NULL, SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    CHECK(p != MAP_FAILED, "mmap");
+
+    memset(p + MB, 'm', 2 * MB);
+    CHECK(msync(p + MB, 2 * MB, MS_SYNC) == 0, "msync MS_SYNC");
+
+    // read it back through another file description
+    int rfd = open(path, O_RDONLY);
+    CHECK(rfd >= 0, "open");
+    char buf[16];
+    CHECK(pread(rfd, buf, sizeof(buf), MB) == sizeof(buf), "pread start");
+    CHECK(memcmp(buf, "mmmmmmmmmmmmmmmm", sizeof(buf)) == 0, "data at 1MB");
+    CHECK(pread(rfd, buf, sizeof(buf), 3 * MB - sizeof(buf)) == sizeof(buf), "pread end");
+    CHECK(memcmp(buf, "mmmmmmmmmmmmmmmm", sizeof(buf)) == 0, "data before 3MB");
+    CHECK(pread(rfd, buf, 1, 3 * MB) == 1 && buf[0] == 0, "data after 3MB");
+
+    p[0] = 'a';
+    CHECK(msync(p, page, MS_ASYNC) == 0, "msync MS_ASYNC");
+    CHECK(msync(p, page, 0) == 0, "msync without flags");
+    CHECK(msync(p, page, MS_SYNC | MS_INVALIDATE) == 0, "msync MS_INVALIDATE");
+    CHECK(pread(rfd, buf, 1, 0) == 1 && buf[0] == 'a', "data at 0");
+
+    errno = 0;
+    CHECK(msync(p, page, MS_SYNC | MS_ASYNC) == -1 && errno == EINVAL, "MS_SYNC|MS_ASYNC");
+    errno = 0;
+    CHECK(msync(p + 1, page, MS_SYNC) == -1 && errno == EINVAL, "unaligned address");
+
+    if (mlock(p, page) == 0) {
+        errno = 0;
+        CHECK(msync(p, page, MS_INVALIDATE) == -1 && errno == EBUSY, "MS_INVALIDATE of locked pages");
+        munlock(p, page);
+    }
+
+    CHECK(munmap(p, SIZE) == 0, "munmap");
+    errno = 0;
+    CHECK(msync(p, page, MS_SYNC) == -1 && errno == ENOMEM, "msync of unmapped memory");
+
+    close(rfd);
+    close(fd);
+    unlink(path);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,90 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <unistd.h>
+#include <sys/stat.h>
+
+#define DIR "/tmp/name_max_test"
+
+static int expectNameTooLong(const char *op, int ret) {
+    if (ret >= 0 || errno != ENAMETOOLONG) {
+        printf("%s with a 300 character name should fail with ENAMETOOLONG, got %d: %s\n",
+               op, ret, strerror(errno));
+        return 1;
+    }
+
+    return 0;
+}
+
+int main() {
+    char longPath[512], okPath[512], file[64];
+    struct stat st;
+
+    mkdir(DIR, 0755);
+    snprintf(file, sizeof(file), "%s/file", DIR);
+    int fd = open(file, O_CREAT | O_RDWR, 0644);
+    if (fd < 0) {
+        printf("create %s fail: %s\n", file, strerror(errno));
+        return 1;
+    }
+    close(fd);
+
+    // NAME_MAX characters is still fine
+    snprintf(okPath, sizeof(okPath), "%s/", DIR);
+    memset(okPath + strlen(okPath), 'b', NAME_MAX);
+    okPath[strlen(DIR) + 1 + NAME_MAX] = 0;
+    fd = open(okPath, O_CREAT | O_RDWR, 0644);
+    if (fd < 0) {
+        printf("create a file with a NAME_MAX name fail: %s\n", strerror(errno));
+        return 1;
+    }
+    close(fd);
+    unlink(okPath);
+
+    snprintf(longPath, sizeof(longPath), "%s/", DIR);
+    memset(longPath + strlen(longPath), 'a', 300);
+    longPath[strlen(DIR) + 1 + 300] = 0;
+
+    int fail = 0;
+    fail |= expectNameTooLong("open O_CREAT", open(longPath, O_CREAT | O_RDWR, 0644));
+    fail |= expectNameTooLong("open", open(longPath, O_RDONLY));
+    fail |= expectNameTooLong("stat", stat(longPath, &st));
+    fail |= expectNameTooLong("mkdir", mkdir(longPath, 0755));
+    fail |= expectNameTooLong("symlink", symlink(file, longPath));
+    fail |= expectNameTooLong("link", link(file, longPath));
+    fail |= expectNameTooLong("rename", rename(file, longPath));
+    fail |= expectNameTooLong("unlink", unlink(longPath));
+
+    // the name is too long as a middle component too
+    strcat(longPath, "/file");
+    fail |= expectNameTooLong("open in a directory", open(longPath, O_CREAT | O_RDWR, 0644));
+
+    if (stat(file, &st) < 0) {
+        printf("%s is lost after the failed rename: %s\n", file, strerror(errno));
+        return 1;
+    }
+
+    if (fail) {
+        return 1;
+    }
+
+    printf("names longer than NAME_MAX fail with ENAMETOOLONG\n");
+    return 0;
+}
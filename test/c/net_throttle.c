@@ -0,0 +1,150 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Network send throttle. "net_throttle pacing" caps one socket at 256KB/s with
+// SO_MAX_PACING_RATE. "net_throttle sandbox <bytes/sec>" checks the sandbox wide
+// rate of /etc/quark/config.json, e.g. for
+//   "NetSendBytesPerSec": 1048576, "NetBurstBytes": 0
+// run "net_throttle sandbox 1048576". Sending 4 seconds worth of data over a TCP
+// connection has to take about 3 seconds, as the first second goes through as the
+// burst. Once the pacing rate is used up, a MSG_DONTWAIT send or a write to a
+// nonblocking socket fails with EAGAIN instead of waiting.
+
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static double now(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec + ts.tv_nsec / 1e9;
+}
+
+// connect_pair returns a connected socket, a child process drains the other end
+static int connect_pair(pid_t *child) {
+    int lfd = socket(AF_INET, SOCK_STREAM, 0);
+    CHECK(lfd >= 0, "socket");
+
+    struct sockaddr_in addr = {0};
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t len = sizeof(addr);
+    CHECK(bind(lfd, (struct sockaddr *)&addr, sizeof(addr)) == 0, "bind");
+    CHECK(getsockname(lfd, (struct sockaddr *)&addr, &len) == 0, "getsockname");
+    CHECK(listen(lfd, 1) == 0, "listen");
+
+    *child = fork();
+    CHECK(*child >= 0, "fork");
+    if (*child == 0) {
+        int cfd = accept(lfd, NULL, NULL);
+        CHECK(cfd >= 0, "accept");
+        char buf[64 * 1024];
+        while (read(cfd, buf, sizeof(buf)) > 0) {
+        }
+        _exit(0);
+    }
+
+    close(lfd);
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    CHECK(fd >= 0, "socket");
+    CHECK(connect(fd, (struct sockaddr *)&addr, sizeof(addr)) == 0, "connect");
+    return fd;
+}
+
+static void send_at(int fd, long rate) {
+    static char buf[16 * 1024];
+    memset(buf, 'x', sizeof(buf));
+
+    long total = 4 * rate;
+    double start = now();
+    for (long sent = 0; sent < total;) {
+        ssize_t n = send(fd, buf, sizeof(buf), 0);
+        CHECK(n > 0, "send");
+        sent += n;
+    }
+    double elapsed = now() - start;
+
+    double expected = (double)(total - rate) / rate;
+    printf("sent %ld bytes in %.2fs, %.0f bytes/sec\n", total, elapsed, total / elapsed);
+    CHECK(elapsed > expected * 0.9, "send rate over the limit");
+    CHECK(elapsed < expected * 1.5 + 1, "send rate far under the limit");
+}
+
+// check_nonblocking puts the socket in debt with a large send, so the nonblocking
+// sends after it find the pacing rate used up
+static void check_nonblocking(int fd) {
+    static char buf[1024 * 1024];
+    memset(buf, 'x', sizeof(buf));
+
+    ssize_t n = send(fd, buf, sizeof(buf), 0);
+    CHECK(n >= 64 * 1024, "large send");
+
+    errno = 0;
+    CHECK(send(fd, buf, 1, MSG_DONTWAIT) < 0 && errno == EAGAIN,
+          "MSG_DONTWAIT send over the pacing rate");
+
+    int flags = fcntl(fd, F_GETFL);
+    CHECK(fcntl(fd, F_SETFL, flags | O_NONBLOCK) == 0, "set O_NONBLOCK");
+    errno = 0;
+    CHECK(write(fd, buf, 1) < 0 && errno == EAGAIN, "nonblocking write over the pacing rate");
+    CHECK(fcntl(fd, F_SETFL, flags) == 0, "clear O_NONBLOCK");
+}
+
+int main(int argc, char *argv[]) {
+    if (argc < 2) {
+        printf("usage: %s pacing | sandbox <send bytes/sec>\n", argv[0]);
+        return 1;
+    }
+
+    pid_t child;
+    int fd = connect_pair(&child);
+
+    if (strcmp(argv[1], "pacing") == 0) {
+        unsigned long rate = 256 * 1024, got = 0;
+        socklen_t len = sizeof(got);
+        CHECK(setsockopt(fd, SOL_SOCKET, SO_MAX_PACING_RATE, &rate, sizeof(rate)) == 0,
+              "set SO_MAX_PACING_RATE");
+        CHECK(getsockopt(fd, SOL_SOCKET, SO_MAX_PACING_RATE, &got, &len) == 0 && got == rate,
+              "get SO_MAX_PACING_RATE");
+        send_at(fd, rate);
+        check_nonblocking(fd);
+    } else if (strcmp(argv[1], "sandbox") == 0 && argc > 2) {
+        send_at(fd, atol(argv[2]));
+    } else {
+        printf("unknown mode %s\n", argv[1]);
+        return 1;
+    }
+
+    close(fd);
+    int status;
+    CHECK(waitpid(child, &status, 0) == child, "waitpid");
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// the tmpfs of the guest and a file of the host-backed root filesystem
+static const char *TEST_FILES[] = {"/dev/shm/noatime_test", "/tmp/noatime_test"};
+
+static struct timespec get_ctime(const char *path) {
+    struct stat st;
+    if (stat(path, &st) < 0) {
+        perror("stat");
+        exit(1);
+    }
+    return st.st_ctim;
+}
+
+static time_t get_atime(const char *path) {
+    struct stat st;
+    if (stat(path, &st) < 0) {
+        perror("stat");
+        exit(1);
+    }
+    return st.st_atime;
+}
+
+static void read_file(const char *path, int flags) {
+    char buf[16];
+    int fd = open(path, O_RDONLY | flags);
+    if (fd < 0) {
+        perror("open");
+        exit(1);
+    }
+    if (read(fd, buf, sizeof(buf)) <= 0) {
+        perror("read");
+        exit(1);
+    }
+    close(fd);
+}
+
+static int check(const char *path) {
+    int fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    if (write(fd, "hello", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+    close(fd);
+
+    // push atime far into the past so that any atime update is visible
+    struct timespec ts[2] = {{.tv_sec = 1000}, {.tv_nsec = UTIME_OMIT}};
+    if (utimensat(AT_FDCWD, path, ts, 0) < 0) {
+        perror("utimensat");
+        return 1;
+    }
+
+    read_file(path, O_NOATIME);
+    if (get_atime(path) != 1000) {
+        fprintf(stderr, "%s: atime advanced on an O_NOATIME read\n", path);
+        return 1;
+    }
+
+    struct timespec ctime = get_ctime(path);
+    read_file(path, 0);
+    if (get_atime(path) == 1000) {
+        fprintf(stderr, "%s: atime didn't advance on a plain read\n", path);
+        return 1;
+    }
+
+    // the atime of a read doesn't change the ctime
+    struct timespec after = get_ctime(path);
+    if (after.tv_sec != ctime.tv_sec || after.tv_nsec != ctime.tv_nsec) {
+        fprintf(stderr, "%s: ctime changed on a read\n", path);
+        return 1;
+    }
+
+    // a read through an O_NOATIME fd keeps the atime while another fd of the file
+    // is open without it
+    if (utimensat(AT_FDCWD, path, ts, 0) < 0) {
+        perror("utimensat");
+        return 1;
+    }
+    int plain = open(path, O_RDONLY);
+    read_file(path, O_NOATIME);
+    if (get_atime(path) != 1000) {
+        fprintf(stderr, "%s: atime advanced on an O_NOATIME read of a shared file\n", path);
+        return 1;
+    }
+    close(plain);
+
+    // O_NOATIME needs the caller to own the file
+    if (geteuid() == 0) {
+        pid_t pid = fork();
+        if (pid == 0) {
+            if (setuid(65534) < 0) {
+                perror("setuid");
+                exit(1);
+            }
+            int fd = open(path, O_RDONLY | O_NOATIME);
+            if (fd >= 0 || errno != EPERM) {
+                fprintf(stderr, "O_NOATIME by a non owner: fd %d errno %d\n", fd, errno);
+                exit(1);
+            }
+            exit(0);
+        }
+
+        int status;
+        waitpid(pid, &status, 0);
+        if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+            return 1;
+        }
+    }
+
+    unlink(path);
+    return 0;
+}
+
+int main() {
+    for (int i = 0; i < (int)(sizeof(TEST_FILES) / sizeof(TEST_FILES[0])); i++) {
+        if (check(TEST_FILES[i]) != 0) {
+            return 1;
+        }
+    }
+
+    printf("noatime works\n");
+    return 0;
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Linux filenames are bytes: create, stat, list, readlink, rename and unlink a file
+// whose name isn't valid UTF-8, and check the name comes back byte for byte.
+
+#include <stdio.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <dirent.h>
+#include <unistd.h>
+#include <sys/stat.h>
+
+#define TEST_DIR "/tmp/non_utf8_name_test"
+
+int main() {
+    // "caf\xe9" is Latin-1, "\xff\xfe" is never valid and "\xef\x9f\xa9" is a valid
+    // char of the private use area
+    const char *names[] = {"caf\xe9", "\xff\xfe\x80", "\xef\x9f\xa9\xe9"};
+    char path[256], path2[256], target[256];
+    struct stat st;
+
+    mkdir(TEST_DIR, 0755);
+    for (int i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+        snprintf(path, sizeof(path), TEST_DIR "/%s", names[i]);
+        int fd = open(path, O_CREAT | O_RDWR, 0644);
+        if (fd < 0) {
+            printf("create %d fail: %s\n", i, strerror(errno));
+            return 1;
+        }
+        close(fd);
+
+        if (stat(path, &st) < 0) {
+            printf("stat %d fail: %s\n", i, strerror(errno));
+            return 1;
+        }
+
+        DIR *dir = opendir(TEST_DIR);
+        struct dirent *de;
+        int found = 0;
+        while ((de = readdir(dir)) != NULL) {
+            if (strcmp(de->d_name, names[i]) == 0) {
+                found = 1;
+            }
+        }
+        closedir(dir);
+        if (!found) {
+            printf("name %d is not in the directory listing\n", i);
+            return 1;
+        }
+
+        snprintf(path2, sizeof(path2), TEST_DIR "/link%d", i);
+        if (symlink(path, path2) < 0) {
+            printf("symlink %d fail: %s\n", i, strerror(errno));
+            return 1;
+        }
+
+        ssize_t n = readlink(path2, target, sizeof(target));
+        if (n != strlen(path) || memcmp(target, path, n) != 0) {
+            printf("readlink %d returns %zd bytes\n", i, n);
+            return 1;
+        }
+        unlink(path2);
+
+        snprintf(path2, sizeof(path2), TEST_DIR "/%s\xe9", names[i]);
+        if (rename(path, path2) < 0 || unlink(path2) < 0) {
+            printf("rename/unlink %d fail: %s\n", i, strerror(errno));
+            return 1;
+        }
+
+        if (stat(path2, &st) == 0 || errno != ENOENT) {
+            printf("%d still exists after unlink\n", i);
+            return 1;
+        }
+    }
+
+    rmdir(TEST_DIR);
+    printf("non utf-8 names work\n");
+    return 0;
+}
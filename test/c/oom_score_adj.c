@@ -0,0 +1,176 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// /proc/self/oom_score_adj is clamped to -1000..1000 and inherited on fork. It
+// can be raised by its owner, lowering it needs CAP_SYS_RESOURCE. With
+// "oom_score_adj oom [limit MB]" and the heuristic overcommit mode, e.g.
+//   "Overcommit": "Heuristic", "MemoryLimit": 256
+// a small child with the adj 1000 is killed by the oom killer before a bigger
+// child with the default adj.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <linux/capability.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static int set_adj(const char *val) {
+    int fd = open("/proc/self/oom_score_adj", O_WRONLY);
+    CHECK(fd >= 0, "open for write");
+    int ret = write(fd, val, strlen(val));
+    close(fd);
+    return ret;
+}
+
+static int get_adj(void) {
+    char buf[32] = {0};
+    int fd = open("/proc/self/oom_score_adj", O_RDONLY);
+    CHECK(fd >= 0, "open for read");
+    CHECK(read(fd, buf, sizeof(buf) - 1) > 0, "read");
+    close(fd);
+    return atoi(buf);
+}
+
+static int has_sys_resource(void) {
+    struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+    struct __user_cap_data_struct data[2];
+    CHECK(syscall(SYS_capget, &hdr, data) == 0, "capget");
+    return (data[0].effective & (1 << CAP_SYS_RESOURCE)) != 0;
+}
+
+static void drop_sys_resource(void) {
+    struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+    struct __user_cap_data_struct data[2];
+    CHECK(syscall(SYS_capget, &hdr, data) == 0, "capget");
+    data[0].effective &= ~(1 << CAP_SYS_RESOURCE);
+    CHECK(syscall(SYS_capset, &hdr, data) == 0, "capset");
+}
+
+static void touch(size_t len) {
+    char *p = mmap(NULL, len, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(p != MAP_FAILED, "mmap");
+    long page = sysconf(_SC_PAGESIZE);
+    for (size_t off = 0; off < len; off += page) {
+        p[off] = 1;
+    }
+}
+
+// range runs in a child, so that the adj of the test process stays 0
+static void range(void) {
+    CHECK(get_adj() == 0, "default adj");
+    errno = 0;
+    CHECK(set_adj("1001") == -1 && errno == EINVAL, "adj over 1000");
+    errno = 0;
+    CHECK(set_adj("-1001") == -1 && errno == EINVAL, "adj under -1000");
+    errno = 0;
+    CHECK(set_adj("abc") == -1 && errno == EINVAL, "adj not a number");
+
+    CHECK(set_adj("500\n") == 4, "set adj");
+    CHECK(get_adj() == 500, "read back adj");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        _exit(get_adj() == 500 ? 0 : 1);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child inherits adj");
+
+    // any decrease needs CAP_SYS_RESOURCE
+    if (has_sys_resource()) {
+        CHECK(set_adj("-1000") > 0, "set adj -1000");
+        CHECK(get_adj() == -1000, "read back adj -1000");
+        drop_sys_resource();
+        CHECK(!has_sys_resource(), "drop CAP_SYS_RESOURCE");
+    }
+
+    CHECK(set_adj("1000") > 0, "raise adj");
+    CHECK(get_adj() == 1000, "read back raised adj");
+    errno = 0;
+    CHECK(set_adj("999") == -1 && errno == EACCES, "lower adj without CAP_SYS_RESOURCE");
+    CHECK(get_adj() == 1000, "adj unchanged");
+    CHECK(set_adj("1000") > 0, "write the same adj");
+
+    _exit(0);
+}
+
+static void oom(size_t limit) {
+    int ready[2];
+    CHECK(pipe(ready) == 0, "pipe");
+
+    pid_t small = fork();
+    CHECK(small >= 0, "fork");
+    if (small == 0) {
+        set_adj("1000");
+        touch(limit / 16);
+        write(ready[1], "x", 1);
+        for (;;) {
+            pause();
+        }
+    }
+
+    char c;
+    CHECK(read(ready[0], &c, 1) == 1, "wait for the small child");
+
+    pid_t big = fork();
+    CHECK(big >= 0, "fork");
+    if (big == 0) {
+        touch(limit + limit / 2);
+        for (;;) {
+            pause();
+        }
+    }
+
+    int status;
+    CHECK(waitpid(small, &status, 0) == small, "waitpid");
+    CHECK(WIFSIGNALED(status) && WTERMSIG(status) == SIGKILL, "adj 1000 child killed first");
+
+    kill(big, SIGKILL);
+    CHECK(waitpid(big, &status, 0) == big, "waitpid");
+}
+
+int main(int argc, char *argv[]) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        range();
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "range checks");
+
+    if (argc > 1 && strcmp(argv[1], "oom") == 0) {
+        oom((size_t)(argc > 2 ? atol(argv[2]) : 256) << 20);
+    }
+
+    printf("PASS\n");
+    return 0;
+}
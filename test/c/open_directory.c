@@ -0,0 +1,80 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// O_DIRECTORY opens a directory, or a symlink to one, and fails with ENOTDIR on
+// a regular file, also with O_PATH. O_CREAT | O_DIRECTORY is EINVAL.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define BASE "/tmp/open_directory"
+
+static void expect(const char *path, int flags, int err) {
+    int fd = open(path, flags, 0644);
+    if (err == 0) {
+        CHECK(fd >= 0, path);
+        close(fd);
+        return;
+    }
+
+    if (fd >= 0 || errno != err) {
+        printf("open(%s, %#x) = %d errno %d, expect errno %d\n", path, flags, fd,
+               fd >= 0 ? 0 : errno, err);
+        exit(1);
+    }
+}
+
+int main() {
+    system("rm -rf " BASE);
+    CHECK(mkdir(BASE, 0755) == 0, "mkdir");
+    int f = open(BASE "/file", O_CREAT | O_WRONLY, 0644);
+    CHECK(f >= 0, "create file");
+    close(f);
+    CHECK(symlink("file", BASE "/filelink") == 0, "symlink file");
+    CHECK(symlink(".", BASE "/dirlink") == 0, "symlink dir");
+
+    expect(BASE, O_RDONLY | O_DIRECTORY, 0);
+    expect(BASE "/dirlink", O_RDONLY | O_DIRECTORY, 0);
+    expect(BASE, O_PATH | O_DIRECTORY, 0);
+
+    expect(BASE "/file", O_RDONLY | O_DIRECTORY, ENOTDIR);
+    expect(BASE "/file", O_RDWR | O_DIRECTORY, ENOTDIR);
+    expect(BASE "/file", O_PATH | O_DIRECTORY, ENOTDIR);
+    expect(BASE "/filelink", O_RDONLY | O_DIRECTORY, ENOTDIR);
+    // the symlink itself isn't a directory either
+    expect(BASE "/dirlink", O_PATH | O_NOFOLLOW | O_DIRECTORY, ENOTDIR);
+
+    expect(BASE "/file", O_RDONLY | O_CREAT | O_DIRECTORY, EINVAL);
+    expect(BASE "/new", O_RDONLY | O_CREAT | O_DIRECTORY, EINVAL);
+    struct stat st;
+    CHECK(stat(BASE "/new", &st) != 0 && errno == ENOENT,
+          "O_CREAT | O_DIRECTORY made a file");
+
+    system("rm -rf " BASE);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,77 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Opens regular files until the open fails. With "UringFixedFile": true the
+// registered file table (16K entries) runs out first, the open must then fail
+// with EMFILE/ENFILE and the sandbox must keep running.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+#define MAX_OPEN (64 * 1024)
+
+int main() {
+  struct rlimit rl;
+  getrlimit(RLIMIT_NOFILE, &rl);
+  rl.rlim_cur = rl.rlim_max;
+  setrlimit(RLIMIT_NOFILE, &rl);
+
+  const char *path = "/tmp/open_exhaust.dat";
+  int fd = open(path, O_CREAT | O_RDWR, 0644);
+  if (fd < 0) {
+    perror("open");
+    return 1;
+  }
+
+  int *fds = malloc(sizeof(int) * MAX_OPEN);
+  int count = 0;
+  int err = 0;
+  while (count < MAX_OPEN) {
+    int f = open(path, O_RDONLY);
+    if (f < 0) {
+      err = errno;
+      break;
+    }
+    fds[count++] = f;
+  }
+
+  printf("opened %d files, last errno %d (%s)\n", count, err, strerror(err));
+  if (count < MAX_OPEN && err != EMFILE && err != ENFILE) {
+    printf("open exhaust test fail\n");
+    return 1;
+  }
+
+  for (int i = 0; i < count; i++)
+    close(fds[i]);
+
+  // the table has room again
+  int f = open(path, O_RDONLY);
+  if (f < 0) {
+    perror("reopen");
+    printf("open exhaust test fail\n");
+    return 1;
+  }
+
+  close(f);
+  close(fd);
+  unlink(path);
+  free(fds);
+  printf("open exhaust test pass\n");
+  return 0;
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// With stdin closed, open and creat return fd 0 as the lowest free fd, and the
+// file works through it.
+
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define FILE_PATH "/tmp/open_fd0"
+
+int main() {
+    unlink(FILE_PATH);
+
+    close(0);
+    int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_EXCL, 0644);
+    CHECK(fd == 0, "create at fd 0");
+    CHECK(write(fd, "data", 4) == 4, "write fd 0");
+    close(fd);
+
+    fd = open(FILE_PATH, O_RDONLY);
+    CHECK(fd == 0, "open at fd 0");
+    char buf[4];
+    CHECK(read(fd, buf, sizeof(buf)) == 4 && memcmp(buf, "data", 4) == 0,
+          "read fd 0");
+    close(fd);
+
+    unlink(FILE_PATH);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,81 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// open follows a symlink to a file unless O_NOFOLLOW is given, then the final
+// symlink is ELOOP while one in the middle of the path is still followed.
+// O_PATH | O_NOFOLLOW opens the symlink itself.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define BASE "/tmp/open_nofollow"
+
+static void read_data(const char *path, int flags) {
+    char buf[16] = {0};
+    int fd = open(path, flags);
+    CHECK(fd >= 0, path);
+    CHECK(read(fd, buf, sizeof(buf)) == 4 && memcmp(buf, "data", 4) == 0,
+          "read through the symlink");
+    close(fd);
+}
+
+int main() {
+    system("rm -rf " BASE);
+    CHECK(mkdir(BASE, 0755) == 0, "mkdir");
+    CHECK(mkdir(BASE "/dir", 0755) == 0, "mkdir dir");
+    int f = open(BASE "/dir/file", O_CREAT | O_WRONLY, 0644);
+    CHECK(f >= 0 && write(f, "data", 4) == 4, "create file");
+    close(f);
+    CHECK(symlink("dir/file", BASE "/link") == 0, "symlink");
+    CHECK(symlink("dir", BASE "/dirlink") == 0, "symlink dir");
+    CHECK(symlink("dir/new", BASE "/dangling") == 0, "symlink dangling");
+
+    read_data(BASE "/link", O_RDONLY);
+    read_data(BASE "/dirlink/file", O_RDONLY | O_NOFOLLOW);
+
+    int fd = open(BASE "/link", O_RDONLY | O_NOFOLLOW);
+    CHECK(fd < 0 && errno == ELOOP, "O_NOFOLLOW on a symlink");
+    fd = open(BASE "/link", O_RDWR | O_CREAT | O_NOFOLLOW, 0644);
+    CHECK(fd < 0 && errno == ELOOP, "O_CREAT | O_NOFOLLOW on a symlink");
+
+    struct stat st;
+    fd = open(BASE "/link", O_PATH | O_NOFOLLOW);
+    CHECK(fd >= 0, "O_PATH | O_NOFOLLOW");
+    CHECK(fstat(fd, &st) == 0 && S_ISLNK(st.st_mode), "fstat of the link");
+    close(fd);
+
+    // O_CREAT without O_NOFOLLOW creates the target of a dangling symlink
+    fd = open(BASE "/dangling", O_WRONLY | O_CREAT, 0644);
+    CHECK(fd >= 0, "create through the symlink");
+    close(fd);
+    CHECK(lstat(BASE "/dir/new", &st) == 0 && S_ISREG(st.st_mode), "target");
+
+    system("rm -rf " BASE);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Overcommit policy of the sandbox, run with the mode set in /etc/quark/config.json
+// and the memory limit in MB (256 if not given), e.g. for
+//   "Overcommit": "Strict", "MemoryLimit": 256
+// run "overcommit strict 256". In the strict mode a private writable mapping over
+// the limit fails with ENOMEM. In the heuristic mode it succeeds, and a child
+// which touches more than the limit is killed by the oom killer.
+
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static void strict(size_t limit) {
+    errno = 0;
+    void *p = mmap(NULL, 2 * limit, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(p == MAP_FAILED && errno == ENOMEM, "mmap over the limit");
+
+    // reserved address space isn't charged until it becomes writable
+    p = mmap(NULL, 2 * limit, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(p != MAP_FAILED, "mmap PROT_NONE");
+    errno = 0;
+    CHECK(mprotect(p, 2 * limit, PROT_READ | PROT_WRITE) == -1 && errno == ENOMEM,
+          "mprotect over the limit");
+    CHECK(mprotect(p, limit / 4, PROT_READ | PROT_WRITE) == 0, "mprotect part");
+    memset(p, 1, limit / 4);
+    CHECK(munmap(p, 2 * limit) == 0, "munmap");
+
+    // unmapping gives the charge back
+    for (int i = 0; i < 8; i++) {
+        p = mmap(NULL, limit / 2, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+        CHECK(p != MAP_FAILED, "mmap under the limit");
+        CHECK(munmap(p, limit / 2) == 0, "munmap");
+    }
+}
+
+static void heuristic(size_t limit) {
+    void *p = mmap(NULL, 2 * limit, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(p != MAP_FAILED, "mmap over the limit");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        long page = sysconf(_SC_PAGESIZE);
+        for (size_t off = 0; off < 2 * limit; off += page) {
+            ((char *)p)[off] = 1;
+        }
+        _exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && WTERMSIG(status) == SIGKILL, "child killed by the oom killer");
+    CHECK(munmap(p, 2 * limit) == 0, "munmap");
+}
+
+int main(int argc, char *argv[]) {
+    if (argc < 2) {
+        printf("usage: %s strict|heuristic [limit MB]\n", argv[0]);
+        return 1;
+    }
+
+    size_t limit = (size_t)(argc > 2 ? atol(argv[2]) : 256) << 20;
+    if (strcmp(argv[1], "strict") == 0) {
+        strict(limit);
+    } else if (strcmp(argv[1], "heuristic") == 0) {
+        heuristic(limit);
+    } else {
+        printf("unknown mode %s\n", argv[1]);
+        return 1;
+    }
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Touching new anonymous pages counts minor faults, reading a cold file mapping
+// counts major faults, in getrusage and in /proc/self/stat.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define PAGES 1024
+#define TESTFILE "/tmp/page_faults_file"
+
+// statFaults reads minflt and majflt, the 10th and 12th fields of /proc/self/stat
+static void statFaults(long *minflt, long *majflt) {
+    char buf[1024];
+    int fd = open("/proc/self/stat", O_RDONLY);
+    CHECK(fd >= 0, "open /proc/self/stat");
+    ssize_t n = read(fd, buf, sizeof(buf) - 1);
+    CHECK(n > 0, "read /proc/self/stat");
+    buf[n] = 0;
+    close(fd);
+
+    char *p = strrchr(buf, ')');
+    CHECK(p != NULL, "parse /proc/self/stat");
+    CHECK(sscanf(p + 2, "%*c %*d %*d %*d %*d %*d %*u %ld %*d %ld", minflt, majflt) == 2,
+          "parse the faults of /proc/self/stat");
+}
+
+int main() {
+    long pageSize = sysconf(_SC_PAGESIZE);
+    struct rusage before, after;
+    long statMin, statMaj;
+
+    CHECK(getrusage(RUSAGE_SELF, &before) == 0, "getrusage");
+    char *anon = mmap(NULL, PAGES * pageSize, PROT_READ | PROT_WRITE,
+                      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(anon != MAP_FAILED, "mmap anonymous");
+    for (int i = 0; i < PAGES; i++) {
+        anon[i * pageSize] = 1;
+    }
+    CHECK(getrusage(RUSAGE_SELF, &after) == 0, "getrusage");
+
+    // the fault around can map up to 8 pages per fault
+    CHECK(after.ru_minflt - before.ru_minflt >= PAGES / 8, "minor faults of anonymous pages");
+
+    int fd = open(TESTFILE, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    CHECK(fd >= 0, "create file");
+    char *buf = malloc(PAGES * pageSize);
+    CHECK(buf != NULL, "malloc");
+    memset(buf, 'x', PAGES * pageSize);
+    CHECK(write(fd, buf, PAGES * pageSize) == PAGES * pageSize, "write file");
+    CHECK(fsync(fd) == 0, "fsync");
+    posix_fadvise(fd, 0, 0, POSIX_FADV_DONTNEED);
+
+    char *file = mmap(NULL, PAGES * pageSize, PROT_READ, MAP_SHARED, fd, 0);
+    CHECK(file != MAP_FAILED, "mmap file");
+
+    CHECK(getrusage(RUSAGE_SELF, &before) == 0, "getrusage");
+    volatile char sum = 0;
+    for (int i = 0; i < PAGES; i++) {
+        sum += file[i * pageSize];
+    }
+    CHECK(getrusage(RUSAGE_SELF, &after) == 0, "getrusage");
+    CHECK(after.ru_majflt > before.ru_majflt, "major faults of a cold file mapping");
+
+    statFaults(&statMin, &statMaj);
+    CHECK(statMin >= after.ru_minflt, "minflt of /proc/self/stat");
+    CHECK(statMaj >= after.ru_majflt, "majflt of /proc/self/stat");
+
+    munmap(file, PAGES * pageSize);
+    close(fd);
+    unlink(TESTFILE);
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <unistd.h>
+#include <sys/stat.h>
+
+#define BASE "/tmp/path_max_test"
+#define COMPONENT_LEN 200
+
+static char path[2 * PATH_MAX];
+
+// appends "/" and a COMPONENT_LEN name made of c to path
+static void append(char c) {
+    size_t len = strlen(path);
+    path[len] = '/';
+    memset(path + len + 1, c, COMPONENT_LEN);
+    path[len + 1 + COMPONENT_LEN] = 0;
+}
+
+int main() {
+    mkdir(BASE, 0755);
+    strcpy(path, BASE);
+
+    // a directory chain making the path longer than 1024 but shorter than PATH_MAX
+    char c = 'a';
+    while (strlen(path) < 1500) {
+        append(c++);
+        if (mkdir(path, 0755) < 0 && errno != EEXIST) {
+            printf("mkdir at length %zu fail: %s\n", strlen(path), strerror(errno));
+            return 1;
+        }
+    }
+
+    strcat(path, "/file");
+    int fd = open(path, O_CREAT | O_RDWR, 0644);
+    if (fd < 0) {
+        printf("create file with path length %zu fail: %s\n", strlen(path), strerror(errno));
+        return 1;
+    }
+
+    if (write(fd, "hello", 5) != 5) {
+        printf("write fail: %s\n", strerror(errno));
+        return 1;
+    }
+    close(fd);
+
+    char buf[16] = {0};
+    fd = open(path, O_RDONLY);
+    if (fd < 0 || read(fd, buf, sizeof(buf)) != 5 || strcmp(buf, "hello") != 0) {
+        printf("reopen file with path length %zu fail: %s\n", strlen(path), strerror(errno));
+        return 1;
+    }
+    close(fd);
+
+    // a symlink with a target longer than 1024
+    char link[64];
+    snprintf(link, sizeof(link), "%s/link", BASE);
+    unlink(link);
+    if (symlink(path, link) < 0) {
+        printf("symlink fail: %s\n", strerror(errno));
+        return 1;
+    }
+
+    char target[PATH_MAX];
+    ssize_t n = readlink(link, target, sizeof(target));
+    if (n != (ssize_t)strlen(path) || memcmp(target, path, n) != 0) {
+        printf("readlink returns %zd, expect %zu: %s\n", n, strlen(path), strerror(errno));
+        return 1;
+    }
+
+    memset(buf, 0, sizeof(buf));
+    fd = open(link, O_RDONLY);
+    if (fd < 0 || read(fd, buf, sizeof(buf)) != 5 || strcmp(buf, "hello") != 0) {
+        printf("open through the symlink fail: %s\n", strerror(errno));
+        return 1;
+    }
+    close(fd);
+
+    // a path of PATH_MAX bytes or more, not counting the terminator, is too long
+    char *longPath = malloc(PATH_MAX + 16);
+    memset(longPath, 'x', PATH_MAX + 15);
+    longPath[0] = '/';
+    longPath[PATH_MAX + 15] = 0;
+    if (open(longPath, O_RDONLY) >= 0 || errno != ENAMETOOLONG) {
+        printf("open a path longer than PATH_MAX should fail with ENAMETOOLONG: %s\n",
+               strerror(errno));
+        return 1;
+    }
+
+    printf("path length %zu works, longer than PATH_MAX fails with ENAMETOOLONG\n", strlen(path));
+    return 0;
+}
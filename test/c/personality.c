@@ -0,0 +1,94 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// personality(ADDR_NO_RANDOMIZE) is kept across fork and exec, and the programs
+// exec'ed with it get the same mmap, stack and load addresses every time.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/personality.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+// the child prints its personality and addresses to stdout
+static int child() {
+    int onStack;
+    void *m = mmap(NULL, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    CHECK(m != MAP_FAILED, "mmap");
+    printf("%x %p %p %p\n", personality(0xffffffff), m, (void *)&onStack, (void *)&child);
+    return 0;
+}
+
+static void run(const char *self, char *out, size_t len) {
+    int pipefd[2];
+    CHECK(pipe(pipefd) == 0, "pipe");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        close(pipefd[0]);
+        dup2(pipefd[1], 1);
+        execl(self, self, "child", NULL);
+        exit(2);
+    }
+
+    close(pipefd[1]);
+    ssize_t n = read(pipefd[0], out, len - 1);
+    CHECK(n > 0, "read child output");
+    out[n] = 0;
+    close(pipefd[0]);
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+}
+
+int main(int argc, char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        return child();
+    }
+
+    int old = personality(0xffffffff);
+    CHECK(old != -1, "query personality");
+    CHECK(personality(old | ADDR_NO_RANDOMIZE) == old, "set ADDR_NO_RANDOMIZE");
+    CHECK(personality(0xffffffff) == (old | ADDR_NO_RANDOMIZE), "personality not set");
+
+    char first[256], second[256];
+    run("/proc/self/exe", first, sizeof(first));
+    run("/proc/self/exe", second, sizeof(second));
+
+    unsigned int persona;
+    CHECK(sscanf(first, "%x", &persona) == 1, "parse child output");
+    CHECK(persona & ADDR_NO_RANDOMIZE, "ADDR_NO_RANDOMIZE lost across exec");
+    if (strcmp(first, second) != 0) {
+        printf("FAIL: addresses differ: %s vs %s", first, second);
+        exit(1);
+    }
+
+    printf("PASS\n");
+    return 0;
+}
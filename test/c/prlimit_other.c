@@ -0,0 +1,110 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A parent lowers the RLIMIT_NOFILE of its child with prlimit and the child then
+// runs out of fds at the new limit. A pid which doesn't exist is ESRCH and a
+// process of another uid can't read the limits without CAP_SYS_RESOURCE.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define NOFILE 16
+
+static void child(int rfd) {
+    char c;
+    CHECK(read(rfd, &c, 1) == 1, "read");
+
+    struct rlimit r;
+    CHECK(getrlimit(RLIMIT_NOFILE, &r) == 0, "getrlimit");
+    CHECK(r.rlim_cur == NOFILE && r.rlim_max == NOFILE, "child limit");
+
+    int fd;
+    while ((fd = open("/dev/null", O_RDONLY)) >= 0) {
+        CHECK(fd < NOFILE, "fd over the limit");
+    }
+    CHECK(errno == EMFILE, "open at the limit");
+    exit(0);
+}
+
+int main(void) {
+    int p[2];
+    CHECK(pipe(p) == 0, "pipe");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        close(p[1]);
+        child(p[0]);
+    }
+    close(p[0]);
+
+    struct rlimit lim = {NOFILE, NOFILE};
+    struct rlimit old;
+    CHECK(prlimit(pid, RLIMIT_NOFILE, &lim, &old) == 0, "prlimit set");
+    CHECK(old.rlim_cur > NOFILE, "old limit");
+
+    struct rlimit got;
+    CHECK(prlimit(pid, RLIMIT_NOFILE, NULL, &got) == 0, "prlimit get");
+    CHECK(got.rlim_cur == NOFILE && got.rlim_max == NOFILE, "got limit");
+
+    // the parent keeps its own limit
+    CHECK(getrlimit(RLIMIT_NOFILE, &got) == 0, "getrlimit");
+    CHECK(got.rlim_cur == old.rlim_cur, "parent limit");
+
+    CHECK(write(p[1], "x", 1) == 1, "write");
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+
+    errno = 0;
+    CHECK(prlimit(0x3fffffff, RLIMIT_NOFILE, NULL, &got) == -1 &&
+              errno == ESRCH,
+          "prlimit of a missing pid");
+
+    if (geteuid() == 0) {
+        pid_t parent = getpid();
+        pid = fork();
+        CHECK(pid >= 0, "fork");
+        if (pid == 0) {
+            CHECK(setresgid(65534, 65534, 65534) == 0, "setresgid");
+            CHECK(setresuid(65534, 65534, 65534) == 0, "setresuid");
+            errno = 0;
+            CHECK(prlimit(parent, RLIMIT_NOFILE, NULL, &got) == -1 &&
+                      errno == EPERM,
+                  "prlimit of another uid");
+            exit(0);
+        }
+
+        CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+        CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0,
+              "unprivileged child failed");
+    }
+
+    printf("PASS\n");
+    return 0;
+}
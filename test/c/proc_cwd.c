@@ -0,0 +1,86 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// /proc/self/cwd follows chdir/fchdir and can be opened through,
+// /proc/self/root resolves to "/".
+
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static int check_cwd(const char *want) {
+  char buf[PATH_MAX];
+  ssize_t n = readlink("/proc/self/cwd", buf, sizeof(buf) - 1);
+  if (n < 0) {
+    perror("readlink /proc/self/cwd");
+    return 1;
+  }
+  buf[n] = 0;
+
+  if (strcmp(buf, want) != 0) {
+    printf("/proc/self/cwd is %s, expect %s\n", buf, want);
+    return 1;
+  }
+  return 0;
+}
+
+int main() {
+  mkdir("/tmp/proc_cwd", 0755);
+  mkdir("/tmp/proc_cwd/sub", 0755);
+
+  if (chdir("/tmp/proc_cwd") != 0 || check_cwd("/tmp/proc_cwd") != 0)
+    goto fail;
+
+  int dfd = open("/tmp/proc_cwd/sub", O_RDONLY | O_DIRECTORY);
+  if (dfd < 0 || fchdir(dfd) != 0 || check_cwd("/tmp/proc_cwd/sub") != 0)
+    goto fail;
+  close(dfd);
+
+  // open through the link
+  int fd = open("/proc/self/cwd/file", O_CREAT | O_WRONLY, 0644);
+  if (fd < 0) {
+    perror("open through /proc/self/cwd");
+    goto fail;
+  }
+  close(fd);
+  if (access("/tmp/proc_cwd/sub/file", F_OK) != 0) {
+    printf("file created through /proc/self/cwd not found\n");
+    goto fail;
+  }
+
+  char buf[PATH_MAX];
+  ssize_t n = readlink("/proc/self/root", buf, sizeof(buf) - 1);
+  if (n < 0) {
+    perror("readlink /proc/self/root");
+    goto fail;
+  }
+  buf[n] = 0;
+  if (strcmp(buf, "/") != 0) {
+    printf("/proc/self/root is %s, expect /\n", buf);
+    goto fail;
+  }
+
+  unlink("/tmp/proc_cwd/sub/file");
+  rmdir("/tmp/proc_cwd/sub");
+  rmdir("/tmp/proc_cwd");
+  printf("proc cwd test pass\n");
+  return 0;
+
+fail:
+  printf("proc cwd test fail\n");
+  return 1;
+}
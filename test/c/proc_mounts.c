@@ -0,0 +1,127 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define MOUNT_POINT "/tmp/proc_mounts_test"
+
+static int has_opt(const char *opts, const char *opt) {
+    char buf[256];
+    snprintf(buf, sizeof(buf), "%s", opts);
+    for (char *tok = strtok(buf, ","); tok != NULL; tok = strtok(NULL, ",")) {
+        if (strcmp(tok, opt) == 0) {
+            return 1;
+        }
+    }
+    return 0;
+}
+
+// find_mount returns the options of MOUNT_POINT in the given file, where
+// opt_field/type_field/src_field are the 0-based fields of the mount options,
+// the filesystem type and the mount source.
+static int find_mount(const char *file, int point_field, int opt_field, int type_field,
+                      int src_field, char *opts, char *fstype, char *src) {
+    FILE *f = fopen(file, "r");
+    if (f == NULL) {
+        perror("fopen");
+        exit(1);
+    }
+
+    char line[1024];
+    int found = 0;
+    while (fgets(line, sizeof(line), f) != NULL) {
+        char *fields[16];
+        int n = 0;
+        for (char *tok = strtok(line, " \n"); tok != NULL && n < 16; tok = strtok(NULL, " \n")) {
+            fields[n++] = tok;
+        }
+
+        if (n <= point_field || n <= opt_field || n <= type_field || n <= src_field) {
+            continue;
+        }
+
+        if (strcmp(fields[point_field], MOUNT_POINT) == 0) {
+            strcpy(opts, fields[opt_field]);
+            strcpy(fstype, fields[type_field]);
+            strcpy(src, fields[src_field]);
+            found = 1;
+        }
+    }
+
+    fclose(f);
+    return found;
+}
+
+int main() {
+    mkdir(MOUNT_POINT, 0755);
+    if (mount("proc_mounts_src", MOUNT_POINT, "tmpfs", MS_NOEXEC | MS_NOATIME, NULL) < 0) {
+        if (errno == EPERM) {
+            printf("proc_mounts: no permission to mount, skip\n");
+            return 0;
+        }
+        perror("mount");
+        return 1;
+    }
+
+    char opts[256], fstype[64], src[256];
+
+    // /proc/mounts: device mountpoint fstype options dump pass
+    if (!find_mount("/proc/mounts", 1, 3, 2, 0, opts, fstype, src)) {
+        fprintf(stderr, "%s isn't in /proc/mounts\n", MOUNT_POINT);
+        return 1;
+    }
+    if (strcmp(fstype, "tmpfs") != 0 || !has_opt(opts, "rw") || !has_opt(opts, "noexec") ||
+        !has_opt(opts, "noatime")) {
+        fprintf(stderr, "/proc/mounts: type %s options %s\n", fstype, opts);
+        return 1;
+    }
+    if (strcmp(src, "proc_mounts_src") != 0) {
+        fprintf(stderr, "/proc/mounts: source %s\n", src);
+        return 1;
+    }
+
+    // /proc/self/mountinfo: id parent major:minor root mountpoint options - fstype ...
+    if (!find_mount("/proc/self/mountinfo", 4, 5, 7, 8, opts, fstype, src)) {
+        fprintf(stderr, "%s isn't in /proc/self/mountinfo\n", MOUNT_POINT);
+        return 1;
+    }
+    if (!has_opt(opts, "rw") || !has_opt(opts, "noexec") || !has_opt(opts, "noatime")) {
+        fprintf(stderr, "/proc/self/mountinfo: options %s\n", opts);
+        return 1;
+    }
+    if (strcmp(src, "proc_mounts_src") != 0) {
+        fprintf(stderr, "/proc/self/mountinfo: source %s\n", src);
+        return 1;
+    }
+
+    if (umount(MOUNT_POINT) < 0) {
+        perror("umount");
+        return 1;
+    }
+
+    if (find_mount("/proc/mounts", 1, 3, 2, 0, opts, fstype, src)) {
+        fprintf(stderr, "%s is still in /proc/mounts after umount\n", MOUNT_POINT);
+        return 1;
+    }
+
+    rmdir(MOUNT_POINT);
+    printf("proc_mounts works\n");
+    return 0;
+}
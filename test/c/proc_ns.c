@@ -0,0 +1,135 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// /proc/[pid]/ns: the links read as "<type>:[<inode>]", an opened namespace file
+// has that inode number, and two processes are in the same namespace exactly when
+// their links match. A child cloned with CLONE_NEWUTS gets a new uts namespace
+// only, which another process can join with setns. Has to run as root.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+const char *types[] = {"ipc", "mnt", "net", "pid", "user", "uts"};
+#define NTYPES (sizeof(types) / sizeof(types[0]))
+
+static void nslink(pid_t pid, const char *type, char *buf, size_t len) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/ns/%s", pid, type);
+    ssize_t n = readlink(path, buf, len - 1);
+    CHECK(n > 0, "readlink /proc/<pid>/ns/<type>");
+    buf[n] = 0;
+}
+
+static void waitchild(pid_t pid) {
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+}
+
+static int stack[16384];
+static int pipefd[2];
+
+static int utsChild(void *arg) {
+    char c;
+    // stay alive until the parent is done with our namespaces
+    close(pipefd[1]);
+    return read(pipefd[0], &c, 1) == 0 ? 0 : 1;
+}
+
+int main() {
+    char link[64], other[64], expect[64];
+    pid_t self = getpid();
+
+    // the link text and the inode of the opened file agree
+    for (int i = 0; i < NTYPES; i++) {
+        nslink(self, types[i], link, sizeof(link));
+
+        unsigned long ino;
+        snprintf(expect, sizeof(expect), "%s:[", types[i]);
+        CHECK(strncmp(link, expect, strlen(expect)) == 0, "link isn't <type>:[<inode>]");
+        CHECK(sscanf(link + strlen(expect), "%lu]", &ino) == 1, "link has no inode");
+
+        char path[64];
+        snprintf(path, sizeof(path), "/proc/self/ns/%s", types[i]);
+        int fd = open(path, O_RDONLY);
+        CHECK(fd >= 0, "open /proc/self/ns/<type>");
+        struct stat st;
+        CHECK(fstat(fd, &st) == 0, "fstat");
+        CHECK(st.st_ino == ino, "inode of the namespace file doesn't match the link");
+        close(fd);
+    }
+
+    // a forked child shares all namespaces
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        for (int i = 0; i < NTYPES; i++) {
+            nslink(self, types[i], link, sizeof(link));
+            nslink(getpid(), types[i], other, sizeof(other));
+            CHECK(strcmp(link, other) == 0, "forked child is in another namespace");
+        }
+        exit(0);
+    }
+    waitchild(pid);
+
+    // a CLONE_NEWUTS child has its own uts namespace only
+    CHECK(pipe(pipefd) == 0, "pipe");
+    pid_t uts = clone(utsChild, stack + 16384, CLONE_NEWUTS | SIGCHLD, NULL);
+    CHECK(uts > 0, "clone CLONE_NEWUTS");
+    for (int i = 0; i < NTYPES; i++) {
+        nslink(self, types[i], link, sizeof(link));
+        nslink(uts, types[i], other, sizeof(other));
+        int same = strcmp(link, other) == 0;
+        CHECK(same == (strcmp(types[i], "uts") != 0), "CLONE_NEWUTS child namespaces");
+    }
+
+    // and another process can join it
+    pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        char path[64];
+        snprintf(path, sizeof(path), "/proc/%d/ns/uts", uts);
+        int fd = open(path, O_RDONLY);
+        CHECK(fd >= 0, "open /proc/<pid>/ns/uts");
+        CHECK(setns(fd, CLONE_NEWUTS) == 0, "setns uts");
+        nslink(uts, "uts", link, sizeof(link));
+        nslink(getpid(), "uts", other, sizeof(other));
+        CHECK(strcmp(link, other) == 0, "setns didn't join the uts namespace");
+        exit(0);
+    }
+    waitchild(pid);
+
+    close(pipefd[1]);
+    waitchild(uts);
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// ptrace a child with PTRACE_TRACEME: stop it at a breakpoint, read and write
+// its memory and registers, single step it and let it exit. Then attach to and
+// detach from a running child with PTRACE_ATTACH.
+
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/ptrace.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define MAGIC_IN 0x1234567890abcdefL
+#define MAGIC_OUT 0x0fedcba987654321L
+
+static volatile long value = 0x1111;
+
+static void traced_child(void) {
+    ptrace(PTRACE_TRACEME, 0, 0, 0);
+    raise(SIGSTOP);
+
+    // the tracer checks r12 at the breakpoint and sets r13
+    long out;
+    __asm__ volatile("mov %1, %%r12\n\t"
+                     "xor %%r13, %%r13\n\t"
+                     "int3\n\t"
+                     "mov %%r13, %0\n\t"
+                     : "=r"(out)
+                     : "r"(MAGIC_IN)
+                     : "r12", "r13");
+    if (out != MAGIC_OUT)
+        _exit(10);
+    if (value != 0x2222)
+        _exit(11);
+    _exit(42);
+}
+
+static void wait_stop(pid_t pid, int sig) {
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSTOPPED(status), "the tracee isn't stopped");
+    CHECK(WSTOPSIG(status) == sig, "wrong stop signal");
+}
+
+static void test_traceme(void) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0)
+        traced_child();
+
+    wait_stop(pid, SIGSTOP);
+
+    siginfo_t si;
+    CHECK(ptrace(PTRACE_GETSIGINFO, pid, 0, &si) == 0, "PTRACE_GETSIGINFO");
+    CHECK(si.si_signo == SIGSTOP, "siginfo of the stop");
+
+    errno = 0;
+    long word = ptrace(PTRACE_PEEKDATA, pid, &value, 0);
+    CHECK(errno == 0 && word == 0x1111, "PTRACE_PEEKDATA");
+    CHECK(ptrace(PTRACE_POKEDATA, pid, &value, (void *)0x2222) == 0,
+          "PTRACE_POKEDATA");
+
+    // the breakpoint
+    CHECK(ptrace(PTRACE_CONT, pid, 0, 0) == 0, "PTRACE_CONT");
+    wait_stop(pid, SIGTRAP);
+
+    struct user_regs_struct regs;
+    CHECK(ptrace(PTRACE_GETREGS, pid, 0, &regs) == 0, "PTRACE_GETREGS");
+    CHECK(regs.r12 == (unsigned long)MAGIC_IN, "r12 at the breakpoint");
+    regs.r13 = MAGIC_OUT;
+    CHECK(ptrace(PTRACE_SETREGS, pid, 0, &regs) == 0, "PTRACE_SETREGS");
+
+    // one instruction, the mov from r13
+    unsigned long rip = regs.rip;
+    CHECK(ptrace(PTRACE_SINGLESTEP, pid, 0, 0) == 0, "PTRACE_SINGLESTEP");
+    wait_stop(pid, SIGTRAP);
+    CHECK(ptrace(PTRACE_GETREGS, pid, 0, &regs) == 0, "PTRACE_GETREGS");
+    CHECK(regs.rip > rip && regs.rip < rip + 16, "rip after the single step");
+
+    CHECK(ptrace(PTRACE_CONT, pid, 0, 0) == 0, "PTRACE_CONT");
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status), "the tracee didn't exit");
+    CHECK(WEXITSTATUS(status) == 42, "exit status of the tracee");
+}
+
+static long tracer_pid(pid_t pid) {
+    char path[64], line[256];
+    snprintf(path, sizeof(path), "/proc/%d/status", pid);
+    FILE *f = fopen(path, "r");
+    CHECK(f != NULL, "open status");
+
+    long tracer = -1;
+    while (fgets(line, sizeof(line), f) != NULL) {
+        if (strncmp(line, "TracerPid:", 10) == 0)
+            tracer = atol(line + 10);
+    }
+    fclose(f);
+    return tracer;
+}
+
+static void test_attach(void) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        for (;;)
+            ;
+    }
+
+    CHECK(ptrace(PTRACE_ATTACH, pid, 0, 0) == 0, "PTRACE_ATTACH");
+    wait_stop(pid, SIGSTOP);
+    CHECK(tracer_pid(pid) == getpid(), "TracerPid after PTRACE_ATTACH");
+    CHECK(ptrace(PTRACE_ATTACH, pid, 0, 0) == -1 && errno == EPERM,
+          "a second PTRACE_ATTACH");
+
+    CHECK(ptrace(PTRACE_DETACH, pid, 0, 0) == 0, "PTRACE_DETACH");
+    CHECK(tracer_pid(pid) == 0, "TracerPid after PTRACE_DETACH");
+    CHECK(ptrace(PTRACE_CONT, pid, 0, 0) == -1 && errno == ESRCH,
+          "PTRACE_CONT after PTRACE_DETACH");
+
+    kill(pid, SIGKILL);
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSIGNALED(status) && WTERMSIG(status) == SIGKILL,
+          "the detached child isn't killed");
+}
+
+int main() {
+    test_traceme();
+    test_attach();
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,170 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Trace a child through its syscalls with PTRACE_SYSCALL and
+// PTRACE_O_TRACESYSGOOD: check the registers at the syscall-enter-stop and the
+// syscall-exit-stop, change an argument at the entry, change a return value at
+// the exit and skip a syscall by setting orig_rax to -1. rt_sigreturn has a
+// syscall-exit-stop as well.
+
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define SYSGOOD_TRAP (SIGTRAP | 0x80)
+#define MAGIC_PPID 0x4242
+
+static volatile int got_usr1;
+
+static void on_usr1(int sig) { got_usr1 = 1; }
+
+static void traced_child(void) {
+    ptrace(PTRACE_TRACEME, 0, 0, 0);
+    raise(SIGSTOP);
+
+    // the tracer changes the return value
+    if (syscall(SYS_getppid) != MAGIC_PPID)
+        _exit(10);
+
+    // the tracer changes the length from 1 to 3
+    int fds[2];
+    char buf[8];
+    if (pipe(fds) != 0)
+        _exit(11);
+    if (syscall(SYS_write, fds[1], "abc", 1) != 3)
+        _exit(12);
+    if (read(fds[0], buf, sizeof(buf)) != 3)
+        _exit(13);
+
+    // the tracer skips the syscall
+    errno = 0;
+    if (syscall(SYS_kill, getpid(), SIGKILL) != -1 || errno != ENOSYS)
+        _exit(14);
+
+    // the handler returns through rt_sigreturn
+    signal(SIGUSR1, on_usr1);
+    syscall(SYS_tgkill, getpid(), syscall(SYS_gettid), SIGUSR1);
+    if (!got_usr1)
+        _exit(15);
+    _exit(42);
+}
+
+// next_stop resumes the tracee to its next syscall-stop and returns its
+// registers.
+static void next_stop(pid_t pid, struct user_regs_struct *regs) {
+    int status;
+    CHECK(ptrace(PTRACE_SYSCALL, pid, 0, 0) == 0, "PTRACE_SYSCALL");
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSTOPPED(status), "the tracee isn't stopped");
+    CHECK(WSTOPSIG(status) == SYSGOOD_TRAP, "wrong syscall-stop signal");
+    CHECK(ptrace(PTRACE_GETREGS, pid, 0, regs) == 0, "PTRACE_GETREGS");
+}
+
+// enter_syscall runs the tracee to the syscall-enter-stop of the syscall nr.
+static void enter_syscall(pid_t pid, long nr, struct user_regs_struct *regs) {
+    for (;;) {
+        next_stop(pid, regs);
+        CHECK(regs->rax == (unsigned long)-ENOSYS,
+              "rax at the syscall-enter-stop");
+        if ((long)regs->orig_rax == nr)
+            return;
+
+        // the exit of another syscall, e.g. of pipe
+        next_stop(pid, regs);
+        CHECK((long)regs->orig_rax != nr, "a syscall-enter-stop is missing");
+    }
+}
+
+int main() {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0)
+        traced_child();
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSTOPPED(status) && WSTOPSIG(status) == SIGSTOP,
+          "the tracee isn't stopped by SIGSTOP");
+    CHECK(ptrace(PTRACE_SETOPTIONS, pid, 0, 0x80000000) == -1 &&
+              errno == EINVAL,
+          "an unsupported option");
+    CHECK(ptrace(PTRACE_SETOPTIONS, pid, 0, PTRACE_O_TRACESYSGOOD) == 0,
+          "PTRACE_SETOPTIONS");
+
+    struct user_regs_struct regs;
+    enter_syscall(pid, SYS_getppid, &regs);
+    siginfo_t si;
+    CHECK(ptrace(PTRACE_GETSIGINFO, pid, 0, &si) == 0, "PTRACE_GETSIGINFO");
+    CHECK(si.si_signo == SIGTRAP && si.si_code == SYSGOOD_TRAP,
+          "siginfo of the syscall-stop");
+
+    next_stop(pid, &regs);
+    CHECK(regs.orig_rax == SYS_getppid, "orig_rax at the syscall-exit-stop");
+    CHECK(regs.rax == (unsigned long)getpid(), "getppid return value");
+    regs.rax = MAGIC_PPID;
+    CHECK(ptrace(PTRACE_SETREGS, pid, 0, &regs) == 0, "PTRACE_SETREGS");
+
+    enter_syscall(pid, SYS_write, &regs);
+    CHECK(regs.rdx == 1, "the write length at the syscall-enter-stop");
+    regs.rdx = 3;
+    CHECK(ptrace(PTRACE_SETREGS, pid, 0, &regs) == 0, "PTRACE_SETREGS");
+    next_stop(pid, &regs);
+    CHECK(regs.orig_rax == SYS_write && regs.rax == 3, "write return value");
+
+    enter_syscall(pid, SYS_kill, &regs);
+    regs.orig_rax = -1;
+    CHECK(ptrace(PTRACE_SETREGS, pid, 0, &regs) == 0, "PTRACE_SETREGS");
+    next_stop(pid, &regs);
+    CHECK(regs.rax == (unsigned long)-ENOSYS, "return value of a skipped syscall");
+
+    enter_syscall(pid, SYS_tgkill, &regs);
+    next_stop(pid, &regs);
+    CHECK(ptrace(PTRACE_SYSCALL, pid, 0, 0) == 0, "PTRACE_SYSCALL");
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSTOPPED(status) && WSTOPSIG(status) == SIGUSR1,
+          "the tracee isn't in the signal-delivery-stop of SIGUSR1");
+    CHECK(ptrace(PTRACE_SYSCALL, pid, 0, SIGUSR1) == 0, "PTRACE_SYSCALL");
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFSTOPPED(status) && WSTOPSIG(status) == SYSGOOD_TRAP,
+          "wrong syscall-stop signal");
+    CHECK(ptrace(PTRACE_GETREGS, pid, 0, &regs) == 0, "PTRACE_GETREGS");
+    CHECK(regs.orig_rax == SYS_rt_sigreturn, "syscall-enter-stop of rt_sigreturn");
+    // the exit of rt_sigreturn has the registers of before the signal, tgkill's
+    // return value 0, the entry of exit_group would have -ENOSYS
+    next_stop(pid, &regs);
+    CHECK(regs.rax == 0, "syscall-exit-stop of rt_sigreturn");
+
+    CHECK(ptrace(PTRACE_CONT, pid, 0, 0) == 0, "PTRACE_CONT");
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status), "the tracee didn't exit");
+    CHECK(WEXITSTATUS(status) == 42, "exit status of the tracee");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,88 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// relatime checks the default relatime policy: the first read after a write
+// bumps the atime, a second read within the day doesn't.
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define TEST_FILE "/dev/shm/relatime_test"
+
+static struct timespec get_atime() {
+    struct stat st;
+    if (stat(TEST_FILE, &st) < 0) {
+        perror("stat");
+        exit(1);
+    }
+    return st.st_atim;
+}
+
+static void read_file() {
+    char buf[16];
+    int fd = open(TEST_FILE, O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        exit(1);
+    }
+    if (read(fd, buf, sizeof(buf)) <= 0) {
+        perror("read");
+        exit(1);
+    }
+    close(fd);
+}
+
+int main() {
+    int fd = open(TEST_FILE, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    if (write(fd, "hello", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+    close(fd);
+
+    // an atime older than the mtime is always updated
+    struct timespec ts[2] = {{.tv_sec = 1000}, {.tv_nsec = UTIME_OMIT}};
+    if (utimensat(AT_FDCWD, TEST_FILE, ts, 0) < 0) {
+        perror("utimensat");
+        return 1;
+    }
+
+    // keep the ctime set by utimensat apart from the atime of the first read
+    sleep(1);
+    read_file();
+    struct timespec first = get_atime();
+    if (first.tv_sec == 1000) {
+        fprintf(stderr, "atime didn't advance on the first read\n");
+        return 1;
+    }
+
+    sleep(1);
+    read_file();
+    struct timespec second = get_atime();
+    if (second.tv_sec != first.tv_sec || second.tv_nsec != first.tv_nsec) {
+        fprintf(stderr, "atime advanced on the second read\n");
+        return 1;
+    }
+
+    unlink(TEST_FILE);
+    printf("relatime works\n");
+    return 0;
+}
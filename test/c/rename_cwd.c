@@ -0,0 +1,81 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// getcwd follows renames of the cwd's ancestors, in place and across directories.
+
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static int check_cwd(const char *want) {
+  char buf[PATH_MAX];
+  if (getcwd(buf, sizeof(buf)) == NULL) {
+    perror("getcwd");
+    return 1;
+  }
+
+  if (strcmp(buf, want) != 0) {
+    printf("getcwd is %s, expect %s\n", buf, want);
+    return 1;
+  }
+  return 0;
+}
+
+int main() {
+  mkdir("/tmp/rename_cwd", 0755);
+  mkdir("/tmp/rename_cwd/a", 0755);
+  mkdir("/tmp/rename_cwd/a/cwd", 0755);
+  mkdir("/tmp/rename_cwd/other", 0755);
+
+  if (chdir("/tmp/rename_cwd/a/cwd") != 0) {
+    perror("chdir");
+    goto fail;
+  }
+
+  // rename the parent in place
+  if (rename("/tmp/rename_cwd/a", "/tmp/rename_cwd/b") != 0) {
+    perror("rename");
+    goto fail;
+  }
+  if (check_cwd("/tmp/rename_cwd/b/cwd") != 0)
+    goto fail;
+
+  // move the parent into another directory
+  if (rename("/tmp/rename_cwd/b", "/tmp/rename_cwd/other/c") != 0) {
+    perror("rename");
+    goto fail;
+  }
+  if (check_cwd("/tmp/rename_cwd/other/c/cwd") != 0)
+    goto fail;
+
+  // relative lookups still work from the moved cwd
+  if (access("../../c/cwd", F_OK) != 0) {
+    perror("access");
+    goto fail;
+  }
+
+  chdir("/");
+  rmdir("/tmp/rename_cwd/other/c/cwd");
+  rmdir("/tmp/rename_cwd/other/c");
+  rmdir("/tmp/rename_cwd/other");
+  rmdir("/tmp/rename_cwd");
+  printf("rename cwd test pass\n");
+  return 0;
+
+fail:
+  printf("rename cwd test fail\n");
+  return 1;
+}
@@ -0,0 +1,69 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// A CPU bound child with RLIMIT_CPU soft 1s / hard 2s gets SIGXCPU first and
+// is then killed with SIGKILL.
+
+#include <signal.h>
+#include <stdio.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static volatile int xcpu = 0;
+
+static void handler(int sig) { xcpu++; }
+
+int main() {
+  int p[2];
+  pipe(p);
+
+  pid_t pid = fork();
+  if (pid == 0) {
+    close(p[0]);
+    signal(SIGXCPU, handler);
+
+    struct rlimit rl = {1, 2};
+    if (setrlimit(RLIMIT_CPU, &rl) != 0) {
+      perror("setrlimit");
+      _exit(1);
+    }
+
+    int reported = 0;
+    volatile unsigned long n = 0;
+    for (;;) {
+      n++;
+      if (xcpu && !reported) {
+        write(p[1], "x", 1);
+        reported = 1;
+      }
+    }
+  }
+
+  close(p[1]);
+  char c;
+  int gotXcpu = read(p[0], &c, 1) == 1;
+
+  int status;
+  waitpid(pid, &status, 0);
+
+  if (!gotXcpu || !WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL) {
+    printf("rlimit cpu test fail: sigxcpu %d status %x\n", gotXcpu, status);
+    return 1;
+  }
+
+  printf("rlimit cpu test pass\n");
+  return 0;
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// A write past RLIMIT_FSIZE is cut at the limit, and a write starting at the
+// limit fails with EFBIG and raises SIGXFSZ, both for plain and O_APPEND fds.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+static volatile int xfsz = 0;
+
+static void handler(int sig) { xfsz++; }
+
+static int check(int flags) {
+  char buf[100];
+  memset(buf, 'a', sizeof(buf));
+
+  int fd = open("/tmp/rlimit_fsize", O_CREAT | O_TRUNC | O_WRONLY | flags, 0644);
+  if (fd < 0) {
+    perror("open");
+    return 1;
+  }
+
+  ssize_t n = write(fd, buf, sizeof(buf));
+  if (n != 64) {
+    printf("flags %x: write returned %zd, expect the partial 64\n", flags, n);
+    return 1;
+  }
+
+  int before = xfsz;
+  n = write(fd, buf, sizeof(buf));
+  if (n != -1 || errno != EFBIG || xfsz != before + 1) {
+    printf("flags %x: write at limit returned %zd errno %d sigxfsz %d\n", flags, n,
+           errno, xfsz - before);
+    return 1;
+  }
+
+  close(fd);
+  unlink("/tmp/rlimit_fsize");
+  return 0;
+}
+
+int main() {
+  signal(SIGXFSZ, handler);
+
+  struct rlimit rl = {64, 64};
+  if (setrlimit(RLIMIT_FSIZE, &rl) != 0) {
+    perror("setrlimit");
+    return 1;
+  }
+
+  if (check(0) != 0 || check(O_APPEND) != 0) {
+    printf("rlimit fsize test fail\n");
+    return 1;
+  }
+
+  printf("rlimit fsize test pass\n");
+  return 0;
+}
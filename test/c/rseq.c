@@ -0,0 +1,196 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Register an rseq area and check the cpu ids it gets, the registration errors,
+// that a critical section spinning until it is aborted gets to its abort handler
+// once it is preempted or migrated by another thread, and that a signal handler
+// interrupting the critical section returns to the abort handler.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <linux/rseq.h>
+#include <pthread.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <ucontext.h>
+#include <unistd.h>
+#if __has_include(<sys/rseq.h>)
+#include <sys/rseq.h>
+#define HAVE_GLIBC_RSEQ 1
+#endif
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define SIG 0x53053053
+#define RSEQ_LEN 32
+
+// rseq_spin(rs) enters the critical section spin_cs and spins in it until the
+// kernel moves it to the abort handler, which returns 1.
+int rseq_spin(struct rseq *rs);
+extern char spin_start[], spin_end[], spin_abort[];
+static struct rseq_cs spin_cs __attribute__((aligned(32)));
+
+__asm__(".text\n"
+        ".globl rseq_spin\n"
+        "rseq_spin:\n"
+        "    leaq spin_cs(%rip), %rax\n"
+        "    movq %rax, 8(%rdi)\n"
+        ".globl spin_start\n"
+        "spin_start:\n"
+        "    jmp spin_start\n"
+        ".globl spin_end\n"
+        "spin_end:\n"
+        "    .long 0x53053053\n"
+        ".globl spin_abort\n"
+        "spin_abort:\n"
+        "    movl $1, %eax\n"
+        "    ret\n");
+
+static struct rseq rs __attribute__((aligned(32)));
+static pid_t main_tid;
+static volatile int aborted;
+static volatile int handler_at_abort;
+static volatile int handler_cs_cleared;
+
+static long sys_rseq(void *addr, uint32_t len, int flags, uint32_t sig) {
+    return syscall(SYS_rseq, addr, len, flags, sig);
+}
+
+static unsigned cur_cpu(void) {
+    unsigned cpu;
+    CHECK(syscall(SYS_getcpu, &cpu, NULL, NULL) == 0, "getcpu");
+    return cpu;
+}
+
+static void sleep_ms(int ms) {
+    struct timespec ts = {0, ms * 1000000L};
+    nanosleep(&ts, NULL);
+}
+
+// preempter moves the spinning main thread to another cpu if there is one and
+// competes with it for its cpu until it is aborted.
+static void *preempter(void *arg) {
+    sleep_ms(50);
+    long ncpus = sysconf(_SC_NPROCESSORS_ONLN);
+    if (ncpus > 1) {
+        cpu_set_t set;
+        CPU_ZERO(&set);
+        CPU_SET((cur_cpu() + 1) % ncpus, &set);
+        sched_setaffinity(main_tid, sizeof(set), &set);
+    }
+
+    while (!aborted)
+        ;
+    return NULL;
+}
+
+static void handler(int sig, siginfo_t *info, void *ucontext) {
+    ucontext_t *uc = ucontext;
+    handler_at_abort = uc->uc_mcontext.gregs[REG_RIP] == (greg_t)spin_abort;
+    handler_cs_cleared = rs.rseq_cs == 0;
+}
+
+// signaller sends SIGUSR1 to the main thread until one lands in the critical
+// section.
+static void *signaller(void *arg) {
+    for (int i = 0; i < 100 && !handler_at_abort; i++) {
+        sleep_ms(20);
+        syscall(SYS_tgkill, getpid(), main_tid, SIGUSR1);
+    }
+    return NULL;
+}
+
+int main() {
+    main_tid = syscall(SYS_gettid);
+    alarm(20);
+
+#ifdef HAVE_GLIBC_RSEQ
+    // glibc registers its own area for every thread, give it up
+    if (__rseq_size > 0) {
+        void *area = (char *)__builtin_thread_pointer() + __rseq_offset;
+        CHECK(sys_rseq(area, RSEQ_LEN, RSEQ_FLAG_UNREGISTER, RSEQ_SIG) == 0,
+              "unregister the glibc rseq area");
+    }
+#endif
+
+    spin_cs.version = 0;
+    spin_cs.flags = 0;
+    spin_cs.start_ip = (uintptr_t)spin_start;
+    spin_cs.post_commit_offset = spin_end - spin_start;
+    spin_cs.abort_ip = (uintptr_t)spin_abort;
+
+    CHECK(sys_rseq(&rs, RSEQ_LEN - 1, 0, SIG) == -1 && errno == EINVAL,
+          "a short area is refused");
+    CHECK(sys_rseq((char *)&rs + 8, RSEQ_LEN, 0, SIG) == -1 && errno == EINVAL,
+          "an unaligned area is refused");
+    CHECK(sys_rseq(&rs, RSEQ_LEN, 2, SIG) == -1 && errno == EINVAL,
+          "unknown flags are refused");
+
+    rs.cpu_id = RSEQ_CPU_ID_UNINITIALIZED;
+    CHECK(sys_rseq(&rs, RSEQ_LEN, 0, SIG) == 0, "register");
+    CHECK(rs.cpu_id == cur_cpu() && rs.cpu_id_start == rs.cpu_id,
+          "the cpu ids of the area");
+    CHECK(sys_rseq(&rs, RSEQ_LEN, 0, SIG) == -1 && errno == EBUSY,
+          "registering twice is EBUSY");
+    CHECK(sys_rseq(&rs, RSEQ_LEN, 0, SIG + 1) == -1 && errno == EPERM,
+          "registering with another signature is EPERM");
+    CHECK(sys_rseq(&rs, RSEQ_LEN, RSEQ_FLAG_UNREGISTER, SIG + 1) == -1 &&
+              errno == EPERM,
+          "unregistering with another signature is EPERM");
+
+    // preempted or migrated in the critical section
+    pthread_t thread;
+    CHECK(pthread_create(&thread, NULL, preempter, NULL) == 0, "pthread_create");
+    CHECK(rseq_spin(&rs) == 1, "the critical section isn't aborted");
+    aborted = 1;
+    CHECK(pthread_join(thread, NULL) == 0, "pthread_join");
+    CHECK(rs.rseq_cs == 0, "rseq_cs isn't cleared by the abort");
+    CHECK(rs.cpu_id == cur_cpu(), "the cpu id after the preemption");
+
+    // a signal handler interrupting the critical section returns to the abort
+    // handler
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = handler;
+    sa.sa_flags = SA_SIGINFO;
+    CHECK(sigaction(SIGUSR1, &sa, NULL) == 0, "sigaction");
+    CHECK(pthread_create(&thread, NULL, signaller, NULL) == 0, "pthread_create");
+    while (!handler_at_abort)
+        CHECK(rseq_spin(&rs) == 1, "the critical section isn't aborted");
+    CHECK(pthread_join(thread, NULL) == 0, "pthread_join");
+    CHECK(handler_cs_cleared, "rseq_cs isn't cleared for the signal handler");
+
+    CHECK(sys_rseq(&rs, RSEQ_LEN, RSEQ_FLAG_UNREGISTER, SIG) == 0, "unregister");
+    CHECK(rs.cpu_id == (uint32_t)RSEQ_CPU_ID_UNINITIALIZED,
+          "the cpu id after the unregistration");
+    CHECK(sys_rseq(&rs, RSEQ_LEN, RSEQ_FLAG_UNREGISTER, SIG) == -1 &&
+              errno == EINVAL,
+          "unregistering twice is EINVAL");
+
+    printf("PASS\n");
+    return 0;
+}
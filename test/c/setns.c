@@ -0,0 +1,184 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// setns(2) with the /proc/[pid]/ns files: a child joins the mount namespace of
+// its parent through /proc/<ppid>/ns/mnt, which moves its cwd to the root, and
+// the namespace type passed to setns has to match the file. An abstract unix
+// socket of the parent is out of reach after unshare(CLONE_NEWNET) and back in
+// reach after joining /proc/<ppid>/ns/net. A multithreaded child can't join a
+// mount namespace. Has to run as root, an unprivileged child is refused with
+// EPERM.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <sched.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static void join(pid_t pid) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/ns/mnt", pid);
+    int fd = open(path, O_RDONLY);
+    CHECK(fd >= 0, "open /proc/<ppid>/ns/mnt");
+
+    errno = 0;
+    CHECK(setns(fd, CLONE_NEWNET) < 0 && errno == EINVAL, "setns with the wrong type");
+
+    CHECK(chdir("/tmp") == 0, "chdir /tmp");
+    CHECK(setns(fd, CLONE_NEWNS) == 0, "setns mnt");
+
+    char cwd[256];
+    CHECK(getcwd(cwd, sizeof(cwd)) != NULL, "getcwd");
+    CHECK(strcmp(cwd, "/") == 0, "cwd isn't the root of the namespace");
+    CHECK(setns(fd, 0) == 0, "setns with any type");
+    close(fd);
+}
+
+static void *idle(void *arg) {
+    pause();
+    return NULL;
+}
+
+static void join_threaded(pid_t pid) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/ns/mnt", pid);
+    int fd = open(path, O_RDONLY);
+    CHECK(fd >= 0, "open /proc/<ppid>/ns/mnt");
+
+    pthread_t thread;
+    CHECK(pthread_create(&thread, NULL, idle, NULL) == 0, "pthread_create");
+    CHECK(chdir("/tmp") == 0, "chdir /tmp");
+    errno = 0;
+    CHECK(setns(fd, CLONE_NEWNS) < 0 && errno == EINVAL, "multithreaded setns mnt");
+
+    char cwd[256];
+    CHECK(getcwd(cwd, sizeof(cwd)) != NULL, "getcwd");
+    CHECK(strcmp(cwd, "/tmp") == 0, "cwd changed by a failed setns");
+    close(fd);
+}
+
+static const char abstract_name[] = "\0quark_setns";
+
+static void abstract_addr(struct sockaddr_un *addr) {
+    memset(addr, 0, sizeof(*addr));
+    addr->sun_family = AF_UNIX;
+    memcpy(addr->sun_path, abstract_name, sizeof(abstract_name) - 1);
+}
+
+static int abstract_connect(void) {
+    struct sockaddr_un addr;
+    abstract_addr(&addr);
+    int fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    CHECK(fd >= 0, "socket");
+    int ret = connect(fd, (struct sockaddr *)&addr,
+                      offsetof(struct sockaddr_un, sun_path) + sizeof(abstract_name) - 1);
+    int err = errno;
+    close(fd);
+    errno = err;
+    return ret;
+}
+
+static void join_net(pid_t pid) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/ns/net", pid);
+    int fd = open(path, O_RDONLY);
+    CHECK(fd >= 0, "open /proc/<ppid>/ns/net");
+
+    CHECK(abstract_connect() == 0, "connect in the parent's network namespace");
+    CHECK(unshare(CLONE_NEWNET) == 0, "unshare net");
+    errno = 0;
+    CHECK(abstract_connect() < 0 && errno == ECONNREFUSED,
+          "the parent's abstract socket is seen from a new network namespace");
+
+    CHECK(setns(fd, CLONE_NEWNET) == 0, "setns net");
+    CHECK(abstract_connect() == 0, "connect after joining the parent's network namespace");
+    close(fd);
+}
+
+static void run(void (*f)(pid_t)) {
+    pid_t parent = getpid();
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        f(parent);
+        exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        exit(1);
+    }
+}
+
+static void unprivileged(pid_t pid) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/ns/mnt", pid);
+    int fd = open(path, O_RDONLY);
+    CHECK(fd >= 0, "open /proc/<ppid>/ns/mnt");
+
+    CHECK(setuid(65534) == 0, "setuid");
+    errno = 0;
+    CHECK(setns(fd, CLONE_NEWNS) < 0 && errno == EPERM, "unprivileged setns");
+}
+
+int main() {
+    CHECK(getuid() == 0, "has to run as root");
+
+    // only namespace files can be joined
+    int fd = open("/proc/self/status", O_RDONLY);
+    CHECK(fd >= 0, "open /proc/self/status");
+    errno = 0;
+    CHECK(setns(fd, 0) < 0 && errno == EINVAL, "setns on a regular file");
+    close(fd);
+
+    fd = open("/proc/self/ns/net", O_RDONLY);
+    CHECK(fd >= 0, "open /proc/self/ns/net");
+    CHECK(setns(fd, CLONE_NEWNET) == 0, "setns net");
+    close(fd);
+
+    struct sockaddr_un addr;
+    abstract_addr(&addr);
+    int lfd = socket(AF_UNIX, SOCK_STREAM, 0);
+    CHECK(lfd >= 0, "socket");
+    CHECK(bind(lfd, (struct sockaddr *)&addr,
+               offsetof(struct sockaddr_un, sun_path) + sizeof(abstract_name) - 1) == 0,
+          "bind abstract socket");
+    CHECK(listen(lfd, 8) == 0, "listen");
+
+    run(join);
+    run(join_threaded);
+    run(join_net);
+    run(unprivileged);
+    close(lfd);
+
+    printf("PASS\n");
+    return 0;
+}
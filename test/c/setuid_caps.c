@@ -0,0 +1,169 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <linux/capability.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define TEST_FILE "/tmp/setuid_caps_test"
+
+static void caps(unsigned *effective, unsigned *permitted) {
+    struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+    struct __user_cap_data_struct data[2];
+    CHECK(syscall(SYS_capget, &hdr, data) == 0, "capget");
+    *effective = data[0].effective;
+    *permitted = data[0].permitted;
+}
+
+static void ids(uid_t r, uid_t e, uid_t s, const char *msg) {
+    uid_t gr, ge, gs;
+    CHECK(getresuid(&gr, &ge, &gs) == 0, "getresuid");
+    if (gr != r || ge != e || gs != s) {
+        printf("FAIL: %s: got %d/%d/%d want %d/%d/%d\n", msg, gr, ge, gs, r, e, s);
+        exit(1);
+    }
+}
+
+static void run(void (*fn)()) {
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        fn();
+        exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+}
+
+// setresuid keeps root in the saved id, so the caps are only dropped from the
+// effective set and come back with the effective id
+static void savedRoot() {
+    unsigned eff, perm;
+    CHECK(setresuid(1000, 1000, 0) == 0, "setresuid keep saved root");
+    ids(1000, 1000, 0, "setresuid");
+    caps(&eff, &perm);
+    CHECK(eff == 0 && perm != 0, "effective caps cleared, permitted kept");
+
+    errno = 0;
+    CHECK(chown(TEST_FILE, 1000, 1000) == -1 && errno == EPERM, "chown without caps");
+
+    CHECK(seteuid(0) == 0, "seteuid back to the saved id");
+    ids(1000, 0, 0, "seteuid");
+    caps(&eff, &perm);
+    CHECK(eff == perm, "permitted copied back to effective");
+    CHECK(chown(TEST_FILE, 1000, 1000) == 0, "chown with caps");
+}
+
+// setreuid updates the saved id when the effective id differs from the old real id
+static void reuid() {
+    CHECK(setresuid(1000, 0, 0) == 0, "setresuid");
+    CHECK(setreuid(-1, 1000) == 0, "setreuid to the real id");
+    ids(1000, 1000, 0, "saved id unchanged when effective is set to the real id");
+
+    CHECK(setresuid(1000, 0, 0) == 0, "setresuid again");
+    CHECK(setreuid(-1, 1001) == 0, "setreuid effective");
+    ids(1000, 1001, 1001, "saved id follows a new effective id");
+
+    // unprivileged: only the real, effective or saved id is allowed
+    errno = 0;
+    CHECK(setreuid(-1, 1003) == -1 && errno == EPERM, "setreuid to a foreign id");
+    CHECK(setreuid(-1, 1000) == 0, "setreuid to the real id");
+    ids(1000, 1000, 1001, "setreuid unprivileged");
+    CHECK(setreuid(-1, 1001) == 0, "setreuid to the saved id");
+    ids(1000, 1001, 1001, "setreuid back to the saved id");
+}
+
+// dropping every root id clears the caps, setuid(0) can't get them back
+static void dropAll() {
+    unsigned eff, perm;
+    CHECK(setgid(2000) == 0, "setgid");
+    CHECK(setuid(2000) == 0, "setuid");
+    ids(2000, 2000, 2000, "setuid as root sets all ids");
+    caps(&eff, &perm);
+    CHECK(eff == 0 && perm == 0, "caps cleared");
+
+    errno = 0;
+    CHECK(setuid(0) == -1 && errno == EPERM, "setuid(0) unprivileged");
+    errno = 0;
+    CHECK(setresuid(-1, 0, -1) == -1 && errno == EPERM, "setresuid(0) unprivileged");
+    errno = 0;
+    CHECK(setgid(0) == -1 && errno == EPERM, "setgid(0) unprivileged");
+    errno = 0;
+    CHECK(chown(TEST_FILE, 2000, 2000) == -1 && errno == EPERM, "chown unprivileged");
+
+    errno = 0;
+    CHECK(setresgid(2000, 2001, 2002) == -1 && errno == EPERM, "setresgid foreign ids");
+    CHECK(setgid(2000) == 0, "setgid to the real gid");
+}
+
+static void gids() {
+    gid_t r, e, s;
+    CHECK(setresgid(3000, 3001, 3002) == 0, "setresgid");
+    CHECK(getresgid(&r, &e, &s) == 0 && r == 3000 && e == 3001 && s == 3002, "getresgid");
+    CHECK(setuid(3000) == 0, "setuid");
+
+    CHECK(setgid(3002) == 0, "setgid to the saved gid");
+    CHECK(getresgid(&r, &e, &s) == 0 && r == 3000 && e == 3002 && s == 3002, "setgid unprivileged");
+
+    CHECK(setregid(-1, 3000) == 0, "setregid to the real gid");
+    CHECK(getresgid(&r, &e, &s) == 0 && e == 3000 && s == 3002, "saved gid unchanged");
+}
+
+// with PR_SET_KEEPCAPS the permitted set survives, the effective set doesn't
+static void keepCaps() {
+    unsigned eff, perm;
+    CHECK(prctl(PR_SET_KEEPCAPS, 1) == 0, "PR_SET_KEEPCAPS");
+    CHECK(prctl(PR_GET_KEEPCAPS) == 1, "PR_GET_KEEPCAPS");
+    CHECK(setresuid(4000, 4000, 4000) == 0, "setresuid");
+    caps(&eff, &perm);
+    CHECK(eff == 0 && perm != 0, "permitted kept, effective cleared");
+}
+
+int main() {
+    if (geteuid() != 0) {
+        printf("SKIP: needs root\n");
+        return 0;
+    }
+
+    unlink(TEST_FILE);
+    FILE *f = fopen(TEST_FILE, "w");
+    CHECK(f != NULL, "create");
+    fclose(f);
+
+    run(savedRoot);
+    run(reuid);
+    run(dropAll);
+    run(gids);
+    run(keepCaps);
+    unlink(TEST_FILE);
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t got_sigio = 0;
+static volatile sig_atomic_t got_sig = 0;
+static volatile sig_atomic_t got_fd = -1;
+
+static void on_sigio(int sig) {
+    got_sigio = 1;
+}
+
+static void on_sig(int sig, siginfo_t *info, void *ctx) {
+    got_sig = sig;
+    got_fd = info->si_fd;
+}
+
+// wait_for waits up to about a second for flag to be set
+static int wait_for(volatile sig_atomic_t *flag) {
+    for (int i = 0; i < 100 && !*flag; i++) {
+        usleep(10000);
+    }
+    return *flag;
+}
+
+int main() {
+    struct sockaddr_in addr = {.sin_family = AF_INET, .sin_addr.s_addr = htonl(INADDR_LOOPBACK)};
+    socklen_t len = sizeof(addr);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0 || bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        listen(listener, 1) < 0 || getsockname(listener, (struct sockaddr *)&addr, &len) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    int client = socket(AF_INET, SOCK_STREAM, 0);
+    if (client < 0 || connect(client, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+
+    int server = accept(listener, NULL, NULL);
+    if (server < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    signal(SIGIO, on_sigio);
+    if (fcntl(server, F_SETOWN, getpid()) < 0 || fcntl(server, F_GETOWN) != getpid()) {
+        perror("F_SETOWN");
+        return 1;
+    }
+    if (fcntl(server, F_SETFL, fcntl(server, F_GETFL) | O_ASYNC) < 0) {
+        perror("F_SETFL");
+        return 1;
+    }
+
+    // the default signal is SIGIO
+    if (write(client, "a", 1) != 1 || !wait_for(&got_sigio)) {
+        fprintf(stderr, "no SIGIO when data arrived\n");
+        return 1;
+    }
+
+    char buf[16];
+    read(server, buf, sizeof(buf));
+
+    // F_SETSIG picks another signal, which carries the fd in si_fd
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = on_sig;
+    sa.sa_flags = SA_SIGINFO;
+    sigaction(SIGUSR1, &sa, NULL);
+
+    if (fcntl(server, F_SETSIG, SIGUSR1) < 0 || fcntl(server, F_GETSIG) != SIGUSR1) {
+        perror("F_SETSIG");
+        return 1;
+    }
+
+    got_sigio = 0;
+    if (write(client, "b", 1) != 1 || !wait_for(&got_sig)) {
+        fprintf(stderr, "no F_SETSIG signal when data arrived\n");
+        return 1;
+    }
+
+    if (got_sig != SIGUSR1 || got_fd != server) {
+        fprintf(stderr, "got signal %d fd %d, want %d fd %d\n", got_sig, got_fd, SIGUSR1, server);
+        return 1;
+    }
+
+    if (fcntl(server, F_SETSIG, 1000) >= 0) {
+        fprintf(stderr, "F_SETSIG accepted an invalid signal\n");
+        return 1;
+    }
+
+    close(server);
+    close(client);
+    close(listener);
+    printf("sigio works\n");
+    return 0;
+}
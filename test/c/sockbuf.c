@@ -0,0 +1,125 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+static int getbuf(int fd, int opt) {
+    int val = 0;
+    socklen_t len = sizeof(val);
+    CHECK(getsockopt(fd, SOL_SOCKET, opt, &val, &len) == 0, "getsockopt");
+    CHECK(len == sizeof(val), "getsockopt len");
+    return val;
+}
+
+// fill writes into a connection nobody reads until it would block and returns
+// how much got buffered. size 0 keeps the default buffers.
+static long fill(int size) {
+    int lfd = socket(AF_INET, SOCK_STREAM, 0);
+    CHECK(lfd >= 0, "socket");
+    if (size > 0) {
+        CHECK(setsockopt(lfd, SOL_SOCKET, SO_RCVBUF, &size, sizeof(size)) == 0, "listener SO_RCVBUF");
+    }
+
+    struct sockaddr_in addr = {0};
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    CHECK(bind(lfd, (struct sockaddr *)&addr, sizeof(addr)) == 0, "bind");
+    CHECK(listen(lfd, 1) == 0, "listen");
+    CHECK(getsockname(lfd, (struct sockaddr *)&addr, &alen) == 0, "getsockname");
+
+    int cfd = socket(AF_INET, SOCK_STREAM, 0);
+    CHECK(cfd >= 0, "socket");
+    if (size > 0) {
+        CHECK(setsockopt(cfd, SOL_SOCKET, SO_SNDBUF, &size, sizeof(size)) == 0, "client SO_SNDBUF");
+    }
+    CHECK(connect(cfd, (struct sockaddr *)&addr, sizeof(addr)) == 0, "connect");
+    int afd = accept(lfd, NULL, NULL);
+    CHECK(afd >= 0, "accept");
+    if (size > 0) {
+        // the accepted socket inherits the listener's size
+        CHECK(getbuf(afd, SO_RCVBUF) == getbuf(lfd, SO_RCVBUF), "accepted SO_RCVBUF");
+    }
+
+    CHECK(fcntl(cfd, F_SETFL, O_NONBLOCK) == 0, "O_NONBLOCK");
+    char buf[4096];
+    memset(buf, 'x', sizeof(buf));
+    long total = 0;
+    int idle = 0;
+    // the data drains through the host in the background, stop once a few
+    // rounds in a row make no progress
+    while (idle < 20) {
+        ssize_t n = write(cfd, buf, sizeof(buf));
+        if (n > 0) {
+            total += n;
+            idle = 0;
+            continue;
+        }
+        CHECK(n < 0 && errno == EAGAIN, "write");
+        idle++;
+        usleep(10000);
+    }
+
+    // flow control: a full buffer isn't writable
+    struct pollfd pfd = {.fd = cfd, .events = POLLOUT};
+    CHECK(poll(&pfd, 1, 0) == 0, "full socket reported writable");
+
+    close(afd);
+    close(cfd);
+    close(lfd);
+    return total;
+}
+
+int main() {
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    CHECK(fd >= 0, "socket");
+
+    int val = 8192;
+    CHECK(setsockopt(fd, SOL_SOCKET, SO_RCVBUF, &val, sizeof(val)) == 0, "SO_RCVBUF");
+    CHECK(getbuf(fd, SO_RCVBUF) == 2 * val, "SO_RCVBUF is doubled");
+    val = 16384;
+    CHECK(setsockopt(fd, SOL_SOCKET, SO_SNDBUF, &val, sizeof(val)) == 0, "SO_SNDBUF");
+    CHECK(getbuf(fd, SO_SNDBUF) == 2 * val, "SO_SNDBUF is doubled");
+
+    // tiny values are raised to the minimum
+    val = 1;
+    CHECK(setsockopt(fd, SOL_SOCKET, SO_RCVBUF, &val, sizeof(val)) == 0, "SO_RCVBUF 1");
+    CHECK(getbuf(fd, SO_RCVBUF) >= 2048, "SO_RCVBUF minimum");
+    close(fd);
+
+    long small = fill(4096);
+    long dflt = fill(0);
+    printf("buffered %ld bytes with 4KB buffers, %ld with the default\n", small, dflt);
+    CHECK(small > 0 && small < dflt, "small buffers hold less");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+static void check_pair(int type) {
+    int sv[2];
+    CHECK(socketpair(AF_UNIX, type | SOCK_NONBLOCK | SOCK_CLOEXEC, 0, sv) == 0, "socketpair");
+
+    for (int i = 0; i < 2; i++) {
+        CHECK(fcntl(sv[i], F_GETFD) & FD_CLOEXEC, "FD_CLOEXEC");
+        CHECK(fcntl(sv[i], F_GETFL) & O_NONBLOCK, "O_NONBLOCK");
+
+        char c;
+        errno = 0;
+        CHECK(read(sv[i], &c, 1) == -1 && errno == EAGAIN, "empty read doesn't block");
+    }
+
+    char buf[16];
+    CHECK(write(sv[0], "ping", 4) == 4, "write 0 -> 1");
+    CHECK(read(sv[1], buf, sizeof(buf)) == 4 && memcmp(buf, "ping", 4) == 0, "read 0 -> 1");
+    CHECK(write(sv[1], "pong", 4) == 4, "write 1 -> 0");
+    CHECK(read(sv[0], buf, sizeof(buf)) == 4 && memcmp(buf, "pong", 4) == 0, "read 1 -> 0");
+
+    close(sv[0]);
+    close(sv[1]);
+
+    // without the flags neither attribute is set
+    CHECK(socketpair(AF_UNIX, type, 0, sv) == 0, "plain socketpair");
+    for (int i = 0; i < 2; i++) {
+        CHECK(!(fcntl(sv[i], F_GETFD) & FD_CLOEXEC), "no FD_CLOEXEC");
+        CHECK(!(fcntl(sv[i], F_GETFL) & O_NONBLOCK), "no O_NONBLOCK");
+    }
+    close(sv[0]);
+    close(sv[1]);
+}
+
+int main() {
+    check_pair(SOCK_STREAM);
+    check_pair(SOCK_DGRAM);
+    check_pair(SOCK_SEQPACKET);
+
+    int sv[2];
+    errno = 0;
+    CHECK(socketpair(AF_UNIX, SOCK_STREAM | O_APPEND, 0, sv) == -1 && errno == EINVAL, "unknown flag");
+
+    // a failed copy out of the fds doesn't leak them
+    int before = dup(0);
+    close(before);
+    errno = 0;
+    CHECK(syscall(SYS_socketpair, AF_UNIX, SOCK_STREAM | SOCK_CLOEXEC, 0, 1) == -1 && errno == EFAULT, "bad address");
+    int after = dup(0);
+    CHECK(after == before, "fds leaked on EFAULT");
+    close(after);
+
+    printf("PASS\n");
+    return 0;
+}
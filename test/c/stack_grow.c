@@ -0,0 +1,73 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// The main stack grows on demand past the initially mapped 8MB up to
+// RLIMIT_STACK, and running past the limit ends in SIGSEGV.
+
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define FRAME 4096
+
+static int recurse(long depth) {
+  volatile char buf[FRAME];
+  memset((char *)buf, 1, sizeof(buf));
+  if (depth == 0)
+    return buf[0];
+  return recurse(depth - 1) + buf[FRAME - 1];
+}
+
+static int run(long bytes) {
+  pid_t pid = fork();
+  if (pid == 0) {
+    recurse(bytes / FRAME);
+    _exit(0);
+  }
+
+  int status;
+  waitpid(pid, &status, 0);
+  return status;
+}
+
+int main() {
+  struct rlimit rl = {32 << 20, 32 << 20};
+  if (setrlimit(RLIMIT_STACK, &rl) != 0) {
+    perror("setrlimit");
+    return 1;
+  }
+
+  int status = run(24 << 20);
+  if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+    printf("recursing 24MB under a 32MB limit failed, status %x\n", status);
+    goto fail;
+  }
+
+  status = run(64 << 20);
+  if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGSEGV) {
+    printf("recursing past the limit should SIGSEGV, status %x\n", status);
+    goto fail;
+  }
+
+  printf("stack grow test pass\n");
+  return 0;
+
+fail:
+  printf("stack grow test fail\n");
+  return 1;
+}
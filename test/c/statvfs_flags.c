@@ -0,0 +1,62 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/statvfs.h>
+#include <unistd.h>
+
+#define MOUNT_POINT "/tmp/statvfs_flags_test"
+
+int main() {
+    mkdir(MOUNT_POINT, 0755);
+    if (mount("tmpfs", MOUNT_POINT, "tmpfs", MS_RDONLY | MS_NOSUID | MS_NOEXEC, NULL) < 0) {
+        if (errno == EPERM) {
+            printf("statvfs_flags: no permission to mount, skip\n");
+            return 0;
+        }
+        perror("mount");
+        return 1;
+    }
+
+    struct statvfs st;
+    if (statvfs(MOUNT_POINT, &st) < 0) {
+        perror("statvfs");
+        return 1;
+    }
+
+    unsigned long want = ST_RDONLY | ST_NOSUID | ST_NOEXEC;
+    if ((st.f_flag & want) != want) {
+        fprintf(stderr, "f_flag %lx, want %lx set\n", st.f_flag, want);
+        return 1;
+    }
+
+    if (statvfs("/proc", &st) < 0) {
+        perror("statvfs");
+        return 1;
+    }
+
+    if (st.f_flag & ST_RDONLY) {
+        fprintf(stderr, "/proc is reported read-only, f_flag %lx\n", st.f_flag);
+        return 1;
+    }
+
+    umount(MOUNT_POINT);
+    rmdir(MOUNT_POINT);
+    printf("statvfs_flags works\n");
+    return 0;
+}
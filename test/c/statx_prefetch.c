@@ -0,0 +1,187 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Run with StatxPrefetch on. Read a directory of many files, which fires the async
+// statx of its entries, then statx every entry and compare the prefetched result
+// with a sync fstat of the entry opened afterwards. Files truncated, written or
+// chmod-ed between the readdir and the statx have to show their new attributes.
+// The time of the statx of the prefetched entries is printed along with the time
+// of the same statx on a directory which was never read.
+
+#define _GNU_SOURCE
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define FILES 300
+
+static char dir[] = "/tmp/statx_prefetch_XXXXXX";
+static char syncdir[] = "/tmp/statx_sync_XXXXXX";
+
+static long long nowus() {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000000LL + ts.tv_nsec / 1000;
+}
+
+static void populate(int dirfd) {
+    char name[64];
+    char data[FILES];
+    memset(data, 'x', sizeof(data));
+    for (int i = 0; i < FILES; i++) {
+        sprintf(name, "f%d", i);
+        int fd = openat(dirfd, name, O_CREAT | O_WRONLY, 0600 | (i & 0077));
+        CHECK(fd >= 0, "create");
+        CHECK(write(fd, data, i) == i, "write");
+        close(fd);
+    }
+    CHECK(mkdirat(dirfd, "sub", 0755) == 0, "mkdir");
+    CHECK(symlinkat("f1", dirfd, "link") == 0, "symlink");
+}
+
+static void cleanup(int dirfd, const char *path) {
+    char name[64];
+    for (int i = 0; i < FILES; i++) {
+        sprintf(name, "f%d", i);
+        unlinkat(dirfd, name, 0);
+    }
+    unlinkat(dirfd, "link", 0);
+    unlinkat(dirfd, "sub", AT_REMOVEDIR);
+    close(dirfd);
+    rmdir(path);
+}
+
+static long long statall(int dirfd) {
+    char name[64];
+    struct statx sx;
+    long long start = nowus();
+    for (int i = 0; i < FILES; i++) {
+        sprintf(name, "f%d", i);
+        CHECK(statx(dirfd, name, AT_SYMLINK_NOFOLLOW, STATX_BASIC_STATS, &sx) == 0, "statx");
+    }
+    return nowus() - start;
+}
+
+static void compare(struct statx *sx, struct stat *st, const char *name) {
+    char msg[128];
+    sprintf(msg, "attributes of %s", name);
+    CHECK((sx->stx_mask & STATX_BASIC_STATS) == STATX_BASIC_STATS, msg);
+    CHECK(sx->stx_ino == st->st_ino, msg);
+    CHECK(sx->stx_mode == st->st_mode, msg);
+    CHECK(sx->stx_nlink == st->st_nlink, msg);
+    CHECK(sx->stx_uid == st->st_uid && sx->stx_gid == st->st_gid, msg);
+    CHECK(sx->stx_size == (unsigned long long)st->st_size, msg);
+    CHECK(sx->stx_blocks == (unsigned long long)st->st_blocks, msg);
+    CHECK(sx->stx_mtime.tv_sec == st->st_mtim.tv_sec &&
+          sx->stx_mtime.tv_nsec == st->st_mtim.tv_nsec, msg);
+    CHECK(sx->stx_ctime.tv_sec == st->st_ctim.tv_sec &&
+          sx->stx_ctime.tv_nsec == st->st_ctim.tv_nsec, msg);
+}
+
+int main() {
+    char name[64];
+    CHECK(mkdtemp(dir) != NULL, "mkdtemp");
+    int dirfd = open(dir, O_RDONLY | O_DIRECTORY);
+    CHECK(dirfd >= 0, "open dir");
+    populate(dirfd);
+
+    DIR *d = fdopendir(dup(dirfd));
+    CHECK(d != NULL, "fdopendir");
+    int count = 0;
+    while (readdir(d) != NULL) {
+        count++;
+    }
+    closedir(d);
+    CHECK(count == FILES + 4, "readdir count");
+
+    // changed after the readdir, the prefetched statx of f7 is out of date
+    CHECK(truncate(strcat(strcpy(name, dir), "/f7"), 4096) == 0, "truncate");
+
+    // the open looks f9 up, the write afterwards has to drop its prefetched statx
+    int wfd = openat(dirfd, "f9", O_WRONLY | O_APPEND);
+    CHECK(wfd >= 0, "open f9");
+    CHECK(write(wfd, "0123456789", 10) == 10, "write f9");
+    close(wfd);
+
+    CHECK(fchmodat(dirfd, "f11", 0640, 0) == 0, "chmod f11");
+
+    const char *extra[] = {"sub", "link"};
+    for (int i = 0; i < FILES + 2; i++) {
+        if (i < FILES) {
+            sprintf(name, "f%d", i);
+        } else {
+            strcpy(name, extra[i - FILES]);
+        }
+
+        struct statx sx;
+        CHECK(statx(dirfd, name, AT_SYMLINK_NOFOLLOW, STATX_BASIC_STATS, &sx) == 0, "statx");
+
+        int fd = openat(dirfd, name, O_PATH | O_NOFOLLOW);
+        CHECK(fd >= 0, "open entry");
+        struct stat st;
+        CHECK(fstat(fd, &st) == 0, "fstat");
+        close(fd);
+
+        compare(&sx, &st, name);
+        if (i == 7) {
+            CHECK(sx.stx_size == 4096, "size after truncate");
+        } else if (i == 9) {
+            CHECK(sx.stx_size == 19, "size after write");
+        } else if (i == 11) {
+            CHECK((sx.stx_mode & 07777) == 0640, "mode after chmod");
+        }
+    }
+    cleanup(dirfd, dir);
+
+    // the same lookups with and without a readdir before them
+    strcpy(dir, "/tmp/statx_prefetch_XXXXXX");
+    CHECK(mkdtemp(dir) != NULL, "mkdtemp");
+    dirfd = open(dir, O_RDONLY | O_DIRECTORY);
+    CHECK(dirfd >= 0, "open dir");
+    populate(dirfd);
+    CHECK(mkdtemp(syncdir) != NULL, "mkdtemp sync");
+    int syncfd = open(syncdir, O_RDONLY | O_DIRECTORY);
+    CHECK(syncfd >= 0, "open sync dir");
+    populate(syncfd);
+
+    d = fdopendir(dup(dirfd));
+    CHECK(d != NULL, "fdopendir");
+    while (readdir(d) != NULL) {
+    }
+    closedir(d);
+
+    long long async = statall(dirfd);
+    long long sync = statall(syncfd);
+    printf("statx of %d files: %lldus prefetched, %lldus sync\n", FILES, async, sync);
+    cleanup(dirfd, dir);
+    cleanup(syncfd, syncdir);
+
+    printf("PASS\n");
+    return 0;
+}
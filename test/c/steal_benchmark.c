@@ -0,0 +1,96 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Spawn all the worker threads from one thread, so that they start on the queue
+// of a single vcpu, give them uneven amounts of work and print how many cpus
+// ran them and the cpu utilization, e.g. "./steal_benchmark 16 200". Without
+// work stealing the workers stay on few vcpus and the utilization is low.
+
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+#include <unistd.h>
+
+#define MAX_THREADS 256
+#define MAX_CPUS 1024
+
+static int workMs;
+static char cpuUsed[MAX_CPUS];
+
+static long long now_ns(clockid_t clock) {
+    struct timespec ts;
+    clock_gettime(clock, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+static void *worker(void *arg) {
+    long idx = (long)arg;
+    // every fourth worker has four times the work
+    long long work = (idx % 4 == 0 ? 4 : 1) * workMs * 1000000LL;
+    long long start = now_ns(CLOCK_THREAD_CPUTIME_ID);
+    volatile unsigned long x = idx;
+    while (now_ns(CLOCK_THREAD_CPUTIME_ID) - start < work) {
+        for (int i = 0; i < 10000; i++) {
+            x = x * 6364136223846793005UL + 1442695040888963407UL;
+        }
+
+        int cpu = sched_getcpu();
+        if (cpu >= 0 && cpu < MAX_CPUS) {
+            cpuUsed[cpu] = 1;
+        }
+    }
+
+    return NULL;
+}
+
+int main(int argc, char *argv[]) {
+    int threads = argc > 1 ? atoi(argv[1]) : 16;
+    workMs = argc > 2 ? atoi(argv[2]) : 200;
+    if (threads <= 0 || threads > MAX_THREADS || workMs <= 0) {
+        printf("usage: %s [threads <= %d] [work ms]\n", argv[0], MAX_THREADS);
+        return 1;
+    }
+
+    long cpus = sysconf(_SC_NPROCESSORS_ONLN);
+    pthread_t tids[MAX_THREADS];
+    long long start = now_ns(CLOCK_MONOTONIC);
+    long long cpuStart = now_ns(CLOCK_PROCESS_CPUTIME_ID);
+    for (long i = 0; i < threads; i++) {
+        if (pthread_create(&tids[i], NULL, worker, (void *)i) != 0) {
+            perror("pthread_create");
+            return 1;
+        }
+    }
+    for (int i = 0; i < threads; i++) {
+        pthread_join(tids[i], NULL);
+    }
+
+    long long wall = now_ns(CLOCK_MONOTONIC) - start;
+    long long cpu = now_ns(CLOCK_PROCESS_CPUTIME_ID) - cpuStart;
+    int used = 0;
+    for (int i = 0; i < MAX_CPUS; i++) {
+        used += cpuUsed[i];
+    }
+
+    long busy = threads < cpus ? threads : cpus;
+    printf("%d threads on %ld cpus: %d cpus used, wall %lld ms, cpu %lld ms, "
+           "utilization %lld%%\n",
+           threads, cpus, used, wall / 1000000, cpu / 1000000,
+           cpu * 100 / (wall * busy));
+    return 0;
+}
@@ -0,0 +1,209 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Creates a tun interface through /dev/net/tun passed through to the sandbox, e.g.
+//   docker run --cap-add NET_ADMIN --device /dev/net/tun ...
+// with "DevicePassthrough": ["10:200"] in /etc/quark/config.json. The test is
+// skipped when the device is missing or not allowed. When the interface can be
+// configured, a udp datagram routed to it is read from the tun fd and a packet
+// is written back.
+
+#include <stdio.h>
+#include <stdint.h>
+#include <string.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <linux/capability.h>
+#include <linux/if.h>
+#include <linux/if_tun.h>
+
+static int setAddr(int s, const char *name, unsigned long req, const char *addr) {
+    struct ifreq ifr;
+    memset(&ifr, 0, sizeof(ifr));
+    strncpy(ifr.ifr_name, name, IFNAMSIZ - 1);
+    struct sockaddr_in *sin = (struct sockaddr_in *)&ifr.ifr_addr;
+    sin->sin_family = AF_INET;
+    inet_pton(AF_INET, addr, &sin->sin_addr);
+    return ioctl(s, req, &ifr);
+}
+
+// persistNeedsCap checks that a child without CAP_NET_ADMIN can't make the
+// interface persistent or change its owner
+static int persistNeedsCap(int fd) {
+    pid_t pid = fork();
+    if (pid < 0) {
+        return -1;
+    }
+
+    if (pid == 0) {
+        struct __user_cap_header_struct hdr = {.version = _LINUX_CAPABILITY_VERSION_3};
+        struct __user_cap_data_struct data[2];
+        if (syscall(SYS_capget, &hdr, data) < 0) {
+            _exit(2);
+        }
+        data[CAP_NET_ADMIN / 32].effective &= ~(1u << (CAP_NET_ADMIN % 32));
+        if (syscall(SYS_capset, &hdr, data) < 0) {
+            _exit(2);
+        }
+
+        if (ioctl(fd, TUNSETPERSIST, 1) == 0 || errno != EPERM) {
+            printf("TUNSETPERSIST without CAP_NET_ADMIN: %s\n", strerror(errno));
+            _exit(1);
+        }
+        if (ioctl(fd, TUNSETOWNER, 0) == 0 || errno != EPERM) {
+            printf("TUNSETOWNER without CAP_NET_ADMIN: %s\n", strerror(errno));
+            _exit(1);
+        }
+        _exit(0);
+    }
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+// configure gives the interface 10.203.0.1/24 and brings it up
+static int configure(const char *name) {
+    int s = socket(AF_INET, SOCK_DGRAM, 0);
+    if (s < 0) {
+        return -1;
+    }
+
+    int ret = -1;
+    struct ifreq ifr;
+    memset(&ifr, 0, sizeof(ifr));
+    strncpy(ifr.ifr_name, name, IFNAMSIZ - 1);
+    if (setAddr(s, name, SIOCSIFADDR, "10.203.0.1") < 0 ||
+        setAddr(s, name, SIOCSIFNETMASK, "255.255.255.0") < 0 ||
+        ioctl(s, SIOCGIFFLAGS, &ifr) < 0) {
+        goto out;
+    }
+
+    ifr.ifr_flags |= IFF_UP | IFF_RUNNING;
+    ret = ioctl(s, SIOCSIFFLAGS, &ifr);
+out:
+    close(s);
+    return ret;
+}
+
+int main() {
+    int fd = open("/dev/net/tun", O_RDWR);
+    if (fd < 0) {
+        printf("skip: open /dev/net/tun fail: %s\n", strerror(errno));
+        return 0;
+    }
+
+    struct ifreq ifr;
+    memset(&ifr, 0, sizeof(ifr));
+    ifr.ifr_flags = IFF_TUN | IFF_NO_PI;
+    strcpy(ifr.ifr_name, "qtun%d");
+    if (ioctl(fd, TUNSETIFF, &ifr) < 0) {
+        if (errno == EPERM) {
+            printf("skip: TUNSETIFF needs CAP_NET_ADMIN\n");
+            return 0;
+        }
+
+        printf("TUNSETIFF fail: %s\n", strerror(errno));
+        return 1;
+    }
+
+    // the template is replaced by the allocated name
+    if (strncmp(ifr.ifr_name, "qtun", 4) != 0 || strchr(ifr.ifr_name, '%') != NULL) {
+        printf("TUNSETIFF returns name %s\n", ifr.ifr_name);
+        return 1;
+    }
+
+    struct ifreq got;
+    memset(&got, 0, sizeof(got));
+    if (ioctl(fd, TUNGETIFF, &got) < 0 || strcmp(got.ifr_name, ifr.ifr_name) != 0) {
+        printf("TUNGETIFF fail: %s %s\n", got.ifr_name, strerror(errno));
+        return 1;
+    }
+
+    if (persistNeedsCap(fd) < 0) {
+        printf("the tun setters don't check CAP_NET_ADMIN\n");
+        return 1;
+    }
+
+    if (configure(ifr.ifr_name) < 0) {
+        printf("created %s, skip packet io: configure fail: %s\n", ifr.ifr_name, strerror(errno));
+        return 0;
+    }
+
+    int s = socket(AF_INET, SOCK_DGRAM, 0);
+    struct sockaddr_in dst;
+    memset(&dst, 0, sizeof(dst));
+    dst.sin_family = AF_INET;
+    dst.sin_port = htons(9);
+    inet_pton(AF_INET, "10.203.0.2", &dst.sin_addr);
+    const char msg[] = "quark tun";
+    if (sendto(s, msg, sizeof(msg), 0, (struct sockaddr *)&dst, sizeof(dst)) < 0) {
+        printf("sendto fail: %s\n", strerror(errno));
+        return 1;
+    }
+
+    // the first packets may be ipv6 traffic of the new interface
+    uint8_t pkt[2048];
+    for (;;) {
+        struct pollfd pfd = {.fd = fd, .events = POLLIN};
+        if (poll(&pfd, 1, 3000) != 1) {
+            printf("no packet on %s\n", ifr.ifr_name);
+            return 1;
+        }
+
+        ssize_t n = read(fd, pkt, sizeof(pkt));
+        if (n < 0) {
+            printf("read tun fail: %s\n", strerror(errno));
+            return 1;
+        }
+
+        struct iphdr *ip = (struct iphdr *)pkt;
+        if (n < (ssize_t)sizeof(*ip) || ip->version != 4 || ip->protocol != IPPROTO_UDP) {
+            continue;
+        }
+
+        if (ip->daddr != dst.sin_addr.s_addr ||
+            memcmp(pkt + n - sizeof(msg), msg, sizeof(msg)) != 0) {
+            printf("unexpected udp packet of %zd bytes\n", n);
+            return 1;
+        }
+
+        // echo the packet back with the addresses swapped
+        uint32_t saddr = ip->saddr;
+        ip->saddr = ip->daddr;
+        ip->daddr = saddr;
+        if (write(fd, pkt, n) != n) {
+            printf("write tun fail: %s\n", strerror(errno));
+            return 1;
+        }
+
+        break;
+    }
+
+    close(s);
+    close(fd);
+    printf("tun %s works\n", ifr.ifr_name);
+    return 0;
+}
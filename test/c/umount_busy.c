@@ -0,0 +1,91 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                   \
+    do {                                                   \
+        if (!(cond)) {                                     \
+            printf("FAIL: %s (errno %d)\n", msg, errno);   \
+            exit(1);                                       \
+        }                                                  \
+    } while (0)
+
+#define MOUNT_POINT "/tmp/umount_busy_test"
+
+static void busy(int flags, const char *msg) {
+    errno = 0;
+    CHECK(umount2(MOUNT_POINT, flags) == -1 && errno == EBUSY, msg);
+}
+
+int main() {
+    mkdir(MOUNT_POINT, 0755);
+    if (mount("tmpfs", MOUNT_POINT, "tmpfs", 0, NULL) < 0) {
+        if (errno == EPERM) {
+            printf("umount_busy: no permission to mount, skip\n");
+            return 0;
+        }
+        CHECK(0, "mount");
+    }
+
+    // an open file
+    int fd = open(MOUNT_POINT "/file", O_CREAT | O_RDWR, 0644);
+    CHECK(fd >= 0, "open");
+    busy(0, "umount with an open file");
+    busy(MNT_FORCE, "MNT_FORCE with an open file");
+    close(fd);
+
+    // an open directory, the mount root itself
+    fd = open(MOUNT_POINT, O_RDONLY | O_DIRECTORY);
+    CHECK(fd >= 0, "open mount root");
+    busy(0, "umount with the root open");
+    close(fd);
+
+    // the working directory
+    CHECK(chdir(MOUNT_POINT) == 0, "chdir");
+    busy(0, "umount with the cwd in the mount");
+    CHECK(mkdir("dir", 0755) == 0 && chdir("dir") == 0, "chdir to a subdir");
+    busy(0, "umount with the cwd below the mount root");
+    CHECK(chdir("/") == 0, "chdir /");
+
+    errno = 0;
+    CHECK(umount2(MOUNT_POINT, 0x100) == -1 && errno == EINVAL, "unknown flag");
+
+    // once released the plain umount works
+    CHECK(umount2(MOUNT_POINT, 0) == 0, "umount");
+
+    // MNT_FORCE on an idle mount is a regular umount
+    CHECK(mount("tmpfs", MOUNT_POINT, "tmpfs", 0, NULL) == 0, "mount again");
+    CHECK(umount2(MOUNT_POINT, MNT_FORCE) == 0, "MNT_FORCE umount");
+
+    // MNT_DETACH doesn't wait for the users
+    CHECK(mount("tmpfs", MOUNT_POINT, "tmpfs", 0, NULL) == 0, "mount for detach");
+    fd = open(MOUNT_POINT "/file", O_CREAT | O_RDWR, 0644);
+    CHECK(fd >= 0, "open for detach");
+    CHECK(umount2(MOUNT_POINT, MNT_DETACH) == 0, "MNT_DETACH");
+    CHECK(write(fd, "x", 1) == 1, "the detached file is still usable");
+    close(fd);
+
+    struct stat st;
+    CHECK(stat(MOUNT_POINT "/file", &st) == -1 && errno == ENOENT, "detached mount is gone");
+    rmdir(MOUNT_POINT);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,57 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// uname reports the hostname of the UTS namespace, a release starting with
+// major.minor and the x86_64 machine.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/utsname.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define HOSTNAME "quark-uname-test"
+
+int main() {
+    struct utsname u;
+
+    CHECK(unshare(CLONE_NEWUTS) == 0, "unshare CLONE_NEWUTS");
+    CHECK(sethostname(HOSTNAME, strlen(HOSTNAME)) == 0, "sethostname");
+    CHECK(uname(&u) == 0, "uname");
+
+    CHECK(strcmp(u.sysname, "Linux") == 0, "sysname");
+    CHECK(strcmp(u.nodename, HOSTNAME) == 0, "nodename");
+    CHECK(strcmp(u.machine, "x86_64") == 0, "machine");
+
+    int major, minor;
+    char dot;
+    CHECK(sscanf(u.release, "%d%c%d", &major, &dot, &minor) == 3 && dot == '.', "release");
+    CHECK(major >= 2 && minor >= 0, "release version");
+    printf("release %s\n", u.release);
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// unshare(CLONE_NEWNS): the child gets a copy of the mounts of its parent, keeps
+// its cwd, and a tmpfs it mounts afterwards is not seen by the parent. Has to
+// run as root, an unprivileged unshare fails with EPERM.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+#define TESTDIR "/tmp/unshare_mount_dir"
+#define TESTFILE TESTDIR "/file"
+
+static void waitchild(pid_t pid) {
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+}
+
+static void nslink(pid_t pid, char *buf, size_t len) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/ns/mnt", pid);
+    ssize_t n = readlink(path, buf, len - 1);
+    CHECK(n > 0, "readlink /proc/<pid>/ns/mnt");
+    buf[n] = 0;
+}
+
+int main() {
+    CHECK(getuid() == 0, "has to run as root");
+    CHECK(mkdir(TESTDIR, 0755) == 0 || errno == EEXIST, "mkdir");
+
+    int toParent[2], toChild[2];
+    char c = 0;
+    CHECK(pipe(toParent) == 0 && pipe(toChild) == 0, "pipe");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        close(toChild[1]);
+        CHECK(chdir("/tmp") == 0, "chdir");
+        CHECK(unshare(CLONE_NEWNS) == 0, "unshare");
+        CHECK(mount(NULL, "/", NULL, MS_REC | MS_PRIVATE, NULL) == 0, "make / private");
+
+        char cwd[256];
+        CHECK(getcwd(cwd, sizeof(cwd)) != NULL && strcmp(cwd, "/tmp") == 0, "cwd moved");
+        CHECK(access("/proc/self/status", R_OK) == 0, "/proc isn't mounted in the copy");
+
+        CHECK(mount("tmpfs", TESTDIR, "tmpfs", 0, NULL) == 0, "mount tmpfs");
+        int fd = open(TESTFILE, O_CREAT | O_WRONLY, 0644);
+        CHECK(fd >= 0, "create a file in the tmpfs");
+        close(fd);
+
+        CHECK(write(toParent[1], &c, 1) == 1, "write");
+        CHECK(read(toChild[0], &c, 1) == 1, "read");
+
+        CHECK(access(TESTFILE, F_OK) == 0, "the file is gone");
+        CHECK(umount(TESTDIR) == 0, "umount");
+        CHECK(access(TESTFILE, F_OK) < 0, "the file is still there after umount");
+        exit(0);
+    }
+
+    close(toParent[1]);
+    CHECK(read(toParent[0], &c, 1) == 1, "read");
+
+    errno = 0;
+    CHECK(access(TESTFILE, F_OK) < 0 && errno == ENOENT, "the mount of the child is seen");
+
+    char link[64], other[64];
+    nslink(getpid(), link, sizeof(link));
+    nslink(pid, other, sizeof(other));
+    CHECK(strcmp(link, other) != 0, "the child is in the same mount namespace");
+
+    CHECK(write(toChild[1], &c, 1) == 1, "write");
+    waitchild(pid);
+
+    pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        CHECK(setuid(65534) == 0, "setuid");
+        errno = 0;
+        CHECK(unshare(CLONE_NEWNS) < 0 && errno == EPERM, "unprivileged unshare");
+        exit(0);
+    }
+    waitchild(pid);
+
+    rmdir(TESTDIR);
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Open a burst of host files from several threads at once, as the lookups do with
+// UringOpenClose, and check that every fd reads its own file, that fstat agrees
+// with stat and that closing them all gives the fds back.
+
+#define _GNU_SOURCE
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define THREADS 8
+#define FILES 64
+
+static char dir[] = "/tmp/uring_open_XXXXXX";
+static int fds[THREADS][FILES];
+
+static void path(char *buf, int i) {
+    sprintf(buf, "%s/f%d", dir, i);
+}
+
+static int countFds() {
+    DIR *d = opendir("/proc/self/fd");
+    CHECK(d != NULL, "opendir /proc/self/fd");
+
+    int n = 0;
+    while (readdir(d) != NULL) {
+        n++;
+    }
+    closedir(d);
+    return n;
+}
+
+static void *opener(void *arg) {
+    long t = (long)arg;
+    char name[64], buf[64], want[64];
+
+    for (int i = 0; i < FILES; i++) {
+        path(name, i);
+        int fd = open(name, O_RDONLY);
+        CHECK(fd >= 0, "open");
+        fds[t][i] = fd;
+
+        sprintf(want, "file %d", i);
+        ssize_t n = pread(fd, buf, sizeof(buf), 0);
+        CHECK(n == (ssize_t)strlen(want) && memcmp(buf, want, n) == 0, "content");
+
+        struct stat fst, st;
+        CHECK(fstat(fd, &fst) == 0 && stat(name, &st) == 0, "stat");
+        CHECK(fst.st_ino == st.st_ino && fst.st_dev == st.st_dev, "fstat ino");
+    }
+
+    return NULL;
+}
+
+int main() {
+    char name[64], data[64];
+    CHECK(mkdtemp(dir) != NULL, "mkdtemp");
+    for (int i = 0; i < FILES; i++) {
+        path(name, i);
+        int fd = open(name, O_CREAT | O_WRONLY, 0644);
+        CHECK(fd >= 0, "create");
+        sprintf(data, "file %d", i);
+        CHECK(write(fd, data, strlen(data)) == (ssize_t)strlen(data), "write");
+        close(fd);
+    }
+
+    int base = countFds();
+
+    pthread_t threads[THREADS];
+    for (long t = 0; t < THREADS; t++) {
+        CHECK(pthread_create(&threads[t], NULL, opener, (void *)t) == 0, "pthread_create");
+    }
+    for (int t = 0; t < THREADS; t++) {
+        pthread_join(threads[t], NULL);
+    }
+
+    CHECK(countFds() == base + THREADS * FILES, "fd count after open");
+
+    // every open got its own fd
+    static char seen[1 << 16];
+    for (int t = 0; t < THREADS; t++) {
+        for (int i = 0; i < FILES; i++) {
+            int fd = fds[t][i];
+            CHECK(fd < (int)sizeof(seen) && !seen[fd], "distinct fds");
+            seen[fd] = 1;
+        }
+    }
+
+    for (int t = 0; t < THREADS; t++) {
+        for (int i = 0; i < FILES; i++) {
+            CHECK(close(fds[t][i]) == 0, "close");
+        }
+    }
+
+    CHECK(countFds() == base, "fd count after close");
+    for (int t = 0; t < THREADS; t++) {
+        errno = 0;
+        CHECK(fcntl(fds[t][0], F_GETFD) == -1 && errno == EBADF, "closed fd");
+    }
+
+    for (int i = 0; i < FILES; i++) {
+        path(name, i);
+        unlink(name);
+    }
+    rmdir(dir);
+
+    printf("PASS\n");
+    return 0;
+}
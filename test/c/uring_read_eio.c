@@ -0,0 +1,60 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// An io_uring read which fails on the host returns its errno to the guest read
+// instead of a short read, and the next read works. Run with a qvisor built with
+// "--features fault_inject", with
+//   "UringIO": true, "UringFallback": true
+// in /etc/quark/config.json and with
+//   QUARK_FAULT_INJECT=uring_read=EIO:1@uring_read_eio.data
+// in the environment of the runtime, so that only the first host read of the data
+// file fails.
+
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+int main(void) {
+    const char *path = "/tmp/uring_read_eio.data";
+    const char data[] = "uring read eio";
+    char buf[64];
+
+    int fd = open(path, O_CREAT | O_TRUNC | O_RDWR, 0600);
+    CHECK(fd >= 0, "open");
+    CHECK(write(fd, data, sizeof(data)) == sizeof(data), "write");
+    CHECK(fsync(fd) == 0, "fsync");
+
+    errno = 0;
+    CHECK(pread(fd, buf, sizeof(buf), 0) == -1 && errno == EIO, "pread returns EIO");
+
+    memset(buf, 0, sizeof(buf));
+    CHECK(pread(fd, buf, sizeof(buf), 0) == sizeof(data), "pread after the fault");
+    CHECK(memcmp(buf, data, sizeof(data)) == 0, "data after the fault");
+
+    close(fd);
+    unlink(path);
+    printf("PASS\n");
+    return 0;
+}
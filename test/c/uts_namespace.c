@@ -0,0 +1,84 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// sethostname and setdomainname in a new UTS namespace show up in uname, and
+// don't change the names seen by the parent.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/utsname.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define HOSTNAME "quark-uts-test"
+#define DOMAINNAME "quark.test"
+
+int main() {
+    struct utsname before, u;
+    CHECK(uname(&before) == 0, "uname");
+
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        CHECK(unshare(CLONE_NEWUTS) == 0, "unshare CLONE_NEWUTS");
+
+        // the name is len bytes, the rest of the buffer is ignored
+        CHECK(sethostname(HOSTNAME "-junk", strlen(HOSTNAME)) == 0, "sethostname");
+        CHECK(setdomainname(DOMAINNAME, strlen(DOMAINNAME)) == 0, "setdomainname");
+
+        char longName[128];
+        memset(longName, 'a', sizeof(longName));
+        CHECK(sethostname(longName, 65) == -1 && errno == EINVAL, "sethostname 65 bytes");
+
+        CHECK(uname(&u) == 0, "uname in child");
+        CHECK(strcmp(u.nodename, HOSTNAME) == 0, "nodename in child");
+        CHECK(strcmp(u.domainname, DOMAINNAME) == 0, "domainname in child");
+
+        char name[128];
+        CHECK(gethostname(name, sizeof(name)) == 0, "gethostname");
+        CHECK(strcmp(name, HOSTNAME) == 0, "gethostname in child");
+
+        // the name may be any bytes, they come back as they were set
+        const char raw[] = {'q', (char)0xff, (char)0x80, 'k', 0, 'x'};
+        CHECK(sethostname(raw, sizeof(raw)) == 0, "sethostname raw bytes");
+        CHECK(uname(&u) == 0, "uname after raw sethostname");
+        CHECK(memcmp(u.nodename, raw, sizeof(raw)) == 0 && u.nodename[sizeof(raw)] == 0,
+              "raw nodename");
+        exit(0);
+    }
+
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid, "waitpid");
+    CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+
+    CHECK(uname(&u) == 0, "uname after child");
+    CHECK(strcmp(u.nodename, before.nodename) == 0, "nodename changed in parent");
+    CHECK(strcmp(u.domainname, before.domainname) == 0, "domainname changed in parent");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,162 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Look up the vDSO symbols through AT_SYSINFO_EHDR, check the clock_gettime,
+// gettimeofday, time, clock_getres and getcpu of the vDSO against the system
+// calls and compare the cost of clock_gettime via the vDSO and the syscall.
+
+#define _GNU_SOURCE
+#include <elf.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <sys/syscall.h>
+#include <sys/sysinfo.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define ROUNDS 100000
+
+static Elf64_Sym *symtab;
+static const char *strtab;
+static unsigned nsyms;
+static unsigned long bias;
+
+static void load_vdso(void) {
+    Elf64_Ehdr *ehdr = (Elf64_Ehdr *)getauxval(AT_SYSINFO_EHDR);
+    CHECK(ehdr != NULL, "no AT_SYSINFO_EHDR");
+    CHECK(memcmp(ehdr->e_ident, ELFMAG, SELFMAG) == 0, "the vDSO isn't an ELF");
+
+    Elf64_Phdr *phdr = (Elf64_Phdr *)((char *)ehdr + ehdr->e_phoff);
+    Elf64_Dyn *dyn = NULL;
+    int loaded = 0;
+    for (int i = 0; i < ehdr->e_phnum; i++) {
+        if (phdr[i].p_type == PT_LOAD && !loaded) {
+            bias = (unsigned long)ehdr + phdr[i].p_offset - phdr[i].p_vaddr;
+            loaded = 1;
+        } else if (phdr[i].p_type == PT_DYNAMIC) {
+            dyn = (Elf64_Dyn *)((char *)ehdr + phdr[i].p_offset);
+        }
+    }
+    CHECK(loaded && dyn != NULL, "no PT_LOAD or PT_DYNAMIC in the vDSO");
+
+    for (; dyn->d_tag != DT_NULL; dyn++) {
+        if (dyn->d_tag == DT_SYMTAB)
+            symtab = (Elf64_Sym *)(dyn->d_un.d_ptr + bias);
+        else if (dyn->d_tag == DT_STRTAB)
+            strtab = (const char *)(dyn->d_un.d_ptr + bias);
+        else if (dyn->d_tag == DT_HASH)
+            nsyms = ((Elf32_Word *)(dyn->d_un.d_ptr + bias))[1];
+    }
+    CHECK(symtab != NULL && strtab != NULL && nsyms > 0,
+          "no symbol table in the vDSO");
+}
+
+static void *lookup(const char *name) {
+    for (unsigned i = 0; i < nsyms; i++) {
+        Elf64_Sym *sym = &symtab[i];
+        if (sym->st_shndx != SHN_UNDEF && strcmp(strtab + sym->st_name, name) == 0)
+            return (void *)(sym->st_value + bias);
+    }
+
+    printf("FAIL: %s isn't in the vDSO\n", name);
+    exit(1);
+}
+
+static long long ns(const struct timespec *ts) {
+    return ts->tv_sec * 1000000000LL + ts->tv_nsec;
+}
+
+static long long now_ns(void) {
+    struct timespec ts;
+    syscall(SYS_clock_gettime, CLOCK_MONOTONIC, &ts);
+    return ns(&ts);
+}
+
+int main() {
+    load_vdso();
+    int (*vdso_clock_gettime)(clockid_t, struct timespec *) =
+        lookup("__vdso_clock_gettime");
+    int (*vdso_gettimeofday)(struct timeval *, void *) =
+        lookup("__vdso_gettimeofday");
+    time_t (*vdso_time)(time_t *) = lookup("__vdso_time");
+    int (*vdso_clock_getres)(clockid_t, struct timespec *) =
+        lookup("__vdso_clock_getres");
+    long (*vdso_getcpu)(unsigned *, unsigned *, void *) =
+        lookup("__vdso_getcpu");
+
+    // the vDSO monotonic clock is between two syscall readings
+    struct timespec before, vdso, after;
+    CHECK(syscall(SYS_clock_gettime, CLOCK_MONOTONIC, &before) == 0,
+          "clock_gettime syscall");
+    CHECK(vdso_clock_gettime(CLOCK_MONOTONIC, &vdso) == 0,
+          "vDSO clock_gettime");
+    CHECK(syscall(SYS_clock_gettime, CLOCK_MONOTONIC, &after) == 0,
+          "clock_gettime syscall");
+    CHECK(ns(&before) <= ns(&vdso) && ns(&vdso) <= ns(&after),
+          "the vDSO CLOCK_MONOTONIC");
+
+    // the realtime clocks agree within a second
+    struct timespec real;
+    CHECK(syscall(SYS_clock_gettime, CLOCK_REALTIME, &real) == 0,
+          "clock_gettime syscall");
+    CHECK(vdso_clock_gettime(CLOCK_REALTIME, &vdso) == 0,
+          "vDSO clock_gettime");
+    CHECK(llabs(ns(&vdso) - ns(&real)) < 1000000000LL, "the vDSO CLOCK_REALTIME");
+
+    struct timeval tv;
+    CHECK(vdso_gettimeofday(&tv, NULL) == 0, "vDSO gettimeofday");
+    CHECK(labs(tv.tv_sec - real.tv_sec) <= 1, "the vDSO gettimeofday");
+    time_t t;
+    CHECK(labs(vdso_time(&t) - real.tv_sec) <= 1 && t >= real.tv_sec,
+          "the vDSO time");
+
+    struct timespec res;
+    CHECK(vdso_clock_getres(CLOCK_MONOTONIC, &res) == 0 && res.tv_sec == 0 &&
+              res.tv_nsec > 0,
+          "the vDSO clock_getres");
+
+    unsigned cpu = ~0u, node = ~0u;
+    CHECK(vdso_getcpu(&cpu, &node, NULL) == 0, "vDSO getcpu");
+    CHECK(cpu < (unsigned)get_nprocs_conf() && node == 0, "the vDSO getcpu");
+
+    long long start = now_ns();
+    for (int i = 0; i < ROUNDS; i++)
+        vdso_clock_gettime(CLOCK_MONOTONIC, &vdso);
+    long long vdso_cost = (now_ns() - start) / ROUNDS;
+
+    start = now_ns();
+    for (int i = 0; i < ROUNDS; i++)
+        syscall(SYS_clock_gettime, CLOCK_MONOTONIC, &vdso);
+    long long syscall_cost = (now_ns() - start) / ROUNDS;
+
+    printf("clock_gettime: vDSO %lld ns, syscall %lld ns\n", vdso_cost,
+           syscall_cost);
+    CHECK(vdso_cost < syscall_cost, "the vDSO clock_gettime isn't faster");
+
+    printf("PASS\n");
+    return 0;
+}
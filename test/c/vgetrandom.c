@@ -0,0 +1,191 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// Look up __vdso_getrandom through AT_SYSINFO_EHDR, allocate a state as the
+// vDSO asks for and check that the output changes between calls, has balanced
+// bits, differs between a parent and its child after a fork sharing the state
+// and is cheaper than the getrandom syscall.
+
+#define _GNU_SOURCE
+#include <elf.h>
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                      \
+    do {                                                                      \
+        if (!(cond)) {                                                        \
+            printf("FAIL: %s (errno %d)\n", msg, errno);                      \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+#define ROUNDS 100000
+#define BUF_SIZE 4096
+
+struct opaque_params {
+    uint32_t size_of_opaque_state;
+    uint32_t mmap_prot;
+    uint32_t mmap_flags;
+    uint32_t reserved[13];
+};
+
+typedef ssize_t (*getrandom_fn)(void *, size_t, unsigned, void *, size_t);
+
+static Elf64_Sym *symtab;
+static const char *strtab;
+static unsigned nsyms;
+static unsigned long bias;
+
+static void load_vdso(void) {
+    Elf64_Ehdr *ehdr = (Elf64_Ehdr *)getauxval(AT_SYSINFO_EHDR);
+    CHECK(ehdr != NULL, "no AT_SYSINFO_EHDR");
+    CHECK(memcmp(ehdr->e_ident, ELFMAG, SELFMAG) == 0, "the vDSO isn't an ELF");
+
+    Elf64_Phdr *phdr = (Elf64_Phdr *)((char *)ehdr + ehdr->e_phoff);
+    Elf64_Dyn *dyn = NULL;
+    int loaded = 0;
+    for (int i = 0; i < ehdr->e_phnum; i++) {
+        if (phdr[i].p_type == PT_LOAD && !loaded) {
+            bias = (unsigned long)ehdr + phdr[i].p_offset - phdr[i].p_vaddr;
+            loaded = 1;
+        } else if (phdr[i].p_type == PT_DYNAMIC) {
+            dyn = (Elf64_Dyn *)((char *)ehdr + phdr[i].p_offset);
+        }
+    }
+    CHECK(loaded && dyn != NULL, "no PT_LOAD or PT_DYNAMIC in the vDSO");
+
+    for (; dyn->d_tag != DT_NULL; dyn++) {
+        if (dyn->d_tag == DT_SYMTAB)
+            symtab = (Elf64_Sym *)(dyn->d_un.d_ptr + bias);
+        else if (dyn->d_tag == DT_STRTAB)
+            strtab = (const char *)(dyn->d_un.d_ptr + bias);
+        else if (dyn->d_tag == DT_HASH)
+            nsyms = ((Elf32_Word *)(dyn->d_un.d_ptr + bias))[1];
+    }
+    CHECK(symtab != NULL && strtab != NULL && nsyms > 0,
+          "no symbol table in the vDSO");
+}
+
+static void *lookup(const char *name) {
+    for (unsigned i = 0; i < nsyms; i++) {
+        Elf64_Sym *sym = &symtab[i];
+        if (sym->st_shndx != SHN_UNDEF && strcmp(strtab + sym->st_name, name) == 0)
+            return (void *)(sym->st_value + bias);
+    }
+
+    return NULL;
+}
+
+static long long now_ns(void) {
+    struct timespec ts;
+    syscall(SYS_clock_gettime, CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+static void fill(getrandom_fn vgetrandom, void *state, size_t state_len,
+                 unsigned char *buf, size_t len) {
+    CHECK(vgetrandom(buf, len, 0, state, state_len) == (ssize_t)len,
+          "vDSO getrandom");
+}
+
+int main() {
+    load_vdso();
+    getrandom_fn vgetrandom = lookup("__vdso_getrandom");
+    if (vgetrandom == NULL) {
+        printf("no __vdso_getrandom, skip\n");
+        printf("PASS\n");
+        return 0;
+    }
+
+    struct opaque_params params;
+    memset(&params, 0xff, sizeof(params));
+    CHECK(vgetrandom(NULL, 0, 0, &params, ~0UL) == 0, "opaque params query");
+    CHECK(params.size_of_opaque_state > 0 &&
+              params.size_of_opaque_state < 4096,
+          "the opaque state size");
+    size_t state_len = params.size_of_opaque_state;
+    void *state = mmap(NULL, 4096, params.mmap_prot, params.mmap_flags, -1, 0);
+    CHECK(state != MAP_FAILED, "mmap the state");
+
+    // two buffers from the same state don't repeat
+    static unsigned char a[BUF_SIZE], b[BUF_SIZE];
+    fill(vgetrandom, state, state_len, a, sizeof(a));
+    fill(vgetrandom, state, state_len, b, sizeof(b));
+    CHECK(memcmp(a, b, sizeof(a)) != 0, "the output repeats");
+
+    // every bit is set in about half of the bytes
+    long ones = 0;
+    for (int i = 0; i < BUF_SIZE; i++)
+        ones += __builtin_popcount(a[i]);
+    long bits = BUF_SIZE * 8;
+    CHECK(ones > bits / 2 - bits / 20 && ones < bits / 2 + bits / 20,
+          "the output bits aren't balanced");
+
+    // odd sizes and a zero length
+    unsigned char small[7];
+    CHECK(vgetrandom(small, 0, 0, state, state_len) == 0, "zero length");
+    fill(vgetrandom, state, state_len, small, sizeof(small));
+
+    // the child has a copy of the state, both have to get new output
+    int fds[2];
+    CHECK(pipe(fds) == 0, "pipe");
+    pid_t pid = fork();
+    CHECK(pid >= 0, "fork");
+    if (pid == 0) {
+        fill(vgetrandom, state, state_len, b, sizeof(b));
+        if (write(fds[1], b, sizeof(b)) != sizeof(b))
+            _exit(1);
+        _exit(0);
+    }
+    fill(vgetrandom, state, state_len, a, sizeof(a));
+    size_t got = 0;
+    while (got < sizeof(b)) {
+        ssize_t n = read(fds[0], b + got, sizeof(b) - got);
+        CHECK(n > 0, "read the child output");
+        got += n;
+    }
+    int status;
+    CHECK(waitpid(pid, &status, 0) == pid && WIFEXITED(status) &&
+              WEXITSTATUS(status) == 0,
+          "the child failed");
+    CHECK(memcmp(a, b, sizeof(a)) != 0,
+          "the parent and the child got the same output");
+
+    long long start = now_ns();
+    for (int i = 0; i < ROUNDS; i++)
+        fill(vgetrandom, state, state_len, small, sizeof(small));
+    long long vdso_cost = (now_ns() - start) / ROUNDS;
+
+    start = now_ns();
+    for (int i = 0; i < ROUNDS; i++)
+        syscall(SYS_getrandom, small, sizeof(small), 0);
+    long long syscall_cost = (now_ns() - start) / ROUNDS;
+
+    printf("getrandom: vDSO %lld ns, syscall %lld ns\n", vdso_cost,
+           syscall_cost);
+    CHECK(vdso_cost < syscall_cost, "the vDSO getrandom isn't faster");
+
+    printf("PASS\n");
+    return 0;
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A deep path with a symlink in the middle resolves to the same file as walking
+// it one component at a time with openat, following the symlink by hand. ".."
+// after the symlink goes to the parent of the target and a symlink loop is ELOOP.
+// chdir resolves its path with Dirent::WalkPath, the other lookups don't, so the
+// directory it ends up in is checked against the walk as well.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            perror(msg);                                                       \
+            exit(1);                                                           \
+        }                                                                      \
+    } while (0)
+
+#define BASE "/tmp/walk_path"
+
+// walk returns an O_PATH fd of path relative to dirfd, one openat per component
+static int walk(int dirfd, const char *path, int depth) {
+    CHECK(depth < 40, "too many symlinks");
+
+    char buf[PATH_MAX];
+    strcpy(buf, path);
+    int cur;
+    if (path[0] == '/')
+        cur = open("/", O_PATH | O_DIRECTORY);
+    else
+        cur = openat(dirfd, ".", O_PATH | O_DIRECTORY);
+    CHECK(cur >= 0, "open start");
+
+    char *save = NULL;
+    for (char *name = strtok_r(buf, "/", &save); name != NULL;
+         name = strtok_r(NULL, "/", &save)) {
+        int next = openat(cur, name, O_PATH | O_NOFOLLOW);
+        CHECK(next >= 0, name);
+
+        struct stat st;
+        CHECK(fstatat(next, "", &st, AT_EMPTY_PATH) == 0, "fstatat");
+        if (S_ISLNK(st.st_mode)) {
+            char target[PATH_MAX];
+            ssize_t n = readlinkat(cur, name, target, sizeof(target) - 1);
+            CHECK(n > 0, "readlinkat");
+            target[n] = 0;
+            close(next);
+            next = walk(cur, target, depth + 1);
+        }
+
+        close(cur);
+        cur = next;
+    }
+
+    return cur;
+}
+
+static void same(const char *path) {
+    struct stat full, step;
+    CHECK(stat(path, &full) == 0, path);
+
+    int fd = walk(AT_FDCWD, path, 0);
+    CHECK(fstatat(fd, "", &step, AT_EMPTY_PATH) == 0, "fstatat walk");
+    close(fd);
+
+    if (full.st_dev != step.st_dev || full.st_ino != step.st_ino) {
+        printf("%s: stat gives ino %lu, the walk gives %lu\n", path,
+               (unsigned long)full.st_ino, (unsigned long)step.st_ino);
+        exit(1);
+    }
+}
+
+static void same_dir(const char *path) {
+    struct stat cwd, step;
+    int fd = walk(AT_FDCWD, path, 0);
+    CHECK(fstatat(fd, "", &step, AT_EMPTY_PATH) == 0, "fstatat walk");
+    close(fd);
+
+    CHECK(chdir(path) == 0, path);
+    CHECK(stat(".", &cwd) == 0, "stat .");
+    if (cwd.st_dev != step.st_dev || cwd.st_ino != step.st_ino) {
+        printf("chdir %s: the cwd is ino %lu, the walk gives %lu\n", path,
+               (unsigned long)cwd.st_ino, (unsigned long)step.st_ino);
+        exit(1);
+    }
+}
+
+int main() {
+    system("rm -rf " BASE);
+    CHECK(mkdir(BASE, 0755) == 0, "mkdir");
+    CHECK(mkdir(BASE "/a", 0755) == 0, "mkdir a");
+    CHECK(mkdir(BASE "/a/b", 0755) == 0, "mkdir b");
+    CHECK(mkdir(BASE "/x", 0755) == 0, "mkdir x");
+    CHECK(mkdir(BASE "/x/y", 0755) == 0, "mkdir y");
+    CHECK(mkdir(BASE "/x/y/c", 0755) == 0, "mkdir c");
+    CHECK(mkdir(BASE "/x/y/c/d", 0755) == 0, "mkdir d");
+    int f = open(BASE "/x/y/c/d/file", O_CREAT | O_WRONLY, 0644);
+    CHECK(f >= 0, "create file");
+    close(f);
+    CHECK(symlink("../../x/y", BASE "/a/b/link") == 0, "symlink");
+    CHECK(symlink(BASE "/x", BASE "/abs") == 0, "symlink abs");
+    CHECK(symlink("loop", BASE "/loop") == 0, "symlink loop");
+
+    same(BASE "/a/b/link/c/d/file");
+    same(BASE "/a/./b//link/c/../c/d/file");
+    same(BASE "/abs/y/c/d/file");
+    same(BASE "/a/b/link/..");
+
+    // ".." after the symlink is the parent of its target, not a/b
+    struct stat up, x;
+    CHECK(stat(BASE "/a/b/link/..", &up) == 0, "stat link/..");
+    CHECK(stat(BASE "/x", &x) == 0, "stat x");
+    CHECK(up.st_ino == x.st_ino, "link/.. isn't x");
+
+    CHECK(chdir(BASE "/a") == 0, "chdir");
+    same("b/link/c/d/file");
+
+    struct stat st;
+    CHECK(stat(BASE "/loop/file", &st) != 0 && errno == ELOOP, "loop");
+    CHECK(stat(BASE "/a/b/link/c/d/file/x", &st) != 0 && errno == ENOTDIR,
+          "file as dir");
+
+    same_dir(BASE "/a/b/link/c/d");
+    same_dir(BASE "/a/./b//link/c/../c");
+    same_dir(BASE "/abs/y");
+    same_dir(BASE "/a/b/link/..");
+    same_dir("y/c/d");
+    CHECK(chdir(BASE "/loop") != 0 && errno == ELOOP, "chdir loop");
+    CHECK(chdir(BASE "/x/y/c/d/file") != 0 && errno == ENOTDIR,
+          "chdir to a file");
+    CHECK(chdir(BASE "/x/y/c/d/file/x") != 0 && errno == ENOTDIR,
+          "chdir through a file");
+
+    chdir("/");
+    system("rm -rf " BASE);
+    printf("PASS\n");
+    return 0;
+}
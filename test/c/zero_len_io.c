@@ -0,0 +1,119 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Zero-length reads and writes return 0 without blocking or side effects on a
+// file, a pipe and a socket. A zero-length write(2) still sends an empty
+// datagram while a zero-length writev(2) doesn't reach the socket.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#define CHECK(cond, msg)                                              \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            printf("FAIL: %s (errno %d)\n", msg, errno);              \
+            exit(1);                                                  \
+        }                                                             \
+    } while (0)
+
+static char buf[16];
+
+static void zeroIO(int rfd, int wfd, const char *name) {
+    struct iovec iov = {buf, 0};
+
+    errno = 0;
+    if (read(rfd, buf, 0) != 0 || readv(rfd, &iov, 1) != 0 || readv(rfd, &iov, 0) != 0) {
+        printf("FAIL: %s zero read (errno %d)\n", name, errno);
+        exit(1);
+    }
+
+    if (writev(wfd, &iov, 1) != 0 || writev(wfd, &iov, 0) != 0) {
+        printf("FAIL: %s zero writev (errno %d)\n", name, errno);
+        exit(1);
+    }
+}
+
+int main() {
+    const char *path = "/tmp/zero_len_io_test";
+    struct stat st;
+
+    // regular file: nothing is written and the offset doesn't move
+    unlink(path);
+    int fd = open(path, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    CHECK(fd >= 0, "open");
+    CHECK(write(fd, "abc", 3) == 3, "write");
+    CHECK(lseek(fd, 1, SEEK_SET) == 1, "lseek");
+    zeroIO(fd, fd, "file");
+    CHECK(write(fd, buf, 0) == 0, "file zero write");
+    CHECK(pread(fd, buf, 0, 0) == 0, "file zero pread");
+    CHECK(pwrite(fd, buf, 0, 10) == 0, "file zero pwrite");
+    CHECK(lseek(fd, 0, SEEK_CUR) == 1, "file offset");
+    CHECK(fstat(fd, &st) == 0 && st.st_size == 3, "file size");
+    close(fd);
+    unlink(path);
+
+    // empty pipe: a blocking zero read returns at once
+    int p[2];
+    CHECK(pipe(p) == 0, "pipe");
+    zeroIO(p[0], p[1], "empty pipe");
+
+    // full pipe: a zero write doesn't fail with EAGAIN
+    CHECK(fcntl(p[1], F_SETFL, O_NONBLOCK) == 0, "fcntl");
+    while (write(p[1], buf, sizeof(buf)) > 0) {
+    }
+    CHECK(errno == EAGAIN, "fill pipe");
+    errno = 0;
+    CHECK(write(p[1], buf, 0) == 0, "full pipe zero write");
+    zeroIO(p[0], p[1], "full pipe");
+
+    // no reader: a zero write is no EPIPE and no SIGPIPE
+    signal(SIGPIPE, SIG_DFL);
+    close(p[0]);
+    CHECK(write(p[1], buf, 0) == 0, "zero write without reader");
+    close(p[1]);
+
+    // stream socket: zero reads don't block on the empty socket
+    int sv[2];
+    CHECK(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0, "socketpair stream");
+    zeroIO(sv[0], sv[1], "stream socket");
+    CHECK(write(sv[1], buf, 0) == 0, "stream zero write");
+    CHECK(write(sv[1], "x", 1) == 1, "stream write");
+    CHECK(read(sv[0], buf, 0) == 0, "stream zero read with data");
+    CHECK(read(sv[0], buf, sizeof(buf)) == 1 && buf[0] == 'x', "stream read");
+    close(sv[0]);
+    close(sv[1]);
+
+    // datagram socket
+    CHECK(socketpair(AF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0, sv) == 0, "socketpair dgram");
+    zeroIO(sv[0], sv[1], "dgram socket");
+    errno = 0;
+    CHECK(recv(sv[0], buf, sizeof(buf), 0) == -1 && errno == EAGAIN, "zero writev sends nothing");
+    CHECK(write(sv[1], buf, 0) == 0, "dgram zero write");
+    CHECK(recv(sv[0], buf, sizeof(buf), 0) == 0, "empty datagram");
+    errno = 0;
+    CHECK(recv(sv[0], buf, sizeof(buf), 0) == -1 && errno == EAGAIN, "one datagram");
+    close(sv[0]);
+    close(sv[1]);
+
+    printf("PASS\n");
+    return 0;
+}
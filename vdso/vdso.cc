@@ -21,6 +21,7 @@
 
 #include "syscalls.h"
 #include "vdso_time.h"
+#include "vgetrandom.h"
 
 namespace vdso {
 namespace {
@@ -30,7 +31,7 @@ int __common_clock_gettime(clockid_t clock, struct timespec* ts) {
 
   switch (clock) {
     case CLOCK_REALTIME:
-    //case CLOCK_REALTIME_COARSE:
+    case CLOCK_REALTIME_COARSE:
       ret = ClockRealtime(ts);
       break;
 
@@ -38,6 +39,7 @@ int __common_clock_gettime(clockid_t clock, struct timespec* ts) {
       // Fallthrough, CLOCK_BOOTTIME is an alias for CLOCK_MONOTONIC
     case CLOCK_MONOTONIC:
     case CLOCK_MONOTONIC_COARSE:
+    case CLOCK_MONOTONIC_RAW:
       ret = ClockMonotonic(ts);
       break;
 
@@ -69,6 +71,23 @@ int __common_gettimeofday(struct timeval* tv, struct timezone* tz) {
 
   return 0;
 }
+
+int __common_clock_getres(clockid_t clock, struct timespec* res) {
+  switch (clock) {
+    case CLOCK_REALTIME:
+    case CLOCK_MONOTONIC:
+    case CLOCK_MONOTONIC_RAW:
+    case CLOCK_BOOTTIME:
+      if (res) {
+        res->tv_sec = 0;
+        res->tv_nsec = 1;
+      }
+      return 0;
+
+    default:
+      return sys_clock_getres(clock, res);
+  }
+}
 }  // namespace
 
 #if __x86_64__
@@ -98,16 +117,29 @@ extern "C" time_t __vdso_time(time_t* t) {
 }
 extern "C" time_t time(time_t* t) __attribute__((weak, alias("__vdso_time")));
 
+// __vdso_clock_getres() implements clock_getres()
+extern "C" int __vdso_clock_getres(clockid_t clock, struct timespec* res) {
+  return __common_clock_getres(clock, res);
+}
+extern "C" int clock_getres(clockid_t clock, struct timespec* res)
+    __attribute__((weak, alias("__vdso_clock_getres")));
+
 // __vdso_getcpu() implements getcpu()
 extern "C" long __vdso_getcpu(unsigned* cpu, unsigned* node,
                               struct getcpu_cache* cache) {
-  // No optimizations yet, just make the real system call.
-  return sys_getcpu(cpu, node, cache);
+  return GetCpu(cpu, node);
 }
 extern "C" long getcpu(unsigned* cpu, unsigned* node,
                        struct getcpu_cache* cache)
     __attribute__((weak, alias("__vdso_getcpu")));
 
+// __vdso_getrandom() implements getrandom() with the per-thread state the libc
+// allocates, like the vgetrandom of Linux.
+extern "C" ssize_t __vdso_getrandom(void* buffer, size_t len, unsigned flags,
+                                    void* opaque_state, size_t opaque_len) {
+  return GetRandom(buffer, len, flags, opaque_state, opaque_len);
+}
+
 #elif __aarch64__
 
 // __kernel_clock_gettime() implements clock_gettime()
@@ -122,23 +154,7 @@ extern "C" int __kernel_gettimeofday(struct timeval* tv, struct timezone* tz) {
 
 // __kernel_clock_getres() implements clock_getres()
 extern "C" int __kernel_clock_getres(clockid_t clock, struct timespec* res) {
-  int ret = 0;
-
-  switch (clock) {
-    case CLOCK_REALTIME:
-    case CLOCK_MONOTONIC:
-    case CLOCK_BOOTTIME: {
-      res->tv_sec = 0;
-      res->tv_nsec = 1;
-      break;
-    }
-
-    default:
-      ret = sys_clock_getres(clock, res);
-      break;
-  }
-
-  return ret;
+  return __common_clock_getres(clock, res);
 }
 
 // __kernel_rt_sigreturn() implements gettimeofday()
@@ -0,0 +1,242 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The vDSO getrandom: every thread gets a ChaCha20 state from the libc and
+// fills the buffers from it without a system call. The key comes from the
+// getrandom system call and is replaced by the first block of every batch, so
+// that the earlier output can't be recovered from the state. The state is
+// reseeded when the kernel bumps rng_generation, which it does on every fork
+// so that a child doesn't repeat the stream of its parent, and at least every
+// kReseedNs.
+
+#include "vgetrandom.h"
+
+#include <stdint.h>
+#include <sys/mman.h>
+#include <time.h>
+
+#include "barrier.h"
+#include "params.h"
+#include "syscalls.h"
+#include "vdso_time.h"
+
+namespace vdso {
+
+#if __x86_64__
+
+namespace {
+
+const size_t kChaChaBlock = 64;
+const size_t kBatchBlocks = 4;
+const size_t kBatch = kChaChaBlock * kBatchBlocks;
+const size_t kKeyWords = 8;
+const int64_t kReseedNs = 60 * 1000000000LL;
+
+const unsigned kGrndNonblock = 0x1;
+const unsigned kGrndRandom = 0x2;
+const unsigned kGrndInsecure = 0x4;
+
+// struct vgetrandom_opaque_params of Linux, returned for opaque_len == ~0.
+struct opaque_params {
+  uint32_t size_of_opaque_state;
+  uint32_t mmap_prot;
+  uint32_t mmap_flags;
+  uint32_t reserved[13];
+};
+
+struct state {
+  uint8_t batch[kBatch];
+  uint32_t key[kKeyWords];
+  uint64_t generation;
+  int64_t seeded_ns;
+  uint64_t counter;
+  uint32_t pos;
+  uint32_t in_use;
+};
+
+inline uint32_t rotl(uint32_t v, int n) { return (v << n) | (v >> (32 - n)); }
+
+#define QUARTER_ROUND(a, b, c, d) \
+  a += b;                         \
+  d = rotl(d ^ a, 16);            \
+  c += d;                         \
+  b = rotl(b ^ c, 12);            \
+  a += b;                         \
+  d = rotl(d ^ a, 8);             \
+  c += d;                         \
+  b = rotl(b ^ c, 7);
+
+// chacha20_block writes the ChaCha20 block of key and counter, with a zero
+// nonce, to out. It isn't inlined into the batch loop to keep the VDSO in
+// two pages.
+__attribute__((noinline)) void chacha20_block(const uint32_t* key, uint64_t counter, uint8_t* out) {
+  uint32_t x[16] = {
+      0x61707865, 0x3320646e, 0x79622d32, 0x6b206574, key[0],
+      key[1],     key[2],     key[3],     key[4],     key[5],
+      key[6],     key[7],     (uint32_t)counter,      (uint32_t)(counter >> 32),
+      0,          0,
+  };
+  uint32_t s[16];
+  for (int i = 0; i < 16; i++) {
+    s[i] = x[i];
+  }
+
+  for (int i = 0; i < 10; i++) {
+    QUARTER_ROUND(x[0], x[4], x[8], x[12]);
+    QUARTER_ROUND(x[1], x[5], x[9], x[13]);
+    QUARTER_ROUND(x[2], x[6], x[10], x[14]);
+    QUARTER_ROUND(x[3], x[7], x[11], x[15]);
+    QUARTER_ROUND(x[0], x[5], x[10], x[15]);
+    QUARTER_ROUND(x[1], x[6], x[11], x[12]);
+    QUARTER_ROUND(x[2], x[7], x[8], x[13]);
+    QUARTER_ROUND(x[3], x[4], x[9], x[14]);
+  }
+
+  for (int i = 0; i < 16; i++) {
+    uint32_t v = x[i] + s[i];
+    out[i * 4] = v;
+    out[i * 4 + 1] = v >> 8;
+    out[i * 4 + 2] = v >> 16;
+    out[i * 4 + 3] = v >> 24;
+  }
+}
+
+// The byte loops are volatile so that the compiler doesn't turn them into
+// memcpy and memset calls, the VDSO has no libc.
+void copy_bytes(uint8_t* dst, const uint8_t* src, size_t len) {
+  for (size_t i = 0; i < len; i++) {
+    ((volatile uint8_t*)dst)[i] = src[i];
+  }
+}
+
+void zero_bytes(uint8_t* dst, size_t len) {
+  for (size_t i = 0; i < len; i++) {
+    ((volatile uint8_t*)dst)[i] = 0;
+  }
+}
+
+// refill generates the next batch and replaces the key with its first bytes.
+void refill(struct state* st) {
+  for (size_t i = 0; i < kBatchBlocks; i++) {
+    chacha20_block(st->key, st->counter++, st->batch + i * kChaChaBlock);
+  }
+
+  copy_bytes((uint8_t*)st->key, st->batch, sizeof(st->key));
+  zero_bytes(st->batch, sizeof(st->key));
+  st->pos = sizeof(st->key);
+}
+
+uint64_t load_generation(struct params* params) {
+  return *(volatile uint64_t*)&params->rng_generation;
+}
+
+int64_t monotonic_ns() {
+  struct timespec ts;
+  if (ClockMonotonic(&ts)) {
+    return 0;
+  }
+  return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+// reseed gets a new key from the kernel, it returns false if it failed.
+bool reseed(struct state* st, uint64_t generation) {
+  long ret = sys_getrandom(st->key, sizeof(st->key), 0);
+  if (ret != (long)sizeof(st->key)) {
+    return false;
+  }
+
+  st->generation = generation;
+  st->seeded_ns = monotonic_ns();
+  st->counter = 0;
+  st->pos = kBatch;
+  return true;
+}
+
+}  // namespace
+
+ssize_t GetRandom(void* buffer, size_t len, unsigned flags, void* opaque_state,
+                  size_t opaque_len) {
+  struct params* params = get_params();
+
+  if (opaque_len == ~0UL && !buffer && !len && !flags) {
+    struct opaque_params* p = (struct opaque_params*)opaque_state;
+    zero_bytes((uint8_t*)p, sizeof(*p));
+    p->size_of_opaque_state = sizeof(struct state);
+    p->mmap_prot = PROT_READ | PROT_WRITE;
+    p->mmap_flags = MAP_PRIVATE | MAP_ANONYMOUS;
+    return 0;
+  }
+
+  struct state* st = (struct state*)opaque_state;
+  if (flags & ~(kGrndNonblock | kGrndRandom | kGrndInsecure) ||
+      !params->rng_ready || !st || opaque_len != sizeof(struct state) ||
+      ((uintptr_t)st & 7)) {
+    return sys_getrandom(buffer, len, flags);
+  }
+
+  if (len == 0) {
+    return 0;
+  }
+
+  // a signal handler interrupted the use of the state
+  if (st->in_use) {
+    return sys_getrandom(buffer, len, flags);
+  }
+  st->in_use = 1;
+  barrier();
+
+retry:
+  uint64_t generation = load_generation(params);
+  if (st->generation != generation || st->seeded_ns == 0 ||
+      monotonic_ns() - st->seeded_ns > kReseedNs) {
+    if (!reseed(st, generation)) {
+      barrier();
+      st->in_use = 0;
+      return sys_getrandom(buffer, len, flags);
+    }
+  }
+
+  uint8_t* out = (uint8_t*)buffer;
+  size_t left = len;
+  while (left > 0) {
+    if (st->pos == kBatch) {
+      refill(st);
+    }
+
+    size_t n = kBatch - st->pos;
+    if (n > left) {
+      n = left;
+    }
+    copy_bytes(out, st->batch + st->pos, n);
+    zero_bytes(st->batch + st->pos, n);
+    st->pos += n;
+    out += n;
+    left -= n;
+  }
+
+  // A fork while the buffer was filled shares the buffer and the state with
+  // the child, so the output has to be generated again from a new key.
+  barrier();
+  if (load_generation(params) != generation) {
+    goto retry;
+  }
+
+  barrier();
+  st->in_use = 0;
+  return len;
+}
+
+#endif
+
+}  // namespace vdso